@@ -0,0 +1,210 @@
+//! Audit trail and reproducibility hash of a strategy run.
+//!
+//! `run_strategy` appends an entry for every order it accepts, every fill
+//! and every resulting portfolio change to the report's `AuditLog`, which can
+//! only be read from outside the crate. `StrategyReport::run_hash` is an
+//! FNV-1a hash of the strategy name, the bars, the engine config (seed
+//! included), the risk limits, the depth model, the signals and the audit log: two runs with the same hash had
+//! the same inputs and booked the same fills, on any machine.
+
+use crate::execution::DepthModel;
+use crate::risk::RiskLimits;
+use crate::{EngineConfig, PriceBar, Signal};
+
+/// Direction of an order or fill.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    /// open the long position
+    Buy,
+    /// close it
+    Sell,
+}
+
+/// What happened.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AuditEvent {
+    /// an order accepted at the close of the bar; `forced` for the close at the last bar
+    Order {
+        /// order direction
+        side: Side,
+        /// closed by the engine at the end of the data, not by a signal
+        forced: bool,
+    },
+    /// execution of the preceding order
+    Fill {
+        /// fill direction
+        side: Side,
+        /// execution price (slippage included)
+        price: f64,
+        /// commission of this fill
+        commission: f64,
+        /// slippage of this fill
+        slippage: f64,
+    },
+    /// portfolio after a fill
+    Portfolio {
+        /// units held (0 when flat, 1 unless a risk limit scaled the entry)
+        units: f64,
+        /// net pnl of the closed trades so far
+        realized_pnl: f64,
+    },
+}
+
+/// One audit record.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AuditEntry {
+    /// position in the log, from 0
+    pub seq: usize,
+    /// timestamp of the bar
+    pub ts: u64,
+    /// the event
+    pub event: AuditEvent,
+}
+
+/// Append-only list of audit entries.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AuditLog {
+    entries: Vec<AuditEntry>,
+}
+
+impl AuditLog {
+    /// Append `event` at bar `ts`.
+    pub(crate) fn record(&mut self, ts: u64, event: AuditEvent) {
+        self.entries.push(AuditEntry { seq: self.entries.len(), ts, event });
+    }
+
+    /// Entries in the order they were recorded.
+    pub fn entries(&self) -> &[AuditEntry] {
+        &self.entries
+    }
+
+    /// Number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether nothing was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// One line per entry: `seq ts event details`.
+    pub fn render(&self) -> String {
+        let side = |s: Side| if s == Side::Buy { "buy" } else { "sell" };
+        let mut out = String::new();
+        for e in &self.entries {
+            let details = match e.event {
+                AuditEvent::Order { side: s, forced } => format!("order {}{}", side(s), if forced { " (end of data)" } else { "" }),
+                AuditEvent::Fill { side: s, price, commission, slippage } => format!("fill {} @ {:.6} commission {:.6} slippage {:.6}", side(s), price, commission, slippage),
+                AuditEvent::Portfolio { units, realized_pnl } => format!("portfolio units={} realized={:.6}", units, realized_pnl),
+            };
+            out.push_str(&format!("{} {} {}\n", e.seq, e.ts, details));
+        }
+        out
+    }
+}
+
+/// FNV-1a over little-endian bytes.
+struct Fnv(u64);
+
+impl Fnv {
+    fn bytes(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 = (self.0 ^ *b as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn u64(&mut self, v: u64) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    fn f64(&mut self, v: f64) {
+        self.u64(v.to_bits());
+    }
+}
+
+/// Reproducibility hash of a run as 16 hex digits.
+pub(crate) fn run_hash(strategy: &str, bars: &[PriceBar], cfg: EngineConfig, limits: &RiskLimits, depth: Option<&DepthModel>, signals: &[Signal], log: &AuditLog) -> String {
+    let mut h = Fnv(0xcbf29ce484222325);
+    h.u64(strategy.len() as u64);
+    h.bytes(strategy.as_bytes());
+    h.u64(bars.len() as u64);
+    for b in bars {
+        h.u64(b.ts);
+        [b.open, b.high, b.low, b.close, b.volume].into_iter().for_each(|v| h.f64(v));
+    }
+    h.f64(cfg.commission_rate);
+    h.f64(cfg.slippage);
+    h.u64(cfg.seed);
+    h.f64(limits.capital);
+    for limit in [limits.max_position, limits.max_daily_loss, limits.max_open_positions.map(|n| n as f64), limits.max_leverage] {
+        match limit {
+            Some(v) => {
+                h.bytes(&[1]);
+                h.f64(v);
+            }
+            None => h.bytes(&[0]),
+        }
+    }
+    match depth {
+        Some(d) => {
+            h.bytes(&[1]);
+            [d.half_spread, d.tick, d.level_size].into_iter().for_each(|v| h.f64(v));
+        }
+        None => h.bytes(&[0]),
+    }
+    for s in signals {
+        h.bytes(&[*s as u8]);
+    }
+    for e in log.entries() {
+        h.u64(e.ts);
+        match e.event {
+            AuditEvent::Order { side, forced } => h.bytes(&[0, side as u8, u8::from(forced)]),
+            AuditEvent::Fill { side, price, commission, slippage } => {
+                h.bytes(&[1, side as u8]);
+                [price, commission, slippage].into_iter().for_each(|v| h.f64(v));
+            }
+            AuditEvent::Portfolio { units, realized_pnl } => {
+                h.bytes(&[2]);
+                h.f64(units);
+                h.f64(realized_pnl);
+            }
+        }
+    }
+    format!("{:016x}", h.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategies::SmaCrossover;
+    use crate::{bars_from_csv, run_strategy};
+
+    #[test]
+    fn log_records_each_fill_and_hash_tracks_inputs() {
+        let bars = bars_from_csv("close\n5\n4\n3\n4\n5\n6\n5\n4\n3\n");
+        assert_eq!(bars.as_ref().map(Vec::len), Ok(9));
+        let Ok(bars) = bars else { return };
+        let cfg = EngineConfig { commission_rate: 0.01, slippage: 0.0, seed: 0 };
+        let strategy = SmaCrossover { fast: 1, slow: 2 };
+        let (a, b) = (run_strategy(&strategy, &bars, cfg), run_strategy(&strategy, &bars, cfg));
+        assert_eq!((a.as_ref().map(|r| r.trades.len()), b.as_ref().map(|r| r.trades.len())), (Ok(1), Ok(1)));
+        let (Ok(a), Ok(b)) = (a, b) else { return };
+        let events: Vec<AuditEvent> = a.audit.entries().iter().map(|e| e.event).collect();
+        assert_eq!(
+            events,
+            vec![
+                AuditEvent::Order { side: Side::Buy, forced: false },
+                AuditEvent::Fill { side: Side::Buy, price: 4.0, commission: 0.04, slippage: 0.0 },
+                AuditEvent::Portfolio { units: 1.0, realized_pnl: 0.0 },
+                AuditEvent::Order { side: Side::Sell, forced: false },
+                AuditEvent::Fill { side: Side::Sell, price: 5.0, commission: 0.05, slippage: 0.0 },
+                AuditEvent::Portfolio { units: 0.0, realized_pnl: a.net_pnl },
+            ]
+        );
+        assert_eq!(a.audit.render().lines().count(), 6);
+        assert_eq!(a.run_hash, b.run_hash);
+        let other_seed = run_strategy(&strategy, &bars, EngineConfig { seed: 1, ..cfg });
+        assert!(other_seed.is_ok_and(|r| r.run_hash != a.run_hash));
+    }
+}
@@ -0,0 +1,204 @@
+//! Data quality checks for bar series.
+//!
+//! `validate` lists what would distort a backtest: gaps in the timestamps,
+//! duplicate or out-of-order bars, zero/negative (or non-finite) prices and
+//! bars whose high is below their low. `repair` fixes a series either by
+//! dropping the offending bars or by forward-filling them from the previous
+//! valid close.
+
+use crate::PriceBar;
+
+/// One problem found by `validate`; `index` is the position in the input.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Issue {
+    /// more than one `interval` between consecutive bars
+    Gap {
+        /// timestamp of the bar before the gap
+        after_ts: u64,
+        /// timestamp of the bar after the gap
+        before_ts: u64,
+        /// bars missing in between
+        missing: u64,
+    },
+    /// same timestamp as the previous bar
+    DuplicateTs {
+        /// position of the repeated bar
+        index: usize,
+        /// the repeated timestamp
+        ts: u64,
+    },
+    /// timestamp earlier than the previous bar
+    NonMonotonic {
+        /// position of the bar
+        index: usize,
+        /// its timestamp
+        ts: u64,
+        /// timestamp of the bar before it
+        prev_ts: u64,
+    },
+    /// a price is zero, negative, NaN or infinite
+    BadPrice {
+        /// position of the bar
+        index: usize,
+        /// its timestamp
+        ts: u64,
+    },
+    /// `high < low`
+    HighBelowLow {
+        /// position of the bar
+        index: usize,
+        /// its timestamp
+        ts: u64,
+    },
+}
+
+/// Result of `validate`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DataQualityReport {
+    /// bars examined
+    pub bars: usize,
+    /// expected spacing of timestamps: the median positive difference (None under 2 increasing bars)
+    pub interval: Option<u64>,
+    /// problems in input order
+    pub issues: Vec<Issue>,
+}
+
+impl DataQualityReport {
+    /// Whether no issue was found.
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Total bars missing in gaps.
+    pub fn missing_bars(&self) -> u64 {
+        self.issues.iter().map(|i| if let Issue::Gap { missing, .. } = i { *missing } else { 0 }).sum()
+    }
+
+    /// Bars that are duplicate, out of order, badly priced or have `high < low`.
+    pub fn invalid_bars(&self) -> usize {
+        self.issues.iter().filter(|i| !matches!(i, Issue::Gap { .. })).count()
+    }
+}
+
+/// How `repair` treats invalid bars and gaps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Repair {
+    /// remove invalid bars, leave gaps as they are
+    Drop,
+    /// replace badly priced bars and fill gaps with flat bars at the previous
+    /// valid close (volume 0); duplicate and out-of-order bars are removed
+    ForwardFill,
+}
+
+fn valid_prices(bar: &PriceBar) -> bool {
+    [bar.open, bar.high, bar.low, bar.close].iter().all(|p| p.is_finite() && *p > 0.0)
+}
+
+fn median_interval(bars: &[PriceBar]) -> Option<u64> {
+    let mut diffs: Vec<u64> = bars.windows(2).filter_map(|w| w.get(1)?.ts.checked_sub(w.first()?.ts)).filter(|d| *d > 0).collect();
+    diffs.sort_unstable();
+    diffs.get(diffs.len() / 2).copied()
+}
+
+/// Check `bars` for gaps, duplicate or out-of-order timestamps, bad prices and
+/// `high < low`. Bars out of order are compared with the latest timestamp seen.
+pub fn validate(bars: &[PriceBar]) -> DataQualityReport {
+    let interval = median_interval(bars);
+    let mut issues = Vec::new();
+    let mut last_ts: Option<u64> = None;
+    for (index, bar) in bars.iter().enumerate() {
+        match last_ts {
+            Some(prev_ts) if bar.ts == prev_ts => issues.push(Issue::DuplicateTs { index, ts: bar.ts }),
+            Some(prev_ts) if bar.ts < prev_ts => issues.push(Issue::NonMonotonic { index, ts: bar.ts, prev_ts }),
+            Some(prev_ts) => {
+                if let Some(step) = interval {
+                    let missing = ((bar.ts - prev_ts) / step).saturating_sub(1);
+                    if missing > 0 {
+                        issues.push(Issue::Gap { after_ts: prev_ts, before_ts: bar.ts, missing });
+                    }
+                }
+            }
+            None => {}
+        }
+        if !valid_prices(bar) {
+            issues.push(Issue::BadPrice { index, ts: bar.ts });
+        } else if bar.high < bar.low {
+            issues.push(Issue::HighBelowLow { index, ts: bar.ts });
+        }
+        last_ts = Some(last_ts.map_or(bar.ts, |t| t.max(bar.ts)));
+    }
+    DataQualityReport { bars: bars.len(), interval, issues }
+}
+
+/// Repaired copy of `bars` and the report of the original series.
+/// Invalid bars before the first valid one are dropped in both modes.
+pub fn repair(bars: &[PriceBar], mode: Repair) -> (Vec<PriceBar>, DataQualityReport) {
+    let report = validate(bars);
+    let flat = |ts: u64, close: f64| PriceBar { ts, open: close, high: close, low: close, close, volume: 0.0 };
+    let mut out: Vec<PriceBar> = Vec::with_capacity(bars.len());
+    for bar in bars {
+        let prev = out.last().copied();
+        if prev.is_some_and(|p| bar.ts <= p.ts) {
+            continue;
+        }
+        let good = valid_prices(bar) && bar.high >= bar.low;
+        let Some(p) = prev.filter(|_| mode == Repair::ForwardFill) else {
+            if good {
+                out.push(*bar);
+            }
+            continue;
+        };
+        if let Some(step) = report.interval {
+            let mut ts = p.ts.saturating_add(step);
+            while ts < bar.ts {
+                out.push(flat(ts, p.close));
+                ts = ts.saturating_add(step);
+            }
+        }
+        out.push(if good { *bar } else { flat(bar.ts, p.close) });
+    }
+    (out, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(ts: u64, low: f64, high: f64, close: f64) -> PriceBar {
+        PriceBar { ts, open: close, high, low, close, volume: 1.0 }
+    }
+
+    #[test]
+    fn finds_and_repairs_every_kind_of_issue() {
+        let bars = [
+            bar(0, 9.0, 11.0, 10.0),
+            bar(60, 9.0, 11.0, 10.5),
+            bar(60, 9.0, 11.0, 10.6),
+            bar(240, 9.0, 11.0, 11.0),
+            bar(120, 9.0, 11.0, 12.0),
+            bar(300, 0.0, 11.0, 10.0),
+            bar(360, 12.0, 11.0, 11.5),
+            bar(420, 9.0, 11.0, 10.0),
+        ];
+        let report = validate(&bars);
+        assert_eq!(report.interval, Some(60));
+        assert_eq!(
+            report.issues,
+            vec![
+                Issue::DuplicateTs { index: 2, ts: 60 },
+                Issue::Gap { after_ts: 60, before_ts: 240, missing: 2 },
+                Issue::NonMonotonic { index: 4, ts: 120, prev_ts: 240 },
+                Issue::BadPrice { index: 5, ts: 300 },
+                Issue::HighBelowLow { index: 6, ts: 360 },
+            ]
+        );
+        assert_eq!((report.missing_bars(), report.invalid_bars()), (2, 4));
+
+        let (dropped, _) = repair(&bars, Repair::Drop);
+        assert_eq!(dropped.iter().map(|b| b.ts).collect::<Vec<_>>(), vec![0, 60, 240, 420]);
+        let (filled, _) = repair(&bars, Repair::ForwardFill);
+        assert_eq!(filled.iter().map(|b| b.ts).collect::<Vec<_>>(), vec![0, 60, 120, 180, 240, 300, 360, 420]);
+        assert_eq!(filled.iter().map(|b| b.close).collect::<Vec<_>>(), vec![10.0, 10.5, 10.5, 10.5, 11.0, 11.0, 11.0, 10.0]);
+        assert!(validate(&filled).is_clean());
+    }
+}
@@ -0,0 +1,75 @@
+//! Order-book depth model for fills.
+//!
+//! By default a fill pays the flat `EngineConfig::slippage` per unit and side.
+//! A `DepthModel` replaces it with a synthetic book around the close: the
+//! best level is `half_spread` away, every further level `tick` more, and
+//! each level holds `level_size` units. An order walks the book, so its
+//! average slippage per unit grows with its size. Models are per symbol:
+//! `run_strategy_with_depth` takes the one of the traded instrument and each
+//! `rebalance::Asset` carries its own.
+
+use crate::EngineConfig;
+
+/// Synthetic order book with equal depth per level.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DepthModel {
+    /// distance from the close to the best bid and ask
+    pub half_spread: f64,
+    /// price distance between consecutive levels
+    pub tick: f64,
+    /// units available at every level (> 0)
+    pub level_size: f64,
+}
+
+impl DepthModel {
+    /// Average distance from the close paid per unit by an order of `units`
+    /// (`half_spread` for an empty order).
+    pub fn slippage(&self, units: f64) -> f64 {
+        let units = units.abs();
+        if units == 0.0 || self.level_size.is_nan() || self.level_size <= 0.0 {
+            return self.half_spread;
+        }
+        // full levels taken, then the rest at the next one
+        let full = (units / self.level_size).floor();
+        let rest = units - full * self.level_size;
+        let walked = self.tick * (self.level_size * full * (full - 1.0) / 2.0 + rest * full);
+        self.half_spread + walked / units
+    }
+}
+
+/// Per-unit slippage of an order of `units`: from `depth` when given, else the flat `cfg.slippage`.
+pub(crate) fn slippage(cfg: EngineConfig, depth: Option<&DepthModel>, units: f64) -> f64 {
+    depth.map_or(cfg.slippage, |d| d.slippage(units))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategies::BuyHold;
+    use crate::{bars_from_csv, run_strategy, run_strategy_with_depth};
+
+    #[test]
+    fn large_orders_walk_the_book() {
+        let book = DepthModel { half_spread: 0.01, tick: 0.02, level_size: 100.0 };
+        assert_eq!(book.slippage(50.0), 0.01);
+        // 100 at +0.01 and 100 at +0.03
+        assert!((book.slippage(200.0) - 0.02).abs() < 1e-12);
+        // 100 at +0.01, 100 at +0.03, 100 at +0.05, 100 at +0.07
+        assert!((book.slippage(400.0) - 0.04).abs() < 1e-12);
+        assert!(book.slippage(1000.0) > book.slippage(400.0));
+
+        let bars = bars_from_csv("close\n10\n12\n");
+        assert_eq!(bars.as_ref().map(Vec::len), Ok(2));
+        let Ok(bars) = bars else { return };
+        let cfg = EngineConfig { commission_rate: 0.0, slippage: 0.05, seed: 0 };
+        let thin = DepthModel { half_spread: 0.0, tick: 0.5, level_size: 0.5 };
+        let (flat, deep) = (run_strategy(&BuyHold, &bars, cfg), run_strategy_with_depth(&BuyHold, &bars, cfg, Default::default(), thin));
+        assert_eq!(flat.as_ref().map(|r| r.trades.len()), Ok(1));
+        // one unit over two levels of half a unit: 0.25 per unit and side
+        assert_eq!(deep.as_ref().map(|r| r.trades.first().map(|t| (t.entry_price, t.exit_price))), Ok(Some((10.25, 11.75))));
+        let (Ok(flat), Ok(deep)) = (flat, deep) else { return };
+        assert!((deep.slippage - 0.5).abs() < 1e-12);
+        assert!(deep.net_pnl < flat.net_pnl);
+        assert_ne!(deep.run_hash, flat.run_hash);
+    }
+}
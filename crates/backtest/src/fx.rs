@@ -0,0 +1,184 @@
+//! Currency conversion and portfolio equity in a base currency.
+//!
+//! Every instrument is quoted in its own currency. `FxRates` holds, per
+//! currency, a series of `(ts, rate)` giving the value of one unit of that
+//! currency in the base currency; the rate at a timestamp is the last one at
+//! or before it. `portfolio_equity` combines the strategy runs of several
+//! instruments (`Leg`s) into one equity curve in the base currency: realized
+//! PnL is converted at the rate of the exit bar, the open position is marked
+//! at the close of the bar and converted at the current rate.
+
+use std::collections::HashMap;
+
+use crate::{PriceBar, StrategyReport};
+
+/// Conversion-rate series into one base currency.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FxRates {
+    base: String,
+    series: HashMap<String, Vec<(u64, f64)>>,
+}
+
+impl FxRates {
+    /// Empty table for `base` (which always converts at 1).
+    pub fn new(base: &str) -> Self {
+        Self { base: base.to_uppercase(), series: HashMap::new() }
+    }
+
+    /// The base currency.
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    /// Set the `(ts, rate)` series of `currency`, one unit of it being worth
+    /// `rate` units of the base. Err for an empty series, a non-positive rate
+    /// or timestamps that do not increase.
+    pub fn insert(&mut self, currency: &str, rates: Vec<(u64, f64)>) -> Result<(), &'static str> {
+        if rates.is_empty() {
+            return Err("empty rate series");
+        }
+        if rates.iter().any(|(_, r)| !r.is_finite() || *r <= 0.0) {
+            return Err("conversion rates must be positive");
+        }
+        if rates.windows(2).any(|w| matches!(w, [a, b] if b.0 <= a.0)) {
+            return Err("rate timestamps must increase");
+        }
+        self.series.insert(currency.to_uppercase(), rates);
+        Ok(())
+    }
+
+    /// Value in the base currency of one unit of `currency` at `ts`.
+    pub fn rate(&self, currency: &str, ts: u64) -> Result<f64, &'static str> {
+        let currency = currency.to_uppercase();
+        if currency == self.base {
+            return Ok(1.0);
+        }
+        let series = self.series.get(&currency).ok_or("no rate series for the currency")?;
+        let at = series.partition_point(|(t, _)| *t <= ts);
+        at.checked_sub(1).and_then(|i| series.get(i)).map(|(_, r)| *r).ok_or("no conversion rate at or before the timestamp")
+    }
+
+    /// `amount` of `currency` at `ts` in the base currency.
+    pub fn convert(&self, amount: f64, currency: &str, ts: u64) -> Result<f64, &'static str> {
+        Ok(amount * self.rate(currency, ts)?)
+    }
+}
+
+/// One instrument of a portfolio: its bars and the strategy run over them.
+#[derive(Clone, Copy, Debug)]
+pub struct Leg<'a> {
+    /// instrument name for the per-symbol breakdown
+    pub symbol: &'a str,
+    /// quote currency of the prices
+    pub currency: &'a str,
+    /// bars the strategy ran on
+    pub bars: &'a [PriceBar],
+    /// result of `run_strategy` over `bars`
+    pub report: &'a StrategyReport,
+}
+
+/// Portfolio result in the base currency.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PortfolioEquity {
+    /// base currency of every amount
+    pub base: String,
+    /// `(ts, equity)` over the union of the legs' timestamps, equity being the
+    /// pnl of all legs so far (realized plus open)
+    pub equity: Vec<(u64, f64)>,
+    /// realized net pnl per symbol, in the order of the legs
+    pub per_symbol: Vec<(String, f64)>,
+    /// realized net pnl of the portfolio
+    pub net_pnl: f64,
+    /// largest peak-to-trough fall of `equity`
+    pub max_drawdown: f64,
+}
+
+/// Combine the legs into one equity curve in the base currency of `fx`.
+/// Err when a currency has no rate at one of the timestamps needed.
+pub fn portfolio_equity(legs: &[Leg], fx: &FxRates) -> Result<PortfolioEquity, &'static str> {
+    let mut times: Vec<u64> = legs.iter().flat_map(|l| l.bars.iter().map(|b| b.ts)).collect();
+    times.sort_unstable();
+    times.dedup();
+
+    let mut per_symbol = Vec::with_capacity(legs.len());
+    for leg in legs {
+        let mut realized = 0.0;
+        for t in &leg.report.trades {
+            realized += fx.convert(t.net_pnl, leg.currency, t.exit_ts)?;
+        }
+        per_symbol.push((leg.symbol.to_string(), realized));
+    }
+
+    let mut equity = Vec::with_capacity(times.len());
+    let (mut peak, mut max_drawdown) = (0.0f64, 0.0f64);
+    for &ts in &times {
+        let mut total = 0.0;
+        for leg in legs {
+            total += leg_equity(leg, ts, fx)?;
+        }
+        peak = peak.max(total);
+        max_drawdown = max_drawdown.max(peak - total);
+        equity.push((ts, total));
+    }
+    Ok(PortfolioEquity {
+        base: fx.base().to_string(),
+        equity,
+        net_pnl: per_symbol.iter().map(|(_, p)| p).sum(),
+        per_symbol,
+        max_drawdown,
+    })
+}
+
+/// Realized plus open pnl of `leg` at `ts`, in the base currency.
+fn leg_equity(leg: &Leg, ts: u64, fx: &FxRates) -> Result<f64, &'static str> {
+    let mut total = 0.0;
+    for t in leg.report.trades.iter().filter(|t| t.exit_ts <= ts) {
+        total += fx.convert(t.net_pnl, leg.currency, t.exit_ts)?;
+    }
+    let last = leg.bars.partition_point(|b| b.ts <= ts).checked_sub(1).and_then(|i| leg.bars.get(i));
+    let open = leg.report.trades.iter().find(|t| t.entry_ts <= ts && ts < t.exit_ts);
+    if let (Some(bar), Some(t)) = (last, open) {
+        total += fx.convert((bar.close - t.entry_price) * t.units, leg.currency, ts)?;
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategies::BuyHold;
+    use crate::{bars_from_csv, run_strategy, EngineConfig};
+
+    #[test]
+    fn equity_is_converted_into_the_base_currency() {
+        let cfg = EngineConfig { commission_rate: 0.0, slippage: 0.0, seed: 0 };
+        let (usd_bars, eur_bars) = (bars_from_csv("ts,close\n0,10\n1,12\n2,11\n"), bars_from_csv("ts,close\n1,20\n2,24\n"));
+        assert_eq!((usd_bars.as_ref().map(Vec::len), eur_bars.as_ref().map(Vec::len)), (Ok(3), Ok(2)));
+        let (Ok(usd_bars), Ok(eur_bars)) = (usd_bars, eur_bars) else { return };
+        let (usd, eur) = (run_strategy(&BuyHold, &usd_bars, cfg), run_strategy(&BuyHold, &eur_bars, cfg));
+        assert_eq!((usd.as_ref().map(|r| r.net_pnl), eur.as_ref().map(|r| r.net_pnl)), (Ok(1.0), Ok(4.0)));
+        let (Ok(usd), Ok(eur)) = (usd, eur) else { return };
+        let mut fx = FxRates::new("usd");
+        assert!(fx.insert("EUR", vec![(0, 1.1), (2, 1.25)]).is_ok());
+        assert!(fx.insert("GBP", vec![(2, 1.2), (1, 1.3)]).is_err());
+        assert_eq!(fx.rate("eur", 1), Ok(1.1));
+        assert_eq!(fx.rate("USD", 7), Ok(1.0));
+
+        let legs = [
+            Leg { symbol: "SPY", currency: "USD", bars: &usd_bars, report: &usd },
+            Leg { symbol: "DAX", currency: "EUR", bars: &eur_bars, report: &eur },
+        ];
+        let p = portfolio_equity(&legs, &fx);
+        assert_eq!(p.as_ref().map(|p| p.equity.len()), Ok(3));
+        let Ok(p) = p else { return };
+        assert_eq!(p.equity.iter().map(|e| e.0).collect::<Vec<_>>(), vec![0, 1, 2]);
+        // ts 1: SPY open +2; ts 2: SPY closed +1, DAX closed +4 EUR at 1.25
+        assert_eq!(p.equity.get(1), Some(&(1, 2.0)));
+        assert_eq!(p.equity.get(2), Some(&(2, 6.0)));
+        assert_eq!(p.per_symbol, vec![("SPY".to_string(), 1.0), ("DAX".to_string(), 5.0)]);
+        assert_eq!((p.net_pnl, p.max_drawdown), (6.0, 0.0));
+
+        let no_rates = FxRates::new("USD");
+        assert!(portfolio_equity(&legs, &no_rates).is_err());
+    }
+}
@@ -0,0 +1,174 @@
+//! Importers for downloaded bar histories.
+//!
+//! `binance_klines` reads the kline CSV of Binance exports (data.binance.vision
+//! and the API dumps): no header or a header row, open time in milliseconds
+//! (microseconds in newer spot files), then open, high, low, close, volume
+//! and further columns that are ignored. `metatrader` reads MetaTrader 4
+//! history exports (`2024.01.02,13:00,o,h,l,c,volume`) and MetaTrader 5 ones
+//! (tab-separated, `<DATE>\t<TIME>\t<OPEN>...` header); their times are in
+//! the broker's server timezone, converted to UTC with `utc_offset_secs`.
+//! All bars come back with `ts` in UTC epoch seconds.
+
+use crate::PriceBar;
+
+/// Epoch seconds from a timestamp in seconds, milliseconds or microseconds,
+/// told apart by magnitude: from 10^11 on it is read as milliseconds, from
+/// 10^14 on as microseconds (both are dates after 1973).
+pub fn normalize_timestamp(raw: u64) -> u64 {
+    if raw >= 100_000_000_000_000 {
+        raw / 1_000_000
+    } else if raw >= 100_000_000_000 {
+        raw / 1_000
+    } else {
+        raw
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date (Howard Hinnant's `days_from_civil`).
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Seconds since the epoch of `2024.01.02` (also `-` or `/` separated) and
+/// `13:00` / `13:00:05` (empty: midnight).
+fn parse_datetime(date: &str, time: &str) -> Result<i64, &'static str> {
+    let parts: Vec<i64> = date.split(['.', '-', '/']).map(|p| p.trim().parse().map_err(|_| "invalid date")).collect::<Result<_, _>>()?;
+    let [year, month, day] = parts.as_slice() else { return Err("invalid date") };
+    if !(1..=12).contains(month) || !(1..=31).contains(day) {
+        return Err("invalid date");
+    }
+    let mut secs = 0i64;
+    if !time.trim().is_empty() {
+        let hms: Vec<i64> = time.split(':').map(|p| p.trim().parse().map_err(|_| "invalid time")).collect::<Result<_, _>>()?;
+        if hms.is_empty() || hms.len() > 3 {
+            return Err("invalid time");
+        }
+        secs = hms.iter().zip([3600, 60, 1]).map(|(v, unit)| v * unit).sum();
+    }
+    Ok(days_from_civil(*year, *month, *day) * 86_400 + secs)
+}
+
+fn number(fields: &[&str], col: usize) -> Result<f64, &'static str> {
+    fields.get(col).and_then(|v| v.parse().ok()).ok_or("non-numeric price column")
+}
+
+/// Bars of a Binance kline CSV (UTC, millisecond or microsecond open times).
+pub fn binance_klines(text: &str) -> Result<Vec<PriceBar>, &'static str> {
+    let mut bars = Vec::new();
+    for (row, line) in text.lines().filter(|l| !l.trim().is_empty()).enumerate() {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let Some(open_time) = fields.first().and_then(|f| f.parse::<u64>().ok()) else {
+            if row == 0 {
+                continue; // header row
+            }
+            return Err("non-numeric kline open time");
+        };
+        bars.push(PriceBar {
+            ts: normalize_timestamp(open_time),
+            open: number(&fields, 1)?,
+            high: number(&fields, 2)?,
+            low: number(&fields, 3)?,
+            close: number(&fields, 4)?,
+            volume: number(&fields, 5)?,
+        });
+    }
+    if bars.is_empty() {
+        return Err("no klines");
+    }
+    Ok(bars)
+}
+
+/// Bars of a MetaTrader 4 or 5 history CSV. `utc_offset_secs` is the broker's
+/// server time minus UTC (7200 for UTC+2) and is subtracted from every time.
+/// The volume is `<VOL>`, or `<TICKVOL>` when the real volume is 0.
+pub fn metatrader(text: &str, utc_offset_secs: i64) -> Result<Vec<PriceBar>, &'static str> {
+    let mut lines = text.lines().filter(|l| !l.trim().is_empty()).peekable();
+    let split = |line: &str| -> Vec<String> {
+        let sep = if line.contains('\t') { '\t' } else { ',' };
+        line.split(sep).map(|f| f.trim().to_string()).collect()
+    };
+    // MT5: <DATE> <TIME> <OPEN> <HIGH> <LOW> <CLOSE> <TICKVOL> <VOL> <SPREAD>
+    let header: Option<Vec<String>> = match lines.peek() {
+        Some(first) if first.trim_start().starts_with('<') => lines.next().map(|h| split(h).iter().map(|f| f.trim_matches(['<', '>']).to_lowercase()).collect()),
+        _ => None,
+    };
+    let column = |name: &str| header.as_ref().and_then(|h| h.iter().position(|c| c == name));
+
+    let mut bars = Vec::new();
+    for line in lines {
+        let owned = split(line);
+        let mut fields: Vec<&str> = owned.iter().map(String::as_str).collect();
+        // MT4 "2024.01.02 13:00" in one column
+        if header.is_none() {
+            if let Some((date, time)) = fields.first().and_then(|f| f.split_once(' ')) {
+                fields.splice(0..1, [date, time]);
+            }
+        }
+        let (date, time, prices, volume) = match &header {
+            Some(_) => {
+                let get = |name: &str| column(name).and_then(|c| fields.get(c)).copied();
+                let price = |name: &str| get(name).and_then(|v| v.parse::<f64>().ok()).ok_or("non-numeric price column");
+                let vol = get("vol").and_then(|v| v.parse::<f64>().ok()).filter(|v| *v > 0.0);
+                let tickvol = get("tickvol").and_then(|v| v.parse::<f64>().ok());
+                (get("date").ok_or("no <DATE> column")?, get("time").unwrap_or(""), [price("open")?, price("high")?, price("low")?, price("close")?], vol.or(tickvol).unwrap_or(0.0))
+            }
+            None => {
+                let date = fields.first().copied().ok_or("empty row")?;
+                let timed = fields.get(1).is_some_and(|f| f.contains(':'));
+                let first = if timed { 2 } else { 1 };
+                let prices = [number(&fields, first)?, number(&fields, first + 1)?, number(&fields, first + 2)?, number(&fields, first + 3)?];
+                let volume = fields.get(first + 4).and_then(|v| v.parse().ok()).unwrap_or(0.0);
+                (date, if timed { fields.get(1).copied().unwrap_or("") } else { "" }, prices, volume)
+            }
+        };
+        let utc = parse_datetime(date, time)? - utc_offset_secs;
+        let [open, high, low, close] = prices;
+        bars.push(PriceBar { ts: u64::try_from(utc).map_err(|_| "bar time before 1970")?, open, high, low, close, volume });
+    }
+    if bars.is_empty() {
+        return Err("no bars");
+    }
+    Ok(bars)
+}
+
+/// Bars of a file in any supported format: a CSV with a header
+/// (`bars_from_csv`), Binance klines or MetaTrader history (in UTC).
+pub fn any(text: &str) -> Result<Vec<PriceBar>, &'static str> {
+    crate::bars_from_csv(text)
+        .or_else(|_| binance_klines(text))
+        .or_else(|_| metatrader(text, 0))
+        .map_err(|_| "unrecognized bar format (expected a close column, Binance klines or MetaTrader history)")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn imports_binance_and_metatrader_exports() {
+        // 2024-01-02 00:00 UTC in ms, the next minute in µs (2025+ spot files)
+        let binance = "1704153600000,42000.1,42100,41950.5,42050,12.5,1704153659999,525000,310,6.1,256000,0\n\
+                       1704153660000000,42050,42080,42010,42020,3.25,1704153719999999,136500,80,1.5,63000,0\n";
+        let bars = binance_klines(binance);
+        assert_eq!(bars.as_ref().map(|b| b.iter().map(|x| (x.ts, x.close, x.volume)).collect::<Vec<_>>()), Ok(vec![(1_704_153_600, 42050.0, 12.5), (1_704_153_660, 42020.0, 3.25)]));
+        let with_header = format!("open_time,open,high,low,close,volume\n{}", binance);
+        assert_eq!(binance_klines(&with_header), bars);
+
+        let mt4 = "2024.01.02,02:00,1.1040,1.1050,1.1030,1.1045,812\n2024.01.02,03:00,1.1045,1.1060,1.1040,1.1055,640\n";
+        let bars = metatrader(mt4, 7200);
+        assert_eq!(bars.as_ref().map(|b| b.iter().map(|x| x.ts).collect::<Vec<_>>()), Ok(vec![1_704_153_600, 1_704_157_200]));
+        let mt5 = "<DATE>\t<TIME>\t<OPEN>\t<HIGH>\t<LOW>\t<CLOSE>\t<TICKVOL>\t<VOL>\t<SPREAD>\n2024.01.02\t00:00:00\t1.1040\t1.1050\t1.1030\t1.1045\t812\t0\t5\n";
+        let bars = metatrader(mt5, 0);
+        assert_eq!(bars.as_ref().map(|b| b.first().map(|x| (x.ts, x.high, x.volume))), Ok(Some((1_704_153_600, 1.1050, 812.0))));
+
+        assert_eq!(any(mt4).map(|b| b.len()), Ok(2));
+        assert_eq!(any("close\n1\n2\n").map(|b| b.len()), Ok(2));
+        assert!(any("hello\nworld\n").is_err());
+        assert_eq!(normalize_timestamp(1_704_153_600), 1_704_153_600);
+    }
+}
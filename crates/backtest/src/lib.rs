@@ -11,6 +11,22 @@
 //!
 //! Contracts: functions return Results for invalid inputs. No panics or unwraps.
 
+pub mod audit;
+pub mod data;
+pub mod execution;
+pub mod fx;
+pub mod import;
+pub mod rebalance;
+pub mod risk;
+pub mod sim;
+pub mod strategies;
+pub mod synthetic;
+pub mod ticks;
+
+use audit::{AuditEvent, Side};
+use execution::DepthModel;
+use risk::{Exposure, RiskLimits};
+
 /// Price bar for a single timeframe
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct PriceBar {
@@ -81,6 +97,241 @@ pub fn simulate_buy_hold(bars: &[PriceBar], cfg: EngineConfig) -> Result<Report,
     })
 }
 
+/// One closed round trip of a strategy.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Trade {
+    /// timestamp of the entry bar
+    pub entry_ts: u64,
+    /// timestamp of the exit bar
+    pub exit_ts: u64,
+    /// entry price (includes slippage)
+    pub entry_price: f64,
+    /// exit price (includes slippage)
+    pub exit_price: f64,
+    /// units held (1 unless a risk limit scaled the entry)
+    pub units: f64,
+    /// pnl after commissions and slippage
+    pub net_pnl: f64,
+}
+
+/// Result of a strategy that trades repeatedly (one unit per trade unless a risk limit scales it).
+#[derive(Clone, Debug, PartialEq)]
+pub struct StrategyReport {
+    /// closed round trips, in order
+    pub trades: Vec<Trade>,
+    /// round trips with positive net pnl
+    pub wins: usize,
+    /// gross pnl summed over trades
+    pub gross_pnl: f64,
+    /// total commissions (entry and exit of every trade)
+    pub commissions: f64,
+    /// total slippage (entry and exit of every trade)
+    pub slippage: f64,
+    /// net pnl after commissions and slippage
+    pub net_pnl: f64,
+    /// largest peak-to-trough fall of the marked-to-market net equity, in price units
+    pub max_drawdown: f64,
+    /// `(ts, net equity)` at the close of every bar, the open position marked to market
+    pub equity: Vec<(u64, f64)>,
+    /// every order, fill and portfolio change of the run
+    pub audit: audit::AuditLog,
+    /// entries rejected or scaled by the risk limits, in order
+    pub violations: Vec<risk::RiskViolation>,
+    /// reproducibility hash of the inputs, config, signals and audit log (16 hex digits)
+    pub run_hash: String,
+}
+
+impl StrategyReport {
+    /// Share of winning trades (0 without trades).
+    pub fn win_rate(&self) -> f64 {
+        safe_div(self.wins as f64, self.trades.len() as f64).unwrap_or(0.0)
+    }
+
+    /// Per-bar Sharpe ratio: mean over standard deviation of the bar-to-bar
+    /// equity changes, not annualized (0 for fewer than two bars or flat equity).
+    pub fn sharpe(&self) -> f64 {
+        let changes: Vec<f64> = self.equity.windows(2).filter_map(|w| Some(w.get(1)?.1 - w.first()?.1)).collect();
+        let n = changes.len() as f64;
+        let mean = safe_div(changes.iter().sum(), n).unwrap_or(0.0);
+        let var = safe_div(changes.iter().map(|c| (c - mean).powi(2)).sum(), n).unwrap_or(0.0);
+        safe_div(mean, var.sqrt()).unwrap_or(0.0)
+    }
+}
+
+/// Order a strategy emits at the close of a bar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Signal {
+    /// open a long position (ignored while one is open)
+    Buy,
+    /// close the open position (ignored while flat)
+    Sell,
+    /// do nothing
+    Hold,
+}
+
+/// A long-only trading rule. Implementations live in `strategies`.
+pub trait Strategy {
+    /// Short name for reports and chat replies.
+    fn name(&self) -> &'static str;
+    /// One signal per bar (same length as `bars`); Err for invalid parameters
+    /// or too few bars.
+    fn signals(&self, bars: &[PriceBar]) -> Result<Vec<Signal>, &'static str>;
+}
+
+/// Trade `strategy` over `bars`, one unit per trade: fills happen at the close of
+/// the signalling bar, an open position is closed at the last bar. Costs are
+/// applied as in `simulate_buy_hold`.
+pub fn run_strategy(strategy: &dyn Strategy, bars: &[PriceBar], cfg: EngineConfig) -> Result<StrategyReport, &'static str> {
+    run_strategy_with_limits(strategy, bars, cfg, RiskLimits::default())
+}
+
+/// `run_strategy` with every entry checked against `limits` (see `risk`):
+/// entries are rejected or scaled down and the breaches listed in
+/// `StrategyReport::violations`. Exits are never blocked.
+pub fn run_strategy_with_limits(strategy: &dyn Strategy, bars: &[PriceBar], cfg: EngineConfig, limits: RiskLimits) -> Result<StrategyReport, &'static str> {
+    simulate(strategy, bars, cfg, limits, None)
+}
+
+/// `run_strategy_with_limits` with fills walking the order book `depth` of the
+/// instrument (see `execution`) instead of paying the flat `cfg.slippage`.
+pub fn run_strategy_with_depth(
+    strategy: &dyn Strategy,
+    bars: &[PriceBar],
+    cfg: EngineConfig,
+    limits: RiskLimits,
+    depth: DepthModel,
+) -> Result<StrategyReport, &'static str> {
+    simulate(strategy, bars, cfg, limits, Some(&depth))
+}
+
+fn simulate(strategy: &dyn Strategy, bars: &[PriceBar], cfg: EngineConfig, limits: RiskLimits, depth: Option<&DepthModel>) -> Result<StrategyReport, &'static str> {
+    let signals = strategy.signals(bars)?;
+    if signals.len() != bars.len() {
+        return Err("strategy returned a signal count different from the bar count");
+    }
+    let mut report = StrategyReport {
+        trades: Vec::new(),
+        wins: 0,
+        gross_pnl: 0.0,
+        commissions: 0.0,
+        slippage: 0.0,
+        net_pnl: 0.0,
+        max_drawdown: 0.0,
+        equity: Vec::with_capacity(bars.len()),
+        audit: audit::AuditLog::default(),
+        violations: Vec::new(),
+        run_hash: String::new(),
+    };
+    // (entry timestamp, entry price, units, slippage per unit)
+    let mut entry: Option<(u64, f64, f64, f64)> = None;
+    let mut peak = 0.0f64;
+    // (day, net pnl realized at its start)
+    let mut day_start = (None, 0.0);
+    for (bar, signal) in bars.iter().zip(&signals) {
+        let day = bar.ts / 86_400;
+        if day_start.0 != Some(day) {
+            day_start = (Some(day), report.net_pnl);
+        }
+        match (signal, entry) {
+            (Signal::Buy, None) => {
+                let quote = bar.close + execution::slippage(cfg, depth, 1.0);
+                let exposure = Exposure { open_positions: 0, day_pnl: report.net_pnl - day_start.1, realized_pnl: report.net_pnl };
+                let units = limits.check_entry(bar.ts, 1.0, quote, &exposure, &mut report.violations);
+                if units > 0.0 {
+                    let slip = execution::slippage(cfg, depth, units);
+                    let price = bar.close + slip;
+                    report.audit.record(bar.ts, AuditEvent::Order { side: Side::Buy, forced: false });
+                    report.audit.record(
+                        bar.ts,
+                        AuditEvent::Fill { side: Side::Buy, price, commission: price.abs() * cfg.commission_rate * units, slippage: slip * units },
+                    );
+                    report.audit.record(bar.ts, AuditEvent::Portfolio { units, realized_pnl: report.net_pnl });
+                    entry = Some((bar.ts, price, units, slip));
+                }
+            }
+            (Signal::Sell, Some(open)) => {
+                close_trade(&mut report, open, bar, cfg, depth, false);
+                entry = None;
+            }
+            _ => {}
+        }
+        let open_pnl = entry.map_or(0.0, |(_, e, units, _)| (bar.close - execution::slippage(cfg, depth, units) - e) * units);
+        let equity = report.net_pnl + open_pnl;
+        peak = peak.max(equity);
+        report.max_drawdown = report.max_drawdown.max(peak - equity);
+        report.equity.push((bar.ts, equity));
+    }
+    if let (Some(open), Some(last)) = (entry, bars.last()) {
+        close_trade(&mut report, open, last, cfg, depth, true);
+    }
+    report.run_hash = audit::run_hash(strategy.name(), bars, cfg, &limits, depth, &signals, &report.audit);
+    Ok(report)
+}
+
+/// Long-only SMA crossover (`strategies::SmaCrossover`) run through `run_strategy`.
+pub fn simulate_sma_crossover(bars: &[PriceBar], fast: usize, slow: usize, cfg: EngineConfig) -> Result<StrategyReport, &'static str> {
+    run_strategy(&strategies::SmaCrossover { fast, slow }, bars, cfg)
+}
+
+/// Book a round trip `(entry_ts, entry_price, units, entry slippage per unit)` (slippage included)
+/// exited at the close of `bar`; `forced` when the engine closes it at the end of the data.
+fn close_trade(
+    report: &mut StrategyReport,
+    (entry_ts, entry_price, units, entry_slip): (u64, f64, f64, f64),
+    bar: &PriceBar,
+    cfg: EngineConfig,
+    depth: Option<&DepthModel>,
+    forced: bool,
+) {
+    let exit_slip = execution::slippage(cfg, depth, units);
+    let exit_price = bar.close - exit_slip;
+    let gross = (exit_price - entry_price) * units;
+    let commissions = (entry_price.abs() + exit_price.abs()) * cfg.commission_rate * units;
+    let slippage = (entry_slip + exit_slip) * units;
+    let net = gross - commissions - slippage;
+    report.trades.push(Trade { entry_ts, exit_ts: bar.ts, entry_price, exit_price, units, net_pnl: net });
+    report.wins += usize::from(net > 0.0);
+    report.gross_pnl += gross;
+    report.commissions += commissions;
+    report.slippage += slippage;
+    report.net_pnl += net;
+    report.audit.record(bar.ts, AuditEvent::Order { side: Side::Sell, forced });
+    report.audit.record(
+        bar.ts,
+        AuditEvent::Fill { side: Side::Sell, price: exit_price, commission: exit_price.abs() * cfg.commission_rate * units, slippage: exit_slip * units },
+    );
+    report.audit.record(bar.ts, AuditEvent::Portfolio { units: 0.0, realized_pnl: report.net_pnl });
+}
+
+/// Parse bars from CSV text with a header row. Recognized columns (case-insensitive):
+/// `ts`/`time`/`timestamp`/`date`, `open`, `high`, `low`, `close`/`price`, `volume`.
+/// Only a close column is required: missing prices default to the close, a missing
+/// volume to 0, and a missing or non-numeric time column to the row index.
+pub fn bars_from_csv(text: &str) -> Result<Vec<PriceBar>, &'static str> {
+    let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+    let header: Vec<String> = lines.next().ok_or("empty csv")?.split(',').map(|h| h.trim().to_lowercase()).collect();
+    let column = |names: &[&str]| header.iter().position(|h| names.contains(&h.as_str()));
+    let close_col = column(&["close", "price"]).ok_or("csv has no close/price column")?;
+    let (ts_col, open_col, high_col, low_col, volume_col) =
+        (column(&["ts", "time", "timestamp", "date"]), column(&["open"]), column(&["high"]), column(&["low"]), column(&["volume"]));
+
+    let mut bars = Vec::new();
+    for (row, line) in lines.enumerate() {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let number = |col: Option<usize>| col.and_then(|c| fields.get(c)).and_then(|v| v.parse::<f64>().ok());
+        let close = number(Some(close_col)).ok_or("non-numeric close price")?;
+        bars.push(PriceBar {
+            ts: ts_col.and_then(|c| fields.get(c)).and_then(|v| v.parse().ok()).unwrap_or(row as u64),
+            open: number(open_col).unwrap_or(close),
+            high: number(high_col).unwrap_or(close),
+            low: number(low_col).unwrap_or(close),
+            close,
+            volume: number(volume_col).unwrap_or(0.0),
+        });
+    }
+    Ok(bars)
+}
+
 /// Safe division returning an error on division by zero.
 ///
 /// Returns `Ok(result)` when `den != 0.0`, otherwise returns `Err("division by zero")`.
@@ -140,6 +391,23 @@ mod tests {
         assert_eq!(simulate_buy_hold(&bars, cfg), Ok(expected));
     }
 
+    #[test]
+    fn sma_crossover_trades_on_crosses() {
+        let bars = bars_from_csv("close\n5\n4\n3\n4\n5\n6\n5\n4\n3\n");
+        assert_eq!(bars.as_ref().map(|b| b.len()), Ok(9));
+        let Ok(bars) = bars else { return };
+        let cfg = EngineConfig { commission_rate: 0.0, slippage: 0.0, seed: 0 };
+        // fast(1) > slow(2) from bar 3 (close 4): bought at 4, sold at bar 6 (close 5)
+        let report = simulate_sma_crossover(&bars, 1, 2, cfg);
+        assert!(report.is_ok());
+        let Ok(report) = report else { return };
+        assert_eq!((report.trades.len(), report.wins, report.net_pnl), (1, 1, 1.0));
+        assert_eq!(report.trades.first().map(|t| (t.entry_ts, t.exit_ts)), Some((3, 6)));
+        assert_eq!(report.max_drawdown, 1.0);
+        assert!(simulate_sma_crossover(&bars, 3, 2, cfg).is_err());
+        assert!(bars_from_csv("ts,open\n1,2\n").is_err());
+    }
+
     #[test]
     fn use_indicator_in_backtest_example() {
         // sanity check: compute sma of close prices and ensure usage possible
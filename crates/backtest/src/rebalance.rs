@@ -0,0 +1,194 @@
+//! Target-weight portfolio rebalancing.
+//!
+//! `rebalance` holds several assets whose bars share timestamps in one cash
+//! account. It buys the target weights at the first close and trades back to
+//! them whenever the `Schedule` says so: at calendar boundaries
+//! (`Every(secs)`, e.g. 604800 for weekly) or when an asset's weight drifts
+//! more than a threshold from its target. Trades fill at the close plus or
+//! minus the slippage (the asset's `execution::DepthModel` when it has one,
+//! else the flat `EngineConfig::slippage`) and pay the commission of
+//! `EngineConfig`; both come out of cash. Weights summing to less than 1 leave the rest in cash.
+
+use crate::execution::{self, DepthModel};
+use crate::{EngineConfig, PriceBar};
+
+/// One asset of the portfolio.
+#[derive(Clone, Copy, Debug)]
+pub struct Asset<'a> {
+    /// name for the trade list
+    pub symbol: &'a str,
+    /// bars, with the same timestamps for every asset
+    pub bars: &'a [PriceBar],
+    /// target share of the portfolio value (0..=1)
+    pub weight: f64,
+    /// order book of the symbol; None pays the flat `EngineConfig::slippage`
+    pub depth: Option<DepthModel>,
+}
+
+/// When to trade back to the target weights.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Schedule {
+    /// at the first bar of every `secs`-long calendar period (`ts / secs` changes)
+    Every(u64),
+    /// when any weight is more than this far (absolute, e.g. 0.05) from its target
+    Threshold(f64),
+}
+
+/// One rebalance fill.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RebalanceTrade {
+    /// bar timestamp
+    pub ts: u64,
+    /// asset symbol
+    pub symbol: String,
+    /// units bought (positive) or sold (negative)
+    pub units: f64,
+    /// execution price (slippage included)
+    pub price: f64,
+    /// commission paid
+    pub commission: f64,
+}
+
+/// Result of `rebalance`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RebalanceReport {
+    /// every fill, in order
+    pub trades: Vec<RebalanceTrade>,
+    /// timestamps of the bars where the portfolio was rebalanced
+    pub rebalances: Vec<u64>,
+    /// `(ts, portfolio value)` at every close, after that bar's trades
+    pub equity: Vec<(u64, f64)>,
+    /// total commissions
+    pub commissions: f64,
+    /// total slippage cost (`|units| * slippage per unit`)
+    pub slippage: f64,
+    /// total traded value at the closes
+    pub turnover: f64,
+    /// portfolio value at the last close
+    pub final_equity: f64,
+    /// largest peak-to-trough fall of `equity`
+    pub max_drawdown: f64,
+}
+
+/// Run the target weights of `assets` from `capital` in cash under `schedule`.
+/// Err for no assets, negative weights or weights summing above 1, a
+/// non-positive capital or threshold, or bars with different timestamps.
+pub fn rebalance(assets: &[Asset], capital: f64, schedule: Schedule, cfg: EngineConfig) -> Result<RebalanceReport, &'static str> {
+    let first = assets.first().ok_or("no assets")?;
+    if assets.iter().any(|a| a.weight.is_nan() || a.weight < 0.0) || assets.iter().map(|a| a.weight).sum::<f64>() > 1.0 + 1e-9 {
+        return Err("weights must be non-negative and sum to at most 1");
+    }
+    if capital.is_nan() || capital <= 0.0 {
+        return Err("capital must be positive");
+    }
+    match schedule {
+        Schedule::Every(0) => return Err("calendar period must be positive"),
+        Schedule::Threshold(t) if t.is_nan() || t <= 0.0 => return Err("drift threshold must be positive"),
+        _ => {}
+    }
+    let aligned = |a: &Asset| a.bars.len() == first.bars.len() && a.bars.iter().zip(first.bars).all(|(x, y)| x.ts == y.ts);
+    if first.bars.is_empty() || !assets.iter().all(aligned) {
+        return Err("asset bars must be non-empty and share timestamps");
+    }
+
+    let mut report = RebalanceReport {
+        trades: Vec::new(),
+        rebalances: Vec::new(),
+        equity: Vec::with_capacity(first.bars.len()),
+        commissions: 0.0,
+        slippage: 0.0,
+        turnover: 0.0,
+        final_equity: capital,
+        max_drawdown: 0.0,
+    };
+    let mut cash = capital;
+    let mut units = vec![0.0; assets.len()];
+    let mut peak = capital;
+    for (i, bar) in first.bars.iter().enumerate() {
+        let closes: Vec<f64> = assets.iter().map(|a| a.bars.get(i).map_or(0.0, |b| b.close)).collect();
+        let value = |cash: f64, units: &[f64]| cash + units.iter().zip(&closes).map(|(u, c)| u * c).sum::<f64>();
+        let equity = value(cash, &units);
+        let due = i == 0
+            || match schedule {
+                Schedule::Every(secs) => first.bars.get(i - 1).is_some_and(|prev| prev.ts / secs != bar.ts / secs),
+                Schedule::Threshold(drift) => {
+                    equity > 0.0 && assets.iter().zip(&units).zip(&closes).any(|((a, u), c)| (u * c / equity - a.weight).abs() > drift)
+                }
+            };
+        if due {
+            report.rebalances.push(bar.ts);
+            for ((asset, held), close) in assets.iter().zip(units.iter_mut()).zip(&closes) {
+                if *close <= 0.0 {
+                    continue;
+                }
+                let delta = asset.weight * equity / close - *held;
+                if (delta * close).abs() < 1e-9 {
+                    continue;
+                }
+                let slip = execution::slippage(cfg, asset.depth.as_ref(), delta);
+                let price = if delta > 0.0 { close + slip } else { close - slip };
+                let commission = (delta * price).abs() * cfg.commission_rate;
+                cash -= delta * price + commission;
+                *held += delta;
+                report.commissions += commission;
+                report.slippage += delta.abs() * slip;
+                report.turnover += (delta * close).abs();
+                report.trades.push(RebalanceTrade { ts: bar.ts, symbol: asset.symbol.to_string(), units: delta, price, commission });
+            }
+        }
+        let equity = value(cash, &units);
+        peak = peak.max(equity);
+        report.max_drawdown = report.max_drawdown.max(peak - equity);
+        report.equity.push((bar.ts, equity));
+        report.final_equity = equity;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bars_from_csv;
+
+    #[test]
+    fn rebalances_on_drift_and_calendar() {
+        let (flat, rising) = (bars_from_csv("ts,close\n0,10\n1,10\n2,10\n"), bars_from_csv("ts,close\n0,10\n1,20\n2,20\n"));
+        assert_eq!((flat.as_ref().map(Vec::len), rising.as_ref().map(Vec::len)), (Ok(3), Ok(3)));
+        let (Ok(flat), Ok(rising)) = (flat, rising) else { return };
+        let assets = [Asset { symbol: "A", bars: &flat, weight: 0.5, depth: None }, Asset { symbol: "B", bars: &rising, weight: 0.5, depth: None }];
+        let free = EngineConfig { commission_rate: 0.0, slippage: 0.0, seed: 0 };
+
+        let r = rebalance(&assets, 100.0, Schedule::Threshold(0.1), free);
+        // bar 1: A 50, B 100 -> weights 1/3 and 2/3, back to 75 each
+        assert_eq!(r.as_ref().map(|r| r.rebalances.clone()), Ok(vec![0, 1]));
+        let Ok(r) = r else { return };
+        let fills: Vec<(u64, &str, f64)> = r.trades.iter().map(|t| (t.ts, t.symbol.as_str(), t.units)).collect();
+        assert_eq!(fills, vec![(0, "A", 5.0), (0, "B", 5.0), (1, "A", 2.5), (1, "B", -1.25)]);
+        assert_eq!((r.final_equity, r.turnover), (150.0, 150.0));
+
+        let periodic = rebalance(&assets, 100.0, Schedule::Every(2), free);
+        assert_eq!(periodic.map(|p| p.rebalances), Ok(vec![0, 2]));
+
+        let costly = EngineConfig { commission_rate: 0.01, slippage: 0.0, seed: 0 };
+        let c = rebalance(&assets, 100.0, Schedule::Threshold(0.1), costly);
+        assert_eq!(c.as_ref().map(|c| c.rebalances.clone()), Ok(vec![0, 1]));
+        let Ok(c) = c else { return };
+        assert!((c.commissions - c.turnover * 0.01).abs() < 1e-9);
+        assert!(c.final_equity < r.final_equity);
+
+        // a thin book for B only: buying 5 units takes 2 at +0, 2 at +0.1 and 1 at +0.2
+        let thin = Some(DepthModel { half_spread: 0.0, tick: 0.1, level_size: 2.0 });
+        let booked = [Asset { symbol: "A", bars: &flat, weight: 0.5, depth: None }, Asset { symbol: "B", bars: &rising, weight: 0.5, depth: thin }];
+        let b = rebalance(&booked, 100.0, Schedule::Threshold(0.1), free);
+        assert_eq!(b.as_ref().map(|b| b.rebalances.clone()), Ok(vec![0, 1]));
+        let Ok(b) = b else { return };
+        let first_fills: Vec<f64> = b.trades.iter().take(2).map(|t| t.price).collect();
+        assert_eq!(first_fills, vec![10.0, 10.08]);
+        assert!(b.final_equity < r.final_equity);
+
+        let misaligned = [Asset { symbol: "A", bars: &flat, weight: 0.5, depth: None }, Asset { symbol: "B", bars: rising.get(..2).unwrap_or_default(), weight: 0.5, depth: None }];
+        assert!(rebalance(&misaligned, 100.0, Schedule::Every(1), free).is_err());
+        let heavy = [Asset { symbol: "A", bars: &flat, weight: 0.7, depth: None }, Asset { symbol: "B", bars: &rising, weight: 0.7, depth: None }];
+        assert!(rebalance(&heavy, 100.0, Schedule::Every(1), free).is_err());
+    }
+}
@@ -0,0 +1,161 @@
+//! Engine-level risk limits.
+//!
+//! `run_strategy_with_limits` checks every entry order against `RiskLimits`
+//! before filling it: an order that breaks a hard limit (open positions,
+//! daily loss) is rejected, one that is too large (position size, leverage)
+//! is scaled down to fit. Each intervention is appended to
+//! `StrategyReport::violations`. `RiskLimits::default()` sets no limit, which
+//! is what `run_strategy` uses.
+
+/// Limits checked before each entry. `None` disables a limit.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RiskLimits {
+    /// account equity at the start of the run, in price units; the base of `max_leverage`
+    pub capital: f64,
+    /// largest position in units (the engine asks for one unit per entry)
+    pub max_position: Option<f64>,
+    /// realized loss within one UTC day (`ts / 86400`) after which entries are rejected until the next day
+    pub max_daily_loss: Option<f64>,
+    /// open positions allowed at once (the engine holds at most one, so only 0 blocks entries)
+    pub max_open_positions: Option<usize>,
+    /// largest position notional divided by equity (`capital` plus realized pnl)
+    pub max_leverage: Option<f64>,
+}
+
+/// The limit an order ran into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RiskLimit {
+    /// `RiskLimits::max_position`
+    MaxPosition,
+    /// `RiskLimits::max_daily_loss`
+    MaxDailyLoss,
+    /// `RiskLimits::max_open_positions`
+    MaxOpenPositions,
+    /// `RiskLimits::max_leverage`
+    MaxLeverage,
+}
+
+/// What the engine did about it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RiskAction {
+    /// the order was dropped
+    Rejected,
+    /// the order was filled with fewer units
+    Scaled {
+        /// units asked for
+        from: f64,
+        /// units filled
+        to: f64,
+    },
+}
+
+/// One limit breach.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RiskViolation {
+    /// timestamp of the bar the order was placed at
+    pub ts: u64,
+    /// the limit
+    pub limit: RiskLimit,
+    /// rejection or scaling
+    pub action: RiskAction,
+}
+
+/// Account state an entry order is checked against.
+pub(crate) struct Exposure {
+    /// positions currently open
+    pub open_positions: usize,
+    /// realized net pnl of the current day
+    pub day_pnl: f64,
+    /// realized net pnl of the run
+    pub realized_pnl: f64,
+}
+
+impl RiskLimits {
+    /// Units to fill for an entry of `units` at `price` (0 when rejected),
+    /// recording every limit that intervened in `violations`.
+    pub(crate) fn check_entry(&self, ts: u64, units: f64, price: f64, exposure: &Exposure, violations: &mut Vec<RiskViolation>) -> f64 {
+        let hard = if self.max_open_positions.is_some_and(|max| exposure.open_positions >= max) {
+            Some(RiskLimit::MaxOpenPositions)
+        } else if self.max_daily_loss.is_some_and(|max| -exposure.day_pnl >= max) {
+            Some(RiskLimit::MaxDailyLoss)
+        } else {
+            None
+        };
+        if let Some(limit) = hard {
+            violations.push(RiskViolation { ts, limit, action: RiskAction::Rejected });
+            return 0.0;
+        }
+        let mut filled = scale_to(ts, units, self.max_position, RiskLimit::MaxPosition, violations);
+        if let Some(max) = self.max_leverage.filter(|_| filled > 0.0) {
+            let equity = self.capital + exposure.realized_pnl;
+            let cap = if equity > 0.0 && price > 0.0 { max * equity / price } else { 0.0 };
+            filled = scale_to(ts, filled, Some(cap), RiskLimit::MaxLeverage, violations);
+        }
+        filled
+    }
+}
+
+/// `units` capped at `cap`, recording the scaling (or the rejection when nothing is left).
+fn scale_to(ts: u64, units: f64, cap: Option<f64>, limit: RiskLimit, violations: &mut Vec<RiskViolation>) -> f64 {
+    match cap {
+        Some(cap) if units > cap => {
+            let to = cap.max(0.0);
+            let action = if to > 0.0 { RiskAction::Scaled { from: units, to } } else { RiskAction::Rejected };
+            violations.push(RiskViolation { ts, limit, action });
+            to
+        }
+        _ => units,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategies::{BuyHold, SmaCrossover};
+    use crate::{bars_from_csv, run_strategy, run_strategy_with_limits, EngineConfig};
+
+    const FREE: EngineConfig = EngineConfig { commission_rate: 0.0, slippage: 0.0, seed: 0 };
+
+    #[test]
+    fn limits_scale_or_reject_entries() {
+        let bars = bars_from_csv("close\n10\n12\n");
+        assert_eq!(bars.as_ref().map(Vec::len), Ok(2));
+        let Ok(bars) = bars else { return };
+        let limits = RiskLimits { capital: 5.0, max_leverage: Some(1.0), ..RiskLimits::default() };
+        let report = run_strategy_with_limits(&BuyHold, &bars, FREE, limits);
+        assert_eq!(report.as_ref().map(|r| r.trades.len()), Ok(1));
+        let Ok(report) = report else { return };
+        // 5 of equity at leverage 1 buys half a unit at 10
+        assert_eq!(report.trades.first().map(|t| (t.units, t.net_pnl)), Some((0.5, 1.0)));
+        assert_eq!(
+            report.violations,
+            vec![RiskViolation { ts: 0, limit: RiskLimit::MaxLeverage, action: RiskAction::Scaled { from: 1.0, to: 0.5 } }]
+        );
+
+        let blocked = RiskLimits { max_open_positions: Some(0), ..RiskLimits::default() };
+        let report = run_strategy_with_limits(&BuyHold, &bars, FREE, blocked);
+        assert_eq!(report.as_ref().map(|r| r.trades.len()), Ok(0));
+        let Ok(report) = report else { return };
+        assert_eq!(report.violations.first().map(|v| (v.limit, v.action)), Some((RiskLimit::MaxOpenPositions, RiskAction::Rejected)));
+
+        let unlimited = run_strategy(&BuyHold, &bars, FREE);
+        assert!(unlimited.is_ok_and(|r| r.violations.is_empty() && r.net_pnl == 2.0));
+    }
+
+    #[test]
+    fn daily_loss_blocks_entries_until_the_next_day() {
+        // a losing round trip on day 0, a second cross later that day and one on day 1
+        let csv = "ts,close\n0,5\n1,4\n2,5\n3,4\n4,3\n5,4\n6,3\n86400,2\n86401,3\n86402,4\n86403,3\n";
+        let bars = bars_from_csv(csv);
+        assert_eq!(bars.as_ref().map(Vec::len), Ok(11));
+        let Ok(bars) = bars else { return };
+        let strategy = SmaCrossover { fast: 1, slow: 2 };
+        let limits = RiskLimits { max_daily_loss: Some(0.5), ..RiskLimits::default() };
+        let (free, limited) = (run_strategy(&strategy, &bars, FREE), run_strategy_with_limits(&strategy, &bars, FREE, limits));
+        assert_eq!((free.as_ref().map(|r| r.trades.len()), limited.as_ref().map(|r| r.trades.len())), (Ok(3), Ok(2)));
+        let (Ok(free), Ok(limited)) = (free, limited) else { return };
+        assert_eq!(limited.trades.iter().map(|t| t.entry_ts).collect::<Vec<_>>(), vec![2, 86401]);
+        assert_eq!(limited.violations, vec![RiskViolation { ts: 5, limit: RiskLimit::MaxDailyLoss, action: RiskAction::Rejected }]);
+        assert_ne!(free.run_hash, limited.run_hash);
+    }
+}
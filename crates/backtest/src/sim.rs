@@ -0,0 +1,210 @@
+//! Bar-by-bar co-simulation of streaming indicators and a strategy.
+//!
+//! A `Simulator` owns named `indicators::streaming` indicators and a
+//! `StreamingStrategy`. For every bar it first updates each indicator with
+//! the bar's close, then calls the strategy with a `BarView`: the bar, the
+//! indicator values and nothing else. Bars are handed over one at a time, so
+//! what the strategy sees at bar `t` comes from bars `0..=t`, all completed
+//! when the signal is filled at the close of `t`. The signals are then booked
+//! by `run_strategy`, so reports, audit log and run hash are the same as for a
+//! batch `Strategy`.
+//!
+//! A batch `Strategy` receives the whole series and has no such guarantee.
+//! `look_ahead_bias` tests any signal function — a batch strategy or a
+//! simulator — by replaying every prefix of the bars and reporting the first
+//! bar whose signal depends on later bars.
+
+use indicators::streaming::StreamingIndicator;
+
+use crate::{run_strategy, EngineConfig, PriceBar, Signal, Strategy, StrategyReport};
+
+/// What a streaming strategy sees at one bar.
+#[derive(Clone, Copy, Debug)]
+pub struct BarView<'a> {
+    /// position of the bar in the series
+    pub index: usize,
+    /// the bar just completed
+    pub bar: &'a PriceBar,
+    names: &'a [String],
+    values: &'a [Option<f64>],
+}
+
+impl BarView<'_> {
+    /// Value of the indicator registered as `name` after this bar (None while
+    /// it warms up or for an unknown name).
+    pub fn get(&self, name: &str) -> Option<f64> {
+        let i = self.names.iter().position(|n| n == name)?;
+        self.values.get(i).copied().flatten()
+    }
+}
+
+/// A trading rule deciding one bar at a time.
+pub trait StreamingStrategy {
+    /// Short name for reports.
+    fn name(&self) -> &'static str;
+    /// Signal at the close of `view.bar`.
+    fn on_bar(&mut self, view: &BarView) -> Signal;
+    /// Forget the state of a previous run.
+    fn reset(&mut self) {}
+}
+
+/// Streaming indicators plus a strategy, run bar by bar.
+pub struct Simulator {
+    names: Vec<String>,
+    indicators: Vec<Box<dyn StreamingIndicator>>,
+    strategy: Box<dyn StreamingStrategy>,
+}
+
+impl Simulator {
+    /// Simulator for `strategy` without indicators.
+    pub fn new(strategy: Box<dyn StreamingStrategy>) -> Self {
+        Self { names: Vec::new(), indicators: Vec::new(), strategy }
+    }
+
+    /// Register `indicator`, fed with the closes, under `name`.
+    pub fn indicator(mut self, name: &str, indicator: Box<dyn StreamingIndicator>) -> Self {
+        self.names.push(name.to_string());
+        self.indicators.push(indicator);
+        self
+    }
+
+    /// One signal per bar, from a fresh state.
+    pub fn signals(&mut self, bars: &[PriceBar]) -> Vec<Signal> {
+        self.indicators.iter_mut().for_each(|i| i.reset());
+        self.strategy.reset();
+        let mut values = vec![None; self.indicators.len()];
+        let mut signals = Vec::with_capacity(bars.len());
+        for (index, bar) in bars.iter().enumerate() {
+            for (slot, indicator) in values.iter_mut().zip(self.indicators.iter_mut()) {
+                *slot = indicator.update(bar.close);
+            }
+            let view = BarView { index, bar, names: &self.names, values: &values };
+            signals.push(self.strategy.on_bar(&view));
+        }
+        signals
+    }
+
+    /// Run over `bars` and book the signals with `run_strategy`.
+    pub fn run(&mut self, bars: &[PriceBar], cfg: EngineConfig) -> Result<StrategyReport, &'static str> {
+        let recorded = Recorded { name: self.strategy.name(), signals: self.signals(bars) };
+        run_strategy(&recorded, bars, cfg)
+    }
+}
+
+/// Signals computed by a `Simulator`, replayed through the batch engine.
+struct Recorded {
+    name: &'static str,
+    signals: Vec<Signal>,
+}
+
+impl Strategy for Recorded {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn signals(&self, _bars: &[PriceBar]) -> Result<Vec<Signal>, &'static str> {
+        Ok(self.signals.clone())
+    }
+}
+
+/// First bar whose signal changes when the bars after it are removed, i.e.
+/// that used information from the future; None when every signal only
+/// depends on the bars up to its own. `signals` is called once per bar with
+/// each prefix (e.g. `|b| strategy.signals(b)` or `|b| Ok(sim.signals(b))`);
+/// an Err, as for a prefix shorter than the warm-up, counts as all `Hold`.
+pub fn look_ahead_bias(mut signals: impl FnMut(&[PriceBar]) -> Result<Vec<Signal>, &'static str>, bars: &[PriceBar]) -> Option<usize> {
+    let mut at = |bars: &[PriceBar], t: usize| signals(bars).ok().and_then(|s| s.get(t).copied()).unwrap_or(Signal::Hold);
+    let full: Vec<Signal> = (0..bars.len()).map(|t| at(bars, t)).collect();
+    (0..bars.len()).find(|&t| Some(&at(bars.get(..=t).unwrap_or_default(), t)) != full.get(t))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bars_from_csv;
+    use crate::strategies::SmaCrossover;
+    use indicators::streaming::Sma;
+
+    /// `SmaCrossover` written against streaming indicators.
+    struct Crossover {
+        prev_above: Option<bool>,
+    }
+
+    impl StreamingStrategy for Crossover {
+        fn name(&self) -> &'static str {
+            "sma-crossover"
+        }
+
+        fn on_bar(&mut self, view: &BarView) -> Signal {
+            let (Some(fast), Some(slow)) = (view.get("fast"), view.get("slow")) else { return Signal::Hold };
+            let above = fast > slow;
+            let signal = match self.prev_above {
+                Some(false) if above => Signal::Buy,
+                Some(true) if !above => Signal::Sell,
+                _ => Signal::Hold,
+            };
+            self.prev_above = Some(above);
+            signal
+        }
+
+        fn reset(&mut self) {
+            self.prev_above = None;
+        }
+    }
+
+    /// Buys when the next close is higher: a batch strategy reading the future.
+    struct Peeking;
+
+    impl Strategy for Peeking {
+        fn name(&self) -> &'static str {
+            "peeking"
+        }
+
+        fn signals(&self, bars: &[PriceBar]) -> Result<Vec<Signal>, &'static str> {
+            let next = bars.iter().skip(1).map(Some).chain([None]);
+            Ok(bars
+                .iter()
+                .zip(next)
+                .map(|(bar, next)| match next {
+                    Some(n) if n.close > bar.close => Signal::Buy,
+                    Some(_) => Signal::Sell,
+                    None => Signal::Hold,
+                })
+                .collect())
+        }
+    }
+
+    fn crossover_sim() -> Option<Simulator> {
+        let (fast, slow) = (Sma::new(1).ok()?, Sma::new(2).ok()?);
+        Some(Simulator::new(Box::new(Crossover { prev_above: None })).indicator("fast", Box::new(fast)).indicator("slow", Box::new(slow)))
+    }
+
+    #[test]
+    fn simulator_matches_batch_strategy_without_look_ahead() {
+        let bars = bars_from_csv("close\n5\n4\n3\n4\n5\n6\n5\n4\n3\n");
+        assert_eq!(bars.as_ref().map(Vec::len), Ok(9));
+        let Ok(bars) = bars else { return };
+        let cfg = EngineConfig { commission_rate: 0.001, slippage: 0.01, seed: 3 };
+        let sim = crossover_sim();
+        assert!(sim.is_some());
+        let Some(mut sim) = sim else { return };
+        let streamed = sim.run(&bars, cfg);
+        assert!(streamed.is_ok());
+        assert_eq!(streamed, run_strategy(&SmaCrossover { fast: 1, slow: 2 }, &bars, cfg));
+        assert_eq!(look_ahead_bias(|b| Ok(sim.signals(b)), &bars), None);
+        assert_eq!(look_ahead_bias(|b| Peeking.signals(b), &bars), Some(0));
+    }
+
+    #[test]
+    fn reference_strategies_have_no_look_ahead() {
+        let bars = crate::synthetic::to_bars(&crate::synthetic::Gbm { start: 100.0, mu: 0.0, sigma: 0.3, dt: 0.01 }.closes(120, 11));
+        let strategies: [&dyn Strategy; 3] = [
+            &SmaCrossover { fast: 3, slow: 8 },
+            &crate::strategies::RsiMeanReversion { period: 5, oversold: 40.0, overbought: 60.0 },
+            &crate::strategies::BollingerBreakout { period: 10, k: 1.0 },
+        ];
+        for strategy in strategies {
+            assert_eq!(look_ahead_bias(|b| strategy.signals(b), &bars), None, "{}", strategy.name());
+        }
+    }
+}
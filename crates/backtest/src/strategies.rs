@@ -0,0 +1,230 @@
+//! Reference strategies implementing `Strategy`.
+//!
+//! Each one turns an indicator from the `indicators` crate into Buy/Sell
+//! signals; `run_strategy` books the trades. They double as examples for
+//! writing new rules: compute the indicator over the closes, align it to the
+//! bar it ends at and emit `Hold` during the warm-up.
+
+use crate::{PriceBar, Signal, Strategy};
+
+fn closes(bars: &[PriceBar]) -> Vec<f64> {
+    bars.iter().map(|b| b.close).collect()
+}
+
+/// Set the signal of bar `index` (ignored when out of range).
+fn emit(signals: &mut [Signal], index: usize, signal: Signal) {
+    if let Some(slot) = signals.get_mut(index) {
+        *slot = signal;
+    }
+}
+
+/// Buy at the first bar, hold to the last one.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BuyHold;
+
+impl Strategy for BuyHold {
+    fn name(&self) -> &'static str {
+        "buy-hold"
+    }
+
+    fn signals(&self, bars: &[PriceBar]) -> Result<Vec<Signal>, &'static str> {
+        if bars.len() < 2 {
+            return Err("need at least 2 bars");
+        }
+        let mut signals = vec![Signal::Hold; bars.len()];
+        emit(&mut signals, 0, Signal::Buy);
+        Ok(signals)
+    }
+}
+
+/// Buy where the `fast` SMA crosses above the `slow` one, sell where it crosses
+/// back below. A position is only opened on a cross, not when the fast SMA
+/// already starts above.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SmaCrossover {
+    /// fast SMA period
+    pub fast: usize,
+    /// slow SMA period (longer than `fast`)
+    pub slow: usize,
+}
+
+impl Default for SmaCrossover {
+    fn default() -> Self {
+        Self { fast: 10, slow: 30 }
+    }
+}
+
+impl Strategy for SmaCrossover {
+    fn name(&self) -> &'static str {
+        "sma-crossover"
+    }
+
+    fn signals(&self, bars: &[PriceBar]) -> Result<Vec<Signal>, &'static str> {
+        let (fast, slow) = (self.fast, self.slow);
+        if fast == 0 || fast >= slow {
+            return Err("fast period must be positive and shorter than slow");
+        }
+        let closes = closes(bars);
+        let fast_sma = indicators::sma(&closes, fast).map_err(|_| "not enough bars for the slow period")?;
+        let slow_sma = indicators::sma(&closes, slow).map_err(|_| "not enough bars for the slow period")?;
+        let mut signals = vec![Signal::Hold; bars.len()];
+        let mut prev_above: Option<bool> = None;
+        // slow_sma[j] and fast_sma[j + slow - fast] both end at bar j + slow - 1
+        for (j, (&s, &f)) in slow_sma.iter().zip(fast_sma.iter().skip(slow - fast)).enumerate() {
+            let above = f > s;
+            match prev_above {
+                Some(false) if above => emit(&mut signals, j + slow - 1, Signal::Buy),
+                Some(true) if !above => emit(&mut signals, j + slow - 1, Signal::Sell),
+                _ => {}
+            }
+            prev_above = Some(above);
+        }
+        Ok(signals)
+    }
+}
+
+/// Mean reversion on RSI: buy when it falls below `oversold`, sell when it
+/// rises above `overbought`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RsiMeanReversion {
+    /// RSI period
+    pub period: usize,
+    /// buy threshold (0..100)
+    pub oversold: f64,
+    /// sell threshold (above `oversold`, below 100)
+    pub overbought: f64,
+}
+
+impl Default for RsiMeanReversion {
+    fn default() -> Self {
+        Self { period: 14, oversold: 30.0, overbought: 70.0 }
+    }
+}
+
+impl Strategy for RsiMeanReversion {
+    fn name(&self) -> &'static str {
+        "rsi-mean-reversion"
+    }
+
+    fn signals(&self, bars: &[PriceBar]) -> Result<Vec<Signal>, &'static str> {
+        if !(0.0 < self.oversold && self.oversold < self.overbought && self.overbought < 100.0) {
+            return Err("RSI thresholds must satisfy 0 < oversold < overbought < 100");
+        }
+        let rsi = indicators::rsi(&closes(bars), self.period).map_err(|_| "RSI period must be positive and shorter than the series")?;
+        let mut signals = vec![Signal::Hold; bars.len()];
+        // rsi[j] covers the changes up to bar j + period
+        for (j, &value) in rsi.iter().enumerate() {
+            if value < self.oversold {
+                emit(&mut signals, j + self.period, Signal::Buy);
+            } else if value > self.overbought {
+                emit(&mut signals, j + self.period, Signal::Sell);
+            }
+        }
+        Ok(signals)
+    }
+}
+
+/// Breakout of Bollinger bands: buy when the close ends above the upper band,
+/// sell when it falls back below the middle one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BollingerBreakout {
+    /// window of the bands
+    pub period: usize,
+    /// band width in standard deviations
+    pub k: f64,
+}
+
+impl Default for BollingerBreakout {
+    fn default() -> Self {
+        Self { period: 20, k: 2.0 }
+    }
+}
+
+impl Strategy for BollingerBreakout {
+    fn name(&self) -> &'static str {
+        "bollinger-breakout"
+    }
+
+    fn signals(&self, bars: &[PriceBar]) -> Result<Vec<Signal>, &'static str> {
+        if self.k.is_nan() || self.k <= 0.0 {
+            return Err("band width must be positive");
+        }
+        let bands = indicators::bollinger(&closes(bars), self.period, self.k).map_err(|_| "not enough bars for the band period")?;
+        let mut signals = vec![Signal::Hold; bars.len()];
+        // bands[j] ends at bar j + period - 1
+        for (j, (bar, b)) in bars.iter().skip(self.period - 1).zip(&bands).enumerate() {
+            if bar.close > b.upper {
+                emit(&mut signals, j + self.period - 1, Signal::Buy);
+            } else if bar.close < b.middle {
+                emit(&mut signals, j + self.period - 1, Signal::Sell);
+            }
+        }
+        Ok(signals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{run_strategy, simulate_buy_hold, EngineConfig, StrategyReport};
+
+    const FREE: EngineConfig = EngineConfig { commission_rate: 0.0, slippage: 0.0, seed: 0 };
+
+    fn series(closes: &[f64]) -> Vec<PriceBar> {
+        closes.iter().enumerate().map(|(i, &c)| PriceBar { ts: i as u64, open: c, high: c, low: c, close: c, volume: 1.0 }).collect()
+    }
+
+    fn round_trips(report: &StrategyReport) -> Vec<(u64, u64)> {
+        report.trades.iter().map(|t| (t.entry_ts, t.exit_ts)).collect()
+    }
+
+    #[test]
+    fn buy_hold_matches_simulate_buy_hold() {
+        let bars = series(&[10.0, 12.0, 9.0, 11.0]);
+        let cfg = EngineConfig { commission_rate: 0.001, slippage: 0.01, seed: 0 };
+        let report = run_strategy(&BuyHold, &bars, cfg);
+        assert!(report.is_ok());
+        let Ok(report) = report else { return };
+        assert_eq!(round_trips(&report), vec![(0, 3)]);
+        assert_eq!(simulate_buy_hold(&bars, cfg).map(|r| r.net_pnl), Ok(report.net_pnl));
+        assert!(BuyHold.signals(&series(&[1.0])).is_err());
+    }
+
+    #[test]
+    fn sma_crossover_signals_on_crosses() {
+        let bars = series(&[5.0, 4.0, 3.0, 4.0, 5.0, 6.0, 5.0, 4.0, 3.0]);
+        let strategy = SmaCrossover { fast: 1, slow: 2 };
+        let signals = strategy.signals(&bars);
+        assert_eq!(signals.as_ref().map(|s| (s.get(3).copied(), s.get(6).copied())), Ok((Some(Signal::Buy), Some(Signal::Sell))));
+        let report = run_strategy(&strategy, &bars, FREE);
+        assert_eq!(report.as_ref().map(round_trips), Ok(vec![(3, 6)]));
+        assert!(SmaCrossover { fast: 2, slow: 2 }.signals(&bars).is_err());
+    }
+
+    #[test]
+    fn rsi_buys_oversold_and_sells_overbought() {
+        // RSI(2) from bar 2: 0, 50, 75, 87.5, 43.75
+        let bars = series(&[10.0, 9.0, 8.0, 9.0, 10.0, 11.0, 10.0]);
+        let strategy = RsiMeanReversion { period: 2, ..RsiMeanReversion::default() };
+        let report = run_strategy(&strategy, &bars, FREE);
+        assert!(report.is_ok());
+        let Ok(report) = report else { return };
+        assert_eq!(round_trips(&report), vec![(2, 4)]);
+        assert_eq!((report.wins, report.net_pnl), (1, 2.0));
+        assert!(RsiMeanReversion { oversold: 70.0, overbought: 30.0, ..strategy }.signals(&bars).is_err());
+    }
+
+    #[test]
+    fn bollinger_enters_on_breakout_and_exits_below_middle() {
+        // bar 3 closes above the upper band of [10, 10, 13]; bar 5 below the mean of [13, 14, 12]
+        let bars = series(&[10.0, 10.0, 10.0, 13.0, 14.0, 12.0, 9.0]);
+        let strategy = BollingerBreakout { period: 3, k: 1.0 };
+        let report = run_strategy(&strategy, &bars, FREE);
+        assert!(report.is_ok());
+        let Ok(report) = report else { return };
+        assert_eq!(round_trips(&report), vec![(3, 5)]);
+        assert_eq!((report.wins, report.net_pnl), (0, -1.0));
+        assert!(BollingerBreakout { k: 0.0, ..strategy }.signals(&bars).is_err());
+        assert!(strategy.signals(&series(&[1.0, 2.0])).is_err());
+    }
+}
@@ -0,0 +1,185 @@
+//! Deterministic synthetic price series.
+//!
+//! Three processes cover the usual test scenarios: geometric Brownian motion
+//! (trending random walk), Ornstein-Uhlenbeck on the log price (mean
+//! reversion) and a regime-switching walk (calm/volatile or bull/bear
+//! phases). Every generator takes a seed for a ChaCha8 RNG, so the same
+//! parameters and seed always give the same series. `to_bars` turns closes
+//! into `PriceBar`s with timestamps 0, 1, 2, ...
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::PriceBar;
+
+/// Standard normal sample (Box-Muller).
+fn normal(rng: &mut ChaCha8Rng) -> f64 {
+    let u1: f64 = rng.gen_range(f64::MIN_POSITIVE..1.0);
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+}
+
+/// Geometric Brownian motion: `dS = mu S dt + sigma S dW`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Gbm {
+    /// first close
+    pub start: f64,
+    /// drift per unit of time
+    pub mu: f64,
+    /// volatility per square root of time
+    pub sigma: f64,
+    /// time step between bars
+    pub dt: f64,
+}
+
+impl Gbm {
+    /// `n` closes, the first being `start`.
+    pub fn closes(&self, n: usize, seed: u64) -> Vec<f64> {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let drift = (self.mu - self.sigma * self.sigma / 2.0) * self.dt;
+        let scale = self.sigma * self.dt.sqrt();
+        let mut price = self.start;
+        (0..n)
+            .map(|i| {
+                if i > 0 {
+                    price *= (drift + scale * normal(&mut rng)).exp();
+                }
+                price
+            })
+            .collect()
+    }
+}
+
+/// Ornstein-Uhlenbeck process on the log price: it reverts to `mean` at speed `theta`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OrnsteinUhlenbeck {
+    /// first close
+    pub start: f64,
+    /// long-run price level
+    pub mean: f64,
+    /// reversion speed per unit of time
+    pub theta: f64,
+    /// volatility of the log price per square root of time
+    pub sigma: f64,
+    /// time step between bars
+    pub dt: f64,
+}
+
+impl OrnsteinUhlenbeck {
+    /// `n` closes, the first being `start`.
+    pub fn closes(&self, n: usize, seed: u64) -> Vec<f64> {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let target = self.mean.ln();
+        let scale = self.sigma * self.dt.sqrt();
+        let mut x = self.start.ln();
+        (0..n)
+            .map(|i| {
+                if i > 0 {
+                    x += self.theta * (target - x) * self.dt + scale * normal(&mut rng);
+                }
+                x.exp()
+            })
+            .collect()
+    }
+}
+
+/// Drift and volatility of one regime.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Regime {
+    /// drift per unit of time
+    pub mu: f64,
+    /// volatility per square root of time
+    pub sigma: f64,
+}
+
+/// GBM whose parameters jump between regimes: after every bar the process
+/// moves to a uniformly chosen other regime with probability `switch_prob`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegimeSwitching {
+    /// first close
+    pub start: f64,
+    /// regimes; the series starts in the first one
+    pub regimes: Vec<Regime>,
+    /// chance of switching after each bar (0..=1)
+    pub switch_prob: f64,
+    /// time step between bars
+    pub dt: f64,
+}
+
+impl RegimeSwitching {
+    /// `n` closes and the regime index of each bar; Err without regimes or
+    /// with `switch_prob` outside 0..=1.
+    pub fn closes(&self, n: usize, seed: u64) -> Result<(Vec<f64>, Vec<usize>), &'static str> {
+        if self.regimes.is_empty() {
+            return Err("need at least one regime");
+        }
+        if !(0.0..=1.0).contains(&self.switch_prob) {
+            return Err("switch probability must be within 0..=1");
+        }
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let (mut price, mut current) = (self.start, 0usize);
+        let (mut closes, mut labels) = (Vec::with_capacity(n), Vec::with_capacity(n));
+        for i in 0..n {
+            if i > 0 {
+                if self.regimes.len() > 1 && rng.gen_bool(self.switch_prob) {
+                    current = (current + rng.gen_range(1..self.regimes.len())) % self.regimes.len();
+                }
+                let regime = self.regimes.get(current).ok_or("regime index out of range")?;
+                let drift = (regime.mu - regime.sigma * regime.sigma / 2.0) * self.dt;
+                price *= (drift + regime.sigma * self.dt.sqrt() * normal(&mut rng)).exp();
+            }
+            closes.push(price);
+            labels.push(current);
+        }
+        Ok((closes, labels))
+    }
+}
+
+/// Bars from closes: each opens at the previous close, high/low span open and
+/// close, volume is 0 and `ts` is the index.
+pub fn to_bars(closes: &[f64]) -> Vec<PriceBar> {
+    let mut open = closes.first().copied().unwrap_or(0.0);
+    closes
+        .iter()
+        .enumerate()
+        .map(|(i, &close)| {
+            let bar = PriceBar { ts: i as u64, open, high: open.max(close), low: open.min(close), close, volume: 0.0 };
+            open = close;
+            bar
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generators_are_seeded_and_behave_like_their_process() {
+        let gbm = Gbm { start: 100.0, mu: 0.5, sigma: 0.2, dt: 1.0 / 252.0 };
+        let a = gbm.closes(2000, 7);
+        assert_eq!(a, gbm.closes(2000, 7));
+        assert_ne!(a, gbm.closes(2000, 8));
+        assert_eq!(a.first(), Some(&100.0));
+        assert!(a.iter().all(|p| *p > 0.0));
+
+        // started far from the mean, the OU path ends up around it
+        let ou = OrnsteinUhlenbeck { start: 200.0, mean: 100.0, theta: 5.0, sigma: 0.1, dt: 1.0 / 252.0 };
+        let path = ou.closes(2000, 1);
+        let tail: f64 = path.iter().skip(1000).sum::<f64>() / 1000.0;
+        assert!((tail - 100.0).abs() < 5.0, "{}", tail);
+
+        let calm = Regime { mu: 0.0, sigma: 0.05 };
+        let wild = Regime { mu: 0.0, sigma: 0.8 };
+        let switching = RegimeSwitching { start: 50.0, regimes: vec![calm, wild], switch_prob: 0.02, dt: 1.0 / 252.0 };
+        let result = switching.closes(1000, 3);
+        assert!(result.is_ok());
+        let Ok((closes, labels)) = result else { return };
+        assert_eq!((closes.len(), labels.first()), (1000, Some(&0)));
+        assert!(labels.windows(2).filter(|w| w.first() != w.get(1)).count() > 2);
+        assert!(RegimeSwitching { regimes: Vec::new(), ..switching }.closes(10, 3).is_err());
+
+        let bars = to_bars(&a);
+        assert!(crate::data::validate(&bars).is_clean());
+    }
+}
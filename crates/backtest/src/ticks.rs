@@ -0,0 +1,154 @@
+//! Tick data and bar construction.
+//!
+//! A `BarBuilder` aggregates `Tick`s into `PriceBar`s as they arrive:
+//! `BarSpec::Time` closes a bar at every clock boundary (bars start at a
+//! multiple of the interval; intervals without ticks produce no bar),
+//! `Volume` and `Dollar` close a bar at the tick that brings its traded size
+//! or value (`price * size`) to the threshold. Ticks are never split, so the
+//! last tick of a volume or dollar bar can overshoot. `build_bars` runs a
+//! whole series and also emits the last, unfinished bar.
+
+use crate::PriceBar;
+
+/// One trade.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tick {
+    /// epoch seconds
+    pub ts: u64,
+    /// trade price
+    pub price: f64,
+    /// traded quantity
+    pub size: f64,
+}
+
+/// When a bar is complete.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BarSpec {
+    /// fixed clock interval in seconds
+    Time(u64),
+    /// traded quantity per bar
+    Volume(f64),
+    /// traded value (`price * size`) per bar
+    Dollar(f64),
+}
+
+impl BarSpec {
+    fn check(self) -> Result<Self, &'static str> {
+        let valid = match self {
+            BarSpec::Time(secs) => secs > 0,
+            BarSpec::Volume(v) | BarSpec::Dollar(v) => v.is_finite() && v > 0.0,
+        };
+        if valid {
+            Ok(self)
+        } else {
+            Err("bar interval or threshold must be positive")
+        }
+    }
+}
+
+/// Incremental tick-to-bar aggregator.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BarBuilder {
+    spec: BarSpec,
+    bar: Option<PriceBar>,
+    /// traded value of the open bar (for dollar bars)
+    value: f64,
+    last_ts: Option<u64>,
+}
+
+impl BarBuilder {
+    /// Builder for `spec`; Err for a zero interval or a non-positive threshold.
+    pub fn new(spec: BarSpec) -> Result<Self, &'static str> {
+        Ok(Self { spec: spec.check()?, bar: None, value: 0.0, last_ts: None })
+    }
+
+    /// Add `tick`; returns the bar it completed, if any. Err for a tick
+    /// earlier than the previous one, a non-positive price or a negative size.
+    pub fn push(&mut self, tick: Tick) -> Result<Option<PriceBar>, &'static str> {
+        if !(tick.price.is_finite() && tick.price > 0.0 && tick.size.is_finite() && tick.size >= 0.0) {
+            return Err("tick price must be positive and size non-negative");
+        }
+        if self.last_ts.is_some_and(|ts| tick.ts < ts) {
+            return Err("ticks out of order");
+        }
+        self.last_ts = Some(tick.ts);
+
+        let mut done = None;
+        if let (BarSpec::Time(secs), Some(bar)) = (self.spec, self.bar) {
+            if tick.ts / secs * secs != bar.ts {
+                done = self.bar.take();
+            }
+        }
+        let start = match self.spec {
+            BarSpec::Time(secs) => tick.ts / secs * secs,
+            _ => tick.ts,
+        };
+        if self.bar.is_none() {
+            self.value = 0.0;
+        }
+        let bar = self.bar.get_or_insert(PriceBar { ts: start, open: tick.price, high: tick.price, low: tick.price, close: tick.price, volume: 0.0 });
+        bar.high = bar.high.max(tick.price);
+        bar.low = bar.low.min(tick.price);
+        bar.close = tick.price;
+        bar.volume += tick.size;
+        self.value += tick.price * tick.size;
+
+        let full = match self.spec {
+            BarSpec::Time(_) => false,
+            BarSpec::Volume(threshold) => bar.volume >= threshold,
+            BarSpec::Dollar(threshold) => self.value >= threshold,
+        };
+        if full {
+            done = self.bar.take();
+        }
+        Ok(done)
+    }
+
+    /// The unfinished bar, if any ticks arrived since the last completed one.
+    pub fn finish(self) -> Option<PriceBar> {
+        self.bar
+    }
+}
+
+/// Bars of a whole tick series, the unfinished last bar included.
+pub fn build_bars(ticks: &[Tick], spec: BarSpec) -> Result<Vec<PriceBar>, &'static str> {
+    let mut builder = BarBuilder::new(spec)?;
+    let mut bars = Vec::new();
+    for tick in ticks {
+        bars.extend(builder.push(*tick)?);
+    }
+    bars.extend(builder.finish());
+    Ok(bars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticks() -> Vec<Tick> {
+        [(0, 10.0, 1.0), (30, 11.0, 2.0), (59, 9.0, 1.0), (61, 10.0, 3.0), (200, 12.0, 1.0)]
+            .into_iter()
+            .map(|(ts, price, size)| Tick { ts, price, size })
+            .collect()
+    }
+
+    #[test]
+    fn time_volume_and_dollar_bars() {
+        let bar = |ts, open, high, low, close, volume| PriceBar { ts, open, high, low, close, volume };
+        assert_eq!(
+            build_bars(&ticks(), BarSpec::Time(60)),
+            Ok(vec![bar(0, 10.0, 11.0, 9.0, 9.0, 4.0), bar(60, 10.0, 10.0, 10.0, 10.0, 3.0), bar(180, 12.0, 12.0, 12.0, 12.0, 1.0)])
+        );
+        assert_eq!(
+            build_bars(&ticks(), BarSpec::Volume(3.0)),
+            Ok(vec![bar(0, 10.0, 11.0, 10.0, 11.0, 3.0), bar(59, 9.0, 10.0, 9.0, 10.0, 4.0), bar(200, 12.0, 12.0, 12.0, 12.0, 1.0)])
+        );
+        // values 10, 22, 9, 30, 12
+        assert_eq!(build_bars(&ticks(), BarSpec::Dollar(30.0)).map(|b| b.iter().map(|b| b.ts).collect::<Vec<_>>()), Ok(vec![0, 59, 200]));
+
+        assert!(BarBuilder::new(BarSpec::Time(0)).is_err());
+        let mut out_of_order = ticks();
+        out_of_order.swap(1, 2);
+        assert!(build_bars(&out_of_order, BarSpec::Volume(3.0)).is_err());
+    }
+}
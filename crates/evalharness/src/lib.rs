@@ -0,0 +1,70 @@
+#![forbid(unsafe_code)]
+#![deny(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    clippy::indexing_slicing
+)]
+#![deny(missing_docs, unused_must_use)]
+
+//! Evaluation harness: task suites, answer matchers, per-category scoring,
+//! JSON reports and run-to-run comparison.
+//!
+//! The harness knows nothing about the system under test. `run_suite` asks a
+//! caller-provided solver for every task's answer and scores it with the
+//! task's matcher:
+//!
+//! ```
+//! use evalharness::{run_suite, Suite};
+//!
+//! let suite = Suite::from_csv("demo", "question,expected\n2+2,4\n").unwrap_or_default();
+//! let report = run_suite(&suite, |_task| "4".to_string());
+//! assert_eq!(report.passed, 1);
+//! ```
+
+/// Suite definitions and answer matchers.
+pub mod suite;
+/// Scored runs, JSON reports and comparison between two runs.
+pub mod report;
+
+pub use report::{compare, CategoryScore, Comparison, Report, TaskResult};
+pub use suite::{Matcher, Suite, Task};
+
+/// Error type for the harness
+#[derive(Debug, thiserror::Error)]
+pub enum EvalError {
+    /// Reading or writing a suite/report failed
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+    /// Malformed JSON suite or report
+    #[error("json: {0}")]
+    Json(#[from] serde_json::Error),
+    /// A regex matcher does not compile
+    #[error("task {task}: invalid regex: {source}")]
+    Regex {
+        /// id of the offending task
+        task: String,
+        /// compile error
+        source: regex::Error,
+    },
+}
+
+/// Run every task of `suite` through `solve` and score the answers.
+pub fn run_suite<F: FnMut(&Task) -> String>(suite: &Suite, mut solve: F) -> Report {
+    let results = suite
+        .tasks
+        .iter()
+        .map(|task| {
+            let answer = solve(task);
+            TaskResult {
+                id: task.id.clone(),
+                category: task.category.clone(),
+                question: task.question.clone(),
+                expected: task.expected.clone(),
+                passed: task.matcher.matches(&answer, &task.expected),
+                answer,
+            }
+        })
+        .collect();
+    Report::new(&suite.name, results)
+}
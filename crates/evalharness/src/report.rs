@@ -0,0 +1,196 @@
+#![forbid(unsafe_code)]
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::EvalError;
+
+/// Outcome of one task.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TaskResult {
+    /// task id
+    pub id: String,
+    /// task category
+    pub category: String,
+    /// the question asked
+    pub question: String,
+    /// expected answer
+    pub expected: String,
+    /// answer given by the system under test
+    pub answer: String,
+    /// whether the matcher accepted the answer
+    pub passed: bool,
+}
+
+/// Pass count of one category (or of the whole suite).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CategoryScore {
+    /// tasks in the category
+    pub total: usize,
+    /// tasks passed
+    pub passed: usize,
+}
+
+impl CategoryScore {
+    /// Fraction passed (0 for an empty category).
+    pub fn score(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.passed as f64 / self.total as f64
+        }
+    }
+}
+
+/// A scored run of a suite.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Report {
+    /// suite name
+    pub suite: String,
+    /// number of tasks
+    pub total: usize,
+    /// number of tasks passed
+    pub passed: usize,
+    /// per-category scores
+    pub categories: BTreeMap<String, CategoryScore>,
+    /// every task, in suite order
+    pub results: Vec<TaskResult>,
+}
+
+impl Report {
+    /// Aggregate task results.
+    pub fn new(suite: &str, results: Vec<TaskResult>) -> Self {
+        let mut categories: BTreeMap<String, CategoryScore> = BTreeMap::new();
+        for r in &results {
+            let c = categories.entry(r.category.clone()).or_default();
+            c.total += 1;
+            c.passed += usize::from(r.passed);
+        }
+        let passed = results.iter().filter(|r| r.passed).count();
+        Self { suite: suite.to_string(), total: results.len(), passed, categories, results }
+    }
+
+    /// Overall fraction passed.
+    pub fn score(&self) -> f64 {
+        CategoryScore { total: self.total, passed: self.passed }.score()
+    }
+
+    /// Write the report as pretty JSON.
+    pub fn save(&self, path: &Path) -> Result<(), EvalError> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Read a report written by `save`.
+    pub fn load(path: &Path) -> Result<Self, EvalError> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Markdown summary: per-category table and failed tasks.
+    pub fn render_markdown(&self) -> String {
+        let mut md = format!("# Evaluation: {}\n\n{}/{} passed ({:.1}%)\n\n", self.suite, self.passed, self.total, 100.0 * self.score());
+        md.push_str("| category | passed | total | score |\n|---|---|---|---|\n");
+        for (name, c) in &self.categories {
+            md.push_str(&format!("| {} | {} | {} | {:.1}% |\n", name, c.passed, c.total, 100.0 * c.score()));
+        }
+        let failed: Vec<&TaskResult> = self.results.iter().filter(|r| !r.passed).collect();
+        if !failed.is_empty() {
+            md.push_str("\n## Failed\n\n");
+            for r in failed {
+                md.push_str(&format!("- [{}] {} — got `{}`, expected `{}`\n", r.id, r.question, r.answer, r.expected));
+            }
+        }
+        md
+    }
+}
+
+/// Score change of one category between two runs.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CategoryDelta {
+    /// category name
+    pub category: String,
+    /// score in the first run (`None` if the category was absent)
+    pub before: Option<CategoryScore>,
+    /// score in the second run (`None` if the category was absent)
+    pub after: Option<CategoryScore>,
+}
+
+/// Difference between two runs, matching tasks by question.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Comparison {
+    /// overall score of the first run
+    pub before_score: f64,
+    /// overall score of the second run
+    pub after_score: f64,
+    /// per-category scores of both runs
+    pub categories: Vec<CategoryDelta>,
+    /// questions that failed before and pass now
+    pub fixed: Vec<String>,
+    /// questions that passed before and fail now
+    pub regressed: Vec<String>,
+}
+
+/// Compare two runs (e.g. before/after a knowledge merge or a fine-tune).
+pub fn compare(before: &Report, after: &Report) -> Comparison {
+    let names: BTreeSet<&String> = before.categories.keys().chain(after.categories.keys()).collect();
+    let categories = names
+        .into_iter()
+        .map(|c| CategoryDelta { category: c.clone(), before: before.categories.get(c).copied(), after: after.categories.get(c).copied() })
+        .collect();
+    let was: HashMap<&str, bool> = before.results.iter().map(|r| (r.question.as_str(), r.passed)).collect();
+    let mut fixed = Vec::new();
+    let mut regressed = Vec::new();
+    for r in &after.results {
+        match (was.get(r.question.as_str()), r.passed) {
+            (Some(false), true) => fixed.push(r.question.clone()),
+            (Some(true), false) => regressed.push(r.question.clone()),
+            _ => {}
+        }
+    }
+    Comparison { before_score: before.score(), after_score: after.score(), categories, fixed, regressed }
+}
+
+impl Comparison {
+    /// Plain-text summary.
+    pub fn render(&self) -> String {
+        let pct = |s: Option<CategoryScore>| s.map(|s| format!("{:.1}%", 100.0 * s.score())).unwrap_or_else(|| "—".to_string());
+        let mut out = format!("score: {:.1}% → {:.1}%\n", 100.0 * self.before_score, 100.0 * self.after_score);
+        for c in &self.categories {
+            out.push_str(&format!("  {}: {} → {}\n", c.category, pct(c.before), pct(c.after)));
+        }
+        for q in &self.fixed {
+            out.push_str(&format!("  + {}\n", q));
+        }
+        for q in &self.regressed {
+            out.push_str(&format!("  - {}\n", q));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{run_suite, Suite};
+
+    #[test]
+    fn categories_json_round_trip_and_comparison() {
+        let suite = Suite::from_csv("s", "question,expected,category\n1+1,2,arith\n2+2,4,arith\nx=1,x = 1,algebra\n").unwrap_or_default();
+        let before = run_suite(&suite, |t| if t.question == "1+1" { "2".into() } else { "?".into() });
+        let after = run_suite(&suite, |t| if t.question == "1+1" { "?".into() } else { t.expected.clone() });
+        assert_eq!(before.categories.get("arith"), Some(&CategoryScore { total: 2, passed: 1 }));
+
+        let json = serde_json::to_string(&after).unwrap_or_default();
+        assert_eq!(serde_json::from_str::<Report>(&json).ok(), Some(after.clone()));
+
+        let cmp = compare(&before, &after);
+        assert_eq!(cmp.fixed, vec!["2+2".to_string(), "x=1".to_string()]);
+        assert_eq!(cmp.regressed, vec!["1+1".to_string()]);
+        assert!(cmp.render().contains("algebra: 0.0% → 100.0%"), "{}", cmp.render());
+    }
+}
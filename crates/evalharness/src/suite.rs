@@ -0,0 +1,242 @@
+#![forbid(unsafe_code)]
+
+use std::path::Path;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::EvalError;
+
+/// Category given to tasks that do not name one.
+pub const DEFAULT_CATEGORY: &str = "general";
+
+/// Default tolerance of `Matcher::Numeric` when a CSV suite does not give one.
+pub const DEFAULT_TOLERANCE: f64 = 1e-3;
+
+/// How an answer is compared with the expected one.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Matcher {
+    /// equal after trimming, lowercasing and dropping spaces
+    #[default]
+    Exact,
+    /// the number in the answer (after the last `=`, or the last number in
+    /// the text) is within `tolerance` of the expected number; `a/b` fractions
+    /// are accepted on both sides
+    Numeric {
+        /// absolute tolerance
+        tolerance: f64,
+    },
+    /// the expected string is a regex that must match somewhere in the answer
+    Regex,
+}
+
+impl Matcher {
+    /// Parse the CSV spelling: `exact`, `numeric`, `numeric:0.01`, `regex`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim().to_lowercase();
+        match s.split_once(':') {
+            Some(("numeric", tol)) => tol.trim().parse().ok().map(|tolerance| Matcher::Numeric { tolerance }),
+            Some(_) => None,
+            None => match s.as_str() {
+                "" | "exact" => Some(Matcher::Exact),
+                "numeric" => Some(Matcher::Numeric { tolerance: DEFAULT_TOLERANCE }),
+                "regex" => Some(Matcher::Regex),
+                _ => None,
+            },
+        }
+    }
+
+    /// Whether `answer` is accepted for `expected`.
+    pub fn matches(&self, answer: &str, expected: &str) -> bool {
+        match self {
+            Matcher::Exact => normalize(answer) == normalize(expected),
+            Matcher::Numeric { tolerance } => match (parse_number(answer), parse_number(expected)) {
+                (Some(a), Some(e)) => (a - e).abs() <= *tolerance,
+                _ => false,
+            },
+            Matcher::Regex => Regex::new(expected).is_ok_and(|re| re.is_match(answer)),
+        }
+    }
+}
+
+/// Normalization used by `Matcher::Exact` (trim, lowercase, drop spaces).
+pub fn normalize(s: &str) -> String {
+    s.trim().to_lowercase().replace(' ', "")
+}
+
+/// The number an answer states: the text after the last `=` if it parses,
+/// otherwise the last number (or `a/b` fraction) in the text.
+pub fn parse_number(s: &str) -> Option<f64> {
+    let value = |t: &str| -> Option<f64> {
+        let t = t.trim().replace(',', ".");
+        match t.split_once('/') {
+            Some((a, b)) => {
+                let (a, b) = (a.trim().parse::<f64>().ok()?, b.trim().parse::<f64>().ok()?);
+                (b != 0.0).then(|| a / b)
+            }
+            None => t.parse().ok(),
+        }
+    };
+    if let Some(v) = s.rsplit('=').next().and_then(value) {
+        return Some(v);
+    }
+    let re = Regex::new(r"-?\d+(?:[.,]\d+)?(?:\s*/\s*\d+(?:[.,]\d+)?)?").ok()?;
+    re.find_iter(s).last().and_then(|m| value(m.as_str()))
+}
+
+/// One question with its expected answer.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Task {
+    /// stable identifier (the row number when loaded from CSV)
+    #[serde(default)]
+    pub id: String,
+    /// scoring category
+    #[serde(default = "default_category")]
+    pub category: String,
+    /// prompt given to the system under test
+    pub question: String,
+    /// expected answer (a regex for `Matcher::Regex`)
+    pub expected: String,
+    /// comparison rule
+    #[serde(default)]
+    pub matcher: Matcher,
+}
+
+fn default_category() -> String {
+    DEFAULT_CATEGORY.to_string()
+}
+
+/// A named list of tasks.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Suite {
+    /// suite name, copied into reports
+    pub name: String,
+    /// the tasks, in evaluation order
+    pub tasks: Vec<Task>,
+}
+
+impl Suite {
+    /// Load a `.json` suite or a CSV one (anything else); the file stem is
+    /// used as the name of CSV suites and of JSON suites without one.
+    pub fn load(path: &Path) -> Result<Self, EvalError> {
+        let text = std::fs::read_to_string(path)?;
+        let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("json")) {
+            let mut suite = Self::from_json(&text)?;
+            if suite.name.is_empty() {
+                suite.name = stem;
+            }
+            Ok(suite)
+        } else {
+            Self::from_csv(&stem, &text)
+        }
+    }
+
+    /// Parse a JSON suite (`{"name": .., "tasks": [{"question", "expected",
+    /// "category"?, "matcher"?: {"type": "numeric", "tolerance": 0.01}}]}`).
+    pub fn from_json(text: &str) -> Result<Self, EvalError> {
+        let mut suite: Suite = serde_json::from_str(text)?;
+        suite.fill_ids();
+        suite.validate()?;
+        Ok(suite)
+    }
+
+    /// Parse `question,expected[,category[,matcher]]` rows (double quotes
+    /// allowed, header row skipped). Rows with an unknown matcher fall back
+    /// to exact matching.
+    pub fn from_csv(name: &str, text: &str) -> Result<Self, EvalError> {
+        let tasks = text
+            .lines()
+            .enumerate()
+            .filter_map(|(i, line)| {
+                let mut fields = split_csv_line(line).into_iter().map(|f| f.trim().to_string());
+                let (question, expected) = (fields.next()?, fields.next()?);
+                if i == 0 && question.eq_ignore_ascii_case("question") {
+                    return None;
+                }
+                let category = fields.next().filter(|c| !c.is_empty()).unwrap_or_else(default_category);
+                let matcher = fields.next().and_then(|m| Matcher::parse(&m)).unwrap_or_default();
+                Some(Task { id: String::new(), category, question, expected, matcher })
+            })
+            .collect();
+        let mut suite = Suite { name: name.to_string(), tasks };
+        suite.fill_ids();
+        suite.validate()?;
+        Ok(suite)
+    }
+
+    /// Build an exact-match suite from question/answer pairs.
+    pub fn from_pairs(name: &str, category: &str, pairs: &[(String, String)]) -> Self {
+        let tasks = pairs
+            .iter()
+            .map(|(q, a)| Task { id: String::new(), category: category.to_string(), question: q.clone(), expected: a.clone(), matcher: Matcher::Exact })
+            .collect();
+        let mut suite = Suite { name: name.to_string(), tasks };
+        suite.fill_ids();
+        suite
+    }
+
+    fn fill_ids(&mut self) {
+        for (i, task) in self.tasks.iter_mut().enumerate().filter(|(_, t)| t.id.is_empty()) {
+            task.id = (i + 1).to_string();
+        }
+    }
+
+    /// Check that every regex matcher compiles.
+    pub fn validate(&self) -> Result<(), EvalError> {
+        for task in self.tasks.iter().filter(|t| t.matcher == Matcher::Regex) {
+            Regex::new(&task.expected).map_err(|source| EvalError::Regex { task: task.id.clone(), source })?;
+        }
+        Ok(())
+    }
+}
+
+/// Split one CSV line into fields, honouring double quotes (`""` escapes a quote).
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matchers() {
+        assert!(Matcher::Exact.matches(" X = 3", "x=3"));
+        let numeric = Matcher::Numeric { tolerance: 1e-3 };
+        assert!(numeric.matches("Интеграл x^2 от 0 до 2 = 2.6667", "8/3"));
+        assert!(numeric.matches("ответ: 42 яблока", "42"));
+        assert!(!numeric.matches("не знаю", "42"));
+        assert!(Matcher::Regex.matches("2^x растет быстрее", "(?i)2\\^x.*быстр"));
+        assert_eq!(Matcher::parse("numeric:0.5"), Some(Matcher::Numeric { tolerance: 0.5 }));
+    }
+
+    #[test]
+    fn csv_and_json_suites() {
+        let csv = Suite::from_csv("p", "question,expected,category,matcher\n\"a, b\",1,arith,numeric\nq,x\n");
+        let tasks = csv.map(|s| s.tasks).unwrap_or_default();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks.first().map(|t| (t.question.as_str(), t.category.as_str(), t.id.as_str())), Some(("a, b", "arith", "1")));
+        assert_eq!(tasks.get(1).map(|t| t.category.as_str()), Some(DEFAULT_CATEGORY));
+
+        let json = r#"{"name":"s","tasks":[{"question":"q","expected":"(","matcher":{"type":"regex"}}]}"#;
+        assert!(matches!(Suite::from_json(json), Err(EvalError::Regex { .. })));
+    }
+}
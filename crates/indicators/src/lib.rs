@@ -10,13 +10,27 @@
 //! Pure indicator implementations. Functions are deterministic and return Results on invalid input.
 //!
 //! Contract: identical input slice -> identical output Vec.
+//! `streaming` has the same indicators updated one value at a time, and
+//! `regression` an online least-squares line (`OnlineRegressor`) with its
+//! streaming forecast `Trend`.
+//!
+//! Output `i` of an indicator belongs to input `i + warmup_len`, where the
+//! `*_warmup_len(period)` functions give the number of leading inputs without
+//! a value. With the `serde` feature the outputs and errors can be serialized.
+
+pub mod regression;
+pub mod streaming;
 
 /// Error type for indicators
 #[derive(Debug, PartialEq, thiserror::Error)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum IndicatorError {
     /// Provided period is zero or larger than input length
     #[error("invalid period")]
     InvalidPeriod,
+    /// A parameter is outside its range (e.g. a forgetting factor outside (0, 1])
+    #[error("invalid parameter")]
+    InvalidParameter,
 }
 
 /// Simple moving average (SMA).
@@ -39,6 +53,11 @@ pub fn sma(values: &[f64], period: usize) -> Result<Vec<f64>, IndicatorError> {
     Ok(res)
 }
 
+/// Leading inputs without an `sma` value: `period - 1`.
+pub fn sma_warmup_len(period: usize) -> usize {
+    period.saturating_sub(1)
+}
+
 /// Exponential moving average (EMA).
 ///
 /// Uses the standard smoothing alpha = 2/(period+1). The first EMA value is the SMA of the first `period` points.
@@ -59,9 +78,78 @@ pub fn ema(values: &[f64], period: usize) -> Result<Vec<f64>, IndicatorError> {
     Ok(res)
 }
 
+/// Leading inputs without an `ema` value: `period - 1`.
+pub fn ema_warmup_len(period: usize) -> usize {
+    period.saturating_sub(1)
+}
+
+/// Relative strength index (RSI) with Wilder smoothing.
+///
+/// The first value averages the gains and losses of the first `period` changes; later
+/// values smooth them by `(avg * (period-1) + x) / period`. Needs `period + 1` points and
+/// returns `values.len() - period` values in 0..=100 (100 when there are no losses).
+pub fn rsi(values: &[f64], period: usize) -> Result<Vec<f64>, IndicatorError> {
+    if period == 0 || period >= values.len() {
+        return Err(IndicatorError::InvalidPeriod);
+    }
+    let changes: Vec<f64> = values.windows(2).filter_map(|w| Some(w.get(1)? - w.first()?)).collect();
+    let value = |gain: f64, loss: f64| if loss == 0.0 { 100.0 } else { 100.0 - 100.0 / (1.0 + gain / loss) };
+    let mut gain = changes.iter().take(period).map(|c| c.max(0.0)).sum::<f64>() / period as f64;
+    let mut loss = changes.iter().take(period).map(|c| (-c).max(0.0)).sum::<f64>() / period as f64;
+    let mut res = Vec::with_capacity(values.len() - period);
+    res.push(value(gain, loss));
+    let n = period as f64;
+    for c in changes.iter().skip(period).copied() {
+        gain = (gain * (n - 1.0) + c.max(0.0)) / n;
+        loss = (loss * (n - 1.0) + (-c).max(0.0)) / n;
+        res.push(value(gain, loss));
+    }
+    Ok(res)
+}
+
+/// Leading inputs without an `rsi` value: `period` (the first one has no change).
+pub fn rsi_warmup_len(period: usize) -> usize {
+    period
+}
+
+/// One point of Bollinger bands.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bands {
+    /// `middle - k * stddev`
+    pub lower: f64,
+    /// SMA of the window
+    pub middle: f64,
+    /// `middle + k * stddev`
+    pub upper: f64,
+}
+
+/// Bollinger bands: SMA of `period` values plus/minus `k` population standard deviations.
+///
+/// Returns `values.len() - period + 1` bands aligned like `sma`.
+pub fn bollinger(values: &[f64], period: usize, k: f64) -> Result<Vec<Bands>, IndicatorError> {
+    let middles = sma(values, period)?;
+    Ok(values
+        .windows(period)
+        .zip(middles)
+        .map(|(window, middle)| {
+            let var = window.iter().map(|v| (v - middle).powi(2)).sum::<f64>() / period as f64;
+            let width = k * var.sqrt();
+            Bands { lower: middle - width, middle, upper: middle + width }
+        })
+        .collect())
+}
+
+/// Leading inputs without a `bollinger` value: `period - 1`, as for `sma`.
+pub fn bollinger_warmup_len(period: usize) -> usize {
+    sma_warmup_len(period)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
 
     #[test]
     fn sma_basic() {
@@ -76,4 +164,66 @@ mod tests {
         // For period=3 and values [1,2,3,4,5], expected EMA outputs are [2.0, 3.0, 4.0]
         assert_eq!(ema(&values, 3), Ok(vec![2.0, 3.0, 4.0]));
     }
+
+    #[test]
+    fn rsi_bounds_and_smoothing() {
+        assert_eq!(rsi(&[1.0, 2.0, 3.0, 4.0], 2), Ok(vec![100.0, 100.0]));
+        // changes +1, -1, +1: first avg gain = avg loss = 0.5 -> 50; then gain 0.75, loss 0.25 -> 75
+        assert_eq!(rsi(&[1.0, 2.0, 1.0, 2.0], 2), Ok(vec![50.0, 75.0]));
+        assert_eq!(rsi(&[1.0, 2.0], 2), Err(IndicatorError::InvalidPeriod));
+    }
+
+    #[test]
+    fn bollinger_width_is_k_stddevs() {
+        // window [1,3]: mean 2, stddev 1
+        assert_eq!(bollinger(&[1.0, 3.0], 2, 2.0), Ok(vec![Bands { lower: 0.0, middle: 2.0, upper: 4.0 }]));
+        assert_eq!(bollinger(&[1.0], 2, 2.0), Err(IndicatorError::InvalidPeriod));
+    }
+
+    #[test]
+    fn warmup_len_counts_missing_leading_values() {
+        let values = [3.0, 1.5, 4.0, 1.0, 5.5, 9.0, 2.0];
+        for period in 1..values.len() {
+            let missing = |len: Result<usize, IndicatorError>| len.map(|len| values.len() - len);
+            assert_eq!(missing(sma(&values, period).map(|v| v.len())), Ok(sma_warmup_len(period)));
+            assert_eq!(missing(ema(&values, period).map(|v| v.len())), Ok(ema_warmup_len(period)));
+            assert_eq!(missing(rsi(&values, period).map(|v| v.len())), Ok(rsi_warmup_len(period)));
+            assert_eq!(missing(bollinger(&values, period, 2.0).map(|v| v.len())), Ok(bollinger_warmup_len(period)));
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn sma_stays_within_window_bounds(values in vec(-1e6..1e6_f64, 1..60), period in 1usize..60) {
+            prop_assume!(period <= values.len());
+            let averages = sma(&values, period);
+            prop_assert_eq!(averages.as_ref().map(Vec::len), Ok(values.len() - period + 1));
+            for (average, window) in averages.unwrap_or_default().iter().zip(values.windows(period)) {
+                let (lo, hi) = window.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| (lo.min(*v), hi.max(*v)));
+                prop_assert!(*average >= lo - 1e-6 && *average <= hi + 1e-6, "{} outside [{}, {}]", average, lo, hi);
+            }
+        }
+
+        #[test]
+        fn ema_converges_to_constant_input(prefix in vec(-1e3..1e3_f64, 0..30), constant in -1e3..1e3_f64, period in 1usize..20) {
+            let mut values = prefix;
+            values.extend(std::iter::repeat_n(constant, 400));
+            let last = ema(&values, period).ok().and_then(|e| e.last().copied());
+            // the distance to the constant shrinks by 1 - 2/(period+1) per step
+            prop_assert!(last.is_some_and(|v| (v - constant).abs() < 1e-6), "{:?} vs {}", last, constant);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn outputs_round_trip_through_serde() {
+        let bands = bollinger(&[1.0, 3.0, 2.0], 2, 2.0);
+        assert_eq!(bands.as_ref().map(Vec::len), Ok(2));
+        let Ok(bands) = bands else { return };
+        let json = serde_json::to_string(&bands).unwrap_or_default();
+        assert!(json.starts_with(r#"[{"lower":0.0,"middle":2.0,"upper":4.0}"#));
+        assert_eq!(serde_json::from_str::<Vec<Bands>>(&json).ok(), Some(bands));
+        let error = serde_json::to_string(&IndicatorError::InvalidPeriod).ok();
+        assert_eq!(error.and_then(|e| serde_json::from_str(&e).ok()), Some(IndicatorError::InvalidPeriod));
+    }
 }
@@ -0,0 +1,167 @@
+//! Linear regression updated one observation at a time.
+//!
+//! `OnlineRegressor` fits `y = slope·x + intercept` by weighted least
+//! squares in which an observation `k` updates old weighs `forgetting^k`:
+//! 1 keeps every point (ordinary least squares), 0.95 forgets with a
+//! half-life of about 13.5 updates. It keeps weighted means and co-moments
+//! (Welford-style) instead of raw sums, so x values far from 0, such as
+//! timestamps, do not cancel out. `Trend` is the streaming indicator built
+//! on it: the line through the values against their index, extrapolated
+//! `horizon` steps past the latest one.
+
+use crate::streaming::StreamingIndicator;
+use crate::IndicatorError;
+
+/// Least-squares line with exponential forgetting.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OnlineRegressor {
+    forgetting: f64,
+    weight: f64,
+    mean_x: f64,
+    mean_y: f64,
+    sxx: f64,
+    sxy: f64,
+    syy: f64,
+}
+
+impl OnlineRegressor {
+    /// Regressor multiplying the weight of past observations by
+    /// `forgetting` (in (0, 1]) at every update.
+    pub fn new(forgetting: f64) -> Result<Self, IndicatorError> {
+        if !(forgetting > 0.0 && forgetting <= 1.0) {
+            return Err(IndicatorError::InvalidParameter);
+        }
+        Ok(Self { forgetting, weight: 0.0, mean_x: 0.0, mean_y: 0.0, sxx: 0.0, sxy: 0.0, syy: 0.0 })
+    }
+
+    /// Add the observation `(x, y)`; non-finite ones are skipped.
+    pub fn update(&mut self, x: f64, y: f64) {
+        if !x.is_finite() || !y.is_finite() {
+            return;
+        }
+        let l = self.forgetting;
+        self.weight = l * self.weight + 1.0;
+        let (dx, dy) = (x - self.mean_x, y - self.mean_y);
+        self.mean_x += dx / self.weight;
+        self.mean_y += dy / self.weight;
+        // dx·(y - new mean_y) = dx·dy·(λ·w_old / w_new), the decayed co-moment update
+        self.sxx = l * self.sxx + dx * (x - self.mean_x);
+        self.sxy = l * self.sxy + dx * (y - self.mean_y);
+        self.syy = l * self.syy + dy * (y - self.mean_y);
+    }
+
+    /// Slope of the line; None until two different x have been seen.
+    pub fn slope(&self) -> Option<f64> {
+        (self.sxx > 0.0).then(|| self.sxy / self.sxx)
+    }
+
+    /// Value of the line at x = 0.
+    pub fn intercept(&self) -> Option<f64> {
+        self.predict(0.0)
+    }
+
+    /// Value of the line at `x`.
+    pub fn predict(&self, x: f64) -> Option<f64> {
+        Some(self.mean_y + self.slope()? * (x - self.mean_x))
+    }
+
+    /// Weighted coefficient of determination (1 when y is constant).
+    pub fn r2(&self) -> Option<f64> {
+        let slope = self.slope()?;
+        Some(if self.syy == 0.0 { 1.0 } else { (slope * self.sxy / self.syy).clamp(0.0, 1.0) })
+    }
+
+    /// Total weight of the observations: their count without forgetting,
+    /// at most `1 / (1 - forgetting)` with it.
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+
+    /// Forget every observation.
+    pub fn reset(&mut self) {
+        *self = Self { forgetting: self.forgetting, weight: 0.0, mean_x: 0.0, mean_y: 0.0, sxx: 0.0, sxy: 0.0, syy: 0.0 };
+    }
+}
+
+/// Streaming forecast: the `OnlineRegressor` line through the values
+/// against their index, evaluated `horizon` values after the latest one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trend {
+    regressor: OnlineRegressor,
+    horizon: usize,
+    index: usize,
+}
+
+impl Trend {
+    /// Trend with `forgetting` in (0, 1], forecasting `horizon` steps ahead
+    /// (0 gives the fitted value of the latest one).
+    pub fn new(forgetting: f64, horizon: usize) -> Result<Self, IndicatorError> {
+        Ok(Self { regressor: OnlineRegressor::new(forgetting)?, horizon, index: 0 })
+    }
+}
+
+impl StreamingIndicator for Trend {
+    fn update(&mut self, value: f64) -> Option<f64> {
+        self.regressor.update(self.index as f64, value);
+        self.index += 1;
+        self.regressor.predict((self.index - 1 + self.horizon) as f64)
+    }
+
+    fn reset(&mut self) {
+        self.regressor.reset();
+        self.index = 0;
+    }
+
+    fn warmup_len(&self) -> usize {
+        1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn online_fit_tracks_lines_and_forgets() {
+        let (plain, forgetful) = (OnlineRegressor::new(1.0), OnlineRegressor::new(0.9));
+        assert_eq!((plain.as_ref().map(OnlineRegressor::slope), forgetful.as_ref().map(OnlineRegressor::weight)), (Ok(None), Ok(0.0)));
+        let (Ok(mut plain), Ok(mut forgetful)) = (plain, forgetful) else { return };
+        for x in 0..20 {
+            // timestamps far from 0
+            let x = 1.7e9 + 60.0 * x as f64;
+            plain.update(x, 0.5 * (x - 1.7e9) + 3.0);
+            forgetful.update(x, 0.5 * (x - 1.7e9) + 3.0);
+        }
+        for r in [&plain, &forgetful] {
+            assert!(r.slope().is_some_and(|s| (s - 0.5).abs() < 1e-9));
+            assert!(r.predict(1.7e9 + 6000.0).is_some_and(|y| (y - 3003.0).abs() < 1e-5));
+            assert!(r.r2().is_some_and(|r2| r2 > 1.0 - 1e-9));
+        }
+        assert_eq!(plain.weight(), 20.0);
+        assert!(forgetful.weight() < 10.0);
+
+        // the slope turns from +1 to -1: forgetting follows, plain least squares lags
+        let (plain, forgetful) = (OnlineRegressor::new(1.0), OnlineRegressor::new(0.8));
+        assert_eq!((plain.as_ref().map(OnlineRegressor::weight), forgetful.as_ref().map(OnlineRegressor::weight)), (Ok(0.0), Ok(0.0)));
+        let (Ok(mut plain), Ok(mut forgetful)) = (plain, forgetful) else { return };
+        for x in 0..100 {
+            let y = if x < 50 { x as f64 } else { 100.0 - x as f64 };
+            plain.update(x as f64, y);
+            forgetful.update(x as f64, y);
+        }
+        assert!(forgetful.slope().is_some_and(|s| (s + 1.0).abs() < 1e-3));
+        assert!(plain.slope().is_some_and(|s| s > -0.5));
+        plain.update(f64::NAN, 1.0);
+        assert_eq!(plain.weight(), 100.0);
+        plain.reset();
+        assert_eq!((plain.slope(), plain.weight()), (None, 0.0));
+
+        let trend = Trend::new(1.0, 1);
+        assert!(trend.is_ok());
+        let Ok(mut trend) = trend else { return };
+        let forecasts: Vec<Option<f64>> = [1.0, 2.0, 3.0].iter().map(|v| trend.update(*v)).collect();
+        assert_eq!(forecasts, vec![None, Some(3.0), Some(4.0)]);
+        assert_eq!(OnlineRegressor::new(0.0), Err(IndicatorError::InvalidParameter));
+        assert!(Trend::new(f64::NAN, 1).is_err());
+    }
+}
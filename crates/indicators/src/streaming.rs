@@ -0,0 +1,178 @@
+//! Incremental indicators fed one value at a time.
+//!
+//! Each `update` returns the indicator after including the new value, or
+//! `None` during the warm-up, and only ever sees the values passed so far.
+//! After the warm-up the outputs equal the batch functions of the crate root
+//! (`sma`, `ema`, `rsi`) on the same prefix, bit for bit.
+
+use std::collections::VecDeque;
+
+use crate::IndicatorError;
+
+/// An indicator updated with one value per bar.
+pub trait StreamingIndicator {
+    /// Add `value`; the indicator over all values so far (None while warming up).
+    fn update(&mut self, value: f64) -> Option<f64>;
+    /// Forget every value.
+    fn reset(&mut self);
+    /// Updates returning None before the first value, as the batch `*_warmup_len`.
+    fn warmup_len(&self) -> usize;
+}
+
+fn check(period: usize) -> Result<usize, IndicatorError> {
+    if period == 0 {
+        Err(IndicatorError::InvalidPeriod)
+    } else {
+        Ok(period)
+    }
+}
+
+/// Streaming `sma`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sma {
+    period: usize,
+    window: VecDeque<f64>,
+}
+
+impl Sma {
+    /// SMA over `period` values (> 0).
+    pub fn new(period: usize) -> Result<Self, IndicatorError> {
+        Ok(Self { period: check(period)?, window: VecDeque::with_capacity(period) })
+    }
+}
+
+impl StreamingIndicator for Sma {
+    fn update(&mut self, value: f64) -> Option<f64> {
+        if self.window.len() == self.period {
+            self.window.pop_front();
+        }
+        self.window.push_back(value);
+        // summed in window order like `sma`, so the results match exactly
+        (self.window.len() == self.period).then(|| self.window.iter().sum::<f64>() / self.period as f64)
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+    }
+
+    fn warmup_len(&self) -> usize {
+        crate::sma_warmup_len(self.period)
+    }
+}
+
+/// Streaming `ema`: the SMA of the first `period` values, then exponential smoothing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ema {
+    period: usize,
+    seen: usize,
+    sum: f64,
+    value: Option<f64>,
+}
+
+impl Ema {
+    /// EMA with alpha `2 / (period + 1)` (period > 0).
+    pub fn new(period: usize) -> Result<Self, IndicatorError> {
+        Ok(Self { period: check(period)?, seen: 0, sum: 0.0, value: None })
+    }
+}
+
+impl StreamingIndicator for Ema {
+    fn update(&mut self, value: f64) -> Option<f64> {
+        self.seen += 1;
+        self.value = match self.value {
+            Some(prev) => {
+                let alpha = 2.0 / (self.period as f64 + 1.0);
+                Some(alpha * value + (1.0 - alpha) * prev)
+            }
+            None => {
+                self.sum += value;
+                (self.seen == self.period).then(|| self.sum / self.period as f64)
+            }
+        };
+        self.value
+    }
+
+    fn reset(&mut self) {
+        (self.seen, self.sum, self.value) = (0, 0.0, None);
+    }
+
+    fn warmup_len(&self) -> usize {
+        crate::ema_warmup_len(self.period)
+    }
+}
+
+/// Streaming `rsi` with Wilder smoothing; the first value needs `period + 1` inputs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rsi {
+    period: usize,
+    prev: Option<f64>,
+    changes: usize,
+    gain: f64,
+    loss: f64,
+}
+
+impl Rsi {
+    /// RSI over `period` changes (> 0).
+    pub fn new(period: usize) -> Result<Self, IndicatorError> {
+        Ok(Self { period: check(period)?, prev: None, changes: 0, gain: 0.0, loss: 0.0 })
+    }
+}
+
+impl StreamingIndicator for Rsi {
+    fn update(&mut self, value: f64) -> Option<f64> {
+        let prev = self.prev.replace(value)?;
+        let change = value - prev;
+        let n = self.period as f64;
+        self.changes += 1;
+        if self.changes <= self.period {
+            // accumulate the first averages as sums, divided once like `rsi`
+            self.gain += change.max(0.0);
+            self.loss += (-change).max(0.0);
+            if self.changes < self.period {
+                return None;
+            }
+            self.gain /= n;
+            self.loss /= n;
+        } else {
+            self.gain = (self.gain * (n - 1.0) + change.max(0.0)) / n;
+            self.loss = (self.loss * (n - 1.0) + (-change).max(0.0)) / n;
+        }
+        Some(if self.loss == 0.0 { 100.0 } else { 100.0 - 100.0 / (1.0 + self.gain / self.loss) })
+    }
+
+    fn reset(&mut self) {
+        (self.prev, self.changes, self.gain, self.loss) = (None, 0, 0.0, 0.0);
+    }
+
+    fn warmup_len(&self) -> usize {
+        crate::rsi_warmup_len(self.period)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ema, ema_warmup_len, rsi, rsi_warmup_len, sma, sma_warmup_len};
+
+    fn run(indicator: &mut dyn StreamingIndicator, values: &[f64]) -> Vec<f64> {
+        values.iter().filter_map(|v| indicator.update(*v)).collect()
+    }
+
+    #[test]
+    fn streaming_matches_batch() {
+        let values = [3.0, 1.5, 4.0, 1.0, 5.5, 9.0, 2.0, 6.5, 5.0, 3.5, 5.0, 8.0];
+        let (s, e, r) = (Sma::new(3), Ema::new(4), Rsi::new(5));
+        let warmup = (s.as_ref().map(|s| s.warmup_len()), e.as_ref().map(|e| e.warmup_len()), r.as_ref().map(|r| r.warmup_len()));
+        assert_eq!(warmup, (Ok(sma_warmup_len(3)), Ok(ema_warmup_len(4)), Ok(rsi_warmup_len(5))));
+        let (Ok(mut s), Ok(mut e), Ok(mut r)) = (s, e, r) else { return };
+        assert_eq!(Ok(run(&mut s, &values)), sma(&values, 3));
+        assert_eq!(Ok(run(&mut e, &values)), ema(&values, 4));
+        assert_eq!(Ok(run(&mut r, &values)), rsi(&values, 5));
+        r.reset();
+        assert_eq!(Ok(run(&mut r, &values)), rsi(&values, 5));
+        r.reset();
+        let leading = values.iter().take_while(|v| r.update(**v).is_none()).count();
+        assert_eq!(leading, r.warmup_len());
+        assert_eq!(Sma::new(0), Err(IndicatorError::InvalidPeriod));
+    }
+}
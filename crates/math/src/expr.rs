@@ -0,0 +1,122 @@
+//! Arithmetic expression evaluator: numbers, `+ - * /` and parentheses,
+//! via shunting-yard to reverse Polish notation.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+#[derive(Debug)]
+enum Token {
+    Num(f64),
+    Op(char),
+    LParen,
+    RParen,
+}
+
+fn tokenize(s: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() { continue; }
+        if c.is_ascii_digit() || c == '.' {
+            let mut num = String::from(c);
+            while let Some(&d) = chars.peek().filter(|d| d.is_ascii_digit() || **d == '.') {
+                num.push(d);
+                chars.next();
+            }
+            tokens.push(Token::Num(num.parse::<f64>().ok()?));
+            continue;
+        }
+        match c {
+            '+' | '-' | '*' | '/' => tokens.push(Token::Op(c)),
+            '(' => tokens.push(Token::LParen),
+            ')' => tokens.push(Token::RParen),
+            // unsupported char
+            _ => return None,
+        }
+    }
+    Some(tokens)
+}
+
+fn prec(op: char) -> i32 {
+    match op { '+'|'-' => 1, '*'|'/' => 2, _ => 0 }
+}
+
+/// Evaluate `expr`; `None` for any other character or a malformed expression.
+pub fn eval(expr: &str) -> Option<f64> {
+    let toks = tokenize(expr)?;
+    // to RPN
+    let mut out: Vec<Token> = Vec::new();
+    let mut ops: Vec<char> = Vec::new();
+    for t in toks {
+        match t {
+            Token::Num(_) => out.push(t),
+            Token::Op(op) => {
+                while let Some(&top) = ops.last() {
+                    if top=='(' { break; }
+                    if prec(top) >= prec(op) {
+                        out.push(Token::Op(top)); ops.pop();
+                    } else { break; }
+                }
+                ops.push(op);
+            }
+            Token::LParen => ops.push('('),
+            Token::RParen => {
+                while let Some(top) = ops.pop() {
+                    if top == '(' { break; }
+                    out.push(Token::Op(top));
+                }
+            }
+        }
+    }
+    while let Some(op) = ops.pop() { out.push(Token::Op(op)); }
+
+    // eval RPN
+    let mut stack: Vec<f64> = Vec::new();
+    for t in out {
+        match t {
+            Token::Num(v) => stack.push(v),
+            Token::Op(op) => {
+                let b = stack.pop()?;
+                let a = stack.pop()?;
+                let r = match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    '/' => a / b,
+                    _ => return None,
+                };
+                stack.push(r);
+            }
+            _ => return None,
+        }
+    }
+    let [v] = stack.as_slice() else { return None };
+    Some(*v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn precedence_parentheses_and_rejects() {
+        assert_eq!(eval("2 + 3 * 4"), Some(14.0));
+        assert_eq!(eval("(2 + 3) * 4"), Some(20.0));
+        assert_eq!(eval("7 / 2 - 0.5"), Some(3.0));
+        assert_eq!(eval("2 +"), None);
+        assert_eq!(eval("2 ^ 3"), None);
+    }
+
+    proptest! {
+        #[test]
+        fn garbage_never_panics(any in ".*", near in "[0-9. +*/()-]{0,40}") {
+            let _ = eval(&any);
+            // mostly valid characters reach the RPN stage: unbalanced parentheses,
+            // runs of operators, several dots in a number
+            if let Some(v) = eval(&near) {
+                prop_assert!(!v.is_nan() || near.contains('/'));
+            }
+        }
+    }
+}
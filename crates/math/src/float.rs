@@ -0,0 +1,32 @@
+//! Float functions missing from `core`: the platform ones with `std`,
+//! `libm` otherwise.
+
+#[cfg(feature = "std")]
+pub(crate) fn exp(x: f32) -> f32 {
+    x.exp()
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn exp(x: f32) -> f32 {
+    libm::expf(x)
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn ln(x: f32) -> f32 {
+    x.ln()
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn ln(x: f32) -> f32 {
+    libm::logf(x)
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn ln_f64(x: f64) -> f64 {
+    x.ln()
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn ln_f64(x: f64) -> f64 {
+    libm::log(x)
+}
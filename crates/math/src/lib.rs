@@ -0,0 +1,22 @@
+#![no_std]
+#![forbid(unsafe_code)]
+#![deny(clippy::unwrap_used, clippy::expect_used, clippy::panic, clippy::indexing_slicing)]
+#![deny(missing_docs, unused_must_use)]
+
+//! Inference primitives of Shark-Core that need only `core` + `alloc`:
+//! softmax and log-softmax, sampling from a distribution, the dense `Linear` layer and the
+//! arithmetic expression evaluator. `predict` re-exports them; embedded
+//! targets can depend on this crate alone with `default-features = false`
+//! (float functions then come from `libm`).
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod expr;
+mod float;
+pub mod linear;
+pub mod sample;
+
+pub use linear::{Accumulation, Linear};
+pub use sample::{log_softmax, log_softmax_with, sample_gumbel, sample_index, sample_top_k, softmax, softmax_with, top_k_indices};
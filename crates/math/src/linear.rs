@@ -0,0 +1,102 @@
+//! Dense layer: `out = W * in + b`.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Type the dot products of a forward pass are summed in. Inputs, weights
+/// and outputs stay f32 either way; f64 sums lose less to cancellation in wide
+/// layers, at the cost of a conversion per product.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "lowercase"))]
+pub enum Accumulation {
+    /// f32 sums (the default)
+    #[default]
+    F32,
+    /// f64 sums, rounded to f32 once per output
+    F64,
+}
+
+impl Accumulation {
+    /// `row · input` (missing inputs count as zero) summed at this precision.
+    pub fn dot(self, row: &[f32], input: &[f32]) -> f32 {
+        match self {
+            Accumulation::F32 => row.iter().zip(input).map(|(w, x)| w * x).sum(),
+            Accumulation::F64 => row.iter().zip(input).map(|(w, x)| *w as f64 * *x as f64).sum::<f64>() as f32,
+        }
+    }
+}
+
+/// Dense layer container
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Linear {
+    /// input dimension
+    pub in_dim: usize,
+    /// output dimension
+    pub out_dim: usize,
+    /// weights in row-major order: out_dim x in_dim
+    pub weights: Vec<f32>,
+    /// bias vector of length out_dim
+    pub bias: Vec<f32>,
+}
+
+impl Linear {
+    /// Create a Linear layer from raw weight buffer. If buffer too small, fill zeros.
+    pub fn from_raw(in_dim: usize, out_dim: usize, raw: &[f32]) -> Self {
+        let expected = out_dim * in_dim;
+        let mut weights = vec![0.0_f32; expected];
+        for (w, r) in weights.iter_mut().zip(raw) {
+            *w = *r;
+        }
+        let mut bias = vec![0.0_f32; out_dim];
+        // if raw contains bias after weights, copy
+        if raw.len() >= expected + out_dim {
+            for (b, r) in bias.iter_mut().zip(raw.iter().skip(expected)) {
+                *b = *r;
+            }
+        }
+        Self { in_dim, out_dim, weights, bias }
+    }
+
+    /// Number of floats `from_raw` consumes: weights followed by bias.
+    pub fn param_count(in_dim: usize, out_dim: usize) -> usize {
+        in_dim * out_dim + out_dim
+    }
+
+    /// Forward pass for a single input vector (missing inputs count as zero)
+    pub fn forward(&self, input: &[f32]) -> Vec<f32> {
+        self.forward_with(input, Accumulation::F32)
+    }
+
+    /// `forward` with the dot products summed at `acc` precision.
+    pub fn forward_with(&self, input: &[f32], acc: Accumulation) -> Vec<f32> {
+        if self.in_dim == 0 {
+            return self.bias.clone();
+        }
+        self.weights
+            .chunks(self.in_dim)
+            .zip(&self.bias)
+            .map(|(row, b)| acc.dot(row, input) + b)
+            .collect()
+    }
+
+    /// `forward` for several inputs in one pass over the weights (each row is
+    /// read once for the whole batch); output `i` belongs to input `i`.
+    pub fn forward_batch(&self, inputs: &[Vec<f32>]) -> Vec<Vec<f32>> {
+        self.forward_batch_with(inputs, Accumulation::F32)
+    }
+
+    /// `forward_batch` with the dot products summed at `acc` precision.
+    pub fn forward_batch_with(&self, inputs: &[Vec<f32>], acc: Accumulation) -> Vec<Vec<f32>> {
+        if self.in_dim == 0 {
+            return inputs.iter().map(|_| self.bias.clone()).collect();
+        }
+        let mut outputs = vec![Vec::with_capacity(self.out_dim); inputs.len()];
+        for (row, b) in self.weights.chunks(self.in_dim).zip(&self.bias) {
+            for (out, input) in outputs.iter_mut().zip(inputs) {
+                out.push(acc.dot(row, input) + b);
+            }
+        }
+        outputs
+    }
+}
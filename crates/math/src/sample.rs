@@ -0,0 +1,416 @@
+//! Softmax, log-softmax and sampling an index from a probability vector.
+//!
+//! The `_with` variants divide the logits by a temperature first and sum the
+//! exponentials at an `Accumulation` precision; `softmax` and `log_softmax`
+//! are temperature 1 with f32 sums. Both shift by the maximum, so large
+//! logits do not overflow, and `log_softmax` stays finite where the softmax
+//! itself underflows to 0. NaN logits count as -inf everywhere, so they are
+//! never sampled.
+//!
+//! `sample_gumbel` samples straight from logits (Gumbel-max: the argmax of
+//! the logits plus Gumbel noise follows their softmax), without normalizing
+//! or walking a CDF. `top_k_indices` selects the `k` largest logits with a
+//! partial sort, and `sample_top_k` samples among them.
+
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+use rand::Rng;
+
+use crate::float;
+use crate::linear::Accumulation;
+
+/// Softmax of `logits` in place (shifted by the maximum for stability).
+/// NaN logits get probability 0 and +inf logits share all of it; logits
+/// that are all -inf (or NaN) give all zeros.
+pub fn softmax(logits: &mut [f32]) {
+    softmax_with(logits, 1.0, Accumulation::F32);
+}
+
+/// Log-softmax of `logits` in place: `x - max - ln Σ exp(x - max)`.
+pub fn log_softmax(logits: &mut [f32]) {
+    log_softmax_with(logits, 1.0, Accumulation::F32);
+}
+
+/// Softmax of `logits / temperature` in place, the exponentials summed at
+/// `acc` precision. A temperature of 0 or below (or NaN) is the greedy
+/// limit: 1 at the first maximum, 0 elsewhere.
+pub fn softmax_with(logits: &mut [f32], temperature: f32, acc: Accumulation) {
+    let Some(max) = scale(logits, temperature) else { return };
+    match acc {
+        Accumulation::F32 => {
+            let mut sum = 0.0_f32;
+            for v in logits.iter_mut() {
+                *v = float::exp(*v - max);
+                sum += *v;
+            }
+            if sum == 0.0 { return; }
+            for v in logits.iter_mut() { *v /= sum; }
+        }
+        Accumulation::F64 => {
+            let mut sum = 0.0_f64;
+            for v in logits.iter_mut() {
+                *v = float::exp(*v - max);
+                sum += *v as f64;
+            }
+            if sum == 0.0 { return; }
+            for v in logits.iter_mut() { *v = (*v as f64 / sum) as f32; }
+        }
+    }
+}
+
+/// Log-softmax of `logits / temperature` in place, the exponentials summed
+/// at `acc` precision. The greedy limit (temperature 0 or below) is 0 at the
+/// first maximum and -inf elsewhere.
+pub fn log_softmax_with(logits: &mut [f32], temperature: f32, acc: Accumulation) {
+    let Some(max) = scale(logits, temperature) else {
+        for v in logits.iter_mut() { *v = if *v > 0.0 { 0.0 } else { f32::NEG_INFINITY }; }
+        return;
+    };
+    match acc {
+        Accumulation::F32 => {
+            let lse = float::ln(logits.iter().map(|v| float::exp(v - max)).sum::<f32>());
+            for v in logits.iter_mut() { *v = *v - max - lse; }
+        }
+        Accumulation::F64 => {
+            let lse = float::ln_f64(logits.iter().map(|v| float::exp(v - max) as f64).sum::<f64>());
+            for v in logits.iter_mut() { *v = ((*v - max) as f64 - lse) as f32; }
+        }
+    }
+}
+
+/// Divide `logits` by `temperature` and return their maximum, with NaN
+/// counted as -inf and, when some logits are +inf, those set to 0 and the
+/// rest to -inf (the +inf ones share the mass). Finite logits that a tiny
+/// temperature pushes to +inf do not share it: the first maximum gets all
+/// of it, the limit as the temperature goes to 0. None when there is nothing
+/// to normalize: for an empty slice, for the greedy limit after setting the
+/// slice to the one-hot of its first maximum, and for all -inf after setting
+/// it to zeros.
+fn scale(logits: &mut [f32], temperature: f32) -> Option<f32> {
+    if logits.is_empty() {
+        return None;
+    }
+    for v in logits.iter_mut().filter(|v| v.is_nan()) {
+        *v = f32::NEG_INFINITY;
+    }
+    let first_max = logits.iter().enumerate().fold(0, |best, (i, v)| if logits.get(best).is_some_and(|b| v > b) { i } else { best });
+    if temperature.is_nan() || temperature <= 0.0 {
+        for (i, v) in logits.iter_mut().enumerate() { *v = if i == first_max { 1.0 } else { 0.0 }; }
+        return None;
+    }
+    if logits.contains(&f32::INFINITY) {
+        for v in logits.iter_mut() { *v = if *v == f32::INFINITY { 0.0 } else { f32::NEG_INFINITY }; }
+        return Some(0.0);
+    }
+    if temperature != 1.0 {
+        for v in logits.iter_mut() { *v /= temperature; }
+    }
+    let max = logits.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+    if max == f32::INFINITY {
+        for (i, v) in logits.iter_mut().enumerate() { *v = if i == first_max { 0.0 } else { f32::NEG_INFINITY }; }
+        return Some(0.0);
+    }
+    if max == f32::NEG_INFINITY {
+        logits.fill(0.0);
+        return None;
+    }
+    Some(max)
+}
+
+/// Sample an index from `probs` (expected to sum to 1) with one uniform
+/// draw from `rng`; rounding leftovers fall to the last index.
+pub fn sample_index<R: Rng + ?Sized>(probs: &[f32], rng: &mut R) -> usize {
+    let r: f32 = rng.gen();
+    let mut acc = 0.0_f32;
+    for (i, &p) in probs.iter().enumerate() {
+        acc += p;
+        if r <= acc {
+            return i;
+        }
+    }
+    probs.len().saturating_sub(1)
+}
+
+/// Sample an index from the softmax of `logits` with the Gumbel-max trick:
+/// the argmax of `logit - ln(-ln u)` over one uniform `u` per logit. Uses
+/// `logits.len()` draws from `rng` (`sample_index` uses one); 0 for no logits.
+pub fn sample_gumbel<R: Rng + ?Sized>(logits: &[f32], rng: &mut R) -> usize {
+    gumbel_argmax(logits.iter().copied().enumerate(), rng)
+}
+
+/// Indices of the `k` largest `logits`, largest first (ties in index order).
+/// Selects them in linear time and sorts only those `k`.
+pub fn top_k_indices(logits: &[f32], k: usize) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..logits.len()).collect();
+    let value = |i: &usize| logits.get(*i).copied().filter(|v| !v.is_nan()).unwrap_or(f32::NEG_INFINITY);
+    let order = |a: &usize, b: &usize| value(b).total_cmp(&value(a)).then(a.cmp(b));
+    if k == 0 {
+        return Vec::new();
+    }
+    if k < indices.len() {
+        indices.select_nth_unstable_by(k - 1, order);
+        indices.truncate(k);
+    }
+    indices.sort_unstable_by(order);
+    indices
+}
+
+/// Sample an index from the softmax of the `k` largest `logits` (top-k
+/// sampling), by Gumbel-max over those `k`; 0 for no logits or `k = 0`.
+pub fn sample_top_k<R: Rng + ?Sized>(logits: &[f32], k: usize, rng: &mut R) -> usize {
+    let top = top_k_indices(logits, k);
+    gumbel_argmax(top.iter().map(|&i| (i, logits.get(i).copied().unwrap_or(f32::NEG_INFINITY))), rng)
+}
+
+/// Index of the largest `logit + Gumbel noise` among `(index, logit)` pairs
+/// (0 when empty). +inf logits beat all others and compare by noise alone,
+/// as `softmax` shares the mass between them.
+fn gumbel_argmax<R: Rng + ?Sized>(logits: impl Iterator<Item = (usize, f32)>, rng: &mut R) -> usize {
+    let mut best = (0, false, f32::NEG_INFINITY);
+    for (i, logit) in logits.filter(|(_, logit)| !logit.is_nan()) {
+        // u in (0, 1): a draw of exactly 0 would make the noise -inf
+        let u = rng.gen::<f32>().max(f32::MIN_POSITIVE);
+        let infinite = logit == f32::INFINITY;
+        let key = if infinite { 0.0 } else { logit } - float::ln(-float::ln(u));
+        if infinite.cmp(&best.1).then(key.total_cmp(&best.2)) == Ordering::Greater {
+            best = (i, infinite, key);
+        }
+    }
+    best.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use rand::rngs::mock::StepRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn softmax_normalizes_and_sampling_follows_the_cdf() {
+        let mut probs = [1.0_f32, 1.0, 1.0, 1.0];
+        softmax(&mut probs);
+        assert_eq!(probs, [0.25; 4]);
+        // constant draws of 0 and of almost 1 pick the first and the last index
+        assert_eq!(sample_index(&probs, &mut StepRng::new(0, 0)), 0);
+        assert_eq!(sample_index(&probs, &mut StepRng::new(u64::MAX, 0)), 3);
+        assert_eq!(sample_index(&[], &mut StepRng::new(0, 0)), 0);
+    }
+
+    #[test]
+    fn log_softmax_stays_finite_and_temperature_zero_is_greedy() {
+        // the softmax of the second logit underflows to 0, its log does not
+        let mut logits = [1000.0_f32, 0.0];
+        log_softmax(&mut logits);
+        assert_eq!(logits, [0.0, -1000.0]);
+        let mut hot = [1.0_f32, 3.0, 3.0];
+        softmax_with(&mut hot, 0.0, Accumulation::F64);
+        assert_eq!(hot, [0.0, 1.0, 0.0]);
+        let mut log_hot = [1.0_f32, 3.0, 3.0];
+        log_softmax_with(&mut log_hot, 0.0, Accumulation::F32);
+        assert_eq!(log_hot, [f32::NEG_INFINITY, 0.0, f32::NEG_INFINITY]);
+        // a high temperature flattens, a low one sharpens
+        let (mut flat, mut sharp) = ([0.0_f32, 1.0], [0.0_f32, 1.0]);
+        softmax_with(&mut flat, 100.0, Accumulation::F32);
+        softmax_with(&mut sharp, 0.1, Accumulation::F32);
+        assert!(flat.first() > Some(&0.49) && sharp.first() < Some(&0.001));
+    }
+
+    #[test]
+    fn gumbel_follows_the_softmax_and_top_k_selects_the_largest() {
+        let logits = [0.0_f32, 1.0, 2.0, 1.0, -1.0];
+        assert_eq!(top_k_indices(&logits, 3), [2, 1, 3]);
+        assert_eq!(top_k_indices(&logits, 9), [2, 1, 3, 0, 4]);
+        assert!(top_k_indices(&logits, 0).is_empty());
+
+        let mut probs = logits;
+        softmax(&mut probs);
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(5);
+        let (mut counts, mut top) = ([0usize; 5], [0usize; 5]);
+        let draws = 20_000;
+        for _ in 0..draws {
+            if let Some(c) = counts.get_mut(sample_gumbel(&logits, &mut rng)) {
+                *c += 1;
+            }
+            if let Some(c) = top.get_mut(sample_top_k(&logits, 1, &mut rng)) {
+                *c += 1;
+            }
+        }
+        for (c, p) in counts.iter().zip(&probs) {
+            assert!((*c as f32 / draws as f32 - p).abs() < 0.02);
+        }
+        assert_eq!(top, [0, 0, draws, 0, 0]);
+    }
+
+    /// Pearson's statistic of `counts` against `probs`; a count where the
+    /// probability is 0 makes it infinite.
+    fn chi_squared(counts: &[usize], probs: &[f32]) -> f64 {
+        let n = counts.iter().sum::<usize>() as f64;
+        counts
+            .iter()
+            .zip(probs)
+            .map(|(&c, &p)| match (c, n * p as f64) {
+                (0, 0.0) => 0.0,
+                (_, 0.0) => f64::INFINITY,
+                (c, e) => (c as f64 - e) * (c as f64 - e) / e,
+            })
+            .sum()
+    }
+
+    /// Draw `draws` indices over `len` outcomes with `sample`.
+    fn histogram(len: usize, draws: usize, mut sample: impl FnMut() -> usize) -> Vec<usize> {
+        let mut counts = alloc::vec![0; len];
+        for _ in 0..draws {
+            if let Some(c) = counts.get_mut(sample()) {
+                *c += 1;
+            }
+        }
+        counts
+    }
+
+    #[test]
+    fn samplers_pass_chi_squared() {
+        let logits = [0.5_f32, 1.0, 2.0, -0.5, 0.0];
+        let draws = 50_000;
+        // critical values at p = 0.001 for 4 and 2 degrees of freedom
+        let (df4, df2) = (18.47, 13.82);
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(11);
+
+        let mut probs = logits;
+        softmax(&mut probs);
+        let cdf = histogram(5, draws, || sample_index(&probs, &mut rng));
+        assert!(chi_squared(&cdf, &probs) < df4);
+        let gumbel = histogram(5, draws, || sample_gumbel(&logits, &mut rng));
+        assert!(chi_squared(&gumbel, &probs) < df4);
+
+        let mut warm = logits;
+        softmax_with(&mut warm, 2.0, Accumulation::F64);
+        let halved = logits.map(|v| v / 2.0);
+        assert!(chi_squared(&histogram(5, draws, || sample_index(&warm, &mut rng)), &warm) < df4);
+        assert!(chi_squared(&histogram(5, draws, || sample_gumbel(&halved, &mut rng)), &warm) < df4);
+
+        // top 3 (indices 2, 1, 0) renormalized, the rest never drawn
+        let mut top = [0.5_f32, 1.0, 2.0, f32::NEG_INFINITY, f32::NEG_INFINITY];
+        softmax(&mut top);
+        let top_k = histogram(5, draws, || sample_top_k(&logits, 3, &mut rng));
+        assert!(chi_squared(&top_k, &top) < df2);
+        // the wrong distribution is rejected
+        assert!(chi_squared(&gumbel, &warm) > df4);
+    }
+
+    #[test]
+    fn degenerate_logits() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(3);
+        let mut empty: [f32; 0] = [];
+        softmax(&mut empty);
+        log_softmax_with(&mut empty, 0.0, Accumulation::F64);
+        assert_eq!((sample_gumbel(&empty, &mut rng), sample_top_k(&empty, 2, &mut rng)), (0, 0));
+        assert!(top_k_indices(&empty, 2).is_empty());
+
+        let (mut one, mut log_one) = ([7.0_f32], [7.0_f32]);
+        softmax(&mut one);
+        log_softmax(&mut log_one);
+        assert_eq!((one, log_one), ([1.0], [0.0]));
+        assert_eq!((sample_index(&one, &mut rng), sample_gumbel(&[7.0], &mut rng), sample_top_k(&[7.0], 5, &mut rng)), (0, 0, 0));
+
+        // NaN never gets mass or a draw
+        let nan = [f32::NAN, 1.0, f32::NAN];
+        let (mut probs, mut logs, mut greedy) = (nan, nan, nan);
+        softmax(&mut probs);
+        log_softmax(&mut logs);
+        softmax_with(&mut greedy, 0.0, Accumulation::F32);
+        assert_eq!((probs, greedy), ([0.0, 1.0, 0.0], [0.0, 1.0, 0.0]));
+        assert_eq!(logs, [f32::NEG_INFINITY, 0.0, f32::NEG_INFINITY]);
+        assert_eq!(histogram(3, 1000, || sample_gumbel(&nan, &mut rng)), [0, 1000, 0]);
+        assert_eq!(top_k_indices(&[f32::NAN, 1.0, 0.0], 2), [1, 2]);
+
+        // +inf logits share the mass
+        let inf = [f32::INFINITY, 0.0, f32::INFINITY];
+        let (mut probs, mut logs) = (inf, inf);
+        softmax(&mut probs);
+        log_softmax_with(&mut logs, 1.0, Accumulation::F64);
+        assert_eq!(probs, [0.5, 0.0, 0.5]);
+        assert_eq!(logs, [-core::f32::consts::LN_2, f32::NEG_INFINITY, -core::f32::consts::LN_2]);
+        assert!(chi_squared(&histogram(3, 10_000, || sample_gumbel(&inf, &mut rng)), &probs) < 13.82);
+
+        // finite logits a tiny temperature overflows do not: the first maximum takes it
+        let tiny = [1.0_f32, 2.0, 2.0];
+        let (mut probs, mut logs) = (tiny, tiny);
+        softmax_with(&mut probs, 1e-39, Accumulation::F32);
+        log_softmax_with(&mut logs, 1e-39, Accumulation::F64);
+        assert_eq!(probs, [0.0, 1.0, 0.0]);
+        assert_eq!(logs, [f32::NEG_INFINITY, 0.0, f32::NEG_INFINITY]);
+        let mut mixed = [f32::INFINITY, 2.0, f32::INFINITY];
+        softmax_with(&mut mixed, 1e-39, Accumulation::F32);
+        assert_eq!(mixed, [0.5, 0.0, 0.5]);
+
+        // nothing finite: zeros, all -inf logs, index 0
+        for dead in [[f32::NEG_INFINITY; 2], [f32::NAN; 2]] {
+            let (mut probs, mut logs) = (dead, dead);
+            softmax(&mut probs);
+            log_softmax(&mut logs);
+            assert_eq!((probs, logs), ([0.0; 2], [f32::NEG_INFINITY; 2]));
+            assert_eq!(sample_gumbel(&dead, &mut rng), 0);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn arbitrary_logits_never_give_nan(
+            logits in vec(any::<f32>(), 0..20),
+            temperature in any::<f32>(),
+            wide in any::<bool>(),
+            seed in any::<u64>(),
+        ) {
+            let acc = if wide { Accumulation::F64 } else { Accumulation::F32 };
+            let mut probs = logits.clone();
+            softmax_with(&mut probs, temperature, acc);
+            prop_assert!(probs.iter().all(|p| (0.0..=1.0).contains(p)));
+            let sum = probs.iter().sum::<f32>();
+            prop_assert!(logits.is_empty() || sum == 0.0 || (sum - 1.0).abs() < 1e-4);
+            let mut logs = logits.clone();
+            log_softmax_with(&mut logs, temperature, acc);
+            prop_assert!(logs.iter().all(|l| *l <= 0.0));
+
+            // a sampler only lands on a NaN or -inf logit when nothing else is left
+            let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(seed);
+            let dead = |v: &f32| v.is_nan() || *v == f32::NEG_INFINITY;
+            for i in [sample_gumbel(&logits, &mut rng), sample_top_k(&logits, 5, &mut rng)] {
+                prop_assert!(logits.iter().all(dead) || logits.get(i).is_some_and(|v| !dead(v)));
+            }
+            prop_assert!(sample_index(&probs, &mut rng) < logits.len().max(1));
+        }
+
+        #[test]
+        fn softmax_sums_to_one_and_is_shift_invariant(
+            logits in vec(-50.0_f32..50.0, 1..40),
+            shift in -100.0_f32..100.0,
+            temperature in 0.1_f32..10.0,
+            wide in any::<bool>(),
+        ) {
+            let acc = if wide { Accumulation::F64 } else { Accumulation::F32 };
+            let mut probs = logits.clone();
+            softmax_with(&mut probs, temperature, acc);
+            prop_assert!((probs.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+
+            let mut shifted: Vec<f32> = logits.iter().map(|v| v + shift).collect();
+            softmax_with(&mut shifted, temperature, acc);
+            for (p, s) in probs.iter().zip(&shifted) {
+                prop_assert!((p - s).abs() < 1e-3);
+            }
+            // larger logits never get smaller probabilities
+            for ((a, pa), (b, pb)) in logits.iter().zip(&probs).zip(logits.iter().zip(&probs).skip(1)) {
+                prop_assert!(a.partial_cmp(b) == pa.partial_cmp(pb) || (pa - pb).abs() < 1e-6);
+            }
+
+            let mut logs = logits.clone();
+            log_softmax_with(&mut logs, temperature, acc);
+            for (l, p) in logs.iter().zip(&probs) {
+                prop_assert!(l.is_finite() && (l.exp() - p).abs() < 1e-5);
+            }
+        }
+    }
+}
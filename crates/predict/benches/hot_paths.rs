@@ -0,0 +1,100 @@
+//! Criterion benchmarks for the inference and search hot paths.
+//!
+//! Run with `cargo bench -p predict` (or `cargo bench -p predict -- softmax`
+//! for one group); compare before/after a change with `--save-baseline` and
+//! `--baseline`.
+
+use std::collections::HashMap;
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use predict::core::{make_rng, sample_gumbel, sample_index, softmax, top_k_indices};
+use predict::linear::Linear;
+use predict::model::Model;
+use predict::ngram::{NgramModel, Smoothing};
+
+fn linear_forward(c: &mut Criterion) {
+    let mut group = c.benchmark_group("linear_forward");
+    for (in_dim, out_dim) in [(Model::EMBED, Model::HIDDEN), (256, 256), (1024, 1024)] {
+        let raw: Vec<f32> = (0..Linear::param_count(in_dim, out_dim)).map(|i| (i % 17) as f32 * 0.01 - 0.08).collect();
+        let layer = Linear::from_raw(in_dim, out_dim, &raw);
+        let input: Vec<f32> = (0..in_dim).map(|i| (i % 7) as f32 * 0.1).collect();
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{in_dim}x{out_dim}")), &input, |b, input| {
+            b.iter(|| layer.forward(black_box(input)))
+        });
+    }
+    group.finish();
+}
+
+fn softmax_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("softmax");
+    for n in [64usize, 1024, 32_768] {
+        let logits: Vec<f32> = (0..n).map(|i| ((i * 31) % 97) as f32 * 0.05).collect();
+        group.bench_with_input(BenchmarkId::from_parameter(n), &logits, |b, logits| {
+            b.iter_batched_ref(|| logits.clone(), |l| softmax(black_box(l)), criterion::BatchSize::SmallInput)
+        });
+    }
+    group.finish();
+}
+
+fn sampling(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sampling");
+    let mut probs: Vec<f32> = (0..1024).map(|i| ((i * 13) % 29) as f32).collect();
+    softmax(&mut probs);
+    let mut rng = make_rng(7);
+    group.bench_function("multinomial_1024", |b| b.iter(|| sample_index(black_box(&probs), &mut rng)));
+    group.bench_function("greedy_1024", |b| {
+        b.iter(|| black_box(&probs).iter().enumerate().max_by(|x, y| x.1.total_cmp(y.1)).map(|(i, _)| i))
+    });
+
+    // from logits: softmax + CDF walk against Gumbel-max, and top-k by partial against full sort
+    for n in [1024usize, 32_768] {
+        let logits: Vec<f32> = (0..n).map(|i| ((i * 31) % 97) as f32 * 0.05).collect();
+        group.bench_with_input(BenchmarkId::new("softmax_cdf", n), &logits, |b, logits| {
+            b.iter_batched_ref(|| logits.clone(), |l| { softmax(l); sample_index(l, &mut rng) }, criterion::BatchSize::SmallInput)
+        });
+        group.bench_with_input(BenchmarkId::new("gumbel", n), &logits, |b, logits| b.iter(|| sample_gumbel(black_box(logits), &mut rng)));
+        group.bench_with_input(BenchmarkId::new("top_k_40_partial", n), &logits, |b, logits| b.iter(|| top_k_indices(black_box(logits), 40)));
+        group.bench_with_input(BenchmarkId::new("top_k_40_full_sort", n), &logits, |b, logits| {
+            b.iter(|| {
+                let mut indices: Vec<usize> = (0..logits.len()).collect();
+                indices.sort_unstable_by(|&x, &y| logits[y].total_cmp(&logits[x]).then(x.cmp(&y)));
+                indices.truncate(40);
+                indices
+            })
+        });
+    }
+
+    let model = Model::zeroed();
+    group.bench_function("model_generate_64", |b| b.iter(|| model.generate(black_box("Q:привет"))));
+
+    let corpus = "что такое алгоритм,последовательность шагов\nинтеграл x^2 от 0 до 2,8/3\nпривет,привет! я shark-core\n".repeat(50);
+    for (name, smoothing) in [("ngram_add_k", Smoothing::AddK(0.5)), ("ngram_kneser_ney", Smoothing::KneserNey(0.75))] {
+        let mut ngram = NgramModel::new(3, smoothing);
+        ngram.train(&corpus);
+        group.bench_function(name, |b| b.iter(|| ngram.generate(black_box(64), 42)));
+    }
+    group.finish();
+}
+
+fn trigram_lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("trigram_similarity");
+    for size in [1_000usize, 10_000] {
+        let knowledge: HashMap<String, String> =
+            (0..size).map(|i| (format!("вопрос номер {i} про тему {}", i % 37), format!("ответ {i}"))).collect();
+        group.bench_with_input(BenchmarkId::new("closest_concept", size), &knowledge, |b, knowledge| {
+            b.iter(|| predict::find_closest_concept(black_box("вопрос про тему 12?"), knowledge))
+        });
+    }
+    group.finish();
+}
+
+fn evolution(c: &mut Criterion) {
+    let mut group = c.benchmark_group("evolution");
+    group.sample_size(10);
+    group.bench_function("search_10gen_pop50", |b| b.iter(|| predict::scientist::search_symbolic(black_box(42), 10, 50)));
+    group.finish();
+}
+
+criterion_group!(benches, linear_forward, softmax_bench, sampling, trigram_lookup, evolution);
+criterion_main!(benches);
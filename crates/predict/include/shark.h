@@ -0,0 +1,54 @@
+/* Shark-Core C API (predict, feature `capi`). Generated by cbindgen — do not edit. */
+
+#ifndef SHARK_H
+#define SHARK_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Opaque handle to a loaded `AI` (model, memory, knowledge, pipeline).
+typedef struct SharkModel SharkModel;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Load the AI described by the `shark.toml` at `config_path`, or the
+// discovered configuration (`SHARK_CONFIG`, `./shark.toml`, defaults) when
+// `config_path` is NULL. Returns NULL on failure, with the error on stderr.
+// Release the handle with `shark_model_free`.
+//
+// # Safety
+// `config_path` must be NULL or a NUL-terminated string.
+struct SharkModel *shark_model_load(const char *config_path);
+
+// Answer `prompt` through the answer pipeline (the dialog is stored in the
+// configured memory, as in the chat CLI). Returns a new string to release
+// with `shark_free_string`, or NULL if `model` or `prompt` is NULL or the
+// prompt is not UTF-8.
+//
+// # Safety
+// `model` must be NULL or a handle from `shark_model_load` that was not
+// freed and is not used concurrently; `prompt` must be NULL or a
+// NUL-terminated string.
+char *shark_chat(struct SharkModel *model, const char *prompt);
+
+// Release a string returned by `shark_chat` (NULL is ignored).
+//
+// # Safety
+// `s` must be NULL or a string returned by this library, released once.
+void shark_free_string(char *s);
+
+// Release a handle returned by `shark_model_load` (NULL is ignored).
+//
+// # Safety
+// `model` must be NULL or a handle from `shark_model_load`, released once.
+void shark_model_free(struct SharkModel *model);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* SHARK_H */
@@ -0,0 +1,197 @@
+#![forbid(unsafe_code)]
+
+//! Speech output and input behind one adapter, so the REPL and the GUI can be
+//! driven hands-free without audio libraries in the crate.
+//!
+//! `Audio::speak` reads an answer aloud and `Audio::listen` returns one
+//! recognised utterance. `NullAudio` (the default, `[audio] backend = "none"`)
+//! is silent and hears nothing. With the `audio` feature, `CommandAudio` pipes
+//! to external programs instead: the answer goes to the stdin of `speak`
+//! (e.g. `espeak-ng --stdin`), and the stdout of `listen` (e.g. a script that
+//! records a few seconds and runs a speech recogniser) is the utterance. Both
+//! are killed after `timeout_ms`.
+//!
+//! ```toml
+//! [audio]
+//! backend = "command"
+//! speak = ["espeak-ng", "-v", "ru", "--stdin"]
+//! listen = ["sh", "scripts/listen.sh"]
+//! timeout_ms = 30000
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::AudioConfig;
+use crate::error::{Result, SharkError};
+
+/// Which `Audio` implementation `from_config` builds (`[audio] backend`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// `NullAudio`
+    #[default]
+    None,
+    /// `CommandAudio` (feature `audio`)
+    Command,
+}
+
+/// Text-to-speech and speech recognition for a front-end.
+pub trait Audio: Send {
+    /// Name for messages (`none`, `command`).
+    fn name(&self) -> &'static str;
+
+    /// Say `text`; returns when it has been spoken.
+    fn speak(&mut self, text: &str) -> Result<()>;
+
+    /// Wait for one utterance and return it as text (empty: nothing was said).
+    fn listen(&mut self) -> Result<String>;
+}
+
+/// Audio that is silent and never hears anything.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NullAudio;
+
+impl Audio for NullAudio {
+    fn name(&self) -> &'static str {
+        "none"
+    }
+
+    fn speak(&mut self, _text: &str) -> Result<()> {
+        Ok(())
+    }
+
+    fn listen(&mut self) -> Result<String> {
+        Ok(String::new())
+    }
+}
+
+/// Audio through external programs (see the module docs).
+#[cfg(feature = "audio")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandAudio {
+    speak: Vec<String>,
+    listen: Vec<String>,
+    timeout: std::time::Duration,
+}
+
+#[cfg(feature = "audio")]
+impl CommandAudio {
+    /// Backend running the `speak` and `listen` commands of `config`.
+    pub fn new(config: &AudioConfig) -> Self {
+        Self { speak: config.speak.clone(), listen: config.listen.clone(), timeout: std::time::Duration::from_millis(config.timeout_ms) }
+    }
+
+    /// Run `command` with `input` on stdin; its trimmed stdout on success.
+    fn run(&self, command: &[String], input: &str) -> Result<String> {
+        use std::io::{Read, Write};
+        use std::process::{Command, Stdio};
+        use std::time::{Duration, Instant};
+
+        let fail = |reason: String| SharkError::Audio { backend: self.name(), reason };
+        let Some((program, args)) = command.split_first() else { return Err(fail("команда не задана".to_string())) };
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| fail(format!("{}: {}", program, e)))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // a program that does not read its input closes the pipe early: not an error
+            let _ = stdin.write_all(input.as_bytes());
+        }
+        let started = Instant::now();
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) if started.elapsed() < self.timeout => std::thread::sleep(Duration::from_millis(10)),
+                Ok(None) => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(fail(format!("{}: нет ответа за {} мс", program, self.timeout.as_millis())));
+                }
+                Err(e) => return Err(fail(format!("{}: {}", program, e))),
+            }
+        };
+        if !status.success() {
+            return Err(fail(format!("{}: {}", program, status)));
+        }
+        let mut output = String::new();
+        if let Some(mut stdout) = child.stdout.take() {
+            stdout.read_to_string(&mut output).map_err(|e| fail(format!("{}: {}", program, e)))?;
+        }
+        Ok(output.trim().to_string())
+    }
+}
+
+#[cfg(feature = "audio")]
+impl Audio for CommandAudio {
+    fn name(&self) -> &'static str {
+        "command"
+    }
+
+    /// Without a `speak` command nothing is said.
+    fn speak(&mut self, text: &str) -> Result<()> {
+        if self.speak.is_empty() {
+            return Ok(());
+        }
+        self.run(&self.speak, text).map(|_| ())
+    }
+
+    fn listen(&mut self) -> Result<String> {
+        self.run(&self.listen, "")
+    }
+}
+
+/// The backend `config` selects; `Command` without the `audio` feature is an error.
+pub fn from_config(config: &AudioConfig) -> Result<Box<dyn Audio>> {
+    match config.backend {
+        Backend::None => Ok(Box::new(NullAudio)),
+        #[cfg(feature = "audio")]
+        Backend::Command => Ok(Box::new(CommandAudio::new(config))),
+        #[cfg(not(feature = "audio"))]
+        Backend::Command => Err(SharkError::Audio { backend: "command", reason: "собрано без функции `audio`".to_string() }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn null_audio_is_silent_and_commands_pipe_text() {
+        let null = from_config(&AudioConfig::default());
+        assert!(null.is_ok(), "{:?}", null.as_ref().err());
+        let Ok(mut null) = null else { return };
+        assert_eq!(null.name(), "none");
+        assert!(null.speak("привет").is_ok());
+        assert_eq!(null.listen().ok().as_deref(), Some(""));
+
+        #[cfg(all(feature = "audio", unix))]
+        {
+            let config = |speak: &[&str], listen: &[&str]| AudioConfig {
+                backend: Backend::Command,
+                speak: speak.iter().map(|s| s.to_string()).collect(),
+                listen: listen.iter().map(|s| s.to_string()).collect(),
+                timeout_ms: 2000,
+            };
+            let audio = from_config(&config(&["cat"], &["echo", " какая погода "]));
+            assert!(audio.is_ok(), "{:?}", audio.as_ref().err());
+            let Ok(mut audio) = audio else { return };
+            assert_eq!(audio.name(), "command");
+            assert!(audio.speak("ответ").is_ok());
+            assert_eq!(audio.listen().ok().as_deref(), Some("какая погода"));
+
+            let broken = from_config(&config(&["false"], &[]));
+            assert!(broken.is_ok(), "{:?}", broken.as_ref().err());
+            let Ok(mut broken) = broken else { return };
+            assert!(matches!(broken.speak("ответ"), Err(SharkError::Audio { backend: "command", .. })));
+            assert!(broken.listen().is_err());
+            let slow = from_config(&AudioConfig { timeout_ms: 50, ..config(&[], &["sleep", "5"]) });
+            assert!(slow.is_ok(), "{:?}", slow.as_ref().err());
+            let Ok(mut slow) = slow else { return };
+            assert!(slow.speak("тихо").is_ok());
+            assert!(slow.listen().is_err_and(|e| e.to_string().contains("нет ответа")));
+        }
+    }
+}
@@ -1,232 +1,1607 @@
 use std::io::{self, BufRead, Write};
-use predict::AI;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use clap::{Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+use rustyline::DefaultEditor;
+use rustyline::error::ReadlineError;
+use predict::commands::{parse_command, ConflictChoice, SlashCommand, HELP};
+use predict::knowledge_store::{self, ConflictKind, Resolution};
+use predict::decode::UNDECODABLE;
+use predict::jobs::Scheduler;
+use predict::replay::{self, ReplayLog};
+use predict::weights;
+use predict::core::RngSource;
+use predict::{decode_with, ChatResponse, Config, Corrector, DecodeOptions, SharkError, Source, AI, MEMORY_FREQ_PATH};
+use predict::memory::Memory;
+use predict::model::Model;
+use predict::ngram::{NgramModel, Smoothing, NGRAM_PATH};
+use evalharness::Report;
+use predict::train::dataset::QaDataset;
+use predict::train::log::write_report;
+use predict::train::finetune::{examples_from_pairs, finetune_from_memory, FinetuneConfig};
+use predict::train::sgd::{Loss, OptimizerConfig, TrainConfig};
+use predict::train::generate::write_dataset;
+use predict::train::selfplay::{self, ProblemKind, SelfplayConfig};
+use predict::train::{generate_dataset, DatasetSpec};
+use predict::train::trainer::{self, Checkpoint, RunConfig, TrainerState};
+use predict::tutor::{check_answer, score_path, TutorState};
+#[cfg(feature = "science")]
 use predict::scientist;
+use predict::reports;
+use predict::export;
+use predict::audio::{self, Audio, Backend};
+use predict::model_registry;
+use predict::usage::{self, UsageStats};
 use predict::reasoner::Reasoner;
-use predict::train::{train_from_csv, load_knowledge_pack, find_answer, eval_arith, solve_linear_equation, append_knowledge, load_rust_knowledge, scan_src_and_update_knowledge, auto_update_and_visualize_structure, evaluate_problems};
+use predict::train::{train_from_csv, load_knowledge_pack, append_knowledge, find_answer, load_rust_knowledge, scan_src_and_update_knowledge, auto_update_and_visualize_structure, evaluate_suite, eval_answer, PROBLEMS_REPORT, load_suite, load_problems, normalize_answer};
 use predict::knowledge_env::{expand_knowledge_environment, merge_knowledge_sources, auto_expand_on_new_topic, detect_knowledge_gap};
-use predict::self_repair::self_repair;
+use predict::self_repair::{self_repair, SOURCE_DIR};
+
+/// Shark-Core local chat: single-shot prompts, interactive REPL and maintenance commands.
+#[derive(Parser)]
+#[command(name = "chat", version)]
+struct Cli {
+    /// Configuration file (default: $SHARK_CONFIG, else ./shark.toml if present).
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+    /// Path to the model weights blob (overrides `model.path`; default weights/model_int4.bin).
+    #[arg(long, global = true)]
+    model: Option<String>,
+    /// Directory holding knowledge.csv, problems.csv, unknowns.csv and knowledge_rust.csv
+    /// (overrides `data.dir`; default crates/predict/data).
+    #[arg(long, global = true)]
+    data_dir: Option<PathBuf>,
+    /// Skip the source scan (code tree + knowledge_rust.csv) during startup
+    /// (same as `features.startup_scan = false`).
+    #[arg(long, global = true)]
+    no_startup_scan: bool,
+    /// Fast start: skip self-repair, merges, scans, science deepening and relearning;
+    /// the knowledge base is loaded on first use.
+    #[arg(long, global = true, env = "SHARK_FAST", value_parser = clap::builder::BoolishValueParser::new())]
+    fast: bool,
+    /// Print answers as JSON objects (answer, source, reasoning, latency_ms), one per line.
+    /// Implies `--fast` so that startup progress does not mix with the JSON on stdout.
+    #[arg(long, global = true)]
+    json: bool,
+    /// Answer prompts in batch: one prompt per line from FILE (or stdin when FILE is
+    /// omitted or `-`). A `.csv` file is read as `question,expected` and scored.
+    #[arg(long, value_name = "FILE", num_args = 0..=1, default_missing_value = "-")]
+    batch: Option<String>,
+    /// Where to write batch results; `.csv` writes CSV, anything else JSONL
+    /// (default: JSONL on stdout, which implies `--fast`).
+    #[arg(long, value_name = "PATH", requires = "batch")]
+    out: Option<PathBuf>,
+    /// After every model-generated answer, print tokens/sec and the time spent in
+    /// embedding, forward pass, softmax and sampling (stderr; needs feature `profile`).
+    #[arg(long, global = true)]
+    profile: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Prompt for a single-shot answer; starts the REPL when omitted.
+    prompt: Vec<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Answer a single prompt, or start the interactive REPL when no prompt is given.
+    Chat {
+        /// Prompt text (words are joined with spaces).
+        prompt: Vec<String>,
+    },
+    /// Ask the Reasoner for an answer with a step-by-step explanation.
+    Ask {
+        /// Task to explain, e.g. "Упростите (x+2)*(x-2)".
+        #[arg(required = true)]
+        prompt: Vec<String>,
+    },
+    /// Run the scientist's symbolic search for a new formula.
+    Research {
+        /// RNG seed for the evolutionary search (default: the `[rng]` evolution
+        /// stream when `[rng] seed` is set, else 42).
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Number of generations.
+        #[arg(long, default_value_t = 300)]
+        generations: usize,
+        /// Population size.
+        #[arg(long, default_value_t = 50)]
+        population: usize,
+    },
+    /// Evaluate a task suite and write problems_report.md plus a JSON report
+    /// into the reports directory.
+    Eval {
+        /// Suite: JSON, `question,expected[,category[,matcher]]` CSV or JSONL
+        /// (defaults to <data-dir>/problems.csv).
+        path: Option<PathBuf>,
+        /// Where to write the JSON report (defaults to <reports-dir>/eval_report.json).
+        #[arg(long)]
+        report: Option<PathBuf>,
+        /// Earlier JSON report to compare against (e.g. before a knowledge merge or fine-tune).
+        #[arg(long)]
+        baseline: Option<PathBuf>,
+    },
+    /// Print the effective configuration (shark.toml merged with flags) as TOML.
+    Config,
+    /// Print the usage statistics recorded with `[stats] enabled = true`:
+    /// answers, sources, latency and stages per day.
+    Stats {
+        /// Number of most recent days to show.
+        #[arg(long, default_value_t = 14)]
+        days: usize,
+    },
+    /// Weight file tools.
+    Weights {
+        #[command(subcommand)]
+        action: WeightsCommand,
+    },
+    /// Knowledge base maintenance.
+    Knowledge {
+        #[command(subcommand)]
+        action: KnowledgeCommand,
+    },
+    /// Load a question/answer CSV through the demo training loader, or gradient-train
+    /// the model with `train fit` / `train resume`.
+    #[command(args_conflicts_with_subcommands = true)]
+    Train {
+        #[command(subcommand)]
+        action: Option<TrainCommand>,
+        /// Dataset CSV for the demo loader (defaults to <data-dir>/knowledge.csv).
+        path: Option<PathBuf>,
+    },
+    /// Fine-tune the model weights on the dialogs stored in memory.db.
+    Finetune {
+        /// Passes over the dialog examples.
+        #[arg(long, default_value_t = 3)]
+        epochs: usize,
+        /// Adam learning rate.
+        #[arg(long, default_value_t = 1e-3)]
+        lr: f32,
+        /// Probability of dropping a hidden activation during training.
+        #[arg(long, default_value_t = 0.1)]
+        dropout: f32,
+        /// L2 weight decay.
+        #[arg(long, default_value_t = 1e-4)]
+        weight_decay: f32,
+        /// Where to write the updated weights (defaults to `--model`).
+        #[arg(long)]
+        out: Option<String>,
+    },
+    /// Character n-gram language model used as the fallback generator.
+    Ngram {
+        #[command(subcommand)]
+        action: NgramCommand,
+    },
+    /// Practise the problems dataset interactively; wrong answers come back in later sessions.
+    Tutor {
+        /// Whose score file to use (`<data-dir>/tutor_<user>.json`).
+        #[arg(long, env = "USER", default_value = "default")]
+        user: String,
+        /// Ask at most this many problems in this session.
+        #[arg(long)]
+        limit: Option<usize>,
+        /// Problems CSV (defaults to <data-dir>/problems.csv).
+        path: Option<PathBuf>,
+    },
+    /// Replay a session recorded with `/record` and diff the answers against the
+    /// recording, or regenerate one generation of the replay log (`[replay] enabled`)
+    /// and check it reproduces token for token.
+    Replay {
+        /// JSONL recording produced by `/record`, or a replay log id (a unique prefix is enough).
+        file: PathBuf,
+        /// Rewrite the recording with the new answers instead of only reporting diffs.
+        #[arg(long)]
+        update: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum WeightsCommand {
+    /// Print the format (raw f32 / safetensors / gguf), tensor shapes against the
+    /// model's, min/max/mean and NaN counts, and whether `Model::load` accepts the file.
+    Inspect {
+        /// Weights file (defaults to `[model] path`).
+        path: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TrainCommand {
+    /// Gradient-train the model weights on a question/answer dataset (CSV or JSONL)
+    /// with a validation split, checkpoints and early stopping.
+    Fit(FitArgs),
+    /// Curriculum self-play: the Reasoner solves generated problems, verified pairs
+    /// fine-tune the model and are added to the knowledge base (provenance "selfplay").
+    Selfplay(SelfplayArgs),
+    /// Write a synthetic math dataset (arithmetic, linear equations, integrals) with
+    /// Reasoner-verified answers, usable as an evaluation suite or for `train fit`.
+    Generate(GenerateArgs),
+    /// Continue a `train fit` run from a checkpoint file.
+    Resume {
+        /// Checkpoint written by `train fit` (e.g. checkpoints/step_000100.ckpt).
+        checkpoint: PathBuf,
+        /// Where to write the trained weights (defaults to `--model`).
+        #[arg(long)]
+        out: Option<String>,
+    },
+}
+
+#[derive(clap::Args)]
+struct FitArgs {
+    /// Dataset (defaults to <data-dir>/knowledge.csv).
+    dataset: Option<PathBuf>,
+    /// Maximum number of epochs.
+    #[arg(long, default_value_t = 20)]
+    epochs: usize,
+    /// Adam learning rate.
+    #[arg(long, default_value_t = 1e-3)]
+    lr: f32,
+    /// Examples per optimizer step.
+    #[arg(long, default_value_t = 16)]
+    batch_size: usize,
+    /// Probability of dropping a hidden activation during training (0 disables).
+    #[arg(long, default_value_t = 0.0)]
+    dropout: f32,
+    /// L2 weight decay (0 disables).
+    #[arg(long, default_value_t = 0.0)]
+    weight_decay: f32,
+    /// Fraction of question/answer pairs held out for validation.
+    #[arg(long, default_value_t = 0.2)]
+    val: f32,
+    /// Seed for the split and the per-epoch shuffles.
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+    /// Directory for checkpoint files.
+    #[arg(long, default_value = "checkpoints")]
+    checkpoint_dir: PathBuf,
+    /// Write a checkpoint every N steps (0 disables checkpoints).
+    #[arg(long, default_value_t = 100)]
+    every: u64,
+    /// Stop after N epochs without validation improvement (0 disables early stopping).
+    #[arg(long, default_value_t = 3)]
+    patience: usize,
+    /// Where to write the trained weights (defaults to `--model`).
+    #[arg(long)]
+    out: Option<String>,
+    /// Start from seeded random weights (He/Xavier, `--seed`) instead of the
+    /// weights file; also used when the file does not exist.
+    #[arg(long)]
+    scratch: bool,
+    /// CSV training log (per-step loss, learning rate, validation loss) shown by the
+    /// GUI Training tab and summarised in training_report.md in the reports directory.
+    #[arg(long, default_value = TRAINING_LOG)]
+    log: PathBuf,
+}
+
+#[derive(clap::Args)]
+struct SelfplayArgs {
+    /// Curriculum rounds; each round is one difficulty level harder.
+    #[arg(long, default_value_t = 3)]
+    rounds: usize,
+    /// Problems per round.
+    #[arg(long, default_value_t = 20)]
+    per_round: usize,
+    /// Seed for problem generation.
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+    /// Fine-tuning epochs after each round (0 only collects pairs and knowledge).
+    #[arg(long, default_value_t = 3)]
+    epochs: usize,
+    /// Adam learning rate.
+    #[arg(long, default_value_t = 1e-3)]
+    lr: f32,
+    /// Do not append the verified pairs to the knowledge base.
+    #[arg(long)]
+    no_knowledge: bool,
+    /// Where to write the fine-tuned weights (defaults to `--model`).
+    #[arg(long)]
+    out: Option<String>,
+}
+
+#[derive(clap::Args)]
+struct GenerateArgs {
+    /// Output file: JSONL for `.jsonl`, CSV (question,expected,category,matcher) otherwise.
+    out: PathBuf,
+    /// Number of problems.
+    #[arg(long, default_value_t = 100)]
+    count: usize,
+    /// Seed; the same seed and options give the same dataset.
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+    /// Problem kinds, comma-separated: arith, linear, integral.
+    #[arg(long, default_value = "arith,linear,integral")]
+    kinds: String,
+    /// Easiest difficulty level.
+    #[arg(long, default_value_t = 1)]
+    min_level: usize,
+    /// Hardest difficulty level.
+    #[arg(long, default_value_t = 3)]
+    max_level: usize,
+}
+
+/// Default location of the JSON evaluation report.
+const EVAL_REPORT: &str = "eval_report.json";
+
+/// Default training log location, shared with the GUI Training tab.
+const TRAINING_LOG: &str = "logs/training_log.csv";
+
+#[derive(Subcommand)]
+enum NgramCommand {
+    /// Train on text files (one line per sample; Q/A CSVs contribute both columns).
+    Train {
+        /// Corpus files (defaults to <data-dir>/knowledge.csv).
+        corpus: Vec<PathBuf>,
+        /// Characters of context plus one (2 = bigram, 3 = trigram).
+        #[arg(long, default_value_t = 3)]
+        order: usize,
+        /// Use add-k smoothing with this k instead of Kneser-Ney.
+        #[arg(long)]
+        add_k: Option<f64>,
+        /// Kneser-Ney discount.
+        #[arg(long, default_value_t = 0.75)]
+        discount: f64,
+        /// Output file; the chat picks it up from the default location.
+        #[arg(long, default_value = NGRAM_PATH)]
+        out: PathBuf,
+    },
+    /// Sample lines from a trained model.
+    Sample {
+        /// Number of lines.
+        #[arg(long, default_value_t = 5)]
+        count: usize,
+        /// RNG seed.
+        #[arg(long, default_value_t = 42)]
+        seed: u64,
+        /// Model file.
+        #[arg(long, default_value = NGRAM_PATH)]
+        file: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum KnowledgeCommand {
+    /// Merge per-topic knowledge files into the central knowledge.csv.
+    Merge,
+}
+
+/// Resolved data file locations derived from `--data-dir`.
+struct DataPaths {
+    dir: PathBuf,
+    knowledge: String,
+    problems: String,
+    unknowns: String,
+    rust_knowledge: String,
+}
+
+impl DataPaths {
+    fn new(dir: &Path) -> Self {
+        let file = |name: &str| dir.join(name).to_string_lossy().to_string();
+        Self {
+            dir: dir.to_path_buf(),
+            knowledge: file("knowledge.csv"),
+            problems: file("problems.csv"),
+            unknowns: file("unknowns.csv"),
+            rust_knowledge: file("knowledge_rust.csv"),
+        }
+    }
+}
+
+/// Global options shared by all commands.
+struct Options {
+    /// shark.toml with the command-line overrides applied
+    config: Config,
+    paths: DataPaths,
+    fast: bool,
+    json: bool,
+    profile: bool,
+}
 
 fn main() {
-    // If a prompt is provided on the command line, run a single-shot chat and exit.
-    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let cli = Cli::parse();
+    let mut config = or_exit(match &cli.config {
+        Some(path) => Config::load(path),
+        None => Config::discover(),
+    });
+    if let Some(model) = cli.model {
+        config.model.path = model;
+    }
+    if let Some(dir) = cli.data_dir {
+        config.data.dir = dir;
+    }
+    config.features.startup_scan &= !cli.no_startup_scan;
+    reports::configure(&config);
+    usage::configure(&config);
+    let opts = Options {
+        paths: DataPaths::new(&config.data.dir),
+        // JSON on stdout (including batch results without --out) must not mix with startup logs
+        fast: cli.fast || config.features.fast || cli.json || (cli.batch.is_some() && cli.out.is_none()),
+        json: cli.json,
+        profile: cli.profile,
+        config,
+    };
+    if cli.profile && !cfg!(feature = "profile") {
+        eprintln!("⚠️ --profile: собрано без функции `profile` (cargo build --features profile)");
+    }
+    let paths = &opts.paths;
 
-    // Self-repair: restore missing/ corrupted critical modules before other startup steps
-    self_repair(); // <- automatically repairs missing code and writes docs/self_fix.log
+    if let Some(input) = cli.batch.as_deref() {
+        if let Err(e) = run_batch(&opts, input, cli.out.as_deref()) {
+            eprintln!("⚠️ Ошибка пакетного режима: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
 
-    // Ensure the knowledge environment exists and seed topic files if needed
-    let topics = ["math", "analysis", "geometry", "logic", "science"];
-    if let Err(e) = expand_knowledge_environment(&topics) {
-        eprintln!("⚠️ Не удалось расширить окружение знаний: {}", e);
+    match cli.command {
+        None => run_chat(&opts, &cli.prompt),
+        Some(Command::Chat { ref prompt }) => run_chat(&opts, prompt),
+        Some(Command::Ask { ref prompt }) => {
+            startup(&opts, false);
+            let prompt = prompt.join(" ");
+            if !opts.json {
+                println!("> {}", prompt);
+            }
+            emit(&explain(&paths.dir, &prompt), opts.json);
+        }
+        Some(Command::Research { seed, generations, population }) => {
+            let seed = seed.or(opts.config.rng_source().map(|rng| rng.stream_seed(RngSource::EVOLUTION))).unwrap_or(42);
+            emit(&research(seed, generations, population), opts.json)
+        }
+        Some(Command::Eval { ref path, ref report, ref baseline }) => {
+            startup(&opts, false);
+            let mut ai = load_ai(&opts, false);
+            let problems = path.as_ref().map(|p| p.to_string_lossy().to_string()).unwrap_or_else(|| paths.problems.clone());
+            let report = report.clone().unwrap_or_else(|| reports::path(EVAL_REPORT));
+            emit(&run_eval(&mut ai, &problems, &report, baseline.as_deref()), opts.json);
+        }
+        Some(Command::Config) => print!("{}", opts.config.to_toml()),
+        Some(Command::Stats { days }) => {
+            let path = opts.config.usage_stats_path();
+            print!("{}", or_exit(UsageStats::load(&path).map_err(|e| SharkError::io(&path, e))).render(days));
+        }
+        Some(Command::Weights { action: WeightsCommand::Inspect { ref path } }) => {
+            let path = path.clone().unwrap_or_else(|| PathBuf::from(&opts.config.model.path));
+            let report = or_exit(weights::inspect_file(&path));
+            if opts.json {
+                println!("{}", serde_json::to_string(&report).unwrap_or_default());
+            } else {
+                println!("{}", path.display());
+                print!("{}", report.render());
+            }
+            if !report.loadable {
+                std::process::exit(1);
+            }
+        }
+        Some(Command::Knowledge { action: KnowledgeCommand::Merge }) => {
+            if let Err(e) = merge_knowledge_sources(Path::new(&paths.knowledge), &paths.dir.join("knowledge")) {
+                eprintln!("⚠️ Ошибка при объединении знаний: {}", e);
+                std::process::exit(1);
+            }
+            println!("[knowledge] источники объединены в {}", paths.knowledge);
+        }
+        Some(Command::Train { action: None, ref path }) => {
+            let dataset = path.as_ref().map(|p| p.to_string_lossy().to_string()).unwrap_or_else(|| paths.knowledge.clone());
+            if let Err(e) = train_from_csv(&dataset) {
+                eprintln!("⚠️ {}: {}", dataset, e);
+                std::process::exit(1);
+            }
+        }
+        Some(Command::Train { action: Some(ref action), .. }) => {
+            let result = match action {
+                TrainCommand::Fit(args) => train_fit(&opts, args),
+                TrainCommand::Selfplay(args) => train_selfplay(&opts, args),
+                TrainCommand::Generate(args) => train_generate(args),
+                TrainCommand::Resume { checkpoint, out } => train_resume(&opts, checkpoint, out.as_deref()),
+            };
+            if let Err(e) = result {
+                eprintln!("⚠️ Обучение не выполнено: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Command::Finetune { epochs, lr, dropout, weight_decay, ref out }) => {
+            let mut model = or_exit(Model::load_or_zeroed(&opts.config.model.path));
+            let memory = or_exit(Memory::load(&opts.config.data.memory));
+            let defaults = FinetuneConfig::default();
+            let cfg = FinetuneConfig {
+                train: TrainConfig { epochs, optimizer: OptimizerConfig::adam(lr), dropout, weight_decay, ..defaults.train },
+                save_to: Some(out.clone().unwrap_or_else(|| opts.config.model.path.clone())),
+                ..defaults
+            };
+            match finetune_from_memory(&mut model, &memory, &cfg) {
+                Ok(report) => {
+                    for (epoch, loss) in report.losses.iter().enumerate() {
+                        println!("[finetune] эпоха {}: loss={:.4}", epoch + 1, loss);
+                    }
+                    println!("[finetune] {} диалогов, {} примеров → {}", report.dialogs, report.examples, cfg.save_to.unwrap_or_default());
+                }
+                Err(e) => {
+                    eprintln!("⚠️ Дообучение не выполнено: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(Command::Ngram { ref action }) => {
+            if let Err(e) = run_ngram(&opts, action) {
+                eprintln!("⚠️ n-gram: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Command::Tutor { ref user, limit, ref path }) => {
+            let problems = path.as_ref().map(|p| p.to_string_lossy().to_string()).unwrap_or_else(|| paths.problems.clone());
+            if let Err(e) = tutor(&opts, user, &problems, limit) {
+                eprintln!("⚠️ Ошибка режима репетитора: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Command::Replay { ref file, .. }) if !file.exists() => {
+            if !replay_generation(&opts, &file.to_string_lossy()) {
+                std::process::exit(1);
+            }
+        }
+        Some(Command::Replay { ref file, update }) => match replay(&opts, file, update) {
+            Ok(0) => {}
+            Ok(_) if update => {}
+            Ok(_) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("⚠️ Не удалось воспроизвести {}: {}", file.display(), e);
+                std::process::exit(1);
+            }
+        },
+    }
+}
+
+/// Startup pipeline shared by the AI-facing commands (chat, ask, eval).
+/// Skipped entirely in `--fast` mode. With `background` (the REPL) the knowledge
+/// merge and deep evolution are left to the job scheduler (`background_jobs`).
+fn startup(opts: &Options, background: bool) {
+    if opts.fast {
+        return;
     }
+    let paths = &opts.paths;
+    let features = &opts.config.features;
+    let no_startup_scan = !features.startup_scan;
 
-    // Merge per-topic knowledge into the central knowledge.csv so loader can read it
-    if let Err(e) = merge_knowledge_sources() {
-        eprintln!("⚠️ Ошибка при объединении знаний: {}", e);
+    // Self-repair (opt-in): check the critical modules, stubs go to the state sandbox
+    if features.self_repair {
+        match self_repair(Path::new(SOURCE_DIR), &opts.config.self_repair_dir()) {
+            Ok(report) => println!("{}", report.render()),
+            Err(e) => eprintln!("⚠️ Self-repair не выполнен: {}", e),
+        }
     }
 
-    // Load the canonical knowledge pack (math, analysis, geometry, logic, relations)
-    load_knowledge_pack();
+    if features.knowledge_merge && !background {
+        // Ensure the knowledge environment exists and seed topic files if needed
+        let topics = ["math", "analysis", "geometry", "logic", "science"];
+        if let Err(e) = expand_knowledge_environment(&paths.dir, &topics) {
+            eprintln!("⚠️ Не удалось расширить окружение знаний: {}", e);
+        }
+
+        // Merge per-topic knowledge into the central knowledge.csv so loader can read it
+        if let Err(e) = merge_knowledge_sources(Path::new(&paths.knowledge), &paths.dir.join("knowledge")) {
+            eprintln!("⚠️ Ошибка при объединении знаний: {}", e);
+        }
+
+    }
+    if features.knowledge_merge {
+        // Load the canonical knowledge pack (math, analysis, geometry, logic, relations)
+        if let Err(e) = load_knowledge_pack(&paths.dir) {
+            eprintln!("⚠️ Не удалось загрузить пакет знаний: {}", e);
+        }
+    }
 
     // Auto-scan source and update docs + CSV, then run tiny dataset loader / trainer (demo)
-    auto_update_and_visualize_structure(); // performs automatic scan and writes docs/code_tree.md
-    train_from_csv("crates/predict/data/knowledge.csv");
-    // legacy: also ensure the CSV is up-to-date (no-op if auto-update already ran)
-    let _ = scan_src_and_update_knowledge("crates/predict/src", "crates/predict/data/knowledge_rust.csv");
-
-    // --- Deepen research from science memory (pick most curious formulas)
-    let science_mem = scientist::load_science_memory();
-    if !science_mem.is_empty() {
-        // sort by curiosity desc (index 3 is curiosity in (name,formula,mse,curiosity))
-        let mut mem = science_mem.clone();
-        mem.sort_by(|a, b| b.3.partial_cmp(&a.3).unwrap_or(std::cmp::Ordering::Equal));
-        // pick top 2 by curiosity to avoid long startup
-        let top_n = mem.iter().take(2).cloned().collect::<Vec<_>>();
-        use std::hash::{Hasher, Hash};
-        use std::collections::hash_map::DefaultHasher;
-        for (_name, formula, _mse, curiosity) in top_n {
-            // derive a reproducible seed from formula text so evolve starts near same region
-            let mut hasher = DefaultHasher::new();
-            formula.hash(&mut hasher);
-            let seed = hasher.finish();
-            println!("[science] углублённый поиск от '{}' (curiosity={:.4})...", formula, curiosity);
-            // run a deeper evolve (fewer gens if you want faster)
-            let (best, fit) = scientist::evolve_symbolic(seed, 200, 60);
-            println!("[science] найдено: {:?} (MSE={:.4})", best, fit);
-        }
-    }
-
-    // load AI (model + memory) once at startup
-    let mut ai = AI::new("weights/model_int4.bin");
+    if !no_startup_scan {
+        // performs automatic scan and writes code_tree.md into the reports directory
+        if let Err(e) = auto_update_and_visualize_structure(&paths.rust_knowledge) {
+            eprintln!("⚠️ Не удалось обновить {}: {}", reports::path("code_tree.md").display(), e);
+        }
+    }
+    if let Err(e) = train_from_csv(&paths.knowledge) {
+        eprintln!("⚠️ {}: {}", paths.knowledge, e);
+    }
+    if !no_startup_scan {
+        // legacy: also ensure the CSV is up-to-date (no-op if auto-update already ran)
+        let _ = scan_src_and_update_knowledge("crates/predict/src", &paths.rust_knowledge);
+    }
+
+    if features.science && !background {
+        deepen_research();
+    }
+}
+
+/// Self-learning jobs for the REPL (none in `--fast` mode), started in the background.
+fn background_jobs(opts: &Options) -> Scheduler {
+    let mut jobs = if opts.fast { Scheduler::new() } else { Scheduler::self_learning(&opts.config) };
+    jobs.start();
+    jobs
+}
+
+/// Deepen research from science memory (pick most curious formulas).
+#[cfg(feature = "science")]
+fn deepen_research() {
+    // top 2 by curiosity to avoid long startup
+    for (formula, curiosity, best, fit) in scientist::deepen_research(2, 200, 60) {
+        println!("[science] углублённый поиск от '{}' (curiosity={:.4}): {:?} (MSE={:.4})", formula, curiosity, best, fit);
+    }
+}
+
+#[cfg(not(feature = "science"))]
+fn deepen_research() {}
+
+/// Unwrap a load result or report the error and exit with status 1.
+fn or_exit<T>(result: Result<T, SharkError>) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("⚠️ {}", e);
+        std::process::exit(1);
+    })
+}
+
+/// Load AI (model + memory) and try to relearn unknowns from previous runs.
+/// In `--fast` mode the knowledge base is loaded lazily and relearning is skipped;
+/// with `background` relearning is left to the job scheduler.
+fn load_ai(opts: &Options, background: bool) -> AI {
+    if opts.fast {
+        return or_exit(AI::new_lazy(&opts.config));
+    }
+    let paths = &opts.paths;
+    let mut ai = or_exit(AI::new(&opts.config));
+    if !opts.config.features.relearn || background {
+        return ai;
+    }
 
     // Try to relearn unknowns from previous runs (require 2 confirmations by default)
-    let (learned, total_unknowns) = predict::train::try_relearn_unknowns(&mut ai, "crates/predict/data/unknowns.csv", 2);
-    if total_unknowns > 0 {
-        println!("[train] relearnt {}/{} unknowns", learned, total_unknowns);
+    match predict::train::try_relearn_unknowns(&mut ai, &paths.unknowns, 2) {
+        Ok((learned, total_unknowns)) if total_unknowns > 0 => println!("[train] relearnt {}/{} due unknowns", learned, total_unknowns),
+        Ok(_) => {}
+        Err(e) => eprintln!("⚠️ Не удалось обновить unknowns: {}", e),
     }
+    ai
+}
 
-    if !args.is_empty() {
-        let prompt = args.join(" ");
-        // Load Rust self-knowledge and handle structure/code queries
-        let rust_knowledge = load_rust_knowledge("crates/predict/data/knowledge_rust.csv");
-        // Detect knowledge gaps and auto-expand topic files if needed
-        if let Some(topic) = detect_knowledge_gap(&prompt) {
-            let _ = auto_expand_on_new_topic(&topic);
-            // After expansion, merge sources so new file is visible to loaders
-            let _ = merge_knowledge_sources();
-        }
+/// `chat` command: single-shot answer when a prompt is given, REPL otherwise.
+fn run_chat(opts: &Options, prompt: &[String]) {
+    let interactive = prompt.is_empty();
+    startup(opts, interactive);
+    let mut ai = load_ai(opts, interactive);
 
-        // Reasoner trigger: if user asks to explain/simplify or requests an integral, run the reasoner first
-        if prompt.to_lowercase().contains("упрост") || prompt.to_lowercase().contains("объясн") || prompt.to_lowercase().contains("рассужд") || prompt.to_lowercase().contains("интеграл") {
-            let (ans, reasoning) = Reasoner::explain(&prompt);
+    if !prompt.is_empty() {
+        let prompt = prompt.join(" ");
+        if !opts.json {
             println!("> {}", prompt);
-            println!("🧠 Ответ: {}", ans);
-            println!("📜 Рассуждение:\n{}", reasoning);
-            return;
         }
-        if prompt.contains("структура") || prompt.contains("код") {
-            println!("🧩 Shark-Core состоит из следующих модулей:");
-            for (file, desc) in rust_knowledge {
-                println!("• {} — {}", file, desc);
+        match parse_command(&prompt) {
+            Some(Ok(cmd)) => return run_command(cmd, &mut ai, opts, &mut Session::default()),
+            Some(Err(msg)) => {
+                eprintln!("⚠️ {}", msg);
+                std::process::exit(2);
             }
-            return;
+            None => {}
         }
+        let answer = respond(&mut ai, opts, &prompt);
+        emit(&answer, opts.json);
+        if opts.profile {
+            show_profile(&ai, &answer);
+        }
+        return;
+    }
 
-        // Command: trigger symbolic exploration
-        if prompt.to_lowercase().contains("исслед") {
-            let (best, fit) = scientist::evolve_symbolic(42, 300, 50);
-            let formula = format!("{:?}", best);
-            let curiosity = scientist::curiosity_from_mse(fit);
-            println!("> {}", prompt);
-            println!("🧠 Я нашёл новую закономерность: {}\nMSE = {:.4} — любознательность={:.4} ✅", formula, fit, curiosity);
+    let session = Session { jobs: background_jobs(opts), audio: voice(opts), ..Session::default() };
+    repl(opts, &mut ai, session);
+}
+
+/// File (in the working directory) where the REPL keeps its line history.
+const HISTORY_FILE: &str = ".shark_history";
+
+/// Interactive REPL with line editing, history (arrows, Ctrl-R) and slash-commands.
+fn repl(opts: &Options, ai: &mut AI, mut session: Session) {
+    let mut editor = match DefaultEditor::new() {
+        Ok(e) => e,
+        Err(e) => {
+            eprintln!("⚠️ Не удалось инициализировать редактор строк: {}", e);
             return;
         }
+    };
+    let _ = editor.load_history(HISTORY_FILE);
+    if !opts.json {
+        println!("Interactive chat — /help для списка команд, /quit или Ctrl-D для выхода");
+    }
 
-        // Command: evaluate problems dataset
-        if prompt.to_lowercase().contains("проверь задачи") {
-            println!("> {}", prompt);
-            let (ok, total) = evaluate_problems(&mut ai, "crates/predict/data/problems.csv");
-            println!("[train] problems scored: {}/{}\nДоклад: docs/problems_report.md", ok, total);
-            return;
+    // runs of the knowledge merge job already reflected in `ai.knowledge`
+    let mut merges_seen = 0;
+    loop {
+        let line = match editor.readline("› ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                eprintln!("⚠️ Ошибка ввода: {}", e);
+                break;
+            }
+        };
+        session.jobs.touch();
+        let s = line.trim();
+        if s.is_empty() {
+            continue;
+        }
+        let merges = session.jobs.status().iter().filter(|j| j.name == "knowledge_merge").map(|j| j.runs).sum();
+        if merges > merges_seen {
+            merges_seen = merges;
+            if let Err(e) = ai.reload_knowledge() {
+                eprintln!("⚠️ {}", e);
+            }
+        }
+        let _ = editor.add_history_entry(s);
+        if s.eq_ignore_ascii_case("quit") || s.eq_ignore_ascii_case("exit") {
+            break;
+        }
+        match parse_command(s) {
+            Some(Ok(SlashCommand::Quit)) => break,
+            Some(Ok(cmd)) => run_command(cmd, ai, opts, &mut session),
+            Some(Err(msg)) => eprintln!("⚠️ {}", msg),
+            None => answer_line(ai, opts, &mut session, s),
         }
+    }
+    let _ = editor.save_history(HISTORY_FILE);
+    if !opts.json {
+        println!("Bye");
+    }
+}
 
-        // Check knowledge base first
-        if let Some(answer) = find_answer("crates/predict/data/knowledge.csv", &prompt) {
-            println!("> {}", prompt);
-            println!("🧠 Из знаний: {}", answer);
-            // persist to memory
-            let _ = ai.memory.save_dialog(&prompt, &answer);
-            return;
+/// Per-session REPL state: the transcript for `/save`, the active `/record` file,
+/// the background jobs for `/jobs` and the speech backend for `/listen`.
+#[derive(Default)]
+struct Session {
+    transcript: Vec<(String, Answer)>,
+    recording: Option<(PathBuf, std::fs::File)>,
+    jobs: Scheduler,
+    audio: Option<Box<dyn Audio>>,
+}
+
+/// Words that end `/listen`.
+const STOP_WORDS: [&str; 3] = ["стоп", "хватит", "stop"];
+
+/// The `[audio]` backend, unless it is `none` or cannot be built.
+fn voice(opts: &Options) -> Option<Box<dyn Audio>> {
+    if opts.config.audio.backend == Backend::None {
+        return None;
+    }
+    match audio::from_config(&opts.config.audio) {
+        Ok(audio) => Some(audio),
+        Err(e) => {
+            eprintln!("⚠️ {}", e);
+            None
         }
+    }
+}
 
-        // Try to compute arithmetic expression
-        if let Some(ans) = eval_arith(&prompt) {
-            println!("> {}", prompt);
-            println!("🧠 Вычислено: {}", ans);
-            let _ = append_knowledge("crates/predict/data/knowledge.csv", &prompt, &ans);
-            let _ = ai.memory.save_dialog(&prompt, &ans);
-            return;
+/// Answer a typed or spoken prompt: show it, read it aloud (`[audio]`), keep it
+/// in the transcript and the recording.
+fn answer_line(ai: &mut AI, opts: &Options, session: &mut Session, s: &str) {
+    let answer = respond(ai, opts, s);
+    emit(&answer, opts.json);
+    if opts.profile {
+        show_profile(ai, &answer);
+    }
+    if let Some(Err(e)) = session.audio.as_mut().map(|audio| audio.speak(&answer.answer)) {
+        eprintln!("⚠️ {}", e);
+    }
+    session.record(ai.model.seed, s, &answer);
+    session.transcript.push((s.to_string(), answer));
+}
+
+/// `/listen`: answer spoken prompts, aloud, until silence or a stop word.
+fn hands_free(ai: &mut AI, opts: &Options, session: &mut Session) {
+    if session.audio.is_none() {
+        println!("🎤 Голосовой режим не настроен: [audio] backend = \"command\" в shark.toml");
+        return;
+    }
+    println!("🎤 Слушаю — скажите «стоп» или промолчите, чтобы закончить");
+    while let Some(audio) = session.audio.as_mut() {
+        let heard = match audio.listen() {
+            Ok(heard) => heard,
+            Err(e) => {
+                eprintln!("⚠️ {}", e);
+                break;
+            }
+        };
+        let word = heard.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase();
+        if word.is_empty() || STOP_WORDS.contains(&word.as_str()) {
+            break;
         }
+        println!("🎤 {}", heard);
+        session.jobs.touch();
+        answer_line(ai, opts, session, &heard);
+    }
+    println!("🎤 Голосовой режим завершён");
+}
 
-        // Try to solve simple linear equation
-        if let Some(ans) = solve_linear_equation(&prompt) {
-            println!("> {}", prompt);
-            println!("🧠 Решено: {}", ans);
-            let _ = append_knowledge("crates/predict/data/knowledge.csv", &prompt, &ans);
-            let _ = ai.memory.save_dialog(&prompt, &ans);
+impl Session {
+    /// Append one exchange to the recording, if one is active.
+    fn record(&mut self, seed: u64, prompt: &str, answer: &Answer) {
+        let Some((path, file)) = &mut self.recording else {
             return;
+        };
+        let entry = RecordEntry { seed, prompt: prompt.to_string(), answer: answer.answer.clone(), source: answer.source };
+        let written = serde_json::to_string(&entry)
+            .map_err(io::Error::from)
+            .and_then(|line| writeln!(file, "{}", line));
+        if let Err(e) = written {
+            eprintln!("⚠️ Запись в {} остановлена: {}", path.display(), e);
+            self.recording = None;
         }
-
-        // single-shot: use ai.chat which returns raw output; decode for presentation
-        let raw = ai.chat(&prompt);
-        let readable = predict::decode::decode_raw(&raw);
-        println!("> {}", prompt);
-        println!("🧠 Ответ: {}", readable);
-        return;
     }
+}
 
-    // Interactive REPL
-    println!("Interactive chat — введите 'quit' или Ctrl-D для выхода");
-    let stdin = io::stdin();
-    let mut stdout = io::stdout();
-    for line in stdin.lock().lines() {
-        match line {
-            Ok(s) => {
-                let s = s.trim();
-                if s.is_empty() {
-                    continue;
-                }
-                if s.eq_ignore_ascii_case("quit") || s.eq_ignore_ascii_case("exit") {
-                    println!("Bye");
-                    break;
+/// Execute a slash-command from the REPL or a single-shot prompt.
+fn run_command(cmd: SlashCommand, ai: &mut AI, opts: &Options, session: &mut Session) {
+    let paths = &opts.paths;
+    let transcript = &session.transcript;
+    match cmd {
+        SlashCommand::Help => {
+            for (usage, what) in HELP {
+                println!("  {:<22} {}", usage, what);
+            }
+        }
+        SlashCommand::Quit => {}
+        SlashCommand::Reset => {
+            ai.dialogue.clear();
+            match ai.memory.clear() {
+                Ok(()) => println!("🧹 Память диалогов очищена."),
+                Err(e) => eprintln!("⚠️ Память очищена, но не сохранена: {}", e),
+            }
+        }
+        SlashCommand::Memory => {
+            let dialogs = ai.memory.dialogs();
+            if dialogs.is_empty() {
+                println!("(память пуста)");
+            }
+            for (q, a) in dialogs.iter().rev().take(10).rev() {
+                println!("Вы: {}\nShark-Core: {}\n", q, a);
+            }
+        }
+        SlashCommand::KnowledgeAdd { question, answer } => match append_knowledge(&paths.knowledge, &question, &answer) {
+            Ok(()) => {
+                ai.knowledge.insert(question.to_lowercase(), answer.clone());
+                // cached answers to this question predate the new entry
+                ai.pipeline.clear_cache();
+                println!("📚 Добавлено в {}: {} → {}", paths.knowledge, question, answer);
+            }
+            Err(e) => eprintln!("⚠️ Не удалось записать {}: {}", paths.knowledge, e),
+        },
+        SlashCommand::KnowledgeConflicts => {
+            let log = paths.dir.join("knowledge").join("knowledge_conflicts.csv");
+            match knowledge_store::open_conflicts(Path::new(&paths.knowledge), &log) {
+                Ok(conflicts) if conflicts.is_empty() => println!("✅ Противоречий в базе знаний нет."),
+                Ok(conflicts) => {
+                    for (i, conflict) in conflicts.iter().enumerate() {
+                        let why = match &conflict.kind {
+                            ConflictKind::DifferentAnswers => "разные ответы".to_string(),
+                            ConflictKind::Mismatch { expected } => format!("расчёт даёт {}", expected),
+                        };
+                        println!("{}. «{}» — {}:", i + 1, conflict.question, why);
+                        for (k, entry) in conflict.entries.iter().enumerate() {
+                            println!("     {}) {} (строка {})", k + 1, entry.answer, entry.line + 1);
+                        }
+                    }
+                    println!("Разрешить: /knowledge resolve N keep K | fix | remove | accept");
                 }
-                // Load Rust self-knowledge and handle structure/code queries
-                let rust_knowledge = load_rust_knowledge("crates/predict/data/knowledge_rust.csv");
-                // Reasoner trigger in REPL
-                // Detect knowledge gaps and auto-expand topic files if needed
-                if let Some(topic) = detect_knowledge_gap(s) {
-                    let _ = auto_expand_on_new_topic(&topic);
-                    let _ = merge_knowledge_sources();
+                Err(e) => eprintln!("⚠️ {}: {}", paths.knowledge, e),
+            }
+        }
+        SlashCommand::KnowledgeResolve { index, choice } => {
+            let log = paths.dir.join("knowledge").join("knowledge_conflicts.csv");
+            let knowledge = Path::new(&paths.knowledge);
+            let conflicts = knowledge_store::open_conflicts(knowledge, &log).unwrap_or_default();
+            let Some(conflict) = conflicts.get(index - 1) else {
+                eprintln!("⚠️ Нет противоречия №{} — см. /knowledge conflicts", index);
+                return;
+            };
+            let resolution = match (choice, &conflict.kind) {
+                (ConflictChoice::Keep(k), _) => conflict.entries.get(k - 1).map(|e| Resolution::Keep(e.answer.clone())),
+                (ConflictChoice::Fix, ConflictKind::Mismatch { expected }) => Some(Resolution::Replace(expected.clone())),
+                (ConflictChoice::Fix, ConflictKind::DifferentAnswers) => None,
+                (ConflictChoice::Remove, _) => Some(Resolution::Remove),
+                (ConflictChoice::Accept, _) => Some(Resolution::Accept),
+            };
+            let Some(resolution) = resolution else {
+                eprintln!("⚠️ Такой вариант не подходит к противоречию №{}", index);
+                return;
+            };
+            match knowledge_store::resolve(knowledge, &log, conflict, &resolution) {
+                Ok(removed) => {
+                    if let Err(e) = ai.reload_knowledge() {
+                        eprintln!("⚠️ {}", e);
+                    }
+                    println!("📚 «{}»: {} (удалено строк: {})", conflict.question, resolution.as_str(), removed);
                 }
-                // Reasoner trigger in REPL (include integrals)
-                if s.to_lowercase().contains("упрост") || s.to_lowercase().contains("объясн") || s.to_lowercase().contains("рассужд") || s.to_lowercase().contains("интеграл") {
-                    let (ans, reasoning) = Reasoner::explain(s);
-                    println!("🧠 Ответ: {}", ans);
-                    println!("📜 Рассуждение:\n{}", reasoning);
-                    continue;
+                Err(e) => eprintln!("⚠️ Не удалось записать {}: {}", paths.knowledge, e),
+            }
+        }
+        SlashCommand::Jobs => {
+            let status = session.jobs.status();
+            if status.is_empty() {
+                println!("(фоновых задач нет)");
+            }
+            for job in status {
+                let last = job.last_message.map(|m| format!(" — {}", m)).unwrap_or_default();
+                println!("  {:<16} {:<8} {} · запусков: {}{}", job.name, format!("{:?}", job.state), job.trigger, job.runs, last);
+            }
+        }
+        SlashCommand::ModelList => {
+            let active = ai.models.active().unwrap_or(model_registry::DEFAULT);
+            let marker = |name: &str| if name == active { "▶" } else { " " };
+            println!("{} {:<12} n-gram, если обучена, иначе символьная модель", marker(model_registry::DEFAULT), model_registry::DEFAULT);
+            for spec in ai.models.specs() {
+                let loaded = if ai.models.is_loaded(&spec.name) { " (загружена)" } else { "" };
+                println!("{} {:<12} {:<5} {}{}", marker(&spec.name), spec.name, spec.kind.as_str(), spec.path, loaded);
+            }
+            let stats = ai.speculative_stats();
+            if stats.target_passes > 0 {
+                println!("черновая модель {}: принято {}/{} ({:.0}%), {:.2} симв. за проход",
+                    ai.config.model.draft, stats.accepted, stats.drafted, stats.acceptance_rate() * 100.0, stats.chars_per_pass());
+            }
+        }
+        SlashCommand::ModelUse(name) => match ai.use_model(&name) {
+            Ok(()) => println!("🧠 Модель генерации: {}", name),
+            Err(e) => println!("⚠️ {}", e),
+        },
+        SlashCommand::Seed(seed) => {
+            ai.model.seed = seed;
+            println!("🎲 Seed генерации: {}", seed);
+        }
+        SlashCommand::Save(path) => match save_transcript(&path, transcript) {
+            Ok(()) => println!("💾 Стенограмма сохранена в {}", path.display()),
+            Err(e) => eprintln!("⚠️ Не удалось сохранить {}: {}", path.display(), e),
+        },
+        SlashCommand::Listen => hands_free(ai, opts, session),
+        SlashCommand::Export(path) => match export::write_transcript(&export_session(transcript), &path) {
+            Ok(()) => println!("📤 Сессия экспортирована в {}", path.display()),
+            Err(e) => eprintln!("⚠️ Не удалось экспортировать {}: {}", path.display(), e),
+        },
+        SlashCommand::Record(None) => match session.recording.take() {
+            Some((path, _)) => println!("⏹ Запись остановлена: {}", path.display()),
+            None => println!("(запись не ведётся)"),
+        },
+        SlashCommand::Record(Some(path)) => {
+            match std::fs::OpenOptions::new().create(true).append(true).open(&path) {
+                Ok(file) => {
+                    println!("⏺ Запись в {} — воспроизведение: chat replay {}", path.display(), path.display());
+                    session.recording = Some((path, file));
                 }
-                if s.contains("структура") || s.contains("код") {
-                    println!("🧩 Shark-Core состоит из следующих модулей:");
-                    for (file, desc) in rust_knowledge {
-                        println!("• {} — {}", file, desc);
+                Err(e) => eprintln!("⚠️ Не удалось открыть {}: {}", path.display(), e),
+            }
+        }
+        SlashCommand::Structure => emit(&show_structure(&paths.rust_knowledge), opts.json),
+        SlashCommand::Research => emit(&research(42, 300, 50), opts.json),
+        SlashCommand::Problems => emit(&run_eval(ai, &paths.problems, &reports::path(EVAL_REPORT), None), opts.json),
+    }
+}
+
+/// `chat ngram train|sample`.
+fn run_ngram(opts: &Options, action: &NgramCommand) -> io::Result<()> {
+    match action {
+        NgramCommand::Train { corpus, order, add_k, discount, out } => {
+            let smoothing = match add_k {
+                Some(k) => Smoothing::AddK(*k),
+                None => Smoothing::KneserNey(*discount),
+            };
+            let files = if corpus.is_empty() { vec![PathBuf::from(&opts.paths.knowledge)] } else { corpus.clone() };
+            let mut model = NgramModel::new(*order, smoothing);
+            let mut text = String::new();
+            for file in &files {
+                let path = file.to_string_lossy();
+                if file.extension().is_some_and(|e| e == "csv" || e == "jsonl") {
+                    for (q, a) in QaDataset::load(&path)?.items {
+                        text.push_str(&format!("{}\n{}\n", q, a));
                     }
-                    continue;
+                } else {
+                    text.push_str(&std::fs::read_to_string(file)?);
+                    text.push('\n');
                 }
+            }
+            model.train(&text);
+            model.save(out)?;
+            println!("[ngram] порядок {}, словарь {} символов, перплексия на обучающем корпусе {:.2} → {}",
+                model.order(), model.vocab_size(), model.perplexity(&text), out.display());
+        }
+        NgramCommand::Sample { count, seed, file } => {
+            let model = NgramModel::load(file)?;
+            for i in 0..*count {
+                println!("{}", model.generate(120, seed.wrapping_add(i as u64)));
+            }
+        }
+    }
+    Ok(())
+}
 
-                // Command: trigger symbolic exploration
-                if s.to_lowercase().contains("исслед") {
-                    let (best, fit) = scientist::evolve_symbolic(42, 300, 50);
-                    let formula = format!("{:?}", best);
-                    let curiosity = scientist::curiosity_from_mse(fit);
-                    println!("🧠 Я нашёл новую закономерность: {}\nMSE = {:.4} — любознательность={:.4} ✅", formula, fit, curiosity);
-                    continue;
-                }
+/// `chat train fit`: start a gradient training run.
+fn train_fit(opts: &Options, args: &FitArgs) -> Result<(), String> {
+    let dataset = args.dataset.as_ref().map(|p| p.to_string_lossy().to_string()).unwrap_or_else(|| opts.paths.knowledge.clone());
+    let run = RunConfig {
+        train: TrainConfig {
+            loss: Loss::CrossEntropy,
+            optimizer: OptimizerConfig::adam(args.lr),
+            batch_size: args.batch_size,
+            epochs: args.epochs,
+            seed: args.seed,
+            dropout: args.dropout,
+            weight_decay: args.weight_decay,
+            ..TrainConfig::default()
+        },
+        checkpoint_every: args.every,
+        checkpoint_dir: args.checkpoint_dir.clone(),
+        patience: args.patience,
+        dataset: Some(dataset),
+        val_fraction: args.val,
+        log_path: Some(args.log.clone()),
+        ..RunConfig::new(TrainConfig::default())
+    };
+    let model = match Model::load(&opts.config.model.path) {
+        Ok(model) if !args.scratch => model,
+        Err(e) if !args.scratch && !e.is_not_found() => return Err(e.to_string()),
+        _ => {
+            println!("[train] начинаю со случайных весов (seed {})", args.seed);
+            Model::new_random((Model::EMBED, Model::HIDDEN), args.seed)
+        }
+    };
+    train_model(model, &run, None, args.out.as_deref().unwrap_or(&opts.config.model.path))
+}
 
-                // Command: evaluate problems dataset
-                if s.to_lowercase().contains("проверь задачи") {
-                    let (ok, total) = evaluate_problems(&mut ai, "crates/predict/data/problems.csv");
-                    println!("[train] problems scored: {}/{} — доклад в docs/problems_report.md", ok, total);
-                    continue;
-                }
+/// `chat train selfplay`: run the Reasoner/model curriculum and save the weights.
+fn train_selfplay(opts: &Options, args: &SelfplayArgs) -> Result<(), String> {
+    let defaults = SelfplayConfig::default();
+    let knowledge_dir = opts.paths.dir.join("knowledge").join("knowledge_selfplay.csv");
+    let cfg = SelfplayConfig {
+        rounds: args.rounds,
+        per_round: args.per_round,
+        seed: args.seed,
+        finetune: defaults
+            .finetune
+            .filter(|_| args.epochs > 0)
+            .map(|t| TrainConfig { epochs: args.epochs, optimizer: OptimizerConfig::adam(args.lr), seed: args.seed, ..t }),
+        knowledge_path: (!args.no_knowledge).then(|| opts.paths.knowledge.clone()),
+        provenance_path: (!args.no_knowledge).then(|| knowledge_dir.to_string_lossy().to_string()),
+        ..defaults
+    };
+    let mut model = Model::load_or_zeroed(&opts.config.model.path).map_err(|e| e.to_string())?;
+    let report = selfplay::run(&mut model, &cfg)?;
+    for r in &report.rounds {
+        let loss = r.losses.last().map(|l| format!(", loss={:.4}", l)).unwrap_or_default();
+        println!("[selfplay] уровень {}: решено {}/{}{}", r.level, r.verified, r.generated, loss);
+    }
+    for pair in report.pairs.iter().take(5) {
+        println!("  {} → {}", pair.question, pair.answer);
+    }
+    if cfg.finetune.is_some() {
+        let out = args.out.as_deref().unwrap_or(&opts.config.model.path);
+        model.save(out).map_err(|e| format!("не удалось сохранить веса в {}: {}", out, e))?;
+        println!("[selfplay] веса сохранены в {}", out);
+    }
+    if let Some(path) = &cfg.knowledge_path {
+        println!("[selfplay] новых фактов в {}: {}", path, report.added_knowledge);
+    }
+    Ok(())
+}
 
-                // Check knowledge base first
-                if let Some(answer) = find_answer("crates/predict/data/knowledge.csv", s) {
-                    println!("🧠 Из знаний: {}", answer);
-                    let _ = ai.memory.save_dialog(s, &answer);
-                    continue;
-                }
-                // Try compute arith
-                if let Some(ans) = eval_arith(s) {
-                    println!("🧠 Вычислено: {}", ans);
-                    let _ = append_knowledge("crates/predict/data/knowledge.csv", s, &ans);
-                    let _ = ai.memory.save_dialog(s, &ans);
-                    continue;
-                }
-                // Try linear eq
-                if let Some(ans) = solve_linear_equation(s) {
-                    println!("🧠 Решено: {}", ans);
-                    let _ = append_knowledge("crates/predict/data/knowledge.csv", s, &ans);
-                    let _ = ai.memory.save_dialog(s, &ans);
-                    continue;
-                }
+/// `chat train generate`: write a synthetic dataset.
+fn train_generate(args: &GenerateArgs) -> Result<(), String> {
+    let kinds = args
+        .kinds
+        .split(',')
+        .map(|k| ProblemKind::parse(k).ok_or_else(|| format!("неизвестный вид задач '{}': arith, linear, integral", k.trim())))
+        .collect::<Result<Vec<_>, _>>()?;
+    let spec = DatasetSpec { count: args.count, kinds, min_level: args.min_level, max_level: args.max_level };
+    let problems = generate_dataset(&spec, args.seed);
+    write_dataset(&problems, &args.out).map_err(|e| format!("{}: {}", args.out.display(), e))?;
+    println!("[generate] {} задач записано в {}", problems.len(), args.out.display());
+    Ok(())
+}
 
-                // call AI (this persists to memory inside)
-                let raw = ai.chat(s);
-                let readable = predict::decode::decode_raw(&raw);
-                println!("AI: {}", readable);
-                // flush to keep REPL responsive
-                let _ = stdout.flush();
+/// `chat train resume`: continue a run from its checkpoint.
+fn train_resume(opts: &Options, checkpoint: &Path, out: Option<&str>) -> Result<(), String> {
+    let ckpt = Checkpoint::load(checkpoint).map_err(|e| format!("{}: {}", checkpoint.display(), e))?;
+    let mut model = Model::load_or_zeroed(&opts.config.model.path).map_err(|e| e.to_string())?;
+    ckpt.restore_layers(&mut [&mut model.lin1, &mut model.lin2])?;
+    println!("[train] продолжаю с эпохи {}, шаг {}", ckpt.state.epoch + 1, ckpt.state.step);
+    train_model(model, &ckpt.run, Some(ckpt.state), out.unwrap_or(&opts.config.model.path))
+}
+
+/// Build examples from the run's dataset, train, report per-epoch losses and save the weights.
+fn train_model(mut model: Model, run: &RunConfig, resume: Option<TrainerState>, out: &str) -> Result<(), String> {
+    let path = run.dataset.clone().ok_or("в конфигурации запуска не указан набор данных")?;
+    let mut data = QaDataset::load(&path).map_err(|e| format!("{}: {}", path, e))?;
+    data.shuffle(run.train.seed);
+    let split = data.split(1.0 - run.val_fraction, run.val_fraction);
+    let train = examples_from_pairs(&model, &split.train.items, 64);
+    let val = examples_from_pairs(&model, &split.val.items, 64);
+    println!("[train] {}: {} примеров для обучения, {} для проверки", path, train.len(), val.len());
+
+    let outcome = trainer::run(&mut [&mut model.lin1, &mut model.lin2], &train, &val, run, resume)?;
+    for e in &outcome.history {
+        match e.val_loss {
+            Some(v) => println!("[train] эпоха {}: loss={:.4} val_loss={:.4}", e.epoch, e.train_loss, v),
+            None => println!("[train] эпоха {}: loss={:.4}", e.epoch, e.train_loss),
+        }
+    }
+    if outcome.stopped_early {
+        println!("[train] ранняя остановка: val_loss не улучшался {} эпох", run.patience);
+    }
+    if let Some(last) = outcome.checkpoints.last() {
+        println!("[train] контрольных точек: {}, последняя: {}", outcome.checkpoints.len(), last.display());
+    }
+    model.save(out).map_err(|e| format!("не удалось сохранить веса в {}: {}", out, e))?;
+    println!("[train] веса сохранены в {}", out);
+    if let Some(log) = &run.log_path {
+        let report = reports::path("training_report.md");
+        match write_report(log, &report) {
+            Ok(()) => println!("[train] журнал: {}, отчёт: {}", log.display(), report.display()),
+            Err(e) => eprintln!("⚠️ Не удалось записать {}: {}", report.display(), e),
+        }
+    }
+    Ok(())
+}
+
+/// `chat tutor`: ask the due problems one by one, check each answer against the
+/// expected one, show the Reasoner's solution on mistakes and save the score
+/// file after every answer.
+fn tutor(opts: &Options, user: &str, problems_path: &str, limit: Option<usize>) -> io::Result<()> {
+    let problems = load_problems(problems_path);
+    if problems.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("нет задач в {}", problems_path)));
+    }
+    let score_file = score_path(&opts.paths.dir, user);
+    let mut state = TutorState::load(&score_file);
+    let session = state.start_session();
+    let queue: Vec<_> = state.queue(&problems).into_iter().take(limit.unwrap_or(usize::MAX)).cloned().collect();
+    println!("🎓 {}: сессия {}, задач к повторению: {} (/quit — выход, пустой ответ — показать решение)", user, session, queue.len());
+    if queue.is_empty() {
+        println!("Все задачи повторены — возвращайтесь в следующей сессии.");
+        return state.save(&score_file);
+    }
+
+    let mut editor = DefaultEditor::new().map_err(io::Error::other)?;
+    let mut right = 0;
+    let mut asked = 0;
+    for (i, (question, expected)) in queue.iter().enumerate() {
+        println!("\n[{}/{}] {}", i + 1, queue.len(), question);
+        let given = match editor.readline("ответ › ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(io::Error::other(e)),
+        };
+        let given = given.trim();
+        if matches!(parse_command(given), Some(Ok(SlashCommand::Quit))) {
+            break;
+        }
+        asked += 1;
+        let correct = !given.is_empty() && check_answer(given, expected);
+        if correct {
+            right += 1;
+            println!("✅ Верно!");
+        } else {
+            println!("❌ Правильный ответ: {}", expected);
+            let (solution, reasoning) = Reasoner::explain(&opts.paths.dir, question);
+            if check_answer(&solution, expected) && !reasoning.trim().is_empty() {
+                println!("📜 Решение:\n{}", reasoning.trim_end());
             }
-            Err(_) => break,
         }
+        state.record(question, correct);
+        state.save(&score_file)?;
+    }
+
+    println!("\n[tutor] сессия {}: {}/{} верно; всего {}/{} — {}", session, right, asked, state.score, state.answered, score_file.display());
+    state.save(&score_file)
+}
+
+/// One recorded exchange (a line of a `/record` JSONL file).
+#[derive(Serialize, Deserialize)]
+struct RecordEntry {
+    /// generation seed in effect when the prompt was answered
+    seed: u64,
+    prompt: String,
+    answer: String,
+    source: Source,
+}
+
+/// `chat replay`: answer every recorded prompt again with the recorded seed and
+/// an empty, non-persistent dialog memory, printing a diff for each changed answer.
+/// Answers go through `answer_only`, so replaying never changes the knowledge base.
+/// Returns the number of differing answers; with `update` the file is rewritten
+/// with the new answers.
+fn replay(opts: &Options, file: &Path, update: bool) -> io::Result<usize> {
+    let text = std::fs::read_to_string(file)?;
+    let mut entries = Vec::new();
+    for (n, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        let entry: RecordEntry = serde_json::from_str(line)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("строка {}: {}", n + 1, e)))?;
+        entries.push(entry);
+    }
+
+    let mut ai = or_exit(AI::builder().config(opts.config.clone()).memory(Memory::in_memory()).build());
+    let mut diffs = 0;
+    for entry in &mut entries {
+        ai.model.seed = entry.seed;
+        let answer = answer_only(&mut ai, &entry.prompt);
+        if answer.answer == entry.answer {
+            println!("✅ {}", entry.prompt);
+        } else {
+            diffs += 1;
+            println!("❌ {}\n  - {}\n  + {}", entry.prompt, entry.answer, answer.answer);
+            entry.answer = answer.answer;
+            entry.source = answer.source;
+        }
+    }
+    println!("[replay] {}/{} ответов совпали", entries.len() - diffs, entries.len());
+
+    if update && diffs > 0 {
+        let mut out = String::new();
+        for entry in &entries {
+            out.push_str(&serde_json::to_string(entry).map_err(io::Error::from)?);
+            out.push('\n');
+        }
+        std::fs::write(file, out)?;
+        println!("[replay] {} обновлён", file.display());
+    }
+    Ok(diffs)
+}
+
+/// Regenerate the replay log record `id`; whether it reproduced exactly.
+fn replay_generation(opts: &Options, id: &str) -> bool {
+    let log = ReplayLog::new(opts.config.replay_log_path());
+    let record = or_exit(log.find(id));
+    let mut ai = load_ai(opts, false);
+    let outcome = or_exit(replay::replay(&mut ai, &record, &log.path));
+    println!("[replay] {} ({}, генератор {}, seed {}, контекст {})", record.id, record.timestamp, record.generator, record.seed, record.context_hash);
+    println!("  записано:  {}", record.text);
+    println!("  повторено: {}", outcome.text);
+    match outcome.divergence {
+        None => println!("✅ совпадает ({} токенов)", outcome.tokens.len()),
+        Some(at) => println!("❌ расхождение с токена {}", at),
+    }
+    outcome.divergence.is_none()
+}
+
+/// Write the session transcript as Markdown.
+fn save_transcript(path: &Path, transcript: &[(String, Answer)]) -> io::Result<()> {
+    let mut md = format!("# Shark-Core — стенограмма\n\n_{}_\n", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"));
+    for (prompt, answer) in transcript {
+        md.push_str(&format!("\n**Вы:** {}\n\n**Shark-Core** (`{}`): {}\n", prompt, answer.source.as_str(), answer.answer));
+    }
+    std::fs::write(path, md)
+}
+
+/// The session transcript with sources and reasoning, for `/export`.
+fn export_session(transcript: &[(String, Answer)]) -> export::Session {
+    let exchanges = transcript
+        .iter()
+        .map(|(prompt, answer)| export::Exchange {
+            question: prompt.clone(),
+            answer: answer.answer.clone(),
+            source: answer.source,
+            confidence: answer.confidence,
+            reasoning: answer.reasoning.clone(),
+        })
+        .collect();
+    export::Session { exchanges }
+}
+
+/// One batch item: the prompt and, for scored datasets, the expected answer.
+struct BatchItem {
+    prompt: String,
+    expected: Option<String>,
+}
+
+/// One row of batch output.
+#[derive(Serialize)]
+struct BatchResult<'a> {
+    prompt: &'a str,
+    #[serde(flatten)]
+    answer: &'a Answer,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expected: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ok: Option<bool>,
+}
+
+/// Read batch prompts: `question,expected` rows from a `.csv` file, otherwise one prompt per line.
+fn read_batch(input: &str) -> io::Result<Vec<BatchItem>> {
+    if input != "-" && input.ends_with(".csv") {
+        if !Path::new(input).exists() {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("{} не найден", input)));
+        }
+        return Ok(load_problems(input)
+            .into_iter()
+            .map(|(prompt, expected)| BatchItem { prompt, expected: Some(expected) })
+            .collect());
+    }
+    let lines: Vec<String> = if input == "-" {
+        io::stdin().lock().lines().collect::<io::Result<_>>()?
+    } else {
+        std::fs::read_to_string(input)?.lines().map(|l| l.to_string()).collect()
+    };
+    Ok(lines
+        .into_iter()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .map(|prompt| BatchItem { prompt, expected: None })
+        .collect())
+}
+
+/// Quote a CSV field, doubling embedded quotes.
+fn csv_field(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\"\"").replace('\n', " "))
+}
+
+/// `--batch`: answer every prompt and write one result per prompt as CSV or JSONL.
+/// Read-only: no startup merges or relearning, answers go through `answer_only`
+/// and the dialog memory is not persisted.
+fn run_batch(opts: &Options, input: &str, out: Option<&Path>) -> io::Result<()> {
+    let items = read_batch(input)?;
+    let mut ai = or_exit(AI::builder().config(opts.config.clone()).memory(Memory::in_memory()).build());
+
+    let csv = out.map(|p| p.extension().map(|e| e == "csv").unwrap_or(false)).unwrap_or(false);
+    let mut writer: Box<dyn Write> = match out {
+        Some(path) => Box::new(io::BufWriter::new(std::fs::File::create(path)?)),
+        None => Box::new(io::stdout()),
+    };
+    if csv {
+        writeln!(writer, "prompt,answer,source,latency_ms,expected,ok")?;
+    }
+
+    let (mut scored, mut ok_count) = (0usize, 0usize);
+    for item in &items {
+        let answer = answer_only(&mut ai, &item.prompt);
+        let ok = item.expected.as_deref().map(|e| normalize_answer(&answer.answer) == normalize_answer(e));
+        if let Some(ok) = ok {
+            scored += 1;
+            ok_count += usize::from(ok);
+        }
+        if csv {
+            writeln!(
+                writer,
+                "{},{},{},{:.3},{},{}",
+                csv_field(&item.prompt),
+                csv_field(&answer.answer),
+                answer.source.as_str(),
+                answer.latency_ms,
+                item.expected.as_deref().map(csv_field).unwrap_or_default(),
+                ok.map(|b| b.to_string()).unwrap_or_default()
+            )?;
+        } else {
+            let row = BatchResult { prompt: &item.prompt, answer: &answer, expected: item.expected.as_deref(), ok };
+            writeln!(writer, "{}", serde_json::to_string(&row).map_err(io::Error::other)?)?;
+        }
+    }
+    writer.flush()?;
+
+    eprintln!("[batch] обработано {} запросов", items.len());
+    if scored > 0 {
+        eprintln!("[batch] совпало с ожидаемым: {}/{}", ok_count, scored);
+    }
+    if let Some(path) = out {
+        eprintln!("[batch] результаты записаны в {}", path.display());
+    }
+    Ok(())
+}
+
+/// How an answer is presented in human-readable mode.
+#[derive(Clone, Copy)]
+enum Kind {
+    Knowledge,
+    Computed,
+    Solved,
+    Explained,
+    Structure,
+    Discovery,
+    Evaluation,
+    Generated,
+}
+
+impl Kind {
+    fn source(self) -> Source {
+        match self {
+            Kind::Knowledge | Kind::Structure => Source::Knowledge,
+            Kind::Computed | Kind::Solved | Kind::Explained | Kind::Discovery | Kind::Evaluation => Source::Reasoner,
+            Kind::Generated => Source::Model,
+        }
+    }
+}
+
+/// One answer together with its provenance, confidence, reasoning trace and latency.
+#[derive(Serialize)]
+struct Answer {
+    answer: String,
+    source: Source,
+    confidence: f32,
+    reasoning: Vec<String>,
+    latency_ms: f64,
+    #[serde(skip)]
+    kind: Kind,
+}
+
+impl Answer {
+    fn new(kind: Kind, answer: String, reasoning: Vec<String>, started: Instant) -> Self {
+        Self { answer, source: kind.source(), confidence: 1.0, reasoning, latency_ms: started.elapsed().as_secs_f64() * 1000.0, kind }
+    }
+}
+
+/// Print an answer either as one JSON object per line or in the human-readable form.
+fn emit(answer: &Answer, json: bool) {
+    if json {
+        match serde_json::to_string(answer) {
+            Ok(line) => println!("{}", line),
+            Err(e) => eprintln!("⚠️ Не удалось сериализовать ответ: {}", e),
+        }
+        return;
+    }
+    match answer.kind {
+        Kind::Knowledge => println!("🧠 Из знаний: {}", answer.answer),
+        Kind::Computed => println!("🧠 Вычислено: {}", answer.answer),
+        Kind::Solved => println!("🧠 Решено: {}", answer.answer),
+        Kind::Explained => {
+            println!("🧠 Ответ: {}", answer.answer);
+            println!("📜 Рассуждение:\n{}", answer.reasoning.join("\n"));
+        }
+        Kind::Structure => println!("🧩 Shark-Core состоит из следующих модулей:\n{}", answer.answer),
+        Kind::Discovery => println!("🧠 Я нашёл новую закономерность: {}", answer.answer),
+        Kind::Evaluation => {
+            println!("[train] {}", answer.answer);
+            for line in &answer.reasoning {
+                println!("  {}", line);
+            }
+        }
+        Kind::Generated => println!("🧠 Ответ: {}", answer.answer),
+    }
+}
+
+/// `--profile`: timings of the generation behind a model answer, on stderr so
+/// they never mix with `--json` output.
+#[cfg(feature = "profile")]
+fn show_profile(ai: &AI, answer: &Answer) {
+    if answer.source == Source::Model {
+        if let Some(profile) = ai.last_profile() {
+            eprint!("{}", profile.render());
+        }
+    }
+}
+
+#[cfg(not(feature = "profile"))]
+fn show_profile(_ai: &AI, _answer: &Answer) {}
+
+/// Route one prompt through the AI's answer pipeline (`pipeline.stages`); answers
+/// computed by the arithmetic and linear-equation stages are added to knowledge.csv.
+/// Structure, research and evaluation are slash-commands and never triggered by keywords.
+fn respond(ai: &mut AI, opts: &Options, prompt: &str) -> Answer {
+    let started = Instant::now();
+    let paths = &opts.paths;
+    // Detect knowledge gaps and auto-expand topic files if needed (file I/O: not in `--fast` mode)
+    let expand = !opts.fast && opts.config.features.knowledge_merge;
+    if let Some(topic) = detect_knowledge_gap(prompt).filter(|_| expand) {
+        let _ = auto_expand_on_new_topic(&paths.dir, &topic);
+        // After expansion, merge sources so new file is visible to loaders
+        let _ = merge_knowledge_sources(Path::new(&paths.knowledge), &paths.dir.join("knowledge"));
+    }
+
+    // ai.chat persists the dialog; model output is decoded for presentation
+    let response = ai.chat(prompt);
+    let kind = kind_of(&response);
+    // tool answers may depend on files that change (e.g. a backtest), so only solver results are remembered;
+    // a result already known (this session, a cached answer, or the file) is not written again
+    if matches!(kind, Kind::Computed | Kind::Solved) && response.stage != "tools" {
+        let key = prompt.trim().to_lowercase();
+        let known = ai.ensure_knowledge().is_ok_and(|k| k.contains_key(&key)) || find_answer(&paths.knowledge, prompt).is_some();
+        if !known && append_knowledge(&paths.knowledge, prompt, &response.text).is_ok() {
+            ai.knowledge.insert(key, response.text.clone());
+        }
+    }
+    present(ai, response, started)
+}
+
+/// Answer one prompt without side effects (`train::eval_answer`): no dialog
+/// memory, no topic files, nothing written to knowledge.csv. For replay and batch runs.
+fn answer_only(ai: &mut AI, prompt: &str) -> Answer {
+    let started = Instant::now();
+    let response = eval_answer(ai, prompt);
+    present(ai, response, started)
+}
+
+/// How the stage that produced `response` is presented.
+fn kind_of(response: &ChatResponse) -> Kind {
+    match response.stage {
+        "knowledge" => Kind::Knowledge,
+        "arithmetic" => Kind::Computed,
+        "linear" => Kind::Solved,
+        "tools" => Kind::Computed,
+        "reasoner" => Kind::Explained,
+        _ => Kind::Generated,
+    }
+}
+
+/// `response` as an `Answer`: model output is decoded and snapped to the word memory.
+fn present(ai: &AI, response: ChatResponse, started: Instant) -> Answer {
+    let kind = kind_of(&response);
+    let (text, mut trace) = (response.text, response.reasoning);
+    // the toy model only knows ASCII; n-gram output may be Cyrillic, which the legacy preset drops
+    let text = if response.source == Source::Model && !response.abstained {
+        trace.push(format!("сырой ответ модели: {:?}", text));
+        let opts = if ai.ngram.is_some() { DecodeOptions::unicode() } else { DecodeOptions::legacy() };
+        let decoded = decode_with(&text, &opts);
+        // snap near-miss words to the word-frequency memory (re-read each turn, it is small)
+        let corrected = if decoded == UNDECODABLE {
+            decoded.clone()
+        } else {
+            Corrector::from_memory_freq(MEMORY_FREQ_PATH).correct(&decoded)
+        };
+        if corrected != decoded {
+            trace.push(format!("исправлено по словарю: {:?}", decoded));
+        }
+        corrected
+    } else {
+        text
+    };
+    if !matches!(kind, Kind::Explained) {
+        trace.push(format!("этап: {}, уверенность: {:.3}", response.stage, response.confidence));
+    }
+    Answer { source: response.source, confidence: response.confidence, ..Answer::new(kind, text, trace, started) }
+}
+
+/// The Reasoner's answer with its step-by-step explanation.
+fn explain(data_dir: &Path, prompt: &str) -> Answer {
+    let started = Instant::now();
+    let (ans, reasoning) = Reasoner::explain(data_dir, prompt);
+    let trace = reasoning.lines().map(|l| l.to_string()).collect();
+    Answer::new(Kind::Explained, ans, trace, started)
+}
+
+/// The module summary produced by the source scanner.
+fn show_structure(rust_knowledge_path: &str) -> Answer {
+    let started = Instant::now();
+    let listing = load_rust_knowledge(rust_knowledge_path)
+        .into_iter()
+        .map(|(file, desc)| format!("• {} — {}", file, desc))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Answer::new(Kind::Structure, listing, vec![format!("прочитано из {}", rust_knowledge_path)], started)
+}
+
+/// Run the symbolic exploration and report the discovered formula.
+#[cfg(feature = "science")]
+fn research(seed: u64, generations: usize, population: usize) -> Answer {
+    let started = Instant::now();
+    let (best, fit) = scientist::evolve_symbolic(seed, generations, population);
+    let formula = format!("{:?}", best);
+    let curiosity = scientist::curiosity_from_mse(fit);
+    let trace = vec![format!("seed={} generations={} population={}", seed, generations, population)];
+    Answer::new(Kind::Discovery, format!("{}\nMSE = {:.4} — любознательность={:.4} ✅", formula, fit, curiosity), trace, started)
+}
+
+#[cfg(not(feature = "science"))]
+fn research(_seed: u64, _generations: usize, _population: usize) -> Answer {
+    Answer::new(Kind::Discovery, "⚠️ собрано без функции `science`".to_string(), Vec::new(), Instant::now())
+}
+
+/// Evaluate a problems CSV with the current AI.
+fn run_eval(ai: &mut AI, problems: &str, report_path: &Path, baseline: Option<&Path>) -> Answer {
+    let started = Instant::now();
+    let mut trace = vec![format!("набор задач: {}", problems)];
+    let suite = match load_suite(problems) {
+        Ok(suite) => suite,
+        Err(e) => return Answer::new(Kind::Evaluation, format!("⚠️ не удалось загрузить набор задач: {}", e), trace, started),
+    };
+    let report = evaluate_suite(ai, &suite);
+    for (name, c) in &report.categories {
+        trace.push(format!("{}: {}/{}", name, c.passed, c.total));
+    }
+    // read the baseline before saving, it may be the same file
+    let comparison = baseline.map(|b| Report::load(b).map(|before| evalharness::compare(&before, &report)));
+    match report.save(report_path) {
+        Ok(()) => trace.push(format!("JSON-отчёт: {}", report_path.display())),
+        Err(e) => trace.push(format!("⚠️ не удалось записать {}: {}", report_path.display(), e)),
+    }
+    match comparison {
+        Some(Ok(cmp)) => trace.extend(cmp.render().lines().map(str::to_string)),
+        Some(Err(e)) => trace.push(format!("⚠️ базовый отчёт не прочитан: {}", e)),
+        None => {}
     }
+    Answer::new(Kind::Evaluation, format!("problems scored: {}/{} — доклад в {}", report.passed, report.total, reports::path(PROBLEMS_REPORT).display()), trace, started)
 }
@@ -1,19 +1,55 @@
 use eframe::{egui, App, Frame};
-use predict::{AI, scientist};
+#[cfg(feature = "science")]
+use predict::scientist;
+use predict::{export, reports, tools, ChatResponse, Config, Pipeline, Source, AI};
+use predict::audio::{self, Audio, NullAudio};
+use predict::knowledge_env::KNOWLEDGE_LOG;
+use predict::events::{self, Event};
+use predict::usage::{self, DaySummary, UsageStats};
+use predict::jobs::Scheduler;
+use predict::knowledge_store::{self, Conflict, ConflictKind, Resolution};
+use predict::clustering::Topic;
+use predict::memory::Memory;
+use predict::model::Model;
+use predict::train::log::{read_log, LogRecord, RecordKind};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::fs;
-use std::time::{Instant, Duration};
+use std::time::{Instant, Duration, SystemTime};
 
 #[derive(Clone, PartialEq)]
 enum Tab {
     Chat,
     Research,
     Memory,
+    Training,
     Settings,
     Metrics,
+    Backtest,
 }
 
+/// Backtest run in a background thread, polled by the Backtest tab.
+#[derive(Default)]
+struct BacktestJob {
+    /// 0..=1, set at each stage of the run
+    progress: f32,
+    stage: String,
+    result: Option<Result<BacktestOutcome, String>>,
+}
+
+/// Metrics and curves of a finished backtest.
+struct BacktestOutcome {
+    /// `(label, value)` rows of the metrics table
+    metrics: Vec<(&'static str, String)>,
+    /// `(bar index, net equity)`
+    equity: Vec<(f32, f32)>,
+    /// `(bar index, distance below the running peak)`
+    drawdown: Vec<(f32, f32)>,
+}
+
+/// Result of a background voice turn: the recognized utterance or an error.
+type VoiceSlot = Arc<Mutex<Option<Result<String, String>>>>;
+
 struct SharkApp {
     ai: Arc<Mutex<AI>>,
     input: String,
@@ -30,14 +66,20 @@ struct SharkApp {
     response_speed: f32,
     dark_mode: bool,
     enable_semantic: bool,
+    /// configured pipeline with the semantic stage first (used when `enable_semantic` is on)
+    semantic_pipeline: Arc<Pipeline>,
     auto_save_history: bool,
     // new fields
     progress: f32,
     progress_start: Option<Instant>,
     thinking: bool,
     training: bool,
-    pending_reply: Option<Arc<Mutex<Option<(String, bool)>>>>,
+    pending_reply: Option<Arc<Mutex<Option<ChatResponse>>>>,
     last_prompt: String,
+    /// the conversation with sources and reasoning, for the export buttons
+    session: export::Session,
+    /// sparkline of the last answer (e.g. from the `indicator` tool), drawn under the chat
+    sparkline: Option<Vec<f32>>,
     // metrics
     question_count: usize,
     total_response_time: f64,
@@ -46,14 +88,87 @@ struct SharkApp {
     // history with timestamps
     history_with_time: Vec<(String, String, String)>, // (time, question, answer)
     start_time: Option<Instant>,
+    // training log (written by `chat train fit`)
+    training_log_path: String,
+    training_log: Vec<LogRecord>,
+    training_log_mtime: Option<SystemTime>,
+    /// background self-learning jobs (shown in the Metrics tab)
+    jobs: Scheduler,
+    /// knowledge.csv and the resolution log of its conflicts
+    knowledge_path: std::path::PathBuf,
+    conflicts_log: std::path::PathBuf,
+    /// contradictions in the knowledge base (badge on the memory tab)
+    conflicts: Vec<Conflict>,
+    /// knowledge and dialog topics (memory tab, "Найти темы")
+    topics: Vec<(&'static str, Vec<Topic>)>,
+    /// persisted usage statistics (`[stats] enabled`), charted in the Metrics tab
+    usage_enabled: bool,
+    usage_path: std::path::PathBuf,
+    usage_days: Vec<DaySummary>,
+    // backtest tab
+    backtest_path: String,
+    backtest_strategy: usize,
+    /// parameters of the selected strategy, starting from `quant_api::STRATEGIES` defaults
+    backtest_params: Vec<(&'static str, f64)>,
+    backtest_commission: f64,
+    backtest_job: Option<Arc<Mutex<BacktestJob>>>,
+    backtest_outcome: Option<Result<BacktestOutcome, String>>,
+    /// speech backend (`[audio]`); `NullAudio` hides the voice controls
+    audio: Arc<Mutex<Box<dyn Audio>>>,
+    voice_enabled: bool,
+    /// read answers aloud
+    speak_answers: bool,
+    /// hands-free: listen again after every answer until silence or «стоп»
+    hands_free: bool,
+    /// utterance of the running voice turn (`voice_turn`)
+    pending_voice: Option<VoiceSlot>,
 }
 
+/// Words that end hands-free mode.
+const STOP_WORDS: [&str; 3] = ["стоп", "хватит", "stop"];
+
 impl Default for SharkApp {
     fn default() -> Self {
+        let (config, config_warning) = match Config::discover() {
+            Ok(config) => (config, None),
+            Err(e) => (Config::default(), Some(format!("⚠️ {} — использую настройки по умолчанию.", e))),
+        };
+        reports::configure(&config);
+        usage::configure(&config);
+        let usage_path = config.usage_stats_path();
+        let usage_days = UsageStats::load(&usage_path).map(|s| s.days()).unwrap_or_default();
+        let model_path = config.model.path.clone();
+        // semantic goes first, after `learn` so a pending fact still gets its "да"
+        let learn = config.pipeline.stages.iter().any(|s| s == "learn");
+        let mut stages: Vec<String> = learn.then(|| "learn".to_string()).into_iter().chain(["semantic".to_string()]).collect();
+        stages.extend(config.pipeline.stages.iter().filter(|s| *s != "semantic" && *s != "learn").cloned());
+        let semantic_pipeline = Arc::new(Pipeline::from_stages(&stages).unwrap_or_else(|_| Pipeline::standard()));
+        let (ai, mut output) = match AI::new(&config) {
+            Ok(ai) => (ai, "🦈 Shark-Core готов к работе.".to_string()),
+            Err(e) => (
+                AI::from_parts(Model::zeroed(), Memory::in_memory()),
+                format!("⚠️ {} — работаю с нулевой моделью, память не сохраняется.", e),
+            ),
+        };
+        if let Some(warning) = config_warning {
+            output = format!("{}\n{}", warning, output);
+        }
+        let audio: Box<dyn Audio> = match audio::from_config(&config.audio) {
+            Ok(audio) => audio,
+            Err(e) => {
+                output = format!("{}\n⚠️ {}", output, e);
+                Box::new(NullAudio)
+            }
+        };
+        let voice_enabled = audio.name() != NullAudio.name();
+        let mut jobs = Scheduler::self_learning(&config);
+        jobs.start();
+        let (knowledge_path, conflicts_log) = (config.knowledge_path(), config.conflicts_log_path());
+        let conflicts = knowledge_store::open_conflicts(&knowledge_path, &conflicts_log).unwrap_or_default();
         Self {
-            ai: Arc::new(Mutex::new(AI::new("weights/model_int4.bin"))),
+            ai: Arc::new(Mutex::new(ai)),
             input: String::new(),
-            output: "🦈 Shark-Core готов к работе.".to_string(),
+            output,
             history: Vec::new(),
             tab: Tab::Chat,
             science_results: Vec::new(),
@@ -61,10 +176,11 @@ impl Default for SharkApp {
             memory_rows: Vec::new(),
             scientist_running: false,
             scientist_output: None,
-            model_path: "weights/model_int4.bin".to_string(),
+            model_path,
             response_speed: 1.0,
             dark_mode: true,
             enable_semantic: true,
+            semantic_pipeline,
             auto_save_history: false,
             progress: 0.0,
             progress_start: None,
@@ -72,6 +188,8 @@ impl Default for SharkApp {
             training: false,
             pending_reply: None,
             last_prompt: String::new(),
+            session: export::Session::default(),
+            sparkline: None,
             // metrics
             question_count: 0,
             total_response_time: 0.0,
@@ -79,6 +197,28 @@ impl Default for SharkApp {
             model_responses: 0,
             history_with_time: Vec::new(),
             start_time: None,
+            training_log_path: "logs/training_log.csv".to_string(),
+            training_log: Vec::new(),
+            training_log_mtime: None,
+            jobs,
+            knowledge_path,
+            conflicts_log,
+            conflicts,
+            topics: Vec::new(),
+            usage_enabled: config.stats.enabled,
+            usage_path,
+            usage_days,
+            backtest_path: "data/prices.csv".to_string(),
+            backtest_strategy: 0,
+            backtest_params: backtest_defaults(0),
+            backtest_commission: 0.001,
+            backtest_job: None,
+            backtest_outcome: None,
+            audio: Arc::new(Mutex::new(audio)),
+            voice_enabled,
+            speak_answers: voice_enabled,
+            hands_free: false,
+            pending_voice: None,
         }
     }
 }
@@ -90,37 +230,45 @@ impl SharkApp {
             return;
         }
 
+        self.jobs.touch();
         self.thinking = true;
         self.output = "🧠 думает...".to_string();
         self.last_prompt = prompt.clone();
         self.start_time = Some(Instant::now());
 
         // prepare shared slot for reply
-        let reply_slot: Arc<Mutex<Option<(String, bool)>>> = Arc::new(Mutex::new(None));
+        let reply_slot: Arc<Mutex<Option<ChatResponse>>> = Arc::new(Mutex::new(None));
         self.pending_reply = Some(reply_slot.clone());
 
         // clone Arc to move into thread
         let ai_arc = self.ai.clone();
         let prompt_clone = prompt.clone();
         let enable_semantic = self.enable_semantic;
+        let semantic_pipeline = self.semantic_pipeline.clone();
         let thread_ctx = ctx.clone();
         thread::spawn(move || {
-            // call model under lock
-            let (reply_raw, is_semantic) = {
+            // call model under lock; the semantic stage runs first when enabled
+            let reply = {
                 let mut ai = ai_arc.lock().unwrap();
                 if enable_semantic {
-                    if let Some(semantic_reply) = predict::interpret_question(&prompt_clone, &ai.knowledge) {
-                        (semantic_reply, true)
-                    } else {
-                        (ai.chat(&prompt_clone), false)
-                    }
+                    let reply = semantic_pipeline.run(&mut ai, &prompt_clone);
+                    // bypasses `AI::chat`, so announce the answer here (usage statistics, plugins)
+                    events::publish(Event::AnswerProduced {
+                        prompt: prompt_clone.clone(),
+                        text: reply.text.clone(),
+                        stage: reply.stage,
+                        source: reply.source,
+                        confidence: reply.confidence,
+                        latency_ms: reply.latency_ms(),
+                    });
+                    reply
                 } else {
-                    (ai.chat(&prompt_clone), false)
+                    ai.chat(&prompt_clone)
                 }
             };
-            // store reply and type
+            // store reply
             if let Ok(mut g) = reply_slot.lock() {
-                *g = Some((reply_raw, is_semantic));
+                *g = Some(reply);
             }
             // request UI repaint
             thread_ctx.request_repaint();
@@ -130,7 +278,55 @@ impl SharkApp {
         self.input.clear();
     }
 
-    fn finish_prompt(&mut self, reply_raw: String, is_semantic: bool) {
+    /// Say `speak` (if any), then listen for the next prompt if `listen`, on a
+    /// background thread; the utterance arrives through `pending_voice`.
+    fn voice_turn(&mut self, ctx: &egui::Context, speak: Option<String>, listen: bool) {
+        let slot: VoiceSlot = Arc::new(Mutex::new(None));
+        if listen {
+            self.pending_voice = Some(slot.clone());
+        }
+        let audio = self.audio.clone();
+        let thread_ctx = ctx.clone();
+        thread::spawn(move || {
+            let Ok(mut audio) = audio.lock() else { return };
+            let spoken = speak.map_or(Ok(()), |text| audio.speak(&text));
+            let result = match spoken {
+                Err(e) => Err(e.to_string()),
+                Ok(()) if listen => audio.listen().map_err(|e| e.to_string()),
+                Ok(()) => return,
+            };
+            if let Ok(mut g) = slot.lock() {
+                *g = Some(result);
+            }
+            thread_ctx.request_repaint();
+        });
+    }
+
+    /// Handle an utterance: send it as the prompt, or leave hands-free mode on
+    /// silence, a stop word or an error.
+    fn finish_voice(&mut self, ctx: &egui::Context, heard: Result<String, String>) {
+        self.pending_voice = None;
+        let heard = match heard {
+            Ok(heard) => heard,
+            Err(e) => {
+                self.hands_free = false;
+                self.output = format!("⚠️ {}", e);
+                return;
+            }
+        };
+        let word = heard.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase();
+        if word.is_empty() || STOP_WORDS.contains(&word.as_str()) {
+            self.hands_free = false;
+            return;
+        }
+        self.input = heard;
+        self.send_prompt(ctx);
+    }
+
+    fn finish_prompt(&mut self, reply: ChatResponse, ctx: &egui::Context) {
+        let is_semantic = reply.source != Source::Model;
+        self.session.push(&self.last_prompt, &reply);
+        let reply_raw = reply.text;
         // calculate response time
         let response_time = if let Some(start) = self.start_time.take() {
             start.elapsed().as_secs_f64()
@@ -144,6 +340,9 @@ impl SharkApp {
         cleaned = cleaned.trim().to_string();
 
         // update metrics
+        if self.usage_enabled {
+            self.usage_days = UsageStats::load(&self.usage_path).map(|s| s.days()).unwrap_or_default();
+        }
         self.question_count += 1;
         self.total_response_time += response_time;
         if is_semantic {
@@ -157,11 +356,45 @@ impl SharkApp {
         self.history_with_time.push((now, self.last_prompt.clone(), cleaned.clone()));
 
         self.history.push((self.last_prompt.clone(), cleaned.clone()));
+        self.sparkline = tools::sparkline_levels(&cleaned).filter(|levels| levels.len() > 1);
+        if self.hands_free || self.speak_answers {
+            let speak = self.speak_answers.then(|| cleaned.clone());
+            self.voice_turn(ctx, speak, self.hands_free);
+        }
         self.output = cleaned;
         self.thinking = false;
         self.pending_reply = None;
     }
 
+    /// Start the configured backtest in a background thread.
+    fn start_backtest(&mut self, ctx: &egui::Context) {
+        let job = Arc::new(Mutex::new(BacktestJob::default()));
+        self.backtest_job = Some(job.clone());
+        self.backtest_outcome = None;
+        let (path, strategy, params, commission) =
+            (self.backtest_path.clone(), self.backtest_strategy, self.backtest_params.clone(), self.backtest_commission);
+        let seed = self.ai.lock().map_or(0, |ai| ai.config.backtest_seed());
+        let thread_ctx = ctx.clone();
+        thread::spawn(move || {
+            let result = run_backtest(&path, strategy, &params, commission, seed, &job, &thread_ctx);
+            if let Ok(mut job) = job.lock() {
+                job.progress = 1.0;
+                job.result = Some(result);
+            }
+            thread_ctx.request_repaint();
+        });
+    }
+
+    /// Re-read the training log if it changed since the last read.
+    fn refresh_training_log(&mut self) {
+        let mtime = fs::metadata(&self.training_log_path).and_then(|m| m.modified()).ok();
+        if mtime.is_some() && mtime == self.training_log_mtime {
+            return;
+        }
+        self.training_log = read_log(std::path::Path::new(&self.training_log_path)).unwrap_or_default();
+        self.training_log_mtime = mtime;
+    }
+
     fn load_memory(&mut self) {
         // read knowledge.csv relative to the crate manifest dir
         let path = format!("{}/data/knowledge.csv", env!("CARGO_MANIFEST_DIR"));
@@ -180,6 +413,21 @@ impl SharkApp {
                 }
             }
         }
+        self.conflicts = knowledge_store::open_conflicts(&self.knowledge_path, &self.conflicts_log).unwrap_or_default();
+    }
+
+    /// Apply a resolution chosen in the memory tab and reload the knowledge base.
+    fn resolve_conflict(&mut self, conflict: &Conflict, resolution: &Resolution) {
+        self.output = match knowledge_store::resolve(&self.knowledge_path, &self.conflicts_log, conflict, resolution) {
+            Ok(_) => {
+                if let Ok(mut ai) = self.ai.lock() {
+                    let _ = ai.reload_knowledge();
+                }
+                format!("📚 «{}»: {}", conflict.question, resolution.as_str())
+            }
+            Err(e) => format!("⚠️ Не удалось записать {}: {}", self.knowledge_path.display(), e),
+        };
+        self.load_memory();
     }
 }
 
@@ -188,12 +436,18 @@ impl App for SharkApp {
         // check pending reply from background thread
         if let Some(slot) = self.pending_reply.as_ref().map(|s| s.clone()) {
             if let Ok(mut guard) = slot.lock() {
-                if let Some((reply, is_semantic)) = guard.take() {
+                if let Some(reply) = guard.take() {
                     // process reply on UI thread
-                    self.finish_prompt(reply, is_semantic);
+                    self.finish_prompt(reply, ctx);
                 }
             }
         }
+        if let Some(slot) = self.pending_voice.clone() {
+            let heard = slot.lock().ok().and_then(|mut g| g.take());
+            if let Some(heard) = heard {
+                self.finish_voice(ctx, heard);
+            }
+        }
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
                 ui.heading("🧠 Shark-Core");
@@ -208,12 +462,23 @@ impl App for SharkApp {
                     self.tab = Tab::Memory;
                     self.load_memory();
                 }
+                if !self.conflicts.is_empty() {
+                    ui.colored_label(egui::Color32::from_rgb(230, 160, 40), format!("⚠️ {}", self.conflicts.len()))
+                        .on_hover_text("противоречия в базе знаний — см. вкладку «Память»");
+                }
+                if ui.selectable_label(self.tab == Tab::Training, "📈 Обучение").clicked() {
+                    self.tab = Tab::Training;
+                    self.training_log_mtime = None;
+                }
                 if ui.selectable_label(self.tab == Tab::Settings, "⚙️ Настройки").clicked() {
                     self.tab = Tab::Settings;
                 }
                 if ui.selectable_label(self.tab == Tab::Metrics, "📊 Метрики").clicked() {
                     self.tab = Tab::Metrics;
                 }
+                if ui.selectable_label(self.tab == Tab::Backtest, "💹 Бэктест").clicked() {
+                    self.tab = Tab::Backtest;
+                }
             });
         });
 
@@ -231,6 +496,19 @@ impl App for SharkApp {
                     if ui.button("Отправить").clicked() || (response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter))) {
                         self.send_prompt(ctx);
                     }
+                    if self.voice_enabled {
+                        let listening = self.pending_voice.is_some();
+                        if ui.add_enabled(!listening && !self.thinking, egui::Button::new("🎤 Сказать")).clicked() {
+                            self.voice_turn(ctx, None, true);
+                        }
+                        ui.checkbox(&mut self.speak_answers, "🔊 Озвучивать");
+                        if ui.checkbox(&mut self.hands_free, "Без рук").changed() && self.hands_free && !listening && !self.thinking {
+                            self.voice_turn(ctx, None, true);
+                        }
+                        if listening {
+                            ui.colored_label(egui::Color32::LIGHT_GREEN, "🎤 слушаю...");
+                        }
+                    }
                 });
                 ui.label("Текущий ответ:");
                 ui.add(egui::TextEdit::multiline(&mut self.output).desired_rows(2));
@@ -266,12 +544,19 @@ impl App for SharkApp {
                         });
                     });
 
+                    if let Some(levels) = &self.sparkline {
+                        let points: Vec<(f32, f32)> = levels.iter().enumerate().map(|(i, &l)| (i as f32, l)).collect();
+                        draw_sparkline(ui, &points);
+                    }
+
                     ui.separator();
 
                     ui.horizontal(|ui| {
                         if ui.button("Очистить историю").clicked() {
                             self.history.clear();
+                            self.sparkline = None;
                             self.history_with_time.clear();
+                            self.session = export::Session::default();
                         }
                         if ui.button("Сохранить историю").clicked() {
                             let content = self.history_with_time.iter()
@@ -285,6 +570,14 @@ impl App for SharkApp {
                                 self.output = format!("История сохранена в {}", path);
                             }
                         }
+                        for path in ["transcript.md", "transcript.html"] {
+                            if ui.button(format!("Экспорт {}", path)).clicked() {
+                                self.output = match export::write_transcript(&self.session, std::path::Path::new(path)) {
+                                    Ok(()) => format!("Сессия экспортирована в {}", path),
+                                    Err(e) => format!("Ошибка экспорта: {}", e),
+                                };
+                            }
+                        }
                     });
                 }
 
@@ -300,24 +593,22 @@ impl App for SharkApp {
                             self.scientist_output = Some(results_arc.clone());
                             // spawn background thread to run the scientist cycle
                             thread::spawn(move || {
-                                let res = scientist::run_scientific_cycle();
+                                let lines = research_cycle();
                                 let mut guard = thread_arc.lock().unwrap();
-                                for r in res {
-                                    guard.push(format!("{} | mse={:.6} | accepted={}", r.name, r.mse, r.accepted));
-                                }
+                                guard.extend(lines);
                             });
                             self.science_results.clear();
                             self.science_results.push("Запущено: исследовательский цикл...".to_string());
                         }
                         if ui.button("Обновить результаты").clicked() {
                             // try to load saved science memory or just show a message
-                            // For now, read docs/knowledge_log.md as a proxy
-                            let path = format!("{}/../docs/knowledge_log.md", env!("CARGO_MANIFEST_DIR"));
+                            // For now, read knowledge_log.md as a proxy
+                            let path = reports::path(KNOWLEDGE_LOG);
                             match fs::read_to_string(&path) {
                                 Ok(s) => {
                                     self.science_results = s.lines().take(200).map(|l| l.to_string()).collect();
                                 }
-                                Err(e) => self.science_results = vec![format!("failed to read {}: {}", path, e)],
+                                Err(e) => self.science_results = vec![format!("failed to read {}: {}", path.display(), e)],
                             }
                         }
                     });
@@ -380,6 +671,59 @@ impl App for SharkApp {
                     });
 
                     ui.separator();
+                    if !self.conflicts.is_empty() {
+                        ui.colored_label(egui::Color32::from_rgb(230, 160, 40), format!("⚠️ Противоречия в базе знаний: {}", self.conflicts.len()));
+                        let mut chosen: Option<(Conflict, Resolution)> = None;
+                        for conflict in &self.conflicts {
+                            ui.horizontal_wrapped(|ui| {
+                                ui.label(format!("«{}»", conflict.question));
+                                for entry in &conflict.entries {
+                                    if ui.button(format!("оставить: {}", entry.answer)).clicked() {
+                                        chosen = Some((conflict.clone(), Resolution::Keep(entry.answer.clone())));
+                                    }
+                                }
+                                if let ConflictKind::Mismatch { expected } = &conflict.kind {
+                                    if ui.button(format!("исправить на {}", expected)).clicked() {
+                                        chosen = Some((conflict.clone(), Resolution::Replace(expected.clone())));
+                                    }
+                                }
+                                if ui.button("удалить").clicked() {
+                                    chosen = Some((conflict.clone(), Resolution::Remove));
+                                }
+                                if ui.button("оставить как есть").clicked() {
+                                    chosen = Some((conflict.clone(), Resolution::Accept));
+                                }
+                            });
+                        }
+                        if let Some((conflict, resolution)) = chosen {
+                            self.resolve_conflict(&conflict, &resolution);
+                        }
+                        ui.separator();
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("Найти темы").clicked() {
+                            if let Ok(mut ai) = self.ai.lock() {
+                                if let Err(e) = ai.ensure_knowledge() {
+                                    self.output = format!("⚠️ база знаний недоступна: {}", e);
+                                }
+                                self.topics = vec![("База знаний", ai.knowledge_topics()), ("Диалоги", ai.memory_topics())];
+                            }
+                        }
+                    });
+                    for (title, topics) in &self.topics {
+                        egui::CollapsingHeader::new(format!("{} — тем: {}", title, topics.len())).default_open(true).show(ui, |ui| {
+                            for topic in topics {
+                                ui.collapsing(format!("{} ({})", topic.words.join(", "), topic.members.len()), |ui| {
+                                    for member in &topic.members {
+                                        ui.label(member);
+                                    }
+                                });
+                            }
+                        });
+                    }
+                    if !self.topics.is_empty() {
+                        ui.separator();
+                    }
                     // show table header and rows
                     egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
                         if !self.memory_rows.is_empty() {
@@ -399,6 +743,41 @@ impl App for SharkApp {
                     });
                 }
 
+                Tab::Training => {
+                    // live view: poll the log file while the tab is open
+                    self.refresh_training_log();
+                    ctx.request_repaint_after(Duration::from_secs(1));
+                    ui.horizontal(|ui| {
+                        ui.label("Журнал:");
+                        if ui.text_edit_singleline(&mut self.training_log_path).lost_focus() {
+                            self.training_log_mtime = None;
+                        }
+                    });
+                    ui.separator();
+                    if self.training_log.is_empty() {
+                        ui.label("Журнал пуст — запустите `chat train fit`.");
+                    } else {
+                        let steps: Vec<(f32, f32)> = self.training_log.iter()
+                            .filter(|r| r.kind == RecordKind::Step)
+                            .map(|r| (r.step as f32, r.loss))
+                            .collect();
+                        let val: Vec<(f32, f32)> = self.training_log.iter()
+                            .filter_map(|r| r.val_loss.map(|v| (r.step as f32, v)))
+                            .collect();
+                        if let Some(last) = self.training_log.last() {
+                            ui.label(format!("Шаг {} · эпоха {} · loss {:.4} · lr {}", last.step, last.epoch, last.loss, last.lr));
+                        }
+                        if let Some(v) = val.last() {
+                            ui.label(format!("val_loss {:.4}", v.1));
+                        }
+                        ui.horizontal(|ui| {
+                            ui.colored_label(egui::Color32::LIGHT_BLUE, "— loss");
+                            ui.colored_label(egui::Color32::from_rgb(255, 160, 60), "— val_loss");
+                        });
+                        draw_loss_chart(ui, &steps, &val);
+                    }
+                }
+
                 Tab::Settings => {
                     ui.label("Настройки");
                     ui.separator();
@@ -406,8 +785,15 @@ impl App for SharkApp {
                         ui.label("Model path:");
                         if ui.text_edit_singleline(&mut self.model_path).lost_focus() {
                             // reload model if changed
-                            if let Ok(mut ai_lock) = self.ai.lock() {
-                                *ai_lock = AI::new(&self.model_path);
+                            let mut config = self.ai.lock().map(|ai| ai.config.clone()).unwrap_or_default();
+                            config.model.path = self.model_path.clone();
+                            match AI::new(&config) {
+                                Ok(ai) => {
+                                    if let Ok(mut ai_lock) = self.ai.lock() {
+                                        *ai_lock = ai;
+                                    }
+                                }
+                                Err(e) => self.output = format!("⚠️ модель не перезагружена: {}", e),
                             }
                         }
                     });
@@ -456,6 +842,85 @@ impl App for SharkApp {
                         ui.label(self.model_responses.to_string());
                     });
                     ui.separator();
+                    ui.label("Фоновые задачи:");
+                    ctx.request_repaint_after(Duration::from_secs(1));
+                    egui::Grid::new("jobs_grid").striped(true).show(ui, |ui| {
+                        ui.label("Задача"); ui.label("Состояние"); ui.label("Запуск"); ui.label("Запусков"); ui.label("Последний результат"); ui.end_row();
+                        for job in self.jobs.status() {
+                            ui.label(job.name);
+                            ui.label(format!("{:?}", job.state));
+                            ui.label(job.trigger);
+                            ui.label(job.runs.to_string());
+                            ui.label(job.last_message.unwrap_or_default());
+                            ui.end_row();
+                        }
+                    });
+                    if let Ok(ai) = self.ai.try_lock() {
+                        if let Some(cache) = ai.pipeline.cache_stats() {
+                            ui.horizontal(|ui| {
+                                ui.label("Кэш ответов:");
+                                ui.label(format!(
+                                    "попаданий {}/{} ({:.0}%), записей {}, вытеснено {}",
+                                    cache.hits,
+                                    cache.hits + cache.misses,
+                                    cache.hit_rate() * 100.0,
+                                    cache.len,
+                                    cache.evictions
+                                ));
+                            });
+                        }
+                        let stats = ai.speculative_stats();
+                        if stats.target_passes > 0 {
+                            ui.horizontal(|ui| {
+                                ui.label("Спекулятивное декодирование:");
+                                ui.label(format!(
+                                    "принято {}/{} ({:.0}%), {:.2} симв. за проход",
+                                    stats.accepted,
+                                    stats.drafted,
+                                    stats.acceptance_rate() * 100.0,
+                                    stats.chars_per_pass()
+                                ));
+                            });
+                        }
+                        #[cfg(feature = "profile")]
+                        if let Some(profile) = ai.last_profile() {
+                            ui.separator();
+                            ui.label(format!(
+                                "Последняя генерация: {} симв. за {:.2} мс, {:.0} симв./с",
+                                profile.tokens,
+                                profile.total.as_secs_f64() * 1e3,
+                                profile.tokens_per_sec()
+                            ));
+                            let total = profile.total.as_secs_f64().max(f64::MIN_POSITIVE);
+                            egui::Grid::new("profile_grid").striped(true).show(ui, |ui| {
+                                for (name, time) in profile.stages() {
+                                    let share = (time.as_secs_f64() / total) as f32;
+                                    ui.label(name);
+                                    ui.label(format!("{:.1} мкс/симв.", time.as_secs_f64() * 1e6 / profile.tokens.max(1) as f64));
+                                    ui.add(egui::ProgressBar::new(share).desired_width(160.0).text(format!("{:.0}%", share * 100.0)));
+                                    ui.end_row();
+                                }
+                            });
+                        }
+                    }
+                    ui.separator();
+                    ui.label("Вопросов по дням:");
+                    if self.usage_days.is_empty() {
+                        ui.label("нет данных — включите [stats] enabled = true в shark.toml");
+                    } else {
+                        let days = &self.usage_days[self.usage_days.len().saturating_sub(USAGE_CHART_DAYS)..];
+                        draw_usage_chart(ui, days);
+                        if let Some(today) = days.last() {
+                            ui.label(format!(
+                                "{}: {} ответов, среднее время {:.1} мс, макс. {:.1} мс",
+                                today.date,
+                                today.total.count,
+                                today.total.mean_latency_ms(),
+                                today.total.max_latency_ms
+                            ));
+                        }
+                    }
+                    ui.separator();
                     if ui.button("Сбросить метрики").clicked() {
                         self.question_count = 0;
                         self.total_response_time = 0.0;
@@ -463,12 +928,301 @@ impl App for SharkApp {
                         self.model_responses = 0;
                     }
                 }
+
+                Tab::Backtest => {
+                    ui.horizontal(|ui| {
+                        ui.label("CSV:");
+                        ui.text_edit_singleline(&mut self.backtest_path);
+                        egui::ComboBox::from_id_salt("backtest_files").selected_text("выбрать…").show_ui(ui, |ui| {
+                            for file in csv_files() {
+                                if ui.selectable_label(self.backtest_path == file, &file).clicked() {
+                                    self.backtest_path = file;
+                                }
+                            }
+                        });
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Стратегия:");
+                        let before = self.backtest_strategy;
+                        egui::ComboBox::from_id_salt("backtest_strategy")
+                            .selected_text(strategy_name(self.backtest_strategy))
+                            .show_ui(ui, |ui| {
+                                for i in 0..STRATEGY_COUNT {
+                                    ui.selectable_value(&mut self.backtest_strategy, i, strategy_name(i));
+                                }
+                            });
+                        if self.backtest_strategy != before {
+                            self.backtest_params = backtest_defaults(self.backtest_strategy);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        for (name, value) in self.backtest_params.iter_mut() {
+                            ui.label(*name);
+                            ui.add(egui::DragValue::new(value).speed(0.5));
+                        }
+                        ui.label("комиссия:");
+                        ui.add(egui::DragValue::new(&mut self.backtest_commission).speed(0.0001).range(0.0..=0.1));
+                    });
+                    let running = self.backtest_job.is_some();
+                    if ui.add_enabled(!running, egui::Button::new("Запустить")).clicked() {
+                        self.start_backtest(ctx);
+                    }
+
+                    if let Some(job) = self.backtest_job.clone() {
+                        if let Ok(mut job) = job.lock() {
+                            ui.add(egui::ProgressBar::new(job.progress).text(job.stage.clone()));
+                            if let Some(result) = job.result.take() {
+                                self.backtest_outcome = Some(result);
+                            }
+                        }
+                        if self.backtest_outcome.is_some() {
+                            self.backtest_job = None;
+                        }
+                    }
+
+                    ui.separator();
+                    match &self.backtest_outcome {
+                        Some(Ok(outcome)) => {
+                            egui::Grid::new("backtest_metrics").striped(true).show(ui, |ui| {
+                                for (label, value) in &outcome.metrics {
+                                    ui.label(*label);
+                                    ui.label(value);
+                                    ui.end_row();
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.colored_label(egui::Color32::LIGHT_GREEN, "— капитал");
+                                ui.colored_label(egui::Color32::from_rgb(230, 90, 90), "— просадка");
+                            });
+                            draw_line_chart(ui, &outcome.equity, egui::Color32::LIGHT_GREEN, 200.0);
+                            draw_line_chart(ui, &outcome.drawdown, egui::Color32::from_rgb(230, 90, 90), 100.0);
+                        }
+                        Some(Err(e)) => {
+                            ui.colored_label(egui::Color32::from_rgb(230, 90, 90), format!("⚠️ {}", e));
+                        }
+                        None => {}
+                    }
+                }
             }
         });
     }
 
 }
 
+/// Days shown in the usage chart of the Metrics tab.
+const USAGE_CHART_DAYS: usize = 14;
+
+/// Bar chart of answers per day, split by source (knowledge, solvers, model).
+fn draw_usage_chart(ui: &mut egui::Ui, days: &[DaySummary]) {
+    let size = egui::vec2(ui.available_width(), 160.0);
+    let (response, painter) = ui.allocate_painter(size, egui::Sense::hover());
+    let rect = response.rect;
+    painter.rect_stroke(rect, 2.0, egui::Stroke::new(1.0, egui::Color32::DARK_GRAY));
+    let max = days.iter().map(|d| d.total.count).max().unwrap_or(1).max(1) as f32;
+    let width = rect.width() / days.len().max(1) as f32;
+    let colors = [
+        ("knowledge", egui::Color32::LIGHT_GREEN),
+        ("reasoner", egui::Color32::LIGHT_BLUE),
+        ("model", egui::Color32::from_rgb(255, 160, 60)),
+    ];
+    for (i, day) in days.iter().enumerate() {
+        let left = rect.left() + width * i as f32 + 2.0;
+        let mut bottom = rect.bottom() - 14.0;
+        for (source, color) in colors {
+            let h = (rect.height() - 30.0) * day.sources.get(source).copied().unwrap_or(0) as f32 / max;
+            painter.rect_filled(egui::Rect::from_min_max(egui::pos2(left, bottom - h), egui::pos2(left + width - 4.0, bottom)), 0.0, color);
+            bottom -= h;
+        }
+        let label = day.date.get(5..).unwrap_or(&day.date);
+        painter.text(egui::pos2(left, rect.bottom() - 2.0), egui::Align2::LEFT_BOTTOM, label, egui::FontId::monospace(10.0), egui::Color32::GRAY);
+    }
+    painter.text(rect.left_top() + egui::vec2(4.0, 2.0), egui::Align2::LEFT_TOP, format!("{}", max), egui::FontId::monospace(11.0), egui::Color32::GRAY);
+}
+
+/// Line chart of per-step training loss and per-epoch validation loss.
+fn draw_loss_chart(ui: &mut egui::Ui, loss: &[(f32, f32)], val: &[(f32, f32)]) {
+    let size = egui::vec2(ui.available_width(), 260.0);
+    let (response, painter) = ui.allocate_painter(size, egui::Sense::hover());
+    let rect = response.rect;
+    painter.rect_stroke(rect, 2.0, egui::Stroke::new(1.0, egui::Color32::DARK_GRAY));
+    let all = || loss.iter().chain(val.iter());
+    let (x_max, y_min, y_max) = all().fold((1.0f32, f32::MAX, f32::MIN), |(x, lo, hi), &(sx, sy)| {
+        (x.max(sx), lo.min(sy), hi.max(sy))
+    });
+    let y_span = (y_max - y_min).max(1e-6);
+    let to_screen = |&(x, y): &(f32, f32)| {
+        egui::pos2(
+            rect.left() + rect.width() * x / x_max,
+            rect.bottom() - rect.height() * (y - y_min) / y_span,
+        )
+    };
+    let line = |points: &[(f32, f32)], color| {
+        if points.len() > 1 {
+            painter.add(egui::Shape::line(points.iter().map(to_screen).collect(), egui::Stroke::new(1.5, color)));
+        }
+    };
+    line(loss, egui::Color32::LIGHT_BLUE);
+    line(val, egui::Color32::from_rgb(255, 160, 60));
+    painter.text(rect.left_top() + egui::vec2(4.0, 2.0), egui::Align2::LEFT_TOP, format!("{:.3}", y_max), egui::FontId::monospace(11.0), egui::Color32::GRAY);
+    painter.text(rect.left_bottom() + egui::vec2(4.0, -2.0), egui::Align2::LEFT_BOTTOM, format!("{:.3}", y_min), egui::FontId::monospace(11.0), egui::Color32::GRAY);
+}
+
+/// Line chart of one series scaled to its own range, `height` points tall.
+fn draw_line_chart(ui: &mut egui::Ui, points: &[(f32, f32)], color: egui::Color32, height: f32) {
+    let size = egui::vec2(ui.available_width(), height);
+    let (response, painter) = ui.allocate_painter(size, egui::Sense::hover());
+    let rect = response.rect;
+    painter.rect_stroke(rect, 2.0, egui::Stroke::new(1.0, egui::Color32::DARK_GRAY));
+    let x_max = points.last().map_or(1.0, |p| p.0.max(1.0));
+    let (y_min, y_max) = points.iter().fold((f32::MAX, f32::MIN), |(lo, hi), &(_, y)| (lo.min(y), hi.max(y)));
+    let y_span = (y_max - y_min).max(1e-6);
+    if points.len() > 1 {
+        let line = points
+            .iter()
+            .map(|&(x, y)| egui::pos2(rect.left() + rect.width() * x / x_max, rect.bottom() - rect.height() * (y - y_min) / y_span))
+            .collect();
+        painter.add(egui::Shape::line(line, egui::Stroke::new(1.5, color)));
+        painter.text(rect.left_top() + egui::vec2(4.0, 2.0), egui::Align2::LEFT_TOP, format!("{:.3}", y_max), egui::FontId::monospace(11.0), egui::Color32::GRAY);
+        painter.text(rect.left_bottom() + egui::vec2(4.0, -2.0), egui::Align2::LEFT_BOTTOM, format!("{:.3}", y_min), egui::FontId::monospace(11.0), egui::Color32::GRAY);
+    }
+}
+
+/// CSV files in the working directory and `data/`, for the Backtest tab's picker.
+fn csv_files() -> Vec<String> {
+    let mut files: Vec<String> = [".", "data"]
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e.eq_ignore_ascii_case("csv")))
+        .map(|path| path.strip_prefix(".").unwrap_or(&path).display().to_string())
+        .collect();
+    files.sort();
+    files
+}
+
+/// Small line chart of the last answer's sparkline.
+fn draw_sparkline(ui: &mut egui::Ui, points: &[(f32, f32)]) {
+    let size = egui::vec2(ui.available_width().min(400.0), 60.0);
+    let (response, painter) = ui.allocate_painter(size, egui::Sense::hover());
+    let rect = response.rect.shrink(4.0);
+    let x_max = points.last().map_or(1.0, |p| p.0.max(1.0));
+    let y_max = (tools::SPARK_CHARS.len() - 1) as f32;
+    let line = points
+        .iter()
+        .map(|&(x, y)| egui::pos2(rect.left() + rect.width() * x / x_max, rect.bottom() - rect.height() * y / y_max))
+        .collect();
+    painter.add(egui::Shape::line(line, egui::Stroke::new(1.5, egui::Color32::LIGHT_GREEN)));
+}
+
+/// One scientific cycle as result lines for the Research tab.
+#[cfg(feature = "science")]
+fn research_cycle() -> Vec<String> {
+    scientist::run_scientific_cycle()
+        .into_iter()
+        .map(|r| format!("{} | mse={:.6} | accepted={}", r.name, r.mse, r.accepted))
+        .collect()
+}
+
+#[cfg(not(feature = "science"))]
+fn research_cycle() -> Vec<String> {
+    vec!["⚠️ собрано без функции `science`".to_string()]
+}
+
+/// Reference strategies offered by the Backtest tab.
+#[cfg(feature = "trading")]
+const STRATEGY_COUNT: usize = predict::quant_api::STRATEGIES.len();
+#[cfg(not(feature = "trading"))]
+const STRATEGY_COUNT: usize = 0;
+
+#[cfg(feature = "trading")]
+fn strategy_name(i: usize) -> &'static str {
+    predict::quant_api::STRATEGIES.get(i).map_or("", |(name, _)| name)
+}
+
+#[cfg(not(feature = "trading"))]
+fn strategy_name(_i: usize) -> &'static str {
+    ""
+}
+
+/// Default parameters of the `i`-th reference strategy.
+#[cfg(feature = "trading")]
+fn backtest_defaults(i: usize) -> Vec<(&'static str, f64)> {
+    predict::quant_api::STRATEGIES.get(i).map(|(_, params)| params.to_vec()).unwrap_or_default()
+}
+
+#[cfg(not(feature = "trading"))]
+fn backtest_defaults(_i: usize) -> Vec<(&'static str, f64)> {
+    Vec::new()
+}
+
+/// Load `path`, run the `strategy`-th reference strategy and collect the
+/// metrics and curves with engine seed `seed`, reporting each stage to `job`.
+#[cfg(feature = "trading")]
+fn run_backtest(
+    path: &str,
+    strategy: usize,
+    params: &[(&'static str, f64)],
+    commission: f64,
+    seed: u64,
+    job: &Mutex<BacktestJob>,
+    ctx: &egui::Context,
+) -> Result<BacktestOutcome, String> {
+    use predict::backtest;
+    let stage = |progress: f32, stage: &str| {
+        if let Ok(mut job) = job.lock() {
+            job.progress = progress;
+            job.stage = stage.to_string();
+        }
+        ctx.request_repaint();
+    };
+    stage(0.1, "чтение файла");
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    stage(0.3, "разбор баров");
+    let bars = backtest::import::any(&text).map_err(|e| format!("{}: {}", path, e))?;
+    stage(0.5, "бэктест");
+    let params: serde_json::Map<String, serde_json::Value> = params.iter().map(|(k, v)| (k.to_string(), serde_json::json!(v))).collect();
+    let strategy = predict::quant_api::backtest_strategy(strategy_name(strategy), &params)?;
+    let cfg = backtest::EngineConfig { commission_rate: commission, slippage: 0.0, seed };
+    let report = backtest::run_strategy(strategy.as_ref(), &bars, cfg).map_err(str::to_string)?;
+    stage(0.9, "метрики");
+    let equity: Vec<(f32, f32)> = report.equity.iter().enumerate().map(|(i, (_, e))| (i as f32, *e as f32)).collect();
+    let mut peak = f32::MIN;
+    let drawdown = equity
+        .iter()
+        .map(|&(i, e)| {
+            peak = peak.max(e);
+            (i, e - peak)
+        })
+        .collect();
+    let metrics = vec![
+        ("Баров", bars.len().to_string()),
+        ("Сделок", report.trades.len().to_string()),
+        ("Прибыльных", format!("{} ({:.0}%)", report.wins, report.win_rate() * 100.0)),
+        ("PnL до издержек", format!("{:.4}", report.gross_pnl)),
+        ("Комиссии", format!("{:.4}", report.commissions)),
+        ("Итог", format!("{:.4}", report.net_pnl)),
+        ("Макс. просадка", format!("{:.4}", report.max_drawdown)),
+        ("Sharpe (на бар)", format!("{:.3}", report.sharpe())),
+        ("Хеш прогона", report.run_hash.clone()),
+    ];
+    Ok(BacktestOutcome { metrics, equity, drawdown })
+}
+
+#[cfg(not(feature = "trading"))]
+fn run_backtest(
+    _path: &str,
+    _strategy: usize,
+    _params: &[(&'static str, f64)],
+    _commission: f64,
+    _seed: u64,
+    _job: &Mutex<BacktestJob>,
+    _ctx: &egui::Context,
+) -> Result<BacktestOutcome, String> {
+    Err("собрано без функции `trading`".to_string())
+}
+
 fn main() -> eframe::Result<()> {
     let native_options = eframe::NativeOptions::default();
     eframe::run_native(
@@ -3,12 +3,19 @@ use predict::AI;
 fn main() {
     // quick test runner that avoids the full chat.rs startup (self-repair, scans, etc.)
     // so we can directly inspect ai.chat behaviour on a simple prompt.
-    let mut ai = AI::new("weights/model_int4.bin");
+    let mut ai = match predict::Config::discover().and_then(|config| AI::new(&config)) {
+        Ok(ai) => ai,
+        Err(e) => {
+            eprintln!("failed to load AI: {}", e);
+            std::process::exit(1);
+        }
+    };
     let prompt = "привет";
-    let raw = ai.chat(prompt);
+    let raw = ai.chat_text(prompt);
     println!("> {}", prompt);
     // also print raw debug to stderr so GUI-style logs are comparable
     eprintln!("[test_chat] raw reply: {:?}", raw);
-    let readable = predict::decode::decode_raw(&raw);
+    // knowledge answers are Cyrillic: keep every script
+    let readable = predict::decode_with(&raw, &predict::DecodeOptions::unicode());
     println!("{}", readable);
 }
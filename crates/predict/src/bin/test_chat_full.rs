@@ -2,15 +2,26 @@ use predict::{AI, train, knowledge_env};
 
 fn main() {
     // Lightweight startup: merge knowledge sources and load knowledge pack without running self_repair
-    if let Err(e) = knowledge_env::merge_knowledge_sources() {
+    let data = std::path::Path::new("crates/predict/data");
+    if let Err(e) = knowledge_env::merge_knowledge_sources(&data.join("knowledge.csv"), &data.join("knowledge")) {
         eprintln!("[test_chat_full] merge_knowledge_sources failed: {}", e);
     }
-    train::load_knowledge_pack();
+    if let Err(e) = train::load_knowledge_pack(data) {
+        eprintln!("[test_chat_full] load_knowledge_pack failed: {}", e);
+    }
     // train_from_csv prints dataset entries and '[train] dataset ready.'
-    train::train_from_csv("crates/predict/data/knowledge.csv");
+    if let Err(e) = train::train_from_csv("crates/predict/data/knowledge.csv") {
+        eprintln!("[test_chat_full] train_from_csv failed: {}", e);
+    }
 
     // Create AI and run a single-shot prompt
-    let mut ai = AI::new("weights/model_int4.bin");
+    let mut ai = match predict::Config::discover().and_then(|config| AI::new(&config)) {
+        Ok(ai) => ai,
+        Err(e) => {
+            eprintln!("failed to load AI: {}", e);
+            std::process::exit(1);
+        }
+    };
         let prompt = "почему буквы A-Z важны?";
     println!("> {}", prompt);
 
@@ -20,7 +31,7 @@ fn main() {
         return;
     }
 
-    let raw = ai.chat(prompt);
+    let raw = ai.chat_text(prompt);
     eprintln!("[test_chat_full] raw reply: {:?}", raw);
     println!("🧠 Ответ: {}", raw);
 }
@@ -0,0 +1,201 @@
+#![forbid(unsafe_code)]
+
+//! `AIBuilder`: assemble an `AI` from injected parts, loading only what is
+//! not given from the configuration.
+//!
+//! ```no_run
+//! use predict::{memory::Memory, model::Model, AI};
+//!
+//! let ai = AI::builder().model(Model::zeroed()).memory(Memory::in_memory()).no_ngram().build()?;
+//! # Ok::<(), predict::SharkError>(())
+//! ```
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::config::{Config, SamplerConfig};
+use crate::core::RngSource;
+use crate::error::{Result, SharkError};
+use crate::memory::Memory;
+use crate::model::Model;
+use crate::ngram::NgramModel;
+use crate::model_registry::ModelRegistry;
+use crate::moderation::Moderator;
+use crate::pipeline::Pipeline;
+use crate::plugins::Plugins;
+use crate::replay::ReplayLog;
+use crate::tools::ToolRegistry;
+use crate::AI;
+
+/// Builder returned by `AI::builder`.
+#[derive(Default)]
+pub struct AIBuilder {
+    config: Config,
+    model: Option<Model>,
+    memory: Option<Memory>,
+    knowledge: Option<HashMap<String, String>>,
+    ngram: Option<Option<NgramModel>>,
+    pipeline: Option<Pipeline>,
+    tools: Option<ToolRegistry>,
+    moderator: Option<Moderator>,
+    eager_knowledge: bool,
+}
+
+impl AIBuilder {
+    /// Configuration used for the parts that are not injected (default: `Config::default()`).
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Use this model instead of loading `model.path`.
+    pub fn model(mut self, model: Model) -> Self {
+        self.model = Some(model);
+        self
+    }
+
+    /// Use this dialog memory instead of loading `data.memory`.
+    pub fn memory(mut self, memory: Memory) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    /// Use this question → answer map instead of reading knowledge.csv.
+    /// Questions are expected in lowercase (as `load_knowledge_for_reasoning` stores them).
+    pub fn knowledge(mut self, knowledge: HashMap<String, String>) -> Self {
+        self.knowledge = Some(knowledge);
+        self
+    }
+
+    /// Use this n-gram fallback generator instead of loading `model.ngram_path`.
+    pub fn ngram(mut self, ngram: NgramModel) -> Self {
+        self.ngram = Some(Some(ngram));
+        self
+    }
+
+    /// Generate with the model only, without looking for an n-gram file.
+    pub fn no_ngram(mut self) -> Self {
+        self.ngram = Some(None);
+        self
+    }
+
+    /// Use this answer pipeline instead of building `pipeline.stages`.
+    pub fn pipeline(mut self, pipeline: Pipeline) -> Self {
+        self.pipeline = Some(pipeline);
+        self
+    }
+
+    /// Use these tools instead of `ToolRegistry::standard`.
+    pub fn tools(mut self, tools: ToolRegistry) -> Self {
+        self.tools = Some(tools);
+        self
+    }
+
+    /// Use this moderator instead of the deny lists of `config.moderation`.
+    pub fn moderator(mut self, moderator: Moderator) -> Self {
+        self.moderator = Some(moderator);
+        self
+    }
+
+    /// Sampler settings (seed, answer lengths); overrides `config.sampler`.
+    pub fn sampler(mut self, sampler: SamplerConfig) -> Self {
+        self.config.sampler = sampler;
+        self
+    }
+
+    /// Read knowledge.csv in `build` instead of on first use (ignored when
+    /// `knowledge` was given).
+    pub fn eager_knowledge(mut self, eager: bool) -> Self {
+        self.eager_knowledge = eager;
+        self
+    }
+
+    /// Load whatever was not injected and assemble the `AI`. The sampler
+    /// settings are applied to the model, injected or not.
+    pub fn build(self) -> Result<AI> {
+        let config = self.config;
+        let mut model = match self.model {
+            Some(model) => model,
+            None => Model::load_or_zeroed(&config.model.path)?,
+        };
+        model.seed = config.rng_source().map_or(config.sampler.seed, |rng| rng.stream_seed(RngSource::GENERATION));
+        model.max_chars = config.sampler.max_chars;
+        model.accumulation = config.model.accumulation;
+        let memory = match self.memory {
+            Some(memory) => memory,
+            None => Memory::load(&config.data.memory)?,
+        };
+        let ngram = match self.ngram {
+            Some(ngram) => ngram,
+            None => {
+                let path = std::path::Path::new(&config.model.ngram_path);
+                match NgramModel::load(path) {
+                    Ok(m) => Some(m),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                    Err(e) => return Err(SharkError::io(path, e)),
+                }
+            }
+        };
+        let mut pipeline = match self.pipeline {
+            Some(pipeline) => pipeline,
+            None => Pipeline::from_stages(&config.pipeline.stages)?
+                .ranked(config.pipeline.rank)
+                .min_confidence(config.pipeline.min_confidence)
+                .cached(config.pipeline.cache_size, (config.pipeline.cache_ttl_secs > 0).then(|| Duration::from_secs(config.pipeline.cache_ttl_secs))),
+        };
+        let moderator = match self.moderator {
+            Some(moderator) => moderator,
+            None => Moderator::from_config(&config.moderation)?,
+        };
+        let mut tools = self.tools.unwrap_or_else(ToolRegistry::standard);
+        Plugins::load(&config.plugins.manifest)?.install(&mut tools, &mut pipeline);
+        let mut ai = AI::from_parts(model, memory);
+        ai.ngram = ngram;
+        ai.config = config;
+        ai.pipeline = std::sync::Arc::new(pipeline);
+        ai.tools = std::sync::Arc::new(tools);
+        ai.moderator = std::sync::Arc::new(moderator);
+        ai.models = ModelRegistry::from_config(&ai.config);
+        if ai.config.replay.enabled {
+            ai.replay = Some(ReplayLog::new(ai.config.replay_log_path()));
+        }
+        if !ai.config.model.draft.is_empty() {
+            let draft = ai.config.model.draft.clone();
+            ai.models.load(&draft)?;
+        }
+        match self.knowledge {
+            Some(knowledge) => {
+                ai.knowledge = knowledge;
+                ai.knowledge_loaded = true;
+            }
+            None if self.eager_knowledge => {
+                ai.ensure_knowledge()?;
+            }
+            None => {}
+        }
+        Ok(ai)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn injected_parts_need_no_files() {
+        let knowledge = HashMap::from([("акула".to_string(), "хрящевая рыба".to_string())]);
+        let sampler = SamplerConfig { seed: 7, ..SamplerConfig::default() };
+        let mut config = Config::default();
+        config.data.dir = "/nonexistent".into();
+        let builder = AI::builder().config(config).model(Model::zeroed()).memory(Memory::in_memory());
+        let ai = builder.knowledge(knowledge).no_ngram().sampler(sampler).build();
+        assert!(ai.is_ok());
+        if let Ok(mut ai) = ai {
+            assert_eq!(ai.model.seed, 7);
+            let reply = ai.chat("что такое акула?");
+            assert!(reply.text.contains("хрящевая рыба"));
+            assert_eq!((reply.source, reply.confidence), (crate::Source::Knowledge, 1.0));
+            assert_eq!(ai.memory.dialogs().len(), 1);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+#![forbid(unsafe_code)]
+
+//! Response cache of the pipeline (`Pipeline::cached`, `[pipeline] cache_size`).
+//!
+//! Answers of deterministic stages (`CACHEABLE_STAGES`: stored knowledge and
+//! the solvers) are kept under the normalized prompt, so a repeated question
+//! on the server or in `evaluate_problems` skips the stages. The cache is a
+//! least-recently-used map with an optional time to live; it is cleared when
+//! the knowledge base changes.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::response::ChatResponse;
+
+/// Stages whose answers depend only on the prompt and the knowledge base.
+pub const CACHEABLE_STAGES: [&str; 4] = ["knowledge", "arithmetic", "linear", "reasoner"];
+
+/// Cache key of a prompt: lowercase, whitespace collapsed, trailing `?!.` removed.
+pub fn normalize(prompt: &str) -> String {
+    let words: Vec<String> = prompt.split_whitespace().map(str::to_lowercase).collect();
+    words.join(" ").trim_end_matches(['?', '!', '.', ' ']).to_string()
+}
+
+/// Hit counters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct CacheStats {
+    /// lookups answered from the cache
+    pub hits: u64,
+    /// lookups that ran the stages
+    pub misses: u64,
+    /// entries dropped for space or age
+    pub evictions: u64,
+    /// entries held now
+    pub len: usize,
+}
+
+impl CacheStats {
+    /// Share of lookups answered from the cache (0 before any lookup).
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 { 0.0 } else { self.hits as f64 / total as f64 }
+    }
+}
+
+struct Entry {
+    response: ChatResponse,
+    stored: Instant,
+    used: u64,
+}
+
+/// LRU map from normalized prompts to responses.
+pub struct ResponseCache {
+    capacity: usize,
+    ttl: Option<Duration>,
+    entries: HashMap<String, Entry>,
+    clock: u64,
+    stats: CacheStats,
+}
+
+impl ResponseCache {
+    /// Cache of at most `capacity` answers, each valid for `ttl` (`None`: until evicted).
+    pub fn new(capacity: usize, ttl: Option<Duration>) -> Self {
+        Self { capacity, ttl, entries: HashMap::new(), clock: 0, stats: CacheStats::default() }
+    }
+
+    /// The stored answer to `prompt`, if present and fresh; counts a hit or a miss.
+    pub fn get(&mut self, prompt: &str) -> Option<ChatResponse> {
+        let key = normalize(prompt);
+        self.clock += 1;
+        let expired = match self.entries.get(&key) {
+            Some(entry) => self.ttl.is_some_and(|ttl| entry.stored.elapsed() > ttl),
+            None => {
+                self.stats.misses += 1;
+                return None;
+            }
+        };
+        if expired {
+            self.entries.remove(&key);
+            self.stats.evictions += 1;
+            self.stats.misses += 1;
+            return None;
+        }
+        let entry = self.entries.get_mut(&key)?;
+        entry.used = self.clock;
+        self.stats.hits += 1;
+        Some(entry.response.clone())
+    }
+
+    /// Store the answer to `prompt`, evicting the least recently used entry when full.
+    pub fn insert(&mut self, prompt: &str, response: ChatResponse) {
+        if self.capacity == 0 {
+            return;
+        }
+        let key = normalize(prompt);
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            let oldest = self.entries.iter().min_by_key(|(_, e)| e.used).map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+                self.stats.evictions += 1;
+            }
+        }
+        self.clock += 1;
+        self.entries.insert(key, Entry { response, stored: Instant::now(), used: self.clock });
+    }
+
+    /// Drop every entry (counters are kept).
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Counters and the current size.
+    pub fn stats(&self) -> CacheStats {
+        CacheStats { len: self.entries.len(), ..self.stats }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::Source;
+
+    #[test]
+    fn least_recently_used_and_expired_entries_are_dropped() {
+        let answer = |text: &str| ChatResponse::new(text.to_string(), Source::Knowledge, 1.0, Instant::now());
+        let mut cache = ResponseCache::new(2, None);
+        cache.insert("Что такое  акула?", answer("рыба"));
+        cache.insert("скат", answer("хрящевая рыба"));
+        assert_eq!(cache.get("что такое акула").map(|r| r.text), Some("рыба".to_string()));
+        cache.insert("кит", answer("млекопитающее"));
+        assert!(cache.get("скат").is_none() && cache.get("акула").is_none() && cache.get("Что такое акула").is_some());
+        assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 2, evictions: 1, len: 2 });
+
+        let mut expiring = ResponseCache::new(2, Some(Duration::ZERO));
+        expiring.insert("кит", answer("млекопитающее"));
+        std::thread::sleep(Duration::from_millis(2));
+        assert!(expiring.get("кит").is_none());
+        assert_eq!((expiring.stats().evictions, expiring.stats().len), (1, 0));
+    }
+}
@@ -0,0 +1,52 @@
+//! Confidence calibration: turns the raw signal each producer has (model
+//! entropy, n-gram perplexity, solver verification) into a 0..=1 confidence
+//! on a common scale, so one threshold (`pipeline.min_confidence`) can decide
+//! when to answer "I don't know".
+
+/// Confidence of a solver result that was checked (e.g. substituted back).
+pub const VERIFIED: f32 = 1.0;
+
+/// Confidence of a solver result that could not be checked.
+pub const UNVERIFIED: f32 = 0.5;
+
+/// Confidence of a solver result given whether it was verified.
+pub fn from_verification(verified: bool) -> f32 {
+    if verified { VERIFIED } else { UNVERIFIED }
+}
+
+/// Shannon entropy (nats) of a probability distribution.
+pub fn entropy(probs: &[f32]) -> f32 {
+    -probs.iter().filter(|&&p| p > 0.0).map(|&p| p * p.ln()).sum::<f32>()
+}
+
+/// `1 - H / ln(vocab)`: 0 for a uniform (untrained) distribution, 1 for a certain one.
+pub fn from_entropy(mean_entropy: f32, vocab: usize) -> f32 {
+    if vocab < 2 {
+        return 1.0;
+    }
+    (1.0 - mean_entropy / (vocab as f32).ln()).clamp(0.0, 1.0)
+}
+
+/// `1 - ln(perplexity) / ln(vocab)`: perplexity is `exp` of the mean entropy,
+/// so this is `from_entropy` for a text scored by a language model.
+pub fn from_perplexity(perplexity: f64, vocab: usize) -> f32 {
+    if !perplexity.is_finite() || perplexity <= 0.0 {
+        return 0.0;
+    }
+    from_entropy(perplexity.ln() as f32, vocab)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uniform_is_zero_and_certain_is_one() {
+        let uniform = [0.25f32; 4];
+        assert!(from_entropy(entropy(&uniform), 4).abs() < 1e-6);
+        assert_eq!(from_entropy(entropy(&[1.0, 0.0, 0.0]), 3), 1.0);
+        assert!((from_perplexity(4.0, 4)).abs() < 1e-6);
+        assert_eq!(from_perplexity(f64::INFINITY, 4), 0.0);
+        assert_eq!(from_verification(false), UNVERIFIED);
+    }
+}
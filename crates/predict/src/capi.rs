@@ -0,0 +1,136 @@
+//! C ABI for embedding the chat core in non-Rust applications (feature `capi`).
+//!
+//! The header is `crates/predict/include/shark.h`, generated by cbindgen from
+//! this module (see `cbindgen.toml`). Every string crossing the boundary is
+//! NUL-terminated UTF-8; strings returned by the library are owned by the
+//! caller and released with `shark_free_string`.
+//!
+//! ```c
+//! SharkModel *shark = shark_model_load("shark.toml");
+//! char *answer = shark_chat(shark, "2 + 2");
+//! puts(answer);
+//! shark_free_string(answer);
+//! shark_model_free(shark);
+//! ```
+
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+use crate::{Config, AI};
+
+/// Opaque handle to a loaded `AI` (model, memory, knowledge, pipeline).
+pub struct SharkModel {
+    ai: AI,
+}
+
+/// Borrow a C string as UTF-8 (`None` for NULL or invalid UTF-8).
+///
+/// # Safety
+/// `s` must be NULL or point to a NUL-terminated string valid for `'a`.
+unsafe fn utf8<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    CStr::from_ptr(s).to_str().ok()
+}
+
+/// Load the AI described by the `shark.toml` at `config_path`, or the
+/// discovered configuration (`SHARK_CONFIG`, `./shark.toml`, defaults) when
+/// `config_path` is NULL. Returns NULL on failure, with the error on stderr.
+/// Release the handle with `shark_model_free`.
+///
+/// # Safety
+/// `config_path` must be NULL or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn shark_model_load(config_path: *const c_char) -> *mut SharkModel {
+    let config = if config_path.is_null() {
+        Config::discover()
+    } else {
+        match utf8(config_path) {
+            Some(path) => Config::load(std::path::Path::new(path)),
+            None => {
+                eprintln!("shark_model_load: config path is not valid UTF-8");
+                return ptr::null_mut();
+            }
+        }
+    };
+    match config.and_then(|config| AI::new(&config)) {
+        Ok(ai) => Box::into_raw(Box::new(SharkModel { ai })),
+        Err(e) => {
+            eprintln!("shark_model_load: {}", e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Answer `prompt` through the answer pipeline (the dialog is stored in the
+/// configured memory, as in the chat CLI). Returns a new string to release
+/// with `shark_free_string`, or NULL if `model` or `prompt` is NULL or the
+/// prompt is not UTF-8.
+///
+/// # Safety
+/// `model` must be NULL or a handle from `shark_model_load` that was not
+/// freed and is not used concurrently; `prompt` must be NULL or a
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn shark_chat(model: *mut SharkModel, prompt: *const c_char) -> *mut c_char {
+    let (Some(model), Some(prompt)) = (model.as_mut(), utf8(prompt)) else {
+        return ptr::null_mut();
+    };
+    let text = model.ai.chat_text(prompt).replace('\0', "");
+    CString::new(text).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Release a string returned by `shark_chat` (NULL is ignored).
+///
+/// # Safety
+/// `s` must be NULL or a string returned by this library, released once.
+#[no_mangle]
+pub unsafe extern "C" fn shark_free_string(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Release a handle returned by `shark_model_load` (NULL is ignored).
+///
+/// # Safety
+/// `model` must be NULL or a handle from `shark_model_load`, released once.
+#[no_mangle]
+pub unsafe extern "C" fn shark_model_free(model: *mut SharkModel) {
+    if !model.is_null() {
+        drop(Box::from_raw(model));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_chat_and_free_through_the_c_abi() {
+        let dir = std::env::temp_dir().join(format!("shark_capi_{}", std::process::id()));
+        let _ = std::fs::create_dir_all(&dir);
+        let toml = format!(
+            "[model]\npath = \"{0}/none.bin\"\nngram_path = \"{0}/none.ngram\"\n\n[data]\ndir = \"{1}/data\"\nmemory = \"{0}/memory.db\"\n",
+            dir.display(),
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let config_path = dir.join("shark.toml");
+        let _ = std::fs::write(&config_path, toml);
+        let (Ok(path), Ok(prompt)) = (CString::new(config_path.to_string_lossy().as_bytes()), CString::new("2 + 2")) else {
+            return;
+        };
+
+        unsafe {
+            let model = shark_model_load(path.as_ptr());
+            assert!(!model.is_null());
+            let answer = shark_chat(model, prompt.as_ptr());
+            assert_eq!(utf8(answer), Some("4"));
+            assert!(shark_chat(model, ptr::null()).is_null());
+            shark_free_string(answer);
+            shark_model_free(model);
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,193 @@
+#![forbid(unsafe_code)]
+
+//! k-means clustering of embeddings and word-frequency vectors.
+//!
+//! `kmeans` seeds its centroids with k-means++ (every next centroid is a
+//! point drawn with probability proportional to its squared distance to the
+//! nearest centroid so far) from a seeded ChaCha8 stream, then alternates
+//! assigning the points to their nearest centroid and moving the centroids
+//! to the means (Lloyd) until no point changes cluster. `word_vectors` turns
+//! texts into unit-length word-count vectors, and `topics` clusters texts by
+//! any vectors and labels each cluster with its most frequent words.
+//! `AI::knowledge_topics` and `AI::memory_topics` apply it to the knowledge
+//! base and the dialog memory (the `topics` stage, "покажи темы").
+
+use std::collections::BTreeMap;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Result of `kmeans`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clustering {
+    /// one centroid per cluster
+    pub centroids: Vec<Vec<f32>>,
+    /// cluster of every point, in input order
+    pub assignments: Vec<usize>,
+    /// sum of squared distances of the points to their centroids
+    pub inertia: f32,
+    /// assignment rounds run
+    pub iterations: usize,
+}
+
+/// Squared Euclidean distance over the common length.
+fn distance2(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+/// Index and squared distance of the centroid nearest to `point`.
+fn nearest(centroids: &[Vec<f32>], point: &[f32]) -> (usize, f32) {
+    centroids.iter().map(|c| distance2(c, point)).enumerate().fold((0, f32::INFINITY), |best, (i, d)| if d < best.1 { (i, d) } else { best })
+}
+
+/// Cluster `points` into `k` groups (at most one per point), running at most
+/// `max_iterations` assignment rounds; deterministic for a given `seed`.
+/// Vectors of different lengths are compared over their common length. A
+/// cluster that loses all its points keeps its centroid. None for no points
+/// or `k = 0`.
+pub fn kmeans(points: &[Vec<f32>], k: usize, seed: u64, max_iterations: usize) -> Option<Clustering> {
+    let k = k.min(points.len());
+    if k == 0 {
+        return None;
+    }
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut centroids: Vec<Vec<f32>> = vec![points.get(rng.gen_range(0..points.len()))?.clone()];
+    while centroids.len() < k {
+        let weights: Vec<f32> = points.iter().map(|p| nearest(&centroids, p).1).collect();
+        let total: f32 = weights.iter().sum();
+        // all points on the centroids already: any point will do
+        let mut r = if total > 0.0 { rng.gen::<f32>() * total } else { -1.0 };
+        let pick = weights.iter().position(|w| {
+            r -= w;
+            r < 0.0
+        });
+        let pick = pick.unwrap_or_else(|| rng.gen_range(0..points.len()));
+        centroids.push(points.get(pick)?.clone());
+    }
+
+    let dim = points.iter().map(Vec::len).max().unwrap_or(0);
+    let mut assignments = vec![usize::MAX; points.len()];
+    let mut iterations = 0;
+    while iterations < max_iterations {
+        iterations += 1;
+        let mut moved = false;
+        for (slot, point) in assignments.iter_mut().zip(points) {
+            let cluster = nearest(&centroids, point).0;
+            moved |= *slot != cluster;
+            *slot = cluster;
+        }
+        if !moved {
+            break;
+        }
+        for (cluster, centroid) in centroids.iter_mut().enumerate() {
+            let mut sum = vec![0.0_f32; dim];
+            let mut count = 0;
+            for point in points.iter().zip(&assignments).filter(|(_, a)| **a == cluster).map(|(p, _)| p) {
+                sum.iter_mut().zip(point).for_each(|(s, v)| *s += v);
+                count += 1;
+            }
+            if count > 0 {
+                *centroid = sum.into_iter().map(|s| s / count as f32).collect();
+            }
+        }
+    }
+    let inertia = points.iter().zip(&assignments).map(|(p, &a)| centroids.get(a).map_or(0.0, |c| distance2(c, p))).sum();
+    Some(Clustering { centroids, assignments, inertia, iterations })
+}
+
+/// Lowercase words of `text` with at least three letters or digits.
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|w| w.chars().count() >= 3).map(str::to_lowercase)
+}
+
+/// Vocabulary (sorted) of `texts` and one unit-length word-count vector per
+/// text over it; a text without words gets a zero vector.
+pub fn word_vectors(texts: &[&str]) -> (Vec<String>, Vec<Vec<f32>>) {
+    let vocabulary: BTreeMap<String, usize> = texts.iter().flat_map(|t| words(t)).map(|w| (w, 0)).collect();
+    let index: BTreeMap<&String, usize> = vocabulary.keys().enumerate().map(|(i, w)| (w, i)).collect();
+    let vectors = texts
+        .iter()
+        .map(|text| {
+            let mut v = vec![0.0_f32; index.len()];
+            for word in words(text) {
+                if let Some(slot) = index.get(&word).and_then(|&i| v.get_mut(i)) {
+                    *slot += 1.0;
+                }
+            }
+            let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+            if norm > 0.0 {
+                v.iter_mut().for_each(|x| *x /= norm);
+            }
+            v
+        })
+        .collect();
+    (vocabulary.into_keys().collect(), vectors)
+}
+
+/// A group of texts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Topic {
+    /// most frequent words of the texts, at most `TOPIC_WORDS`
+    pub words: Vec<String>,
+    /// the texts, in input order
+    pub members: Vec<String>,
+}
+
+/// Words labelling a topic.
+pub const TOPIC_WORDS: usize = 3;
+
+/// Number of topics for `n` texts: √(n/2), between 1 and 8.
+pub fn suggested_k(n: usize) -> usize {
+    ((n as f64 / 2.0).sqrt().round() as usize).clamp(1, 8).min(n)
+}
+
+/// `texts` clustered by their `vectors` (one per text) into `k` topics,
+/// largest first. Empty for no texts or `k = 0`.
+pub fn topics(texts: &[&str], vectors: &[Vec<f32>], k: usize, seed: u64) -> Vec<Topic> {
+    let Some(clustering) = kmeans(vectors, k, seed, 100) else { return Vec::new() };
+    let mut topics: Vec<Topic> = (0..clustering.centroids.len())
+        .map(|cluster| {
+            let members: Vec<&str> = texts.iter().zip(&clustering.assignments).filter(|(_, a)| **a == cluster).map(|(t, _)| *t).collect();
+            let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+            for word in members.iter().flat_map(|t| words(t)) {
+                *counts.entry(word).or_default() += 1;
+            }
+            let mut ranked: Vec<(String, usize)> = counts.into_iter().collect();
+            // most frequent first, alphabetical among equals (the map is sorted and the sort stable)
+            ranked.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+            Topic { words: ranked.into_iter().take(TOPIC_WORDS).map(|(w, _)| w).collect(), members: members.into_iter().map(str::to_string).collect() }
+        })
+        .filter(|t| !t.members.is_empty())
+        .collect();
+    topics.sort_by_key(|t| std::cmp::Reverse(t.members.len()));
+    topics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kmeans_separates_groups_and_topics_get_labels() {
+        let points: Vec<Vec<f32>> = [[0.0, 0.1], [0.2, 0.0], [0.1, 0.2], [5.0, 5.1], [5.2, 4.9], [4.9, 5.0], [-5.0, 5.0], [-5.1, 4.8]].iter().map(|p| p.to_vec()).collect();
+        let c = kmeans(&points, 3, 7, 100);
+        assert_eq!(c.as_ref().map(|c| c.assignments.len()), Some(points.len()));
+        let Some(c) = c else { return };
+        let groups: Vec<usize> = c.assignments.clone();
+        assert!(groups.get(..3).is_some_and(|g| g.iter().all(|a| Some(a) == groups.first())));
+        assert!(groups.get(3..6).is_some_and(|g| g.iter().all(|a| Some(a) == groups.get(3))));
+        assert!(groups.get(6..).is_some_and(|g| g.iter().all(|a| Some(a) == groups.get(6))));
+        assert!(c.inertia < 0.5);
+        assert_eq!(kmeans(&points, 3, 7, 100), Some(c));
+        assert_eq!(kmeans(&points, 20, 1, 100).map(|c| c.centroids.len()), Some(8));
+        assert!(kmeans(&[], 2, 1, 10).is_none());
+
+        let texts = ["акула хищная рыба", "рыба акула в море", "курс биткоина растёт", "курс биткоина падает", "акула плавает в море"];
+        let (vocabulary, vectors) = word_vectors(&texts);
+        assert!(vocabulary.contains(&"акула".to_string()) && !vocabulary.contains(&"в".to_string()));
+        let found = topics(&texts, &vectors, 2, 3);
+        let summary: Vec<(Option<&String>, usize)> = found.iter().map(|t| (t.words.first(), t.members.len())).collect();
+        assert_eq!(summary, vec![(Some(&"акула".to_string()), 3), (Some(&"биткоина".to_string()), 2)]);
+        assert_eq!(suggested_k(50), 5);
+    }
+}
@@ -0,0 +1,219 @@
+#![forbid(unsafe_code)]
+
+//! Slash-commands understood by the interactive front-ends.
+//!
+//! Lines starting with `/` are commands rather than prompts, e.g.
+//! `/knowledge add что такое граф?;набор вершин и рёбер` or `/seed 42`.
+//! Special handlers (code structure, research, problem evaluation) are only
+//! reachable through their command, so any other line goes to normal chat.
+//! Parsing is kept separate from execution so the REPL, the single-shot CLI
+//! and tests share one grammar.
+
+use std::path::PathBuf;
+
+/// A parsed slash-command.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlashCommand {
+    /// `/help` — list available commands.
+    Help,
+    /// `/quit` or `/exit` — leave the REPL.
+    Quit,
+    /// `/reset` — forget the dialog memory.
+    Reset,
+    /// `/memory` — show the most recent dialogs.
+    Memory,
+    /// `/knowledge add Q;A` — append a question/answer pair to the knowledge base.
+    KnowledgeAdd {
+        /// question text
+        question: String,
+        /// answer text
+        answer: String,
+    },
+    /// `/knowledge conflicts` — list contradictions in the knowledge base.
+    KnowledgeConflicts,
+    /// `/knowledge resolve N CHOICE` — settle conflict `N` (1-based, as listed).
+    KnowledgeResolve {
+        /// conflict number from `/knowledge conflicts`
+        index: usize,
+        /// what to do with it
+        choice: ConflictChoice,
+    },
+    /// `/seed N` — reseed model generation.
+    Seed(u64),
+    /// `/save PATH` — write the session transcript as Markdown.
+    Save(PathBuf),
+    /// `/export PATH` — write the session with sources and reasoning as a
+    /// self-contained Markdown or HTML file (by extension).
+    Export(PathBuf),
+    /// `/record PATH` — append every following exchange to a JSONL file;
+    /// `/record` without a path stops recording.
+    Record(Option<PathBuf>),
+    /// `/structure` — scan the sources and show the module structure.
+    Structure,
+    /// `/research` — run the scientist's symbolic search.
+    Research,
+    /// `/problems` — evaluate the problems dataset.
+    Problems,
+    /// `/jobs` — show the background self-learning jobs.
+    Jobs,
+    /// `/listen` — take spoken questions (`[audio]`) until silence or «стоп».
+    Listen,
+    /// `/model` — list the registered generators and the selected one.
+    ModelList,
+    /// `/model use NAME` — answer with another generator (`default` to go back).
+    ModelUse(String),
+}
+
+/// Choice of `/knowledge resolve` (see `knowledge_store::Resolution`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictChoice {
+    /// `keep K` — keep the K-th listed answer (1-based)
+    Keep(usize),
+    /// `fix` — replace the answers with the computed one
+    Fix,
+    /// `remove` — remove the question
+    Remove,
+    /// `accept` — leave it and stop reporting it
+    Accept,
+}
+
+/// One-line usage summary per command, for `/help`.
+pub const HELP: &[(&str, &str)] = &[
+    ("/help", "показать список команд"),
+    ("/reset", "очистить память диалогов"),
+    ("/memory", "показать последние диалоги"),
+    ("/knowledge add Q;A", "добавить пару вопрос;ответ в базу знаний"),
+    ("/knowledge conflicts", "противоречия в базе знаний"),
+    ("/knowledge resolve N …", "разрешить противоречие N: keep K | fix | remove | accept"),
+    ("/seed N", "задать seed генерации модели"),
+    ("/save FILE.md", "сохранить стенограмму сессии в Markdown"),
+    ("/export FILE.html", "экспорт сессии с источниками и рассуждениями (.md или .html)"),
+    ("/record FILE.jsonl", "записывать обмен репликами для `chat replay` (/record — стоп)"),
+    ("/structure", "показать структуру кода Shark-Core"),
+    ("/research", "исследовать закономерности (символьный поиск)"),
+    ("/problems", "проверить задачи из problems.csv"),
+    ("/jobs", "состояние фоновых задач самообучения"),
+    ("/listen", "голосовой режим: вопросы голосом до тишины или «стоп» ([audio])"),
+    ("/model", "зарегистрированные модели генерации"),
+    ("/model use NAME", "отвечать моделью NAME (default — модель по умолчанию)"),
+    ("/quit", "выйти"),
+];
+
+/// Parse a line as a slash-command.
+///
+/// Returns `None` when the line is not a command (does not start with `/`),
+/// `Some(Err(message))` for malformed or unknown commands.
+pub fn parse_command(line: &str) -> Option<Result<SlashCommand, String>> {
+    let line = line.trim();
+    let body = line.strip_prefix('/')?;
+    let (name, rest) = match body.split_once(char::is_whitespace) {
+        Some((n, r)) => (n, r.trim()),
+        None => (body, ""),
+    };
+    let cmd = match name {
+        "help" | "?" => Ok(SlashCommand::Help),
+        "quit" | "exit" => Ok(SlashCommand::Quit),
+        "reset" => Ok(SlashCommand::Reset),
+        "memory" => Ok(SlashCommand::Memory),
+        "knowledge" => parse_knowledge(rest),
+        "seed" => rest
+            .parse::<u64>()
+            .map(SlashCommand::Seed)
+            .map_err(|_| format!("ожидалось число: /seed 42, получено '{}'", rest)),
+        "save" if !rest.is_empty() => Ok(SlashCommand::Save(PathBuf::from(rest))),
+        "save" => Err("укажите файл: /save transcript.md".to_string()),
+        "export" if !rest.is_empty() => Ok(SlashCommand::Export(PathBuf::from(rest))),
+        "export" => Err("укажите файл: /export chat.html или /export chat.md".to_string()),
+        "record" if rest.is_empty() => Ok(SlashCommand::Record(None)),
+        "record" => Ok(SlashCommand::Record(Some(PathBuf::from(rest)))),
+        "structure" => Ok(SlashCommand::Structure),
+        "research" => Ok(SlashCommand::Research),
+        "problems" => Ok(SlashCommand::Problems),
+        "jobs" => Ok(SlashCommand::Jobs),
+        "listen" => Ok(SlashCommand::Listen),
+        "model" if rest.is_empty() || rest == "list" => Ok(SlashCommand::ModelList),
+        "model" => match rest.strip_prefix("use").map(str::trim) {
+            Some(name) if !name.is_empty() && !name.contains(char::is_whitespace) => Ok(SlashCommand::ModelUse(name.to_string())),
+            _ => Err("использование: /model use NAME".to_string()),
+        },
+        other => Err(format!("неизвестная команда /{} — см. /help", other)),
+    };
+    Some(cmd)
+}
+
+fn parse_knowledge(rest: &str) -> Result<SlashCommand, String> {
+    if rest == "conflicts" {
+        return Ok(SlashCommand::KnowledgeConflicts);
+    }
+    if let Some(args) = rest.strip_prefix("resolve") {
+        return parse_resolve(args);
+    }
+    let pair = rest
+        .strip_prefix("add")
+        .ok_or_else(|| "использование: /knowledge add вопрос;ответ".to_string())?;
+    match pair.split_once(';') {
+        Some((q, a)) if !q.trim().is_empty() && !a.trim().is_empty() => Ok(SlashCommand::KnowledgeAdd {
+            question: q.trim().to_string(),
+            answer: a.trim().to_string(),
+        }),
+        _ => Err("использование: /knowledge add вопрос;ответ".to_string()),
+    }
+}
+
+fn parse_resolve(args: &str) -> Result<SlashCommand, String> {
+    const USAGE: &str = "использование: /knowledge resolve N keep K|fix|remove|accept";
+    let words: Vec<&str> = args.split_whitespace().collect();
+    let number = |s: &str| s.parse::<usize>().ok().filter(|n| *n > 0);
+    let (index, choice) = match words.as_slice() {
+        [n, "keep", k] => (number(n), number(k).map(ConflictChoice::Keep)),
+        [n, "fix"] => (number(n), Some(ConflictChoice::Fix)),
+        [n, "remove"] => (number(n), Some(ConflictChoice::Remove)),
+        [n, "accept"] => (number(n), Some(ConflictChoice::Accept)),
+        _ => (None, None),
+    };
+    match (index, choice) {
+        (Some(index), Some(choice)) => Ok(SlashCommand::KnowledgeResolve { index, choice }),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_prompts_are_not_commands() {
+        assert_eq!(parse_command("2 + 2"), None);
+        assert_eq!(parse_command("что такое x/y?"), None);
+        assert_eq!(parse_command("как устроен код?"), None);
+    }
+
+    #[test]
+    fn parses_arguments() {
+        assert_eq!(parse_command("/seed 42"), Some(Ok(SlashCommand::Seed(42))));
+        assert_eq!(
+            parse_command("/knowledge add что такое граф? ; вершины и рёбра"),
+            Some(Ok(SlashCommand::KnowledgeAdd {
+                question: "что такое граф?".to_string(),
+                answer: "вершины и рёбра".to_string(),
+            }))
+        );
+        assert_eq!(parse_command("/save out.md"), Some(Ok(SlashCommand::Save(PathBuf::from("out.md")))));
+        assert_eq!(parse_command("/export chat.html"), Some(Ok(SlashCommand::Export(PathBuf::from("chat.html")))));
+        assert_eq!(parse_command("/record s.jsonl"), Some(Ok(SlashCommand::Record(Some(PathBuf::from("s.jsonl"))))));
+        assert_eq!(parse_command("/record"), Some(Ok(SlashCommand::Record(None))));
+        assert_eq!(parse_command("/model use ngram"), Some(Ok(SlashCommand::ModelUse("ngram".to_string()))));
+        assert_eq!(
+            parse_command("/knowledge resolve 2 keep 1"),
+            Some(Ok(SlashCommand::KnowledgeResolve { index: 2, choice: ConflictChoice::Keep(1) }))
+        );
+    }
+
+    #[test]
+    fn rejects_malformed() {
+        assert!(matches!(parse_command("/seed abc"), Some(Err(_))));
+        assert!(matches!(parse_command("/knowledge add no-separator"), Some(Err(_))));
+        assert!(matches!(parse_command("/knowledge resolve 0 fix"), Some(Err(_))));
+        assert!(matches!(parse_command("/frobnicate"), Some(Err(_))));
+    }
+}
@@ -0,0 +1,460 @@
+#![forbid(unsafe_code)]
+
+//! Workspace configuration (`shark.toml`).
+//!
+//! Every field has a default matching the historical hard-coded value, so an
+//! empty or missing file behaves exactly like before. Only the keys that are
+//! present override the defaults:
+//!
+//! ```toml
+//! [model]
+//! path = "weights/model_int4.bin"
+//!
+//! [data]
+//! dir = "crates/predict/data"
+//! memory = "memory.db"
+//! state_dir = "state"
+//!
+//! [sampler]
+//! seed = 0
+//! max_chars = 64
+//!
+//! [rng]
+//! seed = 1234          # optional master seed, see `RngConfig`
+//!
+//! [server]
+//! host = "0.0.0.0"
+//! port = 3030
+//!
+//! [features]
+//! self_repair = false
+//! startup_scan = true
+//!
+//! [pipeline]
+//! stages = ["learn", "reasoner", "knowledge", "arithmetic", "linear", "topics", "tools", "concepts", "model"]
+//! rank = false
+//! min_confidence = 0.1
+//! cache_size = 256
+//! cache_ttl_secs = 3600
+//! retrieval = "trigram"  # or "tfidf"
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::audio::Backend;
+use crate::core::{RngAlgorithm, RngSource};
+use crate::error::{Result, SharkError};
+use crate::linear::Accumulation;
+use crate::model_registry::ModelSpec;
+use crate::tfidf::Retrieval;
+
+/// Default configuration file name, looked up in the working directory.
+pub const CONFIG_FILE: &str = "shark.toml";
+
+/// Environment variable naming an alternative configuration file.
+pub const CONFIG_ENV: &str = "SHARK_CONFIG";
+
+/// Model weights.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModelConfig {
+    /// weights blob loaded by `Model::load`
+    pub path: String,
+    /// trained n-gram fallback generator (used when the file exists)
+    pub ngram_path: String,
+    /// registered char model (`[[models]]`) that drafts for speculative
+    /// decoding of char models; empty for plain decoding
+    pub draft: String,
+    /// characters the draft model proposes per target pass
+    pub speculative_k: usize,
+    /// precision of the char model's matmul sums: `f32`, or `f64` for wide
+    /// hidden layers (less drift over long generations)
+    pub accumulation: Accumulation,
+}
+
+impl Default for ModelConfig {
+    fn default() -> Self {
+        Self {
+            path: "weights/model_int4.bin".to_string(),
+            ngram_path: crate::ngram::NGRAM_PATH.to_string(),
+            draft: String::new(),
+            speculative_k: crate::speculative::DEFAULT_K,
+            accumulation: Accumulation::F32,
+        }
+    }
+}
+
+/// Data files.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DataConfig {
+    /// directory holding knowledge.csv, problems.csv, unknowns.csv and knowledge_rust.csv
+    pub dir: PathBuf,
+    /// dialog memory file
+    pub memory: String,
+    /// runtime state written by the binaries (self-repair sandbox)
+    pub state_dir: PathBuf,
+}
+
+impl Default for DataConfig {
+    fn default() -> Self {
+        Self { dir: PathBuf::from("crates/predict/data"), memory: "memory.db".to_string(), state_dir: PathBuf::from("state") }
+    }
+}
+
+/// Generation defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SamplerConfig {
+    /// extra seed mixed into the generation RNG (`Model::seed`)
+    pub seed: u64,
+    /// characters generated per answer by the model
+    pub max_chars: usize,
+    /// characters generated per answer by the n-gram fallback
+    pub ngram_max_chars: usize,
+}
+
+impl Default for SamplerConfig {
+    fn default() -> Self {
+        Self { seed: 0, max_chars: 64, ngram_max_chars: 120 }
+    }
+}
+
+/// Session-wide randomness (`core::RngSource`). Its consumers take `u64`
+/// stream seeds for their own ChaCha8 generators (`core::make_rng`), so the
+/// streams are always ChaCha8.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RngConfig {
+    /// master seed; when set, generation (instead of `sampler.seed`), `chat
+    /// research` without `--seed`, backtests and other consumers use its
+    /// named streams
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+/// HTTP server.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// bind address
+    pub host: String,
+    /// bind port
+    pub port: u16,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self { host: "0.0.0.0".to_string(), port: 3030 }
+    }
+}
+
+impl ServerConfig {
+    /// `host:port` for binding.
+    pub fn addr(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+/// Startup steps of the chat front-ends that can be switched off.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Features {
+    /// check the critical modules at startup and write stubs for broken ones
+    /// into `<state_dir>/self_repair` (`self_repair`; off by default)
+    pub self_repair: bool,
+    /// scan the source tree and refresh knowledge_rust.csv / docs/code_tree.md
+    pub startup_scan: bool,
+    /// create topic files and merge them into knowledge.csv
+    pub knowledge_merge: bool,
+    /// deepen the scientist's most curious formulas
+    pub science: bool,
+    /// try to re-solve recorded unknowns
+    pub relearn: bool,
+    /// skip all of the above and load knowledge lazily (like `--fast`)
+    pub fast: bool,
+}
+
+impl Default for Features {
+    fn default() -> Self {
+        Self { self_repair: false, startup_scan: true, knowledge_merge: true, science: true, relearn: true, fast: false }
+    }
+}
+
+/// Answer pipeline used by `AI::chat`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PipelineConfig {
+    /// stage names in the order they are tried (see `pipeline::stage`)
+    pub stages: Vec<String>,
+    /// run every stage and keep the best answer (`rank::rank_answers`)
+    /// instead of the first one
+    pub rank: bool,
+    /// answers with a lower calibrated confidence become "I don't know"
+    /// (`response::DONT_KNOW`); 0 disables the check
+    pub min_confidence: f32,
+    /// answers of knowledge and solver stages kept for repeated prompts
+    /// (`cache::ResponseCache`); 0 disables the cache
+    pub cache_size: usize,
+    /// seconds a cached answer stays valid; 0 keeps it until evicted
+    pub cache_ttl_secs: u64,
+    /// how the `concepts` stage finds fuzzy matches: `trigram`, or `tfidf`
+    /// for long questions (`tfidf::TfIdfIndex`)
+    pub retrieval: Retrieval,
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self { stages: crate::pipeline::DEFAULT_STAGES.iter().map(|s| s.to_string()).collect(), rank: false, min_confidence: 0.1, cache_size: 256, cache_ttl_secs: 3600, retrieval: Retrieval::default() }
+    }
+}
+
+/// Background self-learning jobs (`jobs::Scheduler::self_learning`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JobsConfig {
+    /// seconds without input before recorded unknowns are re-solved
+    pub relearn_idle_secs: u64,
+    /// seconds between deep-evolution runs of the scientist
+    pub evolution_interval_secs: u64,
+    /// dataset evaluated every night (`evaluation::run`; unset: no `evaluate` job)
+    pub evaluate: Option<String>,
+    /// UTC hour of the nightly evaluation
+    pub evaluate_hour: u8,
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        Self { relearn_idle_secs: 30, evolution_interval_secs: 600, evaluate: None, evaluate_hour: 3 }
+    }
+}
+
+/// Third-party plugins (`plugins::Plugins`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PluginsConfig {
+    /// manifest listing the enabled plugins; a missing file enables none
+    pub manifest: PathBuf,
+}
+
+impl Default for PluginsConfig {
+    fn default() -> Self {
+        Self { manifest: PathBuf::from(crate::plugins::DEFAULT_MANIFEST) }
+    }
+}
+
+/// Generated reports (`reports::Report`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReportsConfig {
+    /// directory of Markdown reports, logs and their SVG charts
+    pub dir: PathBuf,
+}
+
+impl Default for ReportsConfig {
+    fn default() -> Self {
+        Self { dir: PathBuf::from(crate::reports::DEFAULT_DIR) }
+    }
+}
+
+/// Local usage statistics (`usage::UsageStats`).
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StatsConfig {
+    /// count answers per day, stage and source in `<data.state_dir>/usage_stats.csv`
+    pub enabled: bool,
+}
+
+/// Replay log of generations (`replay::ReplayLog`).
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReplayConfig {
+    /// append every generation to `<data.state_dir>/replay.jsonl`
+    pub enabled: bool,
+}
+
+/// Deny lists for prompts and answers (`moderation::Moderator`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModerationConfig {
+    /// apply the deny lists
+    pub enabled: bool,
+    /// keywords or phrases, matched as whole words, case-insensitive
+    pub deny_words: Vec<String>,
+    /// regular expressions
+    pub deny_patterns: Vec<String>,
+    /// check prompts before the pipeline
+    pub input: bool,
+    /// check answers before they are shown and stored
+    pub output: bool,
+    /// answer given instead of a blocked one
+    pub refusal: String,
+}
+
+impl Default for ModerationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            deny_words: Vec::new(),
+            deny_patterns: Vec::new(),
+            input: true,
+            output: true,
+            refusal: "Запрос отклонён правилами модерации.".to_string(),
+        }
+    }
+}
+
+/// Speech output and input of the front-ends (`audio::from_config`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AudioConfig {
+    /// `none` (silent) or `command` (external programs, feature `audio`)
+    pub backend: Backend,
+    /// program and arguments reading the text to say on stdin (empty: say nothing)
+    pub speak: Vec<String>,
+    /// program and arguments printing one recognised utterance
+    pub listen: Vec<String>,
+    /// milliseconds before a command is killed
+    pub timeout_ms: u64,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self { backend: Backend::None, speak: Vec::new(), listen: Vec::new(), timeout_ms: 30_000 }
+    }
+}
+
+/// Complete configuration.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// model weights
+    pub model: ModelConfig,
+    /// data files
+    pub data: DataConfig,
+    /// generation defaults
+    pub sampler: SamplerConfig,
+    /// master seed of the session
+    pub rng: RngConfig,
+    /// HTTP server
+    pub server: ServerConfig,
+    /// startup toggles
+    pub features: Features,
+    /// answer pipeline stages
+    pub pipeline: PipelineConfig,
+    /// background jobs
+    pub jobs: JobsConfig,
+    /// plugin manifest
+    pub plugins: PluginsConfig,
+    /// report output
+    pub reports: ReportsConfig,
+    /// usage statistics
+    pub stats: StatsConfig,
+    /// generation replay log
+    pub replay: ReplayConfig,
+    /// safety filters
+    pub moderation: ModerationConfig,
+    /// speech output and input
+    pub audio: AudioConfig,
+    /// further generators selectable by name (`[[models]]`)
+    pub models: Vec<ModelSpec>,
+}
+
+impl Config {
+    /// Parse TOML text; `origin` is used in error messages.
+    pub fn from_toml(text: &str, origin: &Path) -> Result<Self> {
+        toml::from_str(text).map_err(|e| SharkError::Config { path: origin.to_path_buf(), reason: e.to_string() })
+    }
+
+    /// Load a configuration file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(|e| SharkError::io(path, e))?;
+        Self::from_toml(&text, path)
+    }
+
+    /// Load `$SHARK_CONFIG` if set (it must exist), else `shark.toml` if present,
+    /// else the defaults.
+    pub fn discover() -> Result<Self> {
+        if let Some(path) = std::env::var_os(CONFIG_ENV) {
+            return Self::load(Path::new(&path));
+        }
+        match Self::load(Path::new(CONFIG_FILE)) {
+            Err(e) if e.is_not_found() => Ok(Self::default()),
+            other => other,
+        }
+    }
+
+    /// Sandbox of the self-repair step (`<data.state_dir>/self_repair`).
+    pub fn self_repair_dir(&self) -> PathBuf {
+        self.data.state_dir.join("self_repair")
+    }
+
+    /// Central knowledge base (`<data.dir>/knowledge.csv`).
+    pub fn knowledge_path(&self) -> PathBuf {
+        self.data.dir.join("knowledge.csv")
+    }
+
+    /// Resolution log of knowledge conflicts (`<data.dir>/knowledge/knowledge_conflicts.csv`).
+    pub fn conflicts_log_path(&self) -> PathBuf {
+        self.data.dir.join("knowledge").join("knowledge_conflicts.csv")
+    }
+
+    /// Usage statistics (`<data.state_dir>/usage_stats.csv`).
+    pub fn usage_stats_path(&self) -> PathBuf {
+        self.data.state_dir.join("usage_stats.csv")
+    }
+
+    /// Replay log of generations (`[replay] enabled`).
+    pub fn replay_log_path(&self) -> PathBuf {
+        self.data.state_dir.join(crate::replay::FILE)
+    }
+
+    /// Source of the session's random streams, if `[rng] seed` is set.
+    pub fn rng_source(&self) -> Option<RngSource> {
+        self.rng.seed.map(|seed| RngSource::new(seed, RngAlgorithm::ChaCha8))
+    }
+
+    /// Seed of backtests (`EngineConfig::seed`) and the strategy optimizer:
+    /// the `RngSource::BACKTEST` stream when `[rng] seed` is set, else 0.
+    pub fn backtest_seed(&self) -> u64 {
+        self.rng_source().map_or(0, |rng| rng.stream_seed(RngSource::BACKTEST))
+    }
+
+    /// The defaults as a commented-free TOML document (for `shark.toml` templates).
+    pub fn to_toml(&self) -> String {
+        toml::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_files_keep_defaults_and_typos_are_rejected() {
+        let cfg = Config::from_toml("[server]\nport = 8080\n[features]\nself_repair = true\n", Path::new("t.toml"));
+        let cfg = cfg.unwrap_or_default();
+        assert_eq!(cfg.server.port, 8080);
+        assert!(cfg.features.self_repair);
+        assert_eq!(cfg.model, ModelConfig::default());
+
+        assert_eq!(Config::from_toml(&Config::default().to_toml(), Path::new("t.toml")).ok(), Some(Config::default()));
+        assert!(matches!(Config::from_toml("[server]\nprot = 1\n", Path::new("t.toml")), Err(SharkError::Config { .. })));
+
+        let seeded = Config::from_toml("[rng]\nseed = 9\n", Path::new("t.toml")).unwrap_or_default();
+        assert_eq!(seeded.rng_source(), Some(RngSource::new(9, RngAlgorithm::ChaCha8)));
+        assert_eq!(seeded.backtest_seed(), RngSource::new(9, RngAlgorithm::ChaCha8).stream_seed(RngSource::BACKTEST));
+        assert!(Config::from_toml("[rng]\nseed = 9\nalgorithm = \"chacha20\"\n", Path::new("t.toml")).is_err());
+        assert_eq!(Config::from_toml(&seeded.to_toml(), Path::new("t.toml")).ok(), Some(seeded));
+        assert_eq!((Config::default().rng_source(), Config::default().backtest_seed()), (None, 0));
+        let tfidf = Config::from_toml("[pipeline]\nretrieval = \"tfidf\"\n", Path::new("t.toml")).unwrap_or_default();
+        assert_eq!(tfidf.pipeline.retrieval, Retrieval::TfIdf);
+        let nightly = Config::from_toml("[jobs]\nevaluate = \"problems\"\n", Path::new("t.toml")).unwrap_or_default();
+        assert_eq!((nightly.jobs.evaluate.as_deref(), nightly.jobs.evaluate_hour), (Some("problems"), 3));
+        assert_eq!(Config::from_toml(&nightly.to_toml(), Path::new("t.toml")).ok(), Some(nightly));
+        let voice = Config::from_toml("[audio]\nbackend = \"command\"\nspeak = [\"say\"]\n", Path::new("t.toml")).unwrap_or_default();
+        assert_eq!((voice.audio.backend, voice.audio.speak.len(), voice.audio.timeout_ms), (Backend::Command, 1, 30_000));
+    }
+}
@@ -0,0 +1,74 @@
+//! Grammar-constrained generation: an output `Template` of fixed literals and
+//! free text slots. `Model::generate_template` copies the literals and samples
+//! the slots with every character the slot forbids masked out, so the result
+//! always matches the template (e.g. a JSON object with a string field).
+
+/// Part of a `Template`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    /// emitted verbatim
+    Literal(String),
+    /// sampled text; ends when the model picks the first character of the
+    /// following literal (`terminator`) or after `max_len` characters
+    Text {
+        /// longest slot content
+        max_len: usize,
+        /// characters never sampled inside the slot
+        forbidden: Vec<char>,
+    },
+}
+
+/// Sequence of literals and text slots.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Template {
+    /// segments in output order
+    pub segments: Vec<Segment>,
+}
+
+impl Template {
+    /// `{"<field>":"<text>"}` with the text free of `"` and `\`, so the output
+    /// is always valid JSON without escapes.
+    pub fn json_string_field(field: &str, max_len: usize) -> Self {
+        Self {
+            segments: vec![
+                Segment::Literal(format!("{{\"{}\":\"", field)),
+                Segment::Text { max_len, forbidden: vec!['"', '\\'] },
+                Segment::Literal("\"}".to_string()),
+            ],
+        }
+    }
+
+    /// Character that closes the text slot at `index`: the first character
+    /// of the next literal, if any.
+    pub fn terminator(&self, index: usize) -> Option<char> {
+        match self.segments.get(index + 1) {
+            Some(Segment::Literal(text)) => text.chars().next(),
+            _ => None,
+        }
+    }
+
+    /// Whether `c` may be sampled inside the slot at `index` (the terminator
+    /// is allowed: it ends the slot).
+    pub fn allows(&self, index: usize, c: char) -> bool {
+        match self.segments.get(index) {
+            Some(Segment::Text { forbidden, .. }) => !forbidden.contains(&c) || self.terminator(index) == Some(c),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_template_masks_quotes_but_lets_them_close_the_slot() {
+        let template = Template::json_string_field("answer", 8);
+        assert_eq!(template.terminator(1), Some('"'));
+        assert!(template.allows(1, 'a') && template.allows(1, '"') && !template.allows(1, '\\'));
+        assert!(!template.allows(0, 'a'));
+
+        let no_quote = Template { segments: vec![Segment::Text { max_len: 3, forbidden: vec!['"'] }] };
+        assert!(!no_quote.allows(0, '"'));
+    }
+}
@@ -1,24 +1,26 @@
 use std::collections::HashMap;
 
+use crate::grammar::Grammar;
+use crate::memory_freq::WordFrequencyStore;
+
 /// Interpret raw decoded text contextually by counting word frequencies
 /// and returning the top most frequent words/phrases.
 ///
-/// This function builds a frequency map of mapped values from the grammar map,
-/// sorts them by frequency descending, and returns the top 10 as a comma-separated string.
+/// This function builds a frequency map of the values of the grammar tokens
+/// found in `raw` (each occurrence counts its entry's weight), sorts them by
+/// frequency descending, and returns the top 10 as a comma-separated string.
 /// Useful for concise, context-relevant interpretation instead of concatenating all matches.
-pub fn interpret_contextual(raw: &str, map: &HashMap<char, String>) -> String {
-    let mut freq: HashMap<&str, usize> = HashMap::new();
+/// Single-character maps from `load_grammar_from_csv` convert with `Grammar::from(&map)`.
+pub fn interpret_contextual(raw: &str, grammar: &Grammar) -> String {
+    let mut freq: HashMap<&str, f64> = HashMap::new();
 
-    for ch in raw.chars() {
-        let lower_ch = ch.to_ascii_lowercase();
-        if let Some(val) = map.get(&lower_ch) {
-            *freq.entry(val.as_str()).or_insert(0) += 1;
-        }
+    for entry in grammar.matches(raw) {
+        *freq.entry(entry.value.as_str()).or_insert(0.0) += entry.weight;
     }
 
     // Сортировка по частоте (наиболее вероятные слова/понятия)
-    let mut pairs: Vec<(&str, usize)> = freq.into_iter().collect();
-    pairs.sort_by(|a, b| b.1.cmp(&a.1));
+    let mut pairs: Vec<(&str, f64)> = freq.into_iter().collect();
+    pairs.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
 
     // Берём только топ-10 (чтобы не захламлять вывод)
     pairs.iter()
@@ -30,33 +32,19 @@ pub fn interpret_contextual(raw: &str, map: &HashMap<char, String>) -> String {
 
 /// Load word frequencies from a CSV file (word,freq).
 /// If file doesn't exist, returns empty map.
+#[deprecated(note = "use `WordFrequencyStore::load`")]
 pub fn load_memory_freq(path: &str) -> HashMap<String, usize> {
-    let mut freq = HashMap::new();
-    if let Ok(content) = std::fs::read_to_string(path) {
-        for line in content.lines().skip(1) {
-            if let Some((word, count_str)) = line.split_once(',') {
-                if let Ok(count) = count_str.trim().parse::<usize>() {
-                    freq.insert(word.trim().to_string(), count);
-                }
-            }
-        }
-    }
-    freq
+    WordFrequencyStore::load(path).map(WordFrequencyStore::into_counts).unwrap_or_default()
 }
 
 /// Save word frequencies to a CSV file.
+#[deprecated(note = "use `WordFrequencyStore::save`")]
 pub fn save_memory_freq(path: &str, freq: &HashMap<String, usize>) {
-    use std::fs::File;
-    use std::io::Write;
-    if let Ok(mut file) = File::create(path) {
-        writeln!(file, "word,freq").ok();
-        for (word, count) in freq {
-            writeln!(file, "{},{}", word, count).ok();
-        }
-    }
+    let _ = WordFrequencyStore::from_counts(freq.clone()).save(path);
 }
 
 /// Update memory frequencies with current interpretation words.
+#[deprecated(note = "use `WordFrequencyStore::update` with the words of the interpretation")]
 pub fn update_memory_freq(memory_freq: &mut HashMap<String, usize>, interpretation: &str) {
     for word in interpretation.split(", ") {
         let word = word.trim();
@@ -68,21 +56,17 @@ pub fn update_memory_freq(memory_freq: &mut HashMap<String, usize>, interpretati
 
 /// Interpret with memory weighting: combine current freq with historical memory.
 /// context_temp (0.0-1.0): 0.0 = pure memory priority, 1.0 = pure current priority.
-/// Now uses loaded memory_freq for stronger weighting.
+/// Now uses loaded memory_freq (`WordFrequencyStore::counts`) for stronger weighting.
 pub fn interpret_contextual_with_memory(
     raw: &str,
-    map: &HashMap<char, String>,
+    grammar: &Grammar,
     memory_freq: &HashMap<String, usize>,
     context_temp: f64,
 ) -> String {
     let mut freq: HashMap<String, f64> = HashMap::new();
 
-    for ch in raw.chars() {
-        let lower_ch = ch.to_ascii_lowercase();
-        if let Some(val) = map.get(&lower_ch) {
-            let current = freq.entry(val.clone()).or_insert(0.0);
-            *current += 1.0;
-        }
+    for entry in grammar.matches(raw) {
+        *freq.entry(entry.value.clone()).or_insert(0.0) += entry.weight;
     }
 
     // Weight with memory, using actual freq counts
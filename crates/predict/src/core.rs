@@ -1,26 +1,130 @@
 #![forbid(unsafe_code)]
 
-use rand_chacha::ChaCha8Rng;
-use rand::SeedableRng;
+use rand_chacha::{ChaCha20Rng, ChaCha8Rng};
+use rand::{RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
 
-/// Minimal softmax implementation for logits slice
-pub fn softmax(logits: &mut [f32]) {
-    if logits.is_empty() { return; }
-    let max = logits.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
-    let mut sum = 0.0_f32;
-    for v in logits.iter_mut() {
-        *v = (*v - max).exp();
-        sum += *v;
-    }
-    if sum == 0.0 { return; }
-    for v in logits.iter_mut() { *v /= sum; }
-}
+// softmax, log-softmax and sampling live in the no_std `shark-core-math` crate
+pub use shark_core_math::{
+    log_softmax, log_softmax_with, sample_gumbel, sample_index, sample_top_k, softmax, softmax_with, top_k_indices,
+};
 
 /// Simple RNG wrapper returning a seeded ChaCha8Rng
+/// (the same generator as `RngSource::new(seed, RngAlgorithm::ChaCha8).rng()`).
 pub fn make_rng(seed: u64) -> ChaCha8Rng {
     ChaCha8Rng::seed_from_u64(seed)
 }
 
+/// Generator behind an `RngSource`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RngAlgorithm {
+    /// ChaCha with 8 rounds (fast; what `make_rng` uses)
+    #[default]
+    ChaCha8,
+    /// ChaCha with 20 rounds (slower, cryptographic strength)
+    ChaCha20,
+}
+
+/// One master seed from which every random stream of a session is derived.
+///
+/// Each named stream (`GENERATION`, `EVOLUTION`, `BACKTEST` or any other
+/// name) gets its own seed, a hash of the master seed and the name, so the
+/// streams are independent of each other and of the order they are created
+/// in, and the same master seed reproduces all of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RngSource {
+    /// master seed
+    pub seed: u64,
+    /// generator of the streams
+    pub algorithm: RngAlgorithm,
+}
+
+impl RngSource {
+    /// Stream of model and n-gram generation.
+    pub const GENERATION: &'static str = "generation";
+    /// Stream of the scientist's evolutionary searches.
+    pub const EVOLUTION: &'static str = "evolution";
+    /// Stream of backtests, the strategy optimizer and synthetic market data
+    /// (`Config::backtest_seed`).
+    pub const BACKTEST: &'static str = "backtest";
+    /// Stream of topic clustering (`AI::knowledge_topics`).
+    pub const TOPICS: &'static str = "topics";
+
+    /// Source with master `seed` and `algorithm`.
+    pub fn new(seed: u64, algorithm: RngAlgorithm) -> Self {
+        Self { seed, algorithm }
+    }
+
+    /// Generator seeded with the master seed itself.
+    pub fn rng(&self) -> SessionRng {
+        SessionRng::new(self.algorithm, self.seed)
+    }
+
+    /// Seed of stream `name`, for APIs that take a `u64` seed.
+    pub fn stream_seed(&self, name: &str) -> u64 {
+        // FNV-1a of the name, mixed into the master seed by splitmix64
+        let name_hash = name.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |h, b| (h ^ b as u64).wrapping_mul(0x0100_0000_01b3));
+        let mut z = (self.seed ^ name_hash).wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Generator of stream `name`.
+    pub fn stream(&self, name: &str) -> SessionRng {
+        SessionRng::new(self.algorithm, self.stream_seed(name))
+    }
+}
+
+/// Generator of an `RngSource`, whichever the algorithm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionRng {
+    /// `RngAlgorithm::ChaCha8`
+    ChaCha8(ChaCha8Rng),
+    /// `RngAlgorithm::ChaCha20`
+    ChaCha20(ChaCha20Rng),
+}
+
+impl SessionRng {
+    fn new(algorithm: RngAlgorithm, seed: u64) -> Self {
+        match algorithm {
+            RngAlgorithm::ChaCha8 => SessionRng::ChaCha8(ChaCha8Rng::seed_from_u64(seed)),
+            RngAlgorithm::ChaCha20 => SessionRng::ChaCha20(ChaCha20Rng::seed_from_u64(seed)),
+        }
+    }
+}
+
+impl RngCore for SessionRng {
+    fn next_u32(&mut self) -> u32 {
+        match self {
+            SessionRng::ChaCha8(rng) => rng.next_u32(),
+            SessionRng::ChaCha20(rng) => rng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            SessionRng::ChaCha8(rng) => rng.next_u64(),
+            SessionRng::ChaCha20(rng) => rng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self {
+            SessionRng::ChaCha8(rng) => rng.fill_bytes(dest),
+            SessionRng::ChaCha20(rng) => rng.fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        match self {
+            SessionRng::ChaCha8(rng) => rng.try_fill_bytes(dest),
+            SessionRng::ChaCha20(rng) => rng.try_fill_bytes(dest),
+        }
+    }
+}
+
 /// A trivial arena allocator placeholder (not a real arena)
 pub struct Arena {
     // placeholder for future fast allocation
@@ -31,16 +135,28 @@ impl Arena {
     pub fn new(cap: usize) -> Self { Self { _cap: cap } }
 }
 
-/// Sample index from probabilities using provided RNG
-pub fn sample_index(probs: &[f32], rng: &mut ChaCha8Rng) -> usize {
+#[cfg(test)]
+mod tests {
+    use super::*;
     use rand::Rng;
-    let r: f32 = rng.gen();
-    let mut acc = 0.0_f32;
-    for (i, &p) in probs.iter().enumerate() {
-        acc += p;
-        if r <= acc {
-            return i;
-        }
+
+    #[test]
+    fn streams_are_reproducible_and_independent() {
+        let source = RngSource::new(7, RngAlgorithm::ChaCha8);
+        let draw = |mut rng: SessionRng| -> Vec<u64> { (0..4).map(|_| rng.gen()).collect() };
+        let mut plain = make_rng(7);
+        assert_eq!(draw(source.rng()), (0..4).map(|_| plain.gen::<u64>()).collect::<Vec<_>>());
+        assert_eq!(draw(source.stream(RngSource::GENERATION)), draw(RngSource::new(7, RngAlgorithm::ChaCha8).stream("generation")));
+
+        let mut seeds: Vec<u64> = [RngSource::GENERATION, RngSource::EVOLUTION, RngSource::BACKTEST].iter().map(|name| source.stream_seed(name)).collect();
+        seeds.push(source.seed);
+        seeds.sort_unstable();
+        seeds.dedup();
+        assert_eq!(seeds.len(), 4);
+        assert_ne!(source.stream_seed(RngSource::EVOLUTION), RngSource::new(8, RngAlgorithm::ChaCha8).stream_seed(RngSource::EVOLUTION));
+
+        let strong = RngSource::new(7, RngAlgorithm::ChaCha20);
+        assert_eq!(strong.stream_seed(RngSource::BACKTEST), source.stream_seed(RngSource::BACKTEST));
+        assert_ne!(draw(strong.stream(RngSource::BACKTEST)), draw(source.stream(RngSource::BACKTEST)));
     }
-    probs.len().saturating_sub(1)
 }
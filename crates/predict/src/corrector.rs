@@ -0,0 +1,170 @@
+//! Spelling corrector for decoded model output (Norvig-style).
+//!
+//! Each word of the text is replaced by the most frequent dictionary word
+//! among the first non-empty group of candidates: the word itself, known
+//! words one edit away, known words two edits away (an edit is a deletion,
+//! insertion, substitution or transposition of adjacent characters). The
+//! dictionary is the word-frequency memory (`memory_freq.csv`). Candidates
+//! are found by scanning the dictionary with a bounded edit distance, so the
+//! cost grows with the dictionary rather than with the alphabet.
+
+use std::collections::HashMap;
+
+use crate::memory_freq::WordFrequencyStore;
+
+/// Words shorter than this (in characters) are left alone: too many
+/// dictionary words are within two edits of them.
+pub const MIN_WORD_LEN: usize = 3;
+
+/// Largest number of edits between a word and its correction.
+pub const MAX_EDITS: usize = 2;
+
+/// Dictionary-backed word corrector.
+#[derive(Debug, Clone, Default)]
+pub struct Corrector {
+    freq: HashMap<String, usize>,
+}
+
+impl Corrector {
+    /// Corrector over a word → frequency map (words are lowercased, empty ones dropped).
+    pub fn new(freq: HashMap<String, usize>) -> Self {
+        let mut dictionary: HashMap<String, usize> = HashMap::with_capacity(freq.len());
+        for (word, count) in freq {
+            let word = word.trim().to_lowercase();
+            if !word.is_empty() {
+                *dictionary.entry(word).or_insert(0) += count;
+            }
+        }
+        Self { freq: dictionary }
+    }
+
+    /// Corrector over the word-frequency memory at `path` (empty if missing or unreadable).
+    pub fn from_memory_freq(path: &str) -> Self {
+        Self::new(WordFrequencyStore::load(path).map(WordFrequencyStore::into_counts).unwrap_or_default())
+    }
+
+    /// Number of dictionary words.
+    pub fn len(&self) -> usize {
+        self.freq.len()
+    }
+
+    /// Whether the dictionary is empty (then `correct` changes nothing).
+    pub fn is_empty(&self) -> bool {
+        self.freq.is_empty()
+    }
+
+    /// Whether `word` is in the dictionary (case-insensitive).
+    pub fn known(&self, word: &str) -> bool {
+        self.freq.contains_key(&word.to_lowercase())
+    }
+
+    /// Best correction of a single word, or `None` if it is known, too short
+    /// or has no dictionary word within `MAX_EDITS`. The result follows the
+    /// word's capitalization (all caps or first letter).
+    pub fn suggest(&self, word: &str) -> Option<String> {
+        let lower = word.to_lowercase();
+        let len = lower.chars().count();
+        if len < MIN_WORD_LEN || self.freq.contains_key(&lower) {
+            return None;
+        }
+        let mut best: Option<(usize, usize, &str)> = None;
+        for (candidate, &count) in &self.freq {
+            if candidate.chars().count().abs_diff(len) > MAX_EDITS {
+                continue;
+            }
+            let Some(distance) = bounded_distance(&lower, candidate, MAX_EDITS) else { continue };
+            // fewer edits first, then higher frequency, then alphabetical for determinism
+            let better = match best {
+                None => true,
+                Some((d, c, w)) => (distance, std::cmp::Reverse(count), candidate.as_str()) < (d, std::cmp::Reverse(c), w),
+            };
+            if better {
+                best = Some((distance, count, candidate));
+            }
+        }
+        best.map(|(_, _, candidate)| match_case(word, candidate))
+    }
+
+    /// Correct every word (run of letters) in `text`, keeping everything else as is.
+    pub fn correct(&self, text: &str) -> String {
+        if self.freq.is_empty() {
+            return text.to_string();
+        }
+        let mut out = String::with_capacity(text.len());
+        let mut word = String::new();
+        for c in text.chars().chain(std::iter::once('\0')) {
+            if c.is_alphabetic() {
+                word.push(c);
+                continue;
+            }
+            if !word.is_empty() {
+                out.push_str(&self.suggest(&word).unwrap_or_else(|| word.clone()));
+                word.clear();
+            }
+            if c != '\0' {
+                out.push(c);
+            }
+        }
+        out
+    }
+}
+
+/// Optimal-string-alignment distance between `a` and `b`, or `None` if it exceeds `max`.
+fn bounded_distance(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // rows i-2, i-1 and i of the dynamic-programming table
+    let mut before: Vec<usize> = Vec::new();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, &ca) in a.iter().enumerate() {
+        let mut row = vec![i + 1; b.len() + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = prev.get(j).copied().unwrap_or(usize::MAX).saturating_add(usize::from(ca != cb));
+            let deletion = prev.get(j + 1).copied().unwrap_or(usize::MAX).saturating_add(1);
+            let insertion = row.get(j).copied().unwrap_or(usize::MAX).saturating_add(1);
+            let mut cell = substitution.min(deletion).min(insertion);
+            let transposed = i > 0 && j > 0 && a.get(i - 1) == Some(&cb) && b.get(j - 1) == Some(&ca);
+            if transposed {
+                cell = cell.min(before.get(j - 1).copied().unwrap_or(usize::MAX).saturating_add(1));
+            }
+            if let Some(slot) = row.get_mut(j + 1) {
+                *slot = cell;
+            }
+        }
+        if row.iter().min().is_some_and(|&m| m > max) {
+            return None;
+        }
+        before = std::mem::replace(&mut prev, row);
+    }
+    prev.last().copied().filter(|&d| d <= max)
+}
+
+/// `candidate` (lowercase) with the capitalization of `original`.
+fn match_case(original: &str, candidate: &str) -> String {
+    let mut letters = original.chars().filter(|c| c.is_alphabetic());
+    let first_upper = letters.next().is_some_and(char::is_uppercase);
+    if first_upper && original.chars().count() > 1 && original.chars().all(|c| !c.is_lowercase()) {
+        return candidate.to_uppercase();
+    }
+    let mut chars = candidate.chars();
+    match chars.next() {
+        Some(first) if first_upper => first.to_uppercase().chain(chars).collect(),
+        _ => candidate.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn near_misses_snap_to_frequent_words() {
+        let freq = [("hello", 5), ("help", 1), ("world", 3), ("привет", 2)];
+        let corrector = Corrector::new(freq.iter().map(|(w, n)| (w.to_string(), *n)).collect());
+        assert_eq!(corrector.correct("Helo wrold, xyzzy! ok"), "Hello world, xyzzy! ok");
+        assert_eq!(corrector.correct("ПРИВТЕ"), "ПРИВЕТ");
+        assert_eq!(corrector.suggest("hello"), None);
+        assert_eq!(bounded_distance("kitten", "sitting", 3), Some(3));
+        assert_eq!(bounded_distance("kitten", "sitting", 2), None);
+    }
+}
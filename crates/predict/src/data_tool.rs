@@ -0,0 +1,210 @@
+#![forbid(unsafe_code)]
+
+//! CSV analysis for the chat: "проанализируй data/sales.csv" answers with the
+//! schema, the row count and per-column statistics (`stats::RunningStats`);
+//! "регрессия price от volume в data/sales.csv" also fits `price = a·volume + b`
+//! by least squares. Registered in `ToolRegistry::standard` as the `data` tool.
+
+use serde_json::{json, Value};
+
+use crate::intent::Intent;
+use crate::stats::RunningStats;
+use crate::tools::{csv_path, Tool};
+
+/// Words that ask for an analysis of a CSV file.
+const ANALYSIS_WORDS: [&str; 8] = ["анализ", "статистик", "опиши", "схем", "analy", "describe", "stats", "schema"];
+
+/// Words that ask for a regression between two columns.
+const REGRESSION_WORDS: [&str; 3] = ["регресс", "зависимость", "regress"];
+
+/// Header and rows of a CSV file (comma-separated, no quoted commas).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CsvTable {
+    /// column names from the first line
+    pub headers: Vec<String>,
+    /// remaining non-empty lines split into fields
+    pub rows: Vec<Vec<String>>,
+}
+
+impl CsvTable {
+    /// Parse CSV text; the first non-empty line is the header.
+    pub fn parse(text: &str) -> Self {
+        let split = |line: &str| line.split(',').map(|f| f.trim().trim_matches('"').to_string()).collect::<Vec<_>>();
+        let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+        let headers = lines.next().map(split).unwrap_or_default();
+        Self { headers, rows: lines.map(split).collect() }
+    }
+
+    /// Values of column `index`; empty fields are skipped.
+    pub fn values(&self, index: usize) -> impl Iterator<Item = &str> {
+        self.rows.iter().filter_map(move |r| r.get(index)).map(String::as_str).filter(|v| !v.is_empty())
+    }
+
+    /// Column `index` as numbers, or `None` if any non-empty field is not a number.
+    pub fn numbers(&self, index: usize) -> Option<Vec<f64>> {
+        self.values(index).map(|v| v.parse::<f64>().ok()).collect()
+    }
+}
+
+/// Statistics of one column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnSummary {
+    /// column name
+    pub name: String,
+    /// statistics of the values when every non-empty field is a number
+    pub stats: Option<RunningStats>,
+    /// number of distinct non-empty values
+    pub distinct: usize,
+}
+
+/// Summaries of all columns of `table`, in header order.
+pub fn describe(table: &CsvTable) -> Vec<ColumnSummary> {
+    (0..table.headers.len())
+        .filter_map(|i| {
+            let mut distinct: Vec<&str> = table.values(i).collect();
+            distinct.sort_unstable();
+            distinct.dedup();
+            Some(ColumnSummary {
+                name: table.headers.get(i)?.clone(),
+                stats: table.numbers(i).filter(|v| !v.is_empty()).map(|v| v.into_iter().collect()),
+                distinct: distinct.len(),
+            })
+        })
+        .collect()
+}
+
+/// Least-squares line `y = slope·x + intercept`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineFit {
+    /// slope
+    pub slope: f64,
+    /// value at x = 0
+    pub intercept: f64,
+    /// coefficient of determination (1 when y is constant and fitted exactly)
+    pub r2: f64,
+    /// number of (x, y) pairs
+    pub n: usize,
+}
+
+/// Fit `y = slope·x + intercept` over the pairs of `x` and `y`; `None` for
+/// fewer than two pairs or a constant `x`.
+pub fn fit_line(x: &[f64], y: &[f64]) -> Option<LineFit> {
+    let n = x.len().min(y.len());
+    if n < 2 {
+        return None;
+    }
+    let pairs = || x.iter().zip(y);
+    let (mx, my) = (pairs().map(|p| p.0).sum::<f64>() / n as f64, pairs().map(|p| p.1).sum::<f64>() / n as f64);
+    let sxx: f64 = pairs().map(|(a, _)| (a - mx).powi(2)).sum();
+    let sxy: f64 = pairs().map(|(a, b)| (a - mx) * (b - my)).sum();
+    if sxx == 0.0 {
+        return None;
+    }
+    let slope = sxy / sxx;
+    let intercept = my - slope * mx;
+    let ss_res: f64 = pairs().map(|(a, b)| (b - slope * a - intercept).powi(2)).sum();
+    let ss_tot: f64 = pairs().map(|(_, b)| (b - my).powi(2)).sum();
+    let r2 = if ss_tot == 0.0 { 1.0 } else { 1.0 - ss_res / ss_tot };
+    Some(LineFit { slope, intercept, r2, n })
+}
+
+/// Chat tool over `describe` and `fit_line`.
+pub struct DataTool;
+
+impl DataTool {
+    /// Regression of `y` on `x` over the rows where both are numbers.
+    fn regression(table: &CsvTable, y: usize, x: usize) -> Option<LineFit> {
+        let (xs, ys): (Vec<f64>, Vec<f64>) = table
+            .rows
+            .iter()
+            .filter_map(|r| Some((r.get(x)?.parse::<f64>().ok()?, r.get(y)?.parse::<f64>().ok()?)))
+            .unzip();
+        fit_line(&xs, &ys)
+    }
+}
+
+impl Tool for DataTool {
+    fn name(&self) -> &'static str {
+        "data"
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {"type": "string", "description": "CSV file with a header row"},
+                "y": {"type": "string", "description": "column to explain (regression)"},
+                "x": {"type": "string", "description": "explanatory column (regression)"}
+            },
+            "required": ["path"]
+        })
+    }
+
+    fn intents(&self) -> &'static [Intent] {
+        &[Intent::Research, Intent::Instruction]
+    }
+
+    fn run(&self, input: &str) -> Option<String> {
+        let path = csv_path(input)?;
+        // the path itself may contain a keyword or a column name ("stats.csv")
+        let query = input.to_lowercase().replace(&path.to_lowercase(), " ");
+        let regression = REGRESSION_WORDS.iter().any(|w| query.contains(w));
+        if !regression && !ANALYSIS_WORDS.iter().any(|w| query.contains(w)) {
+            return None;
+        }
+        let table = match std::fs::read_to_string(path) {
+            Ok(text) => CsvTable::parse(&text),
+            Err(e) => return Some(format!("⚠️ не удалось прочитать {}: {}", path, e)),
+        };
+        let mut lines = vec![format!("{}: {} столбцов, {} строк", path, table.headers.len(), table.rows.len())];
+        for column in describe(&table) {
+            lines.push(match column.stats {
+                Some(s) => format!(
+                    "- {} (число): среднее {:.4}, мин {:.4}, макс {:.4}, σ {:.4}",
+                    column.name,
+                    s.mean(),
+                    s.min().unwrap_or_default(),
+                    s.max().unwrap_or_default(),
+                    s.stddev()
+                ),
+                None => format!("- {} (текст): {} различных значений", column.name, column.distinct),
+            });
+        }
+        if regression {
+            // columns named in the prompt, in order of mention: the first is explained by the second
+            let mut named: Vec<(usize, usize)> =
+                (0..table.headers.len()).filter_map(|i| Some((query.find(&table.headers.get(i)?.to_lowercase())?, i))).collect();
+            named.sort_unstable();
+            lines.push(match named.as_slice() {
+                [(_, y), (_, x), ..] => match (Self::regression(&table, *y, *x), table.headers.get(*y), table.headers.get(*x)) {
+                    (Some(fit), Some(yn), Some(xn)) => format!(
+                        "регрессия: {} = {:.4}·{} + {:.4} (R² = {:.4}, n = {})",
+                        yn, fit.slope, xn, fit.intercept, fit.r2, fit.n
+                    ),
+                    _ => "⚠️ регрессия: нужны два числовых столбца с меняющимся x".to_string(),
+                },
+                _ => format!("⚠️ регрессия: назовите два столбца из {}", table.headers.join(", ")),
+            });
+        }
+        Some(lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_columns_and_fits_named_ones() {
+        let path = std::env::temp_dir().join(format!("shark_data_{}.csv", std::process::id()));
+        let _ = std::fs::write(&path, "city,volume,price\nA,1,3\nB,2,5\nA,3,7\n");
+        let output = DataTool.run(&format!("регрессия price от volume в {}", path.display()));
+        let _ = std::fs::remove_file(&path);
+        let output = output.unwrap_or_default();
+        assert!(output.contains("3 столбцов, 3 строк"));
+        assert!(output.contains("- city (текст): 2 различных значений"));
+        assert!(output.contains("- volume (число): среднее 2.0000, мин 1.0000, макс 3.0000, σ 1.0000"));
+        assert!(output.ends_with("регрессия: price = 2.0000·volume + 1.0000 (R² = 1.0000, n = 3)"));
+        assert!(DataTool.run("сколько будет 2 + 2").is_none());
+    }
+}
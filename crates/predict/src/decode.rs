@@ -1,3 +1,131 @@
+//! Decoding raw model output into presentable text.
+//!
+//! `decode_with` filters and normalizes according to a `DecodeOptions`
+//! policy; `decode_raw` is the original conservative ASCII-only filter
+//! (`DecodeOptions::legacy`).
+
+/// Message returned when nothing readable survives decoding.
+pub const UNDECODABLE: &str = "(не удалось расшифровать ответ)";
+
+/// Character classes a decoded answer may keep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Script {
+    /// ASCII letters `a-z`, `A-Z`
+    Latin,
+    /// Cyrillic letters (U+0400..=U+052F)
+    Cyrillic,
+    /// any other letter (accented Latin, Greek, CJK, ...)
+    OtherLetters,
+    /// digits `0-9`
+    Digits,
+    /// sentence punctuation `. , ? !`
+    Punctuation,
+    /// every other printable character: math operators, brackets, quotes, `:;`, ...
+    Symbols,
+}
+
+impl Script {
+    /// Every script (keep all printable text).
+    pub const ALL: [Script; 6] =
+        [Script::Latin, Script::Cyrillic, Script::OtherLetters, Script::Digits, Script::Punctuation, Script::Symbols];
+
+    /// Class of a non-whitespace, non-control character.
+    pub fn of(c: char) -> Script {
+        match c {
+            'a'..='z' | 'A'..='Z' => Script::Latin,
+            '\u{0400}'..='\u{052F}' => Script::Cyrillic,
+            '0'..='9' => Script::Digits,
+            '.' | ',' | '?' | '!' => Script::Punctuation,
+            c if c.is_alphabetic() => Script::OtherLetters,
+            _ => Script::Symbols,
+        }
+    }
+}
+
+/// Policy for `decode_with`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeOptions {
+    /// characters of other scripts are dropped (spaces are always kept)
+    pub allowed_scripts: Vec<Script>,
+    /// keep `\n`; otherwise newlines are handled like other whitespace
+    pub keep_newlines: bool,
+    /// turn whitespace other than spaces (and kept newlines) into spaces,
+    /// collapse runs of spaces and trim the ends; otherwise such whitespace is dropped
+    pub normalize_whitespace: bool,
+    /// appended when the text does not already end with `.`, `!`, `?` or `…`
+    pub terminal_punct: Option<char>,
+    /// upper-case the first character
+    pub capitalize: bool,
+}
+
+impl DecodeOptions {
+    /// The original `decode_raw` behavior: ASCII letters, digits, spaces and
+    /// `.,?!` only, first letter capitalized, a period appended.
+    pub fn legacy() -> Self {
+        Self {
+            allowed_scripts: vec![Script::Latin, Script::Digits, Script::Punctuation],
+            keep_newlines: false,
+            normalize_whitespace: false,
+            terminal_punct: Some('.'),
+            capitalize: true,
+        }
+    }
+
+    /// Keep all printable text (any script, newlines), only tidy whitespace.
+    pub fn unicode() -> Self {
+        Self {
+            allowed_scripts: Script::ALL.to_vec(),
+            keep_newlines: true,
+            normalize_whitespace: true,
+            terminal_punct: None,
+            capitalize: false,
+        }
+    }
+}
+
+impl Default for DecodeOptions {
+    /// `DecodeOptions::unicode`.
+    fn default() -> Self {
+        Self::unicode()
+    }
+}
+
+/// Decode raw model output according to `opts`. Control characters are
+/// always dropped. If nothing readable remains, returns `UNDECODABLE`.
+pub fn decode_with(raw: &str, opts: &DecodeOptions) -> String {
+    let mut output = String::with_capacity(raw.len());
+    for ch in raw.chars() {
+        if ch == '\n' && opts.keep_newlines {
+            output.push('\n');
+        } else if ch == ' ' || (ch.is_whitespace() && opts.normalize_whitespace) {
+            output.push(' ');
+        } else if !ch.is_whitespace() && !ch.is_control() && opts.allowed_scripts.contains(&Script::of(ch)) {
+            output.push(ch);
+        }
+    }
+    if opts.normalize_whitespace {
+        let lines: Vec<String> =
+            output.split('\n').map(|line| line.split(' ').filter(|w| !w.is_empty()).collect::<Vec<_>>().join(" ")).collect();
+        output = lines.join("\n").trim_matches('\n').to_string();
+    }
+
+    if output.is_empty() {
+        return UNDECODABLE.to_string();
+    }
+    if opts.capitalize {
+        let mut chars = output.chars();
+        if let Some(first) = chars.next() {
+            output = first.to_uppercase().chain(chars).collect();
+        }
+    }
+    if let Some(punct) = opts.terminal_punct {
+        if !output.ends_with(['.', '!', '?', '…']) {
+            output.push(punct);
+        }
+    }
+    output
+}
+
 /// Decode raw model output into a conservative, human-readable string.
 ///
 /// This function performs a minimal, lossy post-processing step suitable for
@@ -10,34 +138,27 @@
 /// the function returns a short fallback message.
 ///
 /// Note: this is intentionally conservative — it does not attempt to
-/// reconstruct words or correct grammar; for that, use a higher-level
-/// rule-based corrector or retraining pipeline.
+/// reconstruct words or correct grammar, and it drops non-ASCII text; use
+/// `decode_with` and `DecodeOptions::unicode` to keep other scripts.
 pub fn decode_raw(raw: &str) -> String {
-    // Фильтруем только разрешённые символы и восстанавливаем структуру предложения
-    let mut output = String::new();
-    for ch in raw.chars() {
-        match ch {
-            'a'..='z' | 'A'..='Z' | '0'..='9' | ' ' | '.' | ',' | '?' | '!' => output.push(ch),
-            _ => {} // игнорируем шумовые токены
-        }
-    }
+    decode_with(raw, &DecodeOptions::legacy())
+}
 
-    // Попробуем минимально нормализовать текст
-    if output.is_empty() {
-        return "(не удалось расшифровать ответ)".to_string();
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // Заглавная первая буква, точка в конце
-    let mut chars = output.chars();
-    if let Some(first) = chars.next() {
-        let mut result = String::new();
-        result.push(first.to_ascii_uppercase());
-        result.push_str(chars.as_str());
-        if !result.ends_with('.') && !result.ends_with('!') && !result.ends_with('?') {
-            result.push('.');
-        }
-        return result;
-    }
+    #[test]
+    fn legacy_preset_and_unicode_policy() {
+        assert_eq!(decode_raw("hello, wor#ld"), "Hello, world.");
+        assert_eq!(decode_raw("привет"), UNDECODABLE);
+        assert_eq!(decode_raw("ok!\n"), "Ok!");
 
-    output
+        let unicode = DecodeOptions::unicode();
+        assert_eq!(decode_with("  привет,\tмир  \n\n x^2 = 4 ", &unicode), "привет, мир\n\nx^2 = 4");
+        let latin_cyrillic = DecodeOptions { allowed_scripts: vec![Script::Cyrillic, Script::Latin], ..unicode };
+        assert_eq!(decode_with("ёж 42 hedgehog ™", &latin_cyrillic), "ёж hedgehog");
+        let sentence = DecodeOptions { capitalize: true, terminal_punct: Some('.'), ..DecodeOptions::unicode() };
+        assert_eq!(decode_with("это акула", &sentence), "Это акула.");
+    }
 }
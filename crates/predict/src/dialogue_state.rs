@@ -0,0 +1,171 @@
+#![forbid(unsafe_code)]
+
+//! Conversation state for follow-up questions.
+//!
+//! `DialogueState` remembers the current topic ("акула" after "что такое
+//! акула?") and the last expression (from the prompt, or from the answer when
+//! it is one), so `resolve` can rewrite a follow-up such as
+//! "а чему равен его интеграл?" into "а чему равен интеграл x^2?".
+//! `AI::chat` resolves every prompt before running the pipeline.
+
+use crate::extract::extract;
+use crate::teach::Fact;
+
+/// Possessive pronouns placed before the noun they refer from ("его интеграл").
+const POSSESSIVE: [&str; 5] = ["его", "её", "ее", "its", "их"];
+
+/// Pronouns standing for the referent itself.
+const PERSONAL: [&str; 13] = ["него", "неё", "нее", "он", "она", "оно", "это", "этого", "этому", "им", "ней", "нём", "it"];
+
+/// Words that make a follow-up about the expression rather than the topic.
+const MATH_WORDS: [&str; 12] =
+    ["интеграл", "производн", "корн", "корен", "равен", "равна", "посчитай", "вычисли", "упрости", "реши", "значение", "integral"];
+
+/// Prefixes introducing the topic of a question.
+const TOPIC_PREFIXES: [&str; 8] =
+    ["что такое", "кто такой", "кто такая", "что значит", "расскажи про", "расскажи о", "what is", "who is"];
+
+/// Topic and entities of the conversation so far.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DialogueState {
+    topic: Option<String>,
+    expression: Option<String>,
+    variables: Vec<char>,
+    turns: usize,
+    pending_fact: Option<Fact>,
+}
+
+impl DialogueState {
+    /// Empty state.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current topic, if a question named one.
+    pub fn topic(&self) -> Option<&str> {
+        self.topic.as_deref()
+    }
+
+    /// Last expression mentioned by the user or produced by an answer.
+    pub fn expression(&self) -> Option<&str> {
+        self.expression.as_deref()
+    }
+
+    /// Variables of the last expression.
+    pub fn variables(&self) -> &[char] {
+        &self.variables
+    }
+
+    /// Number of observed turns.
+    pub fn turns(&self) -> usize {
+        self.turns
+    }
+
+    /// Fact taught by the user and waiting for confirmation (`teach`).
+    pub fn pending_fact(&self) -> Option<&Fact> {
+        self.pending_fact.as_ref()
+    }
+
+    /// Ask for confirmation of `fact` on the next turn (replaces any earlier proposal).
+    pub fn propose_fact(&mut self, fact: Fact) {
+        self.pending_fact = Some(fact);
+    }
+
+    /// Take the pending fact, leaving none.
+    pub fn take_pending_fact(&mut self) -> Option<Fact> {
+        self.pending_fact.take()
+    }
+
+    /// Forget everything (new conversation).
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Update the state from one turn: the (resolved) prompt and the answer.
+    pub fn observe(&mut self, prompt: &str, answer: &str) {
+        self.turns += 1;
+        if let Some(topic) = topic_of(prompt) {
+            self.topic = Some(topic);
+        }
+        // the answer is the more recent expression ("упрости ..." → "x^2 - 4")
+        for text in [prompt, answer] {
+            let found = extract(text);
+            if let Some(expression) = found.expression {
+                self.expression = Some(expression);
+                self.variables = found.variables;
+            }
+        }
+    }
+
+    /// Replace pronouns in `prompt` with the referent: the last expression if
+    /// the prompt is about math (or there is no topic), else the topic.
+    /// Possessives move behind their noun ("его интеграл" → "интеграл x^2").
+    /// Returns `prompt` unchanged when there is nothing to refer to.
+    pub fn resolve(&self, prompt: &str) -> String {
+        let lower = prompt.to_lowercase();
+        let mathy = MATH_WORDS.iter().any(|w| lower.contains(w));
+        let referent = match (mathy, &self.expression, &self.topic) {
+            (true, Some(expression), _) | (false, Some(expression), None) => expression,
+            (_, _, Some(topic)) => topic,
+            _ => return prompt.to_string(),
+        };
+        let tokens: Vec<&str> = prompt.split_whitespace().collect();
+        let mut out: Vec<String> = Vec::with_capacity(tokens.len() + 1);
+        let mut i = 0;
+        while let Some(token) = tokens.get(i) {
+            let (word, punct) = split_punct(token);
+            let key = word.to_lowercase();
+            let next = tokens.get(i + 1).map(|t| split_punct(t)).filter(|(w, _)| w.chars().all(char::is_alphabetic) && !w.is_empty());
+            if let (true, Some((noun, noun_punct))) = (POSSESSIVE.contains(&key.as_str()), next) {
+                out.push(format!("{} {}{}", noun, referent, noun_punct));
+                i += 2;
+                continue;
+            }
+            // "акула — это рыба": after a dash "это" is the copula, not a pronoun
+            let copula = key == "это" && i > 0 && tokens.get(i - 1).is_some_and(|t| ["—", "–", "-"].contains(t));
+            if !copula && (POSSESSIVE.contains(&key.as_str()) || PERSONAL.contains(&key.as_str())) {
+                out.push(format!("{}{}", referent, punct));
+            } else {
+                out.push(token.to_string());
+            }
+            i += 1;
+        }
+        out.join(" ")
+    }
+}
+
+/// `token` split into the word and its trailing punctuation.
+fn split_punct(token: &str) -> (&str, &str) {
+    let end = token.trim_end_matches(|c: char| c.is_ascii_punctuation() && c != '^' && c != ')').len();
+    (token.get(..end).unwrap_or(token), token.get(end..).unwrap_or(""))
+}
+
+/// Topic named by a question ("что такое акула?" → "акула").
+fn topic_of(prompt: &str) -> Option<String> {
+    let lower = prompt.trim().to_lowercase();
+    let rest = TOPIC_PREFIXES.iter().find_map(|p| lower.strip_prefix(p))?;
+    let topic = rest.trim().trim_end_matches(['?', '!', '.']).trim();
+    (!topic.is_empty()).then(|| topic.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follow_ups_refer_to_the_previous_expression_or_topic() {
+        let mut state = DialogueState::new();
+        assert_eq!(state.resolve("а чему равен его интеграл?"), "а чему равен его интеграл?");
+
+        state.observe("упрости (x+2)*(x-2)", "x^2 - 4");
+        assert_eq!(state.expression(), Some("x^2 - 4"));
+        assert_eq!(state.resolve("а чему равен его интеграл?"), "а чему равен интеграл x^2 - 4?");
+
+        state.observe("что такое акула?", "хрящевая рыба");
+        assert_eq!(state.topic(), Some("акула"));
+        assert_eq!(state.resolve("где она живёт?"), "где акула живёт?");
+        assert_eq!(state.resolve("посчитай его значение при x = 1"), "посчитай значение x^2 - 4 при x = 1");
+        assert_eq!(state.resolve("скат — это рыба"), "скат — это рыба");
+        assert_eq!(state.turns(), 2);
+    }
+}
@@ -0,0 +1,87 @@
+#![forbid(unsafe_code)]
+
+//! Error type shared by the loaders, persistence helpers and configuration of the crate.
+
+use std::path::{Path, PathBuf};
+
+/// Errors from loading or saving model weights, dialog memory and knowledge.
+#[derive(Debug, thiserror::Error)]
+pub enum SharkError {
+    /// A file could not be read or written
+    #[error("{}: {source}", path.display())]
+    Io {
+        /// file involved
+        path: PathBuf,
+        /// underlying IO error
+        source: std::io::Error,
+    },
+    /// A weights file holds fewer floats than the model layout needs
+    #[error("{}: weights too small: {found} floats, {expected} expected", path.display())]
+    WeightsTooSmall {
+        /// weights file
+        path: PathBuf,
+        /// floats required by the layer shapes
+        expected: usize,
+        /// floats present in the file
+        found: usize,
+    },
+    /// A file exists but its contents cannot be decoded
+    #[error("{}: corrupt data: {reason}", path.display())]
+    Corrupt {
+        /// file involved
+        path: PathBuf,
+        /// decoder message
+        reason: String,
+    },
+    /// A configuration file is not valid TOML or has unknown keys
+    #[error("{}: invalid configuration: {reason}", path.display())]
+    Config {
+        /// configuration file
+        path: PathBuf,
+        /// parser message
+        reason: String,
+    },
+    /// `pipeline.stages` names a stage that does not exist
+    #[error("unknown pipeline stage `{0}`")]
+    UnknownStage(String),
+    /// A plugin manifest enables a plugin that was not registered (or has an empty command)
+    #[error("unknown plugin `{0}`")]
+    UnknownPlugin(String),
+    /// A model name that is neither `default` nor registered (`[[models]]`)
+    #[error("unknown model `{0}`")]
+    UnknownModel(String),
+    /// No record (or more than one) in the replay log matches the id
+    #[error("no unique replay record `{0}`")]
+    UnknownReplay(String),
+    /// A speech backend is unavailable or one of its commands failed
+    #[error("audio `{backend}`: {reason}")]
+    Audio {
+        /// backend name (`audio::Audio::name`)
+        backend: &'static str,
+        /// what went wrong
+        reason: String,
+    },
+    /// A moderation deny pattern is not a valid regular expression
+    #[error("invalid moderation pattern `{pattern}`: {reason}")]
+    InvalidPattern {
+        /// pattern as configured
+        pattern: String,
+        /// regex parser message
+        reason: String,
+    },
+}
+
+impl SharkError {
+    /// Wrap an IO error with the path it happened on.
+    pub fn io(path: impl AsRef<Path>, source: std::io::Error) -> Self {
+        SharkError::Io { path: path.as_ref().to_path_buf(), source }
+    }
+
+    /// Whether the error is a missing file (callers may treat that as "start fresh").
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, SharkError::Io { source, .. } if source.kind() == std::io::ErrorKind::NotFound)
+    }
+}
+
+/// Result alias used by the crate's loaders.
+pub type Result<T> = std::result::Result<T, SharkError>;
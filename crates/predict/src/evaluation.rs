@@ -0,0 +1,175 @@
+#![forbid(unsafe_code)]
+
+//! Evaluation of named datasets with a score history, on demand
+//! (`POST /evaluate` on the server) or nightly (`[jobs] evaluate`).
+//!
+//! A dataset is named by its file stem in `data.dir`: `problems` is
+//! `problems.json`, `problems.csv` or `problems.jsonl`, the first that exists
+//! (formats as in `train::load_suite`). `run` answers its tasks with
+//! `train::eval_answer` and, unlike `train::evaluate_suite`, only measures:
+//! failures are not queued as unknowns. Every run is appended to
+//! `<state_dir>/evaluations.jsonl`, so the score of a dataset can be followed
+//! over time (`history`, `GET /evaluate`). A run scoring below the previous
+//! one of its dataset carries a `regression` note; the nightly job then
+//! fails, which shows in `/jobs`.
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use evalharness::{CategoryScore, Report};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::train;
+use crate::AI;
+
+/// History file in `data.state_dir`.
+pub const HISTORY_FILE: &str = "evaluations.jsonl";
+
+/// Dataset extensions, in lookup order.
+const EXTENSIONS: [&str; 3] = ["json", "csv", "jsonl"];
+
+/// Summary of one evaluation, as stored in the history.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Run {
+    /// dataset name
+    pub dataset: String,
+    /// when the run finished (RFC 3339)
+    pub finished: String,
+    /// number of tasks
+    pub total: usize,
+    /// tasks passed
+    pub passed: usize,
+    /// fraction passed
+    pub score: f64,
+    /// per-category scores
+    pub categories: BTreeMap<String, CategoryScore>,
+}
+
+impl Run {
+    fn new(dataset: &str, report: &Report) -> Self {
+        Self {
+            dataset: dataset.to_string(),
+            finished: chrono::Utc::now().to_rfc3339(),
+            total: report.total,
+            passed: report.passed,
+            score: report.score(),
+            categories: report.categories.clone(),
+        }
+    }
+}
+
+/// A run with the previous one of the same dataset.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Outcome {
+    /// this run
+    pub run: Run,
+    /// the last earlier run of the dataset, if any
+    pub previous: Option<Run>,
+    /// what got worse since `previous` (see `regression`)
+    pub regression: Option<String>,
+}
+
+/// Path of the dataset `name` in `config.data.dir`. Names are file stems of
+/// letters, digits, `_` and `-` only, so they cannot leave the directory.
+pub fn dataset_path(config: &Config, name: &str) -> Result<PathBuf, String> {
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') {
+        return Err(format!("недопустимое имя набора: {:?}", name));
+    }
+    EXTENSIONS
+        .iter()
+        .map(|ext| config.data.dir.join(format!("{}.{}", name, ext)))
+        .find(|path| path.is_file())
+        .ok_or_else(|| format!("набор {:?} не найден в {}", name, config.data.dir.display()))
+}
+
+/// Path of the history file of `config`.
+pub fn history_path(config: &Config) -> PathBuf {
+    config.data.state_dir.join(HISTORY_FILE)
+}
+
+/// Recorded runs, oldest first, of `dataset` (or of all datasets); a missing
+/// file gives none and malformed lines are skipped.
+pub fn history(config: &Config, dataset: Option<&str>) -> Vec<Run> {
+    let text = std::fs::read_to_string(history_path(config)).unwrap_or_default();
+    text.lines()
+        .filter_map(|line| serde_json::from_str::<Run>(line).ok())
+        .filter(|run| dataset.is_none_or(|d| run.dataset == d))
+        .collect()
+}
+
+/// Append `run` to the history of `config`.
+pub fn record(config: &Config, run: &Run) -> io::Result<()> {
+    let path = history_path(config);
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let line = serde_json::to_string(run).map_err(io::Error::other)?;
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&path)?;
+    writeln!(file, "{}", line)
+}
+
+/// What got worse from `previous` to `run`: the overall score and every
+/// category passing fewer of its tasks. `None` when nothing dropped.
+pub fn regression(previous: &Run, run: &Run) -> Option<String> {
+    let mut worse = Vec::new();
+    if run.score < previous.score {
+        worse.push(format!("{}: {:.1}% → {:.1}%", run.dataset, 100.0 * previous.score, 100.0 * run.score));
+    }
+    for (name, before) in &previous.categories {
+        let after = run.categories.get(name).copied().unwrap_or_default();
+        if after.score() < before.score() {
+            worse.push(format!("{}: {}/{} → {}/{}", name, before.passed, before.total, after.passed, after.total));
+        }
+    }
+    (!worse.is_empty()).then(|| format!("регрессия — {}", worse.join(", ")))
+}
+
+/// Evaluate the dataset `name` with `ai` and record the run in the history
+/// of `ai.config` (a failed write is reported on stderr, the run is still returned).
+pub fn run(ai: &mut AI, name: &str) -> Result<Outcome, String> {
+    let path = dataset_path(&ai.config, name)?;
+    let suite = train::load_suite(&path.to_string_lossy())?;
+    let report = evalharness::run_suite(&suite, |task| train::eval_answer(ai, &task.question).text);
+    let run = Run::new(name, &report);
+    let previous = history(&ai.config, Some(name)).pop();
+    if let Err(e) = record(&ai.config, &run) {
+        eprintln!("⚠️ {}: {}", history_path(&ai.config).display(), e);
+    }
+    let regression = previous.as_ref().and_then(|p| regression(p, &run));
+    Ok(Outcome { run, previous, regression })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testkit::{self, FakeModel};
+
+    #[test]
+    fn runs_are_recorded_and_regressions_reported() {
+        let dir = std::env::temp_dir().join(format!("shark_evaluation_{}", std::process::id()));
+        let ai = testkit::ai(&[("акула", "хрящевая рыба")], FakeModel::new(["не знаю"]));
+        assert_eq!(ai.as_ref().err().map(ToString::to_string), None);
+        let Ok(mut ai) = ai else { return };
+        ai.config.data.dir = dir.clone();
+        ai.config.data.state_dir = dir.join("state");
+        assert!(std::fs::create_dir_all(&dir).is_ok());
+        assert!(std::fs::write(dir.join("fish.csv"), "question,expected,category\nакула,хрящевая рыба,рыбы\nскат,хрящевая рыба,рыбы\n").is_ok());
+
+        assert!(run(&mut ai, "../fish").is_err() && run(&mut ai, "birds").is_err());
+        let first = run(&mut ai, "fish");
+        assert_eq!(first.as_ref().map(|o| (o.run.passed, o.run.total, o.previous.is_none())), Ok((1, 2, true)));
+
+        // a stricter expectation: the next run is a regression of the dataset and its category
+        assert!(std::fs::write(dir.join("fish.csv"), "question,expected,category\nакула,акула,рыбы\n").is_ok());
+        let second = run(&mut ai, "fish");
+        assert_eq!(second.as_ref().map(|o| (o.run.passed, o.run.total)), Ok((0, 1)));
+        let Ok(second) = second else { return };
+        assert_eq!(second.previous.as_ref().map(|p| p.passed), Some(1));
+        assert_eq!(second.regression.as_deref(), Some("регрессия — fish: 50.0% → 0.0%, рыбы: 1/2 → 0/1"));
+        assert_eq!(history(&ai.config, Some("fish")).len(), 2);
+        assert!(history(&ai.config, Some("birds")).is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
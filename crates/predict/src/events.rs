@@ -0,0 +1,173 @@
+#![forbid(unsafe_code)]
+
+//! Event bus for extensions: the core publishes typed `Event`s (a prompt
+//! arrived, an answer was produced, knowledge was added, the scientist found a
+//! formula, a backtest trade was closed) and subscribers registered with
+//! `EventBus::subscribe` receive them, so logging, metrics, front-ends and
+//! plugins can react without changes to the core.
+//!
+//! The core publishes on the process-wide `bus()`; subscribers run
+//! synchronously on the publishing thread and may publish themselves.
+
+use std::sync::{Arc, Mutex, OnceLock};
+
+use serde::Serialize;
+
+use crate::response::Source;
+
+/// Something that happened in the core.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// `AI::chat` received a prompt (after follow-up resolution).
+    MessageReceived {
+        /// prompt text
+        prompt: String,
+    },
+    /// `AI::chat` answered.
+    AnswerProduced {
+        /// prompt text
+        prompt: String,
+        /// answer text
+        text: String,
+        /// pipeline stage that answered
+        stage: &'static str,
+        /// producer of the answer
+        source: Source,
+        /// calibrated confidence
+        confidence: f32,
+        /// time spent producing the answer, in milliseconds
+        latency_ms: f64,
+    },
+    /// A question/answer pair was appended to a knowledge file.
+    KnowledgeAdded {
+        /// question
+        question: String,
+        /// answer
+        answer: String,
+    },
+    /// The scientist recorded a formula.
+    DiscoveryMade {
+        /// formula as printed
+        formula: String,
+        /// its mean squared error
+        mse: f64,
+    },
+    /// A backtest closed a round trip.
+    TradeExecuted {
+        /// entry price (slippage included)
+        entry_price: f64,
+        /// exit price (slippage included)
+        exit_price: f64,
+        /// net pnl of the trade
+        net_pnl: f64,
+    },
+}
+
+impl Event {
+    /// Variant name, as in the serialized `event` field (e.g. `answer_produced`).
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Event::MessageReceived { .. } => "message_received",
+            Event::AnswerProduced { .. } => "answer_produced",
+            Event::KnowledgeAdded { .. } => "knowledge_added",
+            Event::DiscoveryMade { .. } => "discovery_made",
+            Event::TradeExecuted { .. } => "trade_executed",
+        }
+    }
+}
+
+/// Handle returned by `subscribe`, for `unsubscribe`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+type Subscriber = Arc<dyn Fn(&Event) + Send + Sync>;
+
+/// Subscribers of published events.
+#[derive(Default)]
+pub struct EventBus {
+    subscribers: Mutex<Vec<(SubscriptionId, Subscriber)>>,
+    next_id: Mutex<u64>,
+}
+
+impl EventBus {
+    /// Bus without subscribers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call `subscriber` for every event published from now on.
+    pub fn subscribe(&self, subscriber: impl Fn(&Event) + Send + Sync + 'static) -> SubscriptionId {
+        let id = match self.next_id.lock() {
+            Ok(mut next) => {
+                *next += 1;
+                SubscriptionId(*next)
+            }
+            Err(_) => SubscriptionId(0),
+        };
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push((id, Arc::new(subscriber)));
+        }
+        id
+    }
+
+    /// Remove a subscriber; `false` if it was not subscribed.
+    pub fn unsubscribe(&self, id: SubscriptionId) -> bool {
+        let Ok(mut subscribers) = self.subscribers.lock() else { return false };
+        let before = subscribers.len();
+        subscribers.retain(|(s, _)| *s != id);
+        subscribers.len() != before
+    }
+
+    /// Number of subscribers.
+    pub fn len(&self) -> usize {
+        self.subscribers.lock().map(|s| s.len()).unwrap_or(0)
+    }
+
+    /// Whether nobody is subscribed.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Deliver `event` to every subscriber, in subscription order.
+    pub fn publish(&self, event: &Event) {
+        // snapshot, so subscribers can (un)subscribe or publish without deadlocking
+        let subscribers: Vec<Subscriber> =
+            self.subscribers.lock().map(|s| s.iter().map(|(_, f)| Arc::clone(f)).collect()).unwrap_or_default();
+        for subscriber in subscribers {
+            subscriber(event);
+        }
+    }
+}
+
+/// The process-wide bus the core publishes on.
+pub fn bus() -> &'static EventBus {
+    static BUS: OnceLock<EventBus> = OnceLock::new();
+    BUS.get_or_init(EventBus::new)
+}
+
+/// Publish on `bus()`.
+pub fn publish(event: Event) {
+    bus().publish(&event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subscribers_receive_events_until_unsubscribed() {
+        let bus = EventBus::new();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        let id = bus.subscribe(move |e| sink.lock().iter_mut().for_each(|s| s.push(e.kind())));
+
+        bus.publish(&Event::KnowledgeAdded { question: "q".to_string(), answer: "a".to_string() });
+        assert!(bus.unsubscribe(id) && !bus.unsubscribe(id));
+        bus.publish(&Event::DiscoveryMade { formula: "x".to_string(), mse: 0.0 });
+        assert_eq!(seen.lock().map(|s| s.clone()).unwrap_or_default(), vec!["knowledge_added"]);
+
+        let json = serde_json::to_string(&Event::MessageReceived { prompt: "hi".to_string() }).unwrap_or_default();
+        assert_eq!(json, r#"{"event":"message_received","prompt":"hi"}"#);
+    }
+}
@@ -0,0 +1,163 @@
+#![forbid(unsafe_code)]
+
+//! Shareable export of a conversation.
+//!
+//! A `Session` collects the exchanges of a conversation: question, answer,
+//! source, confidence and the reasoning lines of the stage that answered.
+//! `export_transcript` renders it as one self-contained file through
+//! `reports::Report`: HTML with inline SVG, or Markdown with the charts as
+//! `data:` URIs. An answer ending in a sparkline (the `indicator` tool) gets
+//! its chart, as the GUI draws it. Front-ends: `/export FILE.md|FILE.html` in
+//! the REPL, the "Экспорт" buttons of the GUI chat and `POST /export` on the
+//! server, which renders the session sent in the request.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::reports::{LineChart, Report};
+use crate::response::{ChatResponse, Source};
+use crate::tools;
+
+/// Title of exported transcripts.
+pub const TITLE: &str = "Shark-Core — стенограмма";
+
+/// Output format of `export_transcript`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// Markdown, charts as `data:` URIs
+    #[default]
+    Markdown,
+    /// standalone HTML page, charts as inline SVG
+    Html,
+}
+
+impl Format {
+    /// `Html` for `.html`/`.htm` files, `Markdown` otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase).as_deref() {
+            Some("html" | "htm") => Format::Html,
+            _ => Format::Markdown,
+        }
+    }
+
+    /// MIME type of the rendered file.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Format::Markdown => "text/markdown; charset=utf-8",
+            Format::Html => "text/html; charset=utf-8",
+        }
+    }
+}
+
+/// One question and its answer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Exchange {
+    /// the user's prompt
+    pub question: String,
+    /// the answer shown
+    pub answer: String,
+    /// producer of the answer
+    pub source: Source,
+    /// confidence of the answer (0..=1)
+    pub confidence: f32,
+    /// explanation lines of the stage that answered
+    #[serde(default)]
+    pub reasoning: Vec<String>,
+}
+
+impl Exchange {
+    /// Exchange of `question` answered by `response`.
+    pub fn from_response(question: &str, response: &ChatResponse) -> Self {
+        Self {
+            question: question.to_string(),
+            answer: response.text.clone(),
+            source: response.source,
+            confidence: response.confidence,
+            reasoning: response.reasoning.clone(),
+        }
+    }
+
+    /// Chart of the sparkline the answer ends with, if any.
+    fn plot(&self) -> Option<LineChart> {
+        let levels = tools::sparkline_levels(&self.answer).filter(|levels| levels.len() > 1)?;
+        Some(LineChart::new("график").series("уровень", levels.iter().enumerate().map(|(i, &l)| (i as f64, f64::from(l)))))
+    }
+}
+
+/// Exchanges of a conversation, in order.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Session {
+    /// the exchanges
+    pub exchanges: Vec<Exchange>,
+}
+
+impl Session {
+    /// Record `question` answered by `response`.
+    pub fn push(&mut self, question: &str, response: &ChatResponse) {
+        self.exchanges.push(Exchange::from_response(question, response));
+    }
+}
+
+/// `session` as one self-contained file in `format`: a heading per question,
+/// the answer, its source and confidence, the reasoning and any chart.
+pub fn export_transcript(session: &Session, format: Format) -> String {
+    let mut report = Report::new(TITLE)
+        .metric("экспортировано", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"))
+        .metric("вопросов", session.exchanges.len());
+    for (i, exchange) in session.exchanges.iter().enumerate() {
+        report = report
+            .heading(format!("{}. {}", i + 1, exchange.question))
+            .text(exchange.answer.clone())
+            .metric("источник", exchange.source.as_str())
+            .metric("уверенность", format!("{:.2}", exchange.confidence));
+        if !exchange.reasoning.is_empty() {
+            report = report.text("Рассуждение:").list(&exchange.reasoning);
+        }
+        if let Some(plot) = exchange.plot() {
+            report = report.chart(plot);
+        }
+    }
+    match format {
+        Format::Markdown => report.to_markdown_inline(),
+        Format::Html => report.to_html(),
+    }
+}
+
+/// Write `session` to `path` in the format its extension names (`Format::from_path`).
+pub fn write_transcript(session: &Session, path: &Path) -> std::io::Result<()> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, export_transcript(session, Format::from_path(path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn transcripts_include_sources_reasoning_and_plots() {
+        let mut session = Session::default();
+        let mut solved = ChatResponse::new("4".to_string(), Source::Reasoner, 1.0, Instant::now());
+        solved.reasoning = vec!["2+2 = 4".to_string(), "<проверено>".to_string()];
+        session.push("2+2", &solved);
+        session.push("rsi", &ChatResponse::new("RSI(14): 55.0 ▁▃▅█".to_string(), Source::Reasoner, 0.9, Instant::now()));
+
+        let md = export_transcript(&session, Format::Markdown);
+        assert!(md.starts_with("# Shark-Core — стенограмма\n") && md.contains("- вопросов: 2\n"), "{}", md);
+        assert!(md.contains("## 1. 2+2\n\n4\n\n- источник: reasoner\n- уверенность: 1.00\n\nРассуждение:\n\n- 2+2 = 4\n"), "{}", md);
+        assert_eq!(md.matches("](data:image/svg+xml,").count(), 1);
+
+        let html = export_transcript(&session, Format::Html);
+        assert!(html.contains("<h2>2. rsi</h2>") && html.contains("<li>&lt;проверено&gt;</li>"), "{}", html);
+        assert_eq!(html.matches("<polyline").count(), 1);
+
+        assert_eq!(Format::from_path(Path::new("chat.HTML")), Format::Html);
+        assert_eq!(Format::from_path(Path::new("chat.md")), Format::Markdown);
+        let parsed: Result<Session, _> = serde_json::from_str(r#"{"exchanges": [{"question": "q", "answer": "a", "source": "model", "confidence": 0.5}]}"#);
+        assert_eq!(parsed.map(|s| s.exchanges.len()).ok(), Some(1));
+    }
+}
@@ -0,0 +1,224 @@
+//! Entity and number extraction from prompts.
+//!
+//! `extract` pulls numbers, quantities with units, single-letter variables,
+//! date expressions, an `от A до B` range and the arithmetic expression out
+//! of a prompt, so handlers read typed fields instead of cutting strings.
+
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Units recognised after a number (longest first, matched case-insensitively).
+pub const UNITS: [&str; 32] = [
+    "км/ч", "km/h", "м/с", "m/s", "мм", "см", "км", "кг", "мг", "мин", "сек", "мс", "°c", "°f", "mm", "cm", "km", "kg",
+    "mg", "ms", "min", "руб", "usd", "eur", "м", "г", "с", "ч", "m", "g", "s", "%",
+];
+
+/// A number followed by a unit, e.g. `5 кг`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantity {
+    /// numeric value
+    pub value: f64,
+    /// unit as written in `UNITS` (lowercase)
+    pub unit: String,
+}
+
+/// A date mentioned in a prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateExpr {
+    /// `2024-05-01` or `01.05.2024`
+    Date {
+        /// year
+        year: i32,
+        /// 1..=12
+        month: u32,
+        /// 1..=31
+        day: u32,
+    },
+    /// `1999 год`, `в 1999 году`, `in 1999`
+    Year(i32),
+    /// days from today: `вчера` (-1), `сегодня` (0), `завтра` (1), `послезавтра` (2)
+    Relative(i64),
+}
+
+/// Everything `extract` found in one prompt.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Extraction {
+    /// every number, in order (`-` right before a number counts as a sign
+    /// only at the start or after a space, `(` or `=`)
+    pub numbers: Vec<f64>,
+    /// numbers directly followed by a unit
+    pub quantities: Vec<Quantity>,
+    /// single ASCII letters next to a digit or operator (`x` in `2x + 3 = 7`),
+    /// or lone `x`, `y`, `z`; deduplicated in order
+    pub variables: Vec<char>,
+    /// date expressions, in order
+    pub dates: Vec<DateExpr>,
+    /// bounds of `от A до B` / `from A to B`
+    pub range: Option<(f64, f64)>,
+    /// longest arithmetic expression (digits, variables, operators, parentheses), trimmed
+    pub expression: Option<String>,
+}
+
+fn regex(cell: &'static OnceLock<Option<Regex>>, pattern: &str) -> Option<&'static Regex> {
+    cell.get_or_init(|| Regex::new(pattern).ok()).as_ref()
+}
+
+fn parse_number(text: &str) -> Option<f64> {
+    text.replace(',', ".").parse().ok()
+}
+
+/// Extract the entities of `prompt`.
+pub fn extract(prompt: &str) -> Extraction {
+    let lower = prompt.to_lowercase();
+    Extraction {
+        numbers: numbers(&lower),
+        quantities: quantities(&lower),
+        variables: variables(&lower),
+        dates: dates(&lower),
+        range: range(&lower),
+        expression: expression(&lower),
+    }
+}
+
+fn numbers(text: &str) -> Vec<f64> {
+    static NUMBER: OnceLock<Option<Regex>> = OnceLock::new();
+    let Some(re) = regex(&NUMBER, r"(?:^|[\s(=])-\d+(?:[.,]\d+)?|\d+(?:[.,]\d+)?") else { return Vec::new() };
+    re.find_iter(text).filter_map(|m| parse_number(m.as_str().trim_start_matches(|c: char| c.is_whitespace() || c == '(' || c == '='))).collect()
+}
+
+fn quantities(text: &str) -> Vec<Quantity> {
+    static QUANTITY: OnceLock<Option<Regex>> = OnceLock::new();
+    let Some(re) = regex(&QUANTITY, r"(-?\d+(?:[.,]\d+)?)\s?([^\s\d.,!?;:()]+)") else { return Vec::new() };
+    let mut found = Vec::new();
+    for caps in re.captures_iter(text) {
+        let (Some(value), Some(tail)) = (caps.get(1), caps.get(2)) else { continue };
+        let tail = tail.as_str();
+        // the unit must be the whole word (`5 м`, not the `м` of `5 минут` unless it is `мин`)
+        let unit = UNITS.iter().find(|u| {
+            tail.strip_prefix(**u).is_some_and(|rest| rest.is_empty() || !rest.starts_with(char::is_alphabetic))
+        });
+        if let (Some(unit), Some(value)) = (unit, parse_number(value.as_str())) {
+            found.push(Quantity { value, unit: unit.to_string() });
+        }
+    }
+    found
+}
+
+fn variables(text: &str) -> Vec<char> {
+    let chars: Vec<char> = text.chars().collect();
+    let mathy = |c: Option<&char>| c.is_some_and(|c| c.is_ascii_digit() || "+-*/^=()".contains(*c));
+    let mut found = Vec::new();
+    for (i, c) in chars.iter().enumerate() {
+        if !c.is_ascii_lowercase() {
+            continue;
+        }
+        let before = i.checked_sub(1).and_then(|j| chars.get(j));
+        let after = chars.get(i + 1);
+        if before.is_some_and(|b| b.is_alphabetic()) || after.is_some_and(|a| a.is_alphabetic()) {
+            continue;
+        }
+        // next non-space neighbours on both sides
+        let left = chars.get(..i).and_then(|s| s.iter().rev().find(|c| !c.is_whitespace()));
+        let right = chars.get(i + 1..).and_then(|s| s.iter().find(|c| !c.is_whitespace()));
+        if (mathy(left) || mathy(right) || "xyz".contains(*c)) && !found.contains(c) {
+            found.push(*c);
+        }
+    }
+    found
+}
+
+fn dates(text: &str) -> Vec<DateExpr> {
+    static DATE: OnceLock<Option<Regex>> = OnceLock::new();
+    let pattern = r"(\d{4})-(\d{2})-(\d{2})|(\d{1,2})\.(\d{1,2})\.(\d{4})|\b(?:в|in)\s+(\d{4})(?:\s+году)?\b|(\d{4})\s+год|(позавчера|вчера|сегодня|послезавтра|завтра|yesterday|today|tomorrow)";
+    let Some(re) = regex(&DATE, pattern) else { return Vec::new() };
+    let int = |caps: &regex::Captures<'_>, i: usize| caps.get(i).and_then(|m| m.as_str().parse::<i64>().ok());
+    let mut found = Vec::new();
+    for caps in re.captures_iter(text) {
+        let date = |y: usize, m: usize, d: usize| {
+            let (year, month, day) = (int(&caps, y)?, int(&caps, m)?, int(&caps, d)?);
+            let valid = (1..=12).contains(&month) && (1..=31).contains(&day);
+            valid.then_some(DateExpr::Date { year: year as i32, month: month as u32, day: day as u32 })
+        };
+        let expr = if caps.get(1).is_some() {
+            date(1, 2, 3)
+        } else if caps.get(4).is_some() {
+            date(6, 5, 4)
+        } else if let Some(year) = int(&caps, 7).or_else(|| int(&caps, 8)) {
+            Some(DateExpr::Year(year as i32))
+        } else {
+            caps.get(9).map(|word| {
+                DateExpr::Relative(match word.as_str() {
+                    "позавчера" => -2,
+                    "вчера" | "yesterday" => -1,
+                    "завтра" | "tomorrow" => 1,
+                    "послезавтра" => 2,
+                    _ => 0,
+                })
+            })
+        };
+        found.extend(expr);
+    }
+    found
+}
+
+fn range(text: &str) -> Option<(f64, f64)> {
+    static RANGE: OnceLock<Option<Regex>> = OnceLock::new();
+    let re = regex(&RANGE, r"(?:от|from)\s+([+-]?\d+(?:[.,]\d+)?)\s+(?:до|to)\s+([+-]?\d+(?:[.,]\d+)?)")?;
+    let caps = re.captures(text)?;
+    Some((parse_number(caps.get(1)?.as_str())?, parse_number(caps.get(2)?.as_str())?))
+}
+
+fn expression(text: &str) -> Option<String> {
+    let allowed = |c: char| c.is_ascii_alphanumeric() || " .,+-*/^()=".contains(c);
+    let mut best: Option<String> = None;
+    for run in text.split(|c: char| !allowed(c)) {
+        // drop the words around the expression ("what is 5 + 5", "solve 2x + 1 = 9")
+        let tokens: Vec<&str> = run.split_whitespace().collect();
+        let is_word = |t: &&str| t.len() > 1 && t.chars().all(|c| c.is_ascii_alphabetic());
+        let start = tokens.iter().position(|t| !is_word(t)).unwrap_or(tokens.len());
+        let end = tokens.iter().rposition(|t| !is_word(t)).map_or(start, |i| i + 1);
+        let candidate = tokens.get(start..end).unwrap_or_default().join(" ");
+        let candidate = candidate.trim_matches(|c: char| c == ',' || c == '=' || c.is_whitespace()).to_string();
+        // a leading sign alone (`-1`) does not make an expression
+        let has_operator = candidate.trim_start_matches(['-', '+']).contains(['+', '-', '*', '/', '^', '=']);
+        let has_operand = candidate.chars().any(|c| c.is_ascii_alphanumeric());
+        if has_operator && has_operand && best.as_ref().is_none_or(|b| candidate.len() > b.len()) {
+            best = Some(candidate);
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_typed_entities() {
+        let ex = extract("Посчитай интеграл x^2 от -1 до 2,5");
+        assert_eq!(ex.expression.as_deref(), Some("x^2"));
+        assert_eq!(ex.range, Some((-1.0, 2.5)));
+        assert_eq!(ex.variables, vec!['x']);
+        assert_eq!(ex.numbers, vec![2.0, -1.0, 2.5]);
+
+        assert_eq!(extract("solve 2x + 1 = 9").expression.as_deref(), Some("2x + 1 = 9"));
+        assert_eq!(extract("what is 5 + 5?").expression.as_deref(), Some("5 + 5"));
+        assert_eq!(crate::integrator::try_integrate("интеграл x от -1 до 1").as_deref(), Some("Интеграл x от -1 до 1 = 0.0000"));
+
+        let ex = extract("машина проехала 120 км за 2 ч со скоростью 60 км/ч, 5 минут");
+        let units: Vec<(f64, &str)> = ex.quantities.iter().map(|q| (q.value, q.unit.as_str())).collect();
+        assert_eq!(units, vec![(120.0, "км"), (2.0, "ч"), (60.0, "км/ч")]);
+        assert!(ex.variables.is_empty());
+
+        let ex = extract("встреча 2024-05-01, потом 02.06.2024, завтра и в 1999 году");
+        assert_eq!(
+            ex.dates,
+            vec![
+                DateExpr::Date { year: 2024, month: 5, day: 1 },
+                DateExpr::Date { year: 2024, month: 6, day: 2 },
+                DateExpr::Relative(1),
+                DateExpr::Year(1999),
+            ]
+        );
+    }
+}
@@ -1,4 +1,12 @@
 use std::collections::HashMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, SharkError};
+
+/// JSON grammar used by `build_grammar` when present.
+pub const GRAMMAR_JSON: &str = "crates/predict/data/grammar.json";
 
 /// Load grammar mappings from a CSV file.
 ///
@@ -6,6 +14,8 @@ use std::collections::HashMap;
 /// and value is the word/phrase to map to. The first line (header) is skipped.
 /// If the file can't be read, an empty map is returned.
 /// For long values, only the first word is kept for brevity.
+/// `Grammar::load_json` has no such limits (multi-character tokens, full
+/// values, weights and categories).
 pub fn load_grammar_from_csv(path: &str) -> HashMap<char, String> {
     let mut map = HashMap::new();
     if let Ok(content) = std::fs::read_to_string(path) {
@@ -34,28 +44,195 @@ pub fn build_grammar_map() -> HashMap<char, String> {
     load_grammar_from_csv("crates/predict/data/knowledge/knowledge_alphabet.csv")
 }
 
+/// One grammar rule: a token of one or more characters and what it stands for.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GrammarEntry {
+    /// characters to match in the raw text (case-insensitive)
+    pub token: String,
+    /// word or phrase the token maps to
+    pub value: String,
+    /// how much one occurrence counts in `interpret_contextual`
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+    /// optional group, e.g. "greeting" or "math"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+/// Grammar file layout: `{"entries": [{"token": "sh", "value": "акула", "weight": 2, "category": "animal"}]}`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GrammarFile {
+    /// grammar rules; for duplicate tokens the last one wins
+    pub entries: Vec<GrammarEntry>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    children: HashMap<char, usize>,
+    entry: Option<usize>,
+}
+
+/// Compiled grammar: a trie over the lowercase tokens, matched greedily
+/// (longest token first) from left to right.
+#[derive(Debug, Clone)]
+pub struct Grammar {
+    entries: Vec<GrammarEntry>,
+    nodes: Vec<Node>,
+}
+
+impl Default for Grammar {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl Grammar {
+    /// Compile `entries`; empty tokens are ignored.
+    pub fn new(entries: Vec<GrammarEntry>) -> Self {
+        let mut grammar = Self { entries: Vec::with_capacity(entries.len()), nodes: vec![Node::default()] };
+        for entry in entries.into_iter().filter(|e| !e.token.is_empty()) {
+            grammar.insert(entry);
+        }
+        grammar
+    }
+
+    fn insert(&mut self, entry: GrammarEntry) {
+        let mut node = 0;
+        for c in entry.token.chars().flat_map(char::to_lowercase) {
+            let next = self.nodes.len();
+            let Some(current) = self.nodes.get_mut(node) else { return };
+            node = *current.children.entry(c).or_insert(next);
+            if node == next {
+                self.nodes.push(Node::default());
+            }
+        }
+        let index = self.entries.len();
+        if let Some(end) = self.nodes.get_mut(node) {
+            end.entry = Some(index);
+            self.entries.push(entry);
+        }
+    }
+
+    /// Parse a `GrammarFile` JSON document.
+    pub fn from_json(text: &str) -> std::result::Result<Self, serde_json::Error> {
+        let file: GrammarFile = serde_json::from_str(text)?;
+        Ok(Self::new(file.entries))
+    }
+
+    /// Read a `GrammarFile` from `path`.
+    pub fn load_json(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(|e| SharkError::io(path, e))?;
+        Self::from_json(&text).map_err(|e| SharkError::Corrupt { path: path.into(), reason: e.to_string() })
+    }
+
+    /// Number of distinct tokens.
+    pub fn len(&self) -> usize {
+        self.nodes.iter().filter(|n| n.entry.is_some()).count()
+    }
+
+    /// Whether no token is defined.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Entries for the tokens found in `raw`, in order. At each position the
+    /// longest matching token is taken; characters starting no token are skipped.
+    pub fn matches(&self, raw: &str) -> Vec<&GrammarEntry> {
+        let chars: Vec<char> = raw.chars().flat_map(char::to_lowercase).collect();
+        let mut found = Vec::new();
+        let mut start = 0;
+        while start < chars.len() {
+            let mut node = 0;
+            let mut longest = None;
+            for (offset, c) in chars.iter().skip(start).enumerate() {
+                let Some(&next) = self.nodes.get(node).and_then(|n| n.children.get(c)) else { break };
+                node = next;
+                if let Some(entry) = self.nodes.get(node).and_then(|n| n.entry) {
+                    longest = Some((entry, offset + 1));
+                }
+            }
+            match longest.and_then(|(entry, len)| Some((self.entries.get(entry)?, len))) {
+                Some((entry, len)) => {
+                    found.push(entry);
+                    start += len;
+                }
+                None => start += 1,
+            }
+        }
+        found
+    }
+}
+
+impl From<&HashMap<char, String>> for Grammar {
+    /// Compile a single-character map (`load_grammar_from_csv`) with unit weights.
+    fn from(map: &HashMap<char, String>) -> Self {
+        Self::new(
+            map.iter()
+                .map(|(c, v)| GrammarEntry { token: c.to_string(), value: v.clone(), weight: 1.0, category: None })
+                .collect(),
+        )
+    }
+}
+
+/// The grammar used by `interpret`: `GRAMMAR_JSON` if it exists (an invalid
+/// file is reported on stderr), else the alphabet map from `build_grammar_map`.
+pub fn build_grammar() -> Grammar {
+    let path = Path::new(GRAMMAR_JSON);
+    if path.exists() {
+        match Grammar::load_json(path) {
+            Ok(grammar) => return grammar,
+            Err(e) => eprintln!("⚠️ {}", e),
+        }
+    }
+    Grammar::from(&build_grammar_map())
+}
+
 /// Translate a "raw" (decoded) answer into an approximate phrase based on
-/// the loaded grammar map.
+/// the loaded grammar (`build_grammar`).
 ///
-/// The function walks characters in `raw` and appends mapped words when a
-/// character is present in the grammar map. Words are separated by spaces.
+/// The function walks `raw` and appends the mapped words of the tokens it
+/// finds. Words are separated by spaces.
 /// If no mapping is found, a fallback message is returned indicating that
 /// the answer is unclear and retraining may be required.
 pub fn interpret(raw: &str) -> String {
-    let map = build_grammar_map();
-    let mut result = String::new();
-
-    for ch in raw.chars() {
-        let lower_ch = ch.to_ascii_lowercase();
-        if let Some(word) = map.get(&lower_ch) {
-            result.push_str(word);
-            result.push(' ');
-        }
-    }
+    interpret_with(raw, &build_grammar())
+}
 
-    if result.is_empty() {
+/// `interpret` with an explicit grammar.
+pub fn interpret_with(raw: &str, grammar: &Grammar) -> String {
+    let words: Vec<&str> = grammar.matches(raw).iter().map(|e| e.value.as_str()).collect();
+    if words.is_empty() {
         "(непонятный ответ — требуется переобучение)".to_string()
     } else {
-        result.trim().to_string()
+        words.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_grammar_matches_longest_tokens() {
+        let json = r#"{"entries": [
+            {"token": "s", "value": "эс"},
+            {"token": "sh", "value": "акула", "weight": 2.5, "category": "animal"},
+            {"token": "Привет", "value": "приветствие", "category": "greeting"}
+        ]}"#;
+        let grammar = Grammar::from_json(json);
+        assert!(grammar.is_ok());
+        let Ok(grammar) = grammar else { return };
+        assert_eq!(grammar.len(), 3);
+        let values: Vec<&str> = grammar.matches("SHs привет!").iter().map(|e| e.value.as_str()).collect();
+        assert_eq!(values, vec!["акула", "эс", "приветствие"]);
+        assert_eq!(grammar.matches("sh").first().map(|e| e.weight), Some(2.5));
+        assert_eq!(interpret_with("xyz", &grammar), "(непонятный ответ — требуется переобучение)");
+        assert_eq!(crate::interpret_contextual("shsh s", &grammar), "акула, эс");
+        assert!(Grammar::from_json(r#"{"rules": []}"#).is_err());
     }
 }
@@ -0,0 +1,346 @@
+#![forbid(unsafe_code)]
+
+//! Guessing a hidden integer from "higher / lower" answers.
+//!
+//! A `GuessSolver` keeps the candidates of its range as segments, each with
+//! the number of answers it contradicts. A `GuessStrategy` picks the next
+//! guess among the live candidates: `Binary` takes the median (the classic
+//! halving search), `Random` a seeded uniform candidate, and `Noisy` a
+//! median weighted by `2^-errors`, keeping candidates that contradict up to
+//! `lies` answers, so a few wrong answers do not lose the target. An
+//! `Ordering::Equal` answer is always trusted.
+//!
+//! `GuessGame` is the chat tool built on it ("загадай число от 1 до 1000").
+
+use std::cmp::Ordering;
+use std::ops::RangeInclusive;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// How `GuessSolver` picks its next guess.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum GuessStrategy {
+    /// median of the candidates: at most `log2(range) + 1` guesses
+    #[default]
+    Binary,
+    /// uniform random candidate from a ChaCha8 stream seeded with `seed`
+    Random {
+        /// RNG seed (the same seed and answers give the same guesses)
+        seed: u64,
+    },
+    /// median weighted by `2^-errors`, tolerating up to `lies` wrong answers
+    Noisy {
+        /// wrong "higher / lower" answers the target may have received
+        lies: u32,
+    },
+}
+
+/// Candidates `low..=high` that contradict `errors` answers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Segment {
+    low: i64,
+    high: i64,
+    errors: u32,
+}
+
+impl Segment {
+    fn len(&self) -> u128 {
+        (i128::from(self.high) - i128::from(self.low) + 1) as u128
+    }
+}
+
+/// Guesses an integer of a range from the answers to its guesses.
+#[derive(Clone, Debug)]
+pub struct GuessSolver {
+    range: RangeInclusive<i64>,
+    strategy: GuessStrategy,
+    segments: Vec<Segment>,
+    rng: ChaCha8Rng,
+    guesses: usize,
+    found: Option<i64>,
+}
+
+impl GuessSolver {
+    /// Solver for a target in `range` with the `Binary` strategy; an empty
+    /// range has no guesses.
+    pub fn new(range: RangeInclusive<i64>) -> Self {
+        let segments = if range.is_empty() { Vec::new() } else { vec![Segment { low: *range.start(), high: *range.end(), errors: 0 }] };
+        Self { range, strategy: GuessStrategy::Binary, segments, rng: ChaCha8Rng::seed_from_u64(0), guesses: 0, found: None }
+    }
+
+    /// Use `strategy` (before the first guess).
+    pub fn strategy(mut self, strategy: GuessStrategy) -> Self {
+        if let GuessStrategy::Random { seed } = strategy {
+            self.rng = ChaCha8Rng::seed_from_u64(seed);
+        }
+        self.strategy = strategy;
+        self
+    }
+
+    /// The range the target is in.
+    pub fn range(&self) -> RangeInclusive<i64> {
+        self.range.clone()
+    }
+
+    /// Answers recorded so far.
+    pub fn guesses(&self) -> usize {
+        self.guesses
+    }
+
+    /// The guess answered with `Ordering::Equal`, if any.
+    pub fn found(&self) -> Option<i64> {
+        self.found
+    }
+
+    /// Wrong answers a candidate may contradict and stay live.
+    fn lies(&self) -> u32 {
+        match self.strategy {
+            GuessStrategy::Noisy { lies } => lies,
+            _ => 0,
+        }
+    }
+
+    /// Next guess; None once the target is found or when the answers leave
+    /// no candidate (more wrong answers than the strategy tolerates).
+    pub fn next_guess(&mut self) -> Option<i64> {
+        if self.found.is_some() {
+            return None;
+        }
+        match self.strategy {
+            GuessStrategy::Random { .. } => {
+                let total: u128 = self.segments.iter().map(Segment::len).sum();
+                if total == 0 {
+                    return None;
+                }
+                let mut r = self.rng.gen_range(0..total);
+                for s in &self.segments {
+                    if r < s.len() {
+                        return Some((i128::from(s.low) + r as i128) as i64);
+                    }
+                    r -= s.len();
+                }
+                None
+            }
+            GuessStrategy::Binary | GuessStrategy::Noisy { .. } => self.weighted_median(),
+        }
+    }
+
+    /// First candidate where the cumulative `2^-errors` weight reaches half
+    /// the total (the lower median for `Binary`).
+    fn weighted_median(&self) -> Option<i64> {
+        let weight = |s: &Segment| 0.5_f64.powi(s.errors as i32);
+        let half = self.segments.iter().map(|s| s.len() as f64 * weight(s)).sum::<f64>() / 2.0;
+        let mut before = 0.0;
+        for s in &self.segments {
+            let mass = s.len() as f64 * weight(s);
+            if before + mass >= half {
+                let offset = (((half - before) / weight(s)).ceil() - 1.0).clamp(0.0, (s.len() - 1) as f64);
+                return Some((i128::from(s.low) + offset as i128) as i64);
+            }
+            before += mass;
+        }
+        self.segments.last().map(|s| s.high)
+    }
+
+    /// Record the `answer` to `guess`, as `guess.cmp(&target)`: `Less` when
+    /// the target is higher, `Greater` when it is lower.
+    pub fn feedback(&mut self, guess: i64, answer: Ordering) {
+        self.guesses += 1;
+        // candidates the answer rules out
+        let (from, to) = match answer {
+            Ordering::Equal => {
+                self.found = Some(guess);
+                return;
+            }
+            Ordering::Less => (i64::MIN, guess),
+            Ordering::Greater => (guess, i64::MAX),
+        };
+        let lies = self.lies();
+        let mut segments: Vec<Segment> = Vec::with_capacity(self.segments.len() + 2);
+        let mut push = |s: Segment| {
+            if s.errors > lies {
+                return;
+            }
+            match segments.last_mut() {
+                Some(last) if last.errors == s.errors && last.high.checked_add(1) == Some(s.low) => last.high = s.high,
+                _ => segments.push(s),
+            }
+        };
+        for s in &self.segments {
+            let (low, high) = (s.low.max(from), s.high.min(to));
+            if low > high {
+                push(*s);
+                continue;
+            }
+            if s.low < low {
+                push(Segment { high: low - 1, ..*s });
+            }
+            push(Segment { low, high, errors: s.errors + 1 });
+            if high < s.high {
+                push(Segment { low: high + 1, ..*s });
+            }
+        }
+        self.segments = segments;
+    }
+
+    /// Guess until `feedback` (like `guess.cmp(&target)`) answers `Equal`;
+    /// None when the answers leave no candidate.
+    pub fn solve<F: FnMut(i64) -> Ordering>(&mut self, mut feedback: F) -> Option<i64> {
+        while let Some(guess) = self.next_guess() {
+            self.feedback(guess, feedback(guess));
+        }
+        self.found
+    }
+}
+
+/// Chat tool playing the guessing game: "загадай число" (optionally "от A
+/// до B", 1..=100 by default) starts a game with a `Binary` solver, and
+/// while it runs "больше", "меньше" and "угадал" answer its guesses ("стоп"
+/// ends it). Other prompts are left to the other tools.
+#[cfg(feature = "fs")]
+#[derive(Default)]
+pub struct GuessGame {
+    /// solver of the running game and its pending guess
+    game: std::sync::Mutex<Option<(GuessSolver, i64)>>,
+}
+
+#[cfg(feature = "fs")]
+impl GuessGame {
+    /// Range used when the prompt gives none.
+    pub const DEFAULT_RANGE: RangeInclusive<i64> = 1..=100;
+    /// Phrases starting a game.
+    pub const START: [&'static str; 3] = ["загадай число", "угадай число", "я загадал число"];
+}
+
+#[cfg(feature = "fs")]
+impl crate::tools::Tool for GuessGame {
+    fn name(&self) -> &'static str {
+        "guess"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({"type": "string", "description": "загадай число [от A до B], затем больше / меньше / угадал"})
+    }
+
+    fn intents(&self) -> &'static [crate::intent::Intent] {
+        &[crate::intent::Intent::Instruction, crate::intent::Intent::SmallTalk]
+    }
+
+    fn run(&self, input: &str) -> Option<String> {
+        let lower = input.trim().to_lowercase();
+        let mut game = self.game.lock().ok()?;
+        if Self::START.iter().any(|s| lower.contains(s)) {
+            let range = regex::Regex::new(r"от\s*(-?\d+)\s*до\s*(-?\d+)")
+                .ok()
+                .and_then(|re| re.captures(&lower))
+                .and_then(|caps| Some((caps.get(1)?.as_str().parse::<i64>().ok()?, caps.get(2)?.as_str().parse::<i64>().ok()?)))
+                .map_or(Self::DEFAULT_RANGE, |(a, b)| a.min(b)..=a.max(b));
+            let mut solver = GuessSolver::new(range.clone());
+            let guess = solver.next_guess()?;
+            *game = Some((solver, guess));
+            return Some(format!(
+                "Загадайте целое число от {} до {}, я угадаю. Это {}? Отвечайте «больше», «меньше» или «угадал».",
+                range.start(),
+                range.end(),
+                guess
+            ));
+        }
+        let (solver, guess) = game.as_mut()?;
+        let answer = match lower.trim_end_matches(['!', '.']) {
+            "больше" | "выше" => Ordering::Less,
+            "меньше" | "ниже" => Ordering::Greater,
+            "угадал" | "да" | "верно" => Ordering::Equal,
+            "стоп" | "сдаюсь" => {
+                *game = None;
+                return Some("Игра окончена.".to_string());
+            }
+            _ => return None,
+        };
+        solver.feedback(*guess, answer);
+        if let Some(next) = solver.next_guess() {
+            *guess = next;
+            return Some(format!("Это {}?", next));
+        }
+        let reply = match solver.found() {
+            Some(n) => format!("Угадал: {} (попыток: {}).", n, solver.guesses()),
+            None => "⚠️ ответы противоречат друг другу: подходящих чисел не осталось. Игра окончена.".to_string(),
+        };
+        *game = None;
+        Some(reply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strategies_find_every_target() {
+        // binary search over 0..=99 needs at most 7 guesses and starts at 49
+        for target in 0..=99 {
+            let mut solver = GuessSolver::new(0..=99);
+            assert_eq!(solver.next_guess(), Some(49));
+            assert_eq!(solver.solve(|n| n.cmp(&target)), Some(target));
+            assert!(solver.guesses() <= 7);
+        }
+        let mut wide = GuessSolver::new(-1_000_000..=1_000_000);
+        assert_eq!(wide.solve(|n| n.cmp(&-123_456)), Some(-123_456));
+        let mut full = GuessSolver::new(i64::MIN..=i64::MAX);
+        assert_eq!(full.solve(|n| n.cmp(&i64::MAX)), Some(i64::MAX));
+
+        for seed in 0..5 {
+            let mut random = GuessSolver::new(-20..=20).strategy(GuessStrategy::Random { seed });
+            assert_eq!(random.solve(|n| n.cmp(&13)), Some(13));
+        }
+        let replay = |seed| {
+            let mut solver = GuessSolver::new(0..=1000).strategy(GuessStrategy::Random { seed });
+            let mut guesses = Vec::new();
+            solver.solve(|n| {
+                guesses.push(n);
+                n.cmp(&777)
+            });
+            guesses
+        };
+        assert_eq!(replay(9), replay(9));
+
+        // the first answer is a lie: binary loses the target, noisy does not
+        let liar = |target: i64| {
+            let mut lied = false;
+            move |n: i64| match n.cmp(&target) {
+                Ordering::Equal => Ordering::Equal,
+                answer if !lied => {
+                    lied = true;
+                    answer.reverse()
+                }
+                answer => answer,
+            }
+        };
+        assert_eq!(GuessSolver::new(0..=99).solve(liar(70)), None);
+        for target in [0, 37, 70, 99] {
+            let mut noisy = GuessSolver::new(0..=99).strategy(GuessStrategy::Noisy { lies: 1 });
+            assert_eq!(noisy.solve(liar(target)), Some(target));
+        }
+        assert_eq!(GuessSolver::new(std::ops::RangeInclusive::new(5, 4)).next_guess(), None);
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn guess_game_plays_in_chat() {
+        use crate::tools::Tool;
+        let game = GuessGame::default();
+        assert_eq!(game.run("больше"), None);
+        let started = game.run("Загадай число от 1 до 10").unwrap_or_default();
+        assert!(started.starts_with("Загадайте целое число от 1 до 10") && started.contains("Это 5?"));
+        assert_eq!(game.run("больше").as_deref(), Some("Это 8?"));
+        assert_eq!(game.run("привет"), None);
+        assert_eq!(game.run("меньше").as_deref(), Some("Это 6?"));
+        assert_eq!(game.run("больше").as_deref(), Some("Это 7?"));
+        assert_eq!(game.run("угадал").as_deref(), Some("Угадал: 7 (попыток: 4)."));
+        assert_eq!(game.run("больше"), None);
+
+        let _ = game.run("загадай число от 1 до 2");
+        let _ = game.run("больше");
+        assert!(game.run("меньше").is_some_and(|r| r.contains("противоречат")));
+    }
+}
@@ -37,20 +37,17 @@ pub fn try_integrate(query: &str) -> Option<String> {
     if !query.to_lowercase().contains("интеграл") {
         return None;
     }
-    // try to find pattern: интеграл <expr> от <a> до <b>
-    // use a regex to extract expr, a, b
-    // find expr between 'интеграл' and 'от'
-    let re = Regex::new(r"(?i)интеграл\s+([^\s]+)\s+от\s+([+-]?\d+(?:[\.,]\d+)?)\s+до\s+([+-]?\d+(?:[\.,]\d+)?)").unwrap();
-    if let Some(caps) = re.captures(query) {
-        let expr = caps.get(1).map(|m| m.as_str()).unwrap_or("x");
-        let a_s = caps.get(2).map(|m| m.as_str()).unwrap_or("0");
-        let b_s = caps.get(3).map(|m| m.as_str()).unwrap_or("0");
-        let a = a_s.replace(',', ".").parse::<f64>().unwrap_or(0.0);
-        let b = b_s.replace(',', ".").parse::<f64>().unwrap_or(0.0);
-        let expr_trim = expr.trim();
-        if let Some(res) = integrate_polynomial(expr_trim, a, b) {
-            return Some(format!("Интеграл {} от {} до {} = {:.4}", expr_trim, a, b, res));
-        }
+    // pattern: интеграл <expr> от <a> до <b>; the integrand is the expression,
+    // else the lone variable, else the constant that is not a bound
+    let ex = crate::extract::extract(query);
+    let (a, b) = ex.range?;
+    let integrand = ex
+        .expression
+        .filter(|e| !e.contains(' '))
+        .or_else(|| ex.variables.first().map(char::to_string))
+        .or_else(|| ex.numbers.first().filter(|_| ex.numbers.len() > 2).map(f64::to_string))?;
+    if let Some(res) = integrate_polynomial(&integrand, a, b) {
+        return Some(format!("Интеграл {} от {} до {} = {:.4}", integrand, a, b, res));
     }
     None
 }
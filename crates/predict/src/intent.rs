@@ -0,0 +1,243 @@
+//! Intent classification: a multinomial logistic regression over character
+//! trigrams, trained from a labeled CSV (`data/intents.csv`, `text,intent`).
+//!
+//! `classify` uses the built-in classifier trained once from the shipped CSV;
+//! `IntentClassifier::load_csv` and `train` build one from other examples.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::core::softmax;
+use crate::error::{Result, SharkError};
+
+/// Labeled examples shipped with the crate (embedded, so `classify` works from any directory).
+pub const INTENTS_CSV: &str = "crates/predict/data/intents.csv";
+
+const BUILTIN_EXAMPLES: &str = include_str!("../data/intents.csv");
+
+/// Below this confidence callers should treat the prediction as unknown.
+pub const MIN_CONFIDENCE: f32 = 0.35;
+
+/// What a prompt asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Intent {
+    /// asks for a fact or an explanation ("что такое акула?")
+    Question,
+    /// asks to do something ("покажи пример", "напиши функцию")
+    Instruction,
+    /// arithmetic, equations, simplification
+    Math,
+    /// experiments and law discovery (`scientist`)
+    Research,
+    /// greetings, thanks, fillers
+    SmallTalk,
+}
+
+impl Intent {
+    /// Every intent, in the order of the classifier's outputs.
+    pub const ALL: [Intent; 5] = [Intent::Question, Intent::Instruction, Intent::Math, Intent::Research, Intent::SmallTalk];
+
+    /// Label used in the CSV.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Intent::Question => "question",
+            Intent::Instruction => "instruction",
+            Intent::Math => "math",
+            Intent::Research => "research",
+            Intent::SmallTalk => "smalltalk",
+        }
+    }
+}
+
+impl fmt::Display for Intent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Intent {
+    type Err = String;
+
+    /// Case-insensitive CSV label (`small_talk` is accepted too).
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let label = s.trim().to_lowercase().replace(['_', '-', ' '], "");
+        Intent::ALL.into_iter().find(|i| i.as_str() == label).ok_or_else(|| format!("unknown intent `{}`", s.trim()))
+    }
+}
+
+/// Classifier output.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IntentPrediction {
+    /// most probable intent
+    pub intent: Intent,
+    /// its probability, 0..=1
+    pub confidence: f32,
+}
+
+impl IntentPrediction {
+    /// `Some(intent)` if `confidence >= MIN_CONFIDENCE`.
+    pub fn confident(self) -> Option<Intent> {
+        (self.confidence >= MIN_CONFIDENCE).then_some(self.intent)
+    }
+}
+
+/// Training hyper-parameters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrainOptions {
+    /// passes over the examples
+    pub epochs: usize,
+    /// SGD step size
+    pub learning_rate: f32,
+    /// L2 penalty applied to the weights touched by each example
+    pub l2: f32,
+}
+
+impl Default for TrainOptions {
+    fn default() -> Self {
+        Self { epochs: 40, learning_rate: 0.5, l2: 1e-4 }
+    }
+}
+
+const CLASSES: usize = Intent::ALL.len();
+
+/// Softmax regression over bag-of-trigram features.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IntentClassifier {
+    weights: HashMap<String, [f32; CLASSES]>,
+    bias: [f32; CLASSES],
+}
+
+impl IntentClassifier {
+    /// Train on `(text, intent)` pairs with SGD (deterministic: examples are
+    /// visited in order).
+    pub fn train(examples: &[(String, Intent)], opts: TrainOptions) -> Self {
+        let mut classifier = Self::default();
+        let featurized: Vec<(Vec<(String, f32)>, usize)> =
+            examples.iter().map(|(text, intent)| (features(text), class_index(*intent))).collect();
+        for _ in 0..opts.epochs {
+            for (feats, target) in &featurized {
+                let probs = classifier.probabilities(feats);
+                for (class, p) in probs.iter().enumerate() {
+                    let gradient = p - if class == *target { 1.0 } else { 0.0 };
+                    if let Some(b) = classifier.bias.get_mut(class) {
+                        *b -= opts.learning_rate * gradient;
+                    }
+                    for (feat, value) in feats {
+                        let row = classifier.weights.entry(feat.clone()).or_insert([0.0; CLASSES]);
+                        if let Some(w) = row.get_mut(class) {
+                            *w -= opts.learning_rate * (gradient * value + opts.l2 * *w);
+                        }
+                    }
+                }
+            }
+        }
+        classifier
+    }
+
+    /// Parse `text,intent` lines (a `text,intent` header is skipped; the label
+    /// is after the last comma, so texts may contain commas).
+    pub fn parse_examples(csv: &str) -> std::result::Result<Vec<(String, Intent)>, String> {
+        let mut examples = Vec::new();
+        for (number, line) in csv.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || (number == 0 && line.eq_ignore_ascii_case("text,intent")) {
+                continue;
+            }
+            let (text, label) = line.rsplit_once(',').ok_or_else(|| format!("line {}: expected `text,intent`", number + 1))?;
+            let intent = label.parse::<Intent>().map_err(|e| format!("line {}: {}", number + 1, e))?;
+            examples.push((text.trim().trim_matches('"').to_string(), intent));
+        }
+        Ok(examples)
+    }
+
+    /// Train with default options on the examples in the CSV at `path`.
+    pub fn load_csv(path: &Path) -> Result<Self> {
+        let csv = std::fs::read_to_string(path).map_err(|e| SharkError::io(path, e))?;
+        let examples = Self::parse_examples(&csv).map_err(|reason| SharkError::Corrupt { path: path.into(), reason })?;
+        Ok(Self::train(&examples, TrainOptions::default()))
+    }
+
+    /// Classifier trained on the shipped examples (`INTENTS_CSV`).
+    pub fn builtin() -> Self {
+        Self::train(&Self::parse_examples(BUILTIN_EXAMPLES).unwrap_or_default(), TrainOptions::default())
+    }
+
+    /// Probability of each intent (in `Intent::ALL` order).
+    pub fn scores(&self, text: &str) -> [f32; CLASSES] {
+        self.probabilities(&features(text))
+    }
+
+    /// Most probable intent of `text`.
+    pub fn classify(&self, text: &str) -> IntentPrediction {
+        let scores = self.scores(text);
+        let (best, confidence) =
+            scores.iter().enumerate().fold((0, f32::MIN), |acc, (i, &p)| if p > acc.1 { (i, p) } else { acc });
+        IntentPrediction { intent: Intent::ALL.get(best).copied().unwrap_or(Intent::Question), confidence }
+    }
+
+    fn probabilities(&self, feats: &[(String, f32)]) -> [f32; CLASSES] {
+        let mut logits = self.bias;
+        for (feat, value) in feats {
+            if let Some(row) = self.weights.get(feat) {
+                for (logit, w) in logits.iter_mut().zip(row) {
+                    *logit += w * value;
+                }
+            }
+        }
+        softmax(&mut logits);
+        logits
+    }
+}
+
+/// `classify` with the built-in classifier (trained on first use).
+pub fn classify(text: &str) -> IntentPrediction {
+    static BUILTIN: OnceLock<IntentClassifier> = OnceLock::new();
+    BUILTIN.get_or_init(IntentClassifier::builtin).classify(text)
+}
+
+fn class_index(intent: Intent) -> usize {
+    Intent::ALL.iter().position(|&i| i == intent).unwrap_or(0)
+}
+
+/// L2-normalized counts of the character trigrams of the lowercased text,
+/// padded with spaces; digits are folded to `0` so numbers generalize.
+fn features(text: &str) -> Vec<(String, f32)> {
+    let chars: Vec<char> = std::iter::once(' ')
+        .chain(text.trim().chars().flat_map(char::to_lowercase).map(|c| if c.is_ascii_digit() { '0' } else { c }))
+        .chain(std::iter::once(' '))
+        .collect();
+    let mut counts: HashMap<String, f32> = HashMap::new();
+    for window in chars.windows(3) {
+        *counts.entry(window.iter().collect()).or_insert(0.0) += 1.0;
+    }
+    let norm = counts.values().map(|c| c * c).sum::<f32>().sqrt().max(1.0);
+    let mut feats: Vec<(String, f32)> = counts.into_iter().map(|(k, c)| (k, c / norm)).collect();
+    feats.sort_by(|a, b| a.0.cmp(&b.0));
+    feats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_classifier_separates_the_shipped_intents() {
+        let cases = [
+            ("что такое переменная?", Intent::Question),
+            ("напиши пример функции", Intent::Instruction),
+            ("сколько будет 3 + 4", Intent::Math),
+            ("исследуй зависимость давления от объёма", Intent::Research),
+            ("привет!", Intent::SmallTalk),
+        ];
+        for (text, expected) in cases {
+            let prediction = classify(text);
+            assert_eq!(prediction.intent, expected, "{}: {:?}", text, prediction);
+            assert!(prediction.confidence > 0.0 && prediction.confidence <= 1.0);
+        }
+        assert_eq!("Small_Talk".parse::<Intent>(), Ok(Intent::SmallTalk));
+        assert!(IntentClassifier::parse_examples("text,intent\nпривет,greeting").is_err());
+    }
+}
@@ -0,0 +1,339 @@
+#![forbid(unsafe_code)]
+
+//! Background scheduler for the self-learning jobs that used to run inline at
+//! startup (knowledge merge, relearning unknowns, deep evolution) and for the
+//! nightly evaluation of a dataset.
+//!
+//! A `Scheduler` owns named jobs, each with a `Trigger`: once, every interval,
+//! daily at a UTC hour, or after the front-end has been idle for a while
+//! (`touch` marks activity).
+//! `start` runs them on one worker thread, so the REPL, GUI and server answer
+//! while they work; `status` reports their state (`/jobs` on the server, the
+//! GUI jobs panel, `/jobs` in the REPL).
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Timelike, Utc};
+use serde::Serialize;
+
+use crate::config::Config;
+
+/// How often the worker looks for due jobs.
+const TICK: Duration = Duration::from_millis(100);
+
+/// Result of one run: a short report line, or the error.
+pub type JobResult = std::result::Result<String, String>;
+
+type Task = Box<dyn FnMut() -> JobResult + Send>;
+
+/// When a job runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// once, as soon as the scheduler starts
+    Once,
+    /// first at start, then again this long after each run finished
+    Interval(Duration),
+    /// after this long without `Scheduler::touch`, once per idle period
+    Idle(Duration),
+    /// once a day, at the first tick from this UTC hour on (at start if the
+    /// hour has already passed that day)
+    Daily(u8),
+}
+
+impl Trigger {
+    /// Human-readable form for status listings.
+    pub fn describe(&self) -> String {
+        match self {
+            Trigger::Once => "однократно".to_string(),
+            Trigger::Interval(d) => format!("каждые {} с", d.as_secs()),
+            Trigger::Idle(d) => format!("после {} с простоя", d.as_secs()),
+            Trigger::Daily(hour) => format!("ежедневно в {:02}:00 UTC", hour),
+        }
+    }
+}
+
+/// State of a job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    /// waiting for its trigger
+    Pending,
+    /// running now
+    Running,
+    /// last run succeeded
+    Done,
+    /// last run failed
+    Failed,
+}
+
+/// Snapshot of a job for status listings.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JobStatus {
+    /// job name
+    pub name: &'static str,
+    /// trigger, as `Trigger::describe`
+    pub trigger: String,
+    /// current state
+    pub state: JobState,
+    /// finished runs
+    pub runs: usize,
+    /// report line or error of the last run
+    pub last_message: Option<String>,
+    /// duration of the last run in milliseconds
+    pub last_duration_ms: Option<u128>,
+    /// when the last run finished (RFC 3339)
+    pub last_finished: Option<String>,
+}
+
+struct Job {
+    status: JobStatus,
+    trigger: Trigger,
+    task: Arc<Mutex<Task>>,
+    last_started: Option<Instant>,
+    last_finished: Option<Instant>,
+    /// wall-clock start of the last run, for `Trigger::Daily`
+    last_started_at: Option<DateTime<Utc>>,
+}
+
+impl Job {
+    fn due(&self, now: Instant, wall: DateTime<Utc>, last_activity: Instant) -> bool {
+        if self.status.state == JobState::Running {
+            return false;
+        }
+        match self.trigger {
+            Trigger::Once => self.last_started.is_none(),
+            Trigger::Interval(every) => self.last_finished.is_none_or(|t| now.duration_since(t) >= every),
+            Trigger::Idle(after) => {
+                now.duration_since(last_activity) >= after && self.last_started.is_none_or(|t| t < last_activity)
+            }
+            Trigger::Daily(hour) => {
+                wall.hour() >= u32::from(hour) && self.last_started_at.is_none_or(|t| t.date_naive() < wall.date_naive())
+            }
+        }
+    }
+}
+
+/// State shared between the scheduler handle and its worker.
+struct Shared {
+    jobs: Mutex<Vec<Job>>,
+    last_activity: Mutex<Instant>,
+    stop: AtomicBool,
+}
+
+/// Named background jobs with triggers; see the module docs.
+pub struct Scheduler {
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scheduler {
+    /// No jobs, not started.
+    pub fn new() -> Self {
+        let shared = Shared { jobs: Mutex::new(Vec::new()), last_activity: Mutex::new(Instant::now()), stop: AtomicBool::new(false) };
+        Self { shared: Arc::new(shared), worker: None }
+    }
+
+    /// The self-learning jobs enabled in `config.features`:
+    /// - `knowledge_merge` (once): create topic files and merge them into knowledge.csv;
+    /// - `relearn` (after `jobs.relearn_idle_secs` idle): re-solve recorded unknowns
+    ///   with a separate `AI` whose dialog memory is not persisted;
+    /// - `deep_evolution` (every `jobs.evolution_interval_secs`, feature `science`):
+    ///   `scientist::deepen_research` from the two most curious formulas;
+    /// - `evaluate` (daily at `jobs.evaluate_hour` UTC, when `jobs.evaluate` names a
+    ///   dataset): `evaluation::run` with a fresh `AI`, failing on a regression.
+    pub fn self_learning(config: &Config) -> Self {
+        let mut scheduler = Self::new();
+        if config.features.knowledge_merge {
+            let dir = config.data.dir.clone();
+            scheduler.add("knowledge_merge", Trigger::Once, move || {
+                let topics = ["math", "analysis", "geometry", "logic", "science"];
+                crate::knowledge_env::expand_knowledge_environment(&dir, &topics).map_err(|e| e.to_string())?;
+                crate::knowledge_env::merge_knowledge_sources(&dir.join("knowledge.csv"), &dir.join("knowledge")).map_err(|e| e.to_string())?;
+                Ok("источники знаний объединены".to_string())
+            });
+        }
+        if config.features.relearn {
+            let config = config.clone();
+            let unknowns = config.data.dir.join("unknowns.csv").to_string_lossy().to_string();
+            let idle = Duration::from_secs(config.jobs.relearn_idle_secs);
+            let mut ai = None;
+            scheduler.add("relearn", Trigger::Idle(idle), move || {
+                if ai.is_none() {
+                    let built = crate::AI::builder()
+                        .config(config.clone())
+                        .memory(crate::memory::Memory::in_memory())
+                        .eager_knowledge(true)
+                        .build();
+                    ai = Some(built.map_err(|e| e.to_string())?);
+                }
+                let Some(ai) = ai.as_mut() else { return Err("AI не создан".to_string()) };
+                let (learned, total) = crate::train::try_relearn_unknowns(ai, &unknowns, 2).map_err(|e| e.to_string())?;
+                Ok(format!("relearnt {}/{} due unknowns", learned, total))
+            });
+        }
+        #[cfg(feature = "science")]
+        if config.features.science {
+            let every = Duration::from_secs(config.jobs.evolution_interval_secs);
+            scheduler.add("deep_evolution", Trigger::Interval(every), || {
+                let found = crate::scientist::deepen_research(2, 200, 60);
+                let best = found.iter().map(|f| f.3).fold(f64::INFINITY, f64::min);
+                Ok(format!("{} формул углублено, лучший MSE {:.4}", found.len(), best))
+            });
+        }
+        if let Some(dataset) = config.jobs.evaluate.clone() {
+            let config = config.clone();
+            scheduler.add("evaluate", Trigger::Daily(config.jobs.evaluate_hour), move || {
+                let mut ai = crate::AI::builder()
+                    .config(config.clone())
+                    .memory(crate::memory::Memory::in_memory())
+                    .eager_knowledge(true)
+                    .build()
+                    .map_err(|e| e.to_string())?;
+                let outcome = crate::evaluation::run(&mut ai, &dataset)?;
+                match outcome.regression {
+                    Some(regression) => Err(regression),
+                    None => Ok(format!("{}: {}/{} решено", dataset, outcome.run.passed, outcome.run.total)),
+                }
+            });
+        }
+        scheduler
+    }
+
+    /// Add a job; jobs added after `start` are picked up by the running worker.
+    pub fn add(&mut self, name: &'static str, trigger: Trigger, task: impl FnMut() -> JobResult + Send + 'static) {
+        let status = JobStatus {
+            name,
+            trigger: trigger.describe(),
+            state: JobState::Pending,
+            runs: 0,
+            last_message: None,
+            last_duration_ms: None,
+            last_finished: None,
+        };
+        let task: Task = Box::new(task);
+        let job = Job { status, trigger, task: Arc::new(Mutex::new(task)), last_started: None, last_finished: None, last_started_at: None };
+        if let Ok(mut jobs) = self.shared.jobs.lock() {
+            jobs.push(job);
+        }
+    }
+
+    /// Record user activity (postpones `Trigger::Idle` jobs).
+    pub fn touch(&self) {
+        if let Ok(mut last) = self.shared.last_activity.lock() {
+            *last = Instant::now();
+        }
+    }
+
+    /// Status of every job, in the order they were added.
+    pub fn status(&self) -> Vec<JobStatus> {
+        self.shared.jobs.lock().map(|jobs| jobs.iter().map(|j| j.status.clone()).collect()).unwrap_or_default()
+    }
+
+    /// Run every due job once on the calling thread; returns how many ran.
+    pub fn run_pending(&self) -> usize {
+        run_pending(&self.shared)
+    }
+
+    /// Start the worker thread (no-op if already started).
+    pub fn start(&mut self) {
+        if self.worker.is_some() {
+            return;
+        }
+        let shared = Arc::clone(&self.shared);
+        self.worker = Some(std::thread::spawn(move || {
+            while !shared.stop.load(Ordering::Relaxed) {
+                if run_pending(&shared) == 0 {
+                    std::thread::sleep(TICK);
+                }
+            }
+        }));
+    }
+
+    /// Stop the worker after its current job and wait for it.
+    pub fn stop(&mut self) {
+        self.shared.stop.store(true, Ordering::Relaxed);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Mark due jobs running, run them without holding the job list, record results.
+fn run_pending(shared: &Shared) -> usize {
+    let (now, wall) = (Instant::now(), Utc::now());
+    let last_activity = shared.last_activity.lock().map(|t| *t).unwrap_or(now);
+    let due: Vec<(usize, Arc<Mutex<Task>>)> = match shared.jobs.lock() {
+        Ok(mut jobs) => jobs
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, job)| job.due(now, wall, last_activity))
+            .map(|(i, job)| {
+                job.status.state = JobState::Running;
+                job.last_started = Some(now);
+                job.last_started_at = Some(wall);
+                (i, Arc::clone(&job.task))
+            })
+            .collect(),
+        Err(_) => return 0,
+    };
+    for (index, task) in &due {
+        if shared.stop.load(Ordering::Relaxed) {
+            break;
+        }
+        let started = Instant::now();
+        let result = task.lock().map_err(|_| "задача повреждена".to_string()).and_then(|mut run| run());
+        let Ok(mut jobs) = shared.jobs.lock() else { return due.len() };
+        let Some(job) = jobs.get_mut(*index) else { continue };
+        job.last_finished = Some(Instant::now());
+        job.status.runs += 1;
+        job.status.last_duration_ms = Some(started.elapsed().as_millis());
+        job.status.last_finished = Some(Utc::now().to_rfc3339());
+        (job.status.state, job.status.last_message) = match result {
+            Ok(message) => (JobState::Done, Some(message)),
+            Err(e) => (JobState::Failed, Some(e)),
+        };
+    }
+    due.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn triggers_decide_when_jobs_run() {
+        let mut scheduler = Scheduler::new();
+        scheduler.add("once", Trigger::Once, || Ok("ok".to_string()));
+        scheduler.add("idle", Trigger::Idle(Duration::ZERO), || Err("boom".to_string()));
+        scheduler.add("interval", Trigger::Interval(Duration::from_secs(3600)), || Ok("tick".to_string()));
+        scheduler.add("daily", Trigger::Daily(0), || Ok("night".to_string()));
+
+        assert_eq!(scheduler.run_pending(), 4);
+        // nothing is due again until there is new activity (idle) or the interval passes
+        assert_eq!(scheduler.run_pending(), 0);
+        std::thread::sleep(Duration::from_millis(5));
+        scheduler.touch();
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(scheduler.run_pending(), 1);
+
+        let status = scheduler.status();
+        let summary: Vec<(&str, JobState, usize)> = status.iter().map(|s| (s.name, s.state, s.runs)).collect();
+        assert_eq!(summary, vec![("once", JobState::Done, 1), ("idle", JobState::Failed, 2), ("interval", JobState::Done, 1), ("daily", JobState::Done, 1)]);
+        assert_eq!(status.get(1).and_then(|s| s.last_message.as_deref()), Some("boom"));
+    }
+}
@@ -4,62 +4,56 @@ use std::io::Write;
 use chrono::Utc;
 use std::path::Path;
 
-/// Автоматически создаёт нужные папки и файлы при расширении базы знаний.
-pub fn expand_knowledge_environment(topics: &[&str]) -> std::io::Result<()> {
-    let base_dir = "crates/predict/data/knowledge";
-    let docs_dir = "docs";
+use crate::reports::Report;
+
+/// Log of knowledge-base changes in the reports directory.
+pub const KNOWLEDGE_LOG: &str = "knowledge_log.md";
+
+/// Автоматически создаёт нужные папки и файлы при расширении базы знаний
+/// (`<data_dir>/knowledge/knowledge_<topic>.csv`).
+pub fn expand_knowledge_environment(data_dir: &Path, topics: &[&str]) -> std::io::Result<()> {
+    let base_dir = data_dir.join("knowledge");
     let logs_dir = "logs";
 
     // 1. Ensure base directories exist
-    fs::create_dir_all(base_dir)?;
-    fs::create_dir_all(docs_dir)?;
+    fs::create_dir_all(&base_dir)?;
     fs::create_dir_all(logs_dir)?;
 
     // 2. Create thematic CSVs if missing
     let mut created = Vec::new();
     for topic in topics {
-        let file_path = format!("{}/knowledge_{}.csv", base_dir, topic);
-        if !Path::new(&file_path).exists() {
+        let file_path = base_dir.join(format!("knowledge_{}.csv", topic));
+        if !file_path.exists() {
             let mut file = fs::File::create(&file_path)?;
             // Default header; the project can update schema per-topic later
             writeln!(file, "id,topic,entry,notes,source,date")?;
-            println!("🧠 [expand] создан новый файл знаний: {}", file_path);
+            println!("🧠 [expand] создан новый файл знаний: {}", file_path.display());
             created.push(topic.to_string());
         }
     }
 
-    // 3. Log the changes to docs/knowledge_log.md
-    let mut log = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(format!("{}/knowledge_log.md", docs_dir))?;
-
+    // 3. Log the changes to knowledge_log.md
     let now = Utc::now().to_rfc3339();
-    writeln!(log, "\n### [{}] Расширение базы знаний", now)?;
-    for t in &created {
-        writeln!(log, "- создан файл `knowledge_{}`", t)?;
-    }
+    Report::new(format!("[{}] Расширение базы знаний", now))
+        .list(created.iter().map(|t| format!("создан файл `knowledge_{}`", t)))
+        .append(KNOWLEDGE_LOG)?;
 
     Ok(())
 }
 
-/// Create a new topic file on demand. Logs to docs/knowledge_log.md when created.
-pub fn auto_expand_on_new_topic(topic: &str) -> std::io::Result<()> {
-    let base_dir = "crates/predict/data/knowledge";
-    let docs_dir = "docs";
-    fs::create_dir_all(base_dir)?;
-    fs::create_dir_all(docs_dir)?;
+/// Create a new topic file in `<data_dir>/knowledge` on demand. Logs to knowledge_log.md when created.
+pub fn auto_expand_on_new_topic(data_dir: &Path, topic: &str) -> std::io::Result<()> {
+    let base_dir = data_dir.join("knowledge");
+    fs::create_dir_all(&base_dir)?;
 
-    let new_path = format!("{}/knowledge_{}.csv", base_dir, topic);
-    if !Path::new(&new_path).exists() {
+    let new_path = base_dir.join(format!("knowledge_{}.csv", topic));
+    if !new_path.exists() {
         if let Ok(mut file) = fs::File::create(&new_path) {
             let _ = writeln!(file, "id,topic,entry,notes,source,date");
             println!("🌱 [auto-expand] создан новый файл знаний для темы: {}", topic);
         }
-        let mut log = OpenOptions::new().create(true).append(true).open(format!("{}/knowledge_log.md", docs_dir))?;
         let now = Utc::now().to_rfc3339();
-        writeln!(log, "\n### [{}] Автодобавление темы", now)?;
-        writeln!(log, "- создан файл `knowledge_{}`", topic)?;
+        Report::new(format!("[{}] Автодобавление темы", now)).list([format!("создан файл `knowledge_{}`", topic)]).append(KNOWLEDGE_LOG)?;
     }
     Ok(())
 }
@@ -79,15 +73,18 @@ pub fn detect_knowledge_gap(query: &str) -> Option<String> {
     }
 }
 
-/// Merge simple QA-style knowledge sources (CSV files with two columns) into the central knowledge.csv
-/// This is conservative: only lines with at least two comma-separated fields are merged and duplicates by question are avoided.
-pub fn merge_knowledge_sources() -> std::io::Result<()> {
-    let base_dir = "crates/predict/data/knowledge";
-    let main_path = "crates/predict/data/knowledge.csv";
-    fs::create_dir_all("crates/predict/data")?;
+/// Merge simple QA-style knowledge sources (CSV files with two columns in `source_dir`) into the
+/// central knowledge base `target`. This is conservative: only lines with at least two
+/// comma-separated fields are merged (the first two are taken as question and answer) and
+/// duplicates by question are avoided.
+pub fn merge_knowledge_sources(target: &Path, source_dir: &Path) -> std::io::Result<()> {
+    let main_path = target;
+    if let Some(dir) = main_path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
 
     // Ensure main file exists
-    if !Path::new(main_path).exists() {
+    if !main_path.exists() {
         let mut f = fs::File::create(main_path)?;
         writeln!(f, "question,answer")?;
     }
@@ -102,7 +99,7 @@ pub fn merge_knowledge_sources() -> std::io::Result<()> {
     }
 
     // Iterate knowledge/*.csv
-    if let Ok(entries) = fs::read_dir(base_dir) {
+    if let Ok(entries) = fs::read_dir(source_dir) {
         for entry in entries.flatten() {
             let path = entry.path();
             if path.is_file() && path.extension().map(|e| e == "csv").unwrap_or(false) {
@@ -112,10 +109,10 @@ pub fn merge_knowledge_sources() -> std::io::Result<()> {
                         if l.is_empty() || l.starts_with('#') { continue; }
                         // skip typical header lines
                         if i == 0 && (l.to_lowercase().contains("id") || l.to_lowercase().contains("topic") || l.to_lowercase().contains("question")) { continue; }
-                        let parts: Vec<&str> = l.splitn(2, ',').collect();
-                        if parts.len() < 2 { continue; }
-                        let q = parts[0].trim().trim_matches('"').to_string();
-                        let a = parts[1].trim().trim_matches('"').to_string();
+                        // only the first two fields: extra columns carry provenance/dates
+                        let mut fields = crate::train::dataset::parse_csv_line(l).into_iter();
+                        let (Some(q), Some(a)) = (fields.next(), fields.next()) else { continue };
+                        let (q, a) = (q.trim().to_string(), a.trim().to_string());
                         if !seen.contains(&q) {
                             let mut f = OpenOptions::new().create(true).append(true).open(main_path)?;
                             writeln!(f, "\"{}\",\"{}\"", q.replace('"', "'"), a.replace('"', "'"))?;
@@ -128,10 +125,10 @@ pub fn merge_knowledge_sources() -> std::io::Result<()> {
     }
 
     // Log merge
-    let mut log = OpenOptions::new().create(true).append(true).open("docs/knowledge_log.md")?;
     let now = Utc::now().to_rfc3339();
-    writeln!(log, "\n### [{}] Объединение источников знаний", now)?;
-    writeln!(log, "- объединены файлы из {}/ into crates/predict/data/knowledge.csv", base_dir)?;
+    Report::new(format!("[{}] Объединение источников знаний", now))
+        .list([format!("объединены файлы из {}/ into {}", source_dir.display(), main_path.display())])
+        .append(KNOWLEDGE_LOG)?;
 
     Ok(())
 }
@@ -0,0 +1,426 @@
+#![forbid(unsafe_code)]
+
+//! Consistency of the knowledge base (knowledge.csv).
+//!
+//! `find_conflicts` reports questions stored with several different answers
+//! and facts whose answer disagrees with what the solvers compute (arithmetic
+//! and linear equations). A conflict is settled with `resolve`: keep one
+//! answer, replace them with the computed one, remove the question, or accept
+//! it as is; every choice is appended to a resolution log
+//! (`knowledge/knowledge_conflicts.csv` by default) and accepted conflicts are
+//! not reported again. The REPL shows them with `/knowledge conflicts`, the
+//! GUI with a badge on the memory tab.
+//!
+//! Unknowns (questions the evaluation got wrong, unknowns.csv) form a
+//! `ReviewQueue` with SM-2-style intervals: `train::try_relearn_unknowns` only
+//! retries the due ones, and every failed attempt pushes the next try further
+//! out (1 day, 6 days, then the interval times the ease factor).
+
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::train::{eval_arith, solve_linear_equation};
+
+/// Answer marking a question nobody could answer yet; never a conflict.
+const UNKNOWN: &str = "UNKNOWN";
+
+/// One question/answer line of knowledge.csv.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// line number in the file (0 is the header)
+    pub line: usize,
+    /// question as stored
+    pub question: String,
+    /// answer as stored
+    pub answer: String,
+}
+
+/// Why a question is reported.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConflictKind {
+    /// the question is stored with different answers
+    DifferentAnswers,
+    /// a stored answer disagrees with the solvers
+    Mismatch {
+        /// answer computed by `eval_arith` or `solve_linear_equation`
+        expected: String,
+    },
+}
+
+/// A question whose stored answers are inconsistent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    /// question (as first stored)
+    pub question: String,
+    /// the entries involved, in file order
+    pub entries: Vec<Entry>,
+    /// what is wrong
+    pub kind: ConflictKind,
+}
+
+/// How a conflict is settled.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resolution {
+    /// keep this answer, drop the others
+    Keep(String),
+    /// replace every stored answer with this one
+    Replace(String),
+    /// remove the question
+    Remove,
+    /// leave the file as is and stop reporting the conflict
+    Accept,
+}
+
+impl Resolution {
+    /// Name recorded in the resolution log.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Resolution::Keep(_) => "keep",
+            Resolution::Replace(_) => "replace",
+            Resolution::Remove => "remove",
+            Resolution::Accept => "accept",
+        }
+    }
+}
+
+/// Lookup key of a question: lowercase, without the final question mark.
+fn key(question: &str) -> String {
+    question.trim().trim_end_matches('?').trim().to_lowercase()
+}
+
+/// Answers compared without case and final punctuation.
+fn same_answer(a: &str, b: &str) -> bool {
+    let normalize = |s: &str| s.trim().trim_end_matches(['.', '!']).to_lowercase();
+    normalize(a) == normalize(b)
+}
+
+/// Split one CSV line into question and answer (the answer may contain commas).
+fn parse_line(line: &str) -> Option<(String, String)> {
+    let (q, a) = line.split_once(',')?;
+    Some((q.trim().trim_matches('"').to_string(), a.trim().trim_matches('"').to_string()))
+}
+
+/// Entries of knowledge.csv text (the header line is skipped).
+pub fn parse(text: &str) -> Vec<Entry> {
+    text.lines()
+        .enumerate()
+        .skip(1)
+        .filter_map(|(line, l)| parse_line(l).map(|(question, answer)| Entry { line, question, answer }))
+        .collect()
+}
+
+/// Entries of a knowledge.csv file.
+pub fn load(path: &Path) -> io::Result<Vec<Entry>> {
+    Ok(parse(&std::fs::read_to_string(path)?))
+}
+
+/// Value the solvers compute for `question`, if it is arithmetic or a linear equation.
+fn computed(question: &str) -> Option<String> {
+    if question.contains('=') { solve_linear_equation(question) } else { eval_arith(question) }
+}
+
+/// Value of a numeric answer: a number or `x = number`.
+fn number(answer: &str) -> Option<f64> {
+    answer.trim().trim_start_matches('x').trim_start().trim_start_matches('=').trim().parse::<f64>().ok()
+}
+
+/// Whether `answer` is numeric and differs from `expected` (text answers
+/// such as "цифры 0–9" for "0-9" are not checked).
+fn contradicts(answer: &str, expected: &str) -> bool {
+    matches!((number(answer), number(expected)), (Some(x), Some(y)) if (x - y).abs() > 1e-6)
+}
+
+/// Conflicts among `entries`, in order of first appearance. Questions in
+/// `accepted` (see `accepted`) are skipped.
+pub fn find_conflicts(entries: &[Entry], accepted: &HashSet<String>) -> Vec<Conflict> {
+    let mut order: Vec<String> = Vec::new();
+    let mut groups: std::collections::HashMap<String, Vec<&Entry>> = std::collections::HashMap::new();
+    for entry in entries.iter().filter(|e| !e.answer.eq_ignore_ascii_case(UNKNOWN)) {
+        let k = key(&entry.question);
+        if !groups.contains_key(&k) {
+            order.push(k.clone());
+        }
+        groups.entry(k).or_default().push(entry);
+    }
+    order
+        .into_iter()
+        .filter(|k| !accepted.contains(k))
+        .filter_map(|k| {
+            let group = groups.remove(&k)?;
+            let first = group.first()?;
+            let different = group.iter().any(|e| !same_answer(&e.answer, &first.answer));
+            let kind = match computed(&first.question) {
+                Some(expected) if group.iter().any(|e| contradicts(&e.answer, &expected)) => {
+                    ConflictKind::Mismatch { expected }
+                }
+                _ if different => ConflictKind::DifferentAnswers,
+                _ => return None,
+            };
+            Some(Conflict { question: first.question.clone(), entries: group.into_iter().cloned().collect(), kind })
+        })
+        .collect()
+}
+
+/// Questions (as keys) accepted in the resolution log at `log_path`.
+pub fn accepted(log_path: &Path) -> HashSet<String> {
+    let text = std::fs::read_to_string(log_path).unwrap_or_default();
+    text.lines()
+        .skip(1)
+        .filter_map(|l| {
+            let (question, rest) = parse_line(l)?;
+            rest.starts_with(Resolution::Accept.as_str()).then(|| key(&question))
+        })
+        .collect()
+}
+
+/// Conflicts of the knowledge file at `knowledge_path` not accepted in `log_path`.
+pub fn open_conflicts(knowledge_path: &Path, log_path: &Path) -> io::Result<Vec<Conflict>> {
+    Ok(find_conflicts(&load(knowledge_path)?, &accepted(log_path)))
+}
+
+/// Apply `resolution` to `conflict` in `knowledge_path` and record it in
+/// `log_path`. Returns the number of lines removed.
+pub fn resolve(knowledge_path: &Path, log_path: &Path, conflict: &Conflict, resolution: &Resolution) -> io::Result<usize> {
+    let drop: HashSet<usize> = match resolution {
+        Resolution::Keep(answer) => conflict.entries.iter().filter(|e| !same_answer(&e.answer, answer)).map(|e| e.line).collect(),
+        Resolution::Replace(_) | Resolution::Remove => conflict.entries.iter().map(|e| e.line).collect(),
+        Resolution::Accept => HashSet::new(),
+    };
+    if !drop.is_empty() {
+        let text = std::fs::read_to_string(knowledge_path)?;
+        let kept: Vec<&str> = text.lines().enumerate().filter(|(i, _)| !drop.contains(i)).map(|(_, l)| l).collect();
+        std::fs::write(knowledge_path, kept.join("\n") + "\n")?;
+    }
+    if let Resolution::Replace(answer) = resolution {
+        crate::train::append_knowledge(&knowledge_path.to_string_lossy(), &conflict.question, answer)?;
+    }
+    if let Some(dir) = log_path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut log = OpenOptions::new().create(true).append(true).open(log_path)?;
+    if log.metadata()?.len() == 0 {
+        writeln!(log, "question,choice,answer,date")?;
+    }
+    let answer = match resolution {
+        Resolution::Keep(a) | Resolution::Replace(a) => a.as_str(),
+        _ => "",
+    };
+    let now = chrono::Utc::now().to_rfc3339();
+    writeln!(log, "\"{}\",{},\"{}\",{}", conflict.question.replace('"', "'"), resolution.as_str(), answer.replace('"', "'"), now)?;
+    Ok(drop.len())
+}
+
+/// Ease factor of a new review item (SM-2).
+pub const INITIAL_EASE: f64 = 2.5;
+
+/// Lowest ease factor (SM-2).
+pub const MIN_EASE: f64 = 1.3;
+
+/// An unknown waiting to be relearned.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReviewItem {
+    /// question
+    pub question: String,
+    /// expected answer
+    pub expected: String,
+    /// when it was recorded
+    pub added: DateTime<Utc>,
+    /// when it is due for the next attempt
+    pub due: DateTime<Utc>,
+    /// days between the last attempt and `due`
+    pub interval_days: f64,
+    /// SM-2 ease factor
+    pub ease: f64,
+    /// failed attempts so far
+    pub repetitions: u32,
+}
+
+impl ReviewItem {
+    /// New item, due immediately.
+    pub fn new(question: &str, expected: &str, now: DateTime<Utc>) -> Self {
+        Self {
+            question: question.to_string(),
+            expected: expected.to_string(),
+            added: now,
+            due: now,
+            interval_days: 0.0,
+            ease: INITIAL_EASE,
+            repetitions: 0,
+        }
+    }
+
+    /// Whether an attempt is due at `now`.
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.due <= now
+    }
+
+    /// Schedule the next attempt after a failed one graded `quality` (0..=5,
+    /// higher is closer to the expected answer). Unlike flash cards a failure
+    /// does not reset the interval: it grows 1 day, 6 days, then by the ease
+    /// factor, which drops the more wrong the attempts are.
+    pub fn fail(&mut self, quality: u8, now: DateTime<Utc>) {
+        let q = f64::from(5 - quality.min(5));
+        self.ease = (self.ease + 0.1 - q * (0.08 + q * 0.02)).max(MIN_EASE);
+        self.repetitions += 1;
+        self.interval_days = match self.repetitions {
+            1 => 1.0,
+            2 => 6.0,
+            _ => self.interval_days * self.ease,
+        };
+        self.due = now + Duration::seconds((self.interval_days * 86_400.0) as i64);
+    }
+}
+
+/// Unknowns with their review schedule, stored as CSV
+/// (`question,expected,added,due,interval_days,ease,repetitions`).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ReviewQueue {
+    /// items in insertion order
+    pub items: Vec<ReviewItem>,
+}
+
+impl ReviewQueue {
+    /// Parse queue CSV. Files of the older `question,expected,date,attempts`
+    /// layout are read as items due now; repeated questions are kept once.
+    pub fn parse(text: &str) -> Self {
+        let date = |s: &str| DateTime::parse_from_rfc3339(s.trim().trim_matches('"')).ok().map(|d| d.with_timezone(&Utc));
+        let mut queue = Self::default();
+        for line in text.lines().skip(1) {
+            // question and expected are quoted and may contain commas; the rest are plain fields
+            let fields: Vec<&str> = line.splitn(3, "\",").collect();
+            let [question, expected, rest] = fields.as_slice() else { continue };
+            let rest: Vec<&str> = rest.split(',').collect();
+            let (question, expected) = (question.trim_start_matches('"'), expected.trim_start_matches('"'));
+            let mut item = ReviewItem::new(question, expected, rest.first().and_then(|d| date(d)).unwrap_or_else(Utc::now));
+            if let [_, due, interval, ease, repetitions] = rest.as_slice() {
+                item.due = date(due).unwrap_or(item.added);
+                item.interval_days = interval.trim().parse().unwrap_or(0.0);
+                item.ease = ease.trim().parse().unwrap_or(INITIAL_EASE);
+                item.repetitions = repetitions.trim().parse().unwrap_or(0);
+            }
+            if queue.position(&item.question, &item.expected).is_none() {
+                queue.items.push(item);
+            }
+        }
+        queue
+    }
+
+    /// Load a queue file; a missing file is an empty queue.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Ok(Self::parse(&text)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Write the queue (creating the directory if needed).
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut text = String::from("question,expected,added,due,interval_days,ease,repetitions\n");
+        for item in &self.items {
+            text.push_str(&format!(
+                "\"{}\",\"{}\",{},{},{},{:.3},{}\n",
+                item.question.replace('"', "'"),
+                item.expected.replace('"', "'"),
+                item.added.to_rfc3339(),
+                item.due.to_rfc3339(),
+                item.interval_days,
+                item.ease,
+                item.repetitions
+            ));
+        }
+        std::fs::write(path, text)
+    }
+
+    fn position(&self, question: &str, expected: &str) -> Option<usize> {
+        self.items.iter().position(|i| i.question == question && i.expected == expected)
+    }
+
+    /// Add an unknown due now; `false` if it is already queued.
+    pub fn add(&mut self, question: &str, expected: &str, now: DateTime<Utc>) -> bool {
+        if self.position(question, expected).is_some() {
+            return false;
+        }
+        self.items.push(ReviewItem::new(question, expected, now));
+        true
+    }
+
+    /// Remove an item; `false` if it was not queued.
+    pub fn remove(&mut self, question: &str, expected: &str) -> bool {
+        let before = self.items.len();
+        self.items.retain(|i| !(i.question == question && i.expected == expected));
+        self.items.len() != before
+    }
+
+    /// Items due at `now`.
+    pub fn due(&self, now: DateTime<Utc>) -> Vec<&ReviewItem> {
+        self.items.iter().filter(|i| i.is_due(now)).collect()
+    }
+
+    /// Record a failed attempt on an item (see `ReviewItem::fail`).
+    pub fn fail(&mut self, question: &str, expected: &str, quality: u8, now: DateTime<Utc>) {
+        if let Some(item) = self.position(question, expected).and_then(|i| self.items.get_mut(i)) {
+            item.fail(quality, now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conflicts_are_found_and_resolved() {
+        let dir = std::env::temp_dir().join(format!("shark_conflicts_{}", std::process::id()));
+        let _ = std::fs::create_dir_all(&dir);
+        let (knowledge, log) = (dir.join("knowledge.csv"), dir.join("knowledge_conflicts.csv"));
+        let csv = "input,output\n\"2 + 2\",\"5\"\n\"x + 2 = 5\",\"x = 3\"\n\"скат\",\"рыба\"\n\"Скат?\",\"птица\"\n\"скат\",\"Рыба.\"\n\"q\",\"UNKNOWN\"\n";
+        let _ = std::fs::write(&knowledge, csv);
+
+        let conflicts = open_conflicts(&knowledge, &log).unwrap_or_default();
+        let kinds: Vec<(&str, &ConflictKind)> = conflicts.iter().map(|c| (c.question.as_str(), &c.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![("2 + 2", &ConflictKind::Mismatch { expected: "4".to_string() }), ("скат", &ConflictKind::DifferentAnswers)]
+        );
+
+        let (Some(arith), Some(skat)) = (conflicts.first(), conflicts.get(1)) else { return };
+        assert_eq!(resolve(&knowledge, &log, skat, &Resolution::Keep("рыба".to_string())).ok(), Some(1));
+        assert_eq!(resolve(&knowledge, &log, arith, &Resolution::Accept).ok(), Some(0));
+        let left = open_conflicts(&knowledge, &log).unwrap_or_default();
+        let text = std::fs::read_to_string(&knowledge).unwrap_or_default();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(left.is_empty(), "{:?}", left);
+        assert!(!text.contains("птица") && text.contains("\"2 + 2\",\"5\""));
+    }
+
+    #[test]
+    fn failed_reviews_are_spaced_out() {
+        let old = "question,expected,date,attempts\n\"2, 3\",\"5\",\"2025-10-27T11:04:24+00:00\",0\n\"2, 3\",\"5\",\"2025-10-28T11:04:24+00:00\",0\n";
+        let mut queue = ReviewQueue::parse(old);
+        assert_eq!(queue.items.len(), 1);
+        let now = Utc::now();
+        assert_eq!(queue.due(now).len(), 1);
+
+        let mut intervals = Vec::new();
+        for _ in 0..4 {
+            queue.fail("2, 3", "5", 3, now);
+            intervals.extend(queue.items.first().map(|i| i.interval_days));
+        }
+        assert_eq!(intervals.get(..2), Some(&[1.0, 6.0][..]));
+        assert!(intervals.windows(2).all(|w| matches!(w, [a, b] if b > a)), "{:?}", intervals);
+        assert!(queue.due(now).is_empty() && !queue.add("2, 3", "5", now));
+
+        let path = std::env::temp_dir().join(format!("shark_review_{}.csv", std::process::id()));
+        let reloaded = queue.save(&path).and_then(|_| ReviewQueue::load(&path)).unwrap_or_default();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(reloaded.items.first().map(|i| (i.repetitions, i.due.timestamp())), queue.items.first().map(|i| (i.repetitions, i.due.timestamp())));
+    }
+}
@@ -1,4 +1,6 @@
-#![forbid(unsafe_code)]
+// unsafe code is confined to the C ABI (`capi`); everything else forbids it
+#![cfg_attr(not(feature = "capi"), forbid(unsafe_code))]
+#![cfg_attr(feature = "capi", deny(unsafe_code))]
 #![deny(clippy::unwrap_used, clippy::expect_used, clippy::panic, clippy::indexing_slicing)]
 #![deny(missing_docs, unused_must_use)]
 
@@ -13,90 +15,22 @@
 //! Layout (important files):
 //! - `core.rs` — softmax, RNG helpers, arena placeholder
 //! - `linear.rs` — tiny dense layer (`Linear::from_raw` + `forward`)
+//!   (softmax, sampling, `Linear` and the arithmetic evaluator are defined in
+//!   the no_std `shark-core-math` crate and re-exported here)
 //! - `loader.rs` — helper to load f32 weight blobs
 //! - `model.rs` — `Model` + `SimpleModel` convenience loader
 //! - `memory.rs` — dialog persistence (bincode)
 //! - `tokenizer.rs` — small tokenizer (char/word helpers)
 //! - `bin/chat.rs` — REPL that uses `AI` (model + memory)
-
-use rand::Rng;
-use rand_chacha::ChaCha8Rng;
-use rand::SeedableRng;
-
-/// Deterministic binary search guess using a feedback closure.
-///
-/// `feedback` should behave like `guess.cmp(&target)`: return
-/// - Ordering::Less when guess < target,
-/// - Ordering::Greater when guess > target,
-/// - Ordering::Equal when guess == target.
-pub fn guess_number<F>(mut feedback: F) -> i32
-where
-    F: FnMut(i32) -> std::cmp::Ordering,
-{
-    let (mut low, mut high) = (0i32, 99i32);
-    while low <= high {
-        let mid = low + (high - low) / 2;
-        match feedback(mid) {
-            std::cmp::Ordering::Less => {
-                // mid < target
-                low = mid + 1;
-            }
-            std::cmp::Ordering::Greater => {
-                // mid > target
-                high = mid - 1;
-            }
-            std::cmp::Ordering::Equal => return mid,
-        }
-    }
-    // If not found, return low as fallback
-    low
-}
-
-/// Simple wrapper that, given a secret `target`, uses `guess_number` to find it.
-pub fn guess(target: i32) -> i32 {
-    guess_number(|n| n.cmp(&target))
-}
-
-/// Probabilistic guess that uses a seeded RNG so it's deterministic per seed.
-/// It tries to converge on `target` by random steps shrinking over iterations.
-pub fn probabilistic_guess(target: i32, seed: u64) -> i32 {
-    let mut rng = ChaCha8Rng::seed_from_u64(seed);
-    let mut guess = rng.gen_range(0..100);
-    let mut step = 10i32;
-    for _ in 0..20 {
-        if guess == target {
-            return guess;
-        } else if guess < target {
-            let delta = rng.gen_range(1..=step);
-            guess = (guess + delta).min(99);
-        } else {
-            let delta = rng.gen_range(1..=step);
-            guess = (guess - delta).max(0);
-        }
-        step = std::cmp::max(1, step / 2);
-    }
-    guess
-}
-
-/// Very small least-squares linear regressor using provided `(x,y)` pairs.
-/// Returns predicted y for given `x0`.
-pub fn linear_regressor_predict(pairs: &[(f64, f64)], x0: f64) -> Option<f64> {
-    if pairs.is_empty() {
-        return None;
-    }
-    let n = pairs.len() as f64;
-    let sum_x = pairs.iter().map(|(x, _)| x).sum::<f64>();
-    let sum_y = pairs.iter().map(|(_, y)| y).sum::<f64>();
-    let sum_xy = pairs.iter().map(|(x, y)| x * y).sum::<f64>();
-    let sum_x2 = pairs.iter().map(|(x, _)| x * x).sum::<f64>();
-    let denom = n * sum_x2 - sum_x * sum_x;
-    if denom.abs() < std::f64::EPSILON {
-        return None;
-    }
-    let slope = (n * sum_xy - sum_x * sum_y) / denom;
-    let intercept = (sum_y - slope * sum_x) / n;
-    Some(slope * x0 + intercept)
-}
+//!
+//! Cargo features, all but `capi` on by default: `fs` (file IO and
+//! everything built on it — memory, knowledge, `AI`, training), `threads`
+//! (rayon), `cli`, `gui` and `server` (the binaries), `science` (the
+//! `scientist` module), `trading` (re-exports `backtest` and `indicators`)
+//! and `capi` (the C ABI). With
+//! `--no-default-features` only the inference core (`core`, `linear`,
+//! `model`, `tokenizer`, `decode`, `ngram`) is built, which compiles for
+//! `wasm32-unknown-unknown`.
 
 /// Hidden polynomial function used for symbolic discovery examples.
 /// f(x) = 3*x^2 - 2*x + 7
@@ -104,131 +38,562 @@ pub fn hidden_function(x: f64) -> f64 {
     3.0 * x * x - 2.0 * x + 7.0
 }
 
-/// Discover coefficients (a, b, c) of a quadratic polynomial by simple
-/// gradient-descent-like updates. Deterministic when given a `seed`.
-pub fn discover_equation(seed: u64) -> (f64, f64, f64) {
-    let mut rng = ChaCha8Rng::seed_from_u64(seed);
-    let mut a = rng.gen_range(-10.0..10.0);
-    let mut b = rng.gen_range(-10.0..10.0);
-    let mut c = rng.gen_range(-10.0..10.0);
-
-    // learning rate and iterations chosen for the toy example
-    let lr = 0.0015;
-    for _ in 0..20_000 {
-        let x = rng.gen_range(-5.0..5.0);
-        let predicted = a * x * x + b * x + c;
-        let actual = hidden_function(x);
-        let error = predicted - actual;
-
-        // gradient-like updates
-        a -= lr * error * x * x;
-        b -= lr * error * x;
-        c -= lr * error;
-    }
-
-    (a, b, c)
-}
-
 /// Core utilities: softmax, RNG helpers, arena placeholder.
 pub mod core;
+/// Number guessing over a range with pluggable strategies (`guess::GuessSolver`).
+pub mod guess;
+pub use guess::{GuessSolver, GuessStrategy};
 /// Minimal model container and generation helpers.
 pub mod model;
+/// Per-token stage timings of char model generation.
+#[cfg(feature = "profile")]
+pub mod profile;
+/// Speculative decoding of a char model with a smaller draft model.
+pub mod speculative;
+/// Vector similarity metrics and nearest-neighbour search over embeddings.
+pub mod similarity;
 /// Linear (dense) layer helper.
 pub mod linear;
 /// Training helpers (tiny demo loader)
+#[cfg(feature = "fs")]
 pub mod train;
+/// `AI::builder`: assemble an `AI` from injected model, memory and knowledge.
+#[cfg(feature = "fs")]
+pub mod builder;
+#[cfg(feature = "fs")]
+pub use builder::AIBuilder;
+/// Workspace configuration (`shark.toml`).
+#[cfg(feature = "fs")]
+pub mod config;
+/// Speech output and input adapter (`audio::Audio`, `audio::from_config`).
+#[cfg(feature = "fs")]
+pub mod audio;
+/// `ChatResponse`: answer text with its source, confidence, latency and length.
+#[cfg(feature = "fs")]
+pub mod response;
+#[cfg(feature = "fs")]
+pub use response::{ChatResponse, Source};
+/// Weight file inspection: format, tensor shapes against the model, value statistics.
+#[cfg(feature = "fs")]
+pub mod weights;
+/// Replay log of generations (seed, context, chosen tokens) and their re-runs.
+#[cfg(feature = "fs")]
+pub mod replay;
+/// LRU cache of deterministic pipeline answers (`cache::ResponseCache`).
+#[cfg(feature = "fs")]
+pub mod cache;
+/// Answer pipeline: ordered `Handler` stages shared by all front-ends.
+#[cfg(feature = "fs")]
+pub mod pipeline;
+#[cfg(feature = "fs")]
+pub use pipeline::{Handler, Pipeline};
+/// Tools callable from the pipeline (`Tool`, `ToolRegistry`).
+#[cfg(feature = "fs")]
+pub mod tools;
+#[cfg(feature = "fs")]
+pub use tools::{Tool, ToolRegistry};
+/// CSV analysis tool: schema, per-column statistics, line fits between columns.
+#[cfg(feature = "fs")]
+pub mod data_tool;
+/// Event bus for extensions (`events::bus`, typed `Event`s).
+#[cfg(feature = "fs")]
+pub mod events;
+#[cfg(feature = "fs")]
+pub use events::{Event, EventBus};
+/// Consistency checks of the knowledge base (`knowledge_store::find_conflicts`).
+#[cfg(feature = "fs")]
+pub mod knowledge_store;
+/// Facts taught in conversation ("X — это Y") for the `learn` stage.
+#[cfg(feature = "fs")]
+pub mod teach;
+/// Markdown reports with tables and SVG charts (`reports::Report`).
+#[cfg(feature = "fs")]
+pub mod reports;
+/// Conversation export to self-contained Markdown or HTML (`export::export_transcript`).
+#[cfg(feature = "fs")]
+pub mod export;
+/// Opt-in local usage statistics (`usage::UsageStats`).
+#[cfg(feature = "fs")]
+pub mod usage;
+/// Deny-list filters for prompts and answers (`moderation::Moderator`).
+#[cfg(feature = "fs")]
+pub mod moderation;
+/// Generators registered by name and selected per request (`model_registry::ModelRegistry`).
+#[cfg(feature = "fs")]
+pub mod model_registry;
+/// Plugins registered at startup or run as commands (`plugins::Plugins`).
+#[cfg(feature = "fs")]
+pub mod plugins;
+#[cfg(feature = "fs")]
+pub use plugins::Plugin;
+/// Background scheduler for the self-learning jobs (`jobs::Scheduler`).
+#[cfg(feature = "fs")]
+pub mod jobs;
+/// Dataset evaluations with a score history (`evaluation::run`).
+#[cfg(feature = "fs")]
+pub mod evaluation;
+/// Ranking of competing stage answers (`rank_answers`).
+#[cfg(feature = "fs")]
+pub mod rank;
+#[cfg(feature = "fs")]
+pub use rank::{rank_answers, RankedAnswer};
+#[cfg(feature = "fs")]
+pub use config::Config;
+/// Error type for loaders and persistence.
+pub mod error;
+pub use error::SharkError;
 /// Reasoner: stepwise explanation and reasoning logs.
+#[cfg(feature = "fs")]
 pub mod reasoner;
-/// Self-repair utilities: scan missing/broken modules and restore minimal stubs.
+/// Self-repair utilities: check critical modules and write stubs into a sandbox.
+#[cfg(feature = "fs")]
 pub mod self_repair;
 /// Knowledge environment helpers (expand directories, merge sources)
+#[cfg(feature = "fs")]
 pub mod knowledge_env;
 /// Simple integrator for polynomials and a small query interface.
+#[cfg(feature = "fs")]
 pub mod integrator;
+/// C ABI for embedding (`shark_model_load`, `shark_chat`, `shark_free_string`).
+#[cfg(feature = "capi")]
+#[allow(unsafe_code)]
+pub mod capi;
 /// Weight loader (file helpers).
 pub mod loader;
 /// Local tokenizer utilities.
 pub mod tokenizer;
 /// Simple persistent memory for dialogs.
+#[cfg(feature = "fs")]
 pub mod memory;
+/// HTTP API of the `server` binary (`server::spawn`, `server::serve`).
+#[cfg(feature = "server")]
+pub mod server;
+/// Filesystem-free `AI` fixtures (`testkit::FakeModel`) and golden-file checks.
+#[cfg(feature = "fs")]
+pub mod testkit;
+/// Slash-commands shared by the interactive front-ends.
+#[cfg(feature = "fs")]
+pub mod commands;
+/// Tutor mode: per-user scores and spaced repetition over the problems dataset.
+#[cfg(feature = "fs")]
+pub mod tutor;
+/// Character-level n-gram language model (fallback generator).
+pub mod ngram;
 /// (internal) Scientist and small demo helpers remain in the crate but are
 /// not re-exported as part of the public minimal API.
+#[cfg(feature = "science")]
 pub mod scientist;
+/// Genetic search of backtest strategy parameters (`optimizer::optimize`).
+#[cfg(all(feature = "science", feature = "trading"))]
+pub mod optimizer;
+/// Backtesting engine (feature `trading`).
+#[cfg(feature = "trading")]
+pub use backtest;
+/// Technical indicators (feature `trading`).
+#[cfg(feature = "trading")]
+pub use indicators;
+/// Tearsheet comparing backtest runs (`tearsheet::compare`).
+#[cfg(all(feature = "fs", feature = "trading"))]
+pub mod tearsheet;
+/// JSON backtests and indicators for the server (`POST /backtest`, `POST /indicators/{name}`).
+#[cfg(all(feature = "fs", feature = "trading"))]
+pub mod quant_api;
+#[cfg(feature = "fs")]
 mod simple_model;
-/// Conservative decoding helpers for presenting model output.
+/// Decoding helpers for presenting model output.
 ///
-/// Contains `decode_raw` which performs a minimal, lossy transformation of
-/// raw model bytes into a human-readable string suitable for UI display.
+/// `decode_with` filters raw model output by a `DecodeOptions` policy
+/// (allowed scripts, whitespace, terminal punctuation); `decode_raw` is the
+/// conservative ASCII-only preset.
 pub mod decode;
-pub use decode::decode_raw;
+pub use decode::{decode_raw, decode_with, DecodeOptions};
+/// Confidence calibration (entropy, perplexity, solver verification → 0..=1).
+pub mod calibration;
+/// Running statistics (count, mean, min, max, standard deviation).
+pub mod stats;
+pub use stats::RunningStats;
+/// Least-squares polynomial regression with R², AIC and degree selection.
+pub mod regression;
+pub use regression::{discover_equation, linear_regressor_predict};
+/// Seeded k-means (k-means++ init) and word-frequency topics of texts.
+pub mod clustering;
+/// Grammar-constrained generation templates (literals and masked text slots).
+pub mod constrained;
 /// Small rule-based grammar/interpretation helpers (toy diagnostic layer).
+#[cfg(feature = "fs")]
 pub mod grammar;
-pub use grammar::interpret;
+#[cfg(feature = "fs")]
+pub use grammar::{interpret, Grammar};
 /// Contextual interpretation helpers (frequency-based word selection).
+#[cfg(feature = "fs")]
 pub mod context;
+#[cfg(feature = "fs")]
+#[allow(deprecated)]
 pub use context::{interpret_contextual, load_memory_freq, save_memory_freq, update_memory_freq, interpret_contextual_with_memory};
-/// Memory frequency helpers for persistent word learning.
+/// Word-frequency memory (`WordFrequencyStore`) for persistent word learning.
+#[cfg(feature = "fs")]
 pub mod memory_freq;
-pub use memory_freq::*;
+#[cfg(feature = "fs")]
+pub use memory_freq::{WordFrequencyStore, MEMORY_FREQ_PATH};
+/// Edit-distance spelling corrector over the word-frequency memory.
+#[cfg(feature = "fs")]
+pub mod corrector;
+#[cfg(feature = "fs")]
+pub use corrector::Corrector;
 /// Reasoning helpers for query understanding and response building.
+#[cfg(feature = "fs")]
 pub mod reasoning;
+#[cfg(feature = "fs")]
 pub use reasoning::*;
+/// Reranking of fuzzy knowledge matches by trigrams, shared words and embeddings.
+#[cfg(feature = "fs")]
+pub mod rerank;
+/// TF-IDF similarity over the knowledge questions (`[pipeline] retrieval = "tfidf"`).
+#[cfg(feature = "fs")]
+pub mod tfidf;
 /// Semantic question understanding helpers.
+#[cfg(feature = "fs")]
 pub mod semantic_question_understanding;
+#[cfg(feature = "fs")]
 pub use semantic_question_understanding::*;
+/// Intent classification (logistic regression over trigrams) for routing prompts.
+#[cfg(feature = "fs")]
+pub mod intent;
+#[cfg(feature = "fs")]
+pub use intent::{Intent, IntentClassifier};
+/// Entity extraction: numbers, units, variables, dates and expressions in prompts.
+#[cfg(feature = "fs")]
+pub mod extract;
+#[cfg(feature = "fs")]
+pub use extract::{extract, Extraction};
+/// Conversation state (topic, last expression) for resolving follow-ups.
+#[cfg(feature = "fs")]
+pub mod dialogue_state;
+#[cfg(feature = "fs")]
+pub use dialogue_state::DialogueState;
+/// Structured JSON-mode answers (`AI::chat_structured`).
+#[cfg(feature = "fs")]
+pub mod structured;
+#[cfg(feature = "fs")]
+pub use structured::StructuredAnswer;
 
+#[cfg(feature = "fs")]
 use crate::model::Model;
+#[cfg(feature = "fs")]
 use crate::memory::Memory;
 
 /// Simple AI wrapper combining a `Model` and persistent `Memory`.
+#[cfg(feature = "fs")]
 pub struct AI {
     /// underlying model used for generation
     pub model: Model,
     /// persistent memory for dialogs
     pub memory: Memory,
-    /// knowledge base for reasoning
+    /// knowledge base for reasoning (empty until loaded when created with `new_lazy`)
     pub knowledge: std::collections::HashMap<String, String>,
+    knowledge_loaded: bool,
+    /// n-gram fallback generator used instead of `model` when present (see `ngram::NGRAM_PATH`)
+    pub ngram: Option<ngram::NgramModel>,
+    /// configuration the AI was created with (paths, sampler defaults)
+    pub config: Config,
+    /// stages `chat` routes prompts through (`pipeline.stages`)
+    pub pipeline: std::sync::Arc<Pipeline>,
+    /// topic and last expression, used by `chat` to resolve follow-up pronouns
+    pub dialogue: DialogueState,
+    /// the model stage samples `structured::answer_template` (`chat_structured`)
+    pub(crate) structured: bool,
+    /// tools the `arithmetic`, `linear` and `tools` stages call
+    pub tools: std::sync::Arc<ToolRegistry>,
+    /// filters `Pipeline::run` applies to prompts and answers (`[moderation]`)
+    pub moderator: std::sync::Arc<moderation::Moderator>,
+    /// generators selectable by name (`use_model`); the model stage uses the
+    /// selected one instead of `model` / `ngram`
+    pub models: model_registry::ModelRegistry,
+    /// counters of speculative decoding (`model.draft`)
+    speculative_stats: std::sync::Mutex<speculative::SpeculativeStats>,
+    /// log `generate` appends every generation to (`[replay] enabled`)
+    pub replay: Option<replay::ReplayLog>,
+    /// timings of the last plain char model generation
+    #[cfg(feature = "profile")]
+    last_profile: std::sync::Mutex<Option<profile::Profile>>,
 }
 
+#[cfg(feature = "fs")]
 impl AI {
-    /// Create AI by loading model weights from `path` and memory from default file.
-    pub fn new(path: &str) -> Self {
-        let model = Model::load(path);
-        let memory = Memory::load("memory.db");
-        let knowledge = load_knowledge_for_reasoning();
-        Self { model, memory, knowledge }
+    /// Create AI from a configuration: model weights (`model.path`), dialog memory
+    /// (`data.memory`), the n-gram fallback and the knowledge base. A missing weights
+    /// file gives an untrained model (see `Model::load_or_zeroed`); every other
+    /// failure is returned.
+    pub fn new(config: &Config) -> Result<Self, SharkError> {
+        Self::builder().config(config.clone()).eager_knowledge(true).build()
     }
 
-    /// Produce a response for the given input, persist dialog to memory.
-    pub fn chat(&mut self, input: &str) -> String {
-        // Try reasoning first if it looks like a query
-        if detect_mode(input) != "statement" {
-            let reasoned = reason_response(input, &self.knowledge);
-            if !reasoned.contains("Не нашел") {
-                let _ = self.memory.save_dialog(input, &reasoned);
-                return reasoned;
-            }
+    /// Like `new`, but the knowledge base is loaded on first use
+    /// (see `ensure_knowledge`). Used by fast startup paths.
+    pub fn new_lazy(config: &Config) -> Result<Self, SharkError> {
+        Self::builder().config(config.clone()).build()
+    }
+
+    /// Builder for AI instances with injected parts (in-memory stores, fake
+    /// models, a fixed knowledge map); see `AIBuilder`.
+    pub fn builder() -> AIBuilder {
+        AIBuilder::default()
+    }
+
+    /// Assemble AI from an already loaded model and memory, with the default
+    /// configuration and no n-gram fallback; the knowledge base is loaded on first use.
+    pub fn from_parts(model: Model, memory: Memory) -> Self {
+        Self {
+            model,
+            memory,
+            knowledge: std::collections::HashMap::new(),
+            knowledge_loaded: false,
+            ngram: None,
+            config: Config::default(),
+            pipeline: std::sync::Arc::new(Pipeline::standard()),
+            dialogue: DialogueState::new(),
+            structured: false,
+            tools: std::sync::Arc::new(ToolRegistry::standard()),
+            moderator: std::sync::Arc::new(moderation::Moderator::new()),
+            models: model_registry::ModelRegistry::new(),
+            speculative_stats: std::sync::Mutex::new(speculative::SpeculativeStats::default()),
+            replay: None,
+            #[cfg(feature = "profile")]
+            last_profile: std::sync::Mutex::new(None),
+        }
+    }
+
+    /// Load the knowledge base if it has not been loaded yet and return it.
+    /// A load error is returned once; afterwards the knowledge base stays empty.
+    pub fn ensure_knowledge(&mut self) -> Result<&std::collections::HashMap<String, String>, SharkError> {
+        if !self.knowledge_loaded {
+            self.knowledge_loaded = true;
+            self.knowledge = load_knowledge_for_reasoning(&self.config.knowledge_path())?;
+        }
+        Ok(&self.knowledge)
+    }
+
+    /// Re-read the knowledge base (e.g. after a background merge added entries);
+    /// returns the number of entries.
+    pub fn reload_knowledge(&mut self) -> Result<usize, SharkError> {
+        self.knowledge_loaded = false;
+        self.pipeline.clear_cache();
+        Ok(self.ensure_knowledge()?.len())
+    }
+
+    /// Produce a response for the given input through `pipeline`, persist dialog to memory.
+    /// Pronouns referring to the previous turn are resolved first (`dialogue`);
+    /// the resolved prompt is what the pipeline sees and the memory stores.
+    /// `Event::MessageReceived` and `Event::AnswerProduced` are published on `events::bus`.
+    /// Failures to write the memory file are reported on stderr.
+    pub fn chat(&mut self, input: &str) -> ChatResponse {
+        let resolved = self.dialogue.resolve(input);
+        events::publish(Event::MessageReceived { prompt: resolved.clone() });
+        let pipeline = std::sync::Arc::clone(&self.pipeline);
+        let mut response = pipeline.run(self, &resolved);
+        if resolved != input {
+            response.reasoning.insert(0, format!("уточнение по контексту: «{}»", resolved));
+        }
+        self.dialogue.observe(&resolved, &response.text);
+        events::publish(Event::AnswerProduced {
+            prompt: resolved,
+            text: response.text.clone(),
+            stage: response.stage,
+            source: response.source,
+            confidence: response.confidence,
+            latency_ms: response.latency_ms(),
+        });
+        response
+    }
+
+    /// Select the generator of the model stage by name (`model_registry`):
+    /// `default` for `ngram` / `model`, else a registered one, loaded on first
+    /// use. A char model takes over the current seed and answer length.
+    pub fn use_model(&mut self, name: &str) -> Result<(), SharkError> {
+        if let Some(model_registry::Generator::Char(model)) = self.models.select(Some(name))? {
+            model.seed = self.model.seed;
+            model.max_chars = self.model.max_chars;
+        }
+        Ok(())
+    }
+
+    /// Generator the model stage uses: the selected one, else the n-gram model
+    /// if one is trained, else the toy model.
+    pub fn generator(&self) -> model_registry::GeneratorRef<'_> {
+        use model_registry::GeneratorRef;
+        match (self.models.generator(), &self.ngram) {
+            (Some(selected), _) => selected,
+            (None, Some(ngram)) => GeneratorRef::Ngram(ngram),
+            (None, None) => GeneratorRef::Char(&self.model),
         }
-        // Fallback to model generation
+    }
+
+    /// Generate an answer from the dialog context with `generator`. Returns the
+    /// raw text and its calibrated confidence (`calibration::from_perplexity` /
+    /// `from_entropy`). With a replay log the generation is recorded.
+    pub fn generate(&self, input: &str) -> (String, f32) {
         let context = self.memory.build_context(input);
-        let response_raw = self.model.generate(&context);
-        let _ = self.memory.save_dialog(input, &response_raw);
-        response_raw
+        let (text, confidence) = self.generate_from(&context);
+        if let Some(log) = &self.replay {
+            if let Err(e) = log.append(&replay::record(self, &context, &text)) {
+                eprintln!("⚠️ {}: {}", log.path.display(), e);
+            }
+        }
+        (text, confidence)
     }
-}
 
-/// Load knowledge as map for reasoning.
-pub fn load_knowledge_for_reasoning() -> std::collections::HashMap<String, String> {
-    use std::collections::HashMap;
-    let mut knowledge = HashMap::new();
-    if let Ok(content) = std::fs::read_to_string("crates/predict/data/knowledge.csv") {
-        for line in content.lines().skip(1) {
-            if let Some((q, a)) = line.split_once(',') {
-                knowledge.insert(q.trim().trim_matches('"').to_lowercase(), a.trim().trim_matches('"').to_string());
+    /// `generate` from an already built context, without recording it.
+    pub fn generate_from(&self, context: &str) -> (String, f32) {
+        #[cfg(feature = "profile")]
+        if let Ok(mut last) = self.last_profile.lock() {
+            *last = None;
+        }
+        match self.generator() {
+            model_registry::GeneratorRef::Ngram(ngram) => {
+                let seed = context.bytes().fold(0x9e3779b97f4a7c15u64 ^ self.model.seed, |s, b| s.wrapping_mul(31).wrapping_add(b as u64));
+                let text = ngram.generate(self.config.sampler.ngram_max_chars, seed);
+                let confidence = calibration::from_perplexity(ngram.perplexity(&text), ngram.vocab_size());
+                (text, confidence)
             }
+            model_registry::GeneratorRef::Char(model) => match self.draft() {
+                Some(draft) => {
+                    let (text, confidence, stats) = speculative::generate(draft, model, context, self.config.model.speculative_k);
+                    if let Ok(mut total) = self.speculative_stats.lock() {
+                        total.add(&stats);
+                    }
+                    (text, confidence)
+                }
+                #[cfg(feature = "profile")]
+                None => {
+                    let (text, confidence, profile) = model.generate_profiled(context);
+                    if let Ok(mut last) = self.last_profile.lock() {
+                        *last = Some(profile);
+                    }
+                    (text, confidence)
+                }
+                #[cfg(not(feature = "profile"))]
+                None => model.generate_scored(context),
+            },
+        }
+    }
+
+    /// Draft model of `model.draft`, when configured and loaded.
+    fn draft(&self) -> Option<&Model> {
+        match self.models.get(&self.config.model.draft)? {
+            model_registry::GeneratorRef::Char(draft) => Some(draft),
+            model_registry::GeneratorRef::Ngram(_) => None,
+        }
+    }
+
+    /// Embedding of `text` from the selected char model (`use_model`), else the
+    /// resident one (see `Model::embed`).
+    pub fn embed(&self, text: &str) -> Vec<f32> {
+        match self.models.generator() {
+            Some(model_registry::GeneratorRef::Char(model)) => model.embed(text),
+            _ => self.model.embed(text),
+        }
+    }
+
+    /// The `k` stored questions whose embeddings are closest (cosine) to
+    /// `text`'s, best first, with their scores.
+    pub fn similar_questions(&self, text: &str, k: usize) -> Vec<(String, f32)> {
+        let mut questions: Vec<&String> = self.knowledge.keys().collect();
+        questions.sort();
+        let vectors: Vec<Vec<f32>> = questions.iter().map(|q| self.embed(q)).collect();
+        similarity::knn(&self.embed(text), &vectors, k, similarity::Metric::Cosine)
+            .into_iter()
+            .filter_map(|n| questions.get(n.index).map(|q| (q.to_string(), n.score)))
+            .collect()
+    }
+
+    /// Seed of topic clustering: the `topics` stream of `[rng]`, else fixed.
+    fn topics_seed(&self) -> u64 {
+        self.config.rng_source().map_or(42, |rng| rng.stream_seed(crate::core::RngSource::TOPICS))
+    }
+
+    /// The stored questions grouped into topics by their words (see
+    /// `clustering::topics`), largest first.
+    pub fn knowledge_topics(&self) -> Vec<clustering::Topic> {
+        let mut questions: Vec<&str> = self.knowledge.keys().map(String::as_str).collect();
+        questions.sort();
+        let (_, vectors) = clustering::word_vectors(&questions);
+        clustering::topics(&questions, &vectors, clustering::suggested_k(questions.len()), self.topics_seed())
+    }
+
+    /// The prompts of the dialog memory grouped into topics by their
+    /// embeddings (`embed`), or by their words when the model gives none.
+    pub fn memory_topics(&self) -> Vec<clustering::Topic> {
+        let prompts: Vec<&str> = self.memory.dialogs().iter().map(|(prompt, _)| prompt.as_str()).collect();
+        let mut vectors: Vec<Vec<f32>> = prompts.iter().map(|p| self.embed(p)).collect();
+        if vectors.iter().all(Vec::is_empty) {
+            vectors = clustering::word_vectors(&prompts).1;
+        }
+        clustering::topics(&prompts, &vectors, clustering::suggested_k(prompts.len()), self.topics_seed())
+    }
+
+    /// Timings of the last plain char model generation (`None` before the
+    /// first one; speculative and n-gram generations are not profiled).
+    #[cfg(feature = "profile")]
+    pub fn last_profile(&self) -> Option<profile::Profile> {
+        self.last_profile.lock().ok().and_then(|p| *p)
+    }
+
+    /// Speculative decoding counters since startup (acceptance rate, target passes).
+    pub fn speculative_stats(&self) -> speculative::SpeculativeStats {
+        self.speculative_stats.lock().map(|s| *s).unwrap_or_default()
+    }
+
+    /// `chat` in JSON mode: the model stage generates through the
+    /// grammar-constrained sampler, and the response is returned as a typed
+    /// `StructuredAnswer` (answer, steps, references, numeric value).
+    pub fn chat_structured(&mut self, input: &str) -> StructuredAnswer {
+        self.structured = true;
+        let response = self.chat(input);
+        self.structured = false;
+        StructuredAnswer::from_response(&response)
+    }
+
+    /// `generate` constrained to a `{"answer": "..."}` JSON object: the toy
+    /// model samples `structured::answer_template`; n-gram text is escaped
+    /// into the same object.
+    pub fn generate_structured(&self, input: &str) -> (String, f32) {
+        match self.generator() {
+            model_registry::GeneratorRef::Ngram(_) => {
+                let (text, confidence) = self.generate(input);
+                (serde_json::json!({ structured::ANSWER_FIELD: text }).to_string(), confidence)
+            }
+            model_registry::GeneratorRef::Char(model) => {
+                let context = self.memory.build_context(input);
+                model.generate_template(&context, &structured::answer_template(model.max_chars))
+            }
+        }
+    }
+
+    /// `chat` returning only the answer text.
+    pub fn chat_text(&mut self, input: &str) -> String {
+        self.chat(input).text
+    }
+
+    /// Save a dialog pair, reporting (not failing on) persistence errors.
+    pub fn remember(&mut self, input: &str, response: &str) {
+        if let Err(e) = self.memory.save_dialog(input, response) {
+            eprintln!("⚠️ не удалось сохранить диалог: {}", e);
+        }
+    }
+}
+
+/// Load a knowledge CSV (e.g. `Config::knowledge_path`) as a lowercase
+/// question → answer map for reasoning.
+#[cfg(feature = "fs")]
+pub fn load_knowledge_for_reasoning(path: &std::path::Path) -> Result<std::collections::HashMap<String, String>, SharkError> {
+    let content = std::fs::read_to_string(path).map_err(|e| SharkError::io(path, e))?;
+    let mut knowledge = std::collections::HashMap::new();
+    for line in content.lines().skip(1) {
+        if let Some((q, a)) = line.split_once(',') {
+            knowledge.insert(q.trim().trim_matches('"').to_lowercase(), a.trim().trim_matches('"').to_string());
         }
     }
-    knowledge
+    Ok(knowledge)
 }
 
 #[cfg(test)]
@@ -238,15 +603,15 @@ mod tests {
     #[test]
     fn predict_hidden_number_binary() {
         let target = 23;
-        let result = guess(target);
-        assert_eq!(result, target);
+        let result = GuessSolver::new(0..=99).solve(|n| n.cmp(&target));
+        assert_eq!(result, Some(target));
     }
 
     #[test]
     fn probabilistic_prediction_close() {
         let target = 23;
-        let result = probabilistic_guess(target, 42);
-        assert!((result - target).abs() <= 5, "не попал в диапазон");
+        let result = GuessSolver::new(0..=99).strategy(GuessStrategy::Random { seed: 42 }).solve(|n| n.cmp(&target));
+        assert_eq!(result, Some(target), "не попал в диапазон");
     }
 
     #[test]
@@ -261,7 +626,9 @@ mod tests {
 
     #[test]
     fn discover_hidden_equation_converges() {
-        let (a, b, c) = discover_equation(42);
+        let report = discover_equation(hidden_function, &regression::DiscoverOptions::default());
+        assert_eq!(report.coefficients.len(), 3, "{:?}", report.coefficients);
+        let &[c, b, a] = report.coefficients.as_slice() else { return };
         // print for debugging if needed
         println!("Predicted coefficients: a={:.4}, b={:.4}, c={:.4}", a, b, c);
         assert!((a - 3.0).abs() < 0.1, "a не сходится");
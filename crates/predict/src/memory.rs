@@ -2,29 +2,41 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::error::{Result, SharkError};
+
 #[derive(Serialize, Deserialize, Debug, Default)]
 /// Simple dialog memory storing (user, assistant) pairs.
 pub struct Memory {
     dialogs: Vec<(String, String)>,
+    /// file the memory persists to after each change (`None` keeps it in memory only)
+    #[serde(skip)]
+    path: Option<String>,
 }
 
 impl Memory {
-    /// Load memory from a file (bincode). If file missing, return empty memory.
-    pub fn load(path: &str) -> Self {
-        match std::fs::read(path) {
-            Ok(bytes) => match bincode::deserialize::<Memory>(&bytes) {
-                Ok(m) => m,
-                Err(_) => Memory::default(),
-            },
-            Err(_) => Memory::default(),
-        }
+    /// Load memory from a file (bincode). A missing file gives an empty memory;
+    /// an unreadable or corrupt file is an error (it would be overwritten otherwise).
+    /// Later changes are persisted back to the same file.
+    pub fn load(path: &str) -> Result<Self> {
+        let mut memory = match std::fs::read(path) {
+            Ok(bytes) => bincode::deserialize::<Memory>(&bytes)
+                .map_err(|e| SharkError::Corrupt { path: path.into(), reason: e.to_string() })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Memory::default(),
+            Err(e) => return Err(SharkError::io(path, e)),
+        };
+        memory.path = Some(path.to_string());
+        Ok(memory)
+    }
+
+    /// Empty memory that is never written to disk (replays, tests).
+    pub fn in_memory() -> Self {
+        Memory::default()
     }
 
     /// Save memory to a file path
-    pub fn save(&self, path: &str) {
-        if let Ok(bytes) = bincode::serialize(self) {
-            let _ = std::fs::write(path, bytes);
-        }
+    pub fn save(&self, path: &str) -> Result<()> {
+        let bytes = bincode::serialize(self).map_err(|e| SharkError::Corrupt { path: path.into(), reason: e.to_string() })?;
+        std::fs::write(path, bytes).map_err(|e| SharkError::io(path, e))
     }
 
     /// Build a naive context string combining recent dialogs and the new input.
@@ -38,10 +50,50 @@ impl Memory {
         parts.join("\n")
     }
 
-    /// Append a dialog pair and persist to default file.
-    pub fn save_dialog(&mut self, input: &str, response: &str) {
+    /// Stored (user, assistant) pairs, oldest first.
+    pub fn dialogs(&self) -> &[(String, String)] {
+        &self.dialogs
+    }
+
+    /// Forget all dialogs and persist the empty memory.
+    pub fn clear(&mut self) -> Result<()> {
+        self.dialogs.clear();
+        self.persist()
+    }
+
+    /// Append a dialog pair and persist it. The pair stays in memory even if
+    /// writing the file fails.
+    pub fn save_dialog(&mut self, input: &str, response: &str) -> Result<()> {
+        self.push(input, response);
+        self.persist()
+    }
+
+    /// Append a dialog pair without writing the file.
+    pub fn push(&mut self, input: &str, response: &str) {
         self.dialogs.push((input.to_string(), response.to_string()));
-        // persist to default file
-        self.save("memory.db");
+    }
+
+    fn persist(&self) -> Result<()> {
+        match &self.path {
+            Some(path) => self.save(path),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corrupt_file_is_an_error_and_missing_file_is_empty() {
+        let path = std::env::temp_dir().join(format!("shark_memory_{}.db", std::process::id()));
+        let path = path.to_string_lossy().to_string();
+        assert_eq!(Memory::load(&path).map(|m| m.dialogs().len()).ok(), Some(0));
+
+        let _ = std::fs::write(&path, [0xff; 3]);
+        let corrupt = Memory::load(&path);
+        let _ = std::fs::remove_file(&path);
+        assert!(matches!(corrupt, Err(SharkError::Corrupt { .. })));
     }
 }
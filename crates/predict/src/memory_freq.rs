@@ -1,40 +1,175 @@
 use std::collections::HashMap;
-use std::fs::{self, OpenOptions};
-use std::io::{Write, BufRead, BufReader};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
-/// Update memory frequencies by incrementing counts for each word in the list.
-/// Loads existing frequencies, updates them, and saves back to the file.
-pub fn update_memory_freq(words: &[String], path: &str) {
-    let mut freq: HashMap<String, usize> = load_memory_freq(path);
+use crate::error::{Result, SharkError};
 
-    for w in words {
-        *freq.entry(w.clone()).or_insert(0) += 1;
-    }
+/// Default word-frequency memory (used by the `corrector`).
+pub const MEMORY_FREQ_PATH: &str = "memory_freq.csv";
 
-    save_memory_freq(&freq, path);
+/// Word → count memory persisted as CSV (`word,freq` header, one `word,count` per line).
+///
+/// Lines that do not parse (including the header) are skipped on load, so
+/// both the old headerless files and the `context` files with a header load.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WordFrequencyStore {
+    counts: HashMap<String, usize>,
+    /// file `persist` writes to (`None` keeps the store in memory only)
+    path: Option<PathBuf>,
 }
 
-/// Load word frequencies from a CSV file (word,freq format).
-/// Returns empty map if file doesn't exist or can't be read.
-pub fn load_memory_freq(path: &str) -> HashMap<String, usize> {
-    let mut map = HashMap::new();
-    if let Ok(file) = fs::File::open(path) {
-        for line in BufReader::new(file).lines().flatten() {
-            if let Some((word, count)) = line.split_once(',') {
-                if let Ok(n) = count.trim().parse::<usize>() {
-                    map.insert(word.trim().to_string(), n);
+impl WordFrequencyStore {
+    /// Empty store that is never written to disk.
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Store over existing counts (in memory only).
+    pub fn from_counts(counts: HashMap<String, usize>) -> Self {
+        Self { counts, path: None }
+    }
+
+    /// Load the store from `path`; a missing file gives an empty store.
+    /// `persist` writes back to the same file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let mut counts = HashMap::new();
+        match std::fs::read_to_string(path) {
+            Ok(content) => {
+                for line in content.lines() {
+                    if let Some((word, count)) = line.rsplit_once(',') {
+                        let word = word.trim();
+                        if let (false, Ok(n)) = (word.is_empty(), count.trim().parse::<usize>()) {
+                            *counts.entry(word.to_string()).or_insert(0) += n;
+                        }
+                    }
                 }
             }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(SharkError::io(path, e)),
+        }
+        Ok(Self { counts, path: Some(path.to_path_buf()) })
+    }
+
+    /// Count of `word` (0 if unseen).
+    pub fn get(&self, word: &str) -> usize {
+        self.counts.get(word).copied().unwrap_or(0)
+    }
+
+    /// All counts.
+    pub fn counts(&self) -> &HashMap<String, usize> {
+        &self.counts
+    }
+
+    /// Take the counts out of the store.
+    pub fn into_counts(self) -> HashMap<String, usize> {
+        self.counts
+    }
+
+    /// Number of distinct words.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Whether no word has been counted.
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Add `n` to the count of `word` (trimmed; empty words are ignored).
+    pub fn add(&mut self, word: &str, n: usize) {
+        let word = word.trim();
+        if !word.is_empty() {
+            *self.counts.entry(word.to_string()).or_insert(0) += n;
         }
     }
-    map
-}
 
-/// Save word frequencies to a CSV file (word,freq format).
-fn save_memory_freq(freq: &HashMap<String, usize>, path: &str) {
-    if let Ok(mut file) = OpenOptions::new().create(true).write(true).truncate(true).open(path) {
-        for (w, n) in freq {
-            let _ = writeln!(file, "{},{}", w, n);
+    /// Count one occurrence of each word.
+    pub fn update<I, S>(&mut self, words: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for word in words {
+            self.add(word.as_ref(), 1);
         }
     }
-}
\ No newline at end of file
+
+    /// The `k` most frequent words, ties broken alphabetically.
+    pub fn top_k(&self, k: usize) -> Vec<(&str, usize)> {
+        let mut pairs: Vec<(&str, usize)> = self.counts.iter().map(|(w, &n)| (w.as_str(), n)).collect();
+        pairs.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        pairs.truncate(k);
+        pairs
+    }
+
+    /// Write the store to `path` atomically: the CSV (sorted by word) goes to
+    /// a temporary file next to it, which then replaces `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let mut words: Vec<(&String, &usize)> = self.counts.iter().collect();
+        words.sort();
+        let mut csv = String::from("word,freq\n");
+        for (word, count) in words {
+            csv.push_str(&format!("{},{}\n", word, count));
+        }
+        let mut tmp_name = path.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+        tmp_name.push(".tmp");
+        let tmp = path.with_file_name(tmp_name);
+        let written = std::fs::File::create(&tmp).and_then(|mut file| {
+            file.write_all(csv.as_bytes())?;
+            file.sync_all()
+        });
+        if let Err(e) = written.and_then(|()| std::fs::rename(&tmp, path)) {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(SharkError::io(path, e));
+        }
+        Ok(())
+    }
+
+    /// Save to the file the store was loaded from (no-op for in-memory stores).
+    pub fn persist(&self) -> Result<()> {
+        match &self.path {
+            Some(path) => self.save(path),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Update memory frequencies by incrementing counts for each word in the list.
+/// Loads existing frequencies, updates them, and saves back to the file.
+#[deprecated(note = "use `WordFrequencyStore::load`, `update` and `persist`")]
+pub fn update_memory_freq(words: &[String], path: &str) {
+    let mut store = WordFrequencyStore::load(path).unwrap_or_default();
+    store.update(words);
+    let _ = store.save(path);
+}
+
+/// Load word frequencies from a CSV file (word,freq format).
+/// Returns empty map if file doesn't exist or can't be read.
+#[deprecated(note = "use `WordFrequencyStore::load`")]
+pub fn load_memory_freq(path: &str) -> HashMap<String, usize> {
+    WordFrequencyStore::load(path).map(WordFrequencyStore::into_counts).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store_round_trips_and_reads_both_layouts() {
+        let path = std::env::temp_dir().join(format!("shark_memory_freq_{}.csv", std::process::id()));
+        let _ = std::fs::write(&path, "word,freq\nакула,2\nhello,1\n");
+        let store = WordFrequencyStore::load(&path);
+        assert!(store.is_ok());
+        let Ok(mut store) = store else { return };
+        store.update(["hello", "hello", " ", "мир"]);
+        assert_eq!(store.top_k(2), vec![("hello", 3), ("акула", 2)]);
+        assert!(store.persist().is_ok());
+
+        let _ = std::fs::write(&path, format!("{}world,4\n", std::fs::read_to_string(&path).unwrap_or_default()));
+        let reloaded = WordFrequencyStore::load(&path).map(|s| (s.get("hello"), s.get("world"), s.len()));
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(reloaded.ok(), Some((3, 4, 4)));
+    }
+}
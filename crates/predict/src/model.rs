@@ -1,10 +1,33 @@
 #![forbid(unsafe_code)]
 
+use std::path::Path;
+
+use crate::calibration;
+use crate::constrained::{Segment, Template};
 use crate::loader;
 use crate::core;
-use crate::linear::Linear;
+use crate::error::{Result, SharkError};
+use crate::linear::{Accumulation, Linear};
 use crate::tokenizer::ALPHABET;
 
+/// Weight initialization strategies (Xavier, He, uniform) for training from scratch.
+pub mod init;
+/// Resizing, merging and pruning of trained weights.
+pub mod surgery;
+
+/// Step of the generation loop, reported to `Model::sample_text`'s callback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Step {
+    /// input vector built or advanced
+    Embed,
+    /// logits computed
+    Forward,
+    /// logits normalized
+    Softmax,
+    /// next character drawn
+    Sample,
+}
+
 /// Small toy model with a tiny embedding + MLP for deterministic generation.
 pub struct Model {
     /// first linear layer (embed -> hidden)
@@ -13,78 +36,282 @@ pub struct Model {
     pub lin2: Linear,
     /// vocabulary size used by the decoder
     pub vocab_size: usize,
+    /// extra seed mixed into the per-context generation RNG (0 keeps the default stream)
+    pub seed: u64,
+    /// characters produced by `generate`
+    pub max_chars: usize,
+    /// precision of the matmul sums (`accumulation` in `[model]` / `[[models]]`)
+    pub accumulation: Accumulation,
 }
 
 impl Model {
-    /// Load weights and construct a tiny model. If weights are missing or too small,
-    /// layers are created with zero weights (deterministic fallback).
-    pub fn load(path: &str) -> Self {
-        // load raw bytes and convert to f32 little-endian chunks
-        let raw_bytes = loader::load_weights(path).unwrap_or_default();
-        let mut floats = vec![];
-        let mut i = 0usize;
-        while i + 4 <= raw_bytes.len() {
-            let b = &raw_bytes[i..i+4];
-            let v = f32::from_le_bytes([b[0], b[1], b[2], b[3]]);
-            floats.push(v);
-            i += 4;
+    /// Embedding width of the toy model (input of `lin1`).
+    pub const EMBED: usize = 32;
+    /// Hidden width of the toy model.
+    pub const HIDDEN: usize = 64;
+
+    /// Load weights written by `save` (little-endian f32: lin1 weights, lin1 bias,
+    /// lin2 weights, lin2 bias). Missing, unreadable or truncated files are errors;
+    /// extra trailing floats are ignored.
+    #[cfg(feature = "fs")]
+    pub fn load(path: &str) -> Result<Self> {
+        Self::load_dims(path, Self::EMBED, Self::HIDDEN)
+    }
+
+    /// `load` for a model with other layer widths (e.g. after `surgery::resize_hidden`).
+    #[cfg(feature = "fs")]
+    pub fn load_dims(path: &str, embed: usize, hidden: usize) -> Result<Self> {
+        Self::from_floats(Path::new(path), &loader::load_f32_file(path)?, embed, hidden)
+    }
+
+    /// Build from weights already in memory, in the layout `save` writes
+    /// (e.g. fetched by a browser front-end).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Self::from_floats(Path::new("<bytes>"), &loader::bytes_to_f32(bytes), Self::EMBED, Self::HIDDEN)
+    }
+
+    fn from_floats(path: &Path, floats: &[f32], embed: usize, hidden: usize) -> Result<Self> {
+        let vocab = ALPHABET.len();
+        let needed1 = Linear::param_count(embed, hidden);
+        let needed2 = Linear::param_count(hidden, vocab);
+        if floats.len() < needed1 + needed2 {
+            return Err(SharkError::WeightsTooSmall { path: path.into(), expected: needed1 + needed2, found: floats.len() });
         }
+        let (slice1, rest) = floats.split_at(needed1);
+        let lin1 = Linear::from_raw(embed, hidden, slice1);
+        let lin2 = Linear::from_raw(hidden, vocab, rest);
+        Ok(Self { lin1, lin2, vocab_size: vocab, seed: 0, max_chars: 64, accumulation: Accumulation::F32 })
+    }
+
+    /// Untrained model with all weights zero (generation is then uniform sampling).
+    pub fn zeroed() -> Self {
+        let vocab = ALPHABET.len();
+        Self {
+            lin1: Linear::from_raw(Self::EMBED, Self::HIDDEN, &[]),
+            lin2: Linear::from_raw(Self::HIDDEN, vocab, &[]),
+            vocab_size: vocab,
+            seed: 0,
+            max_chars: 64,
+            accumulation: Accumulation::F32,
+        }
+    }
 
-        // model dims (toy)
-        let embed = 32usize;
-        let hidden = 64usize;
+    /// Untrained model with `dims = (embed, hidden)` layer widths and seeded
+    /// random weights: He for the first layer (ReLU follows), Xavier for the
+    /// output layer. The same seed gives the same weights.
+    pub fn new_random(dims: (usize, usize), seed: u64) -> Self {
+        let (embed, hidden) = dims;
         let vocab = ALPHABET.len();
+        let mut rng = core::make_rng(seed);
+        Self {
+            lin1: init::Strategy::He.layer(embed, hidden, &mut rng),
+            lin2: init::Strategy::Xavier.layer(hidden, vocab, &mut rng),
+            vocab_size: vocab,
+            seed: 0,
+            max_chars: 64,
+            accumulation: Accumulation::F32,
+        }
+    }
 
-        // carve floats into layers: lin1 expects embed->hidden, lin2 hidden->vocab
-        let needed1 = embed * hidden + hidden;
-        let needed2 = hidden * vocab + vocab;
-        let mut offset = 0usize;
-        let slice1 = if floats.len() >= offset + needed1 { &floats[offset..offset+needed1] } else { &[] };
-        offset += needed1;
-        let slice2 = if floats.len() >= offset + needed2 { &floats[offset..offset+needed2] } else { &[] };
+    /// `load`, except that a missing file gives `zeroed` weights (with a warning on
+    /// stderr) so a fresh checkout can still chat. Other errors are returned.
+    #[cfg(feature = "fs")]
+    pub fn load_or_zeroed(path: &str) -> Result<Self> {
+        match Self::load(path) {
+            Err(e) if e.is_not_found() => {
+                eprintln!("⚠️ {} — модель не обучена, использую нулевые веса", e);
+                Ok(Self::zeroed())
+            }
+            other => other,
+        }
+    }
 
-        let lin1 = Linear::from_raw(embed, hidden, slice1);
-        let lin2 = Linear::from_raw(hidden, vocab, slice2);
-        Self { lin1, lin2, vocab_size: vocab }
+    /// Write the weights in the layout `load` expects: lin1 weights, lin1 bias,
+    /// lin2 weights, lin2 bias as little-endian f32.
+    #[cfg(feature = "fs")]
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        let bytes = self.to_bytes();
+        if let Some(dir) = std::path::Path::new(path).parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, bytes)
+    }
+
+    /// The weights as `save` writes them (the input of `from_bytes`).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let floats = [&self.lin1.weights, &self.lin1.bias, &self.lin2.weights, &self.lin2.bias];
+        floats.iter().flat_map(|v| v.iter()).flat_map(|f| f.to_le_bytes()).collect()
+    }
+
+    /// Initial input vector for a context: context bytes folded into `lin1.in_dim` slots.
+    pub(crate) fn context_embedding(&self, context: &str) -> Vec<f32> {
+        let embed_dim = self.lin1.in_dim.max(1);
+        let mut emb = vec![0.0f32; embed_dim];
+        for (i, &b) in context.as_bytes().iter().enumerate() {
+            if let Some(e) = emb.get_mut(i % embed_dim) {
+                *e += (b as f32) * 0.01;
+            }
+        }
+        emb
+    }
+
+    /// Decay the input vector and mix in the last generated character.
+    pub(crate) fn advance_embedding(emb: &mut [f32], last: u8) {
+        let last = last as f32;
+        for (i, e) in emb.iter_mut().enumerate() {
+            *e = *e * 0.9 + (last * (i as f32 + 1.0) * 1e-3);
+        }
+    }
+
+    /// Next-character logits over `ALPHABET` for an input vector.
+    pub(crate) fn logits(&self, emb: &[f32]) -> Vec<f32> {
+        let h = self.lin1.forward_with(emb, self.accumulation);
+        // ReLU
+        let h: Vec<f32> = h.into_iter().map(|v| if v>0.0 { v } else { 0.0 }).collect();
+        self.lin2.forward_with(&h, self.accumulation)
+    }
+
+    /// `logits` for several input vectors in one batched forward pass.
+    pub(crate) fn logits_batch(&self, embs: &[Vec<f32>]) -> Vec<Vec<f32>> {
+        let mut h = self.lin1.forward_batch_with(embs, self.accumulation);
+        h.iter_mut().flatten().for_each(|v| *v = v.max(0.0));
+        self.lin2.forward_batch_with(&h, self.accumulation)
+    }
+
+    /// Embedding of `text`: the mean of the hidden states (after ReLU, width
+    /// `lin1.out_dim`) while the characters of `text` are fed in one by one,
+    /// starting from a zero input vector. Empty text gives zeros. The same
+    /// weights always give the same vector, so results can be stored and compared.
+    pub fn embed(&self, text: &str) -> Vec<f32> {
+        let mut emb = vec![0.0f32; self.lin1.in_dim];
+        let mut sum = vec![0.0f32; self.lin1.out_dim];
+        let mut steps = 0usize;
+        for &b in text.as_bytes() {
+            Self::advance_embedding(&mut emb, b);
+            for (s, h) in sum.iter_mut().zip(self.lin1.forward_with(&emb, self.accumulation)) {
+                *s += h.max(0.0);
+            }
+            steps += 1;
+        }
+        if steps > 0 {
+            sum.iter_mut().for_each(|s| *s /= steps as f32);
+        }
+        sum
     }
 
     /// Generate a short response from a context string using a very small autoreg loop.
     /// This is deterministic and not intended to be a real language model.
     pub fn generate(&self, context: &str) -> String {
-        // simple tokenization: split words, but we'll generate characters from alphabet
-        let toks = context.as_bytes();
+        self.generate_scored(context).0
+    }
+
+    /// Like `generate`, also returning a calibrated confidence: one minus the
+    /// mean normalized entropy of the next-character distributions
+    /// (`calibration::from_entropy`; 0 for an untrained model or an empty answer).
+    pub fn generate_scored(&self, context: &str) -> (String, f32) {
+        self.sample_text(context, &mut |_| {})
+    }
+
+    /// `generate_scored` with the time of every step of every token (feature `profile`).
+    #[cfg(feature = "profile")]
+    pub fn generate_profiled(&self, context: &str) -> (String, f32, crate::profile::Profile) {
+        let mut profile = crate::profile::Profile::default();
+        let start = std::time::Instant::now();
+        let mut lap = crate::profile::Lap::start();
+        let (text, confidence) = self.sample_text(context, &mut |step| profile.add(step, lap.next()));
+        profile.tokens = text.len();
+        profile.total = start.elapsed();
+        (text, confidence, profile)
+    }
+
+    /// The sampling loop of `generate_scored`; `done` is called after each step.
+    fn sample_text(&self, context: &str, done: &mut dyn FnMut(Step)) -> (String, f32) {
         // compute a simple seed vector from context bytes: embed size = lin1.in_dim
-        let embed_dim = self.lin1.in_dim;
-        let mut emb = vec![0.0f32; embed_dim];
-        for (i, &b) in toks.iter().enumerate() {
-            emb[i % embed_dim] += (b as f32) * 0.01;
-        }
+        let mut emb = self.context_embedding(context);
+        done(Step::Embed);
 
-        // autoregressive character generation (max 64 chars)
+        // autoregressive character generation (max `max_chars` chars)
         // create a deterministic RNG seeded from context
-        let mut seed: u64 = 0x9e3779b97f4a7c15u64;
-        for &b in toks.iter() {
+        let mut seed: u64 = 0x9e3779b97f4a7c15u64 ^ self.seed;
+        for &b in context.as_bytes() {
             seed = seed.wrapping_mul(31).wrapping_add(b as u64);
         }
         let mut rng = core::make_rng(seed);
 
         let mut out = Vec::new();
-        for _ in 0..64 {
-            let h = self.lin1.forward(&emb);
-            // ReLU
-            let h: Vec<f32> = h.into_iter().map(|v| if v>0.0 { v } else { 0.0 }).collect();
-            let mut logits = self.lin2.forward(&h);
-            // to f32 slice for softmax
+        let mut entropy_sum = 0.0f32;
+        for _ in 0..self.max_chars {
+            let mut logits = self.logits(&emb);
+            done(Step::Forward);
             core::softmax(&mut logits);
+            done(Step::Softmax);
             // sample from distribution using RNG
             let idx = core::sample_index(&logits, &mut rng);
-            out.push(ALPHABET[idx]);
+            let Some(&ch) = ALPHABET.get(idx) else { break };
+            out.push(ch);
+            entropy_sum += calibration::entropy(&logits);
+            done(Step::Sample);
             // update emb with last char to have some state
-            let last = ALPHABET[idx] as f32;
-            for i in 0..embed_dim { emb[i] = emb[i] * 0.9 + (last * (i as f32 + 1.0) * 1e-3); }
+            Self::advance_embedding(&mut emb, ch);
+            done(Step::Embed);
         }
 
-        String::from_utf8_lossy(&out).to_string()
+        let confidence = if out.is_empty() { 0.0 } else { calibration::from_entropy(entropy_sum / out.len() as f32, ALPHABET.len()) };
+        (String::from_utf8_lossy(&out).to_string(), confidence)
+    }
+
+    /// Generate text matching `template`: literals are copied, text slots are
+    /// sampled with the characters the slot forbids (and those outside
+    /// `ALPHABET`) masked out. Confidence is computed over the sampled
+    /// characters as in `generate_scored` (1 if nothing was sampled).
+    pub fn generate_template(&self, context: &str, template: &Template) -> (String, f32) {
+        let mut emb = self.context_embedding(context);
+        let mut seed: u64 = 0x9e3779b97f4a7c15u64 ^ self.seed;
+        for &b in context.as_bytes() {
+            seed = seed.wrapping_mul(31).wrapping_add(b as u64);
+        }
+        let mut rng = core::make_rng(seed);
+
+        let mut out = String::new();
+        let (mut entropy_sum, mut sampled) = (0.0f32, 0usize);
+        for (index, segment) in template.segments.iter().enumerate() {
+            match segment {
+                Segment::Literal(text) => {
+                    out.push_str(text);
+                    for b in text.bytes() {
+                        Self::advance_embedding(&mut emb, b);
+                    }
+                }
+                Segment::Text { max_len, .. } => {
+                    let terminator = template.terminator(index);
+                    for _ in 0..*max_len {
+                        let mut probs = self.logits(&emb);
+                        core::softmax(&mut probs);
+                        for (p, &c) in probs.iter_mut().zip(ALPHABET) {
+                            if !template.allows(index, c as char) {
+                                *p = 0.0;
+                            }
+                        }
+                        let total: f32 = probs.iter().sum();
+                        if total <= 0.0 {
+                            break;
+                        }
+                        probs.iter_mut().for_each(|p| *p /= total);
+                        entropy_sum += calibration::entropy(&probs);
+                        sampled += 1;
+                        let Some(&ch) = ALPHABET.get(core::sample_index(&probs, &mut rng)) else { break };
+                        // the terminator is emitted by the following literal
+                        if Some(ch as char) == terminator {
+                            break;
+                        }
+                        out.push(ch as char);
+                        Self::advance_embedding(&mut emb, ch);
+                    }
+                }
+            }
+        }
+        let confidence = if sampled == 0 { 1.0 } else { calibration::from_entropy(entropy_sum / sampled as f32, ALPHABET.len()) };
+        (out, confidence)
     }
 }
 
@@ -104,29 +331,23 @@ pub struct SimpleModel {
 impl SimpleModel {
     /// Load f32 weights (little-endian) and construct two Linear layers.
     /// Layout expected: w1 (embed*hidden), b1 (hidden), w2 (hidden*vocab), b2 (vocab)
-    pub fn load(path: &str, embed: usize, hidden: usize, vocab: usize) -> Self {
-        let data = crate::loader::load_f32_file(path).expect("cannot read weights");
-    let needed1 = embed * hidden + hidden;
-
-        // guard against too-small data by using as-slice or empty fallback
-        let w1 = if data.len() >= embed * hidden { &data[..embed * hidden] } else { &[] };
-        let b1 = if data.len() >= embed * hidden + hidden { &data[embed * hidden..needed1] } else { &[] };
-        let start_w2 = needed1;
-        let end_w2 = needed1 + hidden * vocab;
-        let w2 = if data.len() >= end_w2 { &data[start_w2..end_w2] } else { &[] };
-    let b2 = if data.len() >= end_w2 + vocab { &data[end_w2..end_w2 + vocab] } else { &[] };
-
-        // assemble raw buffers as weights followed by biases for from_raw helper
-        let mut raw1 = Vec::with_capacity(w1.len() + b1.len());
-        raw1.extend_from_slice(w1);
-        raw1.extend_from_slice(b1);
-        let mut raw2 = Vec::with_capacity(w2.len() + b2.len());
-        raw2.extend_from_slice(w2);
-        raw2.extend_from_slice(b2);
-
-        let l1 = Linear::from_raw(embed, hidden, &raw1);
-        let l2 = Linear::from_raw(hidden, vocab, &raw2);
-        Self { embed, hidden, vocab, layer1: l1, layer2: l2 }
+    #[cfg(feature = "fs")]
+    pub fn load(path: &str, embed: usize, hidden: usize, vocab: usize) -> Result<Self> {
+        let data = loader::load_f32_file(path)?;
+        let needed1 = Linear::param_count(embed, hidden);
+        let needed2 = Linear::param_count(hidden, vocab);
+        if data.len() < needed1 + needed2 {
+            return Err(SharkError::WeightsTooSmall { path: path.into(), expected: needed1 + needed2, found: data.len() });
+        }
+        let (raw1, raw2) = data.split_at(needed1);
+        let l1 = Linear::from_raw(embed, hidden, raw1);
+        let l2 = Linear::from_raw(hidden, vocab, raw2);
+        Ok(Self { embed, hidden, vocab, layer1: l1, layer2: l2 })
+    }
+
+    /// Layer sizes as `(embed, hidden, vocab)`.
+    pub fn dims(&self) -> (usize, usize, usize) {
+        (self.embed, self.hidden, self.vocab)
     }
 
     /// Forward pass: input is expected to be `embed`-long. Applies tanh after first layer
@@ -137,3 +358,54 @@ impl SimpleModel {
         self.layer2.forward(&h)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn load_reports_missing_and_truncated_weights() {
+        let path = std::env::temp_dir().join(format!("shark_model_{}.bin", std::process::id()));
+        let path = path.to_string_lossy().to_string();
+        assert!(Model::load(&path).is_err_and(|e| e.is_not_found()));
+
+        let mut model = Model::zeroed();
+        model.lin2.bias = vec![0.5; model.lin2.bias.len()];
+        let saved = model.save(&path).map_err(|e| SharkError::io(&path, e)).and_then(|_| Model::load(&path));
+        assert_eq!(saved.map(|m| m.lin2.bias).ok(), Some(model.lin2.bias.clone()));
+
+        let _ = std::fs::write(&path, [0u8; 16]);
+        let truncated = Model::load(&path);
+        let _ = std::fs::remove_file(&path);
+        assert!(matches!(truncated, Err(SharkError::WeightsTooSmall { found: 4, .. })));
+    }
+
+    #[test]
+    fn embeddings_are_hidden_state_means() {
+        let mut model = Model::zeroed();
+        model.lin1.bias.iter_mut().enumerate().for_each(|(i, b)| *b = i as f32 - 1.0);
+        let v = model.embed("акула");
+        assert_eq!(v.len(), Model::HIDDEN);
+        assert_eq!(v.get(..3), Some(&[0.0, 0.0, 1.0][..]));
+        assert_eq!(model.embed("акула"), v);
+        assert!(model.embed("").iter().all(|x| *x == 0.0));
+    }
+
+    #[test]
+    fn f64_accumulation_matches_f32_and_survives_cancellation() {
+        let mut model = Model::new_random((Model::EMBED, 2048), 5);
+        let context = model.context_embedding("длинный контекст акулы");
+        let narrow = model.logits(&context);
+        model.accumulation = Accumulation::F64;
+        let wide = model.logits(&context);
+        assert_eq!(wide.len(), narrow.len());
+        assert!(wide.iter().zip(&narrow).all(|(a, b)| (a - b).abs() < 1e-3), "{:?} vs {:?}", wide.get(..4), narrow.get(..4));
+
+        // 1e8 + 1 rounds back to 1e8 in f32, so the thousand ones vanish
+        let layer = Linear::from_raw(1002, 1, &[1.0; 1002]);
+        let input: Vec<f32> = [1e8].into_iter().chain(std::iter::repeat_n(1.0, 1000)).chain([-1e8]).collect();
+        assert_eq!(layer.forward_with(&input, Accumulation::F32), vec![0.0]);
+        assert_eq!(layer.forward_with(&input, Accumulation::F64), vec![1000.0]);
+    }
+}
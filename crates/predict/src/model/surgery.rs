@@ -0,0 +1,184 @@
+#![forbid(unsafe_code)]
+
+//! Weight surgery for evolutionary and fine-tuning experiments.
+//!
+//! `resize_hidden` grows or shrinks the hidden layer, `interpolate` and
+//! `average` blend two checkpoints of the same shape, and `prune` zeroes
+//! small weights. The results are ordinary models: persist them with
+//! `Model::save` and read a resized one back with `Model::load_dims`.
+
+use rand::Rng;
+
+use crate::core;
+use crate::linear::Linear;
+use crate::model::Model;
+
+/// How new hidden units are initialized.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Init {
+    /// all zero: the unit starts inactive and the outputs are unchanged
+    Zero,
+    /// uniform in `-scale..scale` from a seeded RNG
+    Noise {
+        /// half-width of the range
+        scale: f32,
+        /// RNG seed
+        seed: u64,
+    },
+}
+
+/// Counts of `prune`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PruneStats {
+    /// weights set to zero
+    pub pruned: usize,
+    /// weights examined (biases are kept)
+    pub total: usize,
+}
+
+impl PruneStats {
+    /// Share of zeroed weights.
+    pub fn sparsity(&self) -> f64 {
+        if self.total == 0 { 0.0 } else { self.pruned as f64 / self.total as f64 }
+    }
+}
+
+fn with_layers(model: &Model, lin1: Linear, lin2: Linear) -> Model {
+    Model { lin1, lin2, vocab_size: model.vocab_size, seed: model.seed, max_chars: model.max_chars, accumulation: model.accumulation }
+}
+
+/// Importance of each hidden unit: squared norm of its incoming and outgoing weights.
+fn unit_norms(model: &Model) -> Vec<f32> {
+    let (embed, hidden) = (model.lin1.in_dim, model.lin1.out_dim);
+    (0..hidden)
+        .map(|h| {
+            let incoming: f32 = model.lin1.weights.iter().skip(h * embed).take(embed).map(|w| w * w).sum();
+            let outgoing: f32 = model.lin2.weights.iter().skip(h).step_by(hidden.max(1)).map(|w| w * w).sum();
+            incoming + outgoing
+        })
+        .collect()
+}
+
+/// Copy of `model` with `hidden` hidden units. Growing keeps every unit and
+/// initializes the new ones with `init`; shrinking keeps the `hidden` units
+/// with the largest weight norms, in their original order.
+pub fn resize_hidden(model: &Model, hidden: usize, init: Init) -> Model {
+    let (embed, old, vocab) = (model.lin1.in_dim, model.lin1.out_dim, model.lin2.out_dim);
+    let mut kept: Vec<usize> = (0..old).collect();
+    if hidden < old {
+        let norms = unit_norms(model);
+        kept.sort_by(|&a, &b| norms.get(b).unwrap_or(&0.0).total_cmp(norms.get(a).unwrap_or(&0.0)).then(a.cmp(&b)));
+        kept.truncate(hidden);
+        kept.sort_unstable();
+    }
+    let mut rng = core::make_rng(match init {
+        Init::Noise { seed, .. } => seed,
+        Init::Zero => 0,
+    });
+    let mut fresh = || match init {
+        Init::Zero => 0.0,
+        Init::Noise { scale, .. } if scale > 0.0 => rng.gen_range(-scale..scale),
+        Init::Noise { .. } => 0.0,
+    };
+
+    let mut lin1 = Linear::from_raw(embed, hidden, &[]);
+    let mut lin2 = Linear::from_raw(hidden, vocab, &[]);
+    for h in 0..hidden {
+        let source = kept.get(h).copied();
+        for i in 0..embed {
+            let w = match source {
+                Some(s) => model.lin1.weights.get(s * embed + i).copied().unwrap_or(0.0),
+                None => fresh(),
+            };
+            if let Some(slot) = lin1.weights.get_mut(h * embed + i) {
+                *slot = w;
+            }
+        }
+        if let (Some(slot), Some(s)) = (lin1.bias.get_mut(h), source) {
+            *slot = model.lin1.bias.get(s).copied().unwrap_or(0.0);
+        }
+        for o in 0..vocab {
+            let w = match source {
+                Some(s) => model.lin2.weights.get(o * old + s).copied().unwrap_or(0.0),
+                None => fresh(),
+            };
+            if let Some(slot) = lin2.weights.get_mut(o * hidden + h) {
+                *slot = w;
+            }
+        }
+    }
+    lin2.bias.clone_from(&model.lin2.bias);
+    with_layers(model, lin1, lin2)
+}
+
+fn blend(a: &[f32], b: &[f32], t: f32) -> Vec<f32> {
+    a.iter().zip(b).map(|(x, y)| x + (y - x) * t).collect()
+}
+
+/// `a + (b - a) * t` for every parameter (0 gives `a`, 1 gives `b`); `None`
+/// when the layer shapes differ. Seed and length come from `a`.
+pub fn interpolate(a: &Model, b: &Model, t: f32) -> Option<Model> {
+    let same = |x: &Linear, y: &Linear| x.in_dim == y.in_dim && x.out_dim == y.out_dim;
+    if !same(&a.lin1, &b.lin1) || !same(&a.lin2, &b.lin2) {
+        return None;
+    }
+    let layer = |x: &Linear, y: &Linear| Linear { in_dim: x.in_dim, out_dim: x.out_dim, weights: blend(&x.weights, &y.weights, t), bias: blend(&x.bias, &y.bias, t) };
+    Some(with_layers(a, layer(&a.lin1, &b.lin1), layer(&a.lin2, &b.lin2)))
+}
+
+/// Mean of two checkpoints of the same shape.
+pub fn average(a: &Model, b: &Model) -> Option<Model> {
+    interpolate(a, b, 0.5)
+}
+
+/// Zero every weight with magnitude below `threshold` (biases are kept).
+pub fn prune(model: &mut Model, threshold: f32) -> PruneStats {
+    let mut stats = PruneStats::default();
+    for w in model.lin1.weights.iter_mut().chain(model.lin2.weights.iter_mut()) {
+        stats.total += 1;
+        if w.abs() < threshold && *w != 0.0 {
+            *w = 0.0;
+            stats.pruned += 1;
+        }
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resized_merged_and_pruned_models_keep_their_outputs() {
+        let mut model = Model::zeroed();
+        model.lin1.weights.iter_mut().enumerate().for_each(|(i, w)| *w = ((i % 13) as f32 - 6.0) * 0.01);
+        model.lin2.weights.iter_mut().enumerate().for_each(|(i, w)| *w = ((i % 7) as f32 - 3.0) * 0.02);
+        let context = model.context_embedding("акула");
+
+        let grown = resize_hidden(&model, Model::HIDDEN + 8, Init::Zero);
+        assert_eq!((grown.lin1.out_dim, grown.lin2.in_dim), (Model::HIDDEN + 8, Model::HIDDEN + 8));
+        assert_eq!(grown.logits(&context), model.logits(&context));
+        let noisy = resize_hidden(&model, Model::HIDDEN + 8, Init::Noise { scale: 0.1, seed: 1 });
+        assert!(noisy.lin1.weights.iter().skip(Model::HIDDEN * Model::EMBED).any(|w| *w != 0.0));
+        let back = resize_hidden(&grown, Model::HIDDEN, Init::Zero);
+        assert_eq!(back.logits(&context), model.logits(&context));
+        #[cfg(feature = "fs")]
+        {
+            let path = std::env::temp_dir().join(format!("shark_surgery_{}.bin", std::process::id()));
+            let path = path.to_string_lossy();
+            assert!(noisy.save(&path).is_ok());
+            let loaded = Model::load_dims(&path, Model::EMBED, Model::HIDDEN + 8);
+            let _ = std::fs::remove_file(path.as_ref());
+            assert!(loaded.is_ok_and(|m| m.lin1.weights == noisy.lin1.weights && m.lin2.weights == noisy.lin2.weights));
+        }
+
+        let zero = Model::zeroed();
+        let half = average(&model, &zero);
+        assert!(half.is_some_and(|h| h.lin1.weights.iter().zip(&model.lin1.weights).all(|(a, b)| *a == b * 0.5)));
+        assert!(average(&model, &grown).is_none());
+
+        let stats = prune(&mut model, 0.015);
+        assert!(stats.pruned > 0 && stats.total == model.lin1.weights.len() + model.lin2.weights.len());
+        assert!(model.lin1.weights.iter().chain(&model.lin2.weights).all(|w| *w == 0.0 || w.abs() >= 0.015));
+    }
+}
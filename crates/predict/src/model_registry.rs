@@ -0,0 +1,220 @@
+#![forbid(unsafe_code)]
+
+//! Named generators for the `model` stage.
+//!
+//! Besides the resident model and n-gram fallback of `[model]`, an `AI` can
+//! hold further generators registered by name (`[[models]]` in `shark.toml`):
+//! character models (`kind = "char"`, a `Model::save` weights file) and n-gram
+//! models (`kind = "ngram"`, `NgramModel::save`). `char` and `ngram` name the
+//! `[model]` files. A generator is read from disk the first time it is
+//! selected (`AI::use_model`) and kept loaded; `default` goes back to the
+//! resident pair (the n-gram model when trained, else the char model).
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::error::{Result, SharkError};
+use crate::linear::Accumulation;
+use crate::model::Model;
+use crate::ngram::NgramModel;
+
+/// Name that selects the resident model and n-gram fallback.
+pub const DEFAULT: &str = "default";
+
+/// File format of a registered generator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelKind {
+    /// character model weights (`Model`)
+    Char,
+    /// n-gram model (`NgramModel`)
+    Ngram,
+}
+
+impl ModelKind {
+    /// Lowercase name, as in `shark.toml`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ModelKind::Char => "char",
+            ModelKind::Ngram => "ngram",
+        }
+    }
+}
+
+/// A generator registered by name (`[[models]]`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelSpec {
+    /// name used by `/model use` and the server's `model` field
+    pub name: String,
+    /// file format
+    pub kind: ModelKind,
+    /// weights file
+    pub path: String,
+    /// precision of the matmul sums of a char model (`f32` or `f64`)
+    #[serde(default)]
+    pub accumulation: Accumulation,
+}
+
+/// A loaded generator.
+pub enum Generator {
+    /// character model
+    Char(Model),
+    /// n-gram model
+    Ngram(NgramModel),
+}
+
+/// A generator as `AI::generate` uses it: the resident one or a registered one.
+#[derive(Clone, Copy)]
+pub enum GeneratorRef<'a> {
+    /// character model
+    Char(&'a Model),
+    /// n-gram model
+    Ngram(&'a NgramModel),
+}
+
+impl Generator {
+    /// Read the file of `spec`; a missing char weights file gives an untrained model.
+    pub fn load(spec: &ModelSpec) -> Result<Self> {
+        match spec.kind {
+            ModelKind::Char => {
+                let mut model = Model::load_or_zeroed(&spec.path)?;
+                model.accumulation = spec.accumulation;
+                Ok(Generator::Char(model))
+            }
+            ModelKind::Ngram => NgramModel::load(Path::new(&spec.path)).map(Generator::Ngram).map_err(|e| SharkError::io(&spec.path, e)),
+        }
+    }
+
+    /// Borrowed form.
+    pub fn as_ref(&self) -> GeneratorRef<'_> {
+        match self {
+            Generator::Char(model) => GeneratorRef::Char(model),
+            Generator::Ngram(ngram) => GeneratorRef::Ngram(ngram),
+        }
+    }
+}
+
+/// Registered generators, the loaded ones and the current selection.
+#[derive(Default)]
+pub struct ModelRegistry {
+    specs: Vec<ModelSpec>,
+    loaded: HashMap<String, Generator>,
+    active: Option<String>,
+}
+
+impl ModelRegistry {
+    /// Empty registry (only `default`).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `char` and `ngram` for the `[model]` files, then `[[models]]`.
+    pub fn from_config(config: &Config) -> Self {
+        let mut registry = Self::new();
+        registry.register(ModelSpec { name: "char".to_string(), kind: ModelKind::Char, path: config.model.path.clone(), accumulation: config.model.accumulation });
+        registry.register(ModelSpec { name: "ngram".to_string(), kind: ModelKind::Ngram, path: config.model.ngram_path.clone(), accumulation: Accumulation::F32 });
+        for spec in &config.models {
+            registry.register(spec.clone());
+        }
+        registry
+    }
+
+    /// Register (or replace) a generator; it is loaded when first selected.
+    pub fn register(&mut self, spec: ModelSpec) {
+        self.loaded.remove(&spec.name);
+        match self.specs.iter_mut().find(|s| s.name == spec.name) {
+            Some(existing) => *existing = spec,
+            None => self.specs.push(spec),
+        }
+    }
+
+    /// Registered generators in registration order.
+    pub fn specs(&self) -> &[ModelSpec] {
+        &self.specs
+    }
+
+    /// Whether `name` has been read from disk.
+    pub fn is_loaded(&self, name: &str) -> bool {
+        self.loaded.contains_key(name)
+    }
+
+    /// Selected generator name; `None` for `default`.
+    pub fn active(&self) -> Option<&str> {
+        self.active.as_deref()
+    }
+
+    /// Register an already loaded generator.
+    pub fn insert(&mut self, name: &str, generator: Generator) {
+        let kind = match generator {
+            Generator::Char(_) => ModelKind::Char,
+            Generator::Ngram(_) => ModelKind::Ngram,
+        };
+        self.register(ModelSpec { name: name.to_string(), kind, path: String::new(), accumulation: Accumulation::F32 });
+        self.loaded.insert(name.to_string(), generator);
+    }
+
+    /// Load `name` unless it is loaded already.
+    pub fn load(&mut self, name: &str) -> Result<&mut Generator> {
+        if !self.loaded.contains_key(name) {
+            let spec = self.specs.iter().find(|s| s.name == name).ok_or_else(|| SharkError::UnknownModel(name.to_string()))?;
+            let generator = Generator::load(spec)?;
+            self.loaded.insert(name.to_string(), generator);
+        }
+        self.loaded.get_mut(name).ok_or_else(|| SharkError::UnknownModel(name.to_string()))
+    }
+
+    /// A loaded generator by name.
+    pub fn get(&self, name: &str) -> Option<GeneratorRef<'_>> {
+        self.loaded.get(name).map(Generator::as_ref)
+    }
+
+    /// Select `name` (`default` or `None` for the resident pair), loading it
+    /// on first use. Unknown names and unreadable files leave the selection as it was.
+    pub fn select(&mut self, name: Option<&str>) -> Result<Option<&mut Generator>> {
+        let Some(name) = name.filter(|n| *n != DEFAULT) else {
+            self.active = None;
+            return Ok(None);
+        };
+        self.load(name)?;
+        self.active = Some(name.to_string());
+        Ok(self.loaded.get_mut(name))
+    }
+
+    /// The selected generator; `None` for `default`.
+    pub fn generator(&self) -> Option<GeneratorRef<'_>> {
+        self.active.as_ref().and_then(|name| self.loaded.get(name)).map(Generator::as_ref)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::Memory;
+    use crate::ngram::Smoothing;
+    use crate::AI;
+
+    #[test]
+    fn models_are_selected_by_name_and_loaded_lazily() {
+        let ai = AI::builder().model(Model::zeroed()).memory(Memory::in_memory()).no_ngram().knowledge(HashMap::new()).build();
+        assert_eq!(ai.as_ref().err().map(ToString::to_string), None);
+        let Ok(mut ai) = ai else { return };
+        let mut ngram = NgramModel::new(2, Smoothing::AddK(1.0));
+        ngram.train("акула акула акула");
+        ai.models.insert("tiny", Generator::Ngram(ngram));
+        ai.models.register(ModelSpec { name: "missing".to_string(), kind: ModelKind::Ngram, path: "/nonexistent/ngram.bin".to_string(), accumulation: Accumulation::F32 });
+
+        assert!(matches!(ai.generator(), GeneratorRef::Char(_)));
+        assert!(ai.use_model("tiny").is_ok());
+        assert!(matches!(ai.generator(), GeneratorRef::Ngram(_)));
+        assert!(matches!(ai.use_model("nope"), Err(SharkError::UnknownModel(_))));
+        assert!(ai.use_model("missing").is_err() && !ai.models.is_loaded("missing"));
+        assert_eq!(ai.models.active(), Some("tiny"));
+        assert!(ai.use_model(DEFAULT).is_ok());
+        assert!(matches!(ai.generator(), GeneratorRef::Char(_)) && ai.models.active().is_none());
+        assert_eq!(ai.models.specs().first().map(|s| s.name.as_str()), Some("char"));
+    }
+}
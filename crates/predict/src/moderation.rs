@@ -0,0 +1,211 @@
+#![forbid(unsafe_code)]
+
+//! Safety filters for prompts and answers, for deployments where the server
+//! is shared (`[moderation]` in `shark.toml`, off by default).
+//!
+//! A `Moderator` holds `Filter`s; the built-in `DenyList` blocks keywords
+//! (whole words or phrases, case-insensitive) and regular expressions.
+//! `Pipeline::run` asks the AI's moderator twice: before any stage sees the
+//! prompt and before the answer is stored in the dialog memory. A denied
+//! prompt or answer is replaced by the refusal text (stage `moderation`) and
+//! nothing is remembered. Other filters (a classifier, an external service)
+//! are added with `Moderator::push`.
+
+use std::time::Instant;
+
+use regex::Regex;
+
+use crate::config::ModerationConfig;
+use crate::error::{Result, SharkError};
+use crate::response::{ChatResponse, Source};
+
+/// Stage name of refusals.
+pub const STAGE: &str = "moderation";
+
+/// Which text is checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// the user's prompt, before the pipeline
+    Input,
+    /// the answer, before it is shown and stored
+    Output,
+}
+
+/// Outcome of a check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModerationVerdict {
+    /// the text may pass
+    Allow,
+    /// the text is blocked
+    Deny {
+        /// checked text
+        direction: Direction,
+        /// filter that blocked it
+        filter: &'static str,
+        /// matched keyword or pattern
+        rule: String,
+    },
+}
+
+impl ModerationVerdict {
+    /// Whether the text may pass.
+    pub fn is_allowed(&self) -> bool {
+        matches!(self, ModerationVerdict::Allow)
+    }
+}
+
+/// A check applied to prompts and answers.
+pub trait Filter: Send + Sync {
+    /// Name reported in verdicts.
+    fn name(&self) -> &'static str;
+    /// Judge `text`.
+    fn check(&self, text: &str, direction: Direction) -> ModerationVerdict;
+}
+
+/// Keywords and regular expressions that block a text.
+#[derive(Debug, Clone, Default)]
+pub struct DenyList {
+    words: Vec<String>,
+    patterns: Vec<Regex>,
+    input: bool,
+    output: bool,
+}
+
+/// Lowercase words of `text`, space-separated and padded, for whole-word search.
+fn words(text: &str) -> String {
+    let words: Vec<String> = text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).map(str::to_lowercase).collect();
+    format!(" {} ", words.join(" "))
+}
+
+impl DenyList {
+    /// Deny list for both directions; an invalid pattern is an error.
+    pub fn new<S: AsRef<str>>(words_: &[S], patterns: &[S]) -> Result<Self> {
+        let patterns = patterns
+            .iter()
+            .map(|p| Regex::new(p.as_ref()).map_err(|e| SharkError::InvalidPattern { pattern: p.as_ref().to_string(), reason: e.to_string() }))
+            .collect::<Result<Vec<_>>>()?;
+        let words_ = words_.iter().map(|w| words(w.as_ref())).filter(|w| !w.trim().is_empty()).collect();
+        Ok(Self { words: words_, patterns, input: true, output: true })
+    }
+
+    /// Check only the given directions.
+    pub fn directions(mut self, input: bool, output: bool) -> Self {
+        self.input = input;
+        self.output = output;
+        self
+    }
+}
+
+impl Filter for DenyList {
+    fn name(&self) -> &'static str {
+        "deny_list"
+    }
+
+    fn check(&self, text: &str, direction: Direction) -> ModerationVerdict {
+        let applies = match direction {
+            Direction::Input => self.input,
+            Direction::Output => self.output,
+        };
+        if !applies {
+            return ModerationVerdict::Allow;
+        }
+        let normalized = words(text);
+        let rule = self
+            .words
+            .iter()
+            .find(|w| normalized.contains(w.as_str()))
+            .map(|w| w.trim().to_string())
+            .or_else(|| self.patterns.iter().find(|p| p.is_match(text)).map(|p| p.as_str().to_string()));
+        match rule {
+            Some(rule) => ModerationVerdict::Deny { direction, filter: self.name(), rule },
+            None => ModerationVerdict::Allow,
+        }
+    }
+}
+
+/// Filters applied by `Pipeline::run`, with the text shown instead of a blocked answer.
+pub struct Moderator {
+    filters: Vec<Box<dyn Filter>>,
+    refusal: String,
+}
+
+impl Default for Moderator {
+    fn default() -> Self {
+        Self { filters: Vec::new(), refusal: ModerationConfig::default().refusal }
+    }
+}
+
+impl Moderator {
+    /// Moderator without filters (everything passes).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The `DenyList` of `config`, or no filters when moderation is disabled.
+    pub fn from_config(config: &ModerationConfig) -> Result<Self> {
+        let mut moderator = Self { filters: Vec::new(), refusal: config.refusal.clone() };
+        if config.enabled {
+            moderator.push(DenyList::new(&config.deny_words, &config.deny_patterns)?.directions(config.input, config.output));
+        }
+        Ok(moderator)
+    }
+
+    /// Add a filter.
+    pub fn push(&mut self, filter: impl Filter + 'static) {
+        self.filters.push(Box::new(filter));
+    }
+
+    /// Whether there are no filters.
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// The first denial among the filters, else `Allow`.
+    pub fn check(&self, text: &str, direction: Direction) -> ModerationVerdict {
+        self.filters.iter().map(|f| f.check(text, direction)).find(|v| !v.is_allowed()).unwrap_or(ModerationVerdict::Allow)
+    }
+
+    /// The refusal answer for a denial, timed from `started`.
+    pub fn refusal(&self, verdict: &ModerationVerdict, started: Instant) -> ChatResponse {
+        let mut response = ChatResponse::new(self.refusal.clone(), Source::Model, 1.0, started);
+        if let ModerationVerdict::Deny { direction, filter, rule } = verdict {
+            let what = if *direction == Direction::Input { "запрос" } else { "ответ" };
+            response.reasoning.push(format!("{} заблокирован фильтром {}: «{}»", what, filter, rule));
+        }
+        ChatResponse { stage: STAGE, ..response }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::Memory;
+    use crate::model::Model;
+    use crate::pipeline::Pipeline;
+    use crate::AI;
+
+    #[test]
+    fn denied_prompts_and_answers_are_refused_and_not_remembered() {
+        let config = ModerationConfig { enabled: true, deny_words: vec!["секрет".to_string()], deny_patterns: vec![r"\d{4}-\d{4}".to_string()], ..ModerationConfig::default() };
+        let moderator = Moderator::from_config(&config);
+        let denied = ModerationVerdict::Deny { direction: Direction::Input, filter: "deny_list", rule: "секрет".to_string() };
+        assert_eq!(moderator.as_ref().map(|m| m.check("Расскажи СЕКРЕТ!", Direction::Input)).map_err(|e| e.to_string()), Ok(denied));
+        let Ok(moderator) = moderator else { return };
+        assert!(moderator.check("секретарь пришёл", Direction::Input).is_allowed());
+        assert!(!moderator.check("карта 1234-5678", Direction::Output).is_allowed());
+        assert!(matches!(DenyList::new(&["x"], &["("]), Err(SharkError::InvalidPattern { .. })));
+
+        let knowledge = [("пароль".to_string(), "1111-2222".to_string()), ("акула".to_string(), "рыба".to_string())].into();
+        let ai = AI::builder().model(Model::zeroed()).memory(Memory::in_memory()).no_ngram().knowledge(knowledge).moderator(moderator).build();
+        assert_eq!(ai.as_ref().err().map(ToString::to_string), None);
+        let Ok(mut ai) = ai else { return };
+        let pipeline = Pipeline::standard();
+        for prompt in ["какой секрет?", "пароль"] {
+            let response = pipeline.run(&mut ai, prompt);
+            assert_eq!((response.stage, response.text.as_str()), (STAGE, config.refusal.as_str()));
+        }
+        assert!(ai.memory.dialogs().is_empty());
+        assert_ne!(pipeline.run(&mut ai, "акула").stage, STAGE);
+        assert_eq!(ai.memory.dialogs().len(), 1);
+    }
+}
@@ -0,0 +1,242 @@
+#![forbid(unsafe_code)]
+
+//! Character-level n-gram language model.
+//!
+//! A small, auditable baseline generator: counts of the next character after
+//! every context of up to `order - 1` characters, smoothed either with add-k
+//! or with interpolated Kneser-Ney (absolute discounting on the raw counts,
+//! continuation counts at the unigram level). Each training line is padded
+//! with begin-of-line marks and closed with an end-of-line mark, so sampling
+//! starts from the beginning of a line and stops at the end mark.
+
+use std::collections::{BTreeSet, HashMap};
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core;
+
+/// Padding before the first character of a line.
+const BOS: char = '\u{2}';
+/// End-of-line mark.
+const EOS: char = '\u{3}';
+
+/// Where `AI` looks for a trained n-gram model to use as its fallback generator.
+pub const NGRAM_PATH: &str = "weights/ngram.bin";
+
+/// Probability smoothing.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Smoothing {
+    /// Add `k` to every count of the longest seen context.
+    AddK(f64),
+    /// Interpolated Kneser-Ney with absolute discount `d` (0 < d < 1).
+    KneserNey(f64),
+}
+
+/// Trainable character n-gram model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NgramModel {
+    order: usize,
+    smoothing: Smoothing,
+    /// known characters (plus the end mark), sorted for deterministic sampling
+    vocab: Vec<char>,
+    /// `counts[k]`: context of `k` chars → next char → count
+    counts: Vec<HashMap<String, HashMap<char, u32>>>,
+    /// number of distinct one-character contexts each character follows (KN unigram level)
+    continuation: HashMap<char, u32>,
+}
+
+impl NgramModel {
+    /// Empty model looking at up to `order - 1` previous characters (order ≥ 1).
+    pub fn new(order: usize, smoothing: Smoothing) -> Self {
+        let order = order.max(1);
+        Self { order, smoothing, vocab: vec![EOS], counts: vec![HashMap::new(); order], continuation: HashMap::new() }
+    }
+
+    /// N-gram order.
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// Number of distinct symbols, including the end-of-line mark.
+    pub fn vocab_size(&self) -> usize {
+        self.vocab.len()
+    }
+
+    /// Position of `c` in the sorted vocabulary (the token index `generate` samples).
+    pub fn symbol_index(&self, c: char) -> Option<usize> {
+        self.vocab.binary_search(&c).ok()
+    }
+
+    /// Count every line of `text`.
+    pub fn train(&mut self, text: &str) {
+        let mut vocab: BTreeSet<char> = self.vocab.iter().copied().collect();
+        for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let padded: Vec<char> = std::iter::repeat_n(BOS, self.order - 1).chain(line.chars()).chain([EOS]).collect();
+            for (i, &next) in padded.iter().enumerate().skip(self.order - 1) {
+                vocab.insert(next);
+                for (k, table) in self.counts.iter_mut().enumerate() {
+                    let context: String = padded.get(i - k..i).unwrap_or_default().iter().collect();
+                    *table.entry(context).or_default().entry(next).or_insert(0) += 1;
+                }
+            }
+        }
+        // continuation counts: in how many one-character contexts each character was seen
+        let level = if self.order > 1 { 1 } else { 0 };
+        self.continuation.clear();
+        for next in self.counts.get(level).into_iter().flat_map(|t| t.values()).flat_map(|m| m.keys()) {
+            *self.continuation.entry(*next).or_insert(0) += 1;
+        }
+        self.vocab = vocab.into_iter().collect();
+    }
+
+    /// The last `order - 1` characters of `context`, left-padded with begin marks.
+    fn history(&self, context: &str) -> Vec<char> {
+        let tail: Vec<char> = context.chars().rev().take(self.order - 1).collect();
+        std::iter::repeat_n(BOS, self.order - 1 - tail.len()).chain(tail.into_iter().rev()).collect()
+    }
+
+    fn table(&self, history: &[char], k: usize) -> Option<&HashMap<char, u32>> {
+        let context: String = history.get(history.len().saturating_sub(k)..)?.iter().collect();
+        self.counts.get(k)?.get(&context)
+    }
+
+    fn kn(&self, history: &[char], k: usize, next: char, d: f64) -> f64 {
+        if k == 0 {
+            let total: u32 = self.continuation.values().sum();
+            let uniform = 1.0 / self.vocab.len().max(1) as f64;
+            return match total {
+                0 => uniform,
+                // keep a little mass for characters never seen after anything (e.g. the first one)
+                t => 0.9 * f64::from(self.continuation.get(&next).copied().unwrap_or(0)) / f64::from(t) + 0.1 * uniform,
+            };
+        }
+        let lower = self.kn(history, k - 1, next, d);
+        let Some(table) = self.table(history, k) else { return lower };
+        let total: u32 = table.values().sum();
+        if total == 0 {
+            return lower;
+        }
+        let count = f64::from(table.get(&next).copied().unwrap_or(0));
+        let total = f64::from(total);
+        (count - d).max(0.0) / total + d * table.len() as f64 / total * lower
+    }
+
+    /// P(next | context), using the last `order - 1` characters of `context`.
+    pub fn prob(&self, context: &str, next: char) -> f64 {
+        let history = self.history(context);
+        match self.smoothing {
+            Smoothing::KneserNey(d) => self.kn(&history, self.order - 1, next, d.clamp(0.0, 1.0)),
+            Smoothing::AddK(k) => {
+                let v = self.vocab.len().max(1) as f64;
+                let table = (0..self.order).rev().find_map(|n| self.table(&history, n).filter(|t| !t.is_empty()));
+                let (count, total) = table.map_or((0, 0), |t| (t.get(&next).copied().unwrap_or(0), t.values().sum()));
+                let k = k.max(1e-9);
+                (f64::from(count) + k) / (f64::from(total) + k * v)
+            }
+        }
+    }
+
+    /// Distribution over the vocabulary after `context`.
+    pub fn distribution(&self, context: &str) -> Vec<(char, f64)> {
+        self.vocab.iter().map(|&c| (c, self.prob(context, c))).collect()
+    }
+
+    /// Sample a line of at most `max_len` characters; `seed` makes it deterministic.
+    pub fn generate(&self, max_len: usize, seed: u64) -> String {
+        use rand::Rng;
+        let mut rng = core::make_rng(seed);
+        let mut out = String::new();
+        for _ in 0..max_len {
+            let dist = self.distribution(&out);
+            let total: f64 = dist.iter().map(|(_, p)| p).sum();
+            let mut r = rng.gen::<f64>() * total;
+            let mut next = EOS;
+            for (c, p) in dist {
+                next = c;
+                if r < p {
+                    break;
+                }
+                r -= p;
+            }
+            if next == EOS {
+                break;
+            }
+            out.push(next);
+        }
+        out
+    }
+
+    /// Per-character perplexity of `text` (each line scored like a training line).
+    pub fn perplexity(&self, text: &str) -> f64 {
+        let mut log_sum = 0.0;
+        let mut n = 0usize;
+        for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let mut context = String::new();
+            for c in line.chars().chain([EOS]) {
+                log_sum -= self.prob(&context, c).max(1e-12).ln();
+                n += 1;
+                context.push(c);
+            }
+        }
+        if n == 0 { 1.0 } else { (log_sum / n as f64).exp() }
+    }
+
+    /// Save the model (bincode).
+    #[cfg(feature = "fs")]
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let bytes = bincode::serialize(self).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        std::fs::write(path, bytes)
+    }
+
+    /// Load a model written by `save`.
+    #[cfg(feature = "fs")]
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        bincode::deserialize(&bytes).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CORPUS: &str = "привет мир\nпривет друг\nмир дружба\nhello world\n";
+
+    #[test]
+    fn distributions_sum_to_one() {
+        for smoothing in [Smoothing::AddK(0.1), Smoothing::KneserNey(0.75)] {
+            let mut m = NgramModel::new(3, smoothing);
+            m.train(CORPUS);
+            for context in ["", "пр", "zz", "мир "] {
+                let total: f64 = m.distribution(context).iter().map(|(_, p)| p).sum();
+                assert!((total - 1.0).abs() < 1e-9, "{:?} after {:?}: {}", smoothing, context, total);
+            }
+        }
+    }
+
+    #[test]
+    fn trained_text_is_less_perplexing_than_noise() {
+        let mut m = NgramModel::new(3, Smoothing::KneserNey(0.75));
+        m.train(CORPUS);
+        assert!(m.perplexity("привет мир") < m.perplexity("ьъжщ цйх"));
+        assert!(m.prob("прив", 'е') > 0.5);
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn generation_is_deterministic_and_survives_save_load() {
+        let mut m = NgramModel::new(3, Smoothing::AddK(0.01));
+        m.train(CORPUS);
+        let path = std::env::temp_dir().join(format!("shark_ngram_{}.bin", std::process::id()));
+        let loaded = m.save(&path).and_then(|_| NgramModel::load(&path));
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(loaded.as_ref().ok(), Some(&m));
+        assert_eq!(m.generate(40, 7), m.generate(40, 7));
+        assert!(!m.generate(40, 7).contains(EOS));
+    }
+}
@@ -0,0 +1,183 @@
+//! Genetic search of strategy parameters.
+//!
+//! A `StrategySearch` encodes the parameters of one reference strategy as a
+//! genome (one gene per parameter, each within its range) and scores it by
+//! the per-bar Sharpe ratio of its backtest; `optimize` runs the scientist's
+//! island-model search (`scientist::evolve_islands`) over it. Parameter sets
+//! the strategy rejects (e.g. a fast SMA period not shorter than the slow
+//! one) cost infinity and die out.
+
+use backtest::strategies::{BollingerBreakout, RsiMeanReversion, SmaCrossover};
+use backtest::{run_strategy, EngineConfig, PriceBar, Strategy, StrategyReport};
+use rand::Rng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::scientist::{evolve_islands, IslandConfig, SearchSpace};
+
+/// A tunable parameter: name, range and whether it is an integer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Param {
+    /// name as in the strategy struct
+    pub name: &'static str,
+    /// smallest value
+    pub min: f64,
+    /// largest value
+    pub max: f64,
+    /// rounded to whole numbers (periods)
+    pub integer: bool,
+}
+
+const fn param(name: &'static str, min: f64, max: f64, integer: bool) -> Param {
+    Param { name, min, max, integer }
+}
+
+/// Reference strategy whose parameters are searched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrategyKind {
+    /// `SmaCrossover { fast, slow }`
+    SmaCrossover,
+    /// `RsiMeanReversion { period, oversold, overbought }`
+    RsiMeanReversion,
+    /// `BollingerBreakout { period, k }`
+    BollingerBreakout,
+}
+
+impl StrategyKind {
+    /// Parameters in genome order.
+    pub fn params(self) -> &'static [Param] {
+        const SMA: [Param; 2] = [param("fast", 2.0, 50.0, true), param("slow", 5.0, 200.0, true)];
+        const RSI: [Param; 3] = [param("period", 2.0, 50.0, true), param("oversold", 5.0, 45.0, false), param("overbought", 55.0, 95.0, false)];
+        const BOLLINGER: [Param; 2] = [param("period", 5.0, 100.0, true), param("k", 0.5, 3.5, false)];
+        match self {
+            StrategyKind::SmaCrossover => &SMA,
+            StrategyKind::RsiMeanReversion => &RSI,
+            StrategyKind::BollingerBreakout => &BOLLINGER,
+        }
+    }
+
+    /// The strategy with parameters `genes` (None when a gene is missing).
+    pub fn build(self, genes: &[f64]) -> Option<Box<dyn Strategy>> {
+        let gene = |i: usize| genes.get(i).copied();
+        let period = |i: usize| gene(i).map(|v| v.max(0.0) as usize);
+        Some(match self {
+            StrategyKind::SmaCrossover => Box::new(SmaCrossover { fast: period(0)?, slow: period(1)? }),
+            StrategyKind::RsiMeanReversion => Box::new(RsiMeanReversion { period: period(0)?, oversold: gene(1)?, overbought: gene(2)? }),
+            StrategyKind::BollingerBreakout => Box::new(BollingerBreakout { period: period(0)?, k: gene(1)? }),
+        })
+    }
+}
+
+/// Search space of one strategy's parameters over fixed bars.
+pub struct StrategySearch<'a> {
+    /// strategy to tune
+    pub kind: StrategyKind,
+    /// bars every candidate is backtested on
+    pub bars: &'a [PriceBar],
+    /// costs of the backtests
+    pub cfg: EngineConfig,
+}
+
+impl StrategySearch<'_> {
+    /// Backtest of the parameters `genes`.
+    pub fn backtest(&self, genes: &[f64]) -> Result<StrategyReport, &'static str> {
+        let strategy = self.kind.build(genes).ok_or("wrong number of parameters")?;
+        run_strategy(strategy.as_ref(), self.bars, self.cfg)
+    }
+}
+
+/// `value` clamped to the range of `p`, rounded for integers.
+fn snap(p: &Param, value: f64) -> f64 {
+    let v = value.clamp(p.min, p.max);
+    if p.integer { v.round() } else { v }
+}
+
+impl SearchSpace for StrategySearch<'_> {
+    type Genome = Vec<f64>;
+
+    fn random(&self, rng: &mut ChaCha8Rng) -> Vec<f64> {
+        self.kind.params().iter().map(|p| snap(p, rng.gen_range(p.min..=p.max))).collect()
+    }
+
+    /// Each gene moves with probability 0.3 by up to a tenth of its range
+    /// (at least one step for integers).
+    fn mutate(&self, genome: &Vec<f64>, rng: &mut ChaCha8Rng) -> Vec<f64> {
+        self.kind
+            .params()
+            .iter()
+            .zip(genome)
+            .map(|(p, &v)| {
+                if !rng.gen_bool(0.3) {
+                    return v;
+                }
+                let step = ((p.max - p.min) / 10.0).max(if p.integer { 1.0 } else { 0.0 });
+                snap(p, v + rng.gen_range(-step..=step))
+            })
+            .collect()
+    }
+
+    fn cost(&self, genome: &Vec<f64>) -> f64 {
+        self.backtest(genome).map_or(f64::INFINITY, |r| -r.sharpe())
+    }
+}
+
+/// Best parameters found by `optimize`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Optimized {
+    /// the tuned strategy
+    pub kind: StrategyKind,
+    /// `(name, value)` per parameter
+    pub params: Vec<(&'static str, f64)>,
+    /// per-bar Sharpe ratio of the backtest
+    pub sharpe: f64,
+    /// backtest with these parameters
+    pub report: StrategyReport,
+}
+
+/// Tune `kind` on `bars` by island-model search maximizing the backtest
+/// Sharpe ratio, seeded by `cfg.seed` (`Config::backtest_seed`), so it is
+/// deterministic for a given config. Err when no candidate was a valid
+/// parameter set (e.g. too few bars for every period tried).
+pub fn optimize(kind: StrategyKind, bars: &[PriceBar], cfg: EngineConfig, search: IslandConfig) -> Result<Optimized, &'static str> {
+    let space = StrategySearch { kind, bars, cfg };
+    let (genes, cost) = evolve_islands(&space, cfg.seed, search);
+    if !cost.is_finite() {
+        return Err("no valid parameters found");
+    }
+    let report = space.backtest(&genes)?;
+    Ok(Optimized {
+        kind,
+        params: kind.params().iter().map(|p| p.name).zip(genes).collect(),
+        sharpe: report.sharpe(),
+        report,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backtest::synthetic::{to_bars, OrnsteinUhlenbeck};
+
+    #[test]
+    fn optimizer_finds_valid_parameters_deterministically() {
+        let closes = OrnsteinUhlenbeck { start: 100.0, mean: 100.0, theta: 0.2, sigma: 0.05, dt: 1.0 }.closes(300, 7);
+        let bars = to_bars(&closes);
+        let cfg = EngineConfig { commission_rate: 0.0, slippage: 0.0, seed: 1 };
+        let search = IslandConfig { islands: 3, pop_size: 8, generations: 6, ..IslandConfig::default() };
+
+        let a = optimize(StrategyKind::SmaCrossover, &bars, cfg, search);
+        let b = optimize(StrategyKind::SmaCrossover, &bars, cfg, search);
+        assert_eq!(a.as_ref().map(|best| best.params.len()), Ok(2));
+        assert_eq!(a, b);
+        let Ok(best) = a else { return };
+        let (fast, slow) = (best.params.first().map(|p| p.1), best.params.get(1).map(|p| p.1));
+        assert!(matches!((fast, slow), (Some(f), Some(s)) if f < s), "{:?}", best.params);
+        assert_eq!(best.sharpe, best.report.sharpe());
+
+        let space = StrategySearch { kind: StrategyKind::SmaCrossover, bars: &bars, cfg };
+        assert_eq!(space.cost(&vec![10.0, 30.0]), space.backtest(&[10.0, 30.0]).map_or(f64::NAN, |r| -r.sharpe()));
+        assert!(space.cost(&vec![30.0, 10.0]).is_infinite());
+
+        let few = to_bars(&closes.iter().take(3).copied().collect::<Vec<_>>());
+        assert!(optimize(StrategyKind::BollingerBreakout, &few, cfg, search).is_err());
+    }
+}
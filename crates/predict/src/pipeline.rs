@@ -0,0 +1,521 @@
+#![forbid(unsafe_code)]
+
+//! Answer pipeline: an ordered list of `Handler` stages, the first one that
+//! answers wins. `AI::chat` runs the pipeline built from `[pipeline] stages`
+//! in `shark.toml`, so the chat CLI, GUI, server and evaluator route prompts
+//! the same way.
+//!
+//! Built-in stages, by name: `learn`, `semantic`, `knowledge`, `arithmetic`, `linear`,
+//! `topics`, `tools`, `reasoner`, `concepts`, `model` (see `stage`).
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::cache::{CacheStats, ResponseCache, CACHEABLE_STAGES};
+use crate::calibration;
+use crate::error::SharkError;
+use crate::moderation::Direction;
+use crate::rank::{rank_answers, Candidate, RankedAnswer};
+use crate::reasoner::Reasoner;
+use crate::response::{ChatResponse, Source, DONT_KNOW};
+use crate::teach::{self, Check, Confirmation};
+use crate::tfidf::{Retrieval, TfIdfIndex};
+use crate::AI;
+
+/// Stages used when the configuration does not list any.
+pub const DEFAULT_STAGES: [&str; 9] = ["learn", "reasoner", "knowledge", "arithmetic", "linear", "topics", "tools", "concepts", "model"];
+
+/// Answer produced by one stage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reply {
+    /// answer text
+    pub text: String,
+    /// producer kind reported in `ChatResponse::source`
+    pub source: Source,
+    /// 0..=1, see `ChatResponse::confidence`
+    pub confidence: f32,
+    /// explanation lines (may be empty)
+    pub reasoning: Vec<String>,
+}
+
+impl Reply {
+    /// Reply with a single reasoning line.
+    pub fn new(text: String, source: Source, confidence: f32, note: impl Into<String>) -> Self {
+        Self { text, source, confidence, reasoning: vec![note.into()] }
+    }
+}
+
+/// One stage of the pipeline.
+pub trait Handler: Send + Sync {
+    /// Stage name (as listed in `pipeline.stages`, reported in `ChatResponse::stage`).
+    fn name(&self) -> &'static str;
+
+    /// Answer `input`, or return `None` to pass it to the next stage.
+    fn handle(&self, ai: &mut AI, input: &str) -> Option<Reply>;
+}
+
+/// Ordered list of handlers.
+#[derive(Default)]
+pub struct Pipeline {
+    handlers: Vec<Box<dyn Handler>>,
+    /// ask every stage and rank the replies instead of taking the first one
+    rank: bool,
+    /// answers below this confidence are replaced by `DONT_KNOW`
+    min_confidence: f32,
+    /// answers of deterministic stages by normalized prompt
+    cache: Option<Mutex<ResponseCache>>,
+}
+
+impl Pipeline {
+    /// Pipeline running `handlers` in order.
+    pub fn new(handlers: Vec<Box<dyn Handler>>) -> Self {
+        Self { handlers, rank: false, min_confidence: 0.0, cache: None }
+    }
+
+    /// Whether to rank the replies of all stages (`rank::rank_answers`)
+    /// instead of stopping at the first stage that answers. Ranking runs
+    /// every stage, including their side effects.
+    pub fn ranked(mut self, rank: bool) -> Self {
+        self.rank = rank;
+        self
+    }
+
+    /// Answer `DONT_KNOW` instead of replies whose confidence is below
+    /// `threshold` (0 keeps every reply).
+    pub fn min_confidence(mut self, threshold: f32) -> Self {
+        self.min_confidence = threshold;
+        self
+    }
+
+    /// Keep up to `capacity` answers of `cache::CACHEABLE_STAGES` for `ttl`
+    /// (`None`: until evicted); 0 disables the cache.
+    pub fn cached(mut self, capacity: usize, ttl: Option<Duration>) -> Self {
+        self.cache = (capacity > 0).then(|| Mutex::new(ResponseCache::new(capacity, ttl)));
+        self
+    }
+
+    /// Hit counters of the response cache, when it is enabled.
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().and_then(|c| c.lock().ok()).map(|c| c.stats())
+    }
+
+    /// Forget the cached answers (after the knowledge base changed).
+    pub fn clear_cache(&self) {
+        if let Some(mut cache) = self.cache.as_ref().and_then(|c| c.lock().ok()) {
+            cache.clear();
+        }
+    }
+
+    /// The `DEFAULT_STAGES` pipeline.
+    pub fn standard() -> Self {
+        Self::new(DEFAULT_STAGES.iter().filter_map(|name| stage(name)).collect())
+    }
+
+    /// Build from stage names; unknown names are an error.
+    pub fn from_stages<S: AsRef<str>>(names: &[S]) -> Result<Self, SharkError> {
+        let handlers = names
+            .iter()
+            .map(|name| stage(name.as_ref()).ok_or_else(|| SharkError::UnknownStage(name.as_ref().to_string())))
+            .collect::<Result<_, _>>()?;
+        Ok(Self::new(handlers))
+    }
+
+    /// Append a stage.
+    pub fn push(&mut self, handler: Box<dyn Handler>) {
+        self.handlers.push(handler);
+    }
+
+    /// Insert a stage before the first stage named `before`, or append it
+    /// when there is none.
+    pub fn insert_before(&mut self, before: &str, handler: Box<dyn Handler>) {
+        let at = self.handlers.iter().position(|h| h.name() == before).unwrap_or(self.handlers.len());
+        self.handlers.insert(at, handler);
+    }
+
+    /// Stage names in order.
+    pub fn stages(&self) -> Vec<&'static str> {
+        self.handlers.iter().map(|h| h.name()).collect()
+    }
+
+    /// Route `input` through the stages without touching the dialog memory.
+    /// If no stage answers, the response is empty with `stage == "none"`.
+    /// Ranked pipelines fill `ChatResponse::ranking`. An answer below
+    /// `min_confidence` becomes `DONT_KNOW` with `abstained` set. With a
+    /// cache, a repeated prompt gets the stored answer without running the stages.
+    pub fn answer(&self, ai: &mut AI, input: &str) -> ChatResponse {
+        let started = Instant::now();
+        if let Some(mut response) = self.cache.as_ref().and_then(|c| c.lock().ok()?.get(input)) {
+            response.latency = started.elapsed();
+            response.reasoning.push("ответ из кэша".to_string());
+            return response;
+        }
+        let response = self.checked_answer(ai, input);
+        if CACHEABLE_STAGES.contains(&response.stage) && !response.abstained {
+            if let Some(mut cache) = self.cache.as_ref().and_then(|c| c.lock().ok()) {
+                cache.insert(input, response.clone());
+            }
+        }
+        response
+    }
+
+    fn checked_answer(&self, ai: &mut AI, input: &str) -> ChatResponse {
+        let response = self.best_answer(ai, input);
+        if response.stage == "none" || response.confidence >= self.min_confidence {
+            return response;
+        }
+        let mut reasoning = response.reasoning;
+        reasoning.push(format!("уверенность {:.3} ниже порога {:.3}: ответ скрыт", response.confidence, self.min_confidence));
+        ChatResponse { text: DONT_KNOW.to_string(), tokens: DONT_KNOW.chars().count(), abstained: true, reasoning, ..response }
+    }
+
+    fn best_answer(&self, ai: &mut AI, input: &str) -> ChatResponse {
+        let started = Instant::now();
+        if self.rank {
+            let candidates = self.handlers.iter().filter_map(|h| Some(Candidate::new(h.name(), h.handle(ai, input)?))).collect();
+            if let Some(RankedAnswer { winner, ranking }) = rank_answers(candidates) {
+                let Reply { text, source, confidence, mut reasoning } = winner.reply;
+                reasoning.push(ranking.decision.clone());
+                let response = ChatResponse::new(text, source, confidence, started);
+                return ChatResponse { stage: winner.stage, reasoning, ranking: Some(ranking), ..response };
+            }
+        }
+        for handler in &self.handlers {
+            if let Some(reply) = handler.handle(ai, input) {
+                let response = ChatResponse::new(reply.text, reply.source, reply.confidence, started);
+                return ChatResponse { stage: handler.name(), reasoning: reply.reasoning, ..response };
+            }
+        }
+        ChatResponse { stage: "none", ..ChatResponse::new(String::new(), Source::Model, 0.0, started) }
+    }
+
+    /// `answer`, then store the dialog pair in the AI's memory.
+    /// The AI's moderator checks the prompt first and the answer before it is
+    /// stored; a denied one is replaced by the refusal and not remembered.
+    pub fn run(&self, ai: &mut AI, input: &str) -> ChatResponse {
+        let started = Instant::now();
+        let moderator = std::sync::Arc::clone(&ai.moderator);
+        let verdict = moderator.check(input, Direction::Input);
+        if !verdict.is_allowed() {
+            return moderator.refusal(&verdict, started);
+        }
+        let response = self.answer(ai, input);
+        let verdict = moderator.check(&response.text, Direction::Output);
+        if !verdict.is_allowed() {
+            return moderator.refusal(&verdict, started);
+        }
+        if response.stage != "none" {
+            ai.remember(input, &response.text);
+        }
+        response
+    }
+}
+
+/// Built-in stage by name.
+pub fn stage(name: &str) -> Option<Box<dyn Handler>> {
+    Some(match name {
+        "learn" => Box::new(LearnHandler),
+        "semantic" => Box::new(SemanticHandler),
+        "knowledge" => Box::new(KnowledgeHandler),
+        "arithmetic" => Box::new(ArithmeticHandler),
+        "linear" => Box::new(LinearHandler),
+        "topics" => Box::new(TopicsHandler),
+        "tools" => Box::new(ToolHandler),
+        "reasoner" => Box::new(ReasonerHandler),
+        "concepts" => Box::new(ConceptHandler),
+        "model" => Box::new(ModelHandler),
+        _ => return None,
+    })
+}
+
+/// The knowledge base, reporting (not failing on) load errors.
+fn knowledge(ai: &mut AI) -> Option<&HashMap<String, String>> {
+    match ai.ensure_knowledge() {
+        Ok(knowledge) => Some(knowledge),
+        Err(e) => {
+            eprintln!("⚠️ база знаний недоступна: {}", e);
+            None
+        }
+    }
+}
+
+/// Facts taught in conversation (`teach`): "X — это Y" is checked against the
+/// knowledge base and proposed; "да" on the next turn stores it, "нет" drops it.
+/// Any other reply drops the proposal and goes on to the next stage.
+pub struct LearnHandler;
+
+impl Handler for LearnHandler {
+    fn name(&self) -> &'static str {
+        "learn"
+    }
+
+    fn handle(&self, ai: &mut AI, input: &str) -> Option<Reply> {
+        if let Some(fact) = ai.dialogue.take_pending_fact() {
+            match teach::confirmation(input) {
+                Some(Confirmation::Yes) => {
+                    let provenance = ai.config.data.dir.join("knowledge").join("knowledge_user.csv");
+                    let text = match teach::store(&fact, &ai.config.knowledge_path(), &provenance) {
+                        Ok(()) => {
+                            knowledge(ai)?;
+                            ai.knowledge.insert(fact.term.clone(), fact.definition.clone());
+                            ai.pipeline.clear_cache();
+                            format!("Запомнил: {} — {}.", fact.term, fact.definition)
+                        }
+                        Err(e) => format!("⚠️ не удалось сохранить: {}", e),
+                    };
+                    return Some(Reply::new(text, Source::Knowledge, 1.0, format!("факт от пользователя ({})", teach::PROVENANCE)));
+                }
+                Some(Confirmation::No) => {
+                    return Some(Reply::new("Хорошо, не запоминаю.".to_string(), Source::Knowledge, 1.0, "факт отклонён"));
+                }
+                None => {}
+            }
+        }
+        let fact = teach::parse_statement(input)?;
+        let (text, note) = match teach::check(&fact, knowledge(ai)?) {
+            Check::Known => (format!("Я это уже знаю: {} — {}.", fact.term, fact.definition), "факт уже в базе знаний"),
+            Check::Contradicts(known) => (
+                format!("⚠️ Не запоминаю: в базе знаний {} — {}. Сначала исправьте запись в knowledge.csv.", fact.term, known),
+                "факт противоречит базе знаний",
+            ),
+            Check::New => {
+                let text = format!("Запомнить, что {} — {}? (да/нет)", fact.term, fact.definition);
+                ai.dialogue.propose_fact(fact);
+                (text, "новый факт ждёт подтверждения")
+            }
+        };
+        Some(Reply::new(text, Source::Knowledge, 1.0, note))
+    }
+}
+
+/// Rule-based understanding of greetings, "что такое", "почему" (`interpret_question`).
+pub struct SemanticHandler;
+
+impl Handler for SemanticHandler {
+    fn name(&self) -> &'static str {
+        "semantic"
+    }
+
+    fn handle(&self, ai: &mut AI, input: &str) -> Option<Reply> {
+        let text = crate::interpret_question(input, knowledge(ai)?)?;
+        Some(Reply::new(text, Source::Knowledge, 1.0, "семантический разбор вопроса"))
+    }
+}
+
+/// Exact (case-insensitive) question match in the knowledge base: knowledge.csv
+/// (`Config::knowledge_path`) as loaded by `AI::ensure_knowledge`, or the injected map.
+pub struct KnowledgeHandler;
+
+impl Handler for KnowledgeHandler {
+    fn name(&self) -> &'static str {
+        "knowledge"
+    }
+
+    fn handle(&self, ai: &mut AI, input: &str) -> Option<Reply> {
+        let answer = knowledge(ai)?.get(&input.trim().to_lowercase())?.clone();
+        let path = ai.config.knowledge_path();
+        Some(Reply::new(answer, Source::Knowledge, 1.0, format!("точное совпадение в {}", path.display())))
+    }
+}
+
+/// Arithmetic expression: the `arithmetic` tool of `AI::tools`.
+pub struct ArithmeticHandler;
+
+impl Handler for ArithmeticHandler {
+    fn name(&self) -> &'static str {
+        "arithmetic"
+    }
+
+    fn handle(&self, ai: &mut AI, input: &str) -> Option<Reply> {
+        let call = ai.tools.call("arithmetic", input)?;
+        let reasoning = vec![call.trace(), "арифметическое выражение вычислено".to_string()];
+        Some(Reply { text: call.output, source: Source::Reasoner, confidence: 1.0, reasoning })
+    }
+}
+
+/// Linear equation in `x`: the `linear` tool of `AI::tools`, checked by substitution.
+pub struct LinearHandler;
+
+impl Handler for LinearHandler {
+    fn name(&self) -> &'static str {
+        "linear"
+    }
+
+    fn handle(&self, ai: &mut AI, input: &str) -> Option<Reply> {
+        let call = ai.tools.call("linear", input)?;
+        let equation: String = input.chars().filter(|c| c.is_ascii() && (c.is_ascii_alphanumeric() || "+-*/=()^.".contains(*c))).collect();
+        let verified = call
+            .output
+            .strip_prefix("x = ")
+            .and_then(|x| x.parse::<f64>().ok())
+            .is_some_and(|x| crate::train::verify_linear_solution(&equation, x));
+        let note = if verified { "линейное уравнение решено, проверено подстановкой" } else { "линейное уравнение решено без проверки" };
+        let reasoning = vec![call.trace(), note.to_string()];
+        Some(Reply { text: call.output, source: Source::Reasoner, confidence: calibration::from_verification(verified), reasoning })
+    }
+}
+
+/// Any registered tool, chosen by the prompt's intent (`ToolRegistry::dispatch`).
+pub struct ToolHandler;
+
+impl Handler for ToolHandler {
+    fn name(&self) -> &'static str {
+        "tools"
+    }
+
+    fn handle(&self, ai: &mut AI, input: &str) -> Option<Reply> {
+        let call = ai.tools.dispatch(input)?;
+        let reasoning = vec![call.trace()];
+        Some(Reply { text: call.output, source: Source::Reasoner, confidence: 1.0, reasoning })
+    }
+}
+
+/// "покажи темы": topics of the knowledge base and the dialog memory
+/// (`AI::knowledge_topics`, `AI::memory_topics`), with a few examples each.
+pub struct TopicsHandler;
+
+/// Examples listed per topic.
+const TOPIC_EXAMPLES: usize = 3;
+
+impl Handler for TopicsHandler {
+    fn name(&self) -> &'static str {
+        "topics"
+    }
+
+    fn handle(&self, ai: &mut AI, input: &str) -> Option<Reply> {
+        let lower = input.trim().trim_end_matches(['?', '!', '.']).to_lowercase();
+        if !["покажи темы", "какие темы", "show topics"].contains(&lower.as_str()) {
+            return None;
+        }
+        knowledge(ai)?;
+        let mut text = String::new();
+        for (title, topics) in [("Темы базы знаний", ai.knowledge_topics()), ("Темы диалогов", ai.memory_topics())] {
+            if topics.is_empty() {
+                text.push_str(&format!("{}: пока нет\n", title));
+                continue;
+            }
+            text.push_str(&format!("{}:\n", title));
+            for topic in &topics {
+                let examples: Vec<&str> = topic.members.iter().take(TOPIC_EXAMPLES).map(String::as_str).collect();
+                text.push_str(&format!("• {} ({}): {}\n", topic.words.join(", "), topic.members.len(), examples.join("; ")));
+            }
+        }
+        Some(Reply::new(text.trim_end().to_string(), Source::Knowledge, 1.0, "k-means по словам вопросов и эмбеддингам диалогов"))
+    }
+}
+
+/// Step-by-step Reasoner for prompts asking to simplify, explain or integrate.
+pub struct ReasonerHandler;
+
+impl Handler for ReasonerHandler {
+    fn name(&self) -> &'static str {
+        "reasoner"
+    }
+
+    fn handle(&self, ai: &mut AI, input: &str) -> Option<Reply> {
+        let lower = input.to_lowercase();
+        if !["упрост", "объясн", "рассужд", "интеграл"].iter().any(|k| lower.contains(k)) {
+            return None;
+        }
+        let (text, reasoning) = Reasoner::explain(&ai.config.data.dir, input);
+        Some(Reply { text, source: Source::Reasoner, confidence: 1.0, reasoning: reasoning.lines().map(str::to_string).collect() })
+    }
+}
+
+/// Closest stored concept for questions and instructions (`reason_response`).
+pub struct ConceptHandler;
+
+impl Handler for ConceptHandler {
+    fn name(&self) -> &'static str {
+        "concepts"
+    }
+
+    fn handle(&self, ai: &mut AI, input: &str) -> Option<Reply> {
+        if crate::detect_mode(input) == "statement" {
+            return None;
+        }
+        knowledge(ai)?;
+        let ai = &*ai;
+        let embed = |text: &str| ai.embed(text);
+        let knowledge = &ai.knowledge;
+        let index = (ai.config.pipeline.retrieval == Retrieval::TfIdf).then(|| {
+            let questions: Vec<&str> = knowledge.keys().map(String::as_str).collect();
+            TfIdfIndex::new(&questions)
+        });
+        let signals = crate::rerank::Signals { frequencies: None, embed: Some(&embed), tfidf: index.as_ref() };
+        let text = crate::reasoning::reason_response_with(input, knowledge, &crate::rerank::Reranker::default(), &signals);
+        if text.contains("Не нашел") {
+            return None;
+        }
+        let confidence = concept_confidence(input, knowledge, &signals);
+        Some(Reply::new(text, Source::Knowledge, confidence, "ближайшее понятие в базе знаний"))
+    }
+}
+
+/// 1 for a stored question, else the best surface similarity (trigram or
+/// TF-IDF, `Signals::surface`) between `input` and the stored questions.
+fn concept_confidence(input: &str, knowledge: &HashMap<String, String>, signals: &crate::rerank::Signals) -> f32 {
+    let lower = input.trim().trim_end_matches('?').to_lowercase();
+    let concept = lower.strip_prefix("что такое").map(str::trim).unwrap_or(&lower);
+    if knowledge.contains_key(concept) {
+        return 1.0;
+    }
+    knowledge.keys().map(|q| signals.surface(&lower, q)).fold(0.0, f64::max) as f32
+}
+
+/// Generation by the n-gram fallback if trained, else the toy model (`AI::generate`,
+/// or `AI::generate_structured` in JSON mode).
+pub struct ModelHandler;
+
+impl Handler for ModelHandler {
+    fn name(&self) -> &'static str {
+        "model"
+    }
+
+    fn handle(&self, ai: &mut AI, input: &str) -> Option<Reply> {
+        let (text, confidence) = if ai.structured { ai.generate_structured(input) } else { ai.generate(input) };
+        Some(Reply { text, source: Source::Model, confidence, reasoning: Vec::new() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::Memory;
+    use crate::model::Model;
+
+    #[test]
+    fn first_answering_stage_wins() {
+        let knowledge = HashMap::from([("акула".to_string(), "хрящевая рыба".to_string())]);
+        let ai = AI::builder().model(Model::zeroed()).memory(Memory::in_memory()).knowledge(knowledge).no_ngram().build();
+        assert!(ai.is_ok());
+        let Ok(mut ai) = ai else { return };
+
+        let pipeline = Pipeline::from_stages(&["arithmetic", "linear", "concepts", "model"]);
+        assert!(pipeline.is_ok());
+        let Ok(pipeline) = pipeline else { return };
+        let stages: Vec<&str> = ["сколько будет 2 + 2", "реши 2x + 3 = 7", "что такое акула?", "ммм"]
+            .iter()
+            .map(|q| pipeline.run(&mut ai, q).stage)
+            .collect();
+        assert_eq!(stages, vec!["arithmetic", "linear", "concepts", "model"]);
+        assert_eq!(ai.memory.dialogs().len(), 4);
+
+        assert_eq!(Pipeline::new(Vec::new()).answer(&mut ai, "x").stage, "none");
+        assert!(matches!(Pipeline::from_stages(&["oracle"]), Err(SharkError::UnknownStage(_))));
+
+        let ranked = Pipeline::from_stages(&["model", "concepts", "arithmetic"]).map(|p| p.ranked(true));
+        assert_eq!(ranked.as_ref().err().map(ToString::to_string), None);
+        let Ok(ranked) = ranked else { return };
+        let response = ranked.answer(&mut ai, "сколько будет 2 + 2");
+        assert_eq!((response.stage, response.text.as_str()), ("arithmetic", "4"));
+        assert!(response.ranking.is_some_and(|r| r.scores.len() >= 2));
+
+        let unsure = Pipeline::from_stages(&["linear", "model"]).map(|p| p.min_confidence(0.5));
+        assert_eq!(unsure.as_ref().err().map(ToString::to_string), None);
+        let Ok(unsure) = unsure else { return };
+        let response = unsure.answer(&mut ai, "ммм");
+        assert!(response.abstained && response.text == DONT_KNOW && response.stage == "model");
+        assert_eq!(unsure.answer(&mut ai, "реши 2x + 3 = 7").confidence, 1.0);
+    }
+}
@@ -0,0 +1,295 @@
+#![forbid(unsafe_code)]
+
+//! Plugins: extra chat handlers (pipeline stages), indicators and solvers
+//! (tools) shipped outside this crate.
+//!
+//! Two kinds are enabled from a manifest (`plugins.manifest` in `shark.toml`,
+//! default `plugins/manifest.toml`; a missing file means no plugins):
+//! - compiled plugins: a crate embedding `predict` implements `Plugin` and
+//!   calls `register` at startup, before the `AI` is built; the manifest
+//!   enables them by name;
+//! - command plugins: an executable in any language that reads the prompt on
+//!   stdin and prints the answer (nothing, or a non-zero exit, means "does not
+//!   apply"). It runs as a separate process with a timeout, so a crashing or
+//!   hanging plugin cannot take the chat down.
+//!
+//! ```toml
+//! [[plugin]]
+//! name = "weather"                      # registered with plugins::register
+//!
+//! [[plugin]]
+//! name = "sympy"
+//! command = ["python3", "plugins/sympy_solve.py"]
+//! intents = ["math"]
+//! timeout_ms = 2000
+//! ```
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::{Result, SharkError};
+use crate::intent::Intent;
+use crate::pipeline::{Handler, Pipeline};
+use crate::tools::{Tool, ToolRegistry};
+
+/// Manifest used when `plugins.manifest` is not set.
+pub const DEFAULT_MANIFEST: &str = "plugins/manifest.toml";
+
+/// Timeout of a command plugin when the manifest gives none.
+pub const DEFAULT_TIMEOUT_MS: u64 = 5000;
+
+/// A compiled plugin: contributes tools and pipeline stages.
+pub trait Plugin: Send + Sync {
+    /// Name the manifest enables it by.
+    fn name(&self) -> &'static str;
+
+    /// Tools added to `AI::tools` (indicators, solvers, ...).
+    fn tools(&self) -> Vec<Box<dyn Tool>> {
+        Vec::new()
+    }
+
+    /// Pipeline stages, inserted before the `model` stage.
+    fn stages(&self) -> Vec<Box<dyn Handler>> {
+        Vec::new()
+    }
+}
+
+fn registry() -> &'static Mutex<HashMap<&'static str, Arc<dyn Plugin>>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<&'static str, Arc<dyn Plugin>>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Make a compiled plugin available to manifests (replaces one with the same name).
+pub fn register(plugin: impl Plugin + 'static) {
+    if let Ok(mut plugins) = registry().lock() {
+        plugins.insert(plugin.name(), Arc::new(plugin));
+    }
+}
+
+/// Names of the registered compiled plugins, sorted.
+pub fn registered() -> Vec<&'static str> {
+    let mut names: Vec<&'static str> = registry().lock().map(|p| p.keys().copied().collect()).unwrap_or_default();
+    names.sort_unstable();
+    names
+}
+
+/// `[[plugin]]` entry of a manifest.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginEntry {
+    /// registered plugin to enable, or the tool name of a command plugin
+    pub name: String,
+    /// program and arguments of a command plugin
+    #[serde(default)]
+    pub command: Vec<String>,
+    /// intents a command plugin serves (`question`, `instruction`, `math`, `research`, `small_talk`)
+    #[serde(default)]
+    pub intents: Vec<String>,
+    /// how long a command plugin may run
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    #[serde(default)]
+    plugin: Vec<PluginEntry>,
+}
+
+/// Tools and stages of the plugins a manifest enables.
+#[derive(Default)]
+pub struct Plugins {
+    /// enabled plugin names, in manifest order
+    pub names: Vec<String>,
+    tools: Vec<Box<dyn Tool>>,
+    stages: Vec<Box<dyn Handler>>,
+}
+
+impl Plugins {
+    /// Read a manifest; a missing file gives no plugins. Unknown compiled
+    /// plugins and unknown intents are errors.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(SharkError::io(path, e)),
+        };
+        Self::from_toml(&text, path)
+    }
+
+    /// Parse manifest text; `origin` is used in error messages (relative
+    /// command paths are resolved by the OS from the working directory).
+    pub fn from_toml(text: &str, origin: &Path) -> Result<Self> {
+        let manifest: Manifest =
+            toml::from_str(text).map_err(|e| SharkError::Config { path: origin.to_path_buf(), reason: e.to_string() })?;
+        let mut plugins = Self::default();
+        for entry in manifest.plugin {
+            if entry.command.is_empty() {
+                let plugin = registry().lock().ok().and_then(|p| p.get(entry.name.as_str()).cloned());
+                let plugin = plugin.ok_or_else(|| SharkError::UnknownPlugin(entry.name.clone()))?;
+                plugins.tools.extend(plugin.tools());
+                plugins.stages.extend(plugin.stages());
+            } else {
+                plugins.tools.push(Box::new(CommandTool::from_entry(&entry, origin)?));
+            }
+            plugins.names.push(entry.name);
+        }
+        Ok(plugins)
+    }
+
+    /// Whether the manifest enabled nothing.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Register the tools and insert the stages before `model` (or at the end).
+    pub fn install(self, tools: &mut ToolRegistry, pipeline: &mut Pipeline) {
+        for tool in self.tools {
+            tools.register(tool);
+        }
+        for stage in self.stages {
+            pipeline.insert_before("model", stage);
+        }
+    }
+}
+
+/// Tool backed by an external program (see the module docs).
+pub struct CommandTool {
+    name: &'static str,
+    program: PathBuf,
+    args: Vec<String>,
+    intents: &'static [Intent],
+    timeout: Duration,
+}
+
+impl CommandTool {
+    fn from_entry(entry: &PluginEntry, origin: &Path) -> Result<Self> {
+        let (program, args) = entry.command.split_first().ok_or_else(|| SharkError::UnknownPlugin(entry.name.clone()))?;
+        let intents = entry
+            .intents
+            .iter()
+            .map(|label| {
+                Intent::ALL.iter().copied().find(|i| i.as_str() == label).ok_or_else(|| SharkError::Config {
+                    path: origin.to_path_buf(),
+                    reason: format!("plugin `{}`: unknown intent `{}`", entry.name, label),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        // names and intents live as long as the registry that holds the tool: the whole run
+        Ok(Self {
+            name: Box::leak(entry.name.clone().into_boxed_str()),
+            program: PathBuf::from(program),
+            args: args.to_vec(),
+            intents: Box::leak(intents.into_boxed_slice()),
+            timeout: Duration::from_millis(entry.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS)),
+        })
+    }
+}
+
+impl Tool for CommandTool {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn schema(&self) -> Value {
+        json!({"type": "string", "description": format!("prompt on the stdin of {}", self.program.display())})
+    }
+
+    fn intents(&self) -> &'static [Intent] {
+        self.intents
+    }
+
+    fn run(&self, input: &str) -> Option<String> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = stdin.write_all(input.as_bytes());
+        }
+        let started = Instant::now();
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) if started.elapsed() < self.timeout => std::thread::sleep(Duration::from_millis(10)),
+                _ => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return None;
+                }
+            }
+        };
+        let mut output = String::new();
+        child.stdout.take()?.read_to_string(&mut output).ok()?;
+        let output = output.trim();
+        (status.success() && !output.is_empty()).then(|| output.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Shout;
+
+    impl Tool for Shout {
+        fn name(&self) -> &'static str {
+            "shout"
+        }
+
+        fn schema(&self) -> Value {
+            json!({"type": "string"})
+        }
+
+        fn intents(&self) -> &'static [Intent] {
+            &[]
+        }
+
+        fn run(&self, input: &str) -> Option<String> {
+            input.strip_prefix("крикни ").map(str::to_uppercase)
+        }
+    }
+
+    struct ShoutPlugin;
+
+    impl Plugin for ShoutPlugin {
+        fn name(&self) -> &'static str {
+            "shout"
+        }
+
+        fn tools(&self) -> Vec<Box<dyn Tool>> {
+            vec![Box::new(Shout)]
+        }
+    }
+
+    #[test]
+    fn manifest_enables_registered_and_command_plugins() {
+        register(ShoutPlugin);
+        let manifest = "[[plugin]]\nname = \"shout\"\n\n[[plugin]]\nname = \"echo\"\ncommand = [\"cat\"]\nintents = [\"question\"]\n";
+        let plugins = Plugins::from_toml(manifest, Path::new("manifest.toml"));
+        assert!(plugins.is_ok());
+        let Ok(plugins) = plugins else { return };
+        assert_eq!(plugins.names, vec!["shout", "echo"]);
+
+        let (mut tools, mut pipeline) = (ToolRegistry::new(), Pipeline::default());
+        plugins.install(&mut tools, &mut pipeline);
+        assert_eq!(tools.call("shout", "крикни ура").map(|c| c.output), Some("УРА".to_string()));
+        if cfg!(unix) {
+            assert_eq!(tools.call("echo", "привет\n").map(|c| c.output), Some("привет".to_string()));
+        }
+
+        assert!(matches!(Plugins::from_toml("[[plugin]]\nname = \"nope\"\n", Path::new("m.toml")), Err(SharkError::UnknownPlugin(_))));
+        assert!(Plugins::load(Path::new("/nonexistent/manifest.toml")).is_ok_and(|p| p.is_empty()));
+    }
+}
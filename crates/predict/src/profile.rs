@@ -0,0 +1,114 @@
+#![forbid(unsafe_code)]
+
+//! Per-token timings of char model generation (feature `profile`).
+//!
+//! `Model::generate_profiled` times the four steps of every token: updating
+//! the input vector (embedding), the two-layer forward pass, the softmax and
+//! the sampling. `AI` keeps the profile of the last plain char generation
+//! (`AI::last_profile`); the chat prints it with `--profile`, the GUI shows it
+//! in the Metrics tab and the server returns it in the `X-Shark-Profile`
+//! header. Speculative and n-gram generations are not profiled.
+
+use std::time::{Duration, Instant};
+
+use crate::model::Step;
+
+/// Response header of the server carrying `Profile::header_value`.
+pub const HEADER: &str = "X-Shark-Profile";
+
+/// Time spent in each step of one generation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Profile {
+    /// characters generated
+    pub tokens: usize,
+    /// context embedding and per-token input updates
+    pub embed: Duration,
+    /// `lin1` and `lin2` forward passes
+    pub forward: Duration,
+    /// softmax over the logits
+    pub softmax: Duration,
+    /// sampling the next character
+    pub sample: Duration,
+    /// wall time of the whole generation
+    pub total: Duration,
+}
+
+impl Profile {
+    /// Add `time` to the bucket of `step`.
+    pub(crate) fn add(&mut self, step: Step, time: Duration) {
+        match step {
+            Step::Embed => self.embed += time,
+            Step::Forward => self.forward += time,
+            Step::Softmax => self.softmax += time,
+            Step::Sample => self.sample += time,
+        }
+    }
+
+    /// Generated characters per second of wall time (0 for an instant or empty run).
+    pub fn tokens_per_sec(&self) -> f64 {
+        let secs = self.total.as_secs_f64();
+        if secs > 0.0 { self.tokens as f64 / secs } else { 0.0 }
+    }
+
+    /// `(name, time)` of the four steps.
+    pub fn stages(&self) -> [(&'static str, Duration); 4] {
+        [("embed", self.embed), ("forward", self.forward), ("softmax", self.softmax), ("sample", self.sample)]
+    }
+
+    /// Multi-line breakdown with each step's share of the total.
+    pub fn render(&self) -> String {
+        let total = self.total.as_secs_f64().max(f64::MIN_POSITIVE);
+        let mut out = format!("⏱ {} симв. за {:.2} мс, {:.0} симв./с\n", self.tokens, self.total.as_secs_f64() * 1e3, self.tokens_per_sec());
+        for (name, time) in self.stages() {
+            let per_token = time.as_secs_f64() * 1e6 / self.tokens.max(1) as f64;
+            out.push_str(&format!("  {:<8} {:>8.1} мкс/симв. {:>5.1}%\n", name, per_token, time.as_secs_f64() / total * 100.0));
+        }
+        out
+    }
+
+    /// Compact form for the `X-Shark-Profile` header:
+    /// `tokens=64; tok_per_sec=…; embed_us=…; forward_us=…; softmax_us=…; sample_us=…; total_us=…`.
+    pub fn header_value(&self) -> String {
+        let mut value = format!("tokens={}; tok_per_sec={:.1}", self.tokens, self.tokens_per_sec());
+        for (name, time) in self.stages() {
+            value.push_str(&format!("; {}_us={}", name, time.as_micros()));
+        }
+        value.push_str(&format!("; total_us={}", self.total.as_micros()));
+        value
+    }
+}
+
+/// Stopwatch measuring consecutive steps.
+pub(crate) struct Lap(Instant);
+
+impl Lap {
+    /// Start timing now.
+    pub(crate) fn start() -> Self {
+        Self(Instant::now())
+    }
+
+    /// Time since the previous call (or `start`).
+    pub(crate) fn next(&mut self) -> Duration {
+        let now = Instant::now();
+        now.duration_since(std::mem::replace(&mut self.0, now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Model;
+
+    #[test]
+    fn profiled_generation_matches_and_accounts_for_every_token() {
+        let mut model = Model::zeroed();
+        model.lin2.bias.iter_mut().enumerate().for_each(|(i, b)| *b = (i % 5) as f32);
+        let (text, confidence, profile) = model.generate_profiled("акула");
+        assert_eq!((text.clone(), confidence), model.generate_scored("акула"));
+        assert_eq!(profile.tokens, text.len());
+        let steps: Duration = profile.stages().iter().map(|(_, t)| *t).sum();
+        assert!(steps <= profile.total && profile.tokens_per_sec() > 0.0);
+        assert!(profile.header_value().starts_with(&format!("tokens={}; tok_per_sec=", text.len())));
+        assert_eq!(profile.render().lines().count(), 5);
+    }
+}
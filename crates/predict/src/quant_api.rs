@@ -0,0 +1,217 @@
+//! JSON handlers behind the server's `POST /backtest` and `POST /indicators/{name}`.
+//!
+//! Both take the prices in one of three forms: `bars` (objects with `close`
+//! and optional `ts`, `open`, `high`, `low`, `volume`), `closes` (plain
+//! numbers) or `path` (a price file relative to the server's working
+//! directory, read with `backtest::import::any`). Errors are plain messages,
+//! which the server returns as 400 responses.
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::backtest::strategies::{BollingerBreakout, BuyHold, RsiMeanReversion, SmaCrossover};
+use crate::backtest::{self, EngineConfig, PriceBar, Strategy};
+
+/// One bar of a request; missing prices default to the close, a missing `ts` to the bar's index.
+#[derive(Deserialize)]
+struct BarIn {
+    #[serde(default)]
+    ts: Option<u64>,
+    close: f64,
+    #[serde(default)]
+    open: Option<f64>,
+    #[serde(default)]
+    high: Option<f64>,
+    #[serde(default)]
+    low: Option<f64>,
+    #[serde(default)]
+    volume: f64,
+}
+
+/// Where the prices of a request come from (exactly one of the fields).
+#[derive(Deserialize, Default)]
+struct Prices {
+    #[serde(default)]
+    bars: Option<Vec<BarIn>>,
+    #[serde(default)]
+    closes: Option<Vec<f64>>,
+    #[serde(default)]
+    path: Option<String>,
+}
+
+impl Prices {
+    fn load(self) -> Result<Vec<PriceBar>, String> {
+        let bar = |i: usize, close: f64| PriceBar { ts: i as u64, open: close, high: close, low: close, close, volume: 0.0 };
+        match (self.bars, self.closes, self.path) {
+            (Some(bars), None, None) => Ok(bars
+                .into_iter()
+                .enumerate()
+                .map(|(i, b)| PriceBar {
+                    ts: b.ts.unwrap_or(i as u64),
+                    open: b.open.unwrap_or(b.close),
+                    high: b.high.unwrap_or(b.close),
+                    low: b.low.unwrap_or(b.close),
+                    close: b.close,
+                    volume: b.volume,
+                })
+                .collect()),
+            (None, Some(closes), None) => Ok(closes.into_iter().enumerate().map(|(i, c)| bar(i, c)).collect()),
+            (None, None, Some(path)) => {
+                let relative = std::path::Path::new(&path);
+                if relative.is_absolute() || relative.components().any(|c| matches!(c, std::path::Component::ParentDir)) {
+                    return Err(format!("{}: only paths below the working directory are allowed", path));
+                }
+                let text = std::fs::read_to_string(relative).map_err(|e| format!("{}: {}", path, e))?;
+                backtest::import::any(&text).map_err(|e| format!("{}: {}", path, e))
+            }
+            _ => Err("give exactly one of bars, closes or path".to_string()),
+        }
+    }
+}
+
+fn default_strategy() -> String {
+    "sma-crossover".to_string()
+}
+
+/// Body of `POST /backtest`.
+#[derive(Deserialize)]
+struct BacktestRequest {
+    #[serde(flatten)]
+    prices: Prices,
+    /// `sma-crossover`, `rsi-mean-reversion`, `bollinger-breakout` or `buy-hold`
+    #[serde(default = "default_strategy")]
+    strategy: String,
+    /// strategy parameters by name; missing ones take the defaults of `STRATEGIES`
+    #[serde(default)]
+    params: serde_json::Map<String, Value>,
+    #[serde(default)]
+    commission: f64,
+    #[serde(default)]
+    slippage: f64,
+}
+
+/// Body of `POST /indicators/{name}`.
+#[derive(Deserialize)]
+struct IndicatorRequest {
+    #[serde(flatten)]
+    prices: Prices,
+    #[serde(default)]
+    period: Option<usize>,
+    /// band width of `bollinger`, in standard deviations
+    #[serde(default)]
+    k: Option<f64>,
+}
+
+/// Reference strategies of `backtest_strategy` with their parameters and defaults.
+pub const STRATEGIES: [(&str, &[(&str, f64)]); 4] = [
+    ("sma-crossover", &[("fast", 10.0), ("slow", 30.0)]),
+    ("rsi-mean-reversion", &[("period", 14.0), ("oversold", 30.0), ("overbought", 70.0)]),
+    ("bollinger-breakout", &[("period", 20.0), ("k", 2.0)]),
+    ("buy-hold", &[]),
+];
+
+/// Reference strategy `name` (one of `STRATEGIES`) with `params`; missing parameters take the defaults.
+pub fn backtest_strategy(name: &str, params: &serde_json::Map<String, Value>) -> Result<Box<dyn Strategy>, String> {
+    let defaults = STRATEGIES.iter().find(|(n, _)| *n == name).map(|(_, p)| *p).ok_or(format!("unknown strategy {}", name))?;
+    let number = |key: &str| match params.get(key) {
+        None => Ok(defaults.iter().find(|(k, _)| *k == key).map_or(0.0, |(_, d)| *d)),
+        Some(v) => v.as_f64().ok_or(format!("parameter {} must be a number", key)),
+    };
+    let period = |key: &str| {
+        let p = number(key)?;
+        if p < 0.0 || p.fract() != 0.0 {
+            return Err(format!("parameter {} must be a non-negative integer", key));
+        }
+        Ok(p as usize)
+    };
+    Ok(match name {
+        "sma-crossover" => Box::new(SmaCrossover { fast: period("fast")?, slow: period("slow")? }),
+        "rsi-mean-reversion" => Box::new(RsiMeanReversion { period: period("period")?, oversold: number("oversold")?, overbought: number("overbought")? }),
+        "bollinger-breakout" => Box::new(BollingerBreakout { period: period("period")?, k: number("k")? }),
+        _ => Box::new(BuyHold),
+    })
+}
+
+/// Run the backtest described by the JSON `body` with engine seed `seed`
+/// (`Config::backtest_seed`); the reply holds the metrics and the trades.
+pub fn backtest(body: &str, seed: u64) -> Result<Value, String> {
+    let request: BacktestRequest = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    let strategy = backtest_strategy(&request.strategy, &request.params)?;
+    let bars = request.prices.load()?;
+    let cfg = EngineConfig { commission_rate: request.commission, slippage: request.slippage, seed };
+    let report = backtest::run_strategy(strategy.as_ref(), &bars, cfg).map_err(str::to_string)?;
+    let trades: Vec<Value> = report
+        .trades
+        .iter()
+        .map(|t| json!({"entry_ts": t.entry_ts, "exit_ts": t.exit_ts, "entry_price": t.entry_price, "exit_price": t.exit_price, "units": t.units, "net_pnl": t.net_pnl}))
+        .collect();
+    Ok(json!({
+        "strategy": strategy.name(),
+        "bars": bars.len(),
+        "trades": report.trades.len(),
+        "wins": report.wins,
+        "win_rate": report.win_rate(),
+        "gross_pnl": report.gross_pnl,
+        "commissions": report.commissions,
+        "slippage": report.slippage,
+        "net_pnl": report.net_pnl,
+        "max_drawdown": report.max_drawdown,
+        "sharpe": report.sharpe(),
+        "run_hash": report.run_hash,
+        "fills": trades,
+    }))
+}
+
+/// Values of indicator `name` (`sma`, `ema`, `rsi` or `bollinger`) over the
+/// closes of the JSON `body`, from the first full window on; `warmup` is the
+/// number of leading closes without a value.
+pub fn indicator(name: &str, body: &str) -> Result<Value, String> {
+    let request: IndicatorRequest = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    let default = match name {
+        "rsi" => 14,
+        "sma" | "ema" | "bollinger" => 20,
+        other => return Err(format!("unknown indicator {}", other)),
+    };
+    let period = request.period.unwrap_or(default);
+    let closes: Vec<f64> = request.prices.load()?.iter().map(|b| b.close).collect();
+    let k = request.k.unwrap_or(2.0);
+    let (values, warmup) = match name {
+        "bollinger" => (indicators::bollinger(&closes, period, k).map(|b| json!(b)), indicators::bollinger_warmup_len(period)),
+        "rsi" => (indicators::rsi(&closes, period).map(|v| json!(v)), indicators::rsi_warmup_len(period)),
+        "sma" => (indicators::sma(&closes, period).map(|v| json!(v)), indicators::sma_warmup_len(period)),
+        _ => (indicators::ema(&closes, period).map(|v| json!(v)), indicators::ema_warmup_len(period)),
+    };
+    let mut reply = json!({"indicator": name, "period": period, "warmup": warmup, "values": values.map_err(|e| e.to_string())?});
+    if let Some(fields) = reply.as_object_mut().filter(|_| name == "bollinger") {
+        fields.insert("k".to_string(), json!(k));
+    }
+    Ok(reply)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backtest_and_indicator_requests() {
+        let body = r#"{"closes": [5, 4, 3, 4, 5, 6, 5, 4, 3], "strategy": "sma-crossover", "params": {"fast": 1, "slow": 2}}"#;
+        let closes = [5.0, 4.0, 3.0, 4.0, 5.0, 6.0, 5.0, 4.0, 3.0];
+        let bars: Vec<PriceBar> = closes.iter().enumerate().map(|(i, &c)| PriceBar { ts: i as u64, open: c, high: c, low: c, close: c, volume: 0.0 }).collect();
+        let cfg = EngineConfig { commission_rate: 0.0, slippage: 0.0, seed: 5 };
+        let expected = backtest::run_strategy(&SmaCrossover { fast: 1, slow: 2 }, &bars, cfg);
+        assert_eq!(expected.as_ref().map(|e| e.trades.len()), Ok(1));
+        let Ok(expected) = expected else { return };
+        let field = |reply: &Value, key: &str| reply.get(key).cloned();
+        let reply = backtest(body, 5).map(|r| (field(&r, "trades"), field(&r, "net_pnl"), field(&r, "run_hash")));
+        assert_eq!(reply, Ok((Some(json!(expected.trades.len())), Some(json!(expected.net_pnl)), Some(json!(expected.run_hash)))));
+
+        assert!(backtest(r#"{"closes": [1, 2], "strategy": "martingale"}"#, 0).is_err());
+        assert!(backtest(r#"{"closes": [1, 2], "path": "prices.csv"}"#, 0).is_err());
+        assert!(backtest(r#"{"path": "../secrets.csv"}"#, 0).is_err());
+
+        let sma = indicator("sma", r#"{"bars": [{"close": 1}, {"close": 2}, {"close": 3}], "period": 2}"#);
+        assert_eq!(sma, Ok(json!({"indicator": "sma", "period": 2, "warmup": 1, "values": [1.5, 2.5]})));
+        assert!(indicator("sma", r#"{"closes": [1], "period": 2}"#).is_err());
+        assert!(indicator("macd", r#"{"closes": [1]}"#).is_err());
+    }
+}
@@ -0,0 +1,166 @@
+#![forbid(unsafe_code)]
+
+//! Ranking of competing answers: when several pipeline stages can answer,
+//! `rank_answers` prefers exact knowledge matches over verified solver
+//! results over fuzzy knowledge over generated text, and within a tier the
+//! more confident answer.
+
+use serde::{Deserialize, Serialize};
+
+use crate::pipeline::Reply;
+use crate::response::Source;
+
+/// How trustworthy a kind of answer is, from best to worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Evidence {
+    /// stored answer to exactly this question
+    ExactMatch,
+    /// computed by a solver (arithmetic, equations, the Reasoner)
+    VerifiedSolver,
+    /// closest stored concept or rule-based interpretation
+    FuzzyKnowledge,
+    /// generated by the model or the n-gram fallback
+    Model,
+}
+
+impl Evidence {
+    /// Tier of a reply from `stage`: built-in stages by name, other stages
+    /// by source (knowledge with confidence 1 counts as an exact match).
+    pub fn of(stage: &str, reply: &Reply) -> Self {
+        match stage {
+            "knowledge" => Evidence::ExactMatch,
+            "arithmetic" | "linear" | "tools" | "reasoner" => Evidence::VerifiedSolver,
+            "semantic" | "concepts" => Evidence::FuzzyKnowledge,
+            "model" => Evidence::Model,
+            _ => match reply.source {
+                Source::Knowledge if reply.confidence >= 1.0 => Evidence::ExactMatch,
+                Source::Knowledge => Evidence::FuzzyKnowledge,
+                Source::Reasoner => Evidence::VerifiedSolver,
+                Source::Model => Evidence::Model,
+            },
+        }
+    }
+
+    /// Score added for the tier; tiers are one point apart so confidence
+    /// (0..=1) only orders answers within a tier.
+    pub fn weight(self) -> f32 {
+        match self {
+            Evidence::ExactMatch => 3.0,
+            Evidence::VerifiedSolver => 2.0,
+            Evidence::FuzzyKnowledge => 1.0,
+            Evidence::Model => 0.0,
+        }
+    }
+
+    /// Russian label used in the decision text.
+    pub fn label(self) -> &'static str {
+        match self {
+            Evidence::ExactMatch => "точное совпадение",
+            Evidence::VerifiedSolver => "проверенное решение",
+            Evidence::FuzzyKnowledge => "похожее знание",
+            Evidence::Model => "генерация модели",
+        }
+    }
+}
+
+/// One stage's answer waiting to be ranked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    /// stage that produced the reply
+    pub stage: &'static str,
+    /// the reply
+    pub reply: Reply,
+    /// tier of the reply (`Evidence::of`)
+    pub evidence: Evidence,
+}
+
+impl Candidate {
+    /// Candidate with its tier derived from `stage` and `reply`.
+    pub fn new(stage: &'static str, reply: Reply) -> Self {
+        let evidence = Evidence::of(stage, &reply);
+        Self { stage, reply, evidence }
+    }
+
+    /// Tier weight plus confidence.
+    pub fn score(&self) -> f32 {
+        self.evidence.weight() + self.reply.confidence.clamp(0.0, 1.0)
+    }
+}
+
+/// Score of one candidate, as reported in `ChatResponse::ranking`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CandidateScore {
+    /// stage that produced the candidate
+    pub stage: String,
+    /// its tier
+    pub evidence: Evidence,
+    /// the stage's confidence
+    pub confidence: f32,
+    /// tier weight plus confidence
+    pub score: f32,
+}
+
+/// Why an answer was chosen: a one-line decision and every candidate's score,
+/// best first.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Ranking {
+    /// human-readable decision
+    pub decision: String,
+    /// candidates by descending score
+    pub scores: Vec<CandidateScore>,
+}
+
+/// Result of `rank_answers`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankedAnswer {
+    /// best candidate
+    pub winner: Candidate,
+    /// decision and scores
+    pub ranking: Ranking,
+}
+
+/// Pick the best candidate (highest `Candidate::score`; on ties the earlier
+/// one). Candidates with blank text are ignored; `None` if none is left.
+pub fn rank_answers(candidates: Vec<Candidate>) -> Option<RankedAnswer> {
+    let mut candidates: Vec<Candidate> = candidates.into_iter().filter(|c| !c.reply.text.trim().is_empty()).collect();
+    // stable sort keeps pipeline order among equal scores
+    candidates.sort_by(|a, b| b.score().total_cmp(&a.score()));
+    let scores: Vec<CandidateScore> = candidates
+        .iter()
+        .map(|c| CandidateScore { stage: c.stage.to_string(), evidence: c.evidence, confidence: c.reply.confidence, score: c.score() })
+        .collect();
+    let total = candidates.len();
+    let winner = candidates.into_iter().next()?;
+    let decision = format!(
+        "выбран этап {} ({}, оценка {:.2}) из {} кандидатов",
+        winner.stage,
+        winner.evidence.label(),
+        winner.score(),
+        total
+    );
+    Some(RankedAnswer { winner, ranking: Ranking { decision, scores } })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiers_outrank_confidence() {
+        let reply = |text: &str, source, confidence| Reply::new(text.to_string(), source, confidence, "");
+        let ranked = rank_answers(vec![
+            Candidate::new("model", reply("акула плывёт", Source::Model, 0.99)),
+            Candidate::new("concepts", reply("акула — рыба", Source::Knowledge, 0.6)),
+            Candidate::new("knowledge", reply("хрящевая рыба", Source::Knowledge, 1.0)),
+            Candidate::new("linear", reply("  ", Source::Reasoner, 1.0)),
+        ]);
+        assert!(ranked.is_some());
+        let Some(ranked) = ranked else { return };
+        assert_eq!(ranked.winner.stage, "knowledge");
+        let order: Vec<&str> = ranked.ranking.scores.iter().map(|s| s.stage.as_str()).collect();
+        assert_eq!(order, vec!["knowledge", "concepts", "model"]);
+        assert!(ranked.ranking.decision.contains("точное совпадение"));
+        assert!(rank_answers(Vec::new()).is_none());
+    }
+}
@@ -1,8 +1,10 @@
 use std::fs::OpenOptions;
 use std::io::Write;
+use std::path::Path;
 use crate::integrator::try_integrate;
 use crate::knowledge_env::auto_expand_on_new_topic;
 use crate::train::append_knowledge;
+use crate::reports::Report;
 
 /// Модуль рассуждения Shark-Core.
 /// Позволяет объяснять ход решения и сохранять рассуждения в лог.
@@ -12,7 +14,32 @@ impl Reasoner {
     // integrator logic moved to `integrator.rs`; Reasoner will call try_integrate
 
     /// Главный метод: принимает задачу и возвращает пару (ответ, рассуждение).
-    pub fn explain(input: &str) -> (String, String) {
+    /// Решённые интегралы сохраняются в базу знаний в `data_dir`, остальные
+    /// рассуждения — в `reasoning_log.md` в каталоге отчётов.
+    pub fn explain(data_dir: &Path, input: &str) -> (String, String) {
+        let (answer, reasoning) = Self::solve(input);
+        if input.to_lowercase().contains("интеграл") && try_integrate(input).is_some() {
+            // Persist as knowledge: append to central knowledge.csv and per-topic calculus file
+            let _ = append_knowledge(&data_dir.join("knowledge.csv").to_string_lossy(), input, &answer);
+            // ensure calculus topic exists and append
+            let _ = auto_expand_on_new_topic(data_dir, "calculus");
+            if let Ok(mut f) = OpenOptions::new().create(true).append(true).open(data_dir.join("knowledge").join("knowledge_calculus.csv")) {
+                let now = chrono::Utc::now().to_rfc3339();
+                let _ = writeln!(f, "\"{}\",\"{}\",\"{}\"", input.replace('"', "'"), answer.replace('"', "'"), now);
+            }
+            return (answer, reasoning);
+        }
+
+        // Лог в файл
+        let entry = Report::new(chrono::Utc::now().to_rfc3339()).text(input).text(format!("Рассуждение:\n{}", reasoning)).text("---");
+        let _ = entry.append("reasoning_log.md");
+
+        (answer, reasoning)
+    }
+
+    /// То же, что `explain`, но без побочных эффектов: ничего не пишет ни в
+    /// базу знаний, ни в лог рассуждений (для self-play и проверок).
+    pub fn solve(input: &str) -> (String, String) {
         let mut reasoning = String::new();
         // Prefer algebraic/symbolic simplification patterns before numeric evaluation.
         let normalized = input.replace(' ', "");
@@ -46,14 +73,6 @@ impl Reasoner {
             if let Some(out) = try_integrate(input) {
                 // log the integrator output in reasoning
                 reasoning.push_str(&format!("🧮 {}\n", out));
-                // Persist as knowledge: append to central knowledge.csv and per-topic calculus file
-                let _ = append_knowledge("crates/predict/data/knowledge.csv", input, &out);
-                // ensure calculus topic exists and append
-                let _ = auto_expand_on_new_topic("calculus");
-                if let Ok(mut f) = OpenOptions::new().create(true).append(true).open("crates/predict/data/knowledge/knowledge_calculus.csv") {
-                    let now = chrono::Utc::now().to_rfc3339();
-                    let _ = writeln!(f, "\"{}\",\"{}\",\"{}\"", input.replace('"', "'"), out.replace('"', "'"), now);
-                }
                 return (out, reasoning);
             }
             reasoning.push_str("🧠 Интегралы пока решаются символически позже.\n");
@@ -84,11 +103,6 @@ impl Reasoner {
             "непонятно".into()
         };
 
-        // Лог в файл
-        if let Ok(mut file) = OpenOptions::new().create(true).append(true).open("docs/reasoning_log.md") {
-            let _ = writeln!(file, "### {}\n{}\nРассуждение:\n{}\n---\n", chrono::Utc::now().to_rfc3339(), input, reasoning);
-        }
-
         (answer, reasoning)
     }
 
@@ -1,14 +1,15 @@
 use std::collections::HashMap;
 
-/// Detect query mode based on keywords.
+use crate::intent::Intent;
+use crate::rerank::{Reranker, Signals};
+
+/// Detect query mode ("question", "instruction" or "statement") with the
+/// intent classifier; math, research, small talk and unsure predictions are statements.
 pub fn detect_mode(input: &str) -> &'static str {
-    let lower = input.to_lowercase();
-    if lower.contains('?') || lower.starts_with("что") || lower.starts_with("кто") || lower.starts_with("как") || lower.starts_with("почему") {
-        "question"
-    } else if lower.contains("покажи") || lower.contains("как") || lower.contains("сделай") {
-        "instruction"
-    } else {
-        "statement"
+    match crate::intent::classify(input).confident() {
+        Some(Intent::Question) => "question",
+        Some(Intent::Instruction) => "instruction",
+        _ => "statement",
     }
 }
 
@@ -31,19 +32,91 @@ fn get_trigrams(s: &str) -> HashMap<String, usize> {
     map
 }
 
-/// Find closest knowledge entry by trigram similarity.
-pub fn find_closest_concept(input: &str, knowledge: &std::collections::HashMap<String, String>) -> Option<(String, String)> {
-    let knowledge_vec: Vec<(String, String)> = knowledge.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
-    let mut best = None;
-    let mut best_sim = 0.0;
-    for (q, a) in knowledge_vec {
-        let sim = trigram_similarity(input, &q);
-        if sim > best_sim && sim > 0.3 { // threshold
-            best_sim = sim;
-            best = Some((q.clone(), a.clone()));
+/// Levenshtein distance between `a` and `b` in characters: the fewest
+/// insertions, deletions and substitutions turning one into the other.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = i;
+        let mut left = i + 1;
+        for (cb, up) in b.iter().zip(row.iter_mut().skip(1)) {
+            let cell = (diagonal + usize::from(ca != *cb)).min(*up + 1).min(left + 1);
+            diagonal = *up;
+            *up = cell;
+            left = cell;
+        }
+        if let Some(first) = row.first_mut() {
+            *first = i + 1;
         }
     }
-    best
+    row.last().copied().unwrap_or(0)
+}
+
+/// `1 - levenshtein / longer length` (0.0 to 1.0; 1.0 for two empty strings).
+pub fn levenshtein_similarity(a: &str, b: &str) -> f64 {
+    let longer = a.chars().count().max(b.chars().count());
+    if longer == 0 { 1.0 } else { 1.0 - levenshtein(a, b) as f64 / longer as f64 }
+}
+
+/// Jaro-Winkler similarity (0.0 to 1.0): characters matching within half the
+/// longer length, penalised for transpositions, boosted by a common prefix
+/// of up to 4 characters (scaling factor 0.1).
+pub fn jaro_winkler(a: &str, b: &str) -> f64 {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+    let mut taken = vec![false; b.len()];
+    let mut matched_a = Vec::new();
+    for (i, ca) in a.iter().enumerate() {
+        let lo = i.saturating_sub(window);
+        let hi = (i + window + 1).min(b.len());
+        let found = (lo..hi).find(|&j| !taken.get(j).copied().unwrap_or(true) && b.get(j) == Some(ca));
+        if let Some(slot) = found.and_then(|j| taken.get_mut(j)) {
+            *slot = true;
+            matched_a.push(*ca);
+        }
+    }
+    let m = matched_a.len();
+    if m == 0 {
+        return 0.0;
+    }
+    let matched_b = b.iter().zip(&taken).filter(|(_, t)| **t).map(|(c, _)| c);
+    let transpositions = matched_a.iter().zip(matched_b).filter(|(x, y)| x != y).count() / 2;
+    let m = m as f64;
+    let jaro = (m / a.len() as f64 + m / b.len() as f64 + (m - transpositions as f64) / m) / 3.0;
+    let prefix = a.iter().zip(&b).take(4).take_while(|(x, y)| x == y).count();
+    jaro + prefix as f64 * 0.1 * (1.0 - jaro)
+}
+
+/// Strings up to this many characters (the longer of the two) are compared
+/// by edit distance in full; longer ones by trigrams more and more.
+pub const SHORT_TEXT: usize = 16;
+
+/// Trigram similarity, raised by the edit-distance metrics (mean of
+/// `levenshtein_similarity` and `jaro_winkler`) for short strings, where a
+/// typo or two leaves few trigrams in common: the edit score weighs
+/// `SHORT_TEXT / length` (at most 1) and never lowers the trigram score.
+pub fn combined_similarity(a: &str, b: &str) -> f64 {
+    let trigram = trigram_similarity(a, b);
+    let longer = a.chars().count().max(b.chars().count()).max(1);
+    let weight = (SHORT_TEXT as f64 / longer as f64).min(1.0);
+    let edit = (levenshtein_similarity(a, b) + jaro_winkler(a, b)) / 2.0;
+    trigram.max(weight * edit + (1.0 - weight) * trigram)
+}
+
+/// Find closest knowledge entry: candidates by `combined_similarity` reranked
+/// by shared words (`rerank::Reranker`), both above the 0.3 threshold.
+pub fn find_closest_concept(input: &str, knowledge: &std::collections::HashMap<String, String>) -> Option<(String, String)> {
+    find_closest_concept_with(input, knowledge, &Reranker::default(), &Signals::default())
+}
+
+/// `find_closest_concept` with explicit reranker weights and signals
+/// (word frequencies, embeddings).
+pub fn find_closest_concept_with(input: &str, knowledge: &HashMap<String, String>, reranker: &Reranker, signals: &Signals) -> Option<(String, String)> {
+    reranker.rank(input, knowledge, signals).first().map(|c| (c.question.clone(), c.answer.clone()))
 }
 
 /// Extract rule and example from answer.
@@ -59,6 +132,11 @@ pub fn parse_answer(a: &str) -> (String, String) {
 
 /// Build reasoned response based on mode.
 pub fn reason_response(input: &str, knowledge: &std::collections::HashMap<String, String>) -> String {
+    reason_response_with(input, knowledge, &Reranker::default(), &Signals::default())
+}
+
+/// `reason_response` matching concepts with `find_closest_concept_with`.
+pub fn reason_response_with(input: &str, knowledge: &HashMap<String, String>, reranker: &Reranker, signals: &Signals) -> String {
     // Universal handler for "what is ..." questions
     if input.to_lowercase().starts_with("что такое") {
         let concept = input["что такое".len()..].trim().trim_end_matches('?').to_lowercase();
@@ -72,7 +150,7 @@ pub fn reason_response(input: &str, knowledge: &std::collections::HashMap<String
     let mode = detect_mode(input);
     match mode {
         "question" => {
-            if let Some((q, a)) = find_closest_concept(input, knowledge) {
+            if let Some((q, a)) = find_closest_concept_with(input, knowledge, reranker, signals) {
                 let (rule, example) = parse_answer(&a);
                 format!("{} — {}. {}", q, rule, example)
             } else {
@@ -80,18 +158,44 @@ pub fn reason_response(input: &str, knowledge: &std::collections::HashMap<String
             }
         }
         "instruction" => {
-            if let Some((q, a)) = find_closest_concept(input, knowledge) {
+            if let Some((q, a)) = find_closest_concept_with(input, knowledge, reranker, signals) {
                 format!("Инструкция: {}. {}", q, a)
             } else {
                 "Не понял инструкцию.".to_string()
             }
         }
         _ => {
-            if let Some((q, a)) = find_closest_concept(input, knowledge) {
+            if let Some((q, a)) = find_closest_concept_with(input, knowledge, reranker, signals) {
                 format!("Утверждение: {}. {}", q, a)
             } else {
                 "Не нашел связи.".to_string()
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distances_catch_typos_in_short_queries() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "акула"), 5);
+        assert_eq!(levenshtein("акула", "акула"), 0);
+        assert!((jaro_winkler("martha", "marhta") - 0.9611).abs() < 1e-4);
+        assert!((jaro_winkler("dixon", "dicksonx") - 0.8133).abs() < 1e-4);
+        assert_eq!((jaro_winkler("", ""), jaro_winkler("abc", "")), (1.0, 0.0));
+
+        // one typo leaves few trigrams but most of the letters
+        assert!(trigram_similarity("алгоритм", "алгорим") < 0.6);
+        assert!(combined_similarity("алгоритм", "алгорим") > 0.85);
+        // long strings keep (almost) the trigram score
+        let (a, b) = ("как работает нейронная сеть внутри", "как устроена память компьютера");
+        assert!(combined_similarity(a, b) - trigram_similarity(a, b) < 0.3);
+
+        let knowledge: HashMap<String, String> =
+            [("алгоритм", "последовательность шагов"), ("логарифм", "показатель степени")].iter().map(|(q, a)| (q.to_string(), a.to_string())).collect();
+        assert_eq!(find_closest_concept("алгорим", &knowledge).map(|(q, _)| q), Some("алгоритм".to_string()));
+    }
+}
@@ -0,0 +1,326 @@
+#![forbid(unsafe_code)]
+
+//! Least-squares regression on `(x, y)` pairs.
+//!
+//! `fit_polynomial` fits `y = c0 + c1·x + … + cn·xⁿ`. It builds polynomials
+//! orthogonal over the sample points (Forsythe's three-term recurrence on x
+//! scaled to [-1, 1]), so there is no ill-conditioned normal matrix to
+//! invert, and converts the result back to plain coefficients. Every fit
+//! reports R² and the Akaike criteria; `select_degree` keeps the degree with
+//! the lowest corrected AIC, which penalizes parameters more when there are
+//! few points, so exact data picks its true degree and noisy data is not
+//! interpolated. `discover_equation` fits the same polynomials to a target
+//! function by stochastic gradient descent. With the `trading` feature the online line with forgetting
+//! of the `indicators` crate is re-exported as `OnlineRegressor`.
+
+use std::iter::once;
+
+#[cfg(feature = "trading")]
+pub use indicators::regression::OnlineRegressor;
+
+/// Least-squares polynomial.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolyFit {
+    /// coefficients, constant first: `y = Σ coefficients[k]·x^k`
+    pub coefficients: Vec<f64>,
+    /// residual sum of squares
+    pub rss: f64,
+    /// coefficient of determination (1 when y is constant and fitted exactly)
+    pub r2: f64,
+    /// Akaike information criterion `n·ln(rss/n) + 2·(degree + 1)`; lower is better
+    pub aic: f64,
+    /// AIC with the small-sample correction; infinite without a residual degree of freedom
+    pub aicc: f64,
+    /// number of (x, y) pairs
+    pub n: usize,
+}
+
+impl PolyFit {
+    /// Degree of the polynomial.
+    pub fn degree(&self) -> usize {
+        self.coefficients.len().saturating_sub(1)
+    }
+
+    /// Value at `x` (Horner's scheme).
+    pub fn predict(&self, x: f64) -> f64 {
+        self.coefficients.iter().rev().fold(0.0, |acc, c| acc * x + c)
+    }
+}
+
+/// `a += k·b`, growing `a` to the length of `b`.
+fn add_scaled(a: &mut Vec<f64>, b: &[f64], k: f64) {
+    if a.len() < b.len() {
+        a.resize(b.len(), 0.0);
+    }
+    for (x, y) in a.iter_mut().zip(b) {
+        *x += k * y;
+    }
+}
+
+/// Coefficients of `p(t)·(u·t + v)`.
+fn times_linear(p: &[f64], u: f64, v: f64) -> Vec<f64> {
+    let shifted = once(0.0).chain(p.iter().map(|c| u * c));
+    shifted.zip(p.iter().map(|c| v * c).chain(once(0.0))).map(|(a, b)| a + b).collect()
+}
+
+/// Coefficients in x of the polynomial with coefficients `in_t` in
+/// `t = (x - center) / half` (Horner's scheme over polynomials).
+fn unscale(in_t: &[f64], center: f64, half: f64) -> Vec<f64> {
+    in_t.iter().rev().fold(Vec::new(), |acc, a| {
+        let mut acc = times_linear(&acc, 1.0 / half, -center / half);
+        add_scaled(&mut acc, &[*a], 1.0);
+        acc
+    })
+}
+
+/// Least-squares polynomial of `degree` through `pairs`; None when there
+/// are fewer than `degree + 1` distinct x values or a value is not finite.
+pub fn fit_polynomial(pairs: &[(f64, f64)], degree: usize) -> Option<PolyFit> {
+    if pairs.iter().any(|(x, y)| !x.is_finite() || !y.is_finite()) {
+        return None;
+    }
+    let mut xs: Vec<f64> = pairs.iter().map(|p| p.0).collect();
+    xs.sort_by(f64::total_cmp);
+    xs.dedup();
+    let (&lo, &hi) = (xs.first()?, xs.last()?);
+    if xs.len() <= degree {
+        return None;
+    }
+    // t = (x - center) / half in [-1, 1]
+    let (center, half) = ((lo + hi) / 2.0, if hi > lo { (hi - lo) / 2.0 } else { 1.0 });
+    let t: Vec<f64> = pairs.iter().map(|(x, _)| (x - center) / half).collect();
+    let y: Vec<f64> = pairs.iter().map(|p| p.1).collect();
+
+    // p_{k+1} = (t - α_k)·p_k - β_k·p_{k-1}, as values at the points and as coefficients in t
+    let (mut prev, mut cur) = (vec![0.0; t.len()], vec![1.0; t.len()]);
+    let (mut prev_poly, mut cur_poly) = (Vec::new(), vec![1.0]);
+    let mut prev_norm = 0.0;
+    let mut fitted = vec![0.0; t.len()];
+    let mut in_t = Vec::new();
+    for k in 0..=degree {
+        let norm: f64 = cur.iter().map(|p| p * p).sum();
+        let b = cur.iter().zip(&y).map(|(p, y)| p * y).sum::<f64>() / norm;
+        add_scaled(&mut fitted, &cur, b);
+        add_scaled(&mut in_t, &cur_poly, b);
+        if k == degree {
+            break;
+        }
+        let alpha = cur.iter().zip(&t).map(|(p, t)| t * p * p).sum::<f64>() / norm;
+        let beta = if k == 0 { 0.0 } else { norm / prev_norm };
+        let next: Vec<f64> = cur.iter().zip(&prev).zip(&t).map(|((p, q), t)| (t - alpha) * p - beta * q).collect();
+        let mut next_poly = times_linear(&cur_poly, 1.0, -alpha);
+        add_scaled(&mut next_poly, &prev_poly, -beta);
+        (prev, cur, prev_poly, cur_poly, prev_norm) = (cur, next, cur_poly, next_poly, norm);
+    }
+
+    let coefficients = unscale(&in_t, center, half);
+
+    let n = pairs.len();
+    let mean = y.iter().sum::<f64>() / n as f64;
+    let rss: f64 = y.iter().zip(&fitted).map(|(y, f)| (y - f).powi(2)).sum();
+    let ss_tot: f64 = y.iter().map(|y| (y - mean).powi(2)).sum();
+    let r2 = if ss_tot == 0.0 { 1.0 } else { 1.0 - rss / ss_tot };
+    // an exact fit would make ln(rss) -inf; rounding noise is all that is left there
+    let floor = f64::EPSILON * y.iter().map(|y| y * y).sum::<f64>().max(f64::MIN_POSITIVE);
+    let k = (degree + 1) as f64;
+    let aic = n as f64 * (rss.max(floor) / n as f64).ln() + 2.0 * k;
+    let aicc = if n as f64 > k + 1.0 { aic + 2.0 * k * (k + 1.0) / (n as f64 - k - 1.0) } else { f64::INFINITY };
+    Some(PolyFit { coefficients, rss, r2, aic, aicc, n })
+}
+
+/// Fit of degree `0..=max_degree` with the lowest `aicc` (the lower degree
+/// on ties); None when not even a constant can be fitted.
+pub fn select_degree(pairs: &[(f64, f64)], max_degree: usize) -> Option<PolyFit> {
+    (0..=max_degree).filter_map(|d| fit_polynomial(pairs, d)).fold(None, |best: Option<PolyFit>, fit| match best {
+        Some(best) if best.aicc <= fit.aicc => Some(best),
+        _ => Some(fit),
+    })
+}
+
+/// Step size of `discover_equation` by step number.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LearningRate {
+    /// the same rate at every step
+    Constant(f64),
+    /// `initial / (1 + decay·step)`
+    InverseTime {
+        /// rate at step 0
+        initial: f64,
+        /// decay per step
+        decay: f64,
+    },
+    /// `initial · gamma^step`
+    Exponential {
+        /// rate at step 0
+        initial: f64,
+        /// factor per step (below 1)
+        gamma: f64,
+    },
+}
+
+impl LearningRate {
+    /// Rate at `step` (counted from 0).
+    pub fn at(self, step: usize) -> f64 {
+        match self {
+            LearningRate::Constant(rate) => rate,
+            LearningRate::InverseTime { initial, decay } => initial / (1.0 + decay * step as f64),
+            LearningRate::Exponential { initial, gamma } => initial * gamma.powf(step as f64),
+        }
+    }
+}
+
+/// Settings of `discover_equation`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiscoverOptions {
+    /// degree of the polynomial to fit
+    pub degree: usize,
+    /// step size schedule
+    pub learning_rate: LearningRate,
+    /// most gradient steps
+    pub iterations: usize,
+    /// stop once the loss is at most this (checked every `DiscoverOptions::CHECK_EVERY` steps; 0 runs every iteration)
+    pub tolerance: f64,
+    /// interval the sample points are drawn from
+    pub domain: (f64, f64),
+    /// seed of the starting coefficients and the sample points
+    pub seed: u64,
+}
+
+impl DiscoverOptions {
+    /// Steps between two loss checks against `tolerance`.
+    pub const CHECK_EVERY: usize = 100;
+    /// Evenly spaced points of the domain the loss is measured on.
+    pub const LOSS_POINTS: usize = 101;
+}
+
+impl Default for DiscoverOptions {
+    /// A quadratic on [-5, 5], 20000 steps at rate 0.1, seed 42.
+    fn default() -> Self {
+        Self { degree: 2, learning_rate: LearningRate::Constant(0.1), iterations: 20_000, tolerance: 0.0, domain: (-5.0, 5.0), seed: 42 }
+    }
+}
+
+/// Result of `discover_equation`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoveryReport {
+    /// coefficients, constant first, as `PolyFit::coefficients`
+    pub coefficients: Vec<f64>,
+    /// mean squared error against the target on `DiscoverOptions::LOSS_POINTS` points of the domain
+    pub loss: f64,
+    /// gradient steps taken
+    pub iterations: usize,
+}
+
+impl DiscoveryReport {
+    /// Value of the discovered polynomial at `x`.
+    pub fn predict(&self, x: f64) -> f64 {
+        self.coefficients.iter().rev().fold(0.0, |acc, c| acc * x + c)
+    }
+}
+
+/// Fit a polynomial to `target` by stochastic gradient descent on the
+/// squared error at uniform random points of the domain, for when the target
+/// can only be sampled one point at a time. The points are scaled to
+/// [-1, 1] first, so one learning rate suits any domain and degree (rates
+/// around 0.1 to 0.5; a rate that is too large diverges, leaving an infinite
+/// or NaN loss). Deterministic for a given seed. `fit_polynomial` solves the
+/// same problem in one step when the points are known up front.
+pub fn discover_equation<F: Fn(f64) -> f64>(target: F, options: &DiscoverOptions) -> DiscoveryReport {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(options.seed);
+    let (lo, hi) = (options.domain.0.min(options.domain.1), options.domain.0.max(options.domain.1));
+    let (center, half) = ((lo + hi) / 2.0, if hi > lo { (hi - lo) / 2.0 } else { 1.0 });
+    let mut in_t: Vec<f64> = (0..=options.degree).map(|_| rng.gen_range(-1.0..1.0)).collect();
+    let value = |coefficients: &[f64], t: f64| coefficients.iter().rev().fold(0.0, |acc, c| acc * t + c);
+    let loss = |coefficients: &[f64]| {
+        let last = (DiscoverOptions::LOSS_POINTS - 1) as f64;
+        let errors = (0..DiscoverOptions::LOSS_POINTS).map(|i| -1.0 + 2.0 * i as f64 / last).map(|t| (value(coefficients, t) - target(center + half * t)).powi(2));
+        errors.sum::<f64>() / DiscoverOptions::LOSS_POINTS as f64
+    };
+
+    let mut iterations = 0;
+    while iterations < options.iterations {
+        if iterations % DiscoverOptions::CHECK_EVERY == 0 && options.tolerance > 0.0 && loss(&in_t) <= options.tolerance {
+            break;
+        }
+        let t = if hi > lo { rng.gen_range(-1.0..=1.0) } else { 0.0 };
+        let step = options.learning_rate.at(iterations) * (value(&in_t, t) - target(center + half * t));
+        // d(error²/2)/d(c_k) = error·t^k
+        let mut power = 1.0;
+        for c in in_t.iter_mut() {
+            *c -= step * power;
+            power *= t;
+        }
+        iterations += 1;
+        if in_t.iter().any(|c| !c.is_finite()) {
+            break;
+        }
+    }
+    DiscoveryReport { loss: loss(&in_t), coefficients: unscale(&in_t, center, half), iterations }
+}
+
+/// Very small least-squares linear regressor using provided `(x,y)` pairs.
+/// Returns predicted y for given `x0`; None for fewer than two distinct x.
+pub fn linear_regressor_predict(pairs: &[(f64, f64)], x0: f64) -> Option<f64> {
+    fit_polynomial(pairs, 1).map(|fit| fit.predict(x0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polynomial_fits_and_degree_selection() {
+        let quadratic: Vec<(f64, f64)> = (-5..=5).map(|x| x as f64).map(|x| (x, crate::hidden_function(x))).collect();
+        let fit = fit_polynomial(&quadratic, 2);
+        assert_eq!(fit.as_ref().map(|f| f.coefficients.len()), Some(3));
+        let Some(fit) = fit else { return };
+        for (c, expected) in fit.coefficients.iter().zip([7.0, -2.0, 3.0]) {
+            assert!((c - expected).abs() < 1e-9);
+        }
+        assert!(fit.r2 > 1.0 - 1e-12 && fit.rss < 1e-12);
+        assert!((fit.predict(10.0) - crate::hidden_function(10.0)).abs() < 1e-7);
+        let Some(line) = fit_polynomial(&quadratic, 1) else { return };
+        assert!(line.r2 < fit.r2 && line.aic > fit.aic);
+
+        // far from the origin the scaled basis keeps a cubic exact
+        let cubic: Vec<(f64, f64)> = (0..20).map(|i| 1000.0 + i as f64).map(|x| (x, 0.5 * (x - 1005.0).powi(3) - x)).collect();
+        assert!(fit_polynomial(&cubic, 3).is_some_and(|f| f.rss < 1e-6));
+
+        assert_eq!(select_degree(&quadratic, 6).map(|f| f.degree()), Some(2));
+        // a small deterministic wobble on a line stays a line
+        let noisy: Vec<(f64, f64)> = (0..30).map(|i| (i as f64, 2.0 * i as f64 + 1.0 + if i % 2 == 0 { 0.3 } else { -0.3 })).collect();
+        assert_eq!(select_degree(&noisy, 5).map(|f| f.degree()), Some(1));
+
+        assert!(fit_polynomial(&[(1.0, 2.0), (1.0, 3.0)], 1).is_none());
+        assert!(fit_polynomial(&[(f64::NAN, 1.0)], 0).is_none());
+        assert!(fit_polynomial(&[], 0).is_none());
+        assert_eq!(fit_polynomial(&[(1.0, 2.0), (1.0, 4.0)], 0).map(|f| f.coefficients), Some(vec![3.0]));
+    }
+
+    #[test]
+    fn discovery_follows_the_options() {
+        // a cubic far from the origin, with a decaying rate and an early stop
+        let cubic = |x: f64| 0.01 * (x - 100.0).powi(3) + x;
+        let options = DiscoverOptions {
+            degree: 3,
+            learning_rate: LearningRate::InverseTime { initial: 0.5, decay: 1e-4 },
+            iterations: 200_000,
+            tolerance: 1e-6,
+            domain: (90.0, 110.0),
+            seed: 7,
+        };
+        let report = discover_equation(cubic, &options);
+        assert!(report.loss <= 1e-6 && report.iterations < options.iterations);
+        assert!((report.predict(105.0) - cubic(105.0)).abs() < 1e-2);
+        assert_eq!(discover_equation(cubic, &options), report);
+
+        let line = DiscoverOptions { degree: 1, learning_rate: LearningRate::Exponential { initial: 0.3, gamma: 0.9999 }, ..DiscoverOptions::default() };
+        let report = discover_equation(|x| 2.0 * x - 1.0, &line);
+        assert_eq!((report.coefficients.len(), report.iterations), (2, 20_000));
+        assert!(report.loss < 1e-9);
+        let diverging = DiscoverOptions { learning_rate: LearningRate::Constant(50.0), ..DiscoverOptions::default() };
+        assert!(!discover_equation(crate::hidden_function, &diverging).loss.is_finite());
+    }
+}
@@ -0,0 +1,211 @@
+#![forbid(unsafe_code)]
+
+//! Replay log of generations, for debugging reports of nondeterministic
+//! answers (`[replay] enabled` in `shark.toml`, off by default).
+//!
+//! Every `AI::generate` call appends a JSON line to
+//! `<state_dir>/replay.jsonl`: the generator, its seed and length limits,
+//! the speculative draft, the exact context the model saw (with its FNV-1a
+//! hash) and the chosen token indices (`ALPHABET` positions for char models,
+//! vocabulary positions for n-gram models). `replay` regenerates a record
+//! with the recorded settings and reports the first token that differs;
+//! `chat replay <id>` prints the outcome.
+
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, SharkError};
+use crate::model_registry::{GeneratorRef, ModelKind, DEFAULT};
+use crate::tokenizer::ALPHABET;
+use crate::AI;
+
+/// File name of the log in the state directory.
+pub const FILE: &str = "replay.jsonl";
+
+/// One recorded generation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayRecord {
+    /// 12 hex digits, unique per generation
+    pub id: String,
+    /// RFC 3339 time of the generation
+    pub timestamp: String,
+    /// selected generator (`default` or a registry name)
+    pub generator: String,
+    /// kind of the generator that ran
+    pub kind: ModelKind,
+    /// model seed (`sampler.seed`)
+    pub seed: u64,
+    /// length limit (`max_chars`, or `ngram_max_chars` for n-gram models)
+    pub max_chars: usize,
+    /// speculative draft model (`model.draft`), empty when none
+    #[serde(default)]
+    pub draft: String,
+    /// characters drafted per target pass
+    pub speculative_k: usize,
+    /// FNV-1a hash of `context`, hex
+    pub context_hash: String,
+    /// context the generator was given (dialog history and prompt)
+    pub context: String,
+    /// chosen token indices
+    pub tokens: Vec<usize>,
+    /// generated text
+    pub text: String,
+}
+
+/// Result of `replay`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReplayOutcome {
+    /// regenerated text
+    pub text: String,
+    /// regenerated token indices
+    pub tokens: Vec<usize>,
+    /// index of the first token that differs from the record (`None`: identical)
+    pub divergence: Option<usize>,
+}
+
+/// FNV-1a hash of `text` as 16 hex digits.
+pub fn context_hash(text: &str) -> String {
+    let hash = text.bytes().fold(0xcbf29ce484222325u64, |h, b| (h ^ b as u64).wrapping_mul(0x100000001b3));
+    format!("{:016x}", hash)
+}
+
+/// Token indices of `text` under `generator`; unknown characters are skipped.
+pub fn tokens(generator: GeneratorRef, text: &str) -> Vec<usize> {
+    match generator {
+        GeneratorRef::Char(_) => text.bytes().filter_map(|b| ALPHABET.iter().position(|&a| a == b)).collect(),
+        GeneratorRef::Ngram(ngram) => text.chars().filter_map(|c| ngram.symbol_index(c)).collect(),
+    }
+}
+
+/// Append-only JSONL file of `ReplayRecord`s.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayLog {
+    /// file the records are appended to
+    pub path: PathBuf,
+}
+
+impl ReplayLog {
+    /// Log writing to `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Append one record.
+    pub fn append(&self, record: &ReplayRecord) -> io::Result<()> {
+        if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let line = serde_json::to_string(record).map_err(io::Error::other)?;
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", line)
+    }
+
+    /// All records; lines that do not parse are skipped.
+    pub fn load(&self) -> Result<Vec<ReplayRecord>> {
+        let text = std::fs::read_to_string(&self.path).map_err(|e| SharkError::io(&self.path, e))?;
+        Ok(text.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+    }
+
+    /// The record whose id starts with `id`.
+    pub fn find(&self, id: &str) -> Result<ReplayRecord> {
+        let mut matching = self.load()?.into_iter().filter(|r| !id.is_empty() && r.id.starts_with(id));
+        match (matching.next(), matching.next()) {
+            (Some(record), None) => Ok(record),
+            _ => Err(SharkError::UnknownReplay(id.to_string())),
+        }
+    }
+}
+
+/// Record of `ai` generating `text` from `context`.
+pub fn record(ai: &AI, context: &str, text: &str) -> ReplayRecord {
+    let generator = ai.generator();
+    let (kind, seed, max_chars) = match generator {
+        GeneratorRef::Char(model) => (ModelKind::Char, model.seed, model.max_chars),
+        GeneratorRef::Ngram(_) => (ModelKind::Ngram, ai.model.seed, ai.config.sampler.ngram_max_chars),
+    };
+    let now = chrono::Utc::now();
+    let hash = context_hash(context);
+    let id = context_hash(&format!("{}{}", now.timestamp_nanos_opt().unwrap_or_default(), hash)).chars().take(12).collect();
+    ReplayRecord {
+        id,
+        timestamp: now.to_rfc3339(),
+        generator: ai.models.active().unwrap_or(DEFAULT).to_string(),
+        kind,
+        seed,
+        max_chars,
+        draft: if kind == ModelKind::Char { ai.config.model.draft.clone() } else { String::new() },
+        speculative_k: ai.config.model.speculative_k,
+        context_hash: hash,
+        context: context.to_string(),
+        tokens: tokens(generator, text),
+        text: text.to_string(),
+    }
+}
+
+/// Regenerate `record` with its generator, seed, limits and draft; the AI's
+/// own selection and settings are restored afterwards. A context that does not
+/// match its hash (an edited log) is an error.
+pub fn replay(ai: &mut AI, record: &ReplayRecord, log: &Path) -> Result<ReplayOutcome> {
+    if context_hash(&record.context) != record.context_hash {
+        return Err(SharkError::Corrupt { path: log.to_path_buf(), reason: format!("record {}: context does not match its hash", record.id) });
+    }
+    let previous = ai.models.active().unwrap_or(DEFAULT).to_string();
+    let saved = (ai.model.seed, ai.model.max_chars, ai.config.sampler.ngram_max_chars, ai.config.model.draft.clone(), ai.config.model.speculative_k);
+    ai.model.seed = record.seed;
+    match record.kind {
+        ModelKind::Char => ai.model.max_chars = record.max_chars,
+        ModelKind::Ngram => ai.config.sampler.ngram_max_chars = record.max_chars,
+    }
+    ai.config.model.draft = record.draft.clone();
+    ai.config.model.speculative_k = record.speculative_k;
+    let result = ai.use_model(&record.generator).and_then(|()| if record.draft.is_empty() { Ok(()) } else { ai.models.load(&record.draft).map(|_| ()) });
+    let outcome = result.map(|()| {
+        let (text, _) = ai.generate_from(&record.context);
+        let tokens = tokens(ai.generator(), &text);
+        let divergence = tokens.iter().zip(&record.tokens).position(|(a, b)| a != b).or((tokens.len() != record.tokens.len()).then(|| tokens.len().min(record.tokens.len())));
+        ReplayOutcome { text, tokens, divergence }
+    });
+    (ai.model.seed, ai.model.max_chars, ai.config.sampler.ngram_max_chars, ai.config.model.draft, ai.config.model.speculative_k) = saved;
+    let _ = ai.use_model(&previous);
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::Memory;
+    use crate::model::Model;
+
+    #[test]
+    fn recorded_generations_replay_identically() {
+        let path = std::env::temp_dir().join(format!("shark_replay_{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut model = Model::zeroed();
+        model.lin2.bias.iter_mut().enumerate().for_each(|(i, b)| *b = (i % 5) as f32);
+        let ai = AI::builder().model(model).memory(Memory::in_memory()).no_ngram().knowledge(Default::default()).build();
+        assert!(ai.is_ok());
+        let Ok(mut ai) = ai else { return };
+        ai.replay = Some(ReplayLog::new(&path));
+
+        let (text, _) = ai.generate("акула");
+        let log = ReplayLog::new(&path);
+        let records = log.load();
+        let _ = std::fs::remove_file(&path);
+        assert!(records.is_ok());
+        let Ok(records) = records else { return };
+        assert_eq!(records.iter().map(|r| (r.text.as_str(), r.tokens.len())).collect::<Vec<_>>(), vec![(text.as_str(), text.len())]);
+        let Some(record) = records.first() else { return };
+
+        ai.model.seed += 1;
+        let outcome = replay(&mut ai, record, &path);
+        assert!(matches!(&outcome, Ok(o) if o.text == text && o.divergence.is_none()));
+        let mut tampered = record.clone();
+        tampered.tokens.push(0);
+        assert!(matches!(replay(&mut ai, &tampered, &path), Ok(o) if o.divergence == Some(text.len())));
+        tampered.context.push('!');
+        assert!(matches!(replay(&mut ai, &tampered, &path), Err(SharkError::Corrupt { .. })));
+    }
+}
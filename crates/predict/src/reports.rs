@@ -0,0 +1,418 @@
+#![forbid(unsafe_code)]
+
+//! Markdown reports in one output directory.
+//!
+//! A `Report` is built from typed blocks — headings, text, lists, metrics,
+//! tables and line charts — and rendered to Markdown; charts are written as
+//! SVG files next to the report and linked from it. `to_html` renders a
+//! standalone page with the charts inline instead, and `to_markdown_inline`
+//! a single Markdown file with the charts as `data:` URIs. `write` replaces a
+//! report (`problems_report.md`, `training_report.md`), `append` adds a
+//! dated section to a log (`knowledge_log.md`, `AI_SCIENTIST_REPORT.md`).
+//! Both go to `output_dir()`: `[reports] dir` in `shark.toml` (default
+//! `docs`), applied by the front-ends with `configure`.
+
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+
+use crate::config::Config;
+
+/// Output directory used until `set_output_dir` is called.
+pub const DEFAULT_DIR: &str = "docs";
+
+/// Chart size in pixels.
+const CHART_WIDTH: f64 = 640.0;
+const CHART_HEIGHT: f64 = 320.0;
+const CHART_MARGIN: f64 = 48.0;
+
+/// Series colors, in order.
+const PALETTE: [&str; 6] = ["#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b"];
+
+fn dir_slot() -> &'static RwLock<PathBuf> {
+    static DIR: OnceLock<RwLock<PathBuf>> = OnceLock::new();
+    DIR.get_or_init(|| RwLock::new(PathBuf::from(DEFAULT_DIR)))
+}
+
+/// Directory reports are written to.
+pub fn output_dir() -> PathBuf {
+    dir_slot().read().map(|d| d.clone()).unwrap_or_else(|_| PathBuf::from(DEFAULT_DIR))
+}
+
+/// Write reports to `dir` from now on.
+pub fn set_output_dir(dir: impl Into<PathBuf>) {
+    if let Ok(mut slot) = dir_slot().write() {
+        *slot = dir.into();
+    }
+}
+
+/// Apply `reports.dir` of `config`.
+pub fn configure(config: &Config) {
+    set_output_dir(&config.reports.dir);
+}
+
+/// Path of the report file `name` (e.g. `problems_report.md`) in `output_dir()`.
+pub fn path(name: &str) -> PathBuf {
+    output_dir().join(name)
+}
+
+/// A Markdown table.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Table {
+    /// column names
+    pub headers: Vec<String>,
+    /// rows of cells (shorter rows are padded)
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Table with these columns and no rows.
+    pub fn new<S: ToString>(headers: &[S]) -> Self {
+        Self { headers: headers.iter().map(ToString::to_string).collect(), rows: Vec::new() }
+    }
+
+    /// Add a row.
+    pub fn row<S: ToString>(mut self, cells: &[S]) -> Self {
+        self.push(cells);
+        self
+    }
+
+    /// Add a row in place.
+    pub fn push<S: ToString>(&mut self, cells: &[S]) {
+        self.rows.push(cells.iter().map(ToString::to_string).collect());
+    }
+
+    fn to_markdown(&self) -> String {
+        let cell = |s: &str| s.replace('|', "\\|").replace('\n', " ");
+        let line = |cells: &[String]| {
+            let padded = (0..self.headers.len()).map(|i| cells.get(i).map(|c| cell(c)).unwrap_or_default());
+            format!("| {} |\n", padded.collect::<Vec<_>>().join(" | "))
+        };
+        let mut md = line(&self.headers);
+        md.push_str(&format!("|{}\n", "---|".repeat(self.headers.len())));
+        for row in &self.rows {
+            md.push_str(&line(row));
+        }
+        md
+    }
+
+    fn to_html(&self) -> String {
+        let row = |tag: &str, cells: &[String]| {
+            let cells = (0..self.headers.len()).map(|i| format!("<{tag}>{}</{tag}>", escape(cells.get(i).map(String::as_str).unwrap_or(""))));
+            format!("<tr>{}</tr>\n", cells.collect::<String>())
+        };
+        let mut html = format!("<table>\n{}", row("th", &self.headers));
+        for cells in &self.rows {
+            html.push_str(&row("td", cells));
+        }
+        html.push_str("</table>\n");
+        html
+    }
+}
+
+/// A line chart rendered as SVG.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LineChart {
+    /// title above the plot (also the image alt text)
+    pub title: String,
+    /// named series of (x, y) points
+    pub series: Vec<(String, Vec<(f64, f64)>)>,
+}
+
+impl LineChart {
+    /// Chart without series.
+    pub fn new(title: impl Into<String>) -> Self {
+        Self { title: title.into(), series: Vec::new() }
+    }
+
+    /// Add a series; non-finite points are skipped.
+    pub fn series(mut self, name: impl Into<String>, points: impl IntoIterator<Item = (f64, f64)>) -> Self {
+        self.series.push((name.into(), points.into_iter().filter(|(x, y)| x.is_finite() && y.is_finite()).collect()));
+        self
+    }
+
+    /// SVG document: axes with the value ranges, one polyline and legend entry per series.
+    pub fn to_svg(&self) -> String {
+        let points = self.series.iter().flat_map(|(_, p)| p.iter());
+        let (mut x0, mut x1, mut y0, mut y1) = (f64::INFINITY, f64::NEG_INFINITY, f64::INFINITY, f64::NEG_INFINITY);
+        for (x, y) in points {
+            (x0, x1, y0, y1) = (x0.min(*x), x1.max(*x), y0.min(*y), y1.max(*y));
+        }
+        if x0 > x1 {
+            (x0, x1, y0, y1) = (0.0, 1.0, 0.0, 1.0);
+        }
+        let span = |a: f64, b: f64| if b > a { b - a } else { 1.0 };
+        let (w, h, m) = (CHART_WIDTH, CHART_HEIGHT, CHART_MARGIN);
+        let px = |x: f64| m + (x - x0) / span(x0, x1) * (w - 2.0 * m);
+        let py = |y: f64| h - m - (y - y0) / span(y0, y1) * (h - 2.0 * m);
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\" font-size=\"11\">\n"
+        );
+        svg.push_str(&format!("<rect width=\"{w}\" height=\"{h}\" fill=\"white\"/>\n"));
+        svg.push_str(&format!("<text x=\"{}\" y=\"20\" text-anchor=\"middle\" font-size=\"14\">{}</text>\n", w / 2.0, escape(&self.title)));
+        svg.push_str(&format!(
+            "<path d=\"M{m} {top} V{bottom} H{right}\" stroke=\"#444\" fill=\"none\"/>\n",
+            top = m,
+            bottom = h - m,
+            right = w - m
+        ));
+        for (value, x, y, anchor) in [
+            (y1, m - 4.0, m + 4.0, "end"),
+            (y0, m - 4.0, h - m, "end"),
+            (x0, m, h - m + 14.0, "start"),
+            (x1, w - m, h - m + 14.0, "end"),
+        ] {
+            svg.push_str(&format!("<text x=\"{x}\" y=\"{y}\" text-anchor=\"{anchor}\">{}</text>\n", short(value)));
+        }
+        for (i, (name, points)) in self.series.iter().enumerate() {
+            let color = PALETTE.get(i % PALETTE.len()).copied().unwrap_or("#000");
+            let coords: Vec<String> = points.iter().map(|(x, y)| format!("{:.1},{:.1}", px(*x), py(*y))).collect();
+            svg.push_str(&format!("<polyline points=\"{}\" stroke=\"{color}\" stroke-width=\"1.5\" fill=\"none\"/>\n", coords.join(" ")));
+            let ly = m + 14.0 * i as f64;
+            svg.push_str(&format!("<rect x=\"{}\" y=\"{}\" width=\"10\" height=\"3\" fill=\"{color}\"/>\n", w - m - 110.0, ly - 4.0));
+            svg.push_str(&format!("<text x=\"{}\" y=\"{ly}\">{}</text>\n", w - m - 96.0, escape(name)));
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+/// Axis label: integers without decimals, other values with up to four significant digits.
+fn short(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e9 { format!("{}", value as i64) } else { format!("{:.4}", value) }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// `svg` as a `data:image/svg+xml` URI, percent-encoding everything but
+/// ASCII letters, digits and `-._~`.
+fn svg_data_uri(svg: &str) -> String {
+    let mut uri = String::from("data:image/svg+xml,");
+    for byte in svg.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            uri.push(char::from(byte));
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    uri
+}
+
+/// One part of a report.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    /// `## heading`
+    Heading(String),
+    /// paragraph
+    Text(String),
+    /// bullet list
+    List(Vec<String>),
+    /// `- name: value` lines
+    Metrics(Vec<(String, String)>),
+    /// table
+    Table(Table),
+    /// SVG chart, linked as an image
+    Chart(LineChart),
+}
+
+/// A report: a title and blocks, rendered in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    /// `#` title (`###` section title when appended to a log)
+    pub title: String,
+    /// content
+    pub blocks: Vec<Block>,
+}
+
+impl Report {
+    /// Empty report.
+    pub fn new(title: impl Into<String>) -> Self {
+        Self { title: title.into(), blocks: Vec::new() }
+    }
+
+    /// Add a block.
+    pub fn block(mut self, block: Block) -> Self {
+        self.blocks.push(block);
+        self
+    }
+
+    /// Add a `##` heading.
+    pub fn heading(self, text: impl Into<String>) -> Self {
+        self.block(Block::Heading(text.into()))
+    }
+
+    /// Add a paragraph.
+    pub fn text(self, text: impl Into<String>) -> Self {
+        self.block(Block::Text(text.into()))
+    }
+
+    /// Add a bullet list.
+    pub fn list<S: ToString>(self, items: impl IntoIterator<Item = S>) -> Self {
+        self.block(Block::List(items.into_iter().map(|s| s.to_string()).collect()))
+    }
+
+    /// Add a metric line; consecutive metrics form one block.
+    pub fn metric(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        let entry = (name.into(), value.to_string());
+        match self.blocks.last_mut() {
+            Some(Block::Metrics(metrics)) => metrics.push(entry),
+            _ => self.blocks.push(Block::Metrics(vec![entry])),
+        }
+        self
+    }
+
+    /// Add a table.
+    pub fn table(self, table: Table) -> Self {
+        self.block(Block::Table(table))
+    }
+
+    /// Add a chart.
+    pub fn chart(self, chart: LineChart) -> Self {
+        self.block(Block::Chart(chart))
+    }
+
+    /// Markdown with the title at `level` (`#` count); chart `n` (0-based) is
+    /// linked as `chart_file(n)`.
+    pub fn to_markdown_with(&self, level: usize, chart_file: impl Fn(usize) -> String) -> String {
+        let mut md = format!("{} {}\n", "#".repeat(level), self.title);
+        let mut charts = 0;
+        for block in &self.blocks {
+            md.push('\n');
+            match block {
+                Block::Heading(text) => md.push_str(&format!("{} {}\n", "#".repeat(level + 1), text)),
+                Block::Text(text) => md.push_str(&format!("{}\n", text)),
+                Block::List(items) => items.iter().for_each(|i| md.push_str(&format!("- {}\n", i))),
+                Block::Metrics(metrics) => metrics.iter().for_each(|(k, v)| md.push_str(&format!("- {}: {}\n", k, v))),
+                Block::Table(table) => md.push_str(&table.to_markdown()),
+                Block::Chart(chart) => {
+                    md.push_str(&format!("![{}]({})\n", chart.title, chart_file(charts)));
+                    charts += 1;
+                }
+            }
+        }
+        md
+    }
+
+    /// Markdown with a `#` title, charts linked as `chart_<n>.svg`.
+    pub fn to_markdown(&self) -> String {
+        self.to_markdown_with(1, |n| format!("chart_{}.svg", n))
+    }
+
+    /// Markdown with a `#` title and every chart embedded as a `data:` URI,
+    /// so the file needs nothing next to it.
+    pub fn to_markdown_inline(&self) -> String {
+        let uris: Vec<String> = self.charts().map(|chart| svg_data_uri(&chart.to_svg())).collect();
+        self.to_markdown_with(1, |n| uris.get(n).cloned().unwrap_or_default())
+    }
+
+    fn charts(&self) -> impl Iterator<Item = &LineChart> {
+        self.blocks.iter().filter_map(|b| if let Block::Chart(c) = b { Some(c) } else { None })
+    }
+
+    /// Standalone HTML page: `<h1>` title, blocks in order, charts as inline SVG.
+    pub fn to_html(&self) -> String {
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n\
+             <style>body{{font-family:sans-serif}}table{{border-collapse:collapse}}td,th{{border:1px solid #ccc;padding:2px 8px}}</style>\n\
+             </head>\n<body>\n<h1>{0}</h1>\n",
+            escape(&self.title)
+        );
+        let items = |items: Vec<String>| format!("<ul>\n{}</ul>\n", items.iter().map(|i| format!("<li>{}</li>\n", i)).collect::<String>());
+        for block in &self.blocks {
+            match block {
+                Block::Heading(text) => html.push_str(&format!("<h2>{}</h2>\n", escape(text))),
+                Block::Text(text) => html.push_str(&format!("<p>{}</p>\n", escape(text))),
+                Block::List(list) => html.push_str(&items(list.iter().map(|i| escape(i)).collect())),
+                Block::Metrics(metrics) => html.push_str(&items(metrics.iter().map(|(k, v)| format!("{}: {}", escape(k), escape(v))).collect())),
+                Block::Table(table) => html.push_str(&table.to_html()),
+                Block::Chart(chart) => html.push_str(&chart.to_svg()),
+            }
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    /// Write `to_html` as `name` (e.g. `tearsheet.html`) in `output_dir()`.
+    pub fn write_html(&self, name: &str) -> io::Result<PathBuf> {
+        let file = path(name);
+        std::fs::create_dir_all(output_dir())?;
+        std::fs::write(&file, self.to_html())?;
+        Ok(file)
+    }
+
+    /// Write the report to `md_path`, replacing it; chart `n` goes to
+    /// `<stem>_<n>.svg` in the same directory.
+    pub fn write_to(&self, md_path: &Path) -> io::Result<PathBuf> {
+        let stem = md_path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let chart_file = |n: usize| format!("{}_{}.svg", stem, n);
+        let dir = md_path.parent().unwrap_or(Path::new(""));
+        if !dir.as_os_str().is_empty() {
+            std::fs::create_dir_all(dir)?;
+        }
+        for (n, chart) in self.charts().enumerate() {
+            std::fs::write(dir.join(chart_file(n)), chart.to_svg())?;
+        }
+        std::fs::write(md_path, self.to_markdown_with(1, chart_file))?;
+        Ok(md_path.to_path_buf())
+    }
+
+    /// Write the report as `name` (e.g. `problems_report.md`) in `output_dir()`.
+    pub fn write(&self, name: &str) -> io::Result<PathBuf> {
+        self.write_to(&path(name))
+    }
+
+    /// Append the report as a `###` section to the log `name` in `output_dir()`;
+    /// its charts are written as `<stem>_<unix time>_<n>.svg`.
+    pub fn append(&self, name: &str) -> io::Result<PathBuf> {
+        let log = path(name);
+        let dir = output_dir();
+        std::fs::create_dir_all(&dir)?;
+        let stem = log.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let stamp = chrono::Utc::now().timestamp();
+        let chart_file = |n: usize| format!("{}_{}_{}.svg", stem, stamp, n);
+        for (n, chart) in self.charts().enumerate() {
+            std::fs::write(dir.join(chart_file(n)), chart.to_svg())?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&log)?;
+        write!(file, "\n{}", self.to_markdown_with(3, chart_file))?;
+        Ok(log)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_render_blocks_and_charts() {
+        let report = Report::new("Problems")
+            .metric("solved", "2/3")
+            .metric("categories", 1)
+            .table(Table::new(&["q", "a"]).row(&["1 | 1", "2"]))
+            .list(["x"])
+            .chart(LineChart::new("loss").series("train", [(1.0, 2.0), (2.0, 1.0), (3.0, f64::NAN)]));
+        let md = report.to_markdown();
+        assert_eq!(
+            md,
+            "# Problems\n\n- solved: 2/3\n- categories: 1\n\n| q | a |\n|---|---|\n| 1 \\| 1 | 2 |\n\n- x\n\n![loss](chart_0.svg)\n"
+        );
+
+        let dir = std::env::temp_dir().join(format!("shark_reports_{}", std::process::id()));
+        let written = report.write_to(&dir.join("problems.md"));
+        let svg = std::fs::read_to_string(dir.join("problems_0.svg")).unwrap_or_default();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(written.is_ok());
+        assert!(svg.starts_with("<svg") && svg.matches("<polyline").count() == 1, "{}", svg);
+        assert!(svg.contains("points=\"48.0,48.0 592.0,272.0\""), "{}", svg);
+
+        let html = report.to_html();
+        assert!(html.contains("<h1>Problems</h1>") && html.contains("<li>solved: 2/3</li>"), "{}", html);
+        assert!(html.contains("<tr><td>1 | 1</td><td>2</td></tr>") && html.contains("<polyline"), "{}", html);
+        let inline = report.to_markdown_inline();
+        assert!(inline.contains("![loss](data:image/svg+xml,%3Csvg%20") && !inline.contains("chart_0.svg"), "{}", inline);
+    }
+}
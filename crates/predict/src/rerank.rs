@@ -0,0 +1,184 @@
+#![forbid(unsafe_code)]
+
+//! Reranking of fuzzy knowledge matches.
+//!
+//! Trigram similarity alone lets questions that merely share letters through
+//! (`кошка` vs `окошко`). Candidates above the trigram threshold are scored
+//! again from three features: the trigram similarity, the overlap of whole
+//! words weighted by the word-frequency memory (rare words count more) and
+//! the cosine of their embeddings (`AI::embed`). The score is a weighted sum
+//! with weights fitted on labelled pairs (`Reranker::fit`); features that are
+//! unavailable (no embeddings, untrained model) are left out and the other
+//! weights rescaled, so the score stays comparable to the 0.3 threshold.
+//! For short strings the trigram similarity is raised by edit distance
+//! (`reasoning::combined_similarity`), so single-word typos still match.
+//! With a TF-IDF index (`Signals::tfidf`, `[pipeline] retrieval = "tfidf"`)
+//! its cosine takes the place of the trigram similarity, both for selecting
+//! the candidates and as the first feature.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::memory_freq::WordFrequencyStore;
+use crate::reasoning::combined_similarity;
+use crate::similarity;
+use crate::tfidf::TfIdfIndex;
+
+/// Minimum trigram (or TF-IDF) similarity of a candidate and minimum reranked score of a match.
+pub const THRESHOLD: f64 = 0.3;
+
+/// Signals of a candidate, each in `0..=1`.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub struct Features {
+    /// `combined_similarity` of input and stored question (trigrams, edit
+    /// distance for short ones), or their TF-IDF cosine when `Signals::tfidf`
+    /// is given
+    pub trigram: f64,
+    /// weighted Jaccard overlap of their words
+    pub words: f64,
+    /// cosine of their embeddings, `None` when either is a zero vector
+    pub embedding: Option<f64>,
+}
+
+/// Text embedding function (e.g. `AI::embed`).
+pub type Embedder<'a> = &'a dyn Fn(&str) -> Vec<f32>;
+
+/// Optional inputs of the word and embedding features.
+#[derive(Default, Clone, Copy)]
+pub struct Signals<'a> {
+    /// word counts; without them every word weighs 1
+    pub frequencies: Option<&'a WordFrequencyStore>,
+    /// text embedding
+    pub embed: Option<Embedder<'a>>,
+    /// index of the stored questions; replaces trigram similarity
+    pub tfidf: Option<&'a TfIdfIndex>,
+}
+
+impl Signals<'_> {
+    /// Surface similarity of `input` and `candidate`: TF-IDF cosine with an
+    /// index, else trigrams and, for short strings, edit distance
+    /// (`reasoning::combined_similarity`).
+    pub fn surface(&self, input: &str, candidate: &str) -> f64 {
+        self.tfidf.map_or_else(|| combined_similarity(input, candidate), |index| index.similarity(input, candidate))
+    }
+}
+
+/// Feature weights of the score.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Reranker {
+    /// weight of `Features::trigram`
+    pub trigram: f64,
+    /// weight of `Features::words`
+    pub words: f64,
+    /// weight of `Features::embedding`
+    pub embedding: f64,
+}
+
+impl Default for Reranker {
+    /// Trigram similarity first, then shared words, then embeddings.
+    fn default() -> Self {
+        Self { trigram: 0.5, words: 0.35, embedding: 0.15 }
+    }
+}
+
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).map(str::to_lowercase).collect()
+}
+
+/// Weight of a word: 1 without a frequency memory, else `1 / ln(e + count)`.
+fn word_weight(word: &str, frequencies: Option<&WordFrequencyStore>) -> f64 {
+    frequencies.map_or(1.0, |f| 1.0 / (std::f64::consts::E + f.get(word) as f64).ln())
+}
+
+impl Features {
+    /// Features of stored question `candidate` for `input`.
+    pub fn compute(input: &str, candidate: &str, signals: &Signals) -> Self {
+        let (a, b) = (words(input), words(candidate));
+        let weight = |ws: &mut dyn Iterator<Item = &String>| ws.map(|w| word_weight(w, signals.frequencies)).sum::<f64>();
+        let union = weight(&mut a.union(&b));
+        let words = if union > 0.0 { weight(&mut a.intersection(&b)) / union } else { 0.0 };
+        let embedding = signals.embed.and_then(|embed| {
+            let (x, y) = (embed(input), embed(candidate));
+            (similarity::norm(&x) > 0.0 && similarity::norm(&y) > 0.0).then(|| similarity::cosine(&x, &y).max(0.0) as f64)
+        });
+        Self { trigram: signals.surface(input, candidate), words, embedding }
+    }
+}
+
+/// A stored question kept by `Reranker::rank`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Candidate<'k> {
+    /// stored question
+    pub question: &'k String,
+    /// its answer
+    pub answer: &'k String,
+    /// `Reranker::score`
+    pub score: f64,
+}
+
+impl Reranker {
+    /// Weighted sum of the available features, weights rescaled to their sum.
+    pub fn score(&self, f: &Features) -> f64 {
+        let (mut sum, mut total) = (self.trigram * f.trigram + self.words * f.words, self.trigram + self.words);
+        if let Some(e) = f.embedding {
+            sum += self.embedding * e;
+            total += self.embedding;
+        }
+        if total > 0.0 { sum / total } else { 0.0 }
+    }
+
+    /// Stored questions with surface similarity (`Signals::surface`) above `THRESHOLD`, reranked:
+    /// those scoring above `THRESHOLD`, best first.
+    pub fn rank<'k>(&self, input: &str, knowledge: &'k HashMap<String, String>, signals: &Signals) -> Vec<Candidate<'k>> {
+        let mut ranked: Vec<Candidate> = knowledge
+            .iter()
+            .filter(|(q, _)| signals.surface(input, q) > THRESHOLD)
+            .map(|(question, answer)| Candidate { question, answer, score: self.score(&Features::compute(input, question, signals)) })
+            .filter(|c| c.score > THRESHOLD)
+            .collect();
+        ranked.sort_by(|x, y| y.score.total_cmp(&x.score).then_with(|| x.question.cmp(y.question)));
+        ranked
+    }
+
+    /// Fit the weights to labelled candidates (`true` for a correct match) by
+    /// gradient descent on the squared error of `score`; weights stay non-negative.
+    pub fn fit(&mut self, examples: &[(Features, bool)], epochs: usize, learning_rate: f64) {
+        for _ in 0..epochs {
+            for (f, relevant) in examples {
+                let score = self.score(f);
+                let total = self.trigram + self.words + if f.embedding.is_some() { self.embedding } else { 0.0 };
+                if total <= 0.0 {
+                    continue;
+                }
+                // d score / d w = (feature - score) / total
+                let step = learning_rate * (score - if *relevant { 1.0 } else { 0.0 }) / total;
+                self.trigram = (self.trigram - step * (f.trigram - score)).max(0.0);
+                self.words = (self.words - step * (f.words - score)).max(0.0);
+                if let Some(e) = f.embedding {
+                    self.embedding = (self.embedding - step * (e - score)).max(0.0);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_words_outrank_shared_letters() {
+        let knowledge: HashMap<String, String> =
+            [("что такое кошка", "животное"), ("что такое окошко", "часть окна")].iter().map(|(q, a)| (q.to_string(), a.to_string())).collect();
+        let ranked = Reranker::default().rank("кошка это что такое", &knowledge, &Signals::default());
+        assert_eq!(ranked.first().map(|c| c.question.as_str()), Some("что такое кошка"));
+
+        let (good, bad) = (Features::compute("кошка", "кошка", &Signals::default()), Features::compute("кошка", "окошко", &Signals::default()));
+        assert!(good.words == 1.0 && bad.words == 0.0 && bad.embedding.is_none());
+        let mut reranker = Reranker { trigram: 0.5, words: 0.5, embedding: 0.0 };
+        let before = reranker.score(&bad);
+        reranker.fit(&[(good, true), (bad, false)], 50, 0.5);
+        assert!(reranker.score(&bad) < before && reranker.score(&good) > 0.9);
+    }
+}
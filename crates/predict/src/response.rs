@@ -0,0 +1,75 @@
+#![forbid(unsafe_code)]
+
+//! `ChatResponse`: an answer together with where it came from and how sure
+//! the producer was, so front-ends do not have to guess.
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::rank::Ranking;
+
+/// Answer given instead of a reply below the confidence threshold
+/// (`pipeline.min_confidence`).
+pub const DONT_KNOW: &str = "Не знаю — недостаточно уверенности для ответа.";
+
+/// Which subsystem produced an answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    /// the knowledge base (exact or closest stored question)
+    Knowledge,
+    /// a solver: arithmetic, linear equations, the Reasoner, research, evaluation
+    Reasoner,
+    /// text generated by the model or the n-gram fallback
+    Model,
+}
+
+impl Source {
+    /// Lowercase name, as used in JSON output.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Source::Knowledge => "knowledge",
+            Source::Reasoner => "reasoner",
+            Source::Model => "model",
+        }
+    }
+}
+
+/// Result of `AI::chat`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatResponse {
+    /// answer text (raw model output for `Source::Model`)
+    pub text: String,
+    /// producer of the answer
+    pub source: Source,
+    /// 0..=1, calibrated (`calibration`): similarity of the matched question
+    /// for knowledge answers, 1 for verified solver results (0.5 unverified),
+    /// one minus the normalized entropy / log-perplexity for generated text
+    pub confidence: f32,
+    /// time spent producing the answer
+    pub latency: Duration,
+    /// characters in `text` (the models are character-level)
+    pub tokens: usize,
+    /// pipeline stage that answered (`""` when built outside a pipeline)
+    pub stage: &'static str,
+    /// explanation lines from the stage (may be empty)
+    pub reasoning: Vec<String>,
+    /// candidates and decision when the pipeline ranks answers (`pipeline.rank`)
+    pub ranking: Option<Ranking>,
+    /// the stage's reply was below `pipeline.min_confidence` and `text` is `DONT_KNOW`
+    pub abstained: bool,
+}
+
+impl ChatResponse {
+    /// Build a response timed from `started`.
+    pub fn new(text: String, source: Source, confidence: f32, started: Instant) -> Self {
+        let tokens = text.chars().count();
+        Self { text, source, confidence: confidence.clamp(0.0, 1.0), latency: started.elapsed(), tokens, stage: "", reasoning: Vec::new(), ranking: None, abstained: false }
+    }
+
+    /// Latency in milliseconds.
+    pub fn latency_ms(&self) -> f64 {
+        self.latency.as_secs_f64() * 1000.0
+    }
+}
@@ -6,11 +6,18 @@ use std::io::Write;
 use std::fs;
 use std::path::Path;
 
+use crate::reports;
+
+/// Журнал экспериментов `run_scientific_cycle` в каталоге отчётов.
+pub const SCIENTIST_REPORT: &str = "AI_SCIENTIST_REPORT.md";
+
 /// Простая экспериментальная подсистема "AI Scientist".
 ///
 /// Содержит две части:
-/// - `run_scientific_cycle` — простой гипотезогенератор и тестировщик (лог в `docs/`).
+/// - `run_scientific_cycle` — простой гипотезогенератор и тестировщик (лог в каталоге отчётов).
 /// - `evolve_symbolic` — эволюционный поиск символьных формул (символьная регрессия).
+/// - `evolve_islands` — тот же отбор и мутации на островах для любого `SearchSpace`
+///   (подбор параметров стратегий — `optimizer`).
 
 /// Результат проверки гипотезы (для простого цикла).
 #[derive(Debug)]
@@ -54,18 +61,14 @@ pub fn run_scientific_cycle() -> Vec<HypothesisResult> {
         results.push(HypothesisResult { name: name.to_string(), mse, accepted });
     }
 
-    // Логируем отчёт в docs/AI_SCIENTIST_REPORT.md, но не паниковать при ошибке записи
-    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open("docs/AI_SCIENTIST_REPORT.md") {
-        let now = chrono::Utc::now().to_rfc3339();
-        let _ = writeln!(file, "### Эксперимент от {now}\n");
-        for r in &results {
-            let _ = writeln!(file, "- {} → MSE={:.4} {}", r.name, r.mse, if r.accepted { "✅" } else { "❌" });
-        }
-    } else {
+    // Логируем отчёт в AI_SCIENTIST_REPORT.md, но не паниковать при ошибке записи
+    let now = chrono::Utc::now().to_rfc3339();
+    let lines: Vec<String> = results.iter().map(|r| format!("{} → MSE={:.4} {}", r.name, r.mse, if r.accepted { "✅" } else { "❌" })).collect();
+    if reports::Report::new(format!("Эксперимент от {now}")).list(&lines).append(SCIENTIST_REPORT).is_err() {
         // fallback: печатаем в stdout
-        println!("AI Scientist: не удалось открыть docs/AI_SCIENTIST_REPORT.md для записи");
-        for r in &results {
-            println!("- {} → MSE={:.4} {}", r.name, r.mse, if r.accepted { "✅" } else { "❌" });
+        println!("AI Scientist: не удалось открыть {} для записи", reports::path(SCIENTIST_REPORT).display());
+        for line in &lines {
+            println!("- {}", line);
         }
     }
 
@@ -185,7 +188,7 @@ fn mse(expr: &Expr, target: fn(f64)->f64, rng: &mut ChaCha8Rng, n: usize) -> f64
 }
 
 /// Турнирный отбор — возвращает ссылку на выбранного члена популяции
-fn tournament<'a>(pop: &'a [Expr], fits: &[f64], rng: &mut ChaCha8Rng, k: usize) -> &'a Expr {
+fn tournament<'a, T>(pop: &'a [T], fits: &[f64], rng: &mut ChaCha8Rng, k: usize) -> &'a T {
     // Инициализируем с случайного кандидата, затем проводим k-1 состязаний
     let mut best_idx = rng.gen_range(0..pop.len());
     let mut best_fit = fits[best_idx];
@@ -202,8 +205,57 @@ fn tournament<'a>(pop: &'a [Expr], fits: &[f64], rng: &mut ChaCha8Rng, k: usize)
 
 /// Эволюционный поиск формулы.
 ///
-/// Возвращает лучшую найденную формулу и её MSE на большой выборке.
+/// Возвращает лучшую найденную формулу и её MSE на большой выборке и
+/// сохраняет её в память учёного.
 pub fn evolve_symbolic(seed: u64, generations: usize, pop_size: usize) -> (Expr, f64) {
+    let (best_expr, final_fit) = evolve(seed, generations, pop_size, true);
+    record_discovery(seed, &best_expr, final_fit);
+    (best_expr, final_fit)
+}
+
+/// Сохранить открытие в память учёного (обе формы записи) и опубликовать
+/// `Event::DiscoveryMade`.
+fn record_discovery(seed: u64, expr: &Expr, mse: f64) {
+    let name = format!("evolve_{}_{:x}", seed, chrono::Utc::now().timestamp());
+    let formula = format!("{:?}", expr);
+    let _ = save_discovery(&name, &formula, mse);
+    // also write the simpler CSV record (formula,mse,curiosity,date)
+    log_discovery(expr, mse).ok();
+    crate::events::publish(crate::events::Event::DiscoveryMade { formula, mse });
+}
+
+/// Углублённый поиск от `top_n` самых любопытных формул памяти учёного:
+/// для каждой — `evolve_symbolic` без вывода в консоль, с seed из текста
+/// формулы (поиск стартует в той же области). Открытия сохраняются.
+///
+/// Возвращает `(исходная формула, curiosity, найденная формула, MSE)`.
+pub fn deepen_research(top_n: usize, generations: usize, pop_size: usize) -> Vec<(String, f64, Expr, f64)> {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut memory = load_science_memory();
+    memory.sort_by(|a, b| b.3.partial_cmp(&a.3).unwrap_or(std::cmp::Ordering::Equal));
+    memory
+        .into_iter()
+        .take(top_n)
+        .map(|(_name, formula, _mse, curiosity)| {
+            let mut hasher = DefaultHasher::new();
+            formula.hash(&mut hasher);
+            let seed = hasher.finish();
+            let (best, fit) = evolve(seed, generations, pop_size, false);
+            record_discovery(seed, &best, fit);
+            (formula, curiosity, best, fit)
+        })
+        .collect()
+}
+
+/// Тот же поиск, что `evolve_symbolic`, но без вывода в консоль и без записи
+/// в память учёного (для бенчмарков и проверок).
+pub fn search_symbolic(seed: u64, generations: usize, pop_size: usize) -> (Expr, f64) {
+    evolve(seed, generations, pop_size, false)
+}
+
+fn evolve(seed: u64, generations: usize, pop_size: usize, verbose: bool) -> (Expr, f64) {
     let mut rng = ChaCha8Rng::seed_from_u64(seed);
 
     // неизвестная "истинная" функция (сложно-нелинейная)
@@ -238,7 +290,7 @@ pub fn evolve_symbolic(seed: u64, generations: usize, pop_size: usize) -> (Expr,
         }
         pop = next;
 
-        if gen % 50 == 0 {
+        if verbose && gen % 50 == 0 {
             // периодическая перепроверка на большой выборке
             let check_fit = mse(&best_expr, target, &mut rng, 2000);
             // лёгкий лог в консоль
@@ -248,15 +300,110 @@ pub fn evolve_symbolic(seed: u64, generations: usize, pop_size: usize) -> (Expr,
 
     // финальная оценка на большой выборке
     let final_fit = mse(&best_expr, target, &mut rng, 5000);
+    (best_expr, final_fit)
+}
 
-    // Сохранить открытие в память ученого
-    let name = format!("evolve_{}_{:x}", seed, chrono::Utc::now().timestamp());
-    let formula = format!("{:?}", best_expr);
-    let _ = save_discovery(&name, &formula, final_fit);
-    // also write the simpler CSV record (formula,mse,curiosity,date)
-    log_discovery(&best_expr, final_fit).ok();
+/// Пространство поиска для `evolve_islands`: как создать, изменить и оценить особь.
+pub trait SearchSpace {
+    /// Особь (геном).
+    type Genome: Clone;
+    /// Случайная особь.
+    fn random(&self, rng: &mut ChaCha8Rng) -> Self::Genome;
+    /// Мутированная копия `genome`.
+    fn mutate(&self, genome: &Self::Genome, rng: &mut ChaCha8Rng) -> Self::Genome;
+    /// Стоимость особи: меньше — лучше (NaN считается бесконечностью).
+    fn cost(&self, genome: &Self::Genome) -> f64;
+}
 
-    (best_expr, final_fit)
+/// Параметры островной модели.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IslandConfig {
+    /// число островов (популяций, эволюционирующих отдельно)
+    pub islands: usize,
+    /// особей на острове
+    pub pop_size: usize,
+    /// поколений
+    pub generations: usize,
+    /// каждые сколько поколений лучшие особи переселяются на следующий остров (по кругу); 0 — без миграции
+    pub migration_interval: usize,
+    /// сколько лучших особей переселяется
+    pub migrants: usize,
+    /// размер турнира при отборе
+    pub tournament: usize,
+}
+
+impl Default for IslandConfig {
+    fn default() -> Self {
+        Self { islands: 4, pop_size: 16, generations: 30, migration_interval: 5, migrants: 2, tournament: 3 }
+    }
+}
+
+/// Островной эволюционный поиск: на каждом острове — элитизм, турнирный
+/// отбор и мутации, как в `evolve_symbolic`; раз в `migration_interval`
+/// поколений лучшие особи острова заменяют худшие на следующем. Детерминирован
+/// при одинаковом `seed`.
+///
+/// Возвращает лучшую найденную особь и её стоимость.
+pub fn evolve_islands<S: SearchSpace>(space: &S, seed: u64, cfg: IslandConfig) -> (S::Genome, f64) {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let (islands, pop_size) = (cfg.islands.max(1), cfg.pop_size.max(1));
+    let cost = |g: &S::Genome| {
+        let c = space.cost(g);
+        if c.is_nan() { f64::INFINITY } else { c }
+    };
+
+    let mut pops: Vec<Vec<S::Genome>> = (0..islands).map(|_| (0..pop_size).map(|_| space.random(&mut rng)).collect()).collect();
+    let mut best: Option<(S::Genome, f64)> = None;
+    for gen in 0..cfg.generations.max(1) {
+        // оценка: особи каждого острова по возрастанию стоимости
+        let mut ranked: Vec<Vec<(S::Genome, f64)>> = pops
+            .into_iter()
+            .map(|pop| {
+                let mut scored: Vec<(S::Genome, f64)> = pop.into_iter().map(|g| { let c = cost(&g); (g, c) }).collect();
+                scored.sort_by(|a, b| a.1.total_cmp(&b.1));
+                scored
+            })
+            .collect();
+        for leader in ranked.iter().filter_map(|r| r.first()) {
+            if best.as_ref().is_none_or(|(_, c)| leader.1 < *c) {
+                best = Some(leader.clone());
+            }
+        }
+
+        // миграция по кругу: лучшие острова i вместо худших острова i + 1
+        if cfg.migration_interval > 0 && islands > 1 && (gen + 1) % cfg.migration_interval == 0 {
+            let emigrants: Vec<Vec<(S::Genome, f64)>> = ranked.iter().map(|r| r.iter().take(cfg.migrants).cloned().collect()).collect();
+            for (i, group) in emigrants.into_iter().enumerate() {
+                if let Some(dest) = ranked.get_mut((i + 1) % islands) {
+                    dest.truncate(dest.len().saturating_sub(group.len()));
+                    dest.extend(group);
+                    dest.sort_by(|a, b| a.1.total_cmp(&b.1));
+                }
+            }
+        }
+
+        // следующее поколение: элита + потомки турнирного отбора
+        pops = ranked
+            .into_iter()
+            .map(|scored| {
+                let (pop, fits): (Vec<S::Genome>, Vec<f64>) = scored.into_iter().unzip();
+                let mut next: Vec<S::Genome> = pop.first().cloned().into_iter().collect();
+                while next.len() < pop_size {
+                    let parent = tournament(&pop, &fits, &mut rng, cfg.tournament.max(1));
+                    next.push(space.mutate(parent, &mut rng));
+                }
+                next
+            })
+            .collect();
+    }
+    match best {
+        Some(found) => found,
+        None => {
+            let g = space.random(&mut rng);
+            let c = cost(&g);
+            (g, c)
+        }
+    }
 }
 
 /// Вычислить критерий любознательности (curiosity) из MSE
@@ -1,63 +1,139 @@
+#![forbid(unsafe_code)]
+
+//! Self-repair: check that the critical modules of a source tree are present
+//! and non-empty, and write minimal stubs for the broken ones into a sandbox
+//! directory. The checked tree itself is never modified.
+//!
+//! Opt-in via `features.self_repair` in `shark.toml`; the sandbox lives under
+//! `data.state_dir` (`<state_dir>/self_repair`).
+
 use std::fs;
-use std::io::Write;
-use std::path::Path;
-
-/// Проверка наличия критических файлов и функций, восстановление при необходимости
-pub fn self_repair() {
-    let critical_files = vec![
-        ("core.rs", r#"pub fn softmax(logits: &mut [f32]) {}"#),
-        (
-            "memory.rs",
-            r#"pub fn load(path: &str) -> Self { Self::default() }"#,
-        ),
-        (
-            "model.rs",
-            r#"pub fn forward(&self, _input: &[f32]) -> Vec<f32> { vec![] }"#,
-        ),
-    ];
-
-    let base = "crates/predict/src";
-    let log_path = "docs/self_fix.log";
-
-    let mut log = String::from(format!(
-        "🧠 [Self-Repair {}]\n",
-        chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
-    ));
-
-    fs::create_dir_all("docs").ok();
-
-    for (file, stub) in critical_files {
-        let path = Path::new(base).join(file);
-
-        if !path.exists() {
-            log.push_str(&format!("⚠️  Файл {} отсутствует. Создаю...\n", file));
-            let mut f = fs::File::create(&path).unwrap();
-            writeln!(
-                f,
-                "#![forbid(unsafe_code)]\n// восстановлено автоматически\n{}",
-                stub
-            )
-            .ok();
-        } else {
-            let content = fs::read_to_string(&path).unwrap_or_default();
-            if !content.contains("pub fn") {
-                log.push_str(&format!(
-                    "⚠️  Файл {} пуст или повреждён. Восстанавливаю код...\n",
-                    file
-                ));
-                let mut f = fs::File::create(&path).unwrap();
-                writeln!(
-                    f,
-                    "#![forbid(unsafe_code)]\n// восстановлено автоматически\n{}",
-                    stub
-                )
-                .ok();
-            }
+use std::path::{Path, PathBuf};
+
+use crate::error::{Result, SharkError};
+
+/// Source tree checked by the chat front-end.
+pub const SOURCE_DIR: &str = "crates/predict/src";
+
+/// Log file written into the sandbox after each run.
+pub const LOG_FILE: &str = "self_fix.log";
+
+/// Critical modules with the minimal stub restored for each.
+const CRITICAL_FILES: [(&str, &str); 3] = [
+    ("core.rs", "pub fn softmax(logits: &mut [f32]) {}"),
+    ("memory.rs", "pub fn load(path: &str) -> Self { Self::default() }"),
+    ("model.rs", "pub fn forward(&self, _input: &[f32]) -> Vec<f32> { vec![] }"),
+];
+
+/// State of one critical module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleState {
+    /// present and contains at least one `pub fn`
+    Ok,
+    /// file does not exist
+    Missing,
+    /// file exists but is empty or has no `pub fn`
+    Broken,
+}
+
+/// Result of checking one module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleCheck {
+    /// file name relative to the source tree
+    pub file: String,
+    /// what was found
+    pub state: ModuleState,
+    /// where the stub was written (only for missing/broken modules)
+    pub restored_to: Option<PathBuf>,
+}
+
+/// Outcome of a self-repair run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepairReport {
+    /// source tree that was checked
+    pub source: PathBuf,
+    /// sandbox the stubs and the log were written to
+    pub sandbox: PathBuf,
+    /// one entry per critical module
+    pub modules: Vec<ModuleCheck>,
+}
+
+impl RepairReport {
+    /// Whether every critical module was found intact.
+    pub fn is_healthy(&self) -> bool {
+        self.modules.iter().all(|m| m.state == ModuleState::Ok)
+    }
+
+    /// Modules that needed a stub.
+    pub fn restored(&self) -> impl Iterator<Item = &ModuleCheck> {
+        self.modules.iter().filter(|m| m.state != ModuleState::Ok)
+    }
+
+    /// Human-readable log (also written to `<sandbox>/self_fix.log`).
+    pub fn render(&self) -> String {
+        let mut log = format!(
+            "🧠 [Self-Repair {}] {}\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+            self.source.display()
+        );
+        for m in self.restored() {
+            let what = if m.state == ModuleState::Missing { "отсутствует" } else { "пуст или повреждён" };
+            let dest = m.restored_to.as_deref().map(|p| p.display().to_string()).unwrap_or_default();
+            log.push_str(&format!("⚠️  Файл {} {}. Заглушка: {}\n", m.file, what, dest));
+        }
+        if self.is_healthy() {
+            log.push_str("✅ Проверка завершена. Все критические модули на месте.\n");
         }
+        log
     }
+}
 
-    log.push_str("✅ Проверка завершена. Все критические модули на месте.\n");
+/// Check the critical modules of `source`; stubs for missing or broken ones
+/// and the run log go to `sandbox`.
+pub fn self_repair(source: &Path, sandbox: &Path) -> Result<RepairReport> {
+    fs::create_dir_all(sandbox).map_err(|e| SharkError::io(sandbox, e))?;
+    let mut modules = Vec::with_capacity(CRITICAL_FILES.len());
+    for (file, stub) in CRITICAL_FILES {
+        let state = match fs::read_to_string(source.join(file)) {
+            Ok(content) if content.contains("pub fn") => ModuleState::Ok,
+            Ok(_) => ModuleState::Broken,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => ModuleState::Missing,
+            Err(e) => return Err(SharkError::io(source.join(file), e)),
+        };
+        let restored_to = if state == ModuleState::Ok {
+            None
+        } else {
+            let dest = sandbox.join(file);
+            fs::write(&dest, format!("#![forbid(unsafe_code)]\n// восстановлено автоматически\n{}\n", stub))
+                .map_err(|e| SharkError::io(&dest, e))?;
+            Some(dest)
+        };
+        modules.push(ModuleCheck { file: file.to_string(), state, restored_to });
+    }
+    let report = RepairReport { source: source.to_path_buf(), sandbox: sandbox.to_path_buf(), modules };
+    let log_path = sandbox.join(LOG_FILE);
+    fs::write(&log_path, report.render()).map_err(|e| SharkError::io(&log_path, e))?;
+    Ok(report)
+}
 
-    fs::write(log_path, &log).ok();
-    println!("{}", log);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stubs_go_to_the_sandbox_and_the_source_is_untouched() {
+        let dir = std::env::temp_dir().join(format!("shark_repair_{}", std::process::id()));
+        let (source, sandbox) = (dir.join("src"), dir.join("state"));
+        let _ = fs::create_dir_all(&source);
+        let _ = fs::write(source.join("core.rs"), "pub fn softmax() {}");
+        let _ = fs::write(source.join("memory.rs"), "");
+
+        let report = self_repair(&source, &sandbox);
+        let states: Vec<ModuleState> = report.iter().flat_map(|r| r.modules.iter().map(|m| m.state)).collect();
+        assert_eq!(states, vec![ModuleState::Ok, ModuleState::Broken, ModuleState::Missing]);
+        assert_eq!(fs::read_to_string(source.join("memory.rs")).ok().as_deref(), Some(""));
+        assert!(!source.join("model.rs").exists());
+        assert!(sandbox.join("model.rs").exists() && sandbox.join(LOG_FILE).exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
@@ -1,17 +1,21 @@
 use std::collections::HashMap;
 
+use crate::intent::Intent;
+
 /// Interpret semantic meaning of questions and provide structured responses.
 pub fn interpret_question(input: &str, knowledge: &HashMap<String, String>) -> Option<String> {
     let normalized = input.to_lowercase().trim().to_string();
 
-    // Greetings
-    if normalized.contains("привет") || normalized.contains("hello") || normalized == "hi" {
+    let intent = crate::intent::classify(&normalized).confident();
+
+    // Greetings and other small talk
+    if intent == Some(Intent::SmallTalk) {
         return Some("Привет! Я Shark-Core. Задайте вопрос, например: 'что такое алгоритм?' или 'почему буквы важны?'.".to_string());
     }
 
     // Math expressions
-    if normalized.chars().any(|c| c.is_digit(10)) && (normalized.contains('+') || normalized.contains('-') || normalized.contains('*') || normalized.contains('/')) {
-        if let Ok(result) = meval::eval_str(&normalized.replace("=", "").replace("?", "")) {
+    if let (Some(Intent::Math), Some(expression)) = (intent, crate::extract::extract(&normalized).expression) {
+        if let Ok(result) = meval::eval_str(expression.trim_end_matches('=')) {
             return Some(format!("Результат: {:.2}", result));
         }
     }
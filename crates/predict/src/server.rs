@@ -0,0 +1,469 @@
+#![forbid(unsafe_code)]
+
+//! HTTP API of the `server` binary (tiny_http), usable as a library so it can
+//! be started in-process, e.g. by tests on a free port (`spawn("127.0.0.1:0", ..)`).
+//!
+//! Routes: `GET /health`, `GET /jobs`, `GET /metrics`, `POST /chat`,
+//! `POST /embeddings`, `POST /evaluate`, `GET /evaluate[?dataset=name]`,
+//! `POST /export` (renders the conversation in the body) and,
+//! with `trading`, `POST /backtest` and `POST /indicators/{name}`. Every
+//! request is answered on its own thread; the `AI` is shared behind a mutex
+//! (so chat waits while `POST /evaluate` runs a dataset). Replies are not
+//! streamed, and there are no sessions or authentication: every request stands
+//! alone and the server is meant for a trusted network.
+
+use std::io::Cursor;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Method, Request, Response, Server, StatusCode};
+
+use crate::evaluation;
+use crate::export::{self, Format, Session};
+use crate::jobs::Scheduler;
+use crate::model_registry;
+use crate::moderation;
+use crate::rank::Ranking;
+use crate::{Source, AI};
+
+/// Body of `POST /chat`.
+#[derive(Deserialize)]
+struct ChatRequest {
+    prompt: String,
+    /// answer with a `StructuredAnswer` object (JSON mode)
+    #[serde(default)]
+    structured: bool,
+    /// generator for this request (`/model use` names); the selection is restored afterwards
+    #[serde(default)]
+    model: Option<String>,
+}
+
+/// Reply of `POST /chat`.
+#[derive(Serialize)]
+struct ChatReply {
+    reply: String,
+    source: Source,
+    confidence: f32,
+    latency_ms: f64,
+    tokens: usize,
+    abstained: bool,
+    /// the prompt or the answer was blocked by `[moderation]`
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    moderated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ranking: Option<Ranking>,
+}
+
+/// Body of `POST /evaluate`.
+#[derive(Deserialize)]
+struct EvaluateRequest {
+    /// dataset name (`evaluation::dataset_path`)
+    dataset: String,
+}
+
+/// Body of `POST /export`: a `Session` and the format to render it in.
+#[derive(Deserialize)]
+struct ExportRequest {
+    #[serde(default)]
+    format: Format,
+    #[serde(flatten)]
+    session: Session,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EmbeddingInput {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Body of `POST /embeddings`.
+#[derive(Deserialize)]
+struct EmbeddingsRequest {
+    /// text or list of texts
+    input: EmbeddingInput,
+    /// char model to embed with (`/model use` names); the selection is restored afterwards
+    #[serde(default)]
+    model: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Embedding {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// Reply of `POST /embeddings`.
+#[derive(Serialize)]
+struct EmbeddingsResponse {
+    model: String,
+    dim: usize,
+    data: Vec<Embedding>,
+}
+
+type HttpResponse = Response<Cursor<Vec<u8>>>;
+
+fn with_header(mut response: HttpResponse, name: &str, value: &str) -> HttpResponse {
+    if let Ok(header) = Header::from_bytes(name.as_bytes(), value.as_bytes()) {
+        response.add_header(header);
+    }
+    response
+}
+
+fn text(status: u16, body: impl Into<String>) -> HttpResponse {
+    Response::from_string(body).with_status_code(StatusCode(status))
+}
+
+fn json(body: &impl Serialize) -> HttpResponse {
+    match serde_json::to_string(body) {
+        Ok(body) => with_header(Response::from_string(body), "Content-Type", "application/json"),
+        Err(e) => text(500, e.to_string()),
+    }
+}
+
+/// Embed the request's texts with `Model::embed`.
+fn embeddings(ai: &mut AI, request: EmbeddingsRequest) -> Result<EmbeddingsResponse, String> {
+    let texts = match request.input {
+        EmbeddingInput::One(text) => vec![text],
+        EmbeddingInput::Many(texts) => texts,
+    };
+    let previous = ai.models.active().unwrap_or(model_registry::DEFAULT).to_string();
+    if let Some(name) = &request.model {
+        ai.use_model(name).map_err(|e| e.to_string())?;
+    }
+    let model = ai.models.active().unwrap_or(model_registry::DEFAULT).to_string();
+    let data: Vec<Embedding> = texts.iter().enumerate().map(|(index, text)| Embedding { index, embedding: ai.embed(text) }).collect();
+    if request.model.is_some() {
+        let _ = ai.use_model(&previous);
+    }
+    Ok(EmbeddingsResponse { model, dim: data.first().map(|e| e.embedding.len()).unwrap_or(0), data })
+}
+
+/// Reply of `POST /backtest` or `POST /indicators/{name}` (`quant_api`);
+/// None without the `trading` feature.
+#[cfg(feature = "trading")]
+fn quant(ai: &Mutex<AI>, path: &str, body: &str) -> Option<Result<serde_json::Value, String>> {
+    Some(match path.strip_prefix("/indicators/") {
+        Some(name) => crate::quant_api::indicator(name, body),
+        None => match ai.lock() {
+            Ok(ai) => crate::quant_api::backtest(body, ai.config.backtest_seed()),
+            Err(_) => Err("AI unavailable".to_string()),
+        },
+    })
+}
+
+#[cfg(not(feature = "trading"))]
+fn quant(_ai: &Mutex<AI>, _path: &str, _body: &str) -> Option<Result<serde_json::Value, String>> {
+    None
+}
+
+/// `X-Shark-Profile` header with the timings of the generation behind a model answer.
+#[cfg(feature = "profile")]
+fn profile_header(ai: &AI, source: Source) -> Option<String> {
+    ai.last_profile().filter(|_| source == Source::Model).map(|profile| profile.header_value())
+}
+
+#[cfg(not(feature = "profile"))]
+fn profile_header(_ai: &AI, _source: Source) -> Option<String> {
+    None
+}
+
+/// `POST /chat`: the answer of `AI::chat` (or `chat_structured`) with the
+/// generator of `model` selected for this request.
+fn chat(ai: &Mutex<AI>, jobs: &Scheduler, body: &str) -> HttpResponse {
+    jobs.touch();
+    let Ok(request) = serde_json::from_str::<ChatRequest>(body) else { return text(400, "Bad Request") };
+    let Ok(mut ai) = ai.lock() else { return text(500, "AI unavailable") };
+    let previous = ai.models.active().unwrap_or(model_registry::DEFAULT).to_string();
+    if let Some(name) = &request.model {
+        if let Err(e) = ai.use_model(name) {
+            return text(400, e.to_string());
+        }
+    }
+    let response = if request.structured {
+        json(&ai.chat_structured(&request.prompt))
+    } else {
+        let reply = ai.chat(&request.prompt);
+        let profile = profile_header(&ai, reply.source);
+        let response = json(&ChatReply {
+            latency_ms: reply.latency_ms(),
+            reply: reply.text,
+            source: reply.source,
+            confidence: reply.confidence,
+            tokens: reply.tokens,
+            abstained: reply.abstained,
+            moderated: reply.stage == moderation::STAGE,
+            ranking: reply.ranking,
+        });
+        match profile {
+            #[cfg(feature = "profile")]
+            Some(value) => with_header(response, crate::profile::HEADER, &value),
+            _ => response,
+        }
+    };
+    if request.model.is_some() {
+        let _ = ai.use_model(&previous);
+    }
+    response
+}
+
+/// Recorded evaluations, filtered by the `dataset` query parameter if given.
+fn evaluations(ai: &Mutex<AI>, query: &str) -> HttpResponse {
+    let dataset = query.split('&').find_map(|pair| pair.strip_prefix("dataset="));
+    match ai.lock() {
+        Ok(ai) => json(&evaluation::history(&ai.config, dataset)),
+        Err(_) => text(500, "AI unavailable"),
+    }
+}
+
+/// Response to `method url` with request `body`.
+pub fn route(ai: &Mutex<AI>, jobs: &Scheduler, method: &Method, url: &str, body: &str) -> HttpResponse {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    match (method, path) {
+        (Method::Get, "/health") => Response::from_string("OK"),
+        (Method::Get, "/jobs") => json(&jobs.status()),
+        (Method::Get, "/metrics") => match ai.lock() {
+            Ok(ai) => json(&serde_json::json!({ "cache": ai.pipeline.cache_stats(), "speculative": ai.speculative_stats() })),
+            Err(_) => text(500, "AI unavailable"),
+        },
+        (Method::Post, "/chat") => chat(ai, jobs, body),
+        (Method::Post, "/embeddings") => {
+            let result = match (serde_json::from_str::<EmbeddingsRequest>(body), ai.lock()) {
+                (Ok(request), Ok(mut ai)) => embeddings(&mut ai, request),
+                _ => Err("Bad Request".to_string()),
+            };
+            match result {
+                Ok(reply) => json(&reply),
+                Err(e) => text(400, e),
+            }
+        }
+        (Method::Post, "/evaluate") => {
+            let Ok(request) = serde_json::from_str::<EvaluateRequest>(body) else { return text(400, "Bad Request") };
+            let Ok(mut ai) = ai.lock() else { return text(500, "AI unavailable") };
+            match evaluation::run(&mut ai, &request.dataset) {
+                Ok(outcome) => json(&outcome),
+                Err(e) => text(400, e),
+            }
+        }
+        (Method::Get, "/evaluate") => evaluations(ai, query),
+        (Method::Post, "/export") => match serde_json::from_str::<ExportRequest>(body) {
+            Ok(request) => with_header(
+                Response::from_string(export::export_transcript(&request.session, request.format)),
+                "Content-Type",
+                request.format.content_type(),
+            ),
+            Err(_) => text(400, "Bad Request"),
+        },
+        (Method::Post, _) if path == "/backtest" || path.starts_with("/indicators/") => match quant(ai, path, body) {
+            Some(Ok(reply)) => json(&reply),
+            Some(Err(e)) => text(400, e),
+            None => text(404, "Not Found"),
+        },
+        _ => text(404, "Not Found"),
+    }
+}
+
+/// Answer one request (CORS open to every origin).
+pub fn handle(mut request: Request, ai: &Mutex<AI>, jobs: &Scheduler) {
+    let mut body = String::new();
+    let response = match request.as_reader().read_to_string(&mut body) {
+        Ok(_) => route(ai, jobs, request.method(), request.url(), &body),
+        Err(_) => text(400, "Bad Request"),
+    };
+    let _ = request.respond(with_header(response, "Access-Control-Allow-Origin", "*"));
+}
+
+/// Listen on `addr` (`host:port`; port 0 picks a free one).
+pub fn bind(addr: &str) -> std::io::Result<Server> {
+    Server::http(addr).map_err(|e| std::io::Error::other(format!("server bind error: {}", e)))
+}
+
+/// Answer the requests of `server`, each on its own thread, until it is unblocked.
+pub fn serve(server: &Server, ai: Arc<Mutex<AI>>, jobs: Arc<Scheduler>) {
+    for request in server.incoming_requests() {
+        let (ai, jobs) = (Arc::clone(&ai), Arc::clone(&jobs));
+        thread::spawn(move || handle(request, &ai, &jobs));
+    }
+}
+
+/// Server running on a background thread (`spawn`); stops when dropped.
+pub struct Running {
+    addr: SocketAddr,
+    server: Arc<Server>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Running {
+    /// Address the server listens on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Serve `ai` on `addr` from a background thread.
+pub fn spawn(addr: &str, ai: AI, jobs: Scheduler) -> std::io::Result<Running> {
+    let server = Arc::new(bind(addr)?);
+    let addr = server.server_addr().to_ip().ok_or_else(|| std::io::Error::other("not an IP listener"))?;
+    let (ai, jobs) = (Arc::new(Mutex::new(ai)), Arc::new(jobs));
+    let serving = Arc::clone(&server);
+    let thread = thread::spawn(move || serve(&serving, ai, jobs));
+    Ok(Running { addr, server, thread: Some(thread) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testkit::{self, FakeModel};
+    use serde_json::Value;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    /// Status and body of a raw HTTP/1.1 request.
+    fn request(addr: SocketAddr, method: &str, path: &str, body: &str) -> Option<(u16, String)> {
+        let mut stream = TcpStream::connect(addr).ok()?;
+        let head = format!("{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", method, path, body.len());
+        stream.write_all(head.as_bytes()).ok()?;
+        stream.write_all(body.as_bytes()).ok()?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).ok()?;
+        let status = response.split(' ').nth(1)?.parse().ok()?;
+        let (_, body) = response.split_once("\r\n\r\n")?;
+        Some((status, body.to_string()))
+    }
+
+    fn json_of(reply: Option<(u16, String)>) -> Option<Value> {
+        reply.filter(|(status, _)| *status == 200).and_then(|(_, body)| serde_json::from_str(&body).ok())
+    }
+
+    /// A server for a testkit AI that knows "акула", with `data.dir` (and the
+    /// state directory below it) in `dir` if given.
+    fn start(dir: Option<&std::path::Path>) -> Result<Running, String> {
+        let mut ai = testkit::ai(&[("акула", "хрящевая рыба")], FakeModel::new(["ответ модели"])).map_err(|e| e.to_string())?;
+        if let Some(dir) = dir {
+            (ai.config.data.dir, ai.config.data.state_dir) = (dir.to_path_buf(), dir.join("state"));
+        }
+        spawn("127.0.0.1:0", ai, Scheduler::new()).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn health_and_unknown_routes() {
+        let server = start(None);
+        assert!(server.is_ok(), "{:?}", server.as_ref().err());
+        let Ok(server) = server else { return };
+        let addr = server.addr();
+        assert_eq!(request(addr, "GET", "/health", ""), Some((200, "OK".to_string())));
+        assert_eq!(request(addr, "GET", "/chat", "").map(|r| r.0), Some(404));
+        assert_eq!(request(addr, "GET", "/nowhere", "").map(|r| r.0), Some(404));
+    }
+
+    #[test]
+    fn chat_replies_with_its_schema() {
+        let server = start(None);
+        assert!(server.is_ok(), "{:?}", server.as_ref().err());
+        let Ok(server) = server else { return };
+        let addr = server.addr();
+
+        let reply = json_of(request(addr, "POST", "/chat", r#"{"prompt": "акула"}"#));
+        assert!(reply.as_ref().is_some_and(Value::is_object), "{:?}", reply);
+        let Some(reply) = reply.as_ref().and_then(Value::as_object) else { return };
+        assert_eq!(reply.get("reply").and_then(Value::as_str), Some("хрящевая рыба"));
+        assert_eq!(reply.get("source").and_then(Value::as_str), Some("knowledge"));
+        assert!(reply.get("confidence").is_some_and(Value::is_f64) && reply.get("latency_ms").is_some_and(Value::is_f64));
+        assert!(reply.get("tokens").is_some_and(Value::is_u64) && reply.get("abstained").is_some_and(Value::is_boolean));
+        assert!(!reply.contains_key("moderated") && !reply.contains_key("ranking"));
+        let model = json_of(request(addr, "POST", "/chat", r#"{"prompt": "как дела"}"#));
+        assert_eq!(model.as_ref().and_then(|m| m.get("source")).and_then(Value::as_str), Some("model"));
+        let structured = json_of(request(addr, "POST", "/chat", r#"{"prompt": "2+2", "structured": true}"#));
+        assert!(structured.is_some_and(|s| s.is_object()));
+
+        // malformed bodies and unknown generators are client errors
+        assert_eq!(request(addr, "POST", "/chat", "{").map(|r| r.0), Some(400));
+        assert_eq!(request(addr, "POST", "/chat", r#"{"text": "акула"}"#).map(|r| r.0), Some(400));
+        assert_eq!(request(addr, "POST", "/chat", r#"{"prompt": "акула", "model": "нет такой"}"#).map(|r| r.0), Some(400));
+    }
+
+    #[test]
+    fn embeddings_reply_with_one_vector_per_input() {
+        let server = start(None);
+        assert!(server.is_ok(), "{:?}", server.as_ref().err());
+        let Ok(server) = server else { return };
+        let addr = server.addr();
+
+        let embeddings = json_of(request(addr, "POST", "/embeddings", r#"{"input": ["акула", "скат"]}"#));
+        assert_eq!(embeddings.as_ref().and_then(|e| e.get("model")).and_then(Value::as_str), Some(model_registry::DEFAULT));
+        assert_eq!(embeddings.as_ref().and_then(|e| e.get("data")).and_then(Value::as_array).map(Vec::len), Some(2));
+        assert!(embeddings.as_ref().and_then(|e| e.get("dim")).is_some_and(Value::is_u64));
+        assert_eq!(request(addr, "POST", "/embeddings", r#"{"input": 5}"#).map(|r| r.0), Some(400));
+    }
+
+    #[test]
+    fn metrics_and_jobs_are_json() {
+        let server = start(None);
+        assert!(server.is_ok(), "{:?}", server.as_ref().err());
+        let Ok(server) = server else { return };
+        let addr = server.addr();
+
+        let metrics = json_of(request(addr, "GET", "/metrics", ""));
+        assert!(metrics.is_some_and(|m| m.get("cache").is_some() && m.get("speculative").is_some_and(Value::is_object)));
+        assert!(json_of(request(addr, "GET", "/jobs", "")).is_some_and(|j| j.is_array()));
+    }
+
+    #[test]
+    fn evaluate_runs_datasets_and_keeps_their_history() {
+        let dir = std::env::temp_dir().join(format!("shark_server_{}", std::process::id()));
+        assert!(std::fs::create_dir_all(&dir).is_ok() && std::fs::write(dir.join("fish.csv"), "акула,хрящевая рыба\n").is_ok());
+        let server = start(Some(&dir));
+        assert!(server.is_ok(), "{:?}", server.as_ref().err());
+        let Ok(server) = server else { return };
+        let addr = server.addr();
+
+        let evaluated = json_of(request(addr, "POST", "/evaluate", r#"{"dataset": "fish"}"#));
+        assert_eq!(evaluated.as_ref().and_then(|e| e.pointer("/run/passed")).and_then(Value::as_u64), Some(1));
+        assert!(evaluated.as_ref().is_some_and(|e| e.get("previous").is_some_and(Value::is_null) && e.get("regression").is_some()));
+        assert_eq!(request(addr, "POST", "/evaluate", r#"{"dataset": "../fish"}"#).map(|r| r.0), Some(400));
+        assert_eq!(request(addr, "POST", "/evaluate", r#"{"name": "fish"}"#).map(|r| r.0), Some(400));
+        let history = json_of(request(addr, "GET", "/evaluate?dataset=fish", ""));
+        assert_eq!(history.as_ref().and_then(Value::as_array).map(Vec::len), Some(1));
+        assert_eq!(json_of(request(addr, "GET", "/evaluate?dataset=birds", "")), Some(Value::Array(Vec::new())));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn export_renders_the_session() {
+        let server = start(None);
+        assert!(server.is_ok(), "{:?}", server.as_ref().err());
+        let Ok(server) = server else { return };
+        let addr = server.addr();
+
+        let exported = request(addr, "POST", "/export", r#"{"format": "html", "exchanges": [{"question": "акула", "answer": "хрящевая рыба", "source": "knowledge", "confidence": 1.0}]}"#);
+        assert!(exported.is_some_and(|(status, body)| status == 200 && body.contains("<h2>1. акула</h2>")));
+        assert_eq!(request(addr, "POST", "/export", r#"{"format": "pdf", "exchanges": []}"#).map(|r| r.0), Some(400));
+    }
+
+    #[cfg(feature = "trading")]
+    #[test]
+    fn quant_routes_ignore_the_query() {
+        let server = start(None);
+        assert!(server.is_ok(), "{:?}", server.as_ref().err());
+        let Ok(server) = server else { return };
+        let addr = server.addr();
+
+        let body = r#"{"closes": [1, 2, 3], "strategy": "buy-hold"}"#;
+        let backtest = json_of(request(addr, "POST", "/backtest?verbose=1", body));
+        assert_eq!(backtest.as_ref().and_then(|b| b.get("bars")).and_then(Value::as_u64), Some(3));
+        let sma = json_of(request(addr, "POST", "/indicators/sma?x=1", r#"{"closes": [1, 2, 3], "period": 2}"#));
+        assert_eq!(sma.as_ref().and_then(|s| s.get("indicator")).and_then(Value::as_str), Some("sma"));
+        assert_eq!(request(addr, "POST", "/backtests?x=1", body).map(|r| r.0), Some(404));
+    }
+}
@@ -0,0 +1,171 @@
+#![forbid(unsafe_code)]
+
+//! Vector similarity and nearest-neighbour search over embeddings
+//! (`Model::embed`, `AI::embed`).
+//!
+//! `knn` compares the query with every vector. `BucketIndex` hashes vectors
+//! by the signs of a few random projections (SimHash) and only scores the
+//! query's bucket and the buckets one bit away, falling back to the full scan
+//! when they hold fewer than `k` vectors; results are approximate but cheap
+//! for stores of thousands of entries.
+
+use std::collections::HashMap;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::core;
+
+/// Dot product over the common length.
+pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Euclidean norm.
+pub fn norm(a: &[f32]) -> f32 {
+    dot(a, a).sqrt()
+}
+
+/// Cosine similarity in `-1..=1`; 0 when either vector is zero.
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let n = norm(a) * norm(b);
+    if n > 0.0 { dot(a, b) / n } else { 0.0 }
+}
+
+/// Euclidean distance over the common length.
+pub fn euclidean(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum::<f32>().sqrt()
+}
+
+/// How neighbours are compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Metric {
+    /// cosine similarity
+    #[default]
+    Cosine,
+    /// dot product
+    Dot,
+    /// Euclidean distance
+    Euclidean,
+}
+
+impl Metric {
+    /// Score of `b` for query `a`; higher is closer (Euclidean distance is negated).
+    pub fn score(self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            Metric::Cosine => cosine(a, b),
+            Metric::Dot => dot(a, b),
+            Metric::Euclidean => -euclidean(a, b),
+        }
+    }
+}
+
+/// A search hit.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Neighbor {
+    /// position of the vector in the searched collection
+    pub index: usize,
+    /// `Metric::score` against the query
+    pub score: f32,
+}
+
+fn top_k(query: &[f32], vectors: &[Vec<f32>], candidates: impl Iterator<Item = usize>, k: usize, metric: Metric) -> Vec<Neighbor> {
+    let mut hits: Vec<Neighbor> = candidates.filter_map(|index| vectors.get(index).map(|v| Neighbor { index, score: metric.score(query, v) })).collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.index.cmp(&b.index)));
+    hits.truncate(k);
+    hits
+}
+
+/// The `k` vectors closest to `query`, best first (ties by index).
+pub fn knn(query: &[f32], vectors: &[Vec<f32>], k: usize, metric: Metric) -> Vec<Neighbor> {
+    top_k(query, vectors, 0..vectors.len(), k, metric)
+}
+
+/// Approximate k-NN index bucketing vectors by random-projection signs.
+#[derive(Debug, Clone)]
+pub struct BucketIndex {
+    vectors: Vec<Vec<f32>>,
+    planes: Vec<Vec<f32>>,
+    buckets: HashMap<u64, Vec<usize>>,
+}
+
+impl BucketIndex {
+    /// Index `vectors` with `bits` random hyperplanes (at most 64) drawn from `seed`.
+    pub fn new(vectors: Vec<Vec<f32>>, bits: usize, seed: u64) -> Self {
+        let dim = vectors.iter().map(Vec::len).max().unwrap_or(0);
+        let mut rng = core::make_rng(seed);
+        let planes = (0..bits.min(64)).map(|_| (0..dim).map(|_| rng.gen_range(-1.0f32..1.0)).collect()).collect();
+        let mut index = Self { vectors: Vec::new(), planes, buckets: HashMap::new() };
+        for v in vectors {
+            index.push(v);
+        }
+        index
+    }
+
+    fn hash(&self, v: &[f32]) -> u64 {
+        self.planes.iter().enumerate().fold(0, |h, (bit, plane)| if dot(plane, v) >= 0.0 { h | (1 << bit) } else { h })
+    }
+
+    /// Add a vector; its index is the previous `len`.
+    pub fn push(&mut self, v: Vec<f32>) {
+        let hash = self.hash(&v);
+        self.buckets.entry(hash).or_default().push(self.vectors.len());
+        self.vectors.push(v);
+    }
+
+    /// Number of indexed vectors.
+    pub fn len(&self) -> usize {
+        self.vectors.len()
+    }
+
+    /// Whether nothing is indexed.
+    pub fn is_empty(&self) -> bool {
+        self.vectors.is_empty()
+    }
+
+    /// Indexed vectors in insertion order.
+    pub fn vectors(&self) -> &[Vec<f32>] {
+        &self.vectors
+    }
+
+    /// Up to `k` neighbours from the query's bucket and the buckets one bit
+    /// away; the full scan when those hold fewer than `k` vectors.
+    pub fn knn(&self, query: &[f32], k: usize, metric: Metric) -> Vec<Neighbor> {
+        let hash = self.hash(query);
+        let mut candidates: Vec<usize> = Vec::new();
+        for key in std::iter::once(hash).chain((0..self.planes.len()).map(|bit| hash ^ (1 << bit))) {
+            candidates.extend(self.buckets.get(&key).into_iter().flatten());
+        }
+        if candidates.len() < k {
+            return knn(query, &self.vectors, k, metric);
+        }
+        top_k(query, &self.vectors, candidates.into_iter(), k, metric)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metrics_and_neighbours() {
+        let (a, b) = ([1.0, 0.0], [0.0, 2.0]);
+        assert_eq!((dot(&a, &b), cosine(&a, &b), euclidean(&[0.0, 0.0], &[3.0, 4.0])), (0.0, 0.0, 5.0));
+        assert_eq!(cosine(&[0.0, 0.0], &a), 0.0);
+
+        let vectors: Vec<Vec<f32>> = (0..200).map(|i| { let t = i as f32 * 0.1; vec![t.cos(), t.sin(), 0.1 * (i % 7) as f32] }).collect();
+        let query = vectors.get(5).cloned().unwrap_or_default();
+        let exact = knn(&query, &vectors, 3, Metric::Cosine);
+        assert_eq!(exact.first().map(|n| n.index), Some(5));
+        assert!(exact.windows(2).all(|w| matches!(w, [x, y] if x.score >= y.score)));
+        assert_eq!(knn(&query, &vectors, 1, Metric::Euclidean).first().map(|n| (n.index, n.score)), Some((5, 0.0)));
+
+        let index = BucketIndex::new(vectors.clone(), 6, 7);
+        assert_eq!(index.len(), 200);
+        let approx = index.knn(&query, 3, Metric::Cosine);
+        assert_eq!(approx.len(), 3);
+        assert_eq!(approx.first(), exact.first());
+        assert_eq!(index.knn(&query, 500, Metric::Cosine).len(), 200);
+    }
+}
@@ -0,0 +1,176 @@
+#![forbid(unsafe_code)]
+
+//! Speculative decoding: a small draft model proposes `k` characters, the
+//! target model scores all of them (plus one more position) in a single
+//! batched forward pass, and each proposal is accepted with probability
+//! `min(1, p/q)`. The first rejected position is resampled from the residual
+//! `max(0, p - q)`, so the output follows the target model's distribution
+//! while the target runs once per accepted run instead of once per character.
+//!
+//! The input vector of both character models depends only on the context
+//! and the characters produced so far (`Model::advance_embedding`), so the
+//! target's inputs for the drafted positions are known before it runs.
+
+use rand::Rng;
+use serde::Serialize;
+
+use crate::calibration;
+use crate::core;
+use crate::model::Model;
+use crate::tokenizer::ALPHABET;
+
+/// Characters drafted per target pass when none is configured.
+pub const DEFAULT_K: usize = 4;
+
+/// Counters of speculative runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct SpeculativeStats {
+    /// characters proposed by the draft model
+    pub drafted: u64,
+    /// proposals the target accepted
+    pub accepted: u64,
+    /// batched forward passes of the target model
+    pub target_passes: u64,
+    /// characters produced
+    pub generated: u64,
+}
+
+impl SpeculativeStats {
+    /// Share of drafted characters that were accepted (0 before any run).
+    pub fn acceptance_rate(&self) -> f64 {
+        if self.drafted == 0 { 0.0 } else { self.accepted as f64 / self.drafted as f64 }
+    }
+
+    /// Characters per target pass (1 is plain decoding).
+    pub fn chars_per_pass(&self) -> f64 {
+        if self.target_passes == 0 { 0.0 } else { self.generated as f64 / self.target_passes as f64 }
+    }
+
+    /// Add the counters of another run.
+    pub fn add(&mut self, other: &SpeculativeStats) {
+        self.drafted += other.drafted;
+        self.accepted += other.accepted;
+        self.target_passes += other.target_passes;
+        self.generated += other.generated;
+    }
+}
+
+fn probabilities(mut logits: Vec<f32>) -> Vec<f32> {
+    core::softmax(&mut logits);
+    logits
+}
+
+/// Generate up to `target.max_chars` characters for `context` with `draft`
+/// proposing `k` at a time. Seeding follows `Model::generate_scored` (the
+/// target's `seed`); the confidence is computed from the target's
+/// distributions as there.
+pub fn generate(draft: &Model, target: &Model, context: &str, k: usize) -> (String, f32, SpeculativeStats) {
+    let k = k.max(1);
+    let mut seed: u64 = 0x9e3779b97f4a7c15u64 ^ target.seed;
+    for &b in context.as_bytes() {
+        seed = seed.wrapping_mul(31).wrapping_add(b as u64);
+    }
+    let mut rng = core::make_rng(seed);
+    let mut stats = SpeculativeStats::default();
+    let mut out: Vec<u8> = Vec::new();
+    let mut entropy_sum = 0.0f32;
+    let (mut draft_emb, mut target_emb) = (draft.context_embedding(context), target.context_embedding(context));
+
+    while out.len() < target.max_chars {
+        // draft k characters
+        let n = k.min(target.max_chars - out.len());
+        let mut proposals: Vec<(usize, Vec<f32>)> = Vec::with_capacity(n);
+        let mut emb = draft_emb.clone();
+        for _ in 0..n {
+            let q = probabilities(draft.logits(&emb));
+            let idx = core::sample_index(&q, &mut rng);
+            let Some(&ch) = ALPHABET.get(idx) else { break };
+            Model::advance_embedding(&mut emb, ch);
+            proposals.push((idx, q));
+        }
+        // target inputs for every drafted position plus the one after them
+        let mut inputs = vec![target_emb.clone()];
+        for (idx, _) in &proposals {
+            let mut next = inputs.last().cloned().unwrap_or_default();
+            Model::advance_embedding(&mut next, ALPHABET.get(*idx).copied().unwrap_or(b' '));
+            inputs.push(next);
+        }
+        let ps: Vec<Vec<f32>> = target.logits_batch(&inputs).into_iter().map(probabilities).collect();
+        stats.target_passes += 1;
+        stats.drafted += proposals.len() as u64;
+
+        let mut produced: Vec<usize> = Vec::new();
+        let mut rejected = false;
+        for ((idx, q), p) in proposals.iter().zip(&ps) {
+            let (pi, qi) = (p.get(*idx).copied().unwrap_or(0.0), q.get(*idx).copied().unwrap_or(0.0));
+            entropy_sum += calibration::entropy(p);
+            if qi > 0.0 && rng.gen::<f32>() < pi / qi {
+                stats.accepted += 1;
+                produced.push(*idx);
+                continue;
+            }
+            let mut residual: Vec<f32> = p.iter().zip(q).map(|(a, b)| (a - b).max(0.0)).collect();
+            let total: f32 = residual.iter().sum();
+            if total > 0.0 {
+                residual.iter_mut().for_each(|r| *r /= total);
+            } else {
+                residual.clone_from(p);
+            }
+            produced.push(core::sample_index(&residual, &mut rng));
+            rejected = true;
+            break;
+        }
+        if !rejected && produced.len() == proposals.len() && out.len() + produced.len() < target.max_chars {
+            if let Some(p) = ps.get(proposals.len()) {
+                entropy_sum += calibration::entropy(p);
+                produced.push(core::sample_index(p, &mut rng));
+            }
+        }
+        if produced.is_empty() {
+            break;
+        }
+        for idx in produced {
+            let Some(&ch) = ALPHABET.get(idx) else { break };
+            out.push(ch);
+            Model::advance_embedding(&mut draft_emb, ch);
+            Model::advance_embedding(&mut target_emb, ch);
+        }
+    }
+
+    stats.generated = out.len() as u64;
+    let confidence = if out.is_empty() { 0.0 } else { calibration::from_entropy(entropy_sum / out.len() as f32, ALPHABET.len()) };
+    (String::from_utf8_lossy(&out).to_string(), confidence, stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_models_accept_every_draft() {
+        let (draft, target) = (Model::zeroed(), Model::zeroed());
+        let (text, _, stats) = generate(&draft, &target, "привет", 4);
+        assert_eq!(text.len(), target.max_chars);
+        assert_eq!(stats.accepted, stats.drafted);
+        assert_eq!(stats.acceptance_rate(), 1.0);
+        // four drafted plus one bonus character per pass
+        assert_eq!(stats.target_passes, (target.max_chars as u64).div_ceil(5));
+        assert_eq!(generate(&draft, &target, "привет", 4).0, text);
+    }
+
+    #[test]
+    fn rejected_drafts_are_resampled_from_the_target() {
+        // the bias makes 'k' win every step by far: the target alone is greedy
+        let mut target = Model::new_random((Model::EMBED, Model::HIDDEN), 2);
+        let bias = crate::tokenizer::char_id(b'k').and_then(|id| target.lin2.bias.get_mut(id));
+        assert!(bias.is_some());
+        let Some(bias) = bias else { return };
+        *bias = 1000.0;
+        let draft = Model::new_random((Model::EMBED, Model::HIDDEN), 1);
+
+        let (text, _, stats) = generate(&draft, &target, "привет", 4);
+        assert_eq!(text, target.generate("привет"));
+        assert_eq!(text, "k".repeat(target.max_chars));
+        assert!(stats.accepted < stats.drafted, "{:?}", stats);
+    }
+}
@@ -0,0 +1,93 @@
+//! Running statistics over a stream of numbers (Welford's algorithm): count,
+//! mean, min, max and standard deviation in one pass without storing the values.
+
+/// Accumulated statistics of the values `push`ed so far.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RunningStats {
+    count: usize,
+    mean: f64,
+    m2: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Default for RunningStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RunningStats {
+    /// No values yet.
+    pub fn new() -> Self {
+        Self { count: 0, mean: 0.0, m2: 0.0, min: f64::INFINITY, max: f64::NEG_INFINITY }
+    }
+
+    /// Add a value.
+    pub fn push(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Number of values.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Mean (0 without values).
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// Smallest value, if any.
+    pub fn min(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.min)
+    }
+
+    /// Largest value, if any.
+    pub fn max(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.max)
+    }
+
+    /// Sample variance (divides by `count - 1`; 0 for fewer than two values).
+    pub fn variance(&self) -> f64 {
+        if self.count < 2 { 0.0 } else { self.m2 / (self.count - 1) as f64 }
+    }
+
+    /// Sample standard deviation.
+    pub fn stddev(&self) -> f64 {
+        self.variance().sqrt()
+    }
+}
+
+impl Extend<f64> for RunningStats {
+    fn extend<I: IntoIterator<Item = f64>>(&mut self, values: I) {
+        values.into_iter().for_each(|v| self.push(v));
+    }
+}
+
+impl FromIterator<f64> for RunningStats {
+    fn from_iter<I: IntoIterator<Item = f64>>(values: I) -> Self {
+        let mut stats = Self::new();
+        stats.extend(values);
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_two_pass_formulas() {
+        let stats: RunningStats = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0].into_iter().collect();
+        assert_eq!((stats.count(), stats.mean(), stats.min(), stats.max()), (8, 5.0, Some(2.0), Some(9.0)));
+        assert!((stats.variance() - 32.0 / 7.0).abs() < 1e-12);
+        assert_eq!(RunningStats::new().min(), None);
+        assert_eq!(RunningStats::new().stddev(), 0.0);
+    }
+}
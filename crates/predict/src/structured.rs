@@ -0,0 +1,101 @@
+#![forbid(unsafe_code)]
+
+//! Structured (JSON-mode) answers for downstream tooling.
+//!
+//! `AI::chat_structured` runs the pipeline with the model stage generating
+//! through the grammar-constrained sampler (`constrained::Template`), so the
+//! model's output is always a JSON object `{"answer": "..."}`, and wraps the
+//! result in a typed `StructuredAnswer`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::constrained::Template;
+use crate::response::{ChatResponse, Source};
+
+/// Field the constrained model output stores its text in.
+pub const ANSWER_FIELD: &str = "answer";
+
+/// Template the model stage samples in JSON mode.
+pub fn answer_template(max_len: usize) -> Template {
+    Template::json_string_field(ANSWER_FIELD, max_len)
+}
+
+/// Typed answer, serialized as
+/// `{"answer", "steps", "references", "value", "source", "confidence"}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StructuredAnswer {
+    /// answer text
+    pub answer: String,
+    /// reasoning steps of the stage that answered
+    pub steps: Vec<String>,
+    /// what the answer is based on, as `source/stage` (e.g. `knowledge/knowledge`)
+    pub references: Vec<String>,
+    /// numeric value of the answer when it is a number or `x = <number>`
+    pub value: Option<f64>,
+    /// producer of the answer
+    pub source: Source,
+    /// calibrated confidence, see `ChatResponse::confidence`
+    pub confidence: f32,
+}
+
+impl StructuredAnswer {
+    /// Wrap a pipeline response; model output in JSON mode is unwrapped from
+    /// its `{"answer": ...}` object.
+    pub fn from_response(response: &ChatResponse) -> Self {
+        let generated = (response.source == Source::Model && !response.abstained)
+            .then(|| serde_json::from_str::<serde_json::Value>(&response.text).ok())
+            .flatten()
+            .and_then(|v| v.get(ANSWER_FIELD).and_then(|a| a.as_str()).map(str::to_string));
+        let answer = generated.unwrap_or_else(|| response.text.clone());
+        let references = if response.stage.is_empty() || response.stage == "none" {
+            Vec::new()
+        } else {
+            vec![format!("{}/{}", response.source.as_str(), response.stage)]
+        };
+        Self {
+            value: numeric_value(&answer),
+            answer,
+            steps: response.reasoning.clone(),
+            references,
+            source: response.source,
+            confidence: response.confidence,
+        }
+    }
+
+    /// The answer as one line of JSON.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+}
+
+/// `42`, `-1.5`, `x = 2` → the number; anything else → `None`.
+fn numeric_value(answer: &str) -> Option<f64> {
+    let text = answer.trim().trim_end_matches('.');
+    let text = text.split_once('=').map_or(text, |(_, value)| value.trim());
+    text.replace(',', ".").parse().ok().filter(|v: &f64| v.is_finite())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::Memory;
+    use crate::model::Model;
+
+    #[test]
+    fn model_json_mode_always_parses() {
+        let ai = crate::AI::builder().model(Model::zeroed()).memory(Memory::in_memory()).no_ngram().build();
+        assert!(ai.is_ok());
+        let Ok(mut ai) = ai else { return };
+        ai.pipeline = std::sync::Arc::new(crate::Pipeline::from_stages(&["linear", "model"]).unwrap_or_default());
+
+        let solved = ai.chat_structured("реши 2x + 3 = 7");
+        assert_eq!((solved.answer.as_str(), solved.value), ("x = 2", Some(2.0)));
+        assert_eq!(solved.references, vec!["reasoner/linear".to_string()]);
+
+        let generated = ai.chat_structured("ммм");
+        assert_eq!(generated.source, Source::Model);
+        assert!(!generated.answer.contains('"'));
+        let json = generated.to_json().unwrap_or_default();
+        assert!(serde_json::from_str::<StructuredAnswer>(&json).is_ok_and(|a| a == generated));
+    }
+}
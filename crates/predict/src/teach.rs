@@ -0,0 +1,134 @@
+#![forbid(unsafe_code)]
+
+//! Facts taught in conversation: "акула — это хрящевая рыба" is recognised as
+//! a definition, checked against the knowledge base and, once the user
+//! confirms, appended to knowledge.csv with a provenance copy tagged
+//! `user-taught` in `knowledge/knowledge_user.csv`. The `learn` pipeline stage
+//! drives the exchange; the proposal waits in `DialogueState` for "да"/"нет".
+
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Provenance tag of facts taught by the user.
+pub const PROVENANCE: &str = "user-taught";
+
+/// Replies that confirm a proposed fact.
+const YES: [&str; 6] = ["да", "ага", "верно", "запомни", "yes", "y"];
+
+/// Replies that reject a proposed fact.
+const NO: [&str; 5] = ["нет", "не надо", "отмена", "no", "n"];
+
+/// Words that make the left side a question rather than a term.
+const QUESTION_WORDS: [&str; 8] = ["что", "кто", "как", "почему", "зачем", "где", "когда", "сколько"];
+
+/// A definition taught by the user: `term` — это `definition`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fact {
+    /// defined term, lowercase (the knowledge base key)
+    pub term: String,
+    /// definition as written
+    pub definition: String,
+}
+
+/// How a fact relates to the knowledge base.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Check {
+    /// the term is not known yet
+    New,
+    /// the term is known with the same definition
+    Known,
+    /// the term is known with another definition (given)
+    Contradicts(String),
+}
+
+/// The user's reply to a proposed fact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confirmation {
+    /// store it
+    Yes,
+    /// drop it
+    No,
+}
+
+/// Recognise "X — это Y" (also "X - это Y", optionally after "запомни:").
+/// Questions and sentences with a longer left side are not statements.
+pub fn parse_statement(input: &str) -> Option<Fact> {
+    let text = input.trim();
+    if text.ends_with('?') {
+        return None;
+    }
+    let text = text.strip_prefix("запомни").map(|t| t.trim_start_matches([':', ',', ' '])).unwrap_or(text);
+    let (term, definition) = ["—", "–", " - "].iter().find_map(|dash| {
+        let (left, right) = text.split_once(dash)?;
+        Some((left, right.trim_start().strip_prefix("это ")?))
+    })?;
+    let term = term.trim().trim_matches(['"', '«', '»']).to_lowercase();
+    let definition = definition.trim().trim_end_matches(['.', '!']).trim();
+    let words = term.split_whitespace().count();
+    if !(1..=4).contains(&words) || definition.is_empty() || term.split_whitespace().any(|w| QUESTION_WORDS.contains(&w)) {
+        return None;
+    }
+    Some(Fact { term, definition: definition.to_string() })
+}
+
+/// Compare `fact` with the stored definition of its term (case and final
+/// punctuation are ignored).
+pub fn check(fact: &Fact, knowledge: &HashMap<String, String>) -> Check {
+    let normalize = |s: &str| s.trim().trim_end_matches(['.', '!']).to_lowercase();
+    match knowledge.get(&fact.term) {
+        None => Check::New,
+        Some(known) if normalize(known) == normalize(&fact.definition) => Check::Known,
+        Some(known) => Check::Contradicts(known.clone()),
+    }
+}
+
+/// Whether `input` answers a pending proposal.
+pub fn confirmation(input: &str) -> Option<Confirmation> {
+    let reply = input.trim().trim_end_matches(['.', '!', ',']).to_lowercase();
+    if YES.contains(&reply.as_str()) {
+        Some(Confirmation::Yes)
+    } else if NO.contains(&reply.as_str()) {
+        Some(Confirmation::No)
+    } else {
+        None
+    }
+}
+
+/// Append `fact` to `knowledge_path` and, tagged `user-taught` with the date,
+/// to `provenance_path` (created with a header when missing).
+pub fn store(fact: &Fact, knowledge_path: &Path, provenance_path: &Path) -> io::Result<()> {
+    crate::train::append_knowledge(&knowledge_path.to_string_lossy(), &fact.term, &fact.definition)?;
+    if let Some(dir) = provenance_path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut f = OpenOptions::new().create(true).append(true).open(provenance_path)?;
+    if f.metadata()?.len() == 0 {
+        writeln!(f, "question,answer,source,date")?;
+    }
+    let now = chrono::Utc::now().to_rfc3339();
+    writeln!(f, "\"{}\",\"{}\",{},{}", fact.term.replace('"', "'"), fact.definition.replace('"', "'"), PROVENANCE, now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statements_are_parsed_and_checked() {
+        let fact = parse_statement("Скат — это хрящевая рыба.");
+        assert_eq!(fact, Some(Fact { term: "скат".to_string(), definition: "хрящевая рыба".to_string() }));
+        assert_eq!(parse_statement("запомни: API - это интерфейс").map(|f| f.term), Some("api".to_string()));
+        assert_eq!(parse_statement("что такое скат — это рыба?"), None);
+        assert_eq!(parse_statement("почему небо — это купол"), None);
+        assert_eq!(parse_statement("скат плавает"), None);
+
+        let knowledge = HashMap::from([("скат".to_string(), "Хрящевая рыба".to_string())]);
+        let Some(fact) = fact else { return };
+        assert_eq!(check(&fact, &knowledge), Check::Known);
+        let other = Fact { definition: "птица".to_string(), ..fact };
+        assert_eq!(check(&other, &knowledge), Check::Contradicts("Хрящевая рыба".to_string()));
+        assert_eq!((confirmation("Да!"), confirmation("нет"), confirmation("может")), (Some(Confirmation::Yes), Some(Confirmation::No), None));
+    }
+}
@@ -0,0 +1,109 @@
+//! Side-by-side tearsheet of backtest runs.
+//!
+//! `compare` turns named `StrategyReport`s (e.g. the winners of a parameter
+//! sweep) into a `reports::Report`: a metrics table, an equity chart with one
+//! line per run and a drawdown table. Write it with `Report::write` (Markdown
+//! and SVG) or `Report::write_html` (one page).
+
+use backtest::StrategyReport;
+
+use crate::reports::{LineChart, Report, Table};
+
+/// Deepest peak-to-trough fall of an equity curve.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Drawdown {
+    /// peak minus trough
+    pub depth: f64,
+    /// timestamp of the peak
+    pub peak_ts: u64,
+    /// timestamp of the trough
+    pub trough_ts: u64,
+    /// first timestamp after the trough back at the peak, if any
+    pub recovered_ts: Option<u64>,
+}
+
+/// Deepest drawdown of `(ts, equity)` points, measured from a starting equity
+/// of 0 (None when the curve never falls below its running peak).
+pub fn max_drawdown(equity: &[(u64, f64)]) -> Option<Drawdown> {
+    let first_ts = equity.first()?.0;
+    let (mut peak, mut peak_ts) = (0.0f64, first_ts);
+    // the drawdown and the equity at its peak
+    let mut worst: Option<(Drawdown, f64)> = None;
+    for &(ts, value) in equity {
+        if value > peak {
+            (peak, peak_ts) = (value, ts);
+        } else if peak - value > worst.map_or(0.0, |(d, _)| d.depth) {
+            worst = Some((Drawdown { depth: peak - value, peak_ts, trough_ts: ts, recovered_ts: None }, peak));
+        }
+    }
+    let (mut worst, peak) = worst?;
+    worst.recovered_ts = equity.iter().find(|(ts, v)| *ts > worst.trough_ts && *v >= peak).map(|p| p.0);
+    Some(worst)
+}
+
+/// Tearsheet comparing `runs` given as `(name, report)`, in order.
+pub fn compare(runs: &[(&str, &StrategyReport)]) -> Report {
+    let mut metrics = Table::new(&["run", "trades", "win rate", "net pnl", "commissions", "max drawdown", "sharpe"]);
+    let mut drawdowns = Table::new(&["run", "depth", "peak", "trough", "recovered"]);
+    let mut chart = LineChart::new("Equity");
+    for (name, r) in runs {
+        metrics.push(&[
+            name.to_string(),
+            r.trades.len().to_string(),
+            format!("{:.1}%", r.win_rate() * 100.0),
+            format!("{:.4}", r.net_pnl),
+            format!("{:.4}", r.commissions),
+            format!("{:.4}", r.max_drawdown),
+            format!("{:.3}", r.sharpe()),
+        ]);
+        match max_drawdown(&r.equity) {
+            Some(d) => drawdowns.push(&[
+                name.to_string(),
+                format!("{:.4}", d.depth),
+                d.peak_ts.to_string(),
+                d.trough_ts.to_string(),
+                d.recovered_ts.map_or_else(|| "—".to_string(), |t| t.to_string()),
+            ]),
+            None => drawdowns.push(&[*name, "0", "—", "—", "—"]),
+        }
+        chart = chart.series(*name, r.equity.iter().map(|&(ts, v)| (ts as f64, v)));
+    }
+    let best = runs.iter().max_by(|a, b| a.1.net_pnl.total_cmp(&b.1.net_pnl));
+    let mut report = Report::new("Backtest comparison").metric("runs", runs.len());
+    if let Some((name, r)) = best {
+        report = report.metric("best net pnl", format!("{} ({:.4})", name, r.net_pnl));
+    }
+    report.heading("Metrics").table(metrics).heading("Equity").chart(chart).heading("Drawdowns").table(drawdowns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backtest::strategies::{BuyHold, SmaCrossover};
+    use backtest::{bars_from_csv, run_strategy, EngineConfig};
+
+    #[test]
+    fn tearsheet_lists_every_run() {
+        let bars = bars_from_csv("close\n5\n4\n3\n4\n5\n6\n5\n4\n3\n");
+        assert_eq!(bars.as_ref().map(Vec::len), Ok(9));
+        let Ok(bars) = bars else { return };
+        let cfg = EngineConfig { commission_rate: 0.0, slippage: 0.0, seed: 0 };
+        let (hold, cross) = (run_strategy(&BuyHold, &bars, cfg), run_strategy(&SmaCrossover { fast: 1, slow: 2 }, &bars, cfg));
+        assert_eq!((hold.as_ref().map(|r| r.net_pnl), cross.as_ref().map(|r| r.net_pnl)), (Ok(-2.0), Ok(1.0)));
+        let (Ok(hold), Ok(cross)) = (hold, cross) else { return };
+
+        let dd = max_drawdown(&hold.equity);
+        assert_eq!(dd, Some(Drawdown { depth: 3.0, peak_ts: 5, trough_ts: 8, recovered_ts: None }));
+        let recovered = max_drawdown(&[(0, 0.0), (1, 2.0), (2, 1.0), (3, 2.5)]);
+        assert_eq!(recovered, Some(Drawdown { depth: 1.0, peak_ts: 1, trough_ts: 2, recovered_ts: Some(3) }));
+        assert_eq!(max_drawdown(&[(0, 1.0), (1, 2.0)]), None);
+
+        let report = compare(&[("buy-hold", &hold), ("sma 1/2", &cross)]);
+        let md = report.to_markdown();
+        assert!(md.contains("- best net pnl: sma 1/2 (1.0000)"), "{}", md);
+        assert!(md.contains("| buy-hold | 1 | 0.0% | -2.0000 |"), "{}", md);
+        assert!(md.contains("| buy-hold | 3.0000 | 5 | 8 | — |"), "{}", md);
+        assert!(md.contains("![Equity](chart_0.svg)"), "{}", md);
+        assert_eq!(report.to_html().matches("<polyline").count(), 2);
+    }
+}
@@ -0,0 +1,184 @@
+#![forbid(unsafe_code)]
+
+//! Fixtures for testing `AI` without the filesystem, and golden files.
+//!
+//! `ai` assembles an `AI` from an in-memory dialog memory, an injected
+//! knowledge map and a configuration whose paths point nowhere (`config`),
+//! with the `model` stage replaced by a `FakeModel` that answers with canned
+//! text. `transcript` runs prompts through it and lists where each one was
+//! routed; `check_golden` compares such output with a file under version
+//! control. Setting `SHARK_BLESS=1` rewrites the golden files instead, after
+//! an intended change of routing:
+//!
+//! ```no_run
+//! use predict::testkit::{self, FakeModel};
+//!
+//! let mut ai = testkit::ai(&[("акула", "хрящевая рыба")], FakeModel::new(["..."]))?;
+//! let actual = testkit::transcript(&mut ai, &["акула", "2+2"]);
+//! assert_eq!(testkit::check_golden("tests/golden/routing.txt".as_ref(), &actual), Ok(()));
+//! # Ok::<(), predict::SharkError>(())
+//! ```
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::config::Config;
+use crate::error::Result;
+use crate::memory::Memory;
+use crate::model::Model;
+use crate::pipeline::{stage, Handler, Pipeline, Reply, DEFAULT_STAGES};
+use crate::response::Source;
+use crate::AI;
+
+/// Environment variable that makes `check_golden` write the actual output.
+pub const BLESS_ENV: &str = "SHARK_BLESS";
+
+/// Directory the `config` paths point into; it is never created.
+pub const ISOLATED_DIR: &str = "/nonexistent/shark-testkit";
+
+/// Stand-in for the `model` stage: answers prompts registered with `reply`
+/// with their text and any other prompt with the next of its outputs, in
+/// turn. Deterministic for a given sequence of prompts.
+pub struct FakeModel {
+    outputs: Vec<String>,
+    replies: HashMap<String, String>,
+    next: AtomicUsize,
+    confidence: f32,
+}
+
+impl FakeModel {
+    /// Model answering with `outputs` in turn (after the last, the first
+    /// again); without outputs it passes every prompt on.
+    pub fn new<S: Into<String>>(outputs: impl IntoIterator<Item = S>) -> Self {
+        Self { outputs: outputs.into_iter().map(Into::into).collect(), replies: HashMap::new(), next: AtomicUsize::new(0), confidence: 0.5 }
+    }
+
+    /// Answer `prompt` with `output` whenever it is asked.
+    pub fn reply(mut self, prompt: &str, output: &str) -> Self {
+        self.replies.insert(prompt.to_string(), output.to_string());
+        self
+    }
+
+    /// Confidence of the answers (default 0.5).
+    pub fn confidence(mut self, confidence: f32) -> Self {
+        self.confidence = confidence;
+        self
+    }
+}
+
+impl Handler for FakeModel {
+    fn name(&self) -> &'static str {
+        "model"
+    }
+
+    fn handle(&self, _ai: &mut AI, input: &str) -> Option<Reply> {
+        let text = match self.replies.get(input) {
+            Some(text) => text.clone(),
+            None if self.outputs.is_empty() => return None,
+            None => self.outputs.get(self.next.fetch_add(1, Ordering::Relaxed) % self.outputs.len())?.clone(),
+        };
+        Some(Reply { text, source: Source::Model, confidence: self.confidence, reasoning: Vec::new() })
+    }
+}
+
+/// Question → answer map as `AIBuilder::knowledge` expects it (lowercase questions).
+pub fn knowledge(entries: &[(&str, &str)]) -> HashMap<String, String> {
+    entries.iter().map(|(q, a)| (q.trim().to_lowercase(), a.to_string())).collect()
+}
+
+/// Default configuration with every file path inside `ISOLATED_DIR`, so
+/// nothing is read from or written to the working directory.
+pub fn config() -> Config {
+    let mut config = Config::default();
+    let dir = PathBuf::from(ISOLATED_DIR);
+    config.data.dir = dir.clone();
+    config.data.state_dir = dir.join("state");
+    config.data.memory = dir.join("memory.db").to_string_lossy().into_owned();
+    config.model.path = dir.join("model.bin").to_string_lossy().into_owned();
+    config.model.ngram_path = dir.join("ngram.bin").to_string_lossy().into_owned();
+    config.plugins.manifest = dir.join("manifest.toml");
+    config
+}
+
+/// The `DEFAULT_STAGES` pipeline with `model` as its model stage.
+pub fn pipeline(model: FakeModel) -> Pipeline {
+    let mut handlers: Vec<Box<dyn Handler>> = DEFAULT_STAGES.iter().filter(|name| **name != "model").filter_map(|name| stage(name)).collect();
+    handlers.push(Box::new(model));
+    Pipeline::new(handlers)
+}
+
+/// `AI` over `knowledge`, an untrained model, in-memory dialogs and the
+/// `config` paths, answering with `model` where the real one would generate.
+pub fn ai(knowledge: &[(&str, &str)], model: FakeModel) -> Result<AI> {
+    AI::builder()
+        .config(config())
+        .model(Model::zeroed())
+        .memory(Memory::in_memory())
+        .knowledge(self::knowledge(knowledge))
+        .no_ngram()
+        .pipeline(pipeline(model))
+        .build()
+}
+
+/// One line per prompt, chatted in order: prompt, stage, source, confidence
+/// and answer (line breaks shown as `\n`).
+pub fn transcript(ai: &mut AI, prompts: &[&str]) -> String {
+    prompts
+        .iter()
+        .map(|prompt| {
+            let r = ai.chat(prompt);
+            format!("{} → {} ({}, {:.2}): {}\n", prompt, r.stage, r.source.as_str(), r.confidence, r.text.replace('\n', "\\n"))
+        })
+        .collect()
+}
+
+/// Compare `actual` with the golden file at `path`: `Err` describes the
+/// first differing line (or the missing file). With `SHARK_BLESS` set the
+/// file is (re)written with `actual` instead.
+pub fn check_golden(path: &Path, actual: &str) -> std::result::Result<(), String> {
+    if std::env::var_os(BLESS_ENV).is_some() {
+        let written = path.parent().map_or(Ok(()), std::fs::create_dir_all).and_then(|()| std::fs::write(path, actual));
+        return written.map_err(|e| format!("{}: {}", path.display(), e));
+    }
+    let expected = std::fs::read_to_string(path).map_err(|e| format!("{}: {} (run with {}=1 to create it)", path.display(), e, BLESS_ENV))?;
+    if expected == actual {
+        return Ok(());
+    }
+    let (mut e, mut a) = (expected.lines(), actual.lines());
+    for line in 1.. {
+        match (e.next(), a.next()) {
+            (Some(x), Some(y)) if x == y => continue,
+            (None, None) => break,
+            (x, y) => {
+                return Err(format!(
+                    "{}:{} differs\n  expected: {}\n  actual:   {}\n(run with {}=1 to accept)",
+                    path.display(),
+                    line,
+                    x.unwrap_or("<end of file>"),
+                    y.unwrap_or("<end of output>"),
+                    BLESS_ENV
+                ))
+            }
+        }
+    }
+    Err(format!("{}: line endings differ (run with {}=1 to accept)", path.display(), BLESS_ENV))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pipeline_routing_matches_golden_file() {
+        let model = FakeModel::new(["первый ответ", "второй ответ"]).reply("спой песню", "ля-ля");
+        let ai = ai(&[("акула", "хрящевая рыба"), ("алгоритм", "последовательность шагов")], model);
+        assert_eq!(ai.as_ref().err().map(ToString::to_string), None);
+        let Ok(mut ai) = ai else { return };
+        let prompts = ["акула", "Акула", "2+2*2", "2x + 3 = 7", "алгорим?", "где алгорим?", "скат — это хрящевая рыба", "нет", "спой песню", "как дела", "как дела"];
+        let actual = transcript(&mut ai, &prompts);
+        let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/pipeline_routing.txt");
+        assert_eq!(check_golden(&golden, &actual), Ok(()));
+        assert_eq!(ai.memory.dialogs().len(), prompts.len());
+    }
+}
@@ -0,0 +1,167 @@
+#![forbid(unsafe_code)]
+
+//! TF-IDF similarity of texts against a corpus (the knowledge questions).
+//!
+//! Trigram similarity is a Jaccard index over letter triples: a long question
+//! shares most of its triples with no stored one, so its best match drops
+//! below the 0.3 threshold even when the stored question is a part of it.
+//! TF-IDF compares words instead, each counted sublinearly (`1 + ln count`)
+//! and weighted by its smoothed inverse document frequency
+//! (`1 + ln((1 + n) / (1 + df))`), so the words every question has ("что",
+//! "такое") matter little and the rare ones decide. Similarity is the cosine
+//! of the two L2-normalised vectors. `[pipeline] retrieval = "tfidf"` makes
+//! the `concepts` stage select and score its candidates this way
+//! (`rerank::Signals::tfidf`).
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// How fuzzy knowledge matches are found (`[pipeline] retrieval`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Retrieval {
+    /// letter trigrams (`reasoning::trigram_similarity`, the default)
+    #[default]
+    Trigram,
+    /// word TF-IDF cosine over the stored questions (`TfIdfIndex`)
+    TfIdf,
+}
+
+/// Lowercase words of `text`.
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).map(str::to_lowercase)
+}
+
+/// Sparse vector: (term index, weight), sorted by term.
+type Sparse = Vec<(usize, f64)>;
+
+/// Document-term matrix of a corpus with its inverse document frequencies.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TfIdfIndex {
+    terms: HashMap<String, usize>,
+    idf: Vec<f64>,
+    documents: Vec<Sparse>,
+}
+
+impl TfIdfIndex {
+    /// Index of `documents`, in order.
+    pub fn new(documents: &[&str]) -> Self {
+        let mut terms: HashMap<String, usize> = HashMap::new();
+        let mut df: Vec<usize> = Vec::new();
+        for document in documents {
+            let mut seen: Vec<usize> = words(document)
+                .map(|w| {
+                    let next = terms.len();
+                    *terms.entry(w).or_insert(next)
+                })
+                .collect();
+            seen.sort_unstable();
+            seen.dedup();
+            for term in seen {
+                if df.len() <= term {
+                    df.resize(term + 1, 0);
+                }
+                if let Some(count) = df.get_mut(term) {
+                    *count += 1;
+                }
+            }
+        }
+        let n = documents.len() as f64;
+        let idf = df.iter().map(|&d| 1.0 + ((1.0 + n) / (1.0 + d as f64)).ln()).collect();
+        let mut index = Self { terms, idf, documents: Vec::new() };
+        index.documents = documents.iter().map(|d| index.vector(d)).collect();
+        index
+    }
+
+    /// Number of indexed documents.
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// True when no document is indexed.
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// Unit-length TF-IDF vector of `text`; words outside the corpus are
+    /// ignored, so a text without corpus words gives an empty vector.
+    fn vector(&self, text: &str) -> Sparse {
+        let mut counts: HashMap<usize, usize> = HashMap::new();
+        for term in words(text).filter_map(|w| self.terms.get(&w).copied()) {
+            *counts.entry(term).or_default() += 1;
+        }
+        let mut vector: Sparse = counts
+            .into_iter()
+            .map(|(term, count)| (term, (1.0 + (count as f64).ln()) * self.idf.get(term).copied().unwrap_or(1.0)))
+            .collect();
+        vector.sort_unstable_by_key(|(term, _)| *term);
+        let norm = vector.iter().map(|(_, w)| w * w).sum::<f64>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|(_, w)| *w /= norm);
+        }
+        vector
+    }
+
+    /// Cosine of `a` and `b` (both sorted by term).
+    fn cosine(a: &Sparse, b: &Sparse) -> f64 {
+        let (mut i, mut j, mut dot) = (a.iter().peekable(), b.iter().peekable(), 0.0);
+        while let (Some(&&(ta, wa)), Some(&&(tb, wb))) = (i.peek(), j.peek()) {
+            match ta.cmp(&tb) {
+                std::cmp::Ordering::Less => {
+                    i.next();
+                }
+                std::cmp::Ordering::Greater => {
+                    j.next();
+                }
+                std::cmp::Ordering::Equal => {
+                    dot += wa * wb;
+                    i.next();
+                    j.next();
+                }
+            }
+        }
+        dot.clamp(0.0, 1.0)
+    }
+
+    /// Similarity (0..=1) of two texts under the corpus weights.
+    pub fn similarity(&self, a: &str, b: &str) -> f64 {
+        Self::cosine(&self.vector(a), &self.vector(b))
+    }
+
+    /// The `k` documents most similar to `query`, best first (ties by index),
+    /// as (document index, similarity); documents sharing no word are left out.
+    pub fn search(&self, query: &str, k: usize) -> Vec<(usize, f64)> {
+        let query = self.vector(query);
+        let mut scored: Vec<(usize, f64)> =
+            self.documents.iter().map(|d| Self::cosine(&query, d)).enumerate().filter(|(_, s)| *s > 0.0).collect();
+        scored.sort_by(|x, y| y.1.total_cmp(&x.1).then(x.0.cmp(&y.0)));
+        scored.truncate(k);
+        scored
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reasoning::trigram_similarity;
+
+    #[test]
+    fn long_questions_still_find_their_entry() {
+        let questions = ["что такое производная", "что такое интеграл", "что такое матрица", "как работает нейронная сеть"];
+        let index = TfIdfIndex::new(&questions);
+        assert_eq!(index.len(), 4);
+
+        let query = "объясни подробно и простыми словами на примере, как в целом работает нейронная сеть и зачем она нужна";
+        assert!(trigram_similarity(query, "как работает нейронная сеть") < 0.3);
+        assert_eq!(index.search(query, 1).first().map(|(i, _)| *i), Some(3));
+        assert!(index.similarity(query, "как работает нейронная сеть") > 0.3);
+
+        // the shared "что такое" counts less than the concept
+        let hits = index.search("что такое интеграл функции", 4);
+        assert_eq!(hits.first().map(|(i, _)| *i), Some(1));
+        assert!(hits.get(1).is_some_and(|(_, s)| *s < 0.5));
+        assert!(index.search("погода завтра", 3).is_empty());
+        assert!(TfIdfIndex::new(&[]).search("что", 1).is_empty());
+    }
+}
@@ -4,6 +4,11 @@
 /// space and common punctuation so the generator can produce readable text.
 pub const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 .,!?+-=*/()[]{}<>:'\"";
 
+/// Token id of a character for the model's output layer (its index in `ALPHABET`).
+pub fn char_id(c: u8) -> Option<usize> {
+    ALPHABET.iter().position(|&a| a == c)
+}
+
 /// Very small tokenizer that splits on whitespace and punctuation.
 pub fn tokenize(s: &str) -> Vec<String> {
     s.split(|c: char| c.is_whitespace() || c.is_ascii_punctuation())
@@ -0,0 +1,457 @@
+#![forbid(unsafe_code)]
+
+//! Tools the chat pipeline can call: a `Tool` has a name, a JSON schema of
+//! its input and the intents it serves, and `run`s on the prompt. The
+//! `ToolRegistry` on `AI::tools` holds them; the `arithmetic` and `linear`
+//! stages call their tools by name and the `tools` stage picks tools by the
+//! prompt's intent (`intent::classify`). Every call is recorded in the
+//! reasoning trace as a `ToolCall`.
+
+use serde_json::{json, Value};
+
+#[cfg(feature = "trading")]
+use crate::events::Event;
+use crate::intent::{self, Intent};
+
+/// One tool invocation, as shown in the reasoning trace.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolCall {
+    /// tool name
+    pub tool: &'static str,
+    /// input the tool ran on
+    pub input: String,
+    /// its answer
+    pub output: String,
+}
+
+impl ToolCall {
+    /// Reasoning-trace line for the call.
+    pub fn trace(&self) -> String {
+        format!("🔧 инструмент {}({:?}) → {}", self.tool, self.input, self.output)
+    }
+}
+
+/// A callable capability of the chat pipeline.
+pub trait Tool: Send + Sync {
+    /// Unique name (used by `ToolRegistry::get` and in the trace).
+    fn name(&self) -> &'static str;
+
+    /// JSON schema of the input the tool understands.
+    fn schema(&self) -> Value;
+
+    /// Intents the tool serves; `ToolRegistry::select` tries these tools first.
+    fn intents(&self) -> &'static [Intent];
+
+    /// Answer `input`, or `None` if the tool does not apply to it.
+    fn run(&self, input: &str) -> Option<String>;
+}
+
+/// Ordered set of tools.
+#[derive(Default)]
+pub struct ToolRegistry {
+    tools: Vec<Box<dyn Tool>>,
+}
+
+impl ToolRegistry {
+    /// Empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The built-in tools: `arithmetic`, `linear`, `integral`, `data`, `guess`
+    /// (and `backtest`, `indicator` with the `trading` feature).
+    pub fn standard() -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(Calculator));
+        registry.register(Box::new(LinearSolver));
+        registry.register(Box::new(Integrator));
+        registry.register(Box::new(crate::data_tool::DataTool));
+        registry.register(Box::new(crate::guess::GuessGame::default()));
+        #[cfg(feature = "trading")]
+        registry.register(Box::new(BacktestTool));
+        #[cfg(feature = "trading")]
+        registry.register(Box::new(IndicatorTool));
+        registry
+    }
+
+    /// Add a tool; a tool with the same name is replaced.
+    pub fn register(&mut self, tool: Box<dyn Tool>) {
+        self.tools.retain(|t| t.name() != tool.name());
+        self.tools.push(tool);
+    }
+
+    /// Tool by name.
+    pub fn get(&self, name: &str) -> Option<&dyn Tool> {
+        self.tools.iter().find(|t| t.name() == name).map(|t| t.as_ref())
+    }
+
+    /// Tool names in registration order.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.tools.iter().map(|t| t.name()).collect()
+    }
+
+    /// Run the tool called `name` on `input`.
+    pub fn call(&self, name: &str, input: &str) -> Option<ToolCall> {
+        let tool = self.get(name)?;
+        let output = tool.run(input)?;
+        Some(ToolCall { tool: tool.name(), input: input.to_string(), output })
+    }
+
+    /// Tools in the order `dispatch` tries them for `input`: those serving
+    /// the classified intent first (when the classifier is confident), then the rest.
+    pub fn select(&self, input: &str) -> Vec<&dyn Tool> {
+        let intent = intent::classify(input).confident();
+        let (mut matching, rest): (Vec<&dyn Tool>, Vec<&dyn Tool>) =
+            self.tools.iter().map(|t| t.as_ref()).partition(|t| intent.is_some_and(|i| t.intents().contains(&i)));
+        matching.extend(rest);
+        matching
+    }
+
+    /// First tool (in `select` order) that answers `input`.
+    pub fn dispatch(&self, input: &str) -> Option<ToolCall> {
+        self.select(input).into_iter().find_map(|tool| {
+            let output = tool.run(input)?;
+            Some(ToolCall { tool: tool.name(), input: input.to_string(), output })
+        })
+    }
+}
+
+/// ASCII digits, letters and math operators of `input` (drops the words around an expression).
+fn sanitize(input: &str) -> String {
+    input.chars().filter(|c| c.is_ascii() && (c.is_ascii_alphanumeric() || "+-*/=()^ .".contains(*c))).collect()
+}
+
+/// Arithmetic expression, as typed or with the surrounding words dropped (`train::eval_arith`).
+pub struct Calculator;
+
+impl Tool for Calculator {
+    fn name(&self) -> &'static str {
+        "arithmetic"
+    }
+
+    fn schema(&self) -> Value {
+        json!({"type": "string", "description": "arithmetic expression with + - * / and parentheses"})
+    }
+
+    fn intents(&self) -> &'static [Intent] {
+        &[Intent::Math]
+    }
+
+    fn run(&self, input: &str) -> Option<String> {
+        crate::train::eval_arith(input).or_else(|| crate::train::eval_arith(&sanitize(input)))
+    }
+}
+
+/// Linear equation in `x`, as typed or with the surrounding words dropped (`train::solve_linear_equation`).
+pub struct LinearSolver;
+
+impl Tool for LinearSolver {
+    fn name(&self) -> &'static str {
+        "linear"
+    }
+
+    fn schema(&self) -> Value {
+        json!({"type": "string", "description": "linear equation in x, e.g. 2x + 3 = 7"})
+    }
+
+    fn intents(&self) -> &'static [Intent] {
+        &[Intent::Math]
+    }
+
+    fn run(&self, input: &str) -> Option<String> {
+        if !input.contains('=') {
+            return None;
+        }
+        crate::train::solve_linear_equation(input).or_else(|| crate::train::solve_linear_equation(&sanitize(input)))
+    }
+}
+
+/// Definite integral of a polynomial term: "интеграл x^2 от 0 до 2" (`integrator::try_integrate`).
+pub struct Integrator;
+
+impl Tool for Integrator {
+    fn name(&self) -> &'static str {
+        "integral"
+    }
+
+    fn schema(&self) -> Value {
+        json!({"type": "string", "description": "интеграл <x^n | x | constant> от <a> до <b>"})
+    }
+
+    fn intents(&self) -> &'static [Intent] {
+        &[Intent::Math]
+    }
+
+    fn run(&self, input: &str) -> Option<String> {
+        crate::integrator::try_integrate(input)
+    }
+}
+
+/// Backtest of a CSV price file: "протестируй SMA-crossover 10/30 на data/btc.csv"
+/// runs `backtest::simulate_sma_crossover` (or `simulate_buy_hold` when the
+/// prompt names no SMA strategy) and answers with the metrics summary. Each
+/// closed trade is published as `Event::TradeExecuted`.
+#[cfg(feature = "trading")]
+pub struct BacktestTool;
+
+#[cfg(feature = "trading")]
+impl BacktestTool {
+    /// SMA periods used when the prompt gives none.
+    pub const DEFAULT_PERIODS: (usize, usize) = (10, 30);
+    /// Commission per side applied to every trade.
+    pub const COMMISSION: f64 = 0.001;
+}
+
+#[cfg(feature = "trading")]
+impl Tool for BacktestTool {
+    fn name(&self) -> &'static str {
+        "backtest"
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {"type": "string", "description": "CSV with a close/price column"},
+                "strategy": {"enum": ["sma-crossover", "buy-hold"]},
+                "fast": {"type": "integer", "default": Self::DEFAULT_PERIODS.0},
+                "slow": {"type": "integer", "default": Self::DEFAULT_PERIODS.1}
+            },
+            "required": ["path"]
+        })
+    }
+
+    fn intents(&self) -> &'static [Intent] {
+        &[Intent::Instruction, Intent::Research]
+    }
+
+    fn run(&self, input: &str) -> Option<String> {
+        let lower = input.to_lowercase();
+        if !BACKTEST_WORDS.iter().any(|w| lower.contains(w)) {
+            return None;
+        }
+        let sma = ["sma", "crossover", "кроссовер"].iter().any(|w| lower.contains(w));
+        let path = csv_path(input)?;
+        let bars = match load_bars(path) {
+            Ok(bars) => bars,
+            Err(e) => return Some(e),
+        };
+        let cfg = backtest::EngineConfig { commission_rate: Self::COMMISSION, slippage: 0.0, seed: 0 };
+        if !sma {
+            return Some(match backtest::simulate_buy_hold(&bars, cfg) {
+                Ok(r) => {
+                    crate::events::publish(Event::TradeExecuted { entry_price: r.entry_price, exit_price: r.exit_price, net_pnl: r.net_pnl });
+                    format!(
+                        "buy-hold на {} ({} баров): вход {:.4}, выход {:.4}, PnL {:.4}, комиссии {:.4}, итог {:.4}",
+                        path, bars.len(), r.entry_price, r.exit_price, r.gross_pnl, r.commissions, r.net_pnl
+                    )
+                }
+                Err(e) => format!("⚠️ бэктест {}: {}", path, e),
+            });
+        }
+        let (fast, slow) = sma_periods(&lower.replace(&path.to_lowercase(), " "));
+        Some(match backtest::simulate_sma_crossover(&bars, fast, slow, cfg) {
+            Ok(r) => {
+                for t in &r.trades {
+                    crate::events::publish(Event::TradeExecuted { entry_price: t.entry_price, exit_price: t.exit_price, net_pnl: t.net_pnl });
+                }
+                format!(
+                    "SMA-crossover {}/{} на {} ({} баров): сделок {}, прибыльных {} ({:.0}%), PnL {:.4}, комиссии {:.4}, итог {:.4}, макс. просадка {:.4}, хеш прогона {}",
+                    fast, slow, path, bars.len(), r.trades.len(), r.wins, r.win_rate() * 100.0, r.gross_pnl, r.commissions, r.net_pnl, r.max_drawdown, r.run_hash
+                )
+            }
+            Err(e) => format!("⚠️ бэктест {}: {}", path, e),
+        })
+    }
+}
+
+/// Words that make a prompt a backtest request.
+#[cfg(feature = "trading")]
+const BACKTEST_WORDS: [&str; 5] = ["бэктест", "backtest", "протестируй", "crossover", "кроссовер"];
+
+/// First word of `input` naming a `.csv` file, without surrounding quotes and punctuation.
+pub(crate) fn csv_path(input: &str) -> Option<&str> {
+    input
+        .split_whitespace()
+        .map(|w| w.trim_matches(|c: char| "\"'«»,;:!?()".contains(c)))
+        .find(|w| w.to_lowercase().ends_with(".csv"))
+}
+
+/// Bars of a CSV file with a close column, Binance klines or MetaTrader history
+/// (`backtest::import::any`); the error is the chat reply.
+#[cfg(feature = "trading")]
+fn load_bars(path: &str) -> Result<Vec<backtest::PriceBar>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("⚠️ не удалось прочитать {}: {}", path, e))?;
+    backtest::import::any(&text).map_err(|e| format!("⚠️ {}: {}", path, e))
+}
+
+/// Latest value of an indicator over the closes of a CSV file: "RSI(14) для цен
+/// из файла prices.csv" (also `SMA`, `EMA`). The answer ends with a `sparkline`
+/// of the recent values, which the GUI draws as a chart.
+#[cfg(feature = "trading")]
+pub struct IndicatorTool;
+
+#[cfg(feature = "trading")]
+impl IndicatorTool {
+    /// Supported indicators with their default periods.
+    pub const INDICATORS: [(&'static str, usize); 3] = [("rsi", 14), ("sma", 20), ("ema", 20)];
+    /// Values shown in the sparkline.
+    pub const SPARK_LEN: usize = 30;
+}
+
+#[cfg(feature = "trading")]
+impl Tool for IndicatorTool {
+    fn name(&self) -> &'static str {
+        "indicator"
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "indicator": {"enum": ["rsi", "sma", "ema"]},
+                "period": {"type": "integer"},
+                "path": {"type": "string", "description": "CSV with a close/price column"}
+            },
+            "required": ["indicator", "path"]
+        })
+    }
+
+    fn intents(&self) -> &'static [Intent] {
+        &[Intent::Question, Intent::Research]
+    }
+
+    fn run(&self, input: &str) -> Option<String> {
+        let lower = input.to_lowercase();
+        if BACKTEST_WORDS.iter().any(|w| lower.contains(w)) {
+            return None;
+        }
+        let path = csv_path(input)?;
+        let query = lower.replace(&path.to_lowercase(), " ");
+        let caps = regex::Regex::new(r"\b(rsi|sma|ema)\b\s*[-(]?\s*(\d+)?").ok()?.captures(&query)?;
+        let name = caps.get(1)?.as_str();
+        let default = Self::INDICATORS.iter().find(|(n, _)| *n == name).map_or(14, |(_, p)| *p);
+        let period = caps.get(2).and_then(|p| p.as_str().parse().ok()).unwrap_or(default);
+        let closes: Vec<f64> = match load_bars(path) {
+            Ok(bars) => bars.iter().map(|b| b.close).collect(),
+            Err(e) => return Some(e),
+        };
+        let values = match name {
+            "rsi" => indicators::rsi(&closes, period),
+            "sma" => indicators::sma(&closes, period),
+            _ => indicators::ema(&closes, period),
+        };
+        let label = format!("{}({})", name.to_uppercase(), period);
+        let values = match values {
+            Ok(values) => values,
+            Err(e) => return Some(format!("⚠️ {} по {} ({} цен): {}", label, path, closes.len(), e)),
+        };
+        let recent = values.get(values.len().saturating_sub(Self::SPARK_LEN)..).unwrap_or(&values);
+        let previous: Vec<String> = recent.iter().rev().skip(1).take(3).map(|v| format!("{:.2}", v)).collect();
+        Some(format!(
+            "{} по {}: последнее {:.2} (до этого {}) {}",
+            label,
+            path,
+            recent.last().copied().unwrap_or_default(),
+            previous.join(", "),
+            sparkline(recent)
+        ))
+    }
+}
+
+/// Block characters of a sparkline, lowest to highest.
+pub const SPARK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// One block character per value, scaled between the minimum and the maximum.
+pub fn sparkline(values: &[f64]) -> String {
+    let (lo, hi) = values.iter().fold((f64::MAX, f64::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    let top = (SPARK_CHARS.len() - 1) as f64;
+    values
+        .iter()
+        .filter_map(|&v| {
+            let level = if hi > lo { ((v - lo) / (hi - lo) * top).round() } else { 0.0 };
+            SPARK_CHARS.get(level as usize).copied()
+        })
+        .collect()
+}
+
+/// Levels (0..=7) of the last sparkline in `text`, for drawing it as a chart.
+pub fn sparkline_levels(text: &str) -> Option<Vec<f32>> {
+    let word = text.split_whitespace().rev().find(|w| w.chars().all(|c| SPARK_CHARS.contains(&c)))?;
+    Some(word.chars().filter_map(|c| SPARK_CHARS.iter().position(|&s| s == c)).map(|l| l as f32).collect())
+}
+
+/// First two integers of the prompt as (fast, slow) SMA periods, smaller first;
+/// `BacktestTool::DEFAULT_PERIODS` otherwise.
+#[cfg(feature = "trading")]
+fn sma_periods(text: &str) -> (usize, usize) {
+    let mut numbers = text.split(|c: char| !c.is_ascii_digit()).filter_map(|n| n.parse::<usize>().ok());
+    match (numbers.next(), numbers.next()) {
+        (Some(a), Some(b)) => (a.min(b), a.max(b)),
+        _ => BacktestTool::DEFAULT_PERIODS,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Echo;
+
+    impl Tool for Echo {
+        fn name(&self) -> &'static str {
+            "echo"
+        }
+
+        fn schema(&self) -> Value {
+            json!({"type": "string"})
+        }
+
+        fn intents(&self) -> &'static [Intent] {
+            &[Intent::SmallTalk]
+        }
+
+        fn run(&self, input: &str) -> Option<String> {
+            Some(input.to_uppercase())
+        }
+    }
+
+    #[test]
+    fn registry_dispatches_by_intent_and_name() {
+        let mut registry = ToolRegistry::standard();
+        registry.register(Box::new(Echo));
+        let names = registry.names();
+        assert_eq!((names.get(..3), names.last()), (Some(&["arithmetic", "linear", "integral"][..]), Some(&"echo")));
+
+        let call = registry.dispatch("сколько будет 6 * 7");
+        assert_eq!(call.as_ref().map(|c| (c.tool, c.output.as_str())), Some(("arithmetic", "42")));
+        assert!(call.is_some_and(|c| c.trace().contains("→ 42")));
+        assert_eq!(registry.dispatch("привет").map(|c| c.tool), Some("echo"));
+        assert_eq!(registry.call("linear", "2x + 3 = 7").map(|c| c.output), Some("x = 2".to_string()));
+        assert!(registry.call("linear", "2 + 3").is_none());
+    }
+
+    #[cfg(feature = "trading")]
+    #[test]
+    fn backtest_tool_runs_sma_crossover_on_a_csv() {
+        let path = std::env::temp_dir().join(format!("shark_backtest_{}.csv", std::process::id()));
+        let _ = std::fs::write(&path, "ts,close\n1,5\n2,4\n3,3\n4,4\n5,5\n6,6\n7,5\n8,4\n9,3\n");
+        let prompt = format!("протестируй SMA-crossover 1/2 на {}", path.display());
+        let call = ToolRegistry::standard().dispatch(&prompt);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(call.as_ref().map(|c| c.tool), Some("backtest"));
+        assert!(call.is_some_and(|c| c.output.starts_with("SMA-crossover 1/2") && c.output.contains("сделок 1, прибыльных 1")));
+    }
+
+    #[cfg(feature = "trading")]
+    #[test]
+    fn indicator_tool_reports_latest_value_and_sparkline() {
+        let path = std::env::temp_dir().join(format!("shark_prices_{}.csv", std::process::id()));
+        let _ = std::fs::write(&path, "close\n1\n2\n3\n4\n5\n");
+        let call = ToolRegistry::standard().dispatch(&format!("SMA(2) для цен из файла {}", path.display()));
+        let _ = std::fs::remove_file(&path);
+        let output = call.map(|c| c.output).unwrap_or_default();
+        assert!(output.starts_with("SMA(2) по ") && output.contains("последнее 4.50 (до этого 3.50, 2.50, 1.50)"));
+        assert_eq!(sparkline_levels(&output), Some(vec![0.0, 2.0, 5.0, 7.0]));
+    }
+}
@@ -0,0 +1,205 @@
+#![forbid(unsafe_code)]
+
+//! In-memory datasets: loading question/answer pairs from CSV or JSONL,
+//! deterministic shuffling, train/validation/test splits and mini-batches.
+
+use std::io;
+use std::path::Path;
+
+use rand::seq::SliceRandom;
+
+use crate::core;
+
+/// An ordered collection of examples.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Dataset<T> {
+    /// the examples, in their current order
+    pub items: Vec<T>,
+}
+
+/// Result of `Dataset::split`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Split<T> {
+    /// training part
+    pub train: Dataset<T>,
+    /// validation part
+    pub val: Dataset<T>,
+    /// test part (whatever remains)
+    pub test: Dataset<T>,
+}
+
+impl<T> Dataset<T> {
+    /// Wrap existing examples.
+    pub fn new(items: Vec<T>) -> Self {
+        Self { items }
+    }
+
+    /// Number of examples.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Whether the dataset has no examples.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Iterate over the examples in order.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.items.iter()
+    }
+
+    /// Shuffle in place; the same seed always gives the same order.
+    pub fn shuffle(&mut self, seed: u64) {
+        self.shuffle_with(&mut core::make_rng(seed));
+    }
+
+    /// Shuffle in place with a caller-owned RNG (for runs that keep one stream).
+    pub fn shuffle_with<R: rand::Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.items.shuffle(rng);
+    }
+
+    /// Split into train/validation/test by fractions of the current order
+    /// (shuffle first for a random split). The test part gets the remainder.
+    pub fn split(mut self, train: f32, val: f32) -> Split<T> {
+        let n = self.items.len();
+        let n_train = ((n as f32 * train.clamp(0.0, 1.0)).round() as usize).min(n);
+        let n_val = ((n as f32 * val.clamp(0.0, 1.0)).round() as usize).min(n - n_train);
+        let test = self.items.split_off(n_train + n_val);
+        let val = self.items.split_off(n_train);
+        Split { train: self, val: Dataset::new(val), test: Dataset::new(test) }
+    }
+
+    /// Consecutive mini-batches of at most `size` examples (size 0 is treated as 1).
+    pub fn batches(&self, size: usize) -> std::slice::Chunks<'_, T> {
+        self.items.chunks(size.max(1))
+    }
+}
+
+impl<T> FromIterator<T> for Dataset<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Dataset<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+/// Question/answer pairs such as `problems.csv` or `knowledge.csv`.
+pub type QaDataset = Dataset<(String, String)>;
+
+impl QaDataset {
+    /// Load from `.jsonl` (one object per line) or CSV (anything else).
+    pub fn load(path: &str) -> io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        if Path::new(path).extension().is_some_and(|e| e.eq_ignore_ascii_case("jsonl")) {
+            Self::parse_jsonl(&text)
+        } else {
+            Ok(Self::parse_csv(&text))
+        }
+    }
+
+    /// Parse `question,answer` rows. Fields may be double-quoted (with `""` as an
+    /// escaped quote), so commas inside quotes are kept. A header row whose
+    /// first field is `question`/`input`/`prompt` is skipped, as are rows with
+    /// fewer than two fields.
+    pub fn parse_csv(text: &str) -> Self {
+        text.lines()
+            .enumerate()
+            .filter_map(|(i, line)| {
+                let mut fields = parse_csv_line(line).into_iter();
+                let (q, a) = (fields.next()?, fields.next()?);
+                let header = i == 0 && ["question", "input", "prompt"].contains(&q.trim().to_lowercase().as_str());
+                (!header).then(|| (q.trim().to_string(), a.trim().to_string()))
+            })
+            .collect()
+    }
+
+    /// Parse one JSON object per line with the question under `question`,
+    /// `input` or `prompt` and the answer under `expected`, `answer` or `target`.
+    pub fn parse_jsonl(text: &str) -> io::Result<Self> {
+        let field = |v: &serde_json::Value, keys: &[&str]| keys.iter().find_map(|k| v.get(*k)?.as_str().map(str::to_string));
+        let mut items = Vec::new();
+        for (n, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+            let value: serde_json::Value = serde_json::from_str(line)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", n + 1, e)))?;
+            let q = field(&value, &["question", "input", "prompt"]);
+            let a = field(&value, &["expected", "answer", "target"]);
+            match (q, a) {
+                (Some(q), Some(a)) => items.push((q, a)),
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("line {}: expected question/expected string fields", n + 1),
+                    ))
+                }
+            }
+        }
+        Ok(Self::new(items))
+    }
+}
+
+/// Split one CSV line into fields, honouring double quotes.
+pub fn parse_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_keeps_quoted_commas_and_skips_header() {
+        let ds = QaDataset::parse_csv("question,expected\n\"Сравни: x^2, 2^x\",\"2^x растет быстрее\"\n2+2,4\nbroken\n");
+        assert_eq!(
+            ds.items,
+            vec![
+                ("Сравни: x^2, 2^x".to_string(), "2^x растет быстрее".to_string()),
+                ("2+2".to_string(), "4".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn jsonl_accepts_aliases() {
+        let ds = QaDataset::parse_jsonl("{\"question\":\"a\",\"expected\":\"b\"}\n\n{\"prompt\":\"c\",\"answer\":\"d\"}\n");
+        assert_eq!(ds.map(|d| d.len()).ok(), Some(2));
+        assert!(QaDataset::parse_jsonl("{\"question\":\"a\"}").is_err());
+    }
+
+    #[test]
+    fn shuffle_is_seeded_and_split_covers_everything() {
+        let mut a: Dataset<u32> = (0..10).collect();
+        let mut b = a.clone();
+        a.shuffle(3);
+        b.shuffle(3);
+        assert_eq!(a, b);
+
+        let split = a.split(0.6, 0.2);
+        assert_eq!((split.train.len(), split.val.len(), split.test.len()), (6, 2, 2));
+        let sizes: Vec<usize> = split.train.batches(4).map(|b| b.len()).collect();
+        assert_eq!(sizes, vec![4, 2]);
+    }
+}
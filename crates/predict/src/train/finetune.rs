@@ -0,0 +1,138 @@
+#![forbid(unsafe_code)]
+
+//! Fine-tuning the generation model on dialog memory.
+//!
+//! Every stored `(question, answer)` pair becomes a sequence of next-character
+//! examples built exactly the way `Model::generate` sees them: the context is
+//! what `Memory::build_context` produced at that point of the conversation, and
+//! after each answer character the input vector is advanced as during
+//! generation. Characters outside the model alphabet are skipped.
+
+use crate::memory::Memory;
+use crate::model::Model;
+use crate::tokenizer::{char_id, ALPHABET};
+use crate::train::sgd::{self, Loss, OptimizerConfig, TrainConfig};
+
+/// Settings for `finetune_from_memory`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FinetuneConfig {
+    /// gradient training settings (the loss is always cross-entropy)
+    pub train: TrainConfig,
+    /// use at most this many of the most recent dialogs
+    pub max_dialogs: usize,
+    /// train on at most this many characters of each answer (generation emits 64)
+    pub max_answer_chars: usize,
+    /// where to write the updated weights; `None` keeps them in memory only
+    pub save_to: Option<String>,
+}
+
+impl Default for FinetuneConfig {
+    fn default() -> Self {
+        Self {
+            train: TrainConfig {
+                loss: Loss::CrossEntropy,
+                optimizer: OptimizerConfig::adam(1e-3),
+                epochs: 3,
+                // a few hundred dialogs are memorized within an epoch without these
+                dropout: 0.1,
+                weight_decay: 1e-4,
+                ..TrainConfig::default()
+            },
+            max_dialogs: 200,
+            max_answer_chars: 64,
+            save_to: None,
+        }
+    }
+}
+
+/// What a fine-tuning run did.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FinetuneReport {
+    /// dialogs used
+    pub dialogs: usize,
+    /// (context → next character) examples
+    pub examples: usize,
+    /// mean loss per epoch
+    pub losses: Vec<f32>,
+}
+
+/// Turn dialogs into `(input vector, one-hot next character)` examples for `model`.
+pub fn examples_from_memory(model: &Model, memory: &Memory, cfg: &FinetuneConfig) -> Vec<(Vec<f32>, Vec<f32>)> {
+    let dialogs = memory.dialogs();
+    let skip = dialogs.len().saturating_sub(cfg.max_dialogs);
+    // replay the conversation so each context matches what generation saw
+    let mut history = Memory::in_memory();
+    for (q, a) in dialogs.iter().take(skip) {
+        history.push(q, a);
+    }
+    let mut examples = Vec::new();
+    for (q, a) in dialogs.iter().skip(skip) {
+        push_answer_examples(model, &history.build_context(q), a, cfg.max_answer_chars, &mut examples);
+        history.push(q, a);
+    }
+    examples
+}
+
+/// Turn independent question/answer pairs (e.g. a `QaDataset`) into examples;
+/// each question is used as a fresh single-turn context.
+pub fn examples_from_pairs(model: &Model, pairs: &[(String, String)], max_answer_chars: usize) -> Vec<(Vec<f32>, Vec<f32>)> {
+    let empty = Memory::in_memory();
+    let mut examples = Vec::new();
+    for (q, a) in pairs {
+        push_answer_examples(model, &empty.build_context(q), a, max_answer_chars, &mut examples);
+    }
+    examples
+}
+
+fn push_answer_examples(model: &Model, context: &str, answer: &str, max_chars: usize, out: &mut Vec<(Vec<f32>, Vec<f32>)>) {
+    let mut emb = model.context_embedding(context);
+    for &ch in answer.as_bytes().iter().filter(|&&c| char_id(c).is_some()).take(max_chars) {
+        let Some(id) = char_id(ch) else { continue };
+        let mut target = vec![0.0; ALPHABET.len()];
+        if let Some(t) = target.get_mut(id) {
+            *t = 1.0;
+        }
+        out.push((emb.clone(), target));
+        Model::advance_embedding(&mut emb, ch);
+    }
+}
+
+/// Fine-tune `model` on the dialogs stored in `memory` and, if configured,
+/// persist the updated weights.
+pub fn finetune_from_memory(model: &mut Model, memory: &Memory, cfg: &FinetuneConfig) -> Result<FinetuneReport, String> {
+    let examples = examples_from_memory(model, memory, cfg);
+    if examples.is_empty() {
+        return Err("в памяти нет диалогов с символами алфавита модели".to_string());
+    }
+    let train = TrainConfig { loss: Loss::CrossEntropy, ..cfg.train };
+    let losses = sgd::fit_model(model, &examples, &train)?;
+    if let Some(path) = &cfg.save_to {
+        model.save(path).map_err(|e| format!("не удалось сохранить веса в {}: {}", path, e))?;
+    }
+    Ok(FinetuneReport { dialogs: memory.dialogs().len().min(cfg.max_dialogs), examples: examples.len(), losses })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finetuning_lowers_loss_on_memory() {
+        let mut model = Model::zeroed();
+        // break the symmetry of zero weights
+        for (i, w) in model.lin1.weights.iter_mut().enumerate() {
+            *w = ((i * 7919) % 13) as f32 * 0.01 - 0.06;
+        }
+        let mut memory = Memory::in_memory();
+        memory.push("hi", "hello");
+        memory.push("2+2", "4");
+        let cfg = FinetuneConfig {
+            train: TrainConfig { optimizer: OptimizerConfig::adam(0.01), epochs: 20, ..FinetuneConfig::default().train },
+            ..FinetuneConfig::default()
+        };
+        let report = finetune_from_memory(&mut model, &memory, &cfg);
+        let losses = report.as_ref().map(|r| r.losses.clone()).unwrap_or_default();
+        assert_eq!(report.map(|r| r.examples).ok(), Some(6));
+        assert!(losses.last() < losses.first(), "losses: {:?}", losses);
+    }
+}
@@ -0,0 +1,142 @@
+#![forbid(unsafe_code)]
+
+//! Synthetic math corpora: `generate_dataset` draws problems with
+//! `selfplay::generate_problem` (arithmetic, linear equations, integrals of
+//! `x^n`), keeps those the Reasoner solves to the answer known by
+//! construction, and writes them as CSV (an evaluation suite and a
+//! fine-tuning dataset at once) or JSONL. The same spec and seed always give
+//! the same problems.
+
+use std::io;
+use std::path::Path;
+
+use serde_json::json;
+
+use crate::core;
+use crate::train::selfplay::{generate_problem, verify, ProblemKind};
+
+/// What to generate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatasetSpec {
+    /// number of problems
+    pub count: usize,
+    /// problem families, drawn in turn
+    pub kinds: Vec<ProblemKind>,
+    /// easiest difficulty level (1-based)
+    pub min_level: usize,
+    /// hardest difficulty level; levels are drawn in turn from `min_level`
+    pub max_level: usize,
+}
+
+impl Default for DatasetSpec {
+    fn default() -> Self {
+        Self { count: 100, kinds: ProblemKind::ALL.to_vec(), min_level: 1, max_level: 3 }
+    }
+}
+
+/// A generated problem with its verified answer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratedProblem {
+    /// problem family (also the evaluation category)
+    pub kind: ProblemKind,
+    /// difficulty level
+    pub level: usize,
+    /// question as a user would type it
+    pub question: String,
+    /// expected answer: `7`, `x = -3`, `2.6667`
+    pub expected: String,
+}
+
+/// Output layout of `write_dataset`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatasetFormat {
+    /// `question,expected,category,matcher` (loadable by `load_suite` and `QaDataset`)
+    Csv,
+    /// one `{"question", "expected", "category", "level"}` object per line
+    Jsonl,
+}
+
+impl DatasetFormat {
+    /// `Jsonl` for a `.jsonl` path, `Csv` otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("jsonl")) { Self::Jsonl } else { Self::Csv }
+    }
+}
+
+/// Canonical answer text for a problem's exact value.
+fn expected_text(kind: ProblemKind, value: f64) -> String {
+    match kind {
+        ProblemKind::Arith => format!("{}", value as i64),
+        ProblemKind::Linear => format!("x = {}", value as i64),
+        ProblemKind::Integral => format!("{:.4}", value),
+    }
+}
+
+/// Up to `spec.count` verified problems; gives up after ten draws per
+/// requested problem, so kinds the Reasoner cannot solve yield fewer.
+pub fn generate_dataset(spec: &DatasetSpec, seed: u64) -> Vec<GeneratedProblem> {
+    let mut rng = core::make_rng(seed);
+    let min = spec.min_level.max(1);
+    let levels = spec.max_level.max(min) - min + 1;
+    let mut out = Vec::with_capacity(spec.count);
+    for (draw, kind) in spec.kinds.iter().cycle().take(spec.count * 10).enumerate() {
+        if out.len() == spec.count {
+            break;
+        }
+        let level = min + (draw / spec.kinds.len()) % levels;
+        let problem = generate_problem(*kind, level, &mut rng);
+        if verify(&problem).is_some() {
+            out.push(GeneratedProblem { kind: *kind, level, expected: expected_text(*kind, problem.expected), question: problem.question });
+        }
+    }
+    out
+}
+
+/// Render problems in `format`.
+pub fn render(problems: &[GeneratedProblem], format: DatasetFormat) -> String {
+    let quote = |s: &str| format!("\"{}\"", s.replace('"', "\"\""));
+    let mut text = String::new();
+    if format == DatasetFormat::Csv {
+        text.push_str("question,expected,category,matcher\n");
+    }
+    for p in problems {
+        let line = match format {
+            DatasetFormat::Csv => format!("{},{},{},numeric", quote(&p.question), quote(&p.expected), p.kind.as_str()),
+            DatasetFormat::Jsonl => {
+                json!({"question": p.question, "expected": p.expected, "category": p.kind.as_str(), "level": p.level}).to_string()
+            }
+        };
+        text.push_str(&line);
+        text.push('\n');
+    }
+    text
+}
+
+/// Write problems to `path`, as JSONL for `.jsonl` and CSV otherwise.
+pub fn write_dataset(problems: &[GeneratedProblem], path: &Path) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, render(problems, DatasetFormat::from_path(path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::train::dataset::QaDataset;
+
+    #[test]
+    fn datasets_are_deterministic_and_loadable() {
+        let spec = DatasetSpec { count: 12, ..DatasetSpec::default() };
+        let problems = generate_dataset(&spec, 7);
+        assert_eq!(problems, generate_dataset(&spec, 7));
+        assert_eq!(problems.len(), 12);
+        assert!(problems.iter().any(|p| p.kind == ProblemKind::Integral && p.level > 1));
+
+        let csv = render(&problems, DatasetFormat::Csv);
+        let suite = evalharness::Suite::from_csv("synthetic", &csv);
+        assert_eq!(suite.map(|s| s.tasks.len()).ok(), Some(12));
+        let jsonl = QaDataset::parse_jsonl(&render(&problems, DatasetFormat::Jsonl));
+        assert_eq!(jsonl.ok().and_then(|d| d.items.first().cloned()), problems.first().map(|p| (p.question.clone(), p.expected.clone())));
+    }
+}
@@ -0,0 +1,147 @@
+#![forbid(unsafe_code)]
+
+//! Gradient checking: backprop gradients against central finite differences.
+//!
+//! Every parameter of the model is nudged by `±eps`, the mean cross-entropy
+//! over the batch is re-evaluated and `(up - down) / 2eps` is compared with
+//! the analytic gradient of `sgd::gradients`. A parameter fails when both its
+//! absolute error exceeds `ABS_TOLERANCE` and its relative error exceeds
+//! `REL_TOLERANCE`; ReLU kinks within `eps` of an activation can cause a few
+//! isolated failures, a wrong backward pass fails most parameters.
+
+use serde::Serialize;
+
+use crate::linear::Linear;
+use crate::model::Model;
+use crate::train::sgd::{self, Activation, Loss};
+
+/// Absolute error below which a parameter always passes (f32 loss rounding).
+pub const ABS_TOLERANCE: f32 = 1e-3;
+/// Relative error above which a parameter fails.
+pub const REL_TOLERANCE: f32 = 1e-2;
+
+/// A parameter whose gradients disagree.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Mismatch {
+    /// `lin1.weight`, `lin1.bias`, `lin2.weight` or `lin2.bias`
+    pub param: String,
+    /// index in the buffer (row-major for weights)
+    pub index: usize,
+    /// backprop gradient
+    pub analytic: f32,
+    /// finite-difference gradient
+    pub numeric: f32,
+    /// `|analytic - numeric| / max(|analytic|, |numeric|)`
+    pub rel_error: f32,
+}
+
+/// Result of `grad_check`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GradCheckReport {
+    /// parameters compared
+    pub checked: usize,
+    /// largest absolute error
+    pub max_abs_error: f32,
+    /// largest relative error among parameters above `ABS_TOLERANCE`
+    pub max_rel_error: f32,
+    /// failing parameters, worst first
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl GradCheckReport {
+    /// Whether every parameter is within tolerance.
+    pub fn passed(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    /// Summary with the five worst mismatches.
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            "{} проверено {} параметров: макс. абсолютная ошибка {:.2e}, относительная {:.2e}, расхождений {}\n",
+            if self.passed() { "✅" } else { "❌" },
+            self.checked,
+            self.max_abs_error,
+            self.max_rel_error,
+            self.mismatches.len()
+        );
+        for m in self.mismatches.iter().take(5) {
+            out.push_str(&format!("  {}[{}]: backprop {:.5}, разности {:.5} ({:.1}%)\n", m.param, m.index, m.analytic, m.numeric, m.rel_error * 100.0));
+        }
+        out
+    }
+}
+
+/// Compare the backprop gradients of `model` (ReLU, cross-entropy, as in
+/// fine-tuning) with central differences of step `eps` over `batch`.
+/// The weights are restored afterwards.
+pub fn grad_check(model: &mut Model, batch: &[(Vec<f32>, Vec<f32>)], eps: f32) -> Result<GradCheckReport, String> {
+    let (activation, loss) = (Activation::Relu, Loss::CrossEntropy);
+    let mut layers = [&mut model.lin1, &mut model.lin2];
+    sgd::check_shapes(&layers, batch)?;
+    if batch.is_empty() || eps <= 0.0 {
+        return Err("для проверки градиентов нужны примеры и eps > 0".to_string());
+    }
+    let refs: Vec<&(Vec<f32>, Vec<f32>)> = batch.iter().collect();
+    let (_, analytic) = sgd::gradients(&layers, activation, loss, &refs);
+    let mut report = GradCheckReport { checked: 0, max_abs_error: 0.0, max_rel_error: 0.0, mismatches: Vec::new() };
+
+    let names = ["lin1", "lin2"];
+    for (k, (name, grads)) in names.iter().zip(&analytic).enumerate() {
+        for (kind, expected) in [("weight", &grads.weights), ("bias", &grads.bias)] {
+            for (index, &a) in expected.iter().enumerate() {
+                let set = |layers: &mut [&mut Linear; 2], value: f32| {
+                    let param = layers.get_mut(k).and_then(|l| if kind == "weight" { l.weights.get_mut(index) } else { l.bias.get_mut(index) });
+                    param.map(|p| std::mem::replace(p, value))
+                };
+                let Some(original) = set(&mut layers, 0.0) else { continue };
+                set(&mut layers, original + eps);
+                let up = sgd::evaluate(&layers, activation, loss, batch);
+                set(&mut layers, original - eps);
+                let down = sgd::evaluate(&layers, activation, loss, batch);
+                set(&mut layers, original);
+
+                let numeric = (up - down) / (2.0 * eps);
+                let abs = (a - numeric).abs();
+                let rel = abs / a.abs().max(numeric.abs()).max(f32::MIN_POSITIVE);
+                report.checked += 1;
+                report.max_abs_error = report.max_abs_error.max(abs);
+                if abs > ABS_TOLERANCE {
+                    report.max_rel_error = report.max_rel_error.max(rel);
+                    if rel > REL_TOLERANCE {
+                        report.mismatches.push(Mismatch { param: format!("{}.{}", name, kind), index, analytic: a, numeric, rel_error: rel });
+                    }
+                }
+            }
+        }
+    }
+    report.mismatches.sort_by(|x, y| y.rel_error.total_cmp(&x.rel_error));
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::ALPHABET;
+
+    #[test]
+    fn backprop_agrees_with_finite_differences() {
+        let mut model = Model::new_random((8, 12), 11);
+        let batch: Vec<(Vec<f32>, Vec<f32>)> = ["акула", "shark", "2+2"]
+            .iter()
+            .enumerate()
+            .map(|(i, text)| {
+                let mut target = vec![0.0; ALPHABET.len()];
+                if let Some(t) = target.get_mut(i * 7) {
+                    *t = 1.0;
+                }
+                (model.context_embedding(text), target)
+            })
+            .collect();
+        let before = model.lin1.weights.clone();
+
+        let report = grad_check(&mut model, &batch, 1e-3);
+        assert!(report.as_ref().is_ok_and(|r| r.passed() && r.checked == 8 * 12 + 12 + 12 * ALPHABET.len() + ALPHABET.len()), "{:?}", report.map(|r| r.render()));
+        assert_eq!(model.lin1.weights, before);
+        assert!(grad_check(&mut model, &[(vec![0.0; 3], vec![1.0])], 1e-2).is_err());
+    }
+}
@@ -0,0 +1,157 @@
+#![forbid(unsafe_code)]
+
+//! Structured training log and its Markdown report.
+//!
+//! The log is a CSV file with one `step` row per optimizer step and one
+//! `epoch` row per finished epoch (mean training loss and, when a validation
+//! set is used, the validation loss). Resumed runs append to the same file.
+//! The GUI Training tab and `write_report` both read it back with `read_log`.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::reports::{LineChart, Report, Table};
+
+/// CSV header of the training log.
+pub const HEADER: &str = "kind,step,epoch,loss,lr,val_loss";
+
+/// Whether a row describes one optimizer step or a finished epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordKind {
+    /// one optimizer step (batch loss)
+    Step,
+    /// end of an epoch (mean loss over the epoch)
+    Epoch,
+}
+
+/// One row of the training log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogRecord {
+    /// step or epoch row
+    pub kind: RecordKind,
+    /// optimizer steps taken so far
+    pub step: u64,
+    /// epoch number, starting at 1
+    pub epoch: usize,
+    /// training loss (batch loss for steps, epoch mean for epochs)
+    pub loss: f32,
+    /// learning rate in effect
+    pub lr: f32,
+    /// validation loss (epoch rows with a validation set only)
+    pub val_loss: Option<f32>,
+}
+
+impl LogRecord {
+    fn to_csv(&self) -> String {
+        let kind = match self.kind {
+            RecordKind::Step => "step",
+            RecordKind::Epoch => "epoch",
+        };
+        let val = self.val_loss.map(|v| v.to_string()).unwrap_or_default();
+        format!("{},{},{},{},{},{}", kind, self.step, self.epoch, self.loss, self.lr, val)
+    }
+
+    /// Parse a data row; returns `None` for the header and malformed rows.
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut f = line.trim().split(',');
+        let kind = match f.next()? {
+            "step" => RecordKind::Step,
+            "epoch" => RecordKind::Epoch,
+            _ => return None,
+        };
+        let step = f.next()?.parse().ok()?;
+        let epoch = f.next()?.parse().ok()?;
+        let loss = f.next()?.parse().ok()?;
+        let lr = f.next()?.parse().ok()?;
+        let val_loss = f.next().and_then(|v| v.parse().ok());
+        Some(Self { kind, step, epoch, loss, lr, val_loss })
+    }
+}
+
+/// Append-only writer for the training log.
+pub struct TrainingLog {
+    file: File,
+}
+
+impl TrainingLog {
+    /// Open `path` for appending, writing the header if the file is new or empty.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        if file.metadata()?.len() == 0 {
+            writeln!(file, "{}", HEADER)?;
+        }
+        Ok(Self { file })
+    }
+
+    /// Append one row.
+    pub fn append(&mut self, record: &LogRecord) -> io::Result<()> {
+        writeln!(self.file, "{}", record.to_csv())
+    }
+}
+
+/// Read every well-formed row of a training log.
+pub fn read_log(path: &Path) -> io::Result<Vec<LogRecord>> {
+    Ok(std::fs::read_to_string(path)?.lines().filter_map(LogRecord::parse).collect())
+}
+
+/// Summary of a training log: totals, best validation loss, a per-epoch
+/// table and the loss curves.
+pub fn report(records: &[LogRecord]) -> Report {
+    let epochs: Vec<&LogRecord> = records.iter().filter(|r| r.kind == RecordKind::Epoch).collect();
+    let steps = records.iter().map(|r| r.step).max().unwrap_or(0);
+    let mut report = Report::new("Training report").metric("steps", steps).metric("epochs", epochs.len());
+    if let (Some(first), Some(last)) = (epochs.first(), epochs.last()) {
+        report = report.metric("train loss", format!("{:.4} → {:.4}", first.loss, last.loss));
+    }
+    let best = epochs
+        .iter()
+        .filter_map(|r| r.val_loss.map(|v| (r.epoch, v)))
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+    if let Some((epoch, v)) = best {
+        report = report.metric("best val loss", format!("{:.4} (epoch {})", v, epoch));
+    }
+    let mut table = Table::new(&["epoch", "step", "loss", "val_loss", "lr"]);
+    for r in &epochs {
+        let val = r.val_loss.map(|v| format!("{:.4}", v)).unwrap_or_else(|| "—".to_string());
+        table.push(&[r.epoch.to_string(), r.step.to_string(), format!("{:.4}", r.loss), val, r.lr.to_string()]);
+    }
+    let mut chart = LineChart::new("loss").series("train", epochs.iter().map(|r| (r.epoch as f64, r.loss as f64)));
+    if epochs.iter().any(|r| r.val_loss.is_some()) {
+        chart = chart.series("val", epochs.iter().filter_map(|r| r.val_loss.map(|v| (r.epoch as f64, v as f64))));
+    }
+    report.table(table).chart(chart)
+}
+
+/// `report` as Markdown.
+pub fn render_report(records: &[LogRecord]) -> String {
+    report(records).to_markdown()
+}
+
+/// Write `report` for the log at `log` into `out`, with the loss chart next to it.
+pub fn write_report(log: &Path, out: &Path) -> io::Result<()> {
+    report(&read_log(log)?).write_to(out).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_round_trip_and_report_picks_best_epoch() {
+        let records = vec![
+            LogRecord { kind: RecordKind::Step, step: 1, epoch: 1, loss: 2.0, lr: 0.01, val_loss: None },
+            LogRecord { kind: RecordKind::Epoch, step: 1, epoch: 1, loss: 2.0, lr: 0.01, val_loss: Some(1.5) },
+            LogRecord { kind: RecordKind::Epoch, step: 2, epoch: 2, loss: 1.0, lr: 0.01, val_loss: Some(1.25) },
+        ];
+        let parsed: Vec<LogRecord> = records.iter().filter_map(|r| LogRecord::parse(&r.to_csv())).collect();
+        assert_eq!(parsed, records);
+        assert_eq!(LogRecord::parse(HEADER), None);
+
+        let report = render_report(&records);
+        assert!(report.contains("best val loss: 1.2500 (epoch 2)"), "{}", report);
+    }
+}
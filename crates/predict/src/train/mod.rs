@@ -0,0 +1,500 @@
+#![forbid(unsafe_code)]
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+use regex::Regex;
+
+/// Gradient training for `Linear` layers (backprop, losses, optimizers, mini-batches).
+pub mod sgd;
+/// Fine-tuning the generation model on stored dialogs.
+pub mod finetune;
+/// Datasets: CSV/JSONL loading, seeded shuffling, splits and mini-batches.
+pub mod dataset;
+/// Training runs with validation, checkpoints and early stopping.
+pub mod trainer;
+/// Structured per-step training log (CSV) and its Markdown report.
+pub mod log;
+/// Curriculum self-play: Reasoner-verified synthetic problems for fine-tuning and knowledge.
+pub mod selfplay;
+/// Deterministic synthetic math datasets with verified answers (CSV/JSONL).
+pub mod generate;
+/// Backprop gradients checked against finite differences.
+pub mod gradcheck;
+
+pub use generate::{generate_dataset, DatasetSpec};
+pub use gradcheck::{grad_check, GradCheckReport};
+
+use crate::knowledge_store::ReviewQueue;
+use crate::reports::{self, Table};
+use crate::response::ChatResponse;
+use dataset::QaDataset;
+use evalharness::{Report, Suite};
+
+/// Very small "training" loader that reads a CSV of input→output pairs and prints them.
+/// This is intentionally tiny and side-effecting for demo purposes. Returns the number
+/// of pairs printed.
+pub fn train_from_csv(path: &str) -> io::Result<usize> {
+    let reader = BufReader::new(File::open(path)?);
+
+    println!("[train] loading dataset from {path}");
+    let mut count = 0usize;
+    for (i, line) in reader.lines().enumerate().skip(1) {
+        let line = line?;
+        let parts: Vec<&str> = line.split(',').collect();
+        let [input, output] = parts.as_slice() else { continue };
+        println!("[{i}] Q: {} → A: {}", input.trim_matches('"'), output.trim_matches('"'));
+        count += 1;
+    }
+    println!("[train] dataset ready.");
+    Ok(count)
+}
+
+/// Load a pack of canonical knowledge CSVs from `<data_dir>/knowledge` so the system can
+/// ingest foundational facts. Missing files are skipped; returns the number of pairs read.
+pub fn load_knowledge_pack(data_dir: &Path) -> io::Result<usize> {
+    let base_dir = data_dir.join("knowledge");
+
+    // Start with the canonical list (kept for ordering), then include any additional CSVs found in the folder
+    let mut files: Vec<String> = ["math", "analysis", "geometry", "logic", "relations"]
+        .iter()
+        .map(|topic| base_dir.join(format!("knowledge_{}.csv", topic)).to_string_lossy().to_string())
+        .collect();
+
+    if let Ok(entries) = fs::read_dir(&base_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_file() && path.extension().map(|e| e == "csv").unwrap_or(false) {
+                let s = path.to_string_lossy().to_string();
+                if !files.iter().any(|f| f == &s) {
+                    files.push(s);
+                }
+            }
+        }
+    }
+
+    let mut total = 0usize;
+    for file in files {
+        println!("[knowledge] loading {}", file);
+        if Path::new(&file).exists() {
+            total += train_from_csv(&file).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", file, e)))?;
+        } else {
+            eprintln!("⚠️ Не найдено {} — пропускаем", file);
+        }
+    }
+    Ok(total)
+}
+
+/// Find an exact answer for `question` in a CSV of input,output pairs.
+/// Returns `Some(output)` if a matching input row is found.
+pub fn find_answer(path: &str, question: &str) -> Option<String> {
+    let file = File::open(path).ok()?;
+    let reader = BufReader::new(file);
+
+    for l in reader.lines().skip(1).map_while(Result::ok) {
+        let parts: Vec<&str> = l.split(',').collect();
+        let [input, output] = parts.as_slice() else { continue };
+        if input.trim_matches('"') == question {
+            return Some(output.trim_matches('"').to_string());
+        }
+    }
+    None
+}
+
+/// Try to evaluate a simple arithmetic expression (supports + - * / and parentheses,
+/// see `shark_core_math::expr`); integral results are printed without a fraction.
+pub fn eval_arith(expr: &str) -> Option<String> {
+    let v = shark_core_math::expr::eval(expr)?;
+    if (v - v.round()).abs() < 1e-9 {
+        Some(format!("{}", v.round() as i64))
+    } else {
+        Some(format!("{}", v))
+    }
+}
+
+/// Try to solve simple linear equations with single variable `x`, e.g. "2x + 3 = 7".
+pub fn solve_linear_equation(eq: &str) -> Option<String> {
+    // non-ASCII prompts are never plain equations
+    if !eq.is_ascii() { return None; }
+    let parts: Vec<&str> = eq.split('=').collect();
+    let [left, right] = parts.as_slice() else { return None };
+    let left = left.replace(' ', "");
+    let right = right.replace(' ', "");
+
+    fn coef_and_const(side: &str) -> Option<(f64, f64)> {
+        // split into + / - terms, keeping sign
+        let mut coef = 0.0f64;
+        let mut cons = 0.0f64;
+        let mut rest = side;
+        while !rest.is_empty() {
+            // determine sign
+            let sign = if let Some(r) = rest.strip_prefix('-') {
+                rest = r;
+                -1.0f64
+            } else {
+                rest = rest.strip_prefix('+').unwrap_or(rest);
+                1.0f64
+            };
+            // read term until next + or -
+            let end = rest.find(['+', '-']).unwrap_or(rest.len());
+            let (term, tail) = rest.split_at(end);
+            if term.contains('x') {
+                // coefficient
+                let t = term.replace('x', "");
+                let c = if t.is_empty() { 1.0 } else { t.parse::<f64>().ok()? };
+                coef += sign * c;
+            } else if !term.is_empty() {
+                let v = term.parse::<f64>().ok()?;
+                cons += sign * v;
+            }
+            rest = tail;
+        }
+        Some((coef, cons))
+    }
+
+    let (a1, b1) = coef_and_const(&left)?; // a1*x + b1
+    let (a2, b2) = coef_and_const(&right)?; // a2*x + b2
+    let denom = a1 - a2;
+    if denom.abs() < 1e-12 { return None; }
+    let x = (b2 - b1) / denom;
+    if (x - x.round()).abs() < 1e-9 { Some(format!("x = {}", x.round() as i64)) }
+    else { Some(format!("x = {}", x)) }
+}
+
+/// Check a solution `x` of a linear equation by substituting it into both
+/// sides (`2x` reads as `2*x`); true if they agree within 1e-9.
+pub fn verify_linear_solution(eq: &str, x: f64) -> bool {
+    let substitute = |side: &str| {
+        let mut expr = String::with_capacity(side.len() + 8);
+        for c in side.chars() {
+            if c == 'x' {
+                if expr.ends_with(|p: char| p.is_ascii_digit() || p == ')') {
+                    expr.push('*');
+                }
+                expr.push_str(&format!("({})", x));
+            } else {
+                expr.push(c);
+            }
+        }
+        meval::eval_str(&expr).ok()
+    };
+    let Some((left, right)) = eq.split_once('=') else { return false };
+    matches!((substitute(left), substitute(right)), (Some(l), Some(r)) if (l - r).abs() < 1e-9)
+}
+
+/// Load problems from CSV `question,expected` (header optional) or JSONL. Returns vector of pairs;
+/// a missing or malformed file yields no problems.
+pub fn load_problems(path: &str) -> Vec<(String, String)> {
+    QaDataset::load(path).map(|d| d.items).unwrap_or_default()
+}
+
+/// Normalize an answer for comparison (trim, lowercase, drop spaces).
+pub fn normalize_answer(s: &str) -> String {
+    s.trim().to_lowercase().replace(' ', "")
+}
+
+/// Load an evaluation suite: a JSON suite, a `question,expected[,category[,matcher]]`
+/// CSV, or a JSONL dataset (exact matching).
+pub fn load_suite(path: &str) -> Result<Suite, String> {
+    if Path::new(path).extension().is_some_and(|e| e.eq_ignore_ascii_case("jsonl")) {
+        let data = QaDataset::load(path).map_err(|e| format!("{}: {}", path, e))?;
+        let name = Path::new(path).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        return Ok(Suite::from_pairs(&name, evalharness::suite::DEFAULT_CATEGORY, &data.items));
+    }
+    Suite::load(Path::new(path)).map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Answer a question the way the evaluator does: the AI's pipeline, without
+/// recording the exchange in the dialog memory.
+pub fn eval_answer(ai: &mut crate::AI, q: &str) -> ChatResponse {
+    let pipeline = std::sync::Arc::clone(&ai.pipeline);
+    pipeline.answer(ai, q)
+}
+
+/// Report file of `evaluate_suite` in the reports directory.
+pub const PROBLEMS_REPORT: &str = "problems_report.md";
+
+/// Run `suite` through `eval_answer`. Writes a short report to
+/// `problems_report.md` in `reports::output_dir()` and records every failed task as an unknown
+/// (and a `UNKNOWN` knowledge placeholder) in `ai.config.data.dir` for later re-learning.
+pub fn evaluate_suite(ai: &mut crate::AI, suite: &Suite) -> Report {
+    let unknowns = ai.config.data.dir.join("unknowns.csv").to_string_lossy().to_string();
+    let knowledge = ai.config.knowledge_path().to_string_lossy().to_string();
+    let mut sources = Vec::with_capacity(suite.tasks.len());
+    let result = evalharness::run_suite(suite, |task| {
+        let response = eval_answer(ai, &task.question);
+        sources.push(response.source);
+        response.text
+    });
+    let mut table = Table::new(&["#", "question", "answer", "source", "expected", "result"]);
+    for (i, (r, source)) in result.results.iter().zip(&sources).enumerate() {
+        table.push(&[(i + 1).to_string(), r.question.clone(), r.answer.clone(), source.as_str().to_string(), r.expected.clone(), if r.passed { "✅ OK" } else { "❌ MISMATCH" }.to_string()]);
+        if !r.passed {
+            // record unknown for later automatic re-learning
+            if let Err(e) = append_unknown(&unknowns, &r.question, &r.expected) {
+                eprintln!("⚠️ {}: {}", unknowns, e);
+            }
+            // Also append a placeholder to knowledge.csv so the system remembers the failure
+            // and will attempt to re-solve it on next runs (self-learning loop).
+            // Use a sentinel answer "UNKNOWN"; avoid duplicates.
+            if find_answer(&knowledge, &r.question).is_none() {
+                match append_knowledge(&knowledge, &r.question, "UNKNOWN") {
+                    Ok(()) => println!("[learn] добавлена новая задача в knowledge.csv для повторного изучения: {}", r.question),
+                    Err(e) => eprintln!("⚠️ {}: {}", knowledge, e),
+                }
+            }
+        }
+    }
+
+    let summary = format!("Summary: {}/{} solved", result.passed, result.total);
+    let mut categories = Table::new(&["category", "solved", "total"]);
+    for (name, c) in &result.categories {
+        categories.push(&[name.clone(), c.passed.to_string(), c.total.to_string()]);
+    }
+    let report = reports::Report::new(format!("Problems report — {} entries", result.total))
+        .metric("solved", format!("{}/{}", result.passed, result.total))
+        .table(categories)
+        .heading("Tasks")
+        .table(table);
+    if let Err(e) = report.write(PROBLEMS_REPORT) {
+        eprintln!("⚠️ {}: {}", reports::path(PROBLEMS_REPORT).display(), e);
+    }
+    println!("[train] {}", summary);
+    result
+}
+
+/// Evaluate problems using available heuristics and AI fallback (see `evaluate_suite`)
+/// and return (successes, total).
+pub fn evaluate_problems(ai: &mut crate::AI, path: &str) -> (usize, usize) {
+    let suite = load_suite(path).unwrap_or_default();
+    let report = evaluate_suite(ai, &suite);
+    (report.passed, report.total)
+}
+
+/// Queue an unknown problem for relearning (`knowledge_store::ReviewQueue`
+/// in the CSV at `path`); already queued problems keep their schedule.
+pub fn append_unknown(path: &str, question: &str, expected: &str) -> io::Result<()> {
+    let mut queue = ReviewQueue::load(Path::new(path))?;
+    if queue.add(question, expected, chrono::Utc::now()) {
+        queue.save(Path::new(path))?;
+    }
+    Ok(())
+}
+
+/// Load unknowns into memory as (question, expected, failed attempts)
+pub fn load_unknowns(path: &str) -> Vec<(String, String, i32)> {
+    let queue = ReviewQueue::load(Path::new(path)).unwrap_or_default();
+    queue.items.into_iter().map(|i| (i.question, i.expected, i.repetitions as i32)).collect()
+}
+
+/// Remove an unknown entry (exact match on question and expected)
+pub fn remove_unknown(path: &str, question: &str, expected: &str) -> io::Result<()> {
+    let mut queue = ReviewQueue::load(Path::new(path))?;
+    if queue.remove(question, expected) {
+        queue.save(Path::new(path))?;
+    }
+    Ok(())
+}
+
+/// Try to relearn the unknowns that are due for review: return (learned, due).
+/// A wrong answer or none reschedules the problem further out
+/// (`ReviewItem::fail`), so each one is retried on an increasing schedule
+/// rather than on every run. Fails if the knowledge.csv of `ai.config` or the
+/// unknowns file cannot be updated.
+pub fn try_relearn_unknowns(ai: &mut crate::AI, path: &str, accept_confirmations: usize) -> io::Result<(usize, usize)> {
+    let knowledge = ai.config.knowledge_path().to_string_lossy().to_string();
+    let mut queue = ReviewQueue::load(Path::new(path))?;
+    let now = chrono::Utc::now();
+    let due: Vec<(String, String)> = queue.due(now).into_iter().map(|i| (i.question.clone(), i.expected.clone())).collect();
+    let mut learned = 0usize;
+    for (q, expected) in &due {
+        // try heuristics
+        let mut answer = None;
+        if let Some(a) = find_answer(&knowledge, q) { answer = Some(a); }
+        if answer.is_none() {
+            // sanitized
+            let sanitized: String = q.chars().filter(|c| c.is_ascii() && (c.is_ascii_digit() || c.is_ascii_alphabetic() || 
+                "+-*/=()^ .".contains(*c))).collect();
+            if let Some(a) = eval_arith(&sanitized) { answer = Some(a); }
+        }
+        if answer.is_none() {
+            // unrelated questions: no follow-up resolution between them
+            ai.dialogue.clear();
+            let resp1 = ai.chat_text(q);
+            // require N confirmations
+            let mut agrees = 1usize;
+            for _ in 1..accept_confirmations {
+                ai.dialogue.clear();
+                let respn = ai.chat_text(q);
+                if respn == resp1 { agrees += 1; }
+            }
+            if agrees >= accept_confirmations { answer = Some(resp1); }
+        }
+
+        match answer {
+            Some(ans) if normalize_answer(&ans) == normalize_answer(expected) => {
+                // accept and add to knowledge
+                append_knowledge(&knowledge, q, &ans)?;
+                queue.remove(q, expected);
+                learned += 1;
+            }
+            // a stable but wrong answer is closer than none at all
+            Some(_) => queue.fail(q, expected, 2, now),
+            None => queue.fail(q, expected, 1, now),
+        }
+    }
+    if !due.is_empty() {
+        queue.save(Path::new(path))?;
+    }
+    Ok((learned, due.len()))
+}
+
+/// Append a QA pair to knowledge CSV (naive append) and publish `Event::KnowledgeAdded`.
+pub fn append_knowledge(path: &str, question: &str, answer: &str) -> io::Result<()> {
+    let mut f = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(f, "\"{}\",\"{}\"", question.replace("\n", " "), answer.replace("\n", " "))?;
+    crate::events::publish(crate::events::Event::KnowledgeAdded { question: question.to_string(), answer: answer.to_string() });
+    Ok(())
+}
+
+/// Load Rust source knowledge CSV (file,description) into memory.
+pub fn load_rust_knowledge(path: &str) -> Vec<(String, String)> {
+    let file = File::open(path).ok();
+    let reader = file.map(BufReader::new);
+    let mut result = Vec::new();
+
+    if let Some(r) = reader {
+        for l in r.lines().skip(1).map_while(Result::ok) {
+            // split on first comma only (description may contain commas)
+            if let Some((file, desc)) = l.split_once(',') {
+                result.push((file.trim_matches('"').to_string(), desc.trim().trim_matches('"').to_string()));
+            }
+        }
+    }
+    result
+}
+
+/// Scan a source directory for `.rs` files, extract simple metrics and function names,
+/// and write an updated `knowledge_rust.csv` (overwrites).
+pub fn scan_src_and_update_knowledge(src_dir: &str, out_csv: &str) -> io::Result<()> {
+    let mut rows: Vec<(String, String)> = Vec::new();
+    let fn_re = Regex::new(r"fn\s+([a-zA-Z0-9_]+)").map_err(io::Error::other)?;
+    let struct_re = Regex::new(r"struct\s+([A-Za-z0-9_]+)").map_err(io::Error::other)?;
+    let impl_re = Regex::new(r"impl\b").map_err(io::Error::other)?;
+
+    for entry in fs::read_dir(src_dir)? {
+        let path = entry?.path();
+        if !path.is_file() || path.extension().is_none_or(|ext| ext != "rs") {
+            continue;
+        }
+        let Some(fname) = path.file_name().map(|n| n.to_string_lossy().to_string()) else { continue };
+        let text = fs::read_to_string(&path)?;
+        let fn_names: Vec<String> = fn_re.captures_iter(&text)
+            .filter_map(|c| c.get(1).map(|m| m.as_str().to_string())).take(10).collect();
+        let structs = struct_re.captures_iter(&text).count();
+        let impls = impl_re.find_iter(&text).count();
+        let fns = fn_names.len();
+        let desc = format!("fns={} structs={} impls={} funcs=[{}]",
+            fns, structs, impls, fn_names.join(", "));
+        rows.push((fname, desc));
+    }
+
+    // write CSV
+    let mut f = OpenOptions::new().create(true).write(true).truncate(true).open(out_csv)?;
+    writeln!(f, "file,description")?;
+    for (file, desc) in rows {
+        writeln!(f, "\"{}\",\"{}\"", file, desc.replace('"', "'"))?;
+    }
+    Ok(())
+}
+
+/// Auto-generate the Rust knowledge CSV `out_csv` and a simple code tree markdown for docs.
+pub fn auto_update_and_visualize_structure(out_csv: &str) -> io::Result<()> {
+    let src_dir = "crates/predict/src";
+    let out_tree = reports::path("code_tree.md");
+
+    let mut csv_content = String::from("file,description\n");
+    let mut tree = String::from("# 🧩 Shark-Core Code Tree\n\n```\n");
+
+    fn analyze_file(path: &Path) -> (usize, usize, usize, Vec<String>) {
+        let text = fs::read_to_string(path).unwrap_or_default();
+        let fn_count = text.matches("fn ").count();
+        let struct_count = text.matches("struct ").count();
+        let impl_count = text.matches("impl ").count();
+        let funcs = text
+            .lines()
+            .filter_map(|l| {
+                let t = l.trim_start();
+                if t.starts_with("fn ") {
+                    t.split_whitespace().nth(1).map(|s| s.trim_end_matches('(').to_string())
+                } else { None }
+            })
+            .take(10)
+            .collect();
+        (fn_count, struct_count, impl_count, funcs)
+    }
+
+    for entry in fs::read_dir(src_dir)? {
+        let path = entry?.path();
+        if path.extension().map(|e| e == "rs").unwrap_or(false) {
+            if let Some(file) = path.file_name().map(|n| n.to_string_lossy().to_string()) {
+                let (fns, structs, impls, funcs) = analyze_file(&path);
+
+                csv_content.push_str(&format!(
+                    "\"{}\",\"fns={} structs={} impls={} funcs={:?}\"\n",
+                    file, fns, structs, impls, funcs
+                ));
+
+                tree.push_str(&format!("{} (fns={}, structs={})\n", file, fns, structs));
+            }
+        }
+    }
+
+    tree.push_str("```\n");
+
+    fs::create_dir_all(reports::output_dir())?;
+    fs::write(out_csv, csv_content)?;
+    fs::write(&out_tree, tree)?;
+
+    println!("[auto-doc] обновлены {} и {}", out_csv, out_tree.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn heuristics_and_missing_files() {
+        assert_eq!(eval_arith("2 + 3 * (4 - 1)").as_deref(), Some("11"));
+        assert_eq!(eval_arith("1 +"), None);
+        assert_eq!(solve_linear_equation("2x + 3 = 7").as_deref(), Some("x = 2"));
+        assert_eq!(solve_linear_equation("-x + 4 = x").as_deref(), Some("x = 2"));
+        assert_eq!(solve_linear_equation("x = x"), None);
+        assert!(train_from_csv("/nonexistent/knowledge.csv").is_err());
+    }
+
+    /// Well-formed expressions: non-negative integers and decimals joined by
+    /// `+ - * /`, some of them parenthesized.
+    fn expression() -> impl Strategy<Value = String> {
+        let leaf = prop_oneof![(0u32..1000).prop_map(|n| n.to_string()), (0u32..100, 1u32..100).prop_map(|(a, b)| format!("{}.{}", a, b))];
+        leaf.prop_recursive(4, 24, 2, |inner| {
+            (inner.clone(), prop::sample::select(vec!['+', '-', '*', '/']), inner, any::<bool>())
+                .prop_map(|(a, op, b, parens)| if parens { format!("({} {} {})", a, op, b) } else { format!("{} {} {}", a, op, b) })
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn eval_arith_matches_meval(expr in expression()) {
+            let expected = meval::eval_str(&expr);
+            prop_assume!(expected.as_ref().is_ok_and(|v| v.is_finite()));
+            let expected = expected.unwrap_or_default();
+            let actual = eval_arith(&expr).and_then(|v| v.parse::<f64>().ok());
+            // eval_arith rounds results within 1e-9 of an integer
+            prop_assert!(actual.is_some_and(|v| (v - expected).abs() <= 1e-8 * expected.abs().max(1.0)), "{} = {} (meval), {:?}", expr, expected, actual);
+        }
+    }
+}
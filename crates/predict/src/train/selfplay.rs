@@ -0,0 +1,303 @@
+#![forbid(unsafe_code)]
+
+//! Curriculum self-play between the Reasoner and the model.
+//!
+//! Each round draws random problems (arithmetic, linear equations, definite
+//! integrals of `x^n`) whose answers are known by construction, asks
+//! `Reasoner::solve` for a solution and keeps only the pairs where the
+//! Reasoner's answer matches. Rounds get harder one by one (larger numbers,
+//! more operators, higher powers). The verified pairs fine-tune the model on
+//! everything accepted so far and are appended to the knowledge base, with a
+//! provenance copy tagged `selfplay` in `knowledge/knowledge_selfplay.csv`.
+
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+
+use rand::Rng;
+
+use crate::core;
+use crate::model::Model;
+use crate::reasoner::Reasoner;
+use crate::train::dataset::QaDataset;
+use crate::train::finetune::examples_from_pairs;
+use crate::train::sgd::{self, Loss, OptimizerConfig, TrainConfig};
+
+/// Provenance tag written next to self-play facts.
+pub const PROVENANCE: &str = "selfplay";
+
+/// Kind of generated problem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProblemKind {
+    /// `12 + 7 * 3`
+    Arith,
+    /// `3x + 5 = 20`
+    Linear,
+    /// `Посчитай интеграл x^2 от 0 до 3`
+    Integral,
+}
+
+impl ProblemKind {
+    /// Every kind, in curriculum order.
+    pub const ALL: [ProblemKind; 3] = [ProblemKind::Arith, ProblemKind::Linear, ProblemKind::Integral];
+
+    /// Lowercase name: `arith`, `linear`, `integral`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProblemKind::Arith => "arith",
+            ProblemKind::Linear => "linear",
+            ProblemKind::Integral => "integral",
+        }
+    }
+
+    /// Kind named by `as_str`.
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.as_str() == name.trim())
+    }
+}
+
+/// A generated problem with its answer known by construction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    /// problem family
+    pub kind: ProblemKind,
+    /// question as a user would type it
+    pub question: String,
+    /// exact numeric answer
+    pub expected: f64,
+}
+
+/// A problem the Reasoner solved correctly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelfplayPair {
+    /// problem family
+    pub kind: ProblemKind,
+    /// curriculum level it was drawn at
+    pub level: usize,
+    /// the question
+    pub question: String,
+    /// the Reasoner's (verified) answer
+    pub answer: String,
+}
+
+/// Settings for `run`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelfplayConfig {
+    /// curriculum rounds; round `n` draws problems at level `n`
+    pub rounds: usize,
+    /// problems drawn per round
+    pub per_round: usize,
+    /// problem families to draw from
+    pub kinds: Vec<ProblemKind>,
+    /// seed for problem generation
+    pub seed: u64,
+    /// fine-tune the model after every round; `None` only collects pairs
+    pub finetune: Option<TrainConfig>,
+    /// train on at most this many characters of each answer
+    pub max_answer_chars: usize,
+    /// central knowledge base to append new facts to (`None` skips it)
+    pub knowledge_path: Option<String>,
+    /// provenance file for new facts (`None` skips it)
+    pub provenance_path: Option<String>,
+}
+
+impl Default for SelfplayConfig {
+    fn default() -> Self {
+        Self {
+            rounds: 3,
+            per_round: 20,
+            kinds: ProblemKind::ALL.to_vec(),
+            seed: 42,
+            finetune: Some(TrainConfig {
+                loss: Loss::CrossEntropy,
+                optimizer: OptimizerConfig::adam(1e-3),
+                epochs: 3,
+                ..TrainConfig::default()
+            }),
+            max_answer_chars: 64,
+            knowledge_path: None,
+            provenance_path: None,
+        }
+    }
+}
+
+/// What one curriculum round did.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundReport {
+    /// difficulty level (1-based)
+    pub level: usize,
+    /// problems drawn
+    pub generated: usize,
+    /// problems the Reasoner solved correctly
+    pub verified: usize,
+    /// mean loss per fine-tuning epoch (empty without fine-tuning)
+    pub losses: Vec<f32>,
+}
+
+/// What a self-play run did.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SelfplayReport {
+    /// per-round statistics
+    pub rounds: Vec<RoundReport>,
+    /// every verified pair, in generation order
+    pub pairs: Vec<SelfplayPair>,
+    /// facts appended to the knowledge base (questions it did not know yet)
+    pub added_knowledge: usize,
+}
+
+/// Draw one problem of `kind` at difficulty `level` (1 = easiest).
+pub fn generate_problem<R: Rng + ?Sized>(kind: ProblemKind, level: usize, rng: &mut R) -> Problem {
+    let level = level.max(1) as i64;
+    let (question, expected) = match kind {
+        ProblemKind::Arith => {
+            let max = 10 * level;
+            let (a, b) = (rng.gen_range(0..=max), rng.gen_range(0..=max));
+            let ops: &[char] = if level == 1 { &['+', '-'] } else { &['+', '-', '*'] };
+            let op = ops.get(rng.gen_range(0..ops.len())).copied().unwrap_or('+');
+            let value = apply(a, op, b);
+            if level >= 3 {
+                let c = rng.gen_range(1..=max);
+                (format!("({} {} {}) * {}", a, op, b, c), value * c)
+            } else {
+                (format!("{} {} {}", a, op, b), value)
+            }
+        }
+        ProblemKind::Linear => {
+            let a = rng.gen_range(1..=2 + level);
+            let x = rng.gen_range(-5 * level..=5 * level);
+            let b = rng.gen_range(0..=10 * level);
+            let coef = if a == 1 { String::new() } else { a.to_string() };
+            (format!("{}x + {} = {}", coef, b, a * x + b), x)
+        }
+        ProblemKind::Integral => {
+            let n = rng.gen_range(1..=level.min(4)) as i32;
+            let upper = rng.gen_range(1..=level + 1);
+            let value = f64::from(upper as i32).powi(n + 1) / f64::from(n + 1);
+            return Problem { kind, question: format!("Посчитай интеграл x^{} от 0 до {}", n, upper), expected: value };
+        }
+    };
+    Problem { kind, question, expected: expected as f64 }
+}
+
+fn apply(a: i64, op: char, b: i64) -> i64 {
+    match op {
+        '-' => a - b,
+        '*' => a * b,
+        _ => a + b,
+    }
+}
+
+/// The number in a Reasoner answer: `"7"`, `"x = -3"`, `"Интеграл x^2 от 0 до 3 = 9.0000"`.
+fn answer_value(answer: &str) -> Option<f64> {
+    answer.rsplit('=').next()?.trim().parse().ok()
+}
+
+/// Ask the Reasoner (without side effects) and return its answer if it is correct.
+pub fn verify(problem: &Problem) -> Option<String> {
+    let (answer, _) = Reasoner::solve(&problem.question);
+    let value = answer_value(&answer)?;
+    // integrals are printed with four decimals
+    ((value - problem.expected).abs() < 1e-3).then_some(answer)
+}
+
+/// Draw `count` problems at `level`, cycling through `kinds`, and keep the verified ones.
+pub fn play_round<R: Rng + ?Sized>(level: usize, count: usize, kinds: &[ProblemKind], rng: &mut R) -> (usize, Vec<SelfplayPair>) {
+    let mut pairs = Vec::new();
+    let mut generated = 0;
+    for kind in kinds.iter().cycle().take(if kinds.is_empty() { 0 } else { count }) {
+        let problem = generate_problem(*kind, level, rng);
+        generated += 1;
+        if let Some(answer) = verify(&problem) {
+            pairs.push(SelfplayPair { kind: *kind, level, question: problem.question, answer });
+        }
+    }
+    (generated, pairs)
+}
+
+/// Append pairs whose question is not in `knowledge_path` yet; every appended
+/// pair also goes to `provenance_path` tagged `selfplay`. Returns how many were added.
+pub fn append_to_knowledge(pairs: &[SelfplayPair], knowledge_path: Option<&str>, provenance_path: Option<&str>) -> io::Result<usize> {
+    let mut known: HashSet<String> = knowledge_path
+        .and_then(|p| QaDataset::load(p).ok())
+        .map(|d| d.items.into_iter().map(|(q, _)| q.to_lowercase()).collect())
+        .unwrap_or_default();
+    let fresh: Vec<&SelfplayPair> = pairs.iter().filter(|p| known.insert(p.question.to_lowercase())).collect();
+    if let Some(path) = knowledge_path {
+        for pair in &fresh {
+            crate::train::append_knowledge(path, &pair.question, &pair.answer)?;
+        }
+    }
+    if let Some(path) = provenance_path.filter(|_| !fresh.is_empty()) {
+        if let Some(dir) = std::path::Path::new(path).parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut f = OpenOptions::new().create(true).append(true).open(path)?;
+        if f.metadata()?.len() == 0 {
+            writeln!(f, "question,answer,source,level,date")?;
+        }
+        let now = chrono::Utc::now().to_rfc3339();
+        for pair in &fresh {
+            writeln!(f, "\"{}\",\"{}\",{},{},{}", pair.question.replace('"', "'"), pair.answer.replace('"', "'"), PROVENANCE, pair.level, now)?;
+        }
+    }
+    Ok(fresh.len())
+}
+
+/// Run the curriculum: play every round, fine-tune `model` on all pairs
+/// verified so far after each round, then store the new facts.
+pub fn run(model: &mut Model, cfg: &SelfplayConfig) -> Result<SelfplayReport, String> {
+    let mut rng = core::make_rng(cfg.seed);
+    let mut report = SelfplayReport::default();
+    for level in 1..=cfg.rounds {
+        let (generated, pairs) = play_round(level, cfg.per_round, &cfg.kinds, &mut rng);
+        let verified = pairs.len();
+        report.pairs.extend(pairs);
+        let mut losses = Vec::new();
+        if let Some(train) = &cfg.finetune {
+            let qa: Vec<(String, String)> = report.pairs.iter().map(|p| (p.question.clone(), p.answer.clone())).collect();
+            let examples = examples_from_pairs(model, &qa, cfg.max_answer_chars);
+            if !examples.is_empty() {
+                losses = sgd::fit_model(model, &examples, &TrainConfig { loss: Loss::CrossEntropy, ..*train })?;
+            }
+        }
+        report.rounds.push(RoundReport { level, generated, verified, losses });
+    }
+    report.added_knowledge = append_to_knowledge(&report.pairs, cfg.knowledge_path.as_deref(), cfg.provenance_path.as_deref())
+        .map_err(|e| format!("не удалось записать знания: {}", e))?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reasoner_solves_generated_problems_at_every_level() {
+        let mut rng = core::make_rng(5);
+        for level in 1..=4 {
+            for kind in ProblemKind::ALL {
+                let problem = generate_problem(kind, level, &mut rng);
+                assert!(verify(&problem).is_some(), "{:?}", problem);
+            }
+        }
+        assert_eq!(answer_value("x = -3"), Some(-3.0));
+    }
+
+    #[test]
+    fn new_facts_are_appended_once_with_provenance() {
+        let dir = std::env::temp_dir().join(format!("shark_selfplay_{}", std::process::id()));
+        let knowledge = dir.join("knowledge.csv").to_string_lossy().to_string();
+        let provenance = dir.join("knowledge_selfplay.csv").to_string_lossy().to_string();
+        let _ = std::fs::create_dir_all(&dir);
+        let _ = std::fs::write(&knowledge, "question,answer\n\"1 + 1\",\"2\"\n");
+        let pair = |q: &str, a: &str| SelfplayPair { kind: ProblemKind::Arith, level: 1, question: q.into(), answer: a.into() };
+        let pairs = [pair("1 + 1", "2"), pair("2 + 3", "5"), pair("2 + 3", "5")];
+
+        let added = append_to_knowledge(&pairs, Some(&knowledge), Some(&provenance));
+        let again = append_to_knowledge(&pairs, Some(&knowledge), Some(&provenance));
+        let tagged = std::fs::read_to_string(&provenance).unwrap_or_default();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!((added.ok(), again.ok()), (Some(1), Some(0)));
+        assert!(tagged.contains("\"2 + 3\",\"5\",selfplay,1,"), "{}", tagged);
+    }
+}
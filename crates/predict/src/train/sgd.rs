@@ -0,0 +1,506 @@
+#![forbid(unsafe_code)]
+
+//! Gradient training for stacks of `Linear` layers.
+//!
+//! A stack is a slice of layers with the same activation between consecutive
+//! layers and none after the last one, so the final layer yields raw outputs
+//! (regression values or logits). `fit` runs mini-batch backprop with either
+//! plain SGD (optionally with momentum) or Adam.
+//!
+//! Against overfitting small corpora, `TrainConfig::dropout` zeroes hidden
+//! activations at random during training (inverted dropout: survivors are
+//! scaled by `1 / (1 - rate)`, so evaluation needs no change) and
+//! `TrainConfig::weight_decay` adds an L2 penalty `decay * w` to the gradient
+//! of every weight (biases are not decayed).
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::core;
+use crate::linear::Linear;
+use crate::model::Model;
+use crate::train::trainer::{self, RunConfig};
+
+/// Training objective applied to the last layer's output.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Loss {
+    /// Mean squared error against a target vector.
+    Mse,
+    /// Softmax cross-entropy; the target is a probability distribution (usually one-hot).
+    CrossEntropy,
+}
+
+impl Loss {
+    /// Loss value and its gradient with respect to `output`.
+    pub fn compute(self, output: &[f32], target: &[f32]) -> (f32, Vec<f32>) {
+        match self {
+            Loss::Mse => {
+                let n = output.len().max(1) as f32;
+                let diff: Vec<f32> = output.iter().zip(target).map(|(o, t)| o - t).collect();
+                let loss = diff.iter().map(|d| d * d).sum::<f32>() / n;
+                (loss, diff.iter().map(|d| 2.0 * d / n).collect())
+            }
+            Loss::CrossEntropy => {
+                let mut probs = output.to_vec();
+                core::softmax(&mut probs);
+                let loss = -probs.iter().zip(target).map(|(p, t)| t * p.max(1e-12).ln()).sum::<f32>();
+                (loss, probs.iter().zip(target).map(|(p, t)| p - t).collect())
+            }
+        }
+    }
+}
+
+/// Activation applied between consecutive layers.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Activation {
+    /// No activation.
+    Identity,
+    /// max(0, x), as used by `Model::generate`.
+    Relu,
+    /// Hyperbolic tangent.
+    Tanh,
+}
+
+impl Activation {
+    fn apply(self, x: f32) -> f32 {
+        match self {
+            Activation::Identity => x,
+            Activation::Relu => x.max(0.0),
+            Activation::Tanh => x.tanh(),
+        }
+    }
+
+    /// Derivative expressed through the activation's output `y`.
+    fn derivative(self, y: f32) -> f32 {
+        match self {
+            Activation::Identity => 1.0,
+            Activation::Relu => if y > 0.0 { 1.0 } else { 0.0 },
+            Activation::Tanh => 1.0 - y * y,
+        }
+    }
+}
+
+/// Accumulated parameter gradients of one `Linear` layer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradients {
+    /// d loss / d weights, same layout as `Linear::weights`
+    pub weights: Vec<f32>,
+    /// d loss / d bias
+    pub bias: Vec<f32>,
+}
+
+impl Gradients {
+    /// Zero gradients shaped like `layer`.
+    pub fn zeros(layer: &Linear) -> Self {
+        Self { weights: vec![0.0; layer.weights.len()], bias: vec![0.0; layer.bias.len()] }
+    }
+}
+
+/// Backward pass of one layer: accumulates parameter gradients for `input`
+/// into `grads` and returns the gradient with respect to `input`.
+pub fn linear_backward(layer: &Linear, input: &[f32], grad_out: &[f32], grads: &mut Gradients) -> Vec<f32> {
+    let in_dim = layer.in_dim.max(1);
+    let mut grad_in = vec![0.0_f32; layer.in_dim];
+    let rows = layer.weights.chunks(in_dim).zip(grads.weights.chunks_mut(in_dim));
+    for (((w_row, g_row), go), gb) in rows.zip(grad_out).zip(grads.bias.iter_mut()) {
+        *gb += go;
+        for ((g, w), (x, gi)) in g_row.iter_mut().zip(w_row).zip(input.iter().zip(grad_in.iter_mut())) {
+            *g += go * x;
+            *gi += go * w;
+        }
+    }
+    grad_in
+}
+
+/// Forward pass through a layer stack (no dropout).
+pub fn forward(layers: &[&mut Linear], activation: Activation, input: &[f32]) -> Vec<f32> {
+    trace(layers, activation, input, None).output
+}
+
+/// Activations of a forward pass, kept for backprop.
+struct Trace {
+    /// input of every layer (hidden ones after dropout)
+    inputs: Vec<Vec<f32>>,
+    /// dropout scale of every input unit (0 dropped, `1 / (1 - rate)` kept);
+    /// empty for the network input and without dropout
+    masks: Vec<Vec<f32>>,
+    output: Vec<f32>,
+}
+
+/// Forward pass; with `dropout = Some((rate, rng))` hidden activations are dropped.
+fn trace(layers: &[&mut Linear], activation: Activation, input: &[f32], mut dropout: Option<(f32, &mut dyn rand::RngCore)>) -> Trace {
+    let mut inputs = Vec::with_capacity(layers.len());
+    let mut masks = Vec::with_capacity(layers.len());
+    let (mut x, mut mask) = (input.to_vec(), Vec::new());
+    for (k, layer) in layers.iter().enumerate() {
+        let mut z = layer.forward(&x);
+        let mut next_mask = Vec::new();
+        if k + 1 < layers.len() {
+            z.iter_mut().for_each(|v| *v = activation.apply(*v));
+            if let Some((rate, rng)) = dropout.as_mut().filter(|(rate, _)| *rate > 0.0) {
+                let keep = 1.0 / (1.0 - *rate);
+                next_mask = z.iter().map(|_| if rng.gen::<f32>() < *rate { 0.0 } else { keep }).collect();
+                z.iter_mut().zip(&next_mask).for_each(|(v, m)| *v *= m);
+            }
+        }
+        inputs.push(std::mem::replace(&mut x, z));
+        masks.push(std::mem::replace(&mut mask, next_mask));
+    }
+    Trace { inputs, masks, output: x }
+}
+
+/// Optimizer and its hyper-parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum OptimizerConfig {
+    /// Stochastic gradient descent with optional momentum (0 disables it).
+    Sgd {
+        /// learning rate
+        lr: f32,
+        /// momentum factor
+        momentum: f32,
+    },
+    /// Adam (Kingma & Ba) with bias correction.
+    Adam {
+        /// learning rate
+        lr: f32,
+        /// first moment decay
+        beta1: f32,
+        /// second moment decay
+        beta2: f32,
+        /// numerical stabilizer
+        eps: f32,
+    },
+}
+
+impl OptimizerConfig {
+    /// Plain SGD with learning rate `lr`.
+    pub fn sgd(lr: f32) -> Self {
+        OptimizerConfig::Sgd { lr, momentum: 0.0 }
+    }
+
+    /// Adam with the usual defaults (0.9, 0.999, 1e-8).
+    pub fn adam(lr: f32) -> Self {
+        OptimizerConfig::Adam { lr, beta1: 0.9, beta2: 0.999, eps: 1e-8 }
+    }
+
+    /// Learning rate.
+    pub fn lr(&self) -> f32 {
+        match *self {
+            OptimizerConfig::Sgd { lr, .. } | OptimizerConfig::Adam { lr, .. } => lr,
+        }
+    }
+}
+
+/// Optimizer state: step counter and per-parameter moment buffers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Optimizer {
+    /// hyper-parameters
+    pub config: OptimizerConfig,
+    /// number of applied updates
+    pub t: u64,
+    /// (first, second) moments per parameter buffer, in layer order (weights, bias)
+    pub moments: Vec<(Vec<f32>, Vec<f32>)>,
+    /// L2 coefficient added to weight gradients (0 disables)
+    #[serde(default)]
+    pub weight_decay: f32,
+}
+
+impl Optimizer {
+    /// Fresh optimizer; moment buffers are allocated on the first step.
+    pub fn new(config: OptimizerConfig) -> Self {
+        Self { config, t: 0, moments: Vec::new(), weight_decay: 0.0 }
+    }
+
+    /// The same optimizer with L2 weight decay `decay`.
+    pub fn with_weight_decay(self, decay: f32) -> Self {
+        Self { weight_decay: decay, ..self }
+    }
+
+    /// Apply one update using gradients scaled by `scale` (e.g. 1 / batch size).
+    pub fn step(&mut self, layers: &mut [&mut Linear], grads: &[Gradients], scale: f32) {
+        if self.moments.is_empty() {
+            self.moments = grads
+                .iter()
+                .flat_map(|g| [g.weights.len(), g.bias.len()])
+                .map(|n| (vec![0.0; n], vec![0.0; n]))
+                .collect();
+        }
+        self.t += 1;
+        let t = self.t as i32;
+        let config = self.config;
+        let params = layers.iter_mut().flat_map(|l| {
+            let l = &mut **l;
+            [(&mut l.weights, self.weight_decay), (&mut l.bias, 0.0)]
+        });
+        let grads = grads.iter().flat_map(|g| [&g.weights, &g.bias]);
+        for (((param, decay), grad), (m, v)) in params.zip(grads).zip(self.moments.iter_mut()) {
+            for (((p, g), m), v) in param.iter_mut().zip(grad).zip(m.iter_mut()).zip(v.iter_mut()) {
+                let g = g * scale + decay * *p;
+                match config {
+                    OptimizerConfig::Sgd { lr, momentum } => {
+                        *m = momentum * *m + g;
+                        *p -= lr * *m;
+                    }
+                    OptimizerConfig::Adam { lr, beta1, beta2, eps } => {
+                        *m = beta1 * *m + (1.0 - beta1) * g;
+                        *v = beta2 * *v + (1.0 - beta2) * g * g;
+                        let m_hat = *m / (1.0 - beta1.powi(t));
+                        let v_hat = *v / (1.0 - beta2.powi(t));
+                        *p -= lr * m_hat / (v_hat.sqrt() + eps);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Settings for `fit`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TrainConfig {
+    /// objective
+    pub loss: Loss,
+    /// activation between layers
+    pub activation: Activation,
+    /// optimizer and hyper-parameters
+    pub optimizer: OptimizerConfig,
+    /// examples per update
+    pub batch_size: usize,
+    /// passes over the data
+    pub epochs: usize,
+    /// seed for the per-epoch shuffle and the dropout masks
+    pub seed: u64,
+    /// probability of dropping a hidden activation during training (0 disables)
+    #[serde(default)]
+    pub dropout: f32,
+    /// L2 weight decay of the optimizer (0 disables)
+    #[serde(default)]
+    pub weight_decay: f32,
+}
+
+impl Default for TrainConfig {
+    fn default() -> Self {
+        Self {
+            loss: Loss::Mse,
+            activation: Activation::Relu,
+            optimizer: OptimizerConfig::adam(1e-3),
+            batch_size: 16,
+            epochs: 10,
+            seed: 42,
+            dropout: 0.0,
+            weight_decay: 0.0,
+        }
+    }
+}
+
+impl TrainConfig {
+    /// Check the regularization settings: dropout in `0..1`, non-negative decay.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..1.0).contains(&self.dropout) {
+            return Err(format!("dropout должен быть в диапазоне [0, 1), получено {}", self.dropout));
+        }
+        if !(0.0..).contains(&self.weight_decay) {
+            return Err(format!("weight_decay не может быть отрицательным, получено {}", self.weight_decay));
+        }
+        Ok(())
+    }
+}
+
+/// Check that every example matches the stack's input and output dimensions.
+pub fn check_shapes(layers: &[&mut Linear], data: &[(Vec<f32>, Vec<f32>)]) -> Result<(), String> {
+    let (Some(first), Some(last)) = (layers.first(), layers.last()) else {
+        return Err("нет слоёв для обучения".to_string());
+    };
+    match data.iter().position(|(x, y)| x.len() != first.in_dim || y.len() != last.out_dim) {
+        Some(i) => Err(format!(
+            "пример {}: ожидались размеры {} → {}, получено {} → {}",
+            i,
+            first.in_dim,
+            last.out_dim,
+            data.get(i).map_or(0, |e| e.0.len()),
+            data.get(i).map_or(0, |e| e.1.len())
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Mean loss of the stack over `data` (no updates).
+pub fn evaluate(layers: &[&mut Linear], activation: Activation, loss: Loss, data: &[(Vec<f32>, Vec<f32>)]) -> f32 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    let total: f32 = data.iter().map(|(x, y)| loss.compute(&forward(layers, activation, x), y).0).sum();
+    total / data.len() as f32
+}
+
+/// One update over a mini-batch; returns the batch's mean loss. With
+/// `dropout = Some((rate, rng))` hidden activations are dropped for this update.
+pub fn train_batch(
+    layers: &mut [&mut Linear],
+    optimizer: &mut Optimizer,
+    activation: Activation,
+    loss: Loss,
+    batch: &[&(Vec<f32>, Vec<f32>)],
+    dropout: Option<(f32, &mut dyn rand::RngCore)>,
+) -> f32 {
+    if batch.is_empty() {
+        return 0.0;
+    }
+    let (total, grads) = backprop(layers, activation, loss, batch, dropout);
+    let n = batch.len() as f32;
+    optimizer.step(layers, &grads, 1.0 / n);
+    total / n
+}
+
+/// Mean loss over `batch` and the mean parameter gradients of every layer
+/// (no dropout, no update); `train::grad_check` compares them with finite differences.
+pub fn gradients(layers: &[&mut Linear], activation: Activation, loss: Loss, batch: &[&(Vec<f32>, Vec<f32>)]) -> (f32, Vec<Gradients>) {
+    let (total, mut grads) = backprop(layers, activation, loss, batch, None);
+    let n = batch.len().max(1) as f32;
+    for g in &mut grads {
+        g.weights.iter_mut().chain(g.bias.iter_mut()).for_each(|v| *v /= n);
+    }
+    (total / n, grads)
+}
+
+/// Summed loss and summed gradients over `batch`.
+fn backprop(
+    layers: &[&mut Linear],
+    activation: Activation,
+    loss: Loss,
+    batch: &[&(Vec<f32>, Vec<f32>)],
+    mut dropout: Option<(f32, &mut dyn rand::RngCore)>,
+) -> (f32, Vec<Gradients>) {
+    let mut grads: Vec<Gradients> = layers.iter().map(|l| Gradients::zeros(l)).collect();
+    let mut total = 0.0;
+    for (x, y) in batch.iter().copied() {
+        let dropout = dropout.as_mut().map(|(rate, rng)| (*rate, &mut **rng as &mut dyn rand::RngCore));
+        let Trace { inputs, masks, output } = trace(layers, activation, x, dropout);
+        let (l, mut grad) = loss.compute(&output, y);
+        total += l;
+        for (((layer, input), mask), g) in layers.iter().zip(&inputs).zip(&masks).zip(grads.iter_mut()).rev() {
+            let grad_in = linear_backward(layer, input, &grad, g);
+            grad = if mask.is_empty() {
+                grad_in.iter().zip(input).map(|(g, a)| g * activation.derivative(*a)).collect()
+            } else {
+                // the derivative is taken at the activation before dropout scaled it
+                grad_in.iter().zip(input).zip(mask).map(|((g, a), m)| if *m > 0.0 { g * m * activation.derivative(a / m) } else { 0.0 }).collect()
+            };
+        }
+    }
+    (total, grads)
+}
+
+/// Train the stack on `(input, target)` pairs with mini-batch gradient descent.
+/// Returns the mean training loss of every epoch. See `trainer::run` for
+/// validation, checkpoints and early stopping.
+pub fn fit(layers: &mut [&mut Linear], data: &[(Vec<f32>, Vec<f32>)], cfg: &TrainConfig) -> Result<Vec<f32>, String> {
+    let outcome = trainer::run(layers, data, &[], &RunConfig::new(*cfg), None)?;
+    Ok(outcome.history.iter().map(|e| e.train_loss).collect())
+}
+
+/// Train both layers of `model` (ReLU between them, as in generation).
+pub fn fit_model(model: &mut Model, data: &[(Vec<f32>, Vec<f32>)], cfg: &TrainConfig) -> Result<Vec<f32>, String> {
+    let cfg = TrainConfig { activation: Activation::Relu, ..*cfg };
+    fit(&mut [&mut model.lin1, &mut model.lin2], data, &cfg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layer(in_dim: usize, out_dim: usize, seed: u64) -> Linear {
+        use rand::Rng;
+        let mut rng = core::make_rng(seed);
+        let raw: Vec<f32> = (0..in_dim * out_dim).map(|_| rng.gen_range(-0.5..0.5)).collect();
+        Linear::from_raw(in_dim, out_dim, &raw)
+    }
+
+    #[test]
+    fn backward_matches_finite_differences() {
+        let mut l = layer(3, 2, 1);
+        let x = [0.5, -1.0, 2.0];
+        let y = [1.0, 0.0];
+        let mut grads = Gradients::zeros(&l);
+        let (_, g_out) = Loss::Mse.compute(&l.forward(&x), &y);
+        linear_backward(&l, &x, &g_out, &mut grads);
+
+        let eps = 1e-3;
+        let base = l.weights.first().copied().unwrap_or_default();
+        if let Some(w) = l.weights.first_mut() { *w = base + eps; }
+        let up = Loss::Mse.compute(&l.forward(&x), &y).0;
+        if let Some(w) = l.weights.first_mut() { *w = base - eps; }
+        let down = Loss::Mse.compute(&l.forward(&x), &y).0;
+        let numeric = (up - down) / (2.0 * eps);
+        let analytic = grads.weights.first().copied().unwrap_or_default();
+        assert!((numeric - analytic).abs() < 1e-2, "numeric {} vs analytic {}", numeric, analytic);
+    }
+
+    #[test]
+    fn adam_learns_xor_with_cross_entropy() {
+        let data: Vec<(Vec<f32>, Vec<f32>)> = [(0.0, 0.0, 0), (0.0, 1.0, 1), (1.0, 0.0, 1), (1.0, 1.0, 0)]
+            .iter()
+            .map(|&(a, b, c)| (vec![a, b], if c == 1 { vec![0.0, 1.0] } else { vec![1.0, 0.0] }))
+            .collect();
+        let (mut l1, mut l2) = (layer(2, 8, 3), layer(8, 2, 4));
+        let cfg = TrainConfig {
+            loss: Loss::CrossEntropy,
+            activation: Activation::Tanh,
+            optimizer: OptimizerConfig::adam(0.05),
+            batch_size: 4,
+            epochs: 300,
+            seed: 7,
+            ..TrainConfig::default()
+        };
+        let history = fit(&mut [&mut l1, &mut l2], &data, &cfg).unwrap_or_default();
+        assert!(history.last().copied().unwrap_or(f32::MAX) < 0.1, "loss history ends at {:?}", history.last());
+    }
+
+    #[test]
+    fn sgd_fits_linear_regression_in_mini_batches() {
+        let data: Vec<(Vec<f32>, Vec<f32>)> = (0..32).map(|i| {
+            let x = i as f32 / 16.0 - 1.0;
+            (vec![x], vec![3.0 * x - 0.5])
+        }).collect();
+        let mut l = Linear::from_raw(1, 1, &[]);
+        let cfg = TrainConfig {
+            activation: Activation::Identity,
+            optimizer: OptimizerConfig::Sgd { lr: 0.1, momentum: 0.5 },
+            batch_size: 8,
+            epochs: 100,
+            ..TrainConfig::default()
+        };
+        assert!(fit(&mut [&mut l], &data, &cfg).is_ok());
+        assert!((l.weights.first().copied().unwrap_or_default() - 3.0).abs() < 1e-2);
+        assert!((l.bias.first().copied().unwrap_or_default() + 0.5).abs() < 1e-2);
+    }
+
+    #[test]
+    fn weight_decay_shrinks_weights_and_dropout_only_applies_in_training() {
+        let mut l = Linear::from_raw(2, 1, &[1.0, -2.0, 0.5]);
+        let mut opt = Optimizer::new(OptimizerConfig::sgd(0.1)).with_weight_decay(0.5);
+        let zeros = Gradients::zeros(&l);
+        opt.step(&mut [&mut l], &[zeros], 1.0);
+        assert_eq!((l.weights.clone(), l.bias.clone()), (vec![0.95, -1.9], vec![0.5]));
+
+        let (mut l1, mut l2) = (layer(4, 32, 1), layer(32, 1, 2));
+        let example = (vec![0.5, -0.5, 1.0, 0.25], vec![1.0]);
+        let before = forward(&[&mut l1, &mut l2], Activation::Tanh, &example.0);
+        let mut rng = core::make_rng(3);
+        let dropped = trace(&[&mut l1, &mut l2], Activation::Tanh, &example.0, Some((0.5, &mut rng)));
+        let mask = dropped.masks.get(1).cloned().unwrap_or_default();
+        assert!(mask.contains(&0.0) && mask.contains(&2.0) && mask.iter().all(|m| *m == 0.0 || *m == 2.0));
+        assert_eq!(forward(&[&mut l1, &mut l2], Activation::Tanh, &example.0), before);
+
+        let cfg = TrainConfig { dropout: 0.2, weight_decay: 1e-3, epochs: 50, activation: Activation::Tanh, ..TrainConfig::default() };
+        let history = fit(&mut [&mut l1, &mut l2], &[example], &cfg).unwrap_or_default();
+        assert!(history.last() < history.first(), "losses: {:?}", history);
+        assert!(fit(&mut [&mut l1, &mut l2], &[], &TrainConfig { dropout: 1.0, ..cfg }).is_err());
+    }
+
+    #[test]
+    fn rejects_mismatched_examples() {
+        let mut l = Linear::from_raw(2, 1, &[]);
+        let bad = vec![(vec![1.0], vec![1.0])];
+        assert!(fit(&mut [&mut l], &bad, &TrainConfig::default()).is_err());
+    }
+}
@@ -0,0 +1,324 @@
+#![forbid(unsafe_code)]
+
+//! Training runs with validation, periodic checkpoints and early stopping.
+//!
+//! A checkpoint holds everything needed to continue a run bit-for-bit: the
+//! layer weights, the optimizer moments, the RNG position at the start of the
+//! current epoch and how many batches of that epoch are done. On resume the
+//! epoch order is reshuffled from the stored RNG position and the finished
+//! batches are skipped. Dropout masks come from an RNG seeded by the run seed
+//! and the step number, so they are reproduced on resume as well.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::core;
+use crate::linear::Linear;
+use crate::train::dataset::Dataset;
+use crate::train::log::{LogRecord, RecordKind, TrainingLog};
+use crate::train::sgd::{self, Optimizer, TrainConfig};
+
+/// Settings of a training run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunConfig {
+    /// loss, optimizer, batch size, epochs and seed
+    pub train: TrainConfig,
+    /// write a checkpoint every N optimizer steps (0 disables checkpoints)
+    pub checkpoint_every: u64,
+    /// directory for `step_<N>.ckpt` files
+    pub checkpoint_dir: PathBuf,
+    /// stop after this many epochs without validation improvement (0 disables)
+    pub patience: usize,
+    /// smallest validation loss decrease that counts as an improvement
+    pub min_delta: f32,
+    /// dataset the run was started on, so `train resume` can reload it
+    pub dataset: Option<String>,
+    /// fraction of the dataset held out for validation
+    pub val_fraction: f32,
+    /// CSV training log to append per-step and per-epoch rows to (see `train::log`)
+    pub log_path: Option<PathBuf>,
+}
+
+impl RunConfig {
+    /// A run without checkpoints, validation split or early stopping.
+    pub fn new(train: TrainConfig) -> Self {
+        Self {
+            train,
+            checkpoint_every: 0,
+            checkpoint_dir: PathBuf::from("checkpoints"),
+            patience: 0,
+            min_delta: 0.0,
+            dataset: None,
+            val_fraction: 0.0,
+            log_path: None,
+        }
+    }
+}
+
+/// Loss summary of one finished epoch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EpochStats {
+    /// epoch number, starting at 1
+    pub epoch: usize,
+    /// mean training loss
+    pub train_loss: f32,
+    /// mean validation loss, when a validation set is used
+    pub val_loss: Option<f32>,
+}
+
+/// Mutable progress of a run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrainerState {
+    /// finished epochs
+    pub epoch: usize,
+    /// finished batches of the current epoch
+    pub batch: usize,
+    /// optimizer steps taken
+    pub step: u64,
+    /// running sum of example losses in the current epoch
+    pub epoch_loss: f32,
+    /// optimizer with its moment buffers
+    pub optimizer: Optimizer,
+    /// RNG seed of the run
+    pub rng_seed: u64,
+    /// RNG word position at the start of the current epoch
+    pub rng_word_pos: u128,
+    /// best validation loss so far
+    pub best_val: Option<f32>,
+    /// weights at the best validation loss
+    pub best_layers: Option<Vec<Linear>>,
+    /// epochs since the last validation improvement
+    pub bad_epochs: usize,
+    /// per-epoch losses
+    pub history: Vec<EpochStats>,
+}
+
+impl TrainerState {
+    /// State at the beginning of a run.
+    pub fn new(run: &RunConfig) -> Self {
+        Self {
+            epoch: 0,
+            batch: 0,
+            step: 0,
+            epoch_loss: 0.0,
+            optimizer: Optimizer::new(run.train.optimizer).with_weight_decay(run.train.weight_decay),
+            rng_seed: run.train.seed,
+            rng_word_pos: 0,
+            best_val: None,
+            best_layers: None,
+            bad_epochs: 0,
+            history: Vec::new(),
+        }
+    }
+}
+
+/// Everything needed to continue a run (bincode on disk).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// run settings
+    pub run: RunConfig,
+    /// progress, optimizer and RNG state
+    pub state: TrainerState,
+    /// current weights
+    pub layers: Vec<Linear>,
+}
+
+impl Checkpoint {
+    /// Write the checkpoint, creating parent directories.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let bytes = bincode::serialize(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        std::fs::write(path, bytes)
+    }
+
+    /// Read a checkpoint written by `save`.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        bincode::deserialize(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Copy the stored weights into `layers` (shapes must match).
+    pub fn restore_layers(&self, layers: &mut [&mut Linear]) -> Result<(), String> {
+        if layers.len() != self.layers.len()
+            || layers.iter().zip(&self.layers).any(|(l, c)| l.in_dim != c.in_dim || l.out_dim != c.out_dim)
+        {
+            return Err("форма слоёв не совпадает с контрольной точкой".to_string());
+        }
+        for (layer, saved) in layers.iter_mut().zip(&self.layers) {
+            **layer = saved.clone();
+        }
+        Ok(())
+    }
+}
+
+/// Result of `run`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    /// per-epoch losses, including epochs from before a resume
+    pub history: Vec<EpochStats>,
+    /// whether early stopping ended the run
+    pub stopped_early: bool,
+    /// checkpoint files written by this call
+    pub checkpoints: Vec<PathBuf>,
+    /// final state (pass to a new `Checkpoint` to continue later)
+    pub state: TrainerState,
+}
+
+fn snapshot(layers: &[&mut Linear]) -> Vec<Linear> {
+    layers.iter().map(|l| (**l).clone()).collect()
+}
+
+/// Train `layers` on `train`, evaluating `val` after every epoch.
+///
+/// With `resume` the run continues from the checkpoint's state (its weights
+/// must already be in `layers`, see `Checkpoint::restore_layers`). When a
+/// validation set is given, the weights with the best validation loss are
+/// restored at the end.
+pub fn run(
+    layers: &mut [&mut Linear],
+    train: &[(Vec<f32>, Vec<f32>)],
+    val: &[(Vec<f32>, Vec<f32>)],
+    run: &RunConfig,
+    resume: Option<TrainerState>,
+) -> Result<Outcome, String> {
+    sgd::check_shapes(layers, train)?;
+    sgd::check_shapes(layers, val)?;
+    let cfg = &run.train;
+    cfg.validate()?;
+    let mut state = resume.unwrap_or_else(|| TrainerState::new(run));
+    let mut rng: ChaCha8Rng = core::make_rng(state.rng_seed);
+    rng.set_word_pos(state.rng_word_pos);
+    let mut checkpoints = Vec::new();
+    let mut stopped_early = false;
+    let mut log = match &run.log_path {
+        Some(path) => Some(TrainingLog::open(path).map_err(|e| format!("не удалось открыть журнал {}: {}", path.display(), e))?),
+        None => None,
+    };
+    let lr = cfg.optimizer.lr();
+    let mut write_log = |record: LogRecord| -> Result<(), String> {
+        match log.as_mut() {
+            Some(log) => log.append(&record).map_err(|e| format!("не удалось записать журнал обучения: {}", e)),
+            None => Ok(()),
+        }
+    };
+
+    while state.epoch < cfg.epochs {
+        state.rng_word_pos = rng.get_word_pos();
+        let mut order: Dataset<&(Vec<f32>, Vec<f32>)> = train.iter().collect();
+        order.shuffle_with(&mut rng);
+        for batch in order.batches(cfg.batch_size).skip(state.batch) {
+            let mut dropout_rng = core::make_rng(state.rng_seed ^ state.step.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+            let dropout = (cfg.dropout > 0.0).then_some((cfg.dropout, &mut dropout_rng as &mut dyn rand::RngCore));
+            let loss = sgd::train_batch(layers, &mut state.optimizer, cfg.activation, cfg.loss, batch, dropout);
+            state.epoch_loss += loss * batch.len() as f32;
+            state.batch += 1;
+            state.step += 1;
+            write_log(LogRecord { kind: RecordKind::Step, step: state.step, epoch: state.epoch + 1, loss, lr, val_loss: None })?;
+            if run.checkpoint_every > 0 && state.step.is_multiple_of(run.checkpoint_every) {
+                let path = run.checkpoint_dir.join(format!("step_{:06}.ckpt", state.step));
+                let ckpt = Checkpoint { run: run.clone(), state: state.clone(), layers: snapshot(layers) };
+                ckpt.save(&path).map_err(|e| format!("не удалось записать {}: {}", path.display(), e))?;
+                checkpoints.push(path);
+            }
+        }
+
+        let val_loss = (!val.is_empty()).then(|| sgd::evaluate(layers, cfg.activation, cfg.loss, val));
+        state.epoch += 1;
+        let train_loss = state.epoch_loss / train.len().max(1) as f32;
+        state.history.push(EpochStats { epoch: state.epoch, train_loss, val_loss });
+        write_log(LogRecord { kind: RecordKind::Epoch, step: state.step, epoch: state.epoch, loss: train_loss, lr, val_loss })?;
+        state.batch = 0;
+        state.epoch_loss = 0.0;
+
+        if let Some(v) = val_loss {
+            if state.best_val.is_none_or(|best| v < best - run.min_delta) {
+                state.best_val = Some(v);
+                state.best_layers = Some(snapshot(layers));
+                state.bad_epochs = 0;
+            } else {
+                state.bad_epochs += 1;
+                if run.patience > 0 && state.bad_epochs >= run.patience {
+                    stopped_early = true;
+                    break;
+                }
+            }
+        }
+    }
+    state.rng_word_pos = rng.get_word_pos();
+
+    if let Some(best) = &state.best_layers {
+        for (layer, saved) in layers.iter_mut().zip(best) {
+            **layer = saved.clone();
+        }
+    }
+    Ok(Outcome { history: state.history.clone(), stopped_early, checkpoints, state })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::train::sgd::{Activation, OptimizerConfig};
+
+    fn data() -> Vec<(Vec<f32>, Vec<f32>)> {
+        (0..20).map(|i| {
+            let x = i as f32 / 10.0 - 1.0;
+            (vec![x], vec![2.0 * x + 1.0])
+        }).collect()
+    }
+
+    fn config(dir: &Path) -> RunConfig {
+        RunConfig {
+            checkpoint_every: 3,
+            checkpoint_dir: dir.to_path_buf(),
+            ..RunConfig::new(TrainConfig {
+                activation: Activation::Identity,
+                optimizer: OptimizerConfig::adam(0.05),
+                batch_size: 4,
+                epochs: 4,
+                ..TrainConfig::default()
+            })
+        }
+    }
+
+    #[test]
+    fn resuming_from_a_checkpoint_matches_an_uninterrupted_run() {
+        let dir = std::env::temp_dir().join(format!("shark_ckpt_{}", std::process::id()));
+        let run_cfg = config(&dir);
+        let data = data();
+
+        let mut full = Linear::from_raw(1, 1, &[]);
+        let outcome = run(&mut [&mut full], &data, &[], &run_cfg, None);
+        let checkpoints = outcome.map(|o| o.checkpoints).unwrap_or_default();
+        // step 6 is in the middle of the second epoch (5 batches per epoch)
+        let middle = checkpoints.get(1).cloned().unwrap_or_default();
+        let ckpt = Checkpoint::load(&middle);
+        assert_eq!(ckpt.as_ref().map(|c| (c.state.epoch, c.state.batch)).ok(), Some((1, 1)));
+
+        let mut resumed = Linear::from_raw(1, 1, &[]);
+        let result = ckpt.map_err(|e| e.to_string()).and_then(|c| {
+            c.restore_layers(&mut [&mut resumed])?;
+            run(&mut [&mut resumed], &data, &[], &c.run, Some(c.state))
+        });
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(result.is_ok());
+        assert_eq!(resumed, full);
+    }
+
+    #[test]
+    fn early_stopping_keeps_the_best_weights() {
+        let data = data();
+        let val = vec![(vec![0.0], vec![100.0])];
+        let run_cfg = RunConfig { patience: 2, ..RunConfig::new(TrainConfig { activation: Activation::Identity, epochs: 50, ..TrainConfig::default() }) };
+        let mut l = Linear::from_raw(1, 1, &[0.0, 100.0]);
+        let outcome = run(&mut [&mut l], &data, &val, &run_cfg, None);
+        assert_eq!(outcome.as_ref().map(|o| (o.stopped_early, o.history.len())).ok(), Some((true, 3)));
+        let restored = sgd::evaluate(&[&mut l], Activation::Identity, run_cfg.train.loss, &val);
+        let first = outcome.ok().and_then(|o| o.history.first().and_then(|e| e.val_loss));
+        assert_eq!(Some(restored), first);
+    }
+}
@@ -0,0 +1,139 @@
+#![forbid(unsafe_code)]
+
+//! Tutor mode over the problems dataset with Leitner-style spaced repetition.
+//!
+//! Every problem is a card in a box (`level`). A correct answer moves the card
+//! one box up and postpones it for `2^level` sessions; a wrong answer puts it
+//! back into box 0 so it is asked again in the next session. Progress is kept
+//! per user in a small JSON score file.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::train::normalize_answer;
+
+/// Highest box; cards there are reviewed every `2^MAX_LEVEL` sessions.
+pub const MAX_LEVEL: u32 = 5;
+
+/// Repetition state of one problem.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Card {
+    /// Leitner box, 0 = ask again next session.
+    pub level: u32,
+    /// Session number from which the card is due again.
+    pub due: u64,
+    /// Number of correct answers.
+    pub correct: u32,
+    /// Number of wrong answers.
+    pub wrong: u32,
+}
+
+/// Per-user tutor progress (the score file).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TutorState {
+    /// Number of started sessions.
+    pub session: u64,
+    /// Total correct answers.
+    pub score: u32,
+    /// Total answers given.
+    pub answered: u32,
+    /// Cards keyed by question text.
+    pub cards: BTreeMap<String, Card>,
+}
+
+/// Score file for `user` inside `dir` (`<dir>/tutor_<user>.json`).
+pub fn score_path(dir: &Path, user: &str) -> PathBuf {
+    let safe: String = user.chars().map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' }).collect();
+    dir.join(format!("tutor_{}.json", safe))
+}
+
+/// Whether `given` matches `expected` after answer normalization.
+pub fn check_answer(given: &str, expected: &str) -> bool {
+    normalize_answer(given) == normalize_answer(expected)
+}
+
+impl TutorState {
+    /// Load progress from `path`; a missing or unreadable file starts fresh.
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    /// Persist progress to `path`, creating parent directories.
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::from)?;
+        std::fs::write(path, json)
+    }
+
+    /// Start a new session; returns its number.
+    pub fn start_session(&mut self) -> u64 {
+        self.session += 1;
+        self.session
+    }
+
+    /// Problems to ask in the current session: due cards first (lowest box,
+    /// then longest overdue), followed by problems never seen before.
+    pub fn queue<'a>(&self, problems: &'a [(String, String)]) -> Vec<&'a (String, String)> {
+        let mut due: Vec<(&Card, &'a (String, String))> = problems
+            .iter()
+            .filter_map(|p| self.cards.get(&p.0).filter(|c| c.due <= self.session).map(|c| (c, p)))
+            .collect();
+        due.sort_by_key(|(c, _)| (c.level, c.due));
+        let fresh = problems.iter().filter(|p| !self.cards.contains_key(&p.0));
+        due.into_iter().map(|(_, p)| p).chain(fresh).collect()
+    }
+
+    /// Record an answer to `question` and reschedule its card.
+    pub fn record(&mut self, question: &str, correct: bool) {
+        let session = self.session;
+        let card = self.cards.entry(question.to_string()).or_default();
+        self.answered += 1;
+        if correct {
+            self.score += 1;
+            card.correct += 1;
+            card.level = (card.level + 1).min(MAX_LEVEL);
+            card.due = session + (1u64 << card.level);
+        } else {
+            card.wrong += 1;
+            card.level = 0;
+            card.due = session + 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems() -> Vec<(String, String)> {
+        vec![("2+2".to_string(), "4".to_string()), ("3x = 9".to_string(), "x = 3".to_string())]
+    }
+
+    #[test]
+    fn wrong_answers_come_back_next_session_first() {
+        let problems = problems();
+        let mut state = TutorState::default();
+        state.start_session();
+        state.record("2+2", true);
+        state.record("3x = 9", false);
+
+        state.start_session();
+        let queue: Vec<&str> = state.queue(&problems).iter().map(|p| p.0.as_str()).collect();
+        assert_eq!(queue, vec!["3x = 9"]);
+        assert_eq!(state.cards.get("2+2").map(|c| c.due), Some(3));
+        assert_eq!((state.score, state.answered), (1, 2));
+    }
+
+    #[test]
+    fn answers_are_compared_normalized() {
+        assert!(check_answer(" X=3 ", "x = 3"));
+        assert!(!check_answer("4", "x = 3"));
+    }
+}
@@ -0,0 +1,210 @@
+#![forbid(unsafe_code)]
+
+//! Local usage statistics (opt-in, `[stats] enabled` in `shark.toml`).
+//!
+//! `enable` subscribes a `UsageStats` store to `Event::AnswerProduced` on the
+//! event bus, so the chat, GUI and server all count answers the same way.
+//! Counts are kept per day, pipeline stage and answer source together with
+//! the total and maximum latency, and saved after every answer to a small CSV
+//! (`<state_dir>/usage_stats.csv`): `date,stage,source,count,latency_ms,max_latency_ms`.
+//! Nothing leaves the machine. `chat stats` prints the table, the GUI Metrics
+//! tab charts questions per day.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::config::Config;
+use crate::events::{self, Event, SubscriptionId};
+
+/// CSV header of the statistics file.
+pub const HEADER: &str = "date,stage,source,count,latency_ms,max_latency_ms";
+
+/// Totals of one (day, stage, source) combination.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Counter {
+    /// answers
+    pub count: u64,
+    /// summed latency in milliseconds
+    pub latency_ms: f64,
+    /// slowest answer in milliseconds
+    pub max_latency_ms: f64,
+}
+
+impl Counter {
+    fn add(&mut self, latency_ms: f64) {
+        self.count += 1;
+        self.latency_ms += latency_ms;
+        self.max_latency_ms = self.max_latency_ms.max(latency_ms);
+    }
+
+    fn merge(&mut self, other: &Counter) {
+        self.count += other.count;
+        self.latency_ms += other.latency_ms;
+        self.max_latency_ms = self.max_latency_ms.max(other.max_latency_ms);
+    }
+
+    /// Mean latency in milliseconds (0 without answers).
+    pub fn mean_latency_ms(&self) -> f64 {
+        if self.count == 0 { 0.0 } else { self.latency_ms / self.count as f64 }
+    }
+}
+
+/// Summary of one day.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DaySummary {
+    /// `YYYY-MM-DD` (UTC)
+    pub date: String,
+    /// all answers of the day
+    pub total: Counter,
+    /// answers per source (`knowledge`, `reasoner`, `model`)
+    pub sources: BTreeMap<String, u64>,
+    /// answers per pipeline stage (solver usage)
+    pub stages: BTreeMap<String, u64>,
+}
+
+/// Per-day answer counts, persisted as CSV.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct UsageStats {
+    /// file the store saves to
+    pub path: PathBuf,
+    /// counters by (date, stage, source)
+    pub counters: BTreeMap<(String, String, String), Counter>,
+}
+
+impl UsageStats {
+    /// Parse the CSV of `path`; malformed rows are skipped.
+    pub fn parse(path: &Path, text: &str) -> Self {
+        let mut stats = Self { path: path.to_path_buf(), counters: BTreeMap::new() };
+        for line in text.lines().filter(|l| *l != HEADER) {
+            let fields: Vec<&str> = line.split(',').collect();
+            let [date, stage, source, count, latency, max] = fields.as_slice() else { continue };
+            let (Ok(count), Ok(latency_ms), Ok(max_latency_ms)) = (count.parse(), latency.parse(), max.parse()) else { continue };
+            let counter = Counter { count, latency_ms, max_latency_ms };
+            stats.counters.entry((date.to_string(), stage.to_string(), source.to_string())).or_default().merge(&counter);
+        }
+        stats
+    }
+
+    /// Load `path`; a missing file gives an empty store.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Ok(Self::parse(path, &text)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self { path: path.to_path_buf(), counters: BTreeMap::new() }),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Write the store to its path.
+    pub fn save(&self) -> io::Result<()> {
+        if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut text = format!("{}\n", HEADER);
+        for ((date, stage, source), c) in &self.counters {
+            text.push_str(&format!("{},{},{},{},{:.3},{:.3}\n", date, stage, source, c.count, c.latency_ms, c.max_latency_ms));
+        }
+        std::fs::write(&self.path, text)
+    }
+
+    /// Count one answer on `date`.
+    pub fn record(&mut self, date: &str, stage: &str, source: &str, latency_ms: f64) {
+        let stage = if stage.is_empty() { "-" } else { stage };
+        self.counters.entry((date.to_string(), stage.to_string(), source.to_string())).or_default().add(latency_ms);
+    }
+
+    /// Days in chronological order.
+    pub fn days(&self) -> Vec<DaySummary> {
+        let mut days: BTreeMap<&str, DaySummary> = BTreeMap::new();
+        for ((date, stage, source), c) in &self.counters {
+            let day = days.entry(date).or_insert_with(|| DaySummary { date: date.clone(), ..DaySummary::default() });
+            day.total.merge(c);
+            *day.sources.entry(source.clone()).or_default() += c.count;
+            *day.stages.entry(stage.clone()).or_default() += c.count;
+        }
+        days.into_values().collect()
+    }
+
+    /// Text table of the last `days` days: answers, sources, latency and the
+    /// most used stages.
+    pub fn render(&self, days: usize) -> String {
+        let all = self.days();
+        if all.is_empty() {
+            return "статистика пуста (включите [stats] enabled = true в shark.toml)".to_string();
+        }
+        let mut text = String::from("дата        ответов  знания  решатели  модель  ср.мс   макс.мс  стадии\n");
+        for day in all.iter().skip(all.len().saturating_sub(days)) {
+            let source = |s: &str| day.sources.get(s).copied().unwrap_or(0);
+            let mut stages: Vec<(&String, &u64)> = day.stages.iter().collect();
+            stages.sort_by(|a, b| b.1.cmp(a.1));
+            let top: Vec<String> = stages.iter().take(3).map(|(s, n)| format!("{} {}", s, n)).collect();
+            text.push_str(&format!(
+                "{:<10}  {:>7}  {:>6}  {:>8}  {:>6}  {:>6.1}  {:>7.1}  {}\n",
+                day.date,
+                day.total.count,
+                source("knowledge"),
+                source("reasoner"),
+                source("model"),
+                day.total.mean_latency_ms(),
+                day.total.max_latency_ms,
+                top.join(", ")
+            ));
+        }
+        text
+    }
+}
+
+/// Record every `Event::AnswerProduced` into the store at `path` (loaded
+/// first) and save it after each answer; failures to save go to stderr.
+pub fn enable(path: &Path) -> io::Result<SubscriptionId> {
+    let stats = Arc::new(Mutex::new(UsageStats::load(path)?));
+    Ok(events::bus().subscribe(move |event| {
+        let Event::AnswerProduced { stage, source, latency_ms, .. } = event else { return };
+        let Ok(mut stats) = stats.lock() else { return };
+        let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+        stats.record(&today, stage, source.as_str(), *latency_ms);
+        if let Err(e) = stats.save() {
+            eprintln!("⚠️ {}: {}", stats.path.display(), e);
+        }
+    }))
+}
+
+/// `enable` the store of `config` when `[stats] enabled` is set.
+pub fn configure(config: &Config) {
+    if config.stats.enabled {
+        let path = config.usage_stats_path();
+        if let Err(e) = enable(&path) {
+            eprintln!("⚠️ статистика {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answers_are_counted_per_day_and_round_trip() {
+        let path = Path::new("usage.csv");
+        let mut stats = UsageStats::parse(path, "");
+        stats.record("2026-01-01", "knowledge", "knowledge", 2.0);
+        stats.record("2026-01-01", "reasoner", "reasoner", 6.0);
+        stats.record("2026-01-01", "knowledge", "knowledge", 4.0);
+        stats.record("2026-01-02", "", "model", 10.0);
+
+        let days = stats.days();
+        assert_eq!(days.len(), 2);
+        let Some(first) = days.first() else { return };
+        assert_eq!((first.total.count, first.total.mean_latency_ms(), first.total.max_latency_ms), (3, 4.0, 6.0));
+        assert_eq!(first.stages.get("knowledge"), Some(&2));
+        assert_eq!(days.last().and_then(|d| d.sources.get("model")), Some(&1));
+
+        let mut text = format!("{}\n", HEADER);
+        for ((date, stage, source), c) in &stats.counters {
+            text.push_str(&format!("{},{},{},{},{},{}\n", date, stage, source, c.count, c.latency_ms, c.max_latency_ms));
+        }
+        assert_eq!(UsageStats::parse(path, &format!("{}garbage\n", text)), stats);
+        assert!(stats.render(1).contains("2026-01-02") && !stats.render(1).contains("2026-01-01"));
+    }
+}
@@ -0,0 +1,389 @@
+#![forbid(unsafe_code)]
+
+//! Inspection of weight files (`chat weights inspect <path>`).
+//!
+//! `Model::load` reads raw little-endian f32 in the `manifest` order; files
+//! exported elsewhere are often safetensors or GGUF, and a truncated or
+//! foreign file is easy to miss. `inspect` detects the format, lists the
+//! tensors with their shapes next to the shapes the char model expects,
+//! computes min/max/mean and NaN counts of f32 tensors and says whether the
+//! file is large enough for the architecture.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::error::{Result, SharkError};
+use crate::linear::Linear;
+use crate::model::Model;
+use crate::tokenizer::ALPHABET;
+
+/// Detected file layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WeightFormat {
+    /// little-endian f32 as `Model::save` writes
+    RawF32,
+    /// 8-byte header length, JSON header, tensor data
+    Safetensors,
+    /// `GGUF` magic, metadata, tensor infos, aligned data
+    Gguf,
+}
+
+impl WeightFormat {
+    /// Name used in reports.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WeightFormat::RawF32 => "raw f32",
+            WeightFormat::Safetensors => "safetensors",
+            WeightFormat::Gguf => "gguf",
+        }
+    }
+}
+
+/// Summary of an f32 tensor.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TensorStats {
+    /// smallest finite value
+    pub min: f32,
+    /// largest finite value
+    pub max: f32,
+    /// mean of the finite values
+    pub mean: f64,
+    /// NaN values
+    pub nan: usize,
+    /// values equal to zero
+    pub zeros: usize,
+}
+
+impl TensorStats {
+    /// Statistics of `values` (min/max are 0 without finite values).
+    pub fn of(values: &[f32]) -> Self {
+        let finite: Vec<f32> = values.iter().copied().filter(|v| v.is_finite()).collect();
+        let (min, max) = finite.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+        Self {
+            min: if finite.is_empty() { 0.0 } else { min },
+            max: if finite.is_empty() { 0.0 } else { max },
+            mean: if finite.is_empty() { 0.0 } else { finite.iter().map(|&v| v as f64).sum::<f64>() / finite.len() as f64 },
+            nan: values.iter().filter(|v| v.is_nan()).count(),
+            zeros: values.iter().filter(|&&v| v == 0.0).count(),
+        }
+    }
+}
+
+/// One tensor of the file, or one the model expects.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TensorInfo {
+    /// tensor name (`lin1.weight`, ...)
+    pub name: String,
+    /// element type as stored (`F32`, `F16`, `Q4_0`, ...)
+    pub dtype: String,
+    /// dimensions in the file (`None`: missing from the file)
+    pub shape: Option<Vec<usize>>,
+    /// dimensions the char model expects (`None`: not part of the model)
+    pub expected: Option<Vec<usize>>,
+    /// statistics of f32 data
+    pub stats: Option<TensorStats>,
+}
+
+impl TensorInfo {
+    /// Whether the stored shape is the expected one.
+    pub fn matches(&self) -> bool {
+        self.shape.is_some() && self.shape == self.expected
+    }
+}
+
+/// Result of `inspect`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WeightsReport {
+    /// detected format
+    pub format: WeightFormat,
+    /// file size in bytes
+    pub bytes: usize,
+    /// format version (GGUF)
+    pub version: Option<u32>,
+    /// tensors of the file and those the model expects but the file lacks
+    pub tensors: Vec<TensorInfo>,
+    /// floats `Model::load` needs
+    pub expected_floats: usize,
+    /// whether `Model::load` accepts the file (raw f32 and large enough)
+    pub loadable: bool,
+    /// problems found while reading
+    pub notes: Vec<String>,
+}
+
+/// Tensors of the char model in the order `Model::save` writes them, with
+/// their shapes (`[out, in]` row-major weights, `[out]` biases).
+pub fn manifest() -> Vec<(&'static str, Vec<usize>)> {
+    let (embed, hidden, vocab) = (Model::EMBED, Model::HIDDEN, ALPHABET.len());
+    vec![("lin1.weight", vec![hidden, embed]), ("lin1.bias", vec![hidden]), ("lin2.weight", vec![vocab, hidden]), ("lin2.bias", vec![vocab])]
+}
+
+/// Detect the format from the first bytes.
+pub fn detect(bytes: &[u8]) -> WeightFormat {
+    if bytes.starts_with(b"GGUF") {
+        return WeightFormat::Gguf;
+    }
+    let header = bytes.get(..8).and_then(|b| <[u8; 8]>::try_from(b).ok()).map(u64::from_le_bytes);
+    match header {
+        Some(n) if n > 1 && (n as usize).saturating_add(8) <= bytes.len() && bytes.get(8) == Some(&b'{') => WeightFormat::Safetensors,
+        _ => WeightFormat::RawF32,
+    }
+}
+
+/// Read and inspect the file at `path`.
+pub fn inspect_file(path: &Path) -> Result<WeightsReport> {
+    let bytes = std::fs::read(path).map_err(|e| SharkError::io(path, e))?;
+    inspect(&bytes).map_err(|reason| SharkError::Corrupt { path: path.to_path_buf(), reason })
+}
+
+/// Inspect weights in memory; an unreadable safetensors or GGUF header is an error.
+pub fn inspect(bytes: &[u8]) -> std::result::Result<WeightsReport, String> {
+    let format = detect(bytes);
+    let expected_floats = Linear::param_count(Model::EMBED, Model::HIDDEN) + Linear::param_count(Model::HIDDEN, ALPHABET.len());
+    let mut report = WeightsReport { format, bytes: bytes.len(), version: None, tensors: Vec::new(), expected_floats, loadable: false, notes: Vec::new() };
+    match format {
+        WeightFormat::RawF32 => inspect_raw(bytes, &mut report),
+        WeightFormat::Safetensors => inspect_safetensors(bytes, &mut report)?,
+        WeightFormat::Gguf => inspect_gguf(bytes, &mut report)?,
+    }
+    for (name, shape) in manifest() {
+        if !report.tensors.iter().any(|t| t.name == name) {
+            report.tensors.push(TensorInfo { name: name.to_string(), dtype: "-".to_string(), shape: None, expected: Some(shape), stats: None });
+        }
+    }
+    Ok(report)
+}
+
+fn expected_shape(name: &str) -> Option<Vec<usize>> {
+    manifest().into_iter().find(|(n, _)| *n == name).map(|(_, shape)| shape)
+}
+
+fn inspect_raw(bytes: &[u8], report: &mut WeightsReport) {
+    let floats = crate::loader::bytes_to_f32(bytes);
+    let mut rest = floats.as_slice();
+    for (name, shape) in manifest() {
+        let n: usize = shape.iter().product();
+        let take = n.min(rest.len());
+        let (data, tail) = rest.split_at(take);
+        rest = tail;
+        if take == n {
+            report.tensors.push(TensorInfo { name: name.to_string(), dtype: "F32".to_string(), shape: Some(shape.clone()), expected: Some(shape), stats: Some(TensorStats::of(data)) });
+        } else if take > 0 {
+            report.notes.push(format!("{}: {} из {} значений", name, take, n));
+        }
+    }
+    if !bytes.len().is_multiple_of(4) {
+        report.notes.push(format!("{} лишних байт в конце (не кратно 4)", bytes.len() % 4));
+    }
+    if !rest.is_empty() {
+        report.notes.push(format!("{} лишних значений после lin2.bias (игнорируются)", rest.len()));
+    }
+    report.loadable = floats.len() >= report.expected_floats;
+    if !report.loadable {
+        report.notes.push(format!("файл мал для архитектуры: {} значений, нужно {}", floats.len(), report.expected_floats));
+    }
+}
+
+fn inspect_safetensors(bytes: &[u8], report: &mut WeightsReport) -> std::result::Result<(), String> {
+    let len = bytes.get(..8).and_then(|b| <[u8; 8]>::try_from(b).ok()).map(u64::from_le_bytes).unwrap_or(0) as usize;
+    let header = bytes.get(8..8 + len).ok_or("заголовок safetensors обрезан")?;
+    let header: serde_json::Map<String, serde_json::Value> = serde_json::from_slice(header).map_err(|e| format!("заголовок safetensors: {}", e))?;
+    let data = bytes.get(8 + len..).unwrap_or_default();
+    for (name, entry) in header.iter().filter(|(name, _)| *name != "__metadata__") {
+        let dtype = entry.get("dtype").and_then(|d| d.as_str()).unwrap_or("?").to_string();
+        let shape: Vec<usize> = entry.get("shape").and_then(|s| s.as_array()).map(|s| s.iter().filter_map(|d| d.as_u64()).map(|d| d as usize).collect()).unwrap_or_default();
+        let offsets: Vec<usize> = entry.get("data_offsets").and_then(|o| o.as_array()).map(|o| o.iter().filter_map(|d| d.as_u64()).map(|d| d as usize).collect()).unwrap_or_default();
+        let tensor = match offsets.as_slice() {
+            [start, end] => data.get(*start..*end),
+            _ => None,
+        };
+        if tensor.is_none() {
+            report.notes.push(format!("{}: данные вне файла", name));
+        }
+        let stats = tensor.filter(|_| dtype == "F32").map(|t| TensorStats::of(&crate::loader::bytes_to_f32(t)));
+        report.tensors.push(TensorInfo { name: name.clone(), dtype, shape: Some(shape), expected: expected_shape(name), stats });
+    }
+    report.notes.push("Model::load читает только raw f32: сконвертируйте файл".to_string());
+    Ok(())
+}
+
+/// Little-endian reader over a byte slice.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> std::result::Result<&'a [u8], String> {
+        let slice = self.bytes.get(self.pos..self.pos.saturating_add(n)).ok_or_else(|| format!("файл обрезан на байте {}", self.pos))?;
+        self.pos += n;
+        Ok(slice)
+    }
+
+    fn u32(&mut self) -> std::result::Result<u32, String> {
+        self.take(4).map(|b| b.iter().rev().fold(0u32, |v, &x| (v << 8) | x as u32))
+    }
+
+    fn u64(&mut self) -> std::result::Result<u64, String> {
+        self.take(8).map(|b| b.iter().rev().fold(0u64, |v, &x| (v << 8) | x as u64))
+    }
+
+    fn string(&mut self) -> std::result::Result<String, String> {
+        let n = self.u64()? as usize;
+        self.take(n).map(|b| String::from_utf8_lossy(b).to_string())
+    }
+
+    /// Skip a metadata value of GGUF type `kind`, returning it when it is an integer.
+    fn value(&mut self, kind: u32) -> std::result::Result<Option<u64>, String> {
+        let size = match kind {
+            0 | 1 | 7 => 1,
+            2 | 3 => 2,
+            4..=6 => 4,
+            10..=12 => 8,
+            8 => {
+                self.string()?;
+                return Ok(None);
+            }
+            9 => {
+                let (inner, n) = (self.u32()?, self.u64()?);
+                for _ in 0..n {
+                    self.value(inner)?;
+                }
+                return Ok(None);
+            }
+            other => return Err(format!("неизвестный тип метаданных GGUF {}", other)),
+        };
+        let b = self.take(size)?;
+        Ok(matches!(kind, 4 | 5 | 10 | 11).then(|| b.iter().rev().fold(0u64, |v, &x| (v << 8) | x as u64)))
+    }
+}
+
+/// GGML tensor type names, by id.
+fn ggml_type(id: u32) -> String {
+    let names = ["F32", "F16", "Q4_0", "Q4_1", "", "", "Q5_0", "Q5_1", "Q8_0", "Q8_1", "Q2_K", "Q3_K", "Q4_K", "Q5_K", "Q6_K", "Q8_K"];
+    names.get(id as usize).filter(|n| !n.is_empty()).map(|n| n.to_string()).unwrap_or_else(|| format!("type{}", id))
+}
+
+fn inspect_gguf(bytes: &[u8], report: &mut WeightsReport) -> std::result::Result<(), String> {
+    let mut r = Reader { bytes, pos: 4 };
+    report.version = Some(r.u32()?);
+    let (tensor_count, kv_count) = (r.u64()?, r.u64()?);
+    let mut alignment = 32u64;
+    for _ in 0..kv_count {
+        let key = r.string()?;
+        let kind = r.u32()?;
+        if let (Some(v), "general.alignment") = (r.value(kind)?, key.as_str()) {
+            alignment = v.max(1);
+        }
+    }
+    let mut infos = Vec::new();
+    for _ in 0..tensor_count {
+        let name = r.string()?;
+        let dims = r.u32()?;
+        // GGUF lists the fastest-varying dimension first
+        let mut shape = (0..dims).map(|_| r.u64().map(|d| d as usize)).collect::<std::result::Result<Vec<_>, _>>()?;
+        shape.reverse();
+        let (kind, offset) = (r.u32()?, r.u64()?);
+        infos.push((name, shape, kind, offset as usize));
+    }
+    let start = (r.pos as u64).div_ceil(alignment) * alignment;
+    let data = bytes.get(start as usize..).unwrap_or_default();
+    for (name, shape, kind, offset) in infos {
+        let n: usize = shape.iter().product();
+        let stats = if kind == 0 {
+            match data.get(offset..offset.saturating_add(n * 4)) {
+                Some(t) => Some(TensorStats::of(&crate::loader::bytes_to_f32(t))),
+                None => {
+                    report.notes.push(format!("{}: данные вне файла", name));
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        report.tensors.push(TensorInfo { expected: expected_shape(&name), name, dtype: ggml_type(kind), shape: Some(shape), stats });
+    }
+    report.notes.push("Model::load читает только raw f32: сконвертируйте файл".to_string());
+    Ok(())
+}
+
+impl WeightsReport {
+    /// Text form for the terminal.
+    pub fn render(&self) -> String {
+        let mut text = format!("формат: {}", self.format.as_str());
+        if let Some(v) = self.version {
+            text.push_str(&format!(" (версия {})", v));
+        }
+        text.push_str(&format!("\nразмер: {} байт, тензоров: {}\n", self.bytes, self.tensors.iter().filter(|t| t.shape.is_some()).count()));
+        for t in &self.tensors {
+            let dims = |s: &Option<Vec<usize>>| s.as_ref().map(|s| format!("{:?}", s)).unwrap_or_else(|| "—".to_string());
+            let mark = match (&t.shape, &t.expected) {
+                (_, None) => "·",
+                _ if t.matches() => "✅",
+                _ => "❌",
+            };
+            text.push_str(&format!("{} {:<12} {:<5} {:>12} ожидается {:>12}", mark, t.name, t.dtype, dims(&t.shape), dims(&t.expected)));
+            if let Some(s) = t.stats {
+                text.push_str(&format!("  min {:.4} max {:.4} mean {:.4} NaN {} нулей {}", s.min, s.max, s.mean, s.nan, s.zeros));
+            }
+            text.push('\n');
+        }
+        text.push_str(&format!(
+            "{} Model::load: {}\n",
+            if self.loadable { "✅" } else { "❌" },
+            if self.loadable { "файл подходит".to_string() } else { format!("не загрузится (нужно {} значений f32)", self.expected_floats) }
+        ));
+        for note in &self.notes {
+            text.push_str(&format!("  - {}\n", note));
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_shapes_and_sizes_are_reported() {
+        let mut model = Model::zeroed();
+        model.lin2.bias = vec![f32::NAN; model.lin2.bias.len()];
+        let report = inspect(&model.to_bytes());
+        assert_eq!(report.as_ref().map(|r| (r.format, r.loadable, r.tensors.iter().all(TensorInfo::matches))), Ok((WeightFormat::RawF32, true, true)));
+        let Ok(report) = report else { return };
+        assert_eq!(report.tensors.last().and_then(|t| t.stats).map(|s| s.nan), Some(ALPHABET.len()));
+
+        let bytes = model.to_bytes();
+        let short = inspect(bytes.get(..400).unwrap_or_default());
+        assert_eq!(short.map(|r| (r.loadable, r.tensors.iter().filter(|t| t.shape.is_none()).count())), Ok((false, 4)));
+
+        let header = br#"{"lin1.bias":{"dtype":"F32","shape":[2],"data_offsets":[0,8]}}"#;
+        let mut st = (header.len() as u64).to_le_bytes().to_vec();
+        st.extend_from_slice(header);
+        st.extend([1.0f32, -3.0].iter().flat_map(|f| f.to_le_bytes()));
+        let st = inspect(&st);
+        assert_eq!(st.as_ref().map(|r| (r.format, r.loadable)), Ok((WeightFormat::Safetensors, false)));
+        let Ok(st) = st else { return };
+        let bias = st.tensors.iter().find(|t| t.name == "lin1.bias");
+        assert_eq!(bias.and_then(|t| t.stats).map(|s| (s.min, s.max, s.mean)), Some((-3.0, 1.0, -1.0)));
+        assert!(bias.is_some_and(|t| !t.matches()));
+
+        let mut gguf = b"GGUF".to_vec();
+        gguf.extend(3u32.to_le_bytes());
+        gguf.extend(1u64.to_le_bytes());
+        gguf.extend(0u64.to_le_bytes());
+        gguf.extend((9u64).to_le_bytes());
+        gguf.extend(b"lin2.bias");
+        gguf.extend(1u32.to_le_bytes());
+        gguf.extend((ALPHABET.len() as u64).to_le_bytes());
+        gguf.extend(0u32.to_le_bytes());
+        gguf.extend(0u64.to_le_bytes());
+        gguf.resize(gguf.len().div_ceil(32) * 32, 0);
+        gguf.extend(vec![0u8; ALPHABET.len() * 4]);
+        let gguf = inspect(&gguf);
+        assert_eq!(gguf.as_ref().map(|r| (r.format, r.version)), Ok((WeightFormat::Gguf, Some(3))));
+        let Ok(gguf) = gguf else { return };
+        assert!(gguf.tensors.iter().any(|t| t.name == "lin2.bias" && t.matches() && t.stats.map(|s| s.zeros) == Some(ALPHABET.len())));
+    }
+}
@@ -3,4 +3,4 @@
 pub mod greedy;
 pub mod top_k;
 pub mod nucleus;
-// pub mod temperature; // TODO
\ No newline at end of file
+pub mod temperature;
\ No newline at end of file
@@ -0,0 +1,142 @@
+//! Temperature sampling implementation.
+//!
+//! Logits are divided by the temperature before softmax: below 1 the
+//! distribution sharpens towards the most likely token, above 1 it flattens.
+//! `TemperatureSampler::scale` only rescales, so its output can be handed to
+//! the greedy, top-k or nucleus samplers; `sample` draws from the tempered
+//! distribution itself, the softmax of `shark_core_math::softmax_with`.
+
+use std::fmt;
+
+use core::types::{Probability, TokenId};
+use shark_core_math::{softmax_with, Accumulation};
+
+/// Invalid sampler parameter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TemperatureError {
+    /// temperature is zero, negative or NaN
+    NotPositive(f32),
+    /// temperature is infinite
+    NotFinite(f32),
+}
+
+impl fmt::Display for TemperatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemperatureError::NotPositive(t) => write!(f, "temperature must be > 0, got {}", t),
+            TemperatureError::NotFinite(t) => write!(f, "temperature must be finite, got {}", t),
+        }
+    }
+}
+
+impl std::error::Error for TemperatureError {}
+
+/// Scales logits by `1 / temperature` before softmax.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TemperatureSampler {
+    temperature: f32,
+}
+
+impl TemperatureSampler {
+    /// Sampler with `temperature`, which must be positive and finite.
+    pub fn new(temperature: f32) -> Result<Self, TemperatureError> {
+        if temperature.is_nan() || temperature <= 0.0 {
+            return Err(TemperatureError::NotPositive(temperature));
+        }
+        if temperature.is_infinite() {
+            return Err(TemperatureError::NotFinite(temperature));
+        }
+        Ok(Self { temperature })
+    }
+
+    /// The temperature.
+    pub fn temperature(&self) -> f32 {
+        self.temperature
+    }
+
+    /// `logits` divided by the temperature.
+    pub fn scale(&self, logits: &[f32]) -> Vec<f32> {
+        logits.iter().map(|l| l / self.temperature).collect()
+    }
+
+    /// Softmax of the scaled logits, by `shark_core_math::softmax_with`:
+    /// NaN logits get 0, +inf logits share the mass, all -inf (or NaN) gives
+    /// all zeros, and finite logits a tiny temperature scales past `f32::MAX`
+    /// give it all to the first largest. Empty for empty input.
+    pub fn probabilities(&self, logits: &[f32]) -> Vec<Probability> {
+        let mut probs = logits.to_vec();
+        softmax_with(&mut probs, self.temperature, Accumulation::F32);
+        probs
+    }
+
+    /// Token drawn from the tempered distribution for a uniform draw `u` in
+    /// `[0, 1)`; `None` for empty logits, token 0 when no logit has mass.
+    pub fn sample(&self, logits: &[f32], u: f32) -> Option<TokenId> {
+        let probs = self.probabilities(logits);
+        if !probs.is_empty() && probs.iter().all(|&p| p == 0.0) {
+            return Some(0);
+        }
+        let mut cumulative = 0.0;
+        for (token, p) in probs.iter().enumerate() {
+            cumulative += p;
+            if u < cumulative {
+                return TokenId::try_from(token).ok();
+            }
+        }
+        // rounding left the total just below `u`: the last token
+        probs.len().checked_sub(1).and_then(|last| TokenId::try_from(last).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temperature_sharpens_and_flattens_the_distribution() {
+        assert_eq!(TemperatureSampler::new(0.0), Err(TemperatureError::NotPositive(0.0)));
+        assert_eq!(TemperatureSampler::new(-1.0), Err(TemperatureError::NotPositive(-1.0)));
+        assert!(TemperatureSampler::new(f32::NAN).is_err());
+        assert_eq!(TemperatureSampler::new(f32::INFINITY), Err(TemperatureError::NotFinite(f32::INFINITY)));
+
+        let logits = [2.0, 1.0, 0.0];
+        let (cold, neutral, hot) = (TemperatureSampler::new(0.5), TemperatureSampler::new(1.0), TemperatureSampler::new(4.0));
+        assert_eq!((cold.map(|s| s.temperature()), neutral.map(|s| s.temperature()), hot.map(|s| s.temperature())), (Ok(0.5), Ok(1.0), Ok(4.0)));
+        let (Ok(cold), Ok(neutral), Ok(hot)) = (cold, neutral, hot) else { return };
+        assert_eq!(cold.scale(&logits), vec![4.0, 2.0, 0.0]);
+        let top = |s: &TemperatureSampler| s.probabilities(&logits).first().copied().unwrap_or_default();
+        assert!(top(&cold) > top(&neutral) && top(&neutral) > top(&hot));
+        assert!((neutral.probabilities(&logits).iter().sum::<f32>() - 1.0).abs() < 1e-6);
+        assert!(hot.probabilities(&[1000.0, 999.0]).iter().all(|p| p.is_finite()));
+
+        assert_eq!(neutral.sample(&logits, 0.0), Some(0));
+        assert_eq!(neutral.sample(&logits, 0.999_999), Some(2));
+        assert_eq!(neutral.sample(&[], 0.5), None);
+
+        // 2e9 / 1e-30 overflows f32: the limit is greedy
+        let frozen = TemperatureSampler::new(1e-30);
+        assert_eq!(frozen.map(|s| s.probabilities(&[1e9, 2e9, 2e9, -1.0])), Ok(vec![0.0, 1.0, 0.0, 0.0]));
+        assert_eq!(frozen.map(|s| s.probabilities(&[2.0, 1.0, 0.0])), Ok(vec![1.0, 0.0, 0.0]));
+        assert_eq!(frozen.map(|s| s.sample(&[1e9, 2e9], 0.999)), Ok(Some(1)));
+    }
+
+    #[test]
+    fn nan_and_infinite_logits() {
+        let neutral = TemperatureSampler::new(1.0);
+        assert_eq!(neutral.map(|s| s.temperature()), Ok(1.0));
+        let Ok(neutral) = neutral else { return };
+
+        // NaN counts as -inf: never drawn
+        assert_eq!(neutral.probabilities(&[f32::NAN, 0.0, f32::NAN]), vec![0.0, 1.0, 0.0]);
+        assert_eq!(neutral.sample(&[f32::NAN, 0.0, f32::NAN], 0.999), Some(1));
+        // -inf logits get nothing; all -inf (or NaN) is all zeros and token 0
+        assert_eq!(neutral.probabilities(&[f32::NEG_INFINITY, 0.0]), vec![0.0, 1.0]);
+        assert_eq!(neutral.probabilities(&[f32::NEG_INFINITY; 2]), vec![0.0, 0.0]);
+        assert_eq!(neutral.sample(&[f32::NEG_INFINITY; 2], 0.5), Some(0));
+        assert_eq!(neutral.sample(&[f32::NAN; 3], 0.5), Some(0));
+        // +inf logits share the mass
+        let inf = [f32::INFINITY, 0.0, f32::INFINITY];
+        assert_eq!(neutral.probabilities(&inf), vec![0.5, 0.0, 0.5]);
+        assert_eq!((neutral.sample(&inf, 0.25), neutral.sample(&inf, 0.75)), (Some(0), Some(2)));
+    }
+}
@@ -0,0 +1,81 @@
+// No `forbid(unsafe_code)` here: the `#[wasm_bindgen]` expansion contains the
+// (unsafe) export shims. This crate itself has no unsafe code.
+#![deny(clippy::unwrap_used, clippy::expect_used, clippy::panic, clippy::indexing_slicing)]
+#![deny(missing_docs, unused_must_use)]
+
+//! Browser bindings for the Shark-Core inference core.
+//!
+//! Build with `wasm-pack build crates/wasm --target web`, then from JavaScript:
+//!
+//! ```js
+//! import init, { generate, Shark } from "./pkg/shark_wasm.js";
+//! await init();
+//! generate("Q:привет");                       // untrained (zeroed) model
+//! const weights = new Uint8Array(await (await fetch("model.bin")).arrayBuffer());
+//! new Shark(weights).generate("Q:привет");    // weights written by `Model::save`
+//! ```
+
+use predict::model::Model;
+use wasm_bindgen::prelude::*;
+
+/// Generate with an untrained (zeroed) model; handy to check that the module loads.
+#[wasm_bindgen]
+pub fn generate(prompt: &str) -> String {
+    Model::zeroed().generate(prompt)
+}
+
+/// A model loaded from weight bytes.
+#[wasm_bindgen]
+pub struct Shark {
+    model: Model,
+}
+
+#[wasm_bindgen]
+impl Shark {
+    /// Load weights in the `Model::save` layout; too few bytes is an error.
+    #[wasm_bindgen(constructor)]
+    pub fn new(weights: &[u8]) -> Result<Shark, JsError> {
+        Model::from_bytes(weights).map(|model| Shark { model }).map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    /// Generate an answer for `prompt` (raw model output, see `Model::generate`).
+    pub fn generate(&self, prompt: &str) -> String {
+        self.model.generate(prompt)
+    }
+
+    /// Embedding of `text` (see `Model::embed`), as a `Float32Array`.
+    pub fn embed(&self, text: &str) -> Vec<f32> {
+        self.model.embed(text)
+    }
+
+    /// Mix `seed` into the generation RNG (0 keeps the default stream).
+    #[wasm_bindgen(setter)]
+    pub fn set_seed(&mut self, seed: u64) {
+        self.model.seed = seed;
+    }
+
+    /// Characters produced per answer.
+    #[wasm_bindgen(setter)]
+    pub fn set_max_chars(&mut self, max_chars: usize) {
+        self.model.max_chars = max_chars;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weights_round_trip_through_bytes() {
+        let mut model = Model::zeroed();
+        model.max_chars = 12;
+        let shark = Model::from_bytes(&model.to_bytes()).map(|model| Shark { model });
+        assert!(shark.is_ok());
+        if let Ok(mut shark) = shark {
+            shark.set_max_chars(12);
+            assert_eq!(shark.generate("Q:привет"), model.generate("Q:привет"));
+            assert_eq!(generate("Q:привет").len(), 64);
+        }
+        assert!(Model::from_bytes(&[0u8; 16]).is_err());
+    }
+}